@@ -929,7 +929,9 @@ contract MillionairesProblem {
 
     // Gate description + per-gate leaf recomputation
 
-    enum GateType { AND, XOR, NOT }
+    // AND, XOR, NOT form gate set V1; OR, NAND, NOR (gate set V2) append opcodes 3..5 so
+    // V1 layout roots and circuit ids are unchanged.
+    enum GateType { AND, XOR, NOT, OR, NAND, NOR }
 
     struct GateDesc {
         GateType gateType;
@@ -966,9 +968,15 @@ contract MillionairesProblem {
                     uint8 outBit;
                     if (g.gateType == GateType.AND) {
                         outBit = bitA & bitB;
-                    } else {
-                        // XOR
+                    } else if (g.gateType == GateType.XOR) {
                         outBit = bitA ^ bitB;
+                    } else if (g.gateType == GateType.OR) {
+                        outBit = bitA | bitB;
+                    } else if (g.gateType == GateType.NAND) {
+                        outBit = 1 ^ (bitA & bitB);
+                    } else {
+                        // NOR
+                        outBit = 1 ^ (bitA | bitB);
                     }
 
                     // Input wire labels for this semantic choice
@@ -1449,6 +1449,32 @@ contract MillionairesTest is Test {
         mp.settle(output);
     }
 
+    // Vectors pinned by `v2_leaves_match_contract_vectors` in off-chain-common/tests/gate_sets.rs.
+    function test_ComputeLeaf_GateSetV2_MatchesRust() public view {
+        _assertV2Leaf(
+            MillionairesProblem.GateType.OR,
+            hex"030007001200133ceba8f231e3c528bd5f9d2364804fd11405a58bd60a679475654156274943fa0c33ea573adfb8bfe02ad7c62eaed15bfe3fce0901ddf7475ebc3ed4f626e8d0"
+        );
+        _assertV2Leaf(
+            MillionairesProblem.GateType.NAND,
+            hex"040007001200133d199219040ea8bbdd90320802771a791405a58bd60a679475654156274943fa0c33ea573adfb8bfe02ad7c62eaed15bffcdf4e234309ad43e7391ff90d1bd78"
+        );
+        _assertV2Leaf(
+            MillionairesProblem.GateType.NOR,
+            hex"050007001200133d199219040ea8bbdd90320802771a7915f79f60e3e70a0715aaee7d41be16520dc1d0bc0f32d52c80e578ed485984f3ffcdf4e234309ad43e7391ff90d1bd78"
+        );
+    }
+
+    function _assertV2Leaf(MillionairesProblem.GateType gateType, bytes memory expected) internal view {
+        MillionairesProblem.GateDesc memory g = MillionairesProblem.GateDesc({
+            gateType: gateType,
+            wireA: 7,
+            wireB: 18,
+            wireC: 19
+        });
+        assertEq(mp.computeLeaf(keccak256("seed"), 0, 3, g), expected);
+    }
+
     function test_ChallengeGateLeaf_FalseChallenge_SlashesBob() public {
         bytes32 seed = keccak256("seed");
         RustGateChallengeVector memory v = _rustVectorDefaultAndGate();
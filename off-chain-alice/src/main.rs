@@ -1,30 +1,94 @@
 use off_chain_common::cli::{
-    hex_prefixed, hex16, hex32, parse_bytes32, parse_bytes32_list_csv, parse_flag_value,
-    parse_u64, print_tx_summary, required_env, required_env_any, required_flag_value, rpc_url,
-    run_cast,
+    ClaimTimeoutOptions, SessionSeed, WatchOptions, artifact_encryption, cast_call_values,
+    claim_timeout, configured_private_key, deposit_wei, encode_signed_message, ensure_contract_n,
+    hex_prefixed, hex16, hex32, is_dry_run, migrate_artifact_dir, parse_bytes32, parse_fixed_bytes,
+    parse_number_u64, parse_u8, parse_u64, print_artifact_diff, print_contract_status,
+    print_manifest_report, print_session_resume, print_tx_summary, private_key,
+    read_beacon_binding, read_verified_message, replay_transcript, required_env, rpc_url, run_cast,
+    send_call_or_print, serve_relayer, serve_session, sign_artifact_manifest, start_dry_run,
+    start_key_stdin, start_transcript, transcript_path, u256_to_decimal, verify_manifest_dir,
+    watch_contract,
 };
+use off_chain_common::cli_args::{
+    BeaconArgs, CompletionsArgs, CompressionArgs, ValueList, address_arg, bytes32_arg,
+    bytes32_list_arg, circuit_arg, cli_command, completion_script, parse_cli, restore_session_argv,
+    session_params_arg, u64_arg, u256_arg,
+};
+use off_chain_common::emitln;
+use off_chain_common::abi::{
+    reveal_garbler_labels_call, reveal_openings_call, settle_call, submit_commitments_call,
+    submit_ot_roots_call,
+};
+use off_chain_common::artifact_layout::write_layout_version;
 use off_chain_common::auction_outcome::evaluate_first_price_outcome;
-use off_chain_common::consensus::{derive_wire_label, keccak256};
+use off_chain_common::base_ot::{OT_POINT_LEN, OtSender, record_answered_choices};
+use off_chain_common::bundle::BundleBuilder;
+use off_chain_common::commitment_check::{
+    CommitmentField, DerivedCommitments, FieldValues, check_commitments, drift_hint,
+};
+use off_chain_common::compression::{
+    ArtifactCompression, read_artifact, write_artifact, write_artifact_with,
+};
+use off_chain_common::consensus::{HIGHER_BID_WINS, keccak256, uint256_from_u64};
+use off_chain_common::decoding::OutputDecodingTable;
 use off_chain_common::eip4844::eval_payload_versioned_blob_hash;
+use off_chain_common::encryption::ArtifactKey;
 use off_chain_common::eval_blob::CanonicalEvalBlobPayload;
+use off_chain_common::eval_meta::EvalMeta;
 use off_chain_common::evaluation::{
-    derive_alice_input_labels, derive_bob_label_offers, derive_not_gate_hints,
-    derive_output_labels, label16_to_bytes32, millionaires_gt_output_wire,
+    bits_le_to_u256, derive_alice_input_labels_u256, derive_bob_label_offers,
+    derive_not_gate_hints, label16_to_bytes32, u256_bit_length,
 };
-use off_chain_common::garble::garble_circuit;
+use off_chain_common::export_checkpoint::{
+    DEFAULT_CHECKPOINT_GATES, EXPORT_CHECKPOINT_FILE, EXPORT_CHECKPOINT_VERSION,
+    ExportCheckpoint, GarbleProgress, garble_leaf_file,
+};
+use off_chain_common::garble::{garble_all_instances_with_scheme, garble_circuit};
+use off_chain_common::gas_report::{
+    GasCall, GasEstimate, gas_report, sender_gas, worst_case_dispute_call,
+};
+use off_chain_common::identity::EthIdentity;
 use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::ipfs::IpfsClient;
+use off_chain_common::leaf_file::LeafFile;
+use off_chain_common::line_reader::read_value_lines;
+use off_chain_common::logging::init_logging;
+use off_chain_common::manifest::{
+    ArtifactManifest, ArtifactRole, ExpectedArtifact, ManifestInstance, verify_artifacts,
+};
+use off_chain_common::messages::{EvalPackageMessage, InstanceCommitment, Message};
 use off_chain_common::ot::{recompute_ot_payload_hashes, recompute_ot_root};
+use off_chain_common::orchestrate::{
+    ANVIL_KEYS, AUCTION_CONTRACT, ENS_ADAPTER_CONTRACT, FAULT_KINDS, OrchestratePlan, Step,
+    corrupt_instance, corrupted_instance_id, fault_of_kind, forge_create, output_value,
+    root_gcs_with, run_step,
+};
+use off_chain_common::output::{print_json_output, start_json_output};
+use off_chain_common::profile::{ProfileName, activate_profile, config_path, load_profile};
+use off_chain_common::protocol::{Party, Phase};
+use off_chain_common::registry::{CircuitVersion, DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::scenario::beacon::{BeaconEvidence, verify_beacon_m};
 use off_chain_common::scenario::{
-    CUT_AND_CHOOSE_N, build_millionaires_layout, com_seed, derive_instance_seed,
+    SessionParams, com_seed, derive_instance_seed, verify_choice_opening,
 };
+use off_chain_common::secrets::resolve_secret_args;
+use off_chain_common::session_store::SessionStore;
 use off_chain_common::settlement::{
-    default_circuit_id, encode_auction_output_bytes, output_anchor_hash, output_commitment_hash,
+    encode_auction_output_bytes, output_commitment_hash,
 };
-use off_chain_common::types::CircuitLayout;
+use off_chain_common::retry::RetryPolicy;
+use off_chain_common::secure_channel::{receive_files_on, send_files_to};
+use off_chain_common::timeouts::{CLOSED_STAGE, stage_name};
+use off_chain_common::transport::list_dir_files;
+use off_chain_common::transcript::TRANSCRIPT_FILE;
+use off_chain_common::types::{CircuitLayout, GateDesc};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use std::env;
 use std::error::Error;
 use std::fs;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
 
 type AppResult<T> = Result<T, Box<dyn Error>>;
 
@@ -34,6 +98,8 @@ struct SessionConfig {
     circuit_id: [u8; 32],
     master_seed: [u8; 32],
     winner_formula: u8,
+    params: SessionParams,
+    circuit: &'static CircuitVersion,
 }
 
 #[derive(Debug, Clone)]
@@ -53,60 +119,58 @@ fn bytes32_vec_literal(values: &[[u8; 32]]) -> String {
     format!("[{}]", parts.join(","))
 }
 
-fn uint_vec_literal(values: &[usize]) -> String {
-    if values.is_empty() {
-        return "[]".to_string();
+fn read_bytes32_lines_file(path: &Path) -> AppResult<Vec<[u8; 32]>> {
+    let out = read_value_lines(path, "bytes32 line", parse_bytes32)?;
+    if out.is_empty() {
+        return Err(format!("No bytes32 values found in {}", path.display()).into());
     }
-    let parts = values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
-    format!("[{}]", parts.join(","))
+    Ok(out)
 }
 
-fn read_bytes32_lines_file(path: &Path) -> AppResult<Vec<[u8; 32]>> {
+/// Reads Bob's `wire,choice_point` rows for the y-wires `bit_width..2*bit_width`.
+fn read_ot_choices(path: &Path, bit_width: usize) -> AppResult<Vec<[u8; OT_POINT_LEN]>> {
     let raw = fs::read_to_string(path)?;
-    let mut out = Vec::new();
+    let mut out = vec![None; bit_width];
 
     for (line_idx, line) in raw.lines().enumerate() {
-        let mut value = line
-            .split('#')
-            .next()
-            .unwrap_or("")
-            .trim()
-            .trim_end_matches(',')
-            .trim()
-            .trim_matches('"')
-            .trim();
-
-        if value.is_empty() {
-            continue;
-        }
-
-        value = value.trim_start_matches('[').trim_end_matches(']');
-        if value.is_empty() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
             continue;
         }
-
-        let parsed = parse_bytes32(value).map_err(|e| {
-            format!(
-                "invalid bytes32 line at {}:{}: {}",
+        let Some((wire, point)) = trimmed.split_once(',') else {
+            return Err(format!(
+                "invalid OT choice row at {}:{} (expected wire,point)",
                 path.display(),
-                line_idx + 1,
-                e
+                line_idx + 1
             )
-        })?;
-        out.push(parsed);
+            .into());
+        };
+        let wire_id = parse_u64(wire.trim(), "wire_id")? as usize;
+        if wire_id < bit_width || wire_id >= 2 * bit_width {
+            return Err(format!(
+                "OT choice wire_id {wire_id} out of expected y range [{bit_width}, {})",
+                2 * bit_width
+            )
+            .into());
+        }
+        out[wire_id - bit_width] = Some(parse_fixed_bytes::<OT_POINT_LEN>(point.trim())?);
     }
 
-    if out.is_empty() {
-        return Err(format!("No bytes32 values found in {}", path.display()).into());
-    }
-    Ok(out)
+    out.into_iter()
+        .enumerate()
+        .map(|(idx, maybe)| maybe.ok_or_else(|| format!("missing OT choice for y-bit {idx}").into()))
+        .collect()
 }
 
-fn parse_optional_verifier_seed(args: &[String]) -> AppResult<Option<[u8; 32]>> {
-    parse_flag_value(args, "--verifier-seed")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()
+/// OT sender for Bob's y-labels of instance `instance_id`, keyed off the master seed so
+/// `prepare-eval` and `ot-respond` agree without storing the secret.
+fn ot_sender_for_instance(config: &SessionConfig, instance_id: usize) -> OtSender {
+    OtSender::new(keccak256(&[
+        b"OT-SENDER",
+        &config.master_seed,
+        &config.circuit_id,
+        &uint256_from_u64(instance_id as u64),
+    ]))
 }
 
 fn is_truthy_env(value: &str) -> bool {
@@ -131,84 +195,108 @@ fn parse_u64_csv(value: &str, flag_name: &str) -> AppResult<Vec<u64>> {
                 return Err(format!("{flag_name} contains an empty item at position {}", idx + 1).into());
             }
             let field = format!("{flag_name}[{idx}]");
-            parse_u64(trimmed, &field)
+            parse_number_u64(trimmed, &field)
         })
         .collect()
 }
 
-fn resolve_target_buyer(args: &[String]) -> AppResult<String> {
-    if let Some(buyer) = parse_flag_value(args, "--buyer") {
-        return Ok(buyer);
+/// Circuit, seed and instance-count flags of the commands that build instances.
+#[derive(Debug, Clone, Args)]
+struct SessionConfigArgs {
+    /// Circuit from the registry
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_CIRCUIT, value_parser = circuit_arg)]
+    circuit: &'static CircuitVersion,
+    /// Input bits per party
+    #[arg(long, value_name = "BITS", default_value_t = 8)]
+    bit_width: usize,
+    /// Circuit id [default: derived from --circuit, --bit-width and --winner-formula]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    circuit_id: Option<[u8; 32]>,
+    /// Seed all instance seeds derive from [default: the fixed test seed]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    master_seed: Option<[u8; 32]>,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// 0 (HigherBidWins) or 1 (LowerBidWins)
+    #[arg(
+        long,
+        value_name = "0|1",
+        env = "WINNER_FORMULA",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(0..=1)
+    )]
+    winner_formula: u8,
+}
+
+impl SessionConfigArgs {
+    fn resolve(&self) -> AppResult<SessionConfig> {
+        let circuit = self.circuit;
+        circuit.check_bit_width(self.bit_width)?;
+        Ok(SessionConfig {
+            bit_width: self.bit_width,
+            circuit_id: self
+                .circuit_id
+                .unwrap_or_else(|| circuit.circuit_id(self.bit_width, self.winner_formula)),
+            master_seed: self
+                .master_seed
+                .unwrap_or_else(|| keccak256(&[b"master-seed-v1"])),
+            winner_formula: self.winner_formula,
+            params: self.params,
+            circuit,
+        })
     }
-    required_env("BOB_ADDRESS")
 }
 
-fn parse_session_config(args: &[String]) -> AppResult<SessionConfig> {
-    let bit_width = parse_flag_value(args, "--bit-width")
-        .as_deref()
-        .map(|v| parse_u64(v, "bit-width"))
-        .transpose()?
-        .unwrap_or(8) as usize;
-    let winner_formula = if let Some(raw) = parse_flag_value(args, "--winner-formula") {
-        parse_u64(&raw, "winner-formula")?
-    } else if let Ok(raw) = env::var("WINNER_FORMULA") {
-        parse_u64(&raw, "WINNER_FORMULA")?
-    } else {
-        0
-    };
-    if winner_formula > 1 {
-        return Err("winner-formula must be 0 (HigherBidWins) or 1 (LowerBidWins)".into());
-    }
-    let winner_formula = winner_formula as u8;
-
-    let circuit_id = parse_flag_value(args, "--circuit-id")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?
-        .unwrap_or_else(|| default_circuit_id(bit_width, winner_formula));
-    let master_seed = parse_flag_value(args, "--master-seed")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?
-        .unwrap_or_else(|| keccak256(&[b"master-seed-v1"]));
-
-    Ok(SessionConfig {
-        bit_width,
-        circuit_id,
-        master_seed,
-        winner_formula,
-    })
+fn build_instance(
+    config: &SessionConfig,
+    gates: &[GateDesc],
+    instance_id: usize,
+) -> InstanceArtifacts {
+    let seed = derive_instance_seed(config.master_seed, config.circuit_id, instance_id as u64);
+    let layout = CircuitLayout::new(config.circuit_id, instance_id as u64, gates.to_vec())
+        .with_scheme_id(config.circuit.params.scheme_id);
+    let leaves = garble_circuit(seed, &layout);
+    let block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    let root_gc = incremental_root_from_hashes(&block_hashes);
+
+    InstanceArtifacts {
+        instance_id,
+        seed,
+        com_seed: com_seed(seed),
+        root_gc,
+        leaves,
+    }
 }
 
 fn build_instances(config: &SessionConfig) -> Vec<InstanceArtifacts> {
-    let gates = build_millionaires_layout(config.bit_width);
-
-    (0..CUT_AND_CHOOSE_N)
-        .map(|instance_id| {
-            let seed =
-                derive_instance_seed(config.master_seed, config.circuit_id, instance_id as u64);
-            let layout = CircuitLayout {
-                circuit_id: config.circuit_id,
-                instance_id: instance_id as u64,
-                gates: gates.clone(),
-            };
-            let leaves = garble_circuit(seed, &layout);
-            let block_hashes = leaves
-                .iter()
-                .enumerate()
-                .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
-                .collect::<Vec<_>>();
-            let root_gc = incremental_root_from_hashes(&block_hashes);
-
-            InstanceArtifacts {
-                instance_id,
-                seed,
-                com_seed: com_seed(seed),
-                root_gc,
-                leaves,
-            }
-        })
-        .collect()
+    let gates = config.circuit.build(config.bit_width);
+
+    garble_all_instances_with_scheme(
+        config.master_seed,
+        config.circuit_id,
+        &gates,
+        config.circuit.params.scheme_id,
+        config.params.n,
+    )
+    .into_iter()
+    .map(|inst| InstanceArtifacts {
+        instance_id: inst.instance_id as usize,
+        seed: inst.seed,
+        com_seed: inst.com_seed,
+        root_gc: inst.root_gc,
+        leaves: inst.leaves,
+    })
+    .collect()
 }
 
 fn derive_ot_payload_hashes_for_instance(
@@ -255,102 +343,121 @@ fn derive_ot_root_lists(
         .collect()
 }
 
-fn build_commitment_tuple_items(
+/// On-chain commitment of each instance, in instance order.
+fn build_commitments(
     instances: &[InstanceArtifacts],
     root_gcs: &[[u8; 32]],
     blob_hashes: &[[u8; 32]],
     h_out: &[[u8; 32]],
-) -> Vec<String> {
+) -> Vec<InstanceCommitment> {
     instances
         .iter()
-        .map(|inst| {
-            format!(
-                "({},{},{},{})",
-                hex32(inst.com_seed),
-                hex32(root_gcs[inst.instance_id]),
-                hex32(blob_hashes[inst.instance_id]),
-                hex32(h_out[inst.instance_id]),
-            )
+        .map(|inst| InstanceCommitment {
+            com_seed: inst.com_seed,
+            root_gc: root_gcs[inst.instance_id],
+            blob_hash_gc: blob_hashes[inst.instance_id],
+            h_out: h_out[inst.instance_id],
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
-fn build_commitments_arg(
-    instances: &[InstanceArtifacts],
-    root_gcs: &[[u8; 32]],
-    blob_hashes: &[[u8; 32]],
-    h_out: &[[u8; 32]],
-) -> String {
-    let tuple_items = build_commitment_tuple_items(instances, root_gcs, blob_hashes, h_out);
-    format!("[{}]", tuple_items.join(","))
+/// Output anchors of `submit-commitments` and `submit-core-commitments`: given directly, or
+/// computed from the auction's bids.
+#[derive(Debug, Clone, Args)]
+struct HOutArgs {
+    /// hOut of every instance (disabled with DEMO_MODE)
+    #[arg(
+        long,
+        value_name = "0x..,0x.. xN",
+        value_parser = bytes32_list_arg,
+        conflicts_with_all = ["bids", "chosen_namehash"]
+    )]
+    h_out: Option<ValueList<[u8; 32]>>,
+    /// Bids the first-price auction outcome is computed from
+    #[arg(
+        long,
+        value_name = "u64,u64,...",
+        value_parser = bids_arg,
+        required_unless_present_any = ["h_out", "h0"],
+        requires = "chosen_namehash"
+    )]
+    bids: Option<ValueList<u64>>,
+    /// Namehash of the auctioned name, committed into the output
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg, requires = "bids")]
+    chosen_namehash: Option<[u8; 32]>,
+    /// Removed in favour of --h-out; kept to point old scripts at it
+    #[arg(long, hide = true)]
+    h0: Option<String>,
 }
 
-fn derive_h_out_lists(
-    args: &[String],
-    config: &SessionConfig,
-) -> AppResult<Vec<[u8; 32]>> {
-    if let Some(raw) = parse_flag_value(args, "--h-out") {
-        if env::var("DEMO_MODE")
-            .ok()
-            .as_deref()
-            .is_some_and(is_truthy_env)
-        {
-            return Err("--h-out disabled in demo mode; use --bids + --chosen-namehash".into());
+fn bids_arg(raw: &str) -> Result<Vec<u64>, String> {
+    parse_u64_csv(raw, "--bids").map_err(|e| e.to_string())
+}
+
+impl HOutArgs {
+    fn derive(&self, config: &SessionConfig) -> AppResult<Vec<[u8; 32]>> {
+        if self.h0.is_some() {
+            return Err(
+                "--h0 is no longer supported; use --h-out or --bids + --chosen-namehash".into(),
+            );
         }
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--h-out must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
+        if let Some(h_out) = &self.h_out {
+            if env::var("DEMO_MODE")
+                .ok()
+                .as_deref()
+                .is_some_and(is_truthy_env)
+            {
+                return Err("--h-out disabled in demo mode; use --bids + --chosen-namehash".into());
+            }
+            check_list_len("--h-out", h_out, config.params)?;
+            return Ok(h_out.clone());
         }
-        return Ok(parsed);
-    }
 
-    if parse_flag_value(args, "--h0").is_some() {
-        return Err(
-            "--h0 is no longer supported; use --h-out or --bids + --chosen-namehash".into(),
-        );
+        let (Some(bids), Some(chosen_namehash)) = (&self.bids, self.chosen_namehash) else {
+            return Err("--bids is required unless --h-out is provided".into());
+        };
+        let outcome =
+            evaluate_first_price_outcome(bids).map_err(|e| format!("invalid --bids: {e}"))?;
+        let output_bytes =
+            encode_auction_output_bytes(outcome.winner_id, outcome.winning_bid, chosen_namehash);
+        Ok((0..config.params.n)
+            .map(|instance_id| {
+                output_commitment_hash(config.circuit_id, instance_id as u64, &output_bytes)
+            })
+            .collect())
     }
+}
 
-    let chosen_namehash = parse_bytes32(&required_flag_value(args, "--chosen-namehash")?)?;
-    let raw_bids = if let Some(raw) = parse_flag_value(args, "--bids") {
-        raw
-    } else {
-        return Err("--bids is required unless --h-out is provided".into());
-    };
-    let bids = parse_u64_csv(&raw_bids, "--bids")?;
-    let outcome =
-        evaluate_first_price_outcome(&bids).map_err(|e| format!("invalid --bids: {e}"))?;
-    let output_bytes =
-        encode_auction_output_bytes(outcome.winner_id, outcome.winning_bid, chosen_namehash);
-    Ok((0..CUT_AND_CHOOSE_N)
-        .map(|instance_id| {
-            output_commitment_hash(config.circuit_id, instance_id as u64, &output_bytes)
-        })
-        .collect())
+/// Checks that a per-instance list such as `--root-gcs` has one value per instance.
+fn check_list_len<T>(flag: &str, values: &[T], params: SessionParams) -> AppResult<()> {
+    if values.len() != params.n {
+        return Err(format!(
+            "{flag} must contain {} values, got {}",
+            params.n,
+            values.len()
+        )
+        .into());
+    }
+    Ok(())
 }
 
 fn opened_indices_and_seeds(
     instances: &[InstanceArtifacts],
     m: usize,
+    params: SessionParams,
 ) -> AppResult<(Vec<usize>, Vec<[u8; 32]>)> {
-    if instances.len() != CUT_AND_CHOOSE_N {
+    if instances.len() != params.n {
         return Err(format!(
             "expected {} instances, got {}",
-            CUT_AND_CHOOSE_N,
+            params.n,
             instances.len()
         )
         .into());
     }
-    if m >= CUT_AND_CHOOSE_N {
-        return Err(format!("m={} out of range [0, {})", m, CUT_AND_CHOOSE_N).into());
-    }
+    params.check_m(m)?;
 
-    let mut indices = Vec::with_capacity(CUT_AND_CHOOSE_N - 1);
-    let mut seeds = Vec::with_capacity(CUT_AND_CHOOSE_N - 1);
+    let mut indices = Vec::with_capacity(params.n - 1);
+    let mut seeds = Vec::with_capacity(params.n - 1);
     for inst in instances {
         if inst.instance_id == m {
             continue;
@@ -361,147 +468,349 @@ fn opened_indices_and_seeds(
     Ok((indices, seeds))
 }
 
+fn new_artifact_manifest(config: &SessionConfig) -> ArtifactManifest {
+    ArtifactManifest::new(
+        config.circuit.name,
+        config.circuit.params.scheme_id.to_byte(),
+        config.circuit_id,
+        config.bit_width as u16,
+    )
+}
+
 fn write_instance_files(
     out_dir: &Path,
     config: &SessionConfig,
     instances: &[InstanceArtifacts],
     verifier_seed: Option<[u8; 32]>,
-) -> AppResult<()> {
+    compression: ArtifactCompression,
+    encryption: Option<&ArtifactKey>,
+) -> AppResult<Option<[u8; 20]>> {
     fs::create_dir_all(out_dir)?;
 
-    let mut manifest = String::new();
-    manifest.push_str("# Alice artifacts\n");
-    manifest.push_str("# file format: hex-encoded values\n\n");
-
+    let mut manifest = new_artifact_manifest(config);
     for inst in instances {
-        let seed_file = out_dir.join(format!("instance-{}-seed.txt", inst.instance_id));
-        let com_file = out_dir.join(format!("instance-{}-com-seed.txt", inst.instance_id));
-        let root_file = out_dir.join(format!("instance-{}-root-gc.txt", inst.instance_id));
-        let leaves_file = out_dir.join(format!("instance-{}-leaves.txt", inst.instance_id));
-        let eval_blob_file = out_dir.join(format!("instance-{}-eval-blob.bin", inst.instance_id));
-        let mut root_ot_manifest = None::<String>;
-        let mut payloads_manifest = None::<String>;
-
-        fs::write(&seed_file, format!("{}\n", hex32(inst.seed)))?;
-        fs::write(&com_file, format!("{}\n", hex32(inst.com_seed)))?;
-        fs::write(&root_file, format!("{}\n", hex32(inst.root_gc)))?;
-
-        let mut leaves_raw = String::new();
-        for leaf in &inst.leaves {
-            leaves_raw.push_str(&hex_prefixed(leaf));
-            leaves_raw.push('\n');
-        }
-        fs::write(&leaves_file, leaves_raw)?;
-        let eval_payload = build_eval_blob_payload_for_instance(
+        write_instance_file_set(
+            out_dir,
             config,
-            inst.instance_id,
-            inst.seed,
-            inst.leaves.clone(),
+            inst,
+            verifier_seed,
+            compression,
+            encryption,
+            &mut manifest,
         )?;
-        let eval_blob_hash = write_eval_blob_payload(&eval_blob_file, &eval_payload)?;
-
-        if let Some(verifier_seed) = verifier_seed {
-            let root_ot = recompute_ot_root(
-                config.circuit_id,
-                config.bit_width,
-                inst.seed,
-                verifier_seed,
-                inst.instance_id as u64,
-            )
-            .map_err(|e| {
-                format!(
-                    "failed to derive rootOT for instance {} while exporting artifacts: {e}",
-                    inst.instance_id
-                )
-            })?;
-            let payload_hashes = derive_ot_payload_hashes_for_instance(
-                config,
-                inst.instance_id,
-                inst.seed,
-                verifier_seed,
-            )?;
+    }
 
-            let root_ot_file = out_dir.join(format!("instance-{}-root-ot.txt", inst.instance_id));
-            let payloads_file =
-                out_dir.join(format!("instance-{}-ot-payloads.txt", inst.instance_id));
-            fs::write(&root_ot_file, format!("{}\n", hex32(root_ot)))?;
+    write_signed_manifest(out_dir, &mut manifest)
+}
 
-            let mut payloads_raw = String::new();
-            for payload_hash in payload_hashes {
-                payloads_raw.push_str(&hex32(payload_hash));
-                payloads_raw.push('\n');
+/// Same output as `write_instance_files`, but garbles and writes one instance at a time so
+/// only a single instance's leaves are held in memory. Every `checkpoint_gates` gates the
+/// progress is saved to `export-checkpoint.json`; with `resume` an interrupted export picks up
+/// from it. The checkpoint is removed once the manifest is written.
+fn export_instance_files(
+    out_dir: &Path,
+    config: &SessionConfig,
+    verifier_seed: Option<[u8; 32]>,
+    compression: ArtifactCompression,
+    encryption: Option<&ArtifactKey>,
+    checkpoint_gates: u64,
+    resume: bool,
+) -> AppResult<Option<[u8; 20]>> {
+    fs::create_dir_all(out_dir)?;
+    let gates = config.circuit.build(config.bit_width);
+
+    let (mut manifest, first_instance, mut progress) =
+        match (ExportCheckpoint::read(out_dir)?, resume) {
+            (Some(checkpoint), true) => {
+                check_export_checkpoint(config, &checkpoint)?;
+                let id = checkpoint.instance_id as usize;
+                (checkpoint.manifest, id, Some(checkpoint.progress))
             }
-            fs::write(&payloads_file, payloads_raw)?;
-            root_ot_manifest = Some(root_ot_file.display().to_string());
-            payloads_manifest = Some(payloads_file.display().to_string());
-        }
+            (Some(checkpoint), false) => {
+                return Err(format!(
+                    "{} holds an interrupted export (instance {}, gate {}); pass --resume to \
+                     continue it or remove {EXPORT_CHECKPOINT_FILE} to start over",
+                    out_dir.display(),
+                    checkpoint.instance_id,
+                    checkpoint.progress.gate_index
+                )
+                .into());
+            }
+            (None, _) => (new_artifact_manifest(config), 0, None),
+        };
 
-        manifest.push_str(&format!(
-            "instance {}:\n  seed={}\n  comSeed={}\n  rootGC={}\n  blobHashGC={}\n  evalBlob={}\n",
-            inst.instance_id,
-            seed_file.display(),
-            com_file.display(),
-            root_file.display(),
-            hex32(eval_blob_hash),
-            eval_blob_file.display()
-        ));
-        if let Some(root_ot_file) = root_ot_manifest {
-            manifest.push_str(&format!("  rootOT={}\n", root_ot_file));
-        }
-        if let Some(payloads_file) = payloads_manifest {
-            manifest.push_str(&format!("  otPayloads={}\n", payloads_file));
+    for instance_id in first_instance..config.params.n {
+        let seed = derive_instance_seed(config.master_seed, config.circuit_id, instance_id as u64);
+        let layout = CircuitLayout::new(config.circuit_id, instance_id as u64, gates.clone())
+            .with_scheme_id(config.circuit.params.scheme_id);
+        let start = progress.take().unwrap_or_else(GarbleProgress::start);
+        let mut checkpoint = export_checkpoint(config, manifest, instance_id, start);
+        let partial = out_dir.join(format!("instance-{instance_id}-leaves.txt.partial"));
+        let done = garble_leaf_file(
+            seed,
+            &layout,
+            &partial,
+            Some(checkpoint.progress),
+            checkpoint_gates,
+            |progress| {
+                checkpoint.progress = progress;
+                checkpoint.write(out_dir).map(|_| ())
+            },
+        )?;
+        let inst = InstanceArtifacts {
+            instance_id,
+            seed,
+            com_seed: checkpoint.com_seed,
+            root_gc: done.ih_state,
+            leaves: LeafFile::open(&partial)?.iter().collect::<Result<_, _>>()?,
+        };
+        manifest = checkpoint.manifest;
+        write_instance_file_set(
+            out_dir,
+            config,
+            &inst,
+            verifier_seed,
+            compression,
+            encryption,
+            &mut manifest,
+        )?;
+        fs::remove_file(&partial)?;
+        if instance_id + 1 < config.params.n {
+            export_checkpoint(config, manifest.clone(), instance_id + 1, GarbleProgress::start())
+                .write(out_dir)?;
         }
-        manifest.push_str(&format!("  leaves={}\n\n", leaves_file.display()));
     }
 
-    fs::write(out_dir.join("manifest.txt"), manifest)?;
+    let signer = write_signed_manifest(out_dir, &mut manifest)?;
+    ExportCheckpoint::remove(out_dir)?;
+    Ok(signer)
+}
+
+fn export_checkpoint(
+    config: &SessionConfig,
+    manifest: ArtifactManifest,
+    instance_id: usize,
+    progress: GarbleProgress,
+) -> ExportCheckpoint {
+    let seed = derive_instance_seed(config.master_seed, config.circuit_id, instance_id as u64);
+    ExportCheckpoint {
+        version: EXPORT_CHECKPOINT_VERSION,
+        instance_count: config.params.n as u64,
+        manifest,
+        instance_id: instance_id as u64,
+        com_seed: com_seed(seed),
+        progress,
+    }
+}
+
+/// Refuses to resume a checkpoint written for another circuit, size or master seed.
+fn check_export_checkpoint(config: &SessionConfig, checkpoint: &ExportCheckpoint) -> AppResult<()> {
+    let expected = new_artifact_manifest(config);
+    let manifest = &checkpoint.manifest;
+    let seed = derive_instance_seed(config.master_seed, config.circuit_id, checkpoint.instance_id);
+    if manifest.circuit != expected.circuit
+        || manifest.scheme != expected.scheme
+        || manifest.circuit_id != expected.circuit_id
+        || manifest.bit_width != expected.bit_width
+        || checkpoint.instance_count != config.params.n as u64
+        || checkpoint.com_seed != com_seed(seed)
+    {
+        return Err(format!(
+            "{EXPORT_CHECKPOINT_FILE} was written for {} (bit-width {}, circuit-id {}, n {}) or \
+             another master seed; resume with the options of the interrupted export",
+            manifest.circuit,
+            manifest.bit_width,
+            hex32(manifest.circuit_id),
+            checkpoint.instance_count
+        )
+        .into());
+    }
     Ok(())
 }
 
-fn ensure_value_fits_bits(value: u64, bit_width: usize, name: &str) -> AppResult<()> {
-    if bit_width >= 64 {
-        return Ok(());
+/// Stamps the layout version, writes `out_dir/manifest.json` and, when `ALICE_PRIVATE_KEY`
+/// and `CONTRACT_ADDRESS` are set, `manifest.sig.json`. Returns the signer.
+fn write_signed_manifest(
+    out_dir: &Path,
+    manifest: &mut ArtifactManifest,
+) -> AppResult<Option<[u8; 20]>> {
+    let stamp = write_layout_version(out_dir)?;
+    manifest.add_file(out_dir, &file_name(&stamp)?, ArtifactRole::LayoutVersion, None)?;
+    manifest.write(out_dir)?;
+    sign_artifact_manifest(out_dir, &["ALICE_PRIVATE_KEY", "ALICE_PK"])
+}
+
+fn print_manifest_signer(signer: Option<[u8; 20]>) {
+    match signer {
+        Some(signer) => emitln!("manifest_signer={}", hex_prefixed(&signer)),
+        None => emitln!("manifest_signer=none"),
+    }
+}
+
+/// One exported instance file before compression.
+struct InstanceFile {
+    name: String,
+    role: ArtifactRole,
+    bytes: Vec<u8>,
+    /// Leaves, the eval blob and the OT payload hashes; the one-line files stay plain text.
+    compressible: bool,
+}
+
+/// Renders one instance's artifact files and returns them with the eval blob's
+/// `blobHashGC`. Shared by the export and by `verify-artifacts`.
+fn render_instance_file_set(
+    config: &SessionConfig,
+    inst: &InstanceArtifacts,
+    verifier_seed: Option<[u8; 32]>,
+) -> AppResult<(Vec<InstanceFile>, [u8; 32])> {
+    let id = inst.instance_id;
+    let file = |suffix: &str, role, bytes: Vec<u8>, compressible| InstanceFile {
+        name: format!("instance-{id}-{suffix}"),
+        role,
+        bytes,
+        compressible,
+    };
+    let hex_line = |value: [u8; 32]| format!("{}\n", hex32(value)).into_bytes();
+
+    let mut leaves_raw = String::new();
+    for leaf in &inst.leaves {
+        leaves_raw.push_str(&hex_prefixed(leaf));
+        leaves_raw.push('\n');
+    }
+    let (eval_blob, eval_blob_hash) = encode_instance_eval_blob(config, inst)?;
+    let mut files = vec![
+        file("seed.txt", ArtifactRole::Seed, hex_line(inst.seed), false),
+        file("com-seed.txt", ArtifactRole::ComSeed, hex_line(inst.com_seed), false),
+        file("root-gc.txt", ArtifactRole::RootGc, hex_line(inst.root_gc), false),
+        file("leaves.txt", ArtifactRole::Leaves, leaves_raw.into_bytes(), true),
+        file("eval-blob.bin", ArtifactRole::EvalBlob, eval_blob, true),
+    ];
+
+    if let Some(verifier_seed) = verifier_seed {
+        let root_ot = recompute_ot_root(
+            config.circuit_id,
+            config.bit_width,
+            inst.seed,
+            verifier_seed,
+            id as u64,
+        )
+        .map_err(|e| {
+            format!("failed to derive rootOT for instance {id} while exporting artifacts: {e}")
+        })?;
+        let payload_hashes =
+            derive_ot_payload_hashes_for_instance(config, id, inst.seed, verifier_seed)?;
+
+        let mut payloads_raw = String::new();
+        for payload_hash in payload_hashes {
+            payloads_raw.push_str(&hex32(payload_hash));
+            payloads_raw.push('\n');
+        }
+        files.push(file("root-ot.txt", ArtifactRole::RootOt, hex_line(root_ot), false));
+        files.push(file(
+            "ot-payloads.txt",
+            ArtifactRole::OtPayloads,
+            payloads_raw.into_bytes(),
+            true,
+        ));
+    }
+    Ok((files, eval_blob_hash))
+}
+
+/// Encodes one instance's eval blob and derives its `blobHashGC`, the EIP-4844 versioned hash
+/// of the KZG commitment to the blob a later blob-carrying transaction publishes.
+fn encode_instance_eval_blob(
+    config: &SessionConfig,
+    inst: &InstanceArtifacts,
+) -> AppResult<(Vec<u8>, [u8; 32])> {
+    let id = inst.instance_id;
+    let eval_payload =
+        build_eval_blob_payload_for_instance(config, id, inst.seed, inst.leaves.clone())?;
+    let eval_blob = eval_payload
+        .encode()
+        .map_err(|e| format!("failed to encode eval payload: {e}"))?;
+    let eval_blob_hash = eval_payload_versioned_blob_hash(&eval_blob).map_err(|e| {
+        format!("failed to derive EIP-4844 versioned blob hash for instance {id}: {e}")
+    })?;
+    Ok((eval_blob, eval_blob_hash))
+}
+
+/// Writes one instance's artifact files and records them in `manifest`. `compression`
+/// applies to the compressible files only, `encryption` to the seed and the leaves.
+fn write_instance_file_set(
+    out_dir: &Path,
+    config: &SessionConfig,
+    inst: &InstanceArtifacts,
+    verifier_seed: Option<[u8; 32]>,
+    compression: ArtifactCompression,
+    encryption: Option<&ArtifactKey>,
+    manifest: &mut ArtifactManifest,
+) -> AppResult<()> {
+    let (files, eval_blob_hash) = render_instance_file_set(config, inst, verifier_seed)?;
+    for file in files {
+        let path = out_dir.join(&file.name);
+        let compression = if file.compressible {
+            compression
+        } else {
+            ArtifactCompression::None
+        };
+        let secret = matches!(file.role, ArtifactRole::Seed | ArtifactRole::Leaves);
+        let key = encryption.filter(|_| secret);
+        let written = write_artifact_with(&path, &file.bytes, compression, key)?;
+        let instance_id = Some(inst.instance_id as u64);
+        manifest.add_file(out_dir, &file_name(&written)?, file.role, instance_id)?;
     }
-    if value >= (1u64 << bit_width) {
+    manifest.instances.push(ManifestInstance {
+        instance_id: inst.instance_id as u64,
+        blob_hash_gc: eval_blob_hash,
+        cid: None,
+    });
+    Ok(())
+}
+
+fn file_name(path: &Path) -> AppResult<String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("{} has no file name", path.display()).into())
+}
+
+fn ensure_value_fits_bits(value: [u8; 32], bit_width: usize, name: &str) -> AppResult<()> {
+    if u256_bit_length(value) > bit_width {
+        let max = bits_le_to_u256(&vec![1u8; bit_width.min(256)])?;
         return Err(format!(
             "{name}={} does not fit bit-width {} (max={})",
-            value,
+            u256_to_decimal(value),
             bit_width,
-            (1u64 << bit_width) - 1
+            u256_to_decimal(max)
         )
         .into());
     }
     Ok(())
 }
 
-fn compute_output_anchor(
+fn output_decoding_for_instance(
     config: &SessionConfig,
-    instance_id: u64,
-    winner_bit: bool,
-    label16: [u8; 16],
-) -> [u8; 32] {
-    let as_bytes32 = label16_to_bytes32(label16);
-    output_anchor_hash(config.circuit_id, instance_id, winner_bit, as_bytes32)
+    gates: &[GateDesc],
+    instance_id: usize,
+    seed: [u8; 32],
+) -> AppResult<OutputDecodingTable> {
+    let output_wire = config.circuit.decision_wire(gates, config.bit_width)
+        .map_err(|e| format!("failed to resolve decision wire: {e}"))?;
+    let layout = CircuitLayout::new(config.circuit_id, instance_id as u64, gates.to_vec())
+        .with_scheme_id(config.circuit.params.scheme_id);
+    Ok(OutputDecodingTable::derive(seed, &layout, output_wire)?)
 }
 
 fn derive_anchor_lists(config: &SessionConfig) -> AppResult<(Vec<[u8; 32]>, Vec<[u8; 32]>)> {
-    let gates = build_millionaires_layout(config.bit_width);
-    let out_wire = millionaires_gt_output_wire(&gates, config.bit_width)
-        .map_err(|e| format!("failed to resolve millionaire output wire: {e}"))?;
+    let gates = config.circuit.build(config.bit_width);
 
-    let mut h0 = Vec::with_capacity(CUT_AND_CHOOSE_N);
-    let mut h1 = Vec::with_capacity(CUT_AND_CHOOSE_N);
-    for instance_id in 0..CUT_AND_CHOOSE_N {
+    let mut h0 = Vec::with_capacity(config.params.n);
+    let mut h1 = Vec::with_capacity(config.params.n);
+    for instance_id in 0..config.params.n {
         let seed = derive_instance_seed(config.master_seed, config.circuit_id, instance_id as u64);
-        let label_true = derive_wire_label(config.circuit_id, instance_id as u64, out_wire, 1, seed);
-        let label_false = derive_wire_label(config.circuit_id, instance_id as u64, out_wire, 0, seed);
-        h0.push(compute_output_anchor(config, instance_id as u64, true, label_true));
-        h1.push(compute_output_anchor(
-            config,
-            instance_id as u64,
-            false,
-            label_false,
-        ));
+        let decoding = output_decoding_for_instance(config, &gates, instance_id, seed)?;
+        h0.push(decoding.h0);
+        h1.push(decoding.h1);
     }
     Ok((h0, h1))
 }
@@ -512,28 +821,12 @@ fn build_eval_blob_payload_for_instance(
     seed: [u8; 32],
     leaves: Vec<[u8; 71]>,
 ) -> AppResult<CanonicalEvalBlobPayload> {
-    let gates = build_millionaires_layout(config.bit_width);
-    let output_wire = millionaires_gt_output_wire(&gates, config.bit_width)
-        .map_err(|e| format!("failed to resolve millionaire output wire: {e}"))?;
-    let layout = CircuitLayout {
-        circuit_id: config.circuit_id,
-        instance_id: instance_id as u64,
-        gates,
-    };
+    let gates = config.circuit.build(config.bit_width);
+    let decoding = output_decoding_for_instance(config, &gates, instance_id, seed)?;
+    let output_wire = decoding.output_wire;
+    let layout = CircuitLayout::new(config.circuit_id, instance_id as u64, gates)
+        .with_scheme_id(config.circuit.params.scheme_id);
 
-    let (label_false, label_true) = derive_output_labels(seed, &layout, output_wire)
-        .map_err(|e| format!("failed to derive output labels: {e}"))?;
-    let l_true_32 = label16_to_bytes32(label_true);
-    let l_false_32 = label16_to_bytes32(label_false);
-    let h0 = compute_output_anchor(config, instance_id as u64, true, label_true);
-    let h1 = compute_output_anchor(config, instance_id as u64, false, label_false);
-
-    let y_offers = derive_bob_label_offers(
-        seed,
-        config.circuit_id,
-        instance_id as u64,
-        config.bit_width,
-    );
     let not_hints = derive_not_gate_hints(seed, &layout);
     let block_hashes = leaves
         .iter()
@@ -547,44 +840,61 @@ fn build_eval_blob_payload_for_instance(
         instance_id: instance_id as u64,
         bit_width: config.bit_width as u16,
         output_wire,
-        h0,
-        h1,
-        lout_true: l_true_32,
-        lout_false: l_false_32,
+        h0: decoding.h0,
+        h1: decoding.h1,
+        lout_true: decoding.lout_true,
+        lout_false: decoding.lout_false,
         root_gc,
         block_hashes,
         gc_leaves: leaves,
-        y_offers,
+        // Published for anyone to read: Bob's labels go through `ot-respond` only.
+        y_offers: Vec::new(),
         not_hints,
     })
 }
 
-fn write_eval_blob_payload(path: &Path, payload: &CanonicalEvalBlobPayload) -> AppResult<[u8; 32]> {
+/// Writes the eval payload and returns the file written with the blob hash of the uncompressed
+/// encoding.
+fn write_eval_blob_payload(
+    path: &Path,
+    payload: &CanonicalEvalBlobPayload,
+    compression: ArtifactCompression,
+) -> AppResult<(PathBuf, [u8; 32])> {
     let encoded = payload
         .encode()
         .map_err(|e| format!("failed to encode eval payload: {e}"))?;
-    fs::write(path, &encoded)?;
-    eval_payload_versioned_blob_hash(&encoded).map_err(|e| {
+    let file = write_artifact(path, &encoded, compression)?;
+    let blob_hash = eval_payload_versioned_blob_hash(&encoded).map_err(|e| {
         format!(
             "failed to derive EIP-4844 versioned blob hash for {}: {e}",
             path.display()
         )
-        .into()
-    })
+    })?;
+    Ok((file, blob_hash))
+}
+
+/// `blobHashGC` of every instance, encoded in memory when no export directory holds them.
+fn derive_blob_hashes(
+    config: &SessionConfig,
+    instances: &[InstanceArtifacts],
+) -> AppResult<Vec<[u8; 32]>> {
+    instances
+        .iter()
+        .map(|inst| Ok(encode_instance_eval_blob(config, inst)?.1))
+        .collect()
 }
 
 fn derive_blob_hashes_from_exported_payloads(
     out_dir: &Path,
     instances: &[InstanceArtifacts],
 ) -> AppResult<Vec<[u8; 32]>> {
-    let mut out = vec![[0u8; 32]; CUT_AND_CHOOSE_N];
+    let mut out = vec![[0u8; 32]; instances.len()];
     for inst in instances {
         let path = out_dir.join(format!("instance-{}-eval-blob.bin", inst.instance_id));
-        let encoded = fs::read(&path).map_err(|e| {
+        let encoded = read_artifact(&path).map_err(|e| {
             format!(
-                "failed to read eval blob payload for instance {} at {}: {e}",
-                inst.instance_id,
-                path.display()
+                "failed to read eval blob payload for instance {}: {e}",
+                inst.instance_id
             )
         })?;
         let payload = CanonicalEvalBlobPayload::decode(&encoded).map_err(|e| {
@@ -613,59 +923,202 @@ fn derive_blob_hashes_from_exported_payloads(
     Ok(out)
 }
 
-fn cmd_derive_anchors(args: &[String]) -> AppResult<()> {
-    let config = parse_session_config(args)?;
+#[derive(Debug, Args)]
+struct DeriveAnchorsArgs {
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+fn cmd_derive_anchors(args: DeriveAnchorsArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
     let (h0, h1) = derive_anchor_lists(&config)?;
 
-    println!("bit_width={}", config.bit_width);
-    println!("circuit_id={}", hex32(config.circuit_id));
-    println!("winner_formula={}", config.winner_formula);
-    println!("h0_list={}", bytes32_vec_literal(&h0));
-    println!("h1_list={}", bytes32_vec_literal(&h1));
+    emitln!("bit_width={}", config.bit_width);
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("scheme={}", config.circuit.params.scheme_id);
+    emitln!("circuit_id={}", hex32(config.circuit_id));
+    emitln!("winner_formula={}", config.winner_formula);
+    emitln!("h0_list={}", bytes32_vec_literal(&h0));
+    emitln!("h1_list={}", bytes32_vec_literal(&h1));
     Ok(())
 }
 
-fn cmd_prepare_eval(args: &[String]) -> AppResult<()> {
-    let config = parse_session_config(args)?;
-    let m = parse_u64(&required_flag_value(args, "--m")?, "m")? as usize;
-    let x_value = parse_u64(&required_flag_value(args, "--x")?, "x")?;
-    let out_dir = PathBuf::from(required_flag_value(args, "--out-dir")?);
-    let verifier_seed = parse_optional_verifier_seed(args)?;
+/// Bob's opening of his committed choice of `m`.
+#[derive(Debug, Clone, Args)]
+struct ChoiceOpeningArgs {
+    /// Bob's choice commitment
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg, requires = "choice_salt")]
+    choice_commitment: Option<[u8; 32]>,
+    /// Salt of the choice commitment
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg, requires = "choice_commitment")]
+    choice_salt: Option<[u8; 32]>,
+    /// Index Bob committed to [default: the on-chain m]
+    #[arg(long, value_name = "INDEX")]
+    choice_m: Option<usize>,
+}
 
-    ensure_value_fits_bits(x_value, config.bit_width, "x")?;
-    if m >= CUT_AND_CHOOSE_N {
-        return Err(format!("m={} out of range [0, {})", m, CUT_AND_CHOOSE_N).into());
+impl ChoiceOpeningArgs {
+    /// Checks the opening against `onchain_m`. Returns `false` when no commitment was given.
+    fn check(&self, config: &SessionConfig, onchain_m: usize) -> AppResult<bool> {
+        let (Some(commitment), Some(salt)) = (self.choice_commitment, self.choice_salt) else {
+            return Ok(false);
+        };
+        verify_choice_opening(
+            config.params,
+            commitment,
+            config.circuit_id,
+            self.choice_m.unwrap_or(onchain_m),
+            salt,
+            onchain_m,
+        )?;
+        Ok(true)
     }
+}
 
-    let instances = build_instances(&config);
-    let inst = &instances[m];
-    let eval_payload =
-        build_eval_blob_payload_for_instance(&config, m, inst.seed, inst.leaves.clone())?;
-    let out_wire = eval_payload.output_wire;
-    let l_true_32 = eval_payload.lout_true;
-    let l_false_32 = eval_payload.lout_false;
-    let h0 = eval_payload.h0;
-    let h1 = eval_payload.h1;
-
-    let alice_labels16 = derive_alice_input_labels(
-        inst.seed,
-        config.circuit_id,
-        m as u64,
-        config.bit_width,
-        x_value,
-    );
-    let alice_labels32 = alice_labels16
-        .iter()
-        .map(|label| label16_to_bytes32(*label))
-        .collect::<Vec<_>>();
-
-    let y_offers = eval_payload.y_offers.clone();
-    let not_hints = eval_payload.not_hints.clone();
+#[derive(Debug, Args)]
+struct VerifyChoiceArgs {
+    #[command(flatten)]
+    choice: ChoiceOpeningArgs,
+    /// On-chain m [default: read from the contract]
+    #[arg(long, value_name = "INDEX")]
+    m: Option<usize>,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
 
-    fs::create_dir_all(&out_dir)?;
+/// `m()` of `CONTRACT_ADDRESS`, whose `N()` must match `params`.
+fn read_onchain_m(params: SessionParams) -> AppResult<usize> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    ensure_contract_n(&contract_address, &rpc_url, params)?;
+    let raw = run_cast(&[
+        "call".to_string(),
+        contract_address,
+        "m()(uint256)".to_string(),
+        "--rpc-url".to_string(),
+        rpc_url,
+    ])?;
+    Ok(parse_u64(raw.trim(), "m")? as usize)
+}
+
+fn cmd_verify_choice(args: VerifyChoiceArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let onchain_m = match args.m {
+        Some(m) => m,
+        None => read_onchain_m(config.params)?,
+    };
+    if !args.choice.check(&config, onchain_m)? {
+        return Err("Missing required flag: --choice-commitment".into());
+    }
+
+    emitln!("status=choice_verified");
+    emitln!("m={onchain_m}");
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct VerifyBeaconMArgs {
+    #[command(flatten)]
+    beacon: BeaconArgs,
+    /// m Bob claims the beacon selects [default: read from the contract]
+    #[arg(long, value_name = "INDEX")]
+    m: Option<usize>,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+/// Alice's side of `beacon-m`: re-derives m from the rootGC list on-chain and the block hash
+/// on her own node (or Bob's VRF proof) and checks it against the claimed m.
+fn cmd_verify_beacon_m(args: VerifyBeaconMArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let binding = read_beacon_binding(&contract_address, &rpc_url, config.params)?;
+    let (source, evidence) = args
+        .beacon
+        .evidence(&rpc_url)?
+        .ok_or("verify-beacon-m needs --block-height, or --vrf-key with --vrf-proof")?;
+    let claimed = match args.m {
+        Some(m) => m,
+        None => read_onchain_m(config.params)?,
+    };
+    verify_beacon_m(
+        config.params,
+        config.circuit_id,
+        binding,
+        source,
+        &evidence,
+        claimed,
+    )?;
+
+    emitln!("status=beacon_m_verified");
+    emitln!("binding={}", hex32(binding));
+    if let BeaconEvidence::BlockHash { hash, .. } = evidence {
+        emitln!("block_hash={}", hex32(hash));
+    }
+    emitln!("m={claimed}");
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct PrepareEvalArgs {
+    /// Instance Bob evaluates
+    #[arg(long, value_name = "INDEX")]
+    m: usize,
+    /// Alice's private input
+    #[arg(long, value_name = "uint256", value_parser = u256_arg)]
+    x: [u8; 32],
+    /// Directory the eval package is written to
+    #[arg(long, value_name = "PATH")]
+    out_dir: PathBuf,
+    /// Bob's verifier seed; also writes the instance's OT root and payloads
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    verifier_seed: Option<[u8; 32]>,
+    #[command(flatten)]
+    choice: ChoiceOpeningArgs,
+    #[command(flatten)]
+    compression: CompressionArgs,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+fn cmd_prepare_eval(args: PrepareEvalArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let m = args.m;
+    let x_value = args.x;
+    let out_dir = args.out_dir;
+    let verifier_seed = args.verifier_seed;
+    let compression = args.compression.compression();
+
+    ensure_value_fits_bits(x_value, config.bit_width, "x")?;
+    config.params.check_m(m)?;
+    let choice_verified = args.choice.check(&config, m)?;
+
+    let instances = build_instances(&config);
+    let inst = &instances[m];
+    let eval_payload =
+        build_eval_blob_payload_for_instance(&config, m, inst.seed, inst.leaves.clone())?;
+    let gates = config.circuit.build(config.bit_width);
+    let decoding = output_decoding_for_instance(&config, &gates, m, inst.seed)?;
+
+    let alice_labels16 = derive_alice_input_labels_u256(
+        inst.seed,
+        config.circuit_id,
+        m as u64,
+        config.bit_width,
+        x_value,
+    );
+    let alice_labels32 = alice_labels16
+        .iter()
+        .map(|label| label16_to_bytes32(*label))
+        .collect::<Vec<_>>();
+
+    let not_hints = eval_payload.not_hints.clone();
+
+    fs::create_dir_all(&out_dir)?;
 
-    let blob_file = out_dir.join("eval-m-blob.bin");
-    let blob_hash = write_eval_blob_payload(&blob_file, &eval_payload)?;
+    let (blob_file, blob_hash) =
+        write_eval_blob_payload(&out_dir.join("eval-m-blob.bin"), &eval_payload, compression)?;
 
     let leaves_file = out_dir.join("gc-m-leaves.txt");
     let mut leaves_raw = String::new();
@@ -673,7 +1126,7 @@ fn cmd_prepare_eval(args: &[String]) -> AppResult<()> {
         leaves_raw.push_str(&hex_prefixed(leaf));
         leaves_raw.push('\n');
     }
-    fs::write(&leaves_file, leaves_raw)?;
+    let leaves_file = write_artifact(&leaves_file, leaves_raw.as_bytes(), compression)?;
 
     let x16_file = out_dir.join("alice-x-labels16.txt");
     let mut x16_raw = String::new();
@@ -691,13 +1144,10 @@ fn cmd_prepare_eval(args: &[String]) -> AppResult<()> {
     }
     fs::write(&x32_file, x32_raw)?;
 
-    let offers_file = out_dir.join("bob-y-offers.txt");
-    let mut offers_raw = String::new();
-    for (idx, (l0, l1)) in y_offers.iter().enumerate() {
-        let wire_id = config.bit_width + idx;
-        offers_raw.push_str(&format!("{wire_id},{},{}\n", hex16(*l0), hex16(*l1)));
-    }
-    fs::write(&offers_file, offers_raw)?;
+    // Bob gets his y-labels through `ot-respond`, never both labels of a wire.
+    let ot_setup_file = out_dir.join("ot-setup.txt");
+    let ot_setup = ot_sender_for_instance(&config, m).setup_message();
+    fs::write(&ot_setup_file, format!("{}\n", hex_prefixed(&ot_setup)))?;
 
     let hints_file = out_dir.join("not-hints.txt");
     let mut hints_raw = String::new();
@@ -713,20 +1163,23 @@ fn cmd_prepare_eval(args: &[String]) -> AppResult<()> {
     }
     fs::write(&hints_file, hints_raw)?;
 
-    let meta_file = out_dir.join("eval-meta.txt");
-    let meta = format!(
-        "bit_width={}\ncircuit_id={}\ninstance_id={}\noutput_wire={}\nh0={}\nh1={}\nlout_true={}\nlout_false={}\n",
-        config.bit_width,
-        hex32(config.circuit_id),
-        m,
-        out_wire,
-        hex32(h0),
-        hex32(h1),
-        hex32(l_true_32),
-        hex32(l_false_32)
-    );
-    fs::write(&meta_file, meta)?;
+    let meta_file = EvalMeta::new(config.bit_width as u16, decoding).write(&out_dir)?;
 
+    let package_file = out_dir.join("eval-package.cbor");
+    let package = Message::EvalPackage(EvalPackageMessage {
+        bit_width: config.bit_width as u16,
+        decoding,
+        gc_leaves: inst.leaves.clone(),
+        alice_labels: alice_labels16.clone(),
+        not_hints: not_hints.clone(),
+        ot_setup: Some(ot_setup),
+    });
+    // Signed for Bob's `evaluate-m` when `ALICE_PRIVATE_KEY` and `CONTRACT_ADDRESS` are set.
+    let (package_bytes, package_signer) =
+        encode_signed_message(package, &["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+    let package_file = write_artifact(&package_file, &package_bytes, compression)?;
+
+    let mut ot_files = None;
     if let Some(verifier_seed) = verifier_seed {
         let ot_root = recompute_ot_root(
             config.circuit_id,
@@ -747,32 +1200,111 @@ fn cmd_prepare_eval(args: &[String]) -> AppResult<()> {
             payloads_raw.push_str(&hex32(payload_hash));
             payloads_raw.push('\n');
         }
-        fs::write(&payloads_file, payloads_raw)?;
-    }
-
-    println!("status=prepared_eval");
-    println!("eval_dir={}", out_dir.display());
-    println!("eval_blob_file={}", blob_file.display());
-    println!("eval_blob_hash={}", hex32(blob_hash));
-    println!("instance_id={m}");
-    println!("x_value={x_value}");
-    println!("output_wire={out_wire}");
-    println!("winner_formula={}", config.winner_formula);
-    println!("h0={}", hex32(h0));
-    println!("h1={}", hex32(h1));
-    println!("lout_true={}", hex32(l_true_32));
-    println!("lout_false={}", hex32(l_false_32));
-    println!("x_labels_count={}", alice_labels32.len());
-    println!("y_offer_count={}", y_offers.len());
-    println!("not_hint_count={}", not_hints.len());
+        let payloads_file = write_artifact(&payloads_file, payloads_raw.as_bytes(), compression)?;
+        ot_files = Some((root_file, payloads_file));
+    }
+
+    let mut manifest = new_artifact_manifest(&config);
+    let mut files = vec![
+        (blob_file.clone(), ArtifactRole::EvalBlob),
+        (leaves_file, ArtifactRole::Leaves),
+        (x16_file, ArtifactRole::AliceLabels16),
+        (x32_file, ArtifactRole::AliceLabels32),
+        (ot_setup_file.clone(), ArtifactRole::OtSetup),
+        (hints_file, ArtifactRole::NotHints),
+        (meta_file, ArtifactRole::EvalMeta),
+        (package_file.clone(), ArtifactRole::EvalPackage),
+    ];
+    if let Some((root_file, payloads_file)) = ot_files {
+        files.push((root_file, ArtifactRole::RootOt));
+        files.push((payloads_file, ArtifactRole::OtPayloads));
+    }
+    for (file, role) in files {
+        manifest.add_file(&out_dir, &file_name(&file)?, role, Some(m as u64))?;
+    }
+    manifest.instances.push(ManifestInstance {
+        instance_id: m as u64,
+        blob_hash_gc: blob_hash,
+        cid: None,
+    });
+    let manifest_signer = write_signed_manifest(&out_dir, &mut manifest)?;
+
+    emitln!("status=prepared_eval");
+    emitln!("eval_dir={}", out_dir.display());
+    emitln!("eval_blob_file={}", blob_file.display());
+    emitln!("eval_blob_hash={}", hex32(blob_hash));
+    emitln!("eval_package_file={}", package_file.display());
+    match package_signer {
+        Some(signer) => emitln!("eval_package_signer={}", hex_prefixed(&signer)),
+        None => emitln!("eval_package_signer=none"),
+    }
+    print_manifest_signer(manifest_signer);
+    emitln!("instance_id={m}");
+    emitln!("choice_verified={choice_verified}");
+    emitln!("x_value={}", u256_to_decimal(x_value));
+    emitln!("output_wire={}", decoding.output_wire);
+    emitln!("winner_formula={}", config.winner_formula);
+    emitln!("h0={}", hex32(decoding.h0));
+    emitln!("h1={}", hex32(decoding.h1));
+    emitln!("lout_true={}", hex32(decoding.lout_true));
+    emitln!("lout_false={}", hex32(decoding.lout_false));
+    emitln!("lsb_true={}", decoding.lsb_true);
+    emitln!("x_labels_count={}", alice_labels32.len());
+    emitln!("ot_setup_file={}", ot_setup_file.display());
+    emitln!("not_hint_count={}", not_hints.len());
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct OtRespondArgs {
+    /// Instance Bob evaluates
+    #[arg(long, value_name = "INDEX")]
+    m: usize,
+    /// Eval directory the responses are written to
+    #[arg(long, value_name = "PATH")]
+    eval_dir: PathBuf,
+    /// Bob's OT choices [default: <eval-dir>/bob-ot-choices.txt]
+    #[arg(long, value_name = "PATH")]
+    choices_file: Option<PathBuf>,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+fn cmd_ot_respond(args: OtRespondArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let m = args.m;
+    let eval_dir = args.eval_dir;
+    let choices_file = args
+        .choices_file
+        .unwrap_or_else(|| eval_dir.join("bob-ot-choices.txt"));
+    config.params.check_m(m)?;
+
+    let choices = read_ot_choices(&choices_file, config.bit_width)?;
+    record_answered_choices(&eval_dir, m as u64, &choices)?;
+    let seed = derive_instance_seed(config.master_seed, config.circuit_id, m as u64);
+    let y_offers = derive_bob_label_offers(seed, config.circuit_id, m as u64, config.bit_width);
+    let responses = ot_sender_for_instance(&config, m).respond(&choices, &y_offers)?;
+
+    let responses_file = eval_dir.join("bob-y-ot.txt");
+    let mut responses_raw = String::new();
+    for (idx, [e0, e1]) in responses.iter().enumerate() {
+        let wire_id = config.bit_width + idx;
+        responses_raw.push_str(&format!("{wire_id},{},{}\n", hex16(*e0), hex16(*e1)));
+    }
+    fs::write(&responses_file, responses_raw)?;
+
+    emitln!("status=ot_responded");
+    emitln!("instance_id={m}");
+    emitln!("ot_choice_count={}", choices.len());
+    emitln!("ot_responses_file={}", responses_file.display());
     Ok(())
 }
 
 fn cmd_deposit() -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let alice_private_key = required_env_any(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
-    let deposit_wei = env::var("DEPOSIT_WEI").unwrap_or_else(|_| "1000000000000000000".to_string());
+    let alice_private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+    let deposit_wei = deposit_wei()?;
 
     let stage_before = run_cast(&[
         "call".to_string(),
@@ -781,7 +1313,7 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url.clone(),
     ])?;
-    println!("stage_before={stage_before}");
+    emitln!("stage_before={stage_before}");
 
     let configured_alice = run_cast(&[
         "call".to_string(),
@@ -802,9 +1334,9 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url.clone(),
     ])?;
-    println!("configured_alice={configured_alice}");
-    println!("signer_alice={signer_alice}");
-    println!("alice_wallet_before={wallet_before}");
+    emitln!("configured_alice={configured_alice}");
+    emitln!("signer_alice={signer_alice}");
+    emitln!("alice_wallet_before={wallet_before}");
 
     let tx_result = run_cast(&[
         "send".to_string(),
@@ -824,7 +1356,7 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url.clone(),
     ])?;
-    println!("alice_wallet_after={wallet_after}");
+    emitln!("alice_wallet_after={wallet_after}");
 
     let vault = run_cast(&[
         "call".to_string(),
@@ -841,89 +1373,171 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url,
     ])?;
-    println!("alice_vault={vault}");
-    println!("stage_after={stage_after}");
+    emitln!("alice_vault={vault}");
+    emitln!("stage_after={stage_after}");
 
     Ok(())
 }
 
-fn cmd_submit_commitments(args: &[String]) -> AppResult<()> {
-    let rpc_url = rpc_url();
-    let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let alice_private_key = required_env_any(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
-    let buyer_address = resolve_target_buyer(args)?;
-    let config = parse_session_config(args)?;
-    let instances = build_instances(&config);
-    let zero = [0u8; 32];
-    let export_dir = parse_flag_value(args, "--export-dir").map(PathBuf::from);
-    let verifier_seed = parse_optional_verifier_seed(args)?;
-    let h_out = derive_h_out_lists(args, &config)?;
-
-    let root_gcs = if let Some(raw) = parse_flag_value(args, "--root-gcs") {
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--root-gcs must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
-        }
-        parsed
-    } else {
-        instances
-            .iter()
-            .map(|inst| inst.root_gc)
-            .collect::<Vec<_>>()
-    };
+/// Buyer whose OT roots Alice commits, from `--buyer` or `BOB_ADDRESS`.
+#[derive(Debug, Clone, Args)]
+struct BuyerArgs {
+    /// Buyer the OT roots are committed for
+    #[arg(long, value_name = "ADDRESS", env = "BOB_ADDRESS", value_parser = address_arg)]
+    buyer: [u8; 20],
+}
+
+/// Per-instance values `submit-commitments` and `submit-core-commitments` otherwise derive.
+#[derive(Debug, Clone, Args)]
+struct CoreCommitmentArgs {
+    /// rootGC of every instance [default: garbled from the master seed]
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    root_gcs: Option<ValueList<[u8; 32]>>,
+    /// blobHashGC of every instance [default: from the exported or encoded eval blobs]
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    blob_hashes: Option<ValueList<[u8; 32]>>,
+    #[command(flatten)]
+    h_out: HOutArgs,
+    /// Also export the instance artifacts to this directory
+    #[arg(long, value_name = "PATH")]
+    export_dir: Option<PathBuf>,
+    #[command(flatten)]
+    compression: CompressionArgs,
+    /// Encrypt the exported secret artifacts
+    #[arg(long)]
+    encrypt: bool,
+}
+
+/// OT roots given directly or derived from Bob's verifier seed.
+#[derive(Debug, Clone, Args)]
+struct OtRootArgs {
+    /// Bob's revealed verifier seed
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    verifier_seed: Option<[u8; 32]>,
+    /// rootOT of every instance
+    #[arg(
+        long,
+        value_name = "0x..,0x.. xN",
+        value_parser = bytes32_list_arg,
+        required_unless_present = "verifier_seed"
+    )]
+    root_ots: Option<ValueList<[u8; 32]>>,
+}
 
-    if let Some(path) = export_dir.as_ref() {
-        write_instance_files(path, &config, &instances, verifier_seed)?;
-        println!("artifacts_exported={}", path.display());
+impl OtRootArgs {
+    fn root_ots(
+        &self,
+        config: &SessionConfig,
+        instances: &[InstanceArtifacts],
+    ) -> AppResult<Vec<[u8; 32]>> {
+        match (&self.root_ots, self.verifier_seed) {
+            (Some(root_ots), _) => {
+                check_list_len("--root-ots", root_ots, config.params)?;
+                Ok(root_ots.clone())
+            }
+            (None, Some(verifier_seed)) => derive_ot_root_lists(config, instances, verifier_seed),
+            (None, None) => Err("Provide --verifier-seed or --root-ots for the OT roots".into()),
+        }
     }
+}
 
-    let blob_hashes = if let Some(raw) = parse_flag_value(args, "--blob-hashes") {
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--blob-hashes must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
+impl CoreCommitmentArgs {
+    /// `--root-gcs`, or the roots of the garbled instances.
+    fn root_gcs(
+        &self,
+        config: &SessionConfig,
+        instances: &[InstanceArtifacts],
+    ) -> AppResult<Vec<[u8; 32]>> {
+        match &self.root_gcs {
+            Some(root_gcs) => {
+                check_list_len("--root-gcs", root_gcs, config.params)?;
+                Ok(root_gcs.clone())
+            }
+            None => Ok(instances.iter().map(|inst| inst.root_gc).collect()),
         }
-        parsed
-    } else if let Some(path) = export_dir.as_ref() {
-        derive_blob_hashes_from_exported_payloads(path, &instances)?
-    } else {
-        vec![zero; CUT_AND_CHOOSE_N]
-    };
-    let root_ots = if let Some(raw) = parse_flag_value(args, "--root-ots") {
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--root-ots must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
+    }
+
+    /// `--blob-hashes`, or the hashes of the exported (else freshly encoded) eval blobs.
+    fn blob_hashes(
+        &self,
+        config: &SessionConfig,
+        instances: &[InstanceArtifacts],
+    ) -> AppResult<Vec<[u8; 32]>> {
+        match (&self.blob_hashes, &self.export_dir) {
+            (Some(blob_hashes), _) => {
+                check_list_len("--blob-hashes", blob_hashes, config.params)?;
+                Ok(blob_hashes.clone())
+            }
+            (None, Some(path)) => derive_blob_hashes_from_exported_payloads(path, instances),
+            (None, None) => derive_blob_hashes(config, instances),
         }
-        parsed
-    } else if let Some(verifier_seed) = verifier_seed {
-        derive_ot_root_lists(&config, &instances, verifier_seed)?
-    } else {
-        return Err(
-            "Provide --verifier-seed or --root-ots so Alice can commit rootOT values".into(),
-        );
-    };
-    let core_commitments_arg = build_commitments_arg(&instances, &root_gcs, &blob_hashes, &h_out);
+    }
+
+    /// Writes the instance files to `--export-dir`, if given.
+    fn export(
+        &self,
+        config: &SessionConfig,
+        instances: &[InstanceArtifacts],
+        verifier_seed: Option<[u8; 32]>,
+    ) -> AppResult<()> {
+        let Some(path) = &self.export_dir else {
+            return Ok(());
+        };
+        write_instance_files(
+            path,
+            config,
+            instances,
+            verifier_seed,
+            self.compression.compression(),
+            artifact_encryption(self.encrypt)?.as_ref(),
+        )?;
+        emitln!("artifacts_exported={}", path.display());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Args)]
+struct SubmitCommitmentsArgs {
+    #[command(flatten)]
+    buyer: BuyerArgs,
+    #[command(flatten)]
+    ot_roots: OtRootArgs,
+    #[command(flatten)]
+    commitments: CoreCommitmentArgs,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
 
-    println!("circuit_id={}", hex32(config.circuit_id));
-    println!("master_seed={}", hex32(config.master_seed));
-    println!("bit_width={}", config.bit_width);
-    println!("ot_roots_buyer={buyer_address}");
+fn cmd_submit_commitments(args: SubmitCommitmentsArgs) -> AppResult<()> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let alice_private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+    let buyer_address = args.buyer.buyer;
+    let config = args.config.resolve()?;
+    ensure_contract_n(&contract_address, &rpc_url, config.params)?;
+    let instances = build_instances(&config);
+    let commitments = &args.commitments;
+    let h_out = commitments.h_out.derive(&config)?;
+    let root_gcs = commitments.root_gcs(&config, &instances)?;
+    commitments.export(&config, &instances, args.ot_roots.verifier_seed)?;
+    let blob_hashes = commitments.blob_hashes(&config, &instances)?;
+    let root_ots = args.ot_roots.root_ots(&config, &instances)?;
+    let core_call = submit_commitments_call(&build_commitments(
+        &instances,
+        &root_gcs,
+        &blob_hashes,
+        &h_out,
+    ));
+    let ot_call = submit_ot_roots_call(buyer_address, &root_ots);
+
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("scheme={}", config.circuit.params.scheme_id);
+    emitln!("circuit_id={}", hex32(config.circuit_id));
+    emitln!("master_seed={}", hex32(config.master_seed));
+    emitln!("bit_width={}", config.bit_width);
+    emitln!("ot_roots_buyer={}", hex_prefixed(&buyer_address));
     for inst in &instances {
-        println!(
+        emitln!(
             "instance={} comSeed={} rootGC={} rootOT={} blobHashGC={} hOut={}",
             inst.instance_id,
             hex32(inst.com_seed),
@@ -934,90 +1548,62 @@ fn cmd_submit_commitments(args: &[String]) -> AppResult<()> {
         );
     }
 
-    let core_tx_result = run_cast(&[
-        "send".to_string(),
-        contract_address.clone(),
-        "submitCommitments((bytes32,bytes32,bytes32,bytes32)[10])".to_string(),
-        core_commitments_arg,
-        "--private-key".to_string(),
-        alice_private_key.clone(),
-        "--rpc-url".to_string(),
-        rpc_url.clone(),
-    ])?;
-    print_tx_summary("submit_core_commitments", &core_tx_result);
-
-    let ot_tx_result = run_cast(&[
-        "send".to_string(),
-        contract_address,
-        "submitOtRootsForBuyer(address,bytes32[10])".to_string(),
-        buyer_address,
-        bytes32_vec_literal(&root_ots),
+    let options = [
         "--private-key".to_string(),
         alice_private_key,
         "--rpc-url".to_string(),
         rpc_url,
-    ])?;
-    print_tx_summary("submit_ot_roots", &ot_tx_result);
+    ];
+    send_call_or_print(
+        "submit_core_commitments",
+        &contract_address,
+        &core_call,
+        &options,
+    )?;
+    send_call_or_print(
+        "submit_ot_roots",
+        &contract_address,
+        &ot_call,
+        &options,
+    )?;
     Ok(())
 }
 
-fn cmd_submit_core_commitments(args: &[String]) -> AppResult<()> {
+#[derive(Debug, Args)]
+struct SubmitCoreCommitmentsArgs {
+    #[command(flatten)]
+    commitments: CoreCommitmentArgs,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+fn cmd_submit_core_commitments(args: SubmitCoreCommitmentsArgs) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let alice_private_key = required_env_any(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
-    let config = parse_session_config(args)?;
+    let alice_private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+    let config = args.config.resolve()?;
+    ensure_contract_n(&contract_address, &rpc_url, config.params)?;
     let instances = build_instances(&config);
-    let zero = [0u8; 32];
-    let export_dir = parse_flag_value(args, "--export-dir").map(PathBuf::from);
-    let h_out = derive_h_out_lists(args, &config)?;
-
-    let root_gcs = if let Some(raw) = parse_flag_value(args, "--root-gcs") {
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--root-gcs must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
-        }
-        parsed
-    } else {
-        instances
-            .iter()
-            .map(|inst| inst.root_gc)
-            .collect::<Vec<_>>()
-    };
-
-    if let Some(path) = export_dir.as_ref() {
-        // core commit export does not depend on verifier seed
-        write_instance_files(path, &config, &instances, None)?;
-        println!("artifacts_exported={}", path.display());
-    }
-
-    let blob_hashes = if let Some(raw) = parse_flag_value(args, "--blob-hashes") {
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--blob-hashes must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
-        }
-        parsed
-    } else if let Some(path) = export_dir.as_ref() {
-        derive_blob_hashes_from_exported_payloads(path, &instances)?
-    } else {
-        vec![zero; CUT_AND_CHOOSE_N]
-    };
-    let commitments_arg = build_commitments_arg(&instances, &root_gcs, &blob_hashes, &h_out);
-
-    println!("circuit_id={}", hex32(config.circuit_id));
-    println!("master_seed={}", hex32(config.master_seed));
-    println!("bit_width={}", config.bit_width);
+    let commitments = &args.commitments;
+    let h_out = commitments.h_out.derive(&config)?;
+    let root_gcs = commitments.root_gcs(&config, &instances)?;
+    // core commit export does not depend on verifier seed
+    commitments.export(&config, &instances, None)?;
+    let blob_hashes = commitments.blob_hashes(&config, &instances)?;
+    let call = submit_commitments_call(&build_commitments(
+        &instances,
+        &root_gcs,
+        &blob_hashes,
+        &h_out,
+    ));
+
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("scheme={}", config.circuit.params.scheme_id);
+    emitln!("circuit_id={}", hex32(config.circuit_id));
+    emitln!("master_seed={}", hex32(config.master_seed));
+    emitln!("bit_width={}", config.bit_width);
     for inst in &instances {
-        println!(
+        emitln!(
             "instance={} comSeed={} rootGC={} blobHashGC={} hOut={}",
             inst.instance_id,
             hex32(inst.com_seed),
@@ -1027,246 +1613,1560 @@ fn cmd_submit_core_commitments(args: &[String]) -> AppResult<()> {
         );
     }
 
-    let tx_result = run_cast(&[
-        "send".to_string(),
-        contract_address,
-        "submitCommitments((bytes32,bytes32,bytes32,bytes32)[10])".to_string(),
-        commitments_arg,
-        "--private-key".to_string(),
-        alice_private_key,
-        "--rpc-url".to_string(),
-        rpc_url,
-    ])?;
-    print_tx_summary("submit_core_commitments", &tx_result);
+    send_call_or_print(
+        "submit_core_commitments",
+        &contract_address,
+        &call,
+        &[
+            "--private-key".to_string(),
+            alice_private_key,
+            "--rpc-url".to_string(),
+            rpc_url,
+        ],
+    )?;
     Ok(())
 }
 
-fn cmd_submit_ot_roots(args: &[String]) -> AppResult<()> {
+#[derive(Debug, Args)]
+struct SubmitOtRootsArgs {
+    #[command(flatten)]
+    buyer: BuyerArgs,
+    #[command(flatten)]
+    ot_roots: OtRootArgs,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+fn cmd_submit_ot_roots(args: SubmitOtRootsArgs) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let alice_private_key = required_env_any(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
-    let buyer_address = resolve_target_buyer(args)?;
-    let config = parse_session_config(args)?;
+    let alice_private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+    let buyer_address = args.buyer.buyer;
+    let config = args.config.resolve()?;
+    ensure_contract_n(&contract_address, &rpc_url, config.params)?;
     let instances = build_instances(&config);
-    let verifier_seed = parse_optional_verifier_seed(args)?;
-
-    let root_ots = if let Some(raw) = parse_flag_value(args, "--root-ots") {
-        let parsed = parse_bytes32_list_csv(&raw)?;
-        if parsed.len() != CUT_AND_CHOOSE_N {
-            return Err(format!(
-                "--root-ots must contain {} values, got {}",
-                CUT_AND_CHOOSE_N,
-                parsed.len()
-            )
-            .into());
-        }
-        parsed
-    } else if let Some(verifier_seed) = verifier_seed {
-        derive_ot_root_lists(&config, &instances, verifier_seed)?
-    } else {
-        return Err("Provide --verifier-seed or --root-ots for OT root submission".into());
-    };
-
-    println!("circuit_id={}", hex32(config.circuit_id));
-    println!("master_seed={}", hex32(config.master_seed));
-    println!("bit_width={}", config.bit_width);
-    println!("ot_roots_buyer={buyer_address}");
+    let root_ots = args.ot_roots.root_ots(&config, &instances)?;
+
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("scheme={}", config.circuit.params.scheme_id);
+    emitln!("circuit_id={}", hex32(config.circuit_id));
+    emitln!("master_seed={}", hex32(config.master_seed));
+    emitln!("bit_width={}", config.bit_width);
+    emitln!("ot_roots_buyer={}", hex_prefixed(&buyer_address));
     for inst in &instances {
-        println!(
+        emitln!(
             "instance={} rootOT={}",
             inst.instance_id,
             hex32(root_ots[inst.instance_id])
         );
     }
 
-    let tx_result = run_cast(&[
-        "send".to_string(),
-        contract_address,
-        "submitOtRootsForBuyer(address,bytes32[10])".to_string(),
-        buyer_address,
-        bytes32_vec_literal(&root_ots),
-        "--private-key".to_string(),
-        alice_private_key,
-        "--rpc-url".to_string(),
-        rpc_url,
-    ])?;
-    print_tx_summary("submit_ot_roots", &tx_result);
+    send_call_or_print(
+        "submit_ot_roots",
+        &contract_address,
+        &submit_ot_roots_call(buyer_address, &root_ots),
+        &[
+            "--private-key".to_string(),
+            alice_private_key,
+            "--rpc-url".to_string(),
+            rpc_url,
+        ],
+    )?;
     Ok(())
 }
 
-fn cmd_export_artifacts(args: &[String]) -> AppResult<()> {
-    let config = parse_session_config(args)?;
-    let out_dir = required_flag_value(args, "--out-dir")?;
-    let out_dir_path = PathBuf::from(out_dir);
-    let instances = build_instances(&config);
-    let verifier_seed = parse_optional_verifier_seed(args)?;
-    write_instance_files(&out_dir_path, &config, &instances, verifier_seed)?;
-
-    println!("status=exported");
-    println!("circuit_id={}", hex32(config.circuit_id));
-    println!("master_seed={}", hex32(config.master_seed));
-    println!("bit_width={}", config.bit_width);
-    println!("ot_artifacts_exported={}", verifier_seed.is_some());
-    println!("out_dir={}", out_dir_path.display());
-    Ok(())
+#[derive(Debug, Args)]
+struct ExportArtifactsArgs {
+    /// Directory the instance files are written to
+    #[arg(long, value_name = "PATH", required_unless_present = "bundle")]
+    out_dir: Option<PathBuf>,
+    /// Also pack the export into this bundle; alone, the files are staged in a scratch dir
+    #[arg(long, value_name = "FILE")]
+    bundle: Option<PathBuf>,
+    /// Eval directory packed into the bundle under eval/
+    #[arg(long, value_name = "PATH", requires = "bundle")]
+    eval_dir: Option<PathBuf>,
+    /// Bob's verifier seed; also exports the OT roots and payloads
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    verifier_seed: Option<[u8; 32]>,
+    #[command(flatten)]
+    compression: CompressionArgs,
+    /// Encrypt the secret artifacts
+    #[arg(long)]
+    encrypt: bool,
+    /// Gates garbled between two checkpoints
+    #[arg(
+        long,
+        value_name = "GATES",
+        default_value_t = DEFAULT_CHECKPOINT_GATES,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    checkpoint_gates: u64,
+    /// Continue the interrupted export in --out-dir
+    #[arg(long, requires = "out_dir")]
+    resume: bool,
+    #[command(flatten)]
+    config: SessionConfigArgs,
 }
 
-fn cmd_reveal_openings(args: &[String]) -> AppResult<()> {
-    let rpc_url = rpc_url();
-    let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let alice_private_key = required_env_any(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
-
-    let m = parse_u64(&required_flag_value(args, "--m")?, "m")? as usize;
-    let config = parse_session_config(args)?;
-    let instances = build_instances(&config);
-    let (indices, seeds) = opened_indices_and_seeds(&instances, m)?;
-
-    let indices_arg = uint_vec_literal(&indices);
-    let seeds_arg = bytes32_vec_literal(&seeds);
-    let tx_result = run_cast(&[
-        "send".to_string(),
-        contract_address,
-        "revealOpenings(uint256[],bytes32[])".to_string(),
-        indices_arg,
-        seeds_arg,
-        "--private-key".to_string(),
-        alice_private_key,
-        "--rpc-url".to_string(),
-        rpc_url,
-    ])?;
+fn cmd_export_artifacts(args: ExportArtifactsArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let bundle_path = args.bundle;
+    let eval_dir = args.eval_dir;
+    // A bundle-only export stages its files in a scratch directory.
+    let staging = args.out_dir.is_none();
+    let out_dir_path = match args.out_dir {
+        Some(out_dir) => out_dir,
+        None => {
+            let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+            env::temp_dir().join(format!("alice-bundle-{}-{nanos}", std::process::id()))
+        }
+    };
+    let verifier_seed = args.verifier_seed;
+    let compression = args.compression.compression();
+    let encryption = artifact_encryption(args.encrypt)?;
+    let manifest_signer = export_instance_files(
+        &out_dir_path,
+        &config,
+        verifier_seed,
+        compression,
+        encryption.as_ref(),
+        args.checkpoint_gates,
+        args.resume,
+    )?;
+
+    let bundle = match bundle_path.as_ref() {
+        Some(bundle_path) => {
+            let mut bundle = BundleBuilder::new();
+            bundle.add_dir("", &out_dir_path)?;
+            if let Some(eval_dir) = eval_dir.as_ref() {
+                bundle.add_dir("eval", eval_dir)?;
+            }
+            let manifest_hash = bundle.write(bundle_path)?;
+            if staging {
+                fs::remove_dir_all(&out_dir_path)?;
+            }
+            Some((bundle_path, bundle.len(), manifest_hash))
+        }
+        None => None,
+    };
 
-    print_tx_summary("reveal_openings", &tx_result);
-    println!("m={}", m);
-    println!("open_indices={:?}", indices);
+    emitln!("status=exported");
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("scheme={}", config.circuit.params.scheme_id);
+    emitln!("circuit_id={}", hex32(config.circuit_id));
+    emitln!("master_seed={}", hex32(config.master_seed));
+    emitln!("bit_width={}", config.bit_width);
+    emitln!("ot_artifacts_exported={}", verifier_seed.is_some());
+    emitln!("compressed={}", compression != ArtifactCompression::None);
+    emitln!("encrypted={}", encryption.is_some());
+    print_manifest_signer(manifest_signer);
+    if !staging {
+        emitln!("out_dir={}", out_dir_path.display());
+    }
+    if let Some((bundle_path, entries, manifest_hash)) = bundle {
+        emitln!("bundle={}", bundle_path.display());
+        emitln!("bundle_entries={entries}");
+        emitln!("bundle_manifest_hash={}", hex32(manifest_hash));
+    }
     Ok(())
 }
 
-fn cmd_reveal_labels(args: &[String]) -> AppResult<()> {
-    let rpc_url = rpc_url();
-    let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let alice_private_key = required_env_any(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
-
-    let labels = if let Some(raw) = parse_flag_value(args, "--labels") {
-        parse_bytes32_list_csv(&raw)?
-    } else if let Some(path) = parse_flag_value(args, "--labels-file") {
-        read_bytes32_lines_file(Path::new(&path))?
-    } else {
-        return Err("Provide --labels or --labels-file".into());
-    };
+#[derive(Debug, Args)]
+struct VerifyArtifactsArgs {
+    /// Export directory or bundle
+    #[arg(long, value_name = "PATH")]
+    dir: PathBuf,
+    /// Bob's verifier seed, to re-derive the OT artifacts too
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    verifier_seed: Option<[u8; 32]>,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
 
-    let labels_arg = bytes32_vec_literal(&labels);
-    let mut tx_args = vec![
-        "send".to_string(),
-        contract_address,
-        "revealGarblerLabels(bytes32[])".to_string(),
-        labels_arg,
-        "--private-key".to_string(),
-        alice_private_key,
-        "--rpc-url".to_string(),
-        rpc_url,
-    ];
-    let use_blob = args.iter().any(|arg| arg == "--blob");
-    if use_blob {
-        let blob_path = required_flag_value(args, "--path")?;
-        tx_args.push("--blob".to_string());
-        tx_args.push("--path".to_string());
-        tx_args.push(blob_path.clone());
+/// Re-derives every instance from the master seed and checks an export directory (or bundle)
+/// against both its manifest digests and the re-derived contents.
+fn cmd_verify_artifacts(args: VerifyArtifactsArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let dir = args.dir;
+    let verifier_seed = args.verifier_seed;
+
+    let manifest = ArtifactManifest::read(&dir)?;
+    if manifest.circuit != config.circuit.name
+        || manifest.circuit_id != config.circuit_id
+        || manifest.bit_width as usize != config.bit_width
+    {
+        return Err(format!(
+            "{} was exported for {} (bit-width {}, circuit-id {}), not {} (bit-width {}, circuit-id {})",
+            dir.display(),
+            manifest.circuit,
+            manifest.bit_width,
+            hex32(manifest.circuit_id),
+            config.circuit.name,
+            config.bit_width,
+            hex32(config.circuit_id)
+        )
+        .into());
     }
 
-    let tx_result = run_cast(&tx_args)?;
+    let gates = config.circuit.build(config.bit_width);
+    let mut expected = Vec::new();
+    let mut instances = Vec::with_capacity(config.params.n);
+    for instance_id in 0..config.params.n {
+        let inst = build_instance(&config, &gates, instance_id);
+        let (files, blob_hash_gc) = render_instance_file_set(&config, &inst, verifier_seed)?;
+        expected.extend(files.into_iter().map(|file| ExpectedArtifact {
+            name: file.name,
+            bytes: file.bytes,
+        }));
+        instances.push(ManifestInstance {
+            instance_id: instance_id as u64,
+            blob_hash_gc,
+            cid: None,
+        });
+    }
 
-    print_tx_summary("reveal_labels", &tx_result);
-    println!("labels_count={}", labels.len());
-    println!("blob_enabled={use_blob}");
-    Ok(())
+    let report = verify_artifacts(&dir, &expected, &instances)?;
+    emitln!("rederived_files={}", expected.len());
+    emitln!("ot_artifacts_rederived={}", verifier_seed.is_some());
+    print_manifest_report(&dir, &report)
 }
 
-fn print_help() {
-    println!("off-chain-alice commands:");
-    println!("  deposit");
-    println!(
-        "  derive-anchors [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>] [--winner-formula <0|1>]"
-    );
-    println!(
-        "  submit-commitments [--buyer <addr>] [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>] [--winner-formula <0|1>] [--verifier-seed <0x..32> | --root-ots <0x..,0x.. x10>] [--root-gcs <0x..,0x.. x10>] [--blob-hashes <0x..,0x.. x10>] [--h-out <0x..,0x.. x10> | --bids <u64,u64,...> --chosen-namehash <0x..32>] [--export-dir <path>]"
-    );
-    println!(
-        "  submit-core-commitments [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>] [--winner-formula <0|1>] [--root-gcs <0x..,0x.. x10>] [--blob-hashes <0x..,0x.. x10>] [--h-out <0x..,0x.. x10> | --bids <u64,u64,...> --chosen-namehash <0x..32>] [--export-dir <path>]"
-    );
-    println!(
-        "  submit-ot-roots [--buyer <addr>] [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>] [--verifier-seed <0x..32> | --root-ots <0x..,0x.. x10>]"
-    );
-    println!(
-        "  export-artifacts --out-dir <path> [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>] [--verifier-seed <0x..32>]"
-    );
-    println!(
-        "  prepare-eval --m <index> --x <u64> --out-dir <path> [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>] [--winner-formula <0|1>] [--verifier-seed <0x..32>]"
-    );
-    println!(
-        "  reveal-openings --m <index> [--bit-width <bits>] [--circuit-id <0x..32>] [--master-seed <0x..32>]"
-    );
-    println!(
-        "  reveal-labels (--labels <0x..,0x..> | --labels-file <path>) [--blob --path <payload-file>]"
-    );
-    println!();
-    println!("Default command with no args: deposit");
+#[derive(Debug, Args)]
+struct VerifyCommitmentsArgs {
+    /// comSeed of every instance about to be submitted
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    com_seeds: Option<ValueList<[u8; 32]>>,
+    /// rootGC of every instance about to be submitted
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    root_gcs: Option<ValueList<[u8; 32]>>,
+    /// blobHashGC of every instance about to be submitted
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    blob_hashes: Option<ValueList<[u8; 32]>>,
+    /// h0 of every instance, as `derive-anchors` printed it
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    h0_list: Option<ValueList<[u8; 32]>>,
+    /// h1 of every instance, as `derive-anchors` printed it
+    #[arg(long, value_name = "0x..,0x.. xN", value_parser = bytes32_list_arg)]
+    h1_list: Option<ValueList<[u8; 32]>>,
+    /// Export directory whose instance files are about to be committed to
+    #[arg(long, value_name = "PATH")]
+    export_dir: Option<PathBuf>,
+    /// Also check the commitments already submitted to CONTRACT_ADDRESS
+    #[arg(long)]
+    onchain: bool,
+    #[command(flatten)]
+    config: SessionConfigArgs,
 }
 
-fn main() -> AppResult<()> {
-    let args: Vec<String> = env::args().skip(1).collect();
-    let command = args.first().map(String::as_str).unwrap_or("deposit");
-    let tail = if args.is_empty() { &[][..] } else { &args[1..] };
-
-    match command {
-        "deposit" => cmd_deposit(),
-        "derive-anchors" => cmd_derive_anchors(tail),
-        "submit-commitments" => cmd_submit_commitments(tail),
-        "submit-core-commitments" => cmd_submit_core_commitments(tail),
-        "submit-ot-roots" => cmd_submit_ot_roots(tail),
-        "export-artifacts" => cmd_export_artifacts(tail),
-        "prepare-eval" => cmd_prepare_eval(tail),
-        "reveal-openings" => cmd_reveal_openings(tail),
-        "reveal-labels" => cmd_reveal_labels(tail),
-        "-h" | "--help" | "help" => {
-            print_help();
-            Ok(())
-        }
-        _ => Err(format!("Unknown command: {command}. Use --help.").into()),
+/// Re-derives every instance and checks the values about to be submitted (the lists given or
+/// an export directory) or already on chain against it, before a deposit depends on them.
+fn cmd_verify_commitments(args: VerifyCommitmentsArgs) -> AppResult<()> {
+    let config = args.config.resolve()?;
+    let n = config.params.n;
+    let mut sources = [
+        ("--com-seeds", CommitmentField::ComSeed, args.com_seeds),
+        ("--root-gcs", CommitmentField::RootGc, args.root_gcs),
+        ("--blob-hashes", CommitmentField::BlobHashGc, args.blob_hashes),
+        ("--h0-list", CommitmentField::H0, args.h0_list),
+        ("--h1-list", CommitmentField::H1, args.h1_list),
+    ]
+    .into_iter()
+    .filter_map(|(flag, field, values)| Some((flag.to_string(), vec![(field, values?)])))
+    .collect::<Vec<_>>();
+    if let Some(dir) = &args.export_dir {
+        sources.push((dir.display().to_string(), exported_commitment_values(dir, n)?));
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::{SystemTime, UNIX_EPOCH};
-
-    fn test_config() -> SessionConfig {
-        SessionConfig {
-            bit_width: 4,
-            circuit_id: keccak256(&[b"millionaires-yao-v1"]),
-            master_seed: keccak256(&[b"master-seed-v1"]),
-            winner_formula: 0,
-        }
+    if args.onchain {
+        sources.push(("contract".to_string(), onchain_commitment_values(config.params)?));
     }
-
-    #[test]
-    fn builds_all_instances() {
-        let instances = build_instances(&test_config());
-        assert_eq!(instances.len(), CUT_AND_CHOOSE_N);
-        assert!(instances.iter().all(|i| i.root_gc != [0u8; 32]));
-        assert!(instances.iter().all(|i| i.com_seed != [0u8; 32]));
+    if sources.is_empty() {
+        return Err("Nothing to verify: give the lists to submit, --export-dir or --onchain".into());
     }
 
-    #[test]
-    fn openings_exclude_m() {
-        let instances = build_instances(&test_config());
-        let (indices, seeds) = opened_indices_and_seeds(&instances, 7).expect("openings");
-        assert_eq!(indices.len(), CUT_AND_CHOOSE_N - 1);
-        assert_eq!(seeds.len(), CUT_AND_CHOOSE_N - 1);
-        assert!(!indices.contains(&7));
+    let derived = derive_commitments(&config)?;
+    let mut mismatches = Vec::new();
+    for (source, lists) in &sources {
+        mismatches.extend(check_commitments(&derived, source, lists)?);
     }
-
-    #[test]
+    emitln!(
+        "status={}",
+        if mismatches.is_empty() { "verified" } else { "mismatch" }
+    );
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("circuit_id={}", hex32(config.circuit_id));
+    emitln!("master_seed={}", hex32(config.master_seed));
+    emitln!("bit_width={}", config.bit_width);
+    for (source, _) in &sources {
+        emitln!("checked={source}");
+    }
+    for mismatch in &mismatches {
+        emitln!("mismatch={mismatch}");
+    }
+    if let Some(hint) = drift_hint(&mismatches) {
+        emitln!("hint={hint}");
+        return Err(format!(
+            "{} commitment values differ from the re-derived ones",
+            mismatches.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// comSeed, rootGC, blobHashGC, h0 and h1 of every instance, re-derived from `config`.
+fn derive_commitments(config: &SessionConfig) -> AppResult<DerivedCommitments> {
+    let gates = config.circuit.build(config.bit_width);
+    let mut derived = DerivedCommitments::default();
+    for instance_id in 0..config.params.n {
+        let inst = build_instance(config, &gates, instance_id);
+        derived.com_seeds.push(inst.com_seed);
+        derived.root_gcs.push(inst.root_gc);
+        let payload =
+            build_eval_blob_payload_for_instance(config, instance_id, inst.seed, inst.leaves)?;
+        let encoded = payload
+            .encode()
+            .map_err(|e| format!("failed to encode eval payload: {e}"))?;
+        derived
+            .blob_hashes
+            .push(eval_payload_versioned_blob_hash(&encoded)?);
+        derived.h0.push(payload.h0);
+        derived.h1.push(payload.h1);
+    }
+    Ok(derived)
+}
+
+/// comSeed, rootGC, blobHashGC, h0 and h1 of the first `n` instances exported to `dir`.
+fn exported_commitment_values(dir: &Path, n: usize) -> AppResult<Vec<FieldValues>> {
+    let mut com_seeds = Vec::with_capacity(n);
+    let mut root_gcs = Vec::with_capacity(n);
+    let mut blob_hashes = Vec::with_capacity(n);
+    let mut h0 = Vec::with_capacity(n);
+    let mut h1 = Vec::with_capacity(n);
+    for instance_id in 0..n {
+        let path = |suffix: &str| dir.join(format!("instance-{instance_id}-{suffix}"));
+        let read_value = |path: PathBuf| -> AppResult<[u8; 32]> {
+            let raw = fs::read_to_string(&path)
+                .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            parse_bytes32(raw.trim())
+        };
+        com_seeds.push(read_value(path("com-seed.txt"))?);
+        root_gcs.push(read_value(path("root-gc.txt"))?);
+
+        let blob_path = path("eval-blob.bin");
+        let encoded = read_artifact(&blob_path)?;
+        let payload = CanonicalEvalBlobPayload::decode(&encoded).map_err(|e| {
+            format!(
+                "failed to decode eval blob payload at {}: {e}",
+                blob_path.display()
+            )
+        })?;
+        blob_hashes.push(eval_payload_versioned_blob_hash(&encoded)?);
+        h0.push(payload.h0);
+        h1.push(payload.h1);
+    }
+    Ok(vec![
+        (CommitmentField::ComSeed, com_seeds),
+        (CommitmentField::RootGc, root_gcs),
+        (CommitmentField::BlobHashGc, blob_hashes),
+        (CommitmentField::H0, h0),
+        (CommitmentField::H1, h1),
+    ])
+}
+
+/// comSeed, rootGC and blobHashGC of every instance as stored in `instanceCommitments`.
+fn onchain_commitment_values(params: SessionParams) -> AppResult<Vec<FieldValues>> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    ensure_contract_n(&contract_address, &rpc_url, params)?;
+    let mut lists = [
+        (CommitmentField::ComSeed, Vec::with_capacity(params.n)),
+        (CommitmentField::RootGc, Vec::with_capacity(params.n)),
+        (CommitmentField::BlobHashGc, Vec::with_capacity(params.n)),
+    ];
+    for instance_id in 0..params.n {
+        let values = cast_call_values(
+            &contract_address,
+            &rpc_url,
+            &[
+                "instanceCommitments(uint256)(bytes32,bytes32,bytes32,bytes32)",
+                &instance_id.to_string(),
+            ],
+        )?;
+        for (idx, (field, list)) in lists.iter_mut().enumerate() {
+            let raw = values
+                .get(idx)
+                .ok_or_else(|| format!("instanceCommitments() returned no {field}"))?;
+            list.push(parse_bytes32(raw)?);
+        }
+    }
+    if lists[0].1.iter().all(|com_seed| *com_seed == [0u8; 32]) {
+        return Err(format!("{contract_address} has no commitments submitted yet").into());
+    }
+    Ok(lists.into())
+}
+
+#[derive(Debug, Args)]
+struct PublishIpfsArgs {
+    /// Export directory
+    #[arg(long, value_name = "PATH")]
+    dir: PathBuf,
+    /// Only publish this instance
+    #[arg(long, value_name = "ID")]
+    instance_id: Option<u64>,
+}
+
+/// Pins the eval blob of every instance (or `--instance-id`) of an exported directory to IPFS
+/// and records the CIDs in its manifest, which is then re-signed.
+fn cmd_publish_ipfs(args: PublishIpfsArgs) -> AppResult<()> {
+    let dir = args.dir;
+    let only = args.instance_id;
+    let mut manifest = ArtifactManifest::read(&dir)?;
+    let client = IpfsClient::from_env()?;
+
+    let instance_ids = manifest
+        .instances
+        .iter()
+        .map(|instance| instance.instance_id)
+        .filter(|id| only.is_none_or(|only| *id == only))
+        .collect::<Vec<_>>();
+    if instance_ids.is_empty() {
+        return Err(match only {
+            Some(id) => format!("{} has no instance {id} in its manifest", dir.display()),
+            None => format!("{} has no instances in its manifest", dir.display()),
+        }
+        .into());
+    }
+
+    let mut published = Vec::with_capacity(instance_ids.len());
+    for instance_id in instance_ids {
+        let name = manifest
+            .instance_file(instance_id, ArtifactRole::EvalBlob)
+            .map(|file| file.name.clone())
+            .ok_or_else(|| format!("manifest records no eval blob for instance {instance_id}"))?;
+        let blob = read_artifact(&dir.join(&name))?;
+        let blob_hash = eval_payload_versioned_blob_hash(&blob)?;
+        let instance = manifest
+            .instances
+            .iter_mut()
+            .find(|instance| instance.instance_id == instance_id)
+            .expect("listed above");
+        // Only publish the blob the commitment names.
+        if blob_hash != instance.blob_hash_gc {
+            return Err(format!(
+                "{name} does not hash to the manifest's blobHashGC {} of instance {instance_id}",
+                hex32(instance.blob_hash_gc)
+            )
+            .into());
+        }
+        let cid = client.pin(&blob)?;
+        instance.cid = Some(cid.to_string());
+        published.push((instance_id, cid, blob.len()));
+    }
+    let manifest_signer = write_signed_manifest(&dir, &mut manifest)?;
+
+    emitln!("status=published");
+    emitln!("ipfs_api={}", client.url());
+    emitln!("dir={}", dir.display());
+    for (instance_id, cid, byte_count) in published {
+        emitln!("instance={instance_id} cid={cid} bytes={byte_count}");
+    }
+    print_manifest_signer(manifest_signer);
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct RevealOpeningsArgs {
+    /// Instance Bob evaluates; all others are opened
+    #[arg(long, value_name = "INDEX")]
+    m: usize,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+fn cmd_reveal_openings(args: RevealOpeningsArgs) -> AppResult<()> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let alice_private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+
+    let m = args.m;
+    let config = args.config.resolve()?;
+    let instances = build_instances(&config);
+    ensure_contract_n(&contract_address, &rpc_url, config.params)?;
+    let (indices, seeds) = opened_indices_and_seeds(&instances, m, config.params)?;
+
+    send_call_or_print(
+        "reveal_openings",
+        &contract_address,
+        &reveal_openings_call(&indices, &seeds),
+        &[
+            "--private-key".to_string(),
+            alice_private_key,
+            "--rpc-url".to_string(),
+            rpc_url,
+        ],
+    )?;
+    emitln!("m={}", m);
+    emitln!("open_indices={:?}", indices);
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct RevealLabelsArgs {
+    /// Garbler labels
+    #[arg(
+        long,
+        value_name = "0x..,0x..",
+        value_parser = bytes32_list_arg,
+        required_unless_present_any = ["labels_file", "m"],
+        conflicts_with_all = ["labels_file", "m"]
+    )]
+    labels: Option<ValueList<[u8; 32]>>,
+    /// File with one label per line
+    #[arg(long, value_name = "PATH", conflicts_with = "m")]
+    labels_file: Option<PathBuf>,
+    /// Derive the labels of --x for this instance instead of reading them
+    #[arg(long, value_name = "INDEX", requires = "x")]
+    m: Option<usize>,
+    /// Alice's private input, with --m
+    #[arg(long, value_name = "uint256", value_parser = u256_arg, requires = "m")]
+    x: Option<[u8; 32]>,
+    /// Send the labels in a blob transaction carrying --path
+    #[arg(long, requires = "path")]
+    blob: bool,
+    /// Payload file of the blob
+    #[arg(long, value_name = "PAYLOAD-FILE", requires = "blob")]
+    path: Option<String>,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+/// Alice's x-wire labels of instance `m` as `revealGarblerLabels` takes them, the ones
+/// `prepare-eval` writes to `alice-x-labels32.txt`.
+fn revealed_labels(
+    config: &SessionConfig,
+    m: usize,
+    x_value: [u8; 32],
+) -> AppResult<Vec<[u8; 32]>> {
+    ensure_value_fits_bits(x_value, config.bit_width, "x")?;
+    config.params.check_m(m)?;
+    let seed = derive_instance_seed(config.master_seed, config.circuit_id, m as u64);
+    let labels16 = derive_alice_input_labels_u256(
+        seed,
+        config.circuit_id,
+        m as u64,
+        config.bit_width,
+        x_value,
+    );
+    Ok(labels16.into_iter().map(label16_to_bytes32).collect())
+}
+
+fn cmd_reveal_labels(args: RevealLabelsArgs) -> AppResult<()> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let alice_private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+
+    let labels = match (args.labels, &args.labels_file, args.m.zip(args.x)) {
+        (Some(labels), _, _) => labels,
+        (None, Some(path), _) => read_bytes32_lines_file(path)?,
+        (None, None, Some((m, x_value))) => revealed_labels(&args.config.resolve()?, m, x_value)?,
+        (None, None, None) => return Err("Provide --labels, --labels-file or --m with --x".into()),
+    };
+    if let Some(m) = args.m {
+        let onchain_m = cast_call_values(&contract_address, &rpc_url, &["m()(uint256)"])?
+            .into_iter()
+            .next()
+            .ok_or("m() returned nothing")?;
+        let onchain_m = parse_u64(&onchain_m, "m")?;
+        if onchain_m != m as u64 {
+            return Err(format!(
+                "--m {m} is not the evaluated instance: the contract's m() is {onchain_m}"
+            )
+            .into());
+        }
+    }
+
+    let labels_arg = bytes32_vec_literal(&labels);
+    let mut tx_args = vec![
+        "send".to_string(),
+        contract_address,
+        "revealGarblerLabels(bytes32[])".to_string(),
+        labels_arg,
+        "--private-key".to_string(),
+        alice_private_key,
+        "--rpc-url".to_string(),
+        rpc_url,
+    ];
+    let use_blob = args.blob;
+    if let Some(blob_path) = args.path.filter(|_| use_blob) {
+        tx_args.push("--blob".to_string());
+        tx_args.push("--path".to_string());
+        tx_args.push(blob_path);
+    }
+
+    let tx_result = run_cast(&tx_args)?;
+
+    print_tx_summary("reveal_labels", &tx_result);
+    if let Some(m) = args.m {
+        emitln!("instance_id={m}");
+    }
+    emitln!("labels_count={}", labels.len());
+    emitln!("blob_enabled={use_blob}");
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct GasReportArgs {
+    /// Bids the settled output and the committed hOut are computed from
+    #[arg(long, value_name = "u64,u64,...", value_parser = bids_arg)]
+    bids: ValueList<u64>,
+    /// Namehash of the auctioned name, committed into the output
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    chosen_namehash: [u8; 32],
+    /// Instance chosen for evaluation
+    #[arg(long, value_name = "INDEX", default_value_t = 0)]
+    m: usize,
+    /// Buyer the settle and dispute are estimated from [default: the contract's first buyer]
+    #[arg(long, value_name = "ADDRESS", env = "BOB_ADDRESS", value_parser = address_arg)]
+    buyer: Option<[u8; 20]>,
+    /// Gas price the fees are computed at [default: the node's]
+    #[arg(long, value_name = "WEI")]
+    gas_price: Option<u128>,
+    #[command(flatten)]
+    config: SessionConfigArgs,
+}
+
+/// The session's transactions, with the calldata they carry for `config`.
+fn gas_report_calls(
+    config: &SessionConfig,
+    bids: &[u64],
+    chosen_namehash: [u8; 32],
+    m: usize,
+) -> AppResult<Vec<GasCall>> {
+    config.params.check_m(m)?;
+    let instances = build_instances(config);
+    let h_out = HOutArgs {
+        h_out: None,
+        bids: Some(bids.to_vec()),
+        chosen_namehash: Some(chosen_namehash),
+        h0: None,
+    }
+    .derive(config)?;
+    let root_gcs = instances
+        .iter()
+        .map(|inst| inst.root_gc)
+        .collect::<Vec<_>>();
+    let blob_hashes = derive_blob_hashes(config, &instances)?;
+    let (indices, seeds) = opened_indices_and_seeds(&instances, m, config.params)?;
+    let outcome = evaluate_first_price_outcome(bids).map_err(|e| format!("invalid --bids: {e}"))?;
+    let output_bytes =
+        encode_auction_output_bytes(outcome.winner_id, outcome.winning_bid, chosen_namehash);
+    let disputed = indices[0];
+    let gas_call = |name, sender, call| GasCall { name, sender, call };
+    Ok(vec![
+        gas_call(
+            "submit_commitments",
+            Party::Garbler,
+            submit_commitments_call(&build_commitments(
+                &instances,
+                &root_gcs,
+                &blob_hashes,
+                &h_out,
+            )),
+        ),
+        gas_call(
+            "reveal_openings",
+            Party::Garbler,
+            reveal_openings_call(&indices, &seeds),
+        ),
+        // The gas does not depend on x, so the labels of x = 0 stand in for Alice's.
+        gas_call(
+            "reveal_labels",
+            Party::Garbler,
+            reveal_garbler_labels_call(&revealed_labels(config, m, [0u8; 32])?),
+        ),
+        gas_call("settle", Party::Evaluator, settle_call(&output_bytes)),
+        gas_call(
+            "dispute",
+            Party::Evaluator,
+            worst_case_dispute_call(
+                config.circuit,
+                config.bit_width,
+                config.circuit_id,
+                disputed as u64,
+                instances[disputed].seed,
+            )?,
+        ),
+    ])
+}
+
+fn cmd_gas_report(args: GasReportArgs) -> AppResult<()> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let config = args.config.resolve()?;
+    ensure_contract_n(&contract_address, &rpc_url, config.params)?;
+    let calls = gas_report_calls(&config, &args.bids, args.chosen_namehash, args.m)?;
+
+    let first_value = |call: &[&str]| -> AppResult<String> {
+        cast_call_values(&contract_address, &rpc_url, call)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("empty result from {}", call[0]).into())
+    };
+    let alice = first_value(&["alice()(address)"])?;
+    let buyer = match args.buyer {
+        Some(buyer) => hex_prefixed(&buyer),
+        None => first_value(&["buyerAt(uint256)(address)", "0"])?,
+    };
+    let gas_price = match args.gas_price {
+        Some(gas_price) => gas_price,
+        None => {
+            let raw = run_cast(&[
+                "gas-price".to_string(),
+                "--rpc-url".to_string(),
+                rpc_url.clone(),
+            ])?;
+            raw.split_whitespace()
+                .next()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| format!("invalid gas price from cast: {raw}"))?
+        }
+    };
+
+    let entries = gas_report(&calls, |call| {
+        let from = match call.sender {
+            Party::Garbler => &alice,
+            _ => &buyer,
+        };
+        let raw = run_cast(&[
+            "estimate".to_string(),
+            "--from".to_string(),
+            from.clone(),
+            contract_address.clone(),
+            call.call.calldata_hex(),
+            "--rpc-url".to_string(),
+            rpc_url.clone(),
+        ])
+        .map_err(|e| e.to_string())?;
+        let value = raw.split_whitespace().next().unwrap_or("");
+        value
+            .parse()
+            .map_err(|_| format!("invalid gas estimate from cast: {raw}"))
+    });
+
+    let sender_name = |party: Party| match party {
+        Party::Garbler => "alice",
+        _ => "bob",
+    };
+    emitln!("circuit={}", config.circuit.name);
+    emitln!("bit_width={}", config.bit_width);
+    emitln!("n={}", config.params.n);
+    emitln!("gas_price_wei={gas_price}");
+    for entry in &entries {
+        let estimated = match &entry.estimate {
+            GasEstimate::Estimated(gas) => gas.to_string(),
+            GasEstimate::Unavailable(_) => "unavailable".to_string(),
+        };
+        emitln!(
+            "call={} sender={} calldata_bytes={} intrinsic_gas={} estimate={} gas={} fee_wei={}",
+            entry.name,
+            sender_name(entry.sender),
+            entry.calldata_bytes,
+            entry.intrinsic_gas,
+            estimated,
+            entry.gas(),
+            u128::from(entry.gas()) * gas_price
+        );
+        if let GasEstimate::Unavailable(reason) = &entry.estimate {
+            emitln!("estimate_error={}: {}", entry.name, reason.trim());
+        }
+    }
+    for sender in [Party::Garbler, Party::Evaluator] {
+        let gas = sender_gas(&entries, sender);
+        let name = sender_name(sender);
+        emitln!("{name}_gas={gas}");
+        emitln!("{name}_fee_wei={}", u128::from(gas) * gas_price);
+    }
+    Ok(())
+}
+
+/// Own key for the secure channel.
+fn channel_identity() -> AppResult<EthIdentity> {
+    let private_key = parse_bytes32(&private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?)?;
+    Ok(EthIdentity::from_private_key(private_key)?)
+}
+
+#[derive(Debug, Args)]
+struct SendFilesArgs {
+    /// Peer to connect to
+    #[arg(long, value_name = "HOST:PORT")]
+    connect: String,
+    /// Address the peer must prove
+    #[arg(long, value_name = "0x..", value_parser = address_arg)]
+    peer_address: [u8; 20],
+    /// Directory the files are read from
+    #[arg(long, value_name = "PATH")]
+    dir: PathBuf,
+    /// Files to send [default: every file in --dir]
+    #[arg(long, value_name = "NAME,NAME", value_delimiter = ',')]
+    files: Option<Vec<String>>,
+}
+
+fn cmd_send_files(args: SendFilesArgs) -> AppResult<()> {
+    let addr = args.connect;
+    let dir = args.dir;
+    let names = match args.files {
+        Some(names) => names
+            .iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect(),
+        None => list_dir_files(&dir)?,
+    };
+    let (identity, peer_address) = (channel_identity()?, args.peer_address);
+    let policy = RetryPolicy::from_env()?;
+    let byte_count = send_files_to(&addr, &identity, peer_address, &dir, &names, &policy)?;
+
+    emitln!("status=sent");
+    emitln!("peer={addr}");
+    emitln!("peer_address={}", hex_prefixed(&peer_address));
+    emitln!("file_count={}", names.len());
+    emitln!("byte_count={byte_count}");
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct ReceiveFilesArgs {
+    /// Address to accept the peer on
+    #[arg(long, value_name = "HOST:PORT")]
+    listen: String,
+    /// Address the peer must prove
+    #[arg(long, value_name = "0x..", value_parser = address_arg)]
+    peer_address: [u8; 20],
+    /// Directory the files are written to
+    #[arg(long, value_name = "PATH")]
+    out_dir: PathBuf,
+}
+
+fn cmd_receive_files(args: ReceiveFilesArgs) -> AppResult<()> {
+    let listen = args.listen;
+    let out_dir = args.out_dir;
+    let (identity, peer_address) = (channel_identity()?, args.peer_address);
+    let policy = RetryPolicy::from_env()?;
+    let (peer, batch) = receive_files_on(&listen, &identity, peer_address, &out_dir, &policy)?;
+
+    emitln!("status=received");
+    emitln!("peer={peer}");
+    emitln!("peer_address={}", hex_prefixed(&peer_address));
+    emitln!("out_dir={}", out_dir.display());
+    emitln!("file_count={}", batch.names.len());
+    emitln!("files={}", batch.names.join(","));
+    emitln!("duplicate_batch={}", batch.duplicate);
+    Ok(())
+}
+
+/// Commands `serve` exposes over JSON-RPC: local computations only, no transactions.
+const SERVE_METHODS: &[&str] = &["derive-anchors", "export-artifacts", "prepare-eval", "ot-respond"];
+
+#[derive(Debug, Args)]
+struct ServeArgs {
+    /// Address of the JSON-RPC endpoint
+    #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:8701")]
+    listen: String,
+}
+
+fn cmd_serve(args: ServeArgs, session: Option<&SessionStore>) -> AppResult<()> {
+    let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+    serve_session(&cli, &args.listen, SERVE_METHODS, session)
+}
+
+#[derive(Debug, Args)]
+struct RelayArgs {
+    /// Address of the relayer endpoint; may be public, behind a TLS proxy
+    #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:8703")]
+    listen: String,
+}
+
+fn cmd_claim_timeout(options: ClaimTimeoutOptions) -> AppResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let private_key = private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])?;
+    claim_timeout(&contract_address, &rpc_url(), Party::Garbler, &private_key, options)
+}
+
+fn cmd_status() -> AppResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    print_contract_status(&contract_address, &rpc_url())
+}
+
+fn cmd_watch_events(options: &WatchOptions) -> AppResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    watch_contract(&contract_address, &rpc_url(), options)
+}
+
+/// Deployment and parties shared by `orchestrate` and `orchestrate-dispute`.
+#[derive(Debug, Args)]
+struct OrchestrateRunArgs {
+    /// Foundry project the contracts are deployed from
+    #[arg(long, value_name = "PATH", default_value = "../contract")]
+    contract_dir: PathBuf,
+    /// Bob's binary [default: off-chain-bob next to this binary, else on PATH]
+    #[arg(long, value_name = "PATH")]
+    bob_bin: Option<PathBuf>,
+    /// Private keys of the buyers [default: anvil accounts 1 to 3]
+    #[arg(long, value_name = "0x..,0x..", value_delimiter = ',')]
+    buyer_keys: Option<Vec<String>>,
+    /// One bid per buyer; buyer 0 also evaluates with its bid as input
+    #[arg(
+        long,
+        value_name = "u64,u64,...",
+        value_parser = bids_arg,
+        default_value = "70000000000000000,100000000000000000,90000000000000000"
+    )]
+    bids: ValueList<u64>,
+    /// Input bits per party
+    #[arg(long, value_name = "BITS", default_value_t = 57)]
+    bit_width: usize,
+    /// Directory for the exported instances, eval package and leaves [default: a new temp dir]
+    #[arg(long, value_name = "PATH")]
+    work_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+struct OrchestrateArgs {
+    #[command(flatten)]
+    run: OrchestrateRunArgs,
+    /// Alice's input to the evaluated instance
+    #[arg(long, value_name = "u64", default_value_t = 0, value_parser = u64_arg)]
+    x: u64,
+}
+
+#[derive(Debug, Args)]
+struct OrchestrateDisputeArgs {
+    #[command(flatten)]
+    run: OrchestrateRunArgs,
+    /// Corruption of the cheated instance's gate
+    #[arg(long, value_name = "KIND", value_parser = FAULT_KINDS, default_value = FAULT_KINDS[0])]
+    fault: String,
+    /// Gate whose leaf is corrupted
+    #[arg(long, value_name = "INDEX", default_value_t = 0)]
+    gate_index: usize,
+}
+
+/// A contract deployed for one run, and how its steps are sent.
+struct Orchestration {
+    plan: OrchestratePlan,
+    rpc_url: String,
+    contract_address: String,
+    alice_bin: PathBuf,
+    bob_bin: PathBuf,
+    garbler_deposit: String,
+    evaluator_deposit: String,
+}
+
+impl Orchestration {
+    /// Deploys the contracts and registers every buyer; Alice's input is `x`.
+    fn deploy(args: OrchestrateRunArgs, x: u64) -> AppResult<Self> {
+        if is_dry_run() {
+            return Err("orchestrate runs the protocol on-chain and cannot be a --dry-run".into());
+        }
+        let rpc_url = rpc_url();
+        let alice_key = configured_private_key(&["ALICE_PRIVATE_KEY", "ALICE_PK"])
+            .unwrap_or_else(|| ANVIL_KEYS[0].to_string());
+        let buyer_keys = args
+            .buyer_keys
+            .unwrap_or_else(|| ANVIL_KEYS[1..].iter().map(|key| key.to_string()).collect());
+        let work_dir = args.work_dir.unwrap_or_else(|| {
+            env::temp_dir().join(format!("ppa-orchestrate-{}", std::process::id()))
+        });
+        let plan = OrchestratePlan::new(
+            args.bit_width,
+            alice_key,
+            buyer_keys,
+            args.bids,
+            x,
+            work_dir,
+        )?;
+        let alice_bin = env::current_exe()?;
+        let bob_bin = args.bob_bin.unwrap_or_else(|| {
+            let sibling = alice_bin.with_file_name("off-chain-bob");
+            if sibling.is_file() {
+                sibling
+            } else {
+                PathBuf::from("off-chain-bob")
+            }
+        });
+        let circuit = lookup_circuit(DEFAULT_CIRCUIT)?;
+        circuit.check_bit_width(plan.bit_width)?;
+        let circuit_id = circuit.circuit_id(plan.bit_width, HIGHER_BID_WINS);
+        let layout_root = circuit.layout_root(circuit_id, plan.bit_width);
+        run_cast(&[
+            "chain-id".to_string(),
+            "--rpc-url".to_string(),
+            rpc_url.clone(),
+        ])
+        .map_err(|e| {
+            format!("no node at {rpc_url} (start one with scripts/start_anvil.sh): {e}")
+        })?;
+
+        let ens_adapter = forge_create(
+            &args.contract_dir,
+            ENS_ADAPTER_CONTRACT,
+            &rpc_url,
+            &plan.alice_key,
+            &[],
+        )?;
+        let contract_address = forge_create(
+            &args.contract_dir,
+            AUCTION_CONTRACT,
+            &rpc_url,
+            &plan.alice_key,
+            &plan.constructor_args(&ens_adapter, circuit_id, layout_root),
+        )?;
+        emitln!("contract={contract_address}");
+        emitln!("ens_adapter={ens_adapter}");
+        emitln!("circuit_id={}", hex32(circuit_id));
+        emitln!("buyer_count={}", plan.buyers.len());
+        emitln!("work_dir={}", plan.work_dir.display());
+        if let Some([buyers, receivers]) = plan.register_buyers_args() {
+            let tx_result = run_cast(&[
+                "send".to_string(),
+                contract_address.clone(),
+                "registerBuyers(address[],address[])".to_string(),
+                buyers,
+                receivers,
+                "--private-key".to_string(),
+                plan.alice_key.clone(),
+                "--rpc-url".to_string(),
+                rpc_url.clone(),
+            ])?;
+            print_tx_summary("register_buyers", &tx_result);
+        }
+
+        let mut orchestration = Self {
+            plan,
+            rpc_url,
+            contract_address,
+            alice_bin,
+            bob_bin,
+            garbler_deposit: String::new(),
+            evaluator_deposit: String::new(),
+        };
+        orchestration.garbler_deposit = orchestration.call(&["DEPOSIT_GARBLER()(uint256)"])?;
+        orchestration.evaluator_deposit = orchestration.call(&["DEPOSIT_EVALUATOR()(uint256)"])?;
+        Ok(orchestration)
+    }
+
+    /// First value a view function of the contract returns.
+    fn call(&self, call: &[&str]) -> AppResult<String> {
+        cast_call_values(&self.contract_address, &self.rpc_url, call)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("empty cast output for {}", call[0]).into())
+    }
+
+    /// Runs `step` with its party's binary and key and returns its stdout.
+    fn run(&self, step: &Step) -> AppResult<String> {
+        let (exe, deposit_wei) = match step.party {
+            Party::Bidder(_) => (&self.bob_bin, &self.evaluator_deposit),
+            _ => (&self.alice_bin, &self.garbler_deposit),
+        };
+        let mut step_env = vec![
+            ("RPC_URL", self.rpc_url.clone()),
+            ("CONTRACT_ADDRESS", self.contract_address.clone()),
+            ("DEPOSIT_WEI", deposit_wei.clone()),
+        ];
+        step_env.extend(self.plan.party_env(step.party));
+        let stdout = run_step(exe, step, &step_env)
+            .map_err(|e| format!("{} of {} failed: {e}", step.command, step.party_name()))?;
+        emitln!("step={} party={}", step.command, step.party_name());
+        Ok(stdout)
+    }
+
+    /// Runs `steps` and returns the stdout of the last one.
+    fn run_all(&self, steps: &[Step]) -> AppResult<String> {
+        let mut stdout = String::new();
+        for step in steps {
+            stdout = self.run(step)?;
+        }
+        Ok(stdout)
+    }
+
+    /// Checks that the run left the contract closed.
+    fn check_closed(&self) -> AppResult<()> {
+        let stage = parse_u8(&self.call(&["currentStage()(uint8)"])?, "stage")?;
+        if stage != CLOSED_STAGE {
+            return Err(
+                format!("the run ended in stage {}, not Closed", stage_name(stage)?).into(),
+            );
+        }
+        emitln!("stage={}", stage_name(stage)?);
+        Ok(())
+    }
+}
+
+fn cmd_orchestrate(args: OrchestrateArgs) -> AppResult<()> {
+    let orchestration = Orchestration::deploy(args.run, args.x)?;
+    let plan = &orchestration.plan;
+    let choose_output = orchestration.run_all(&plan.steps_until_choose())?;
+    let m = output_value(&choose_output, "selected_m")
+        .ok_or("choose printed no selected_m")?
+        .parse::<usize>()?;
+    emitln!("m={m}");
+    let expected = evaluate_first_price_outcome(&plan.bids)?;
+    for step in plan.steps_from_open(m) {
+        let stdout = orchestration.run(&step)?;
+        if step.command == "evaluate-m" {
+            let decoded_bit = output_value(&stdout, "decoded_bit").unwrap_or_default();
+            if !matches!(decoded_bit.as_str(), "0" | "1") {
+                return Err("evaluate-m output label matches neither output anchor".into());
+            }
+            emitln!("decoded_bit={decoded_bit}");
+        } else if step.command == "settle-auction" {
+            let winner_id = output_value(&stdout, "winner_id").unwrap_or_default();
+            if winner_id != expected.winner_id.to_string() {
+                return Err(format!(
+                    "settled winner {winner_id}, but buyer {} bid highest",
+                    expected.winner_id
+                )
+                .into());
+            }
+            emitln!("winner_id={winner_id}");
+            emitln!("winning_bid={}", expected.winning_bid);
+        }
+    }
+
+    orchestration.check_closed()?;
+    emitln!("status=passed");
+    Ok(())
+}
+
+fn cmd_orchestrate_dispute(args: OrchestrateDisputeArgs) -> AppResult<()> {
+    let fault = fault_of_kind(&args.fault, args.gate_index)?;
+    // Alice's commands garble from the fixed test seed, since the steps pass no --master-seed.
+    let master_seed = keccak256(&[b"master-seed-v1"]);
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT)?;
+    let bit_width = args.run.bit_width;
+    circuit.check_bit_width(bit_width)?;
+    // A fault leaving the leaf unchanged fails here rather than after the deployment.
+    corrupt_instance(circuit, bit_width, master_seed, 0, fault)?;
+
+    let orchestration = Orchestration::deploy(args.run, 0)?;
+    let plan = &orchestration.plan;
+    orchestration.run_all(&plan.setup_steps())?;
+    let m = parse_u64(orchestration.call(&["m()(uint256)"])?.trim(), "m")? as usize;
+    emitln!("m={m}");
+    let corrupted = corrupt_instance(
+        circuit,
+        bit_width,
+        master_seed,
+        corrupted_instance_id(m),
+        fault,
+    )?;
+    let leaves_file = corrupted.write_leaves(&plan.dispute_dir())?;
+    let root_gcs = root_gcs_with(
+        circuit,
+        bit_width,
+        master_seed,
+        SessionParams::default(),
+        &corrupted,
+    );
+    emitln!("corrupted_instance={}", corrupted.instance_id);
+    emitln!("corruption={}", corrupted.description);
+    emitln!("corrupted_root_gc={}", hex32(corrupted.root_gc));
+    emitln!("claimed_leaves_file={}", leaves_file.display());
+
+    orchestration.run_all(&plan.commitment_steps(Some(&root_gcs)))?;
+    let dispute_output = orchestration.run_all(&plan.dispute_steps(m, &corrupted))?;
+    if output_value(&dispute_output, "dispute_submitted").as_deref() != Some("true") {
+        return Err("auto-dispute submitted no dispute".into());
+    }
+    let gate_index = output_value(&dispute_output, "selected_gate_index").unwrap_or_default();
+    if gate_index != args.gate_index.to_string() {
+        return Err(format!(
+            "auto-dispute challenged gate {gate_index}, not the corrupted gate {}",
+            args.gate_index
+        )
+        .into());
+    }
+    emitln!("disputed_gate={gate_index}");
+
+    orchestration.check_closed()?;
+    let alice = hex_prefixed(&plan.alice);
+    let alice_vault = orchestration.call(&["vault(address)(uint256)", &alice])?;
+    if parse_u64(alice_vault.trim(), "vault")? != 0 {
+        return Err(format!("Alice kept {alice_vault} wei after the dispute").into());
+    }
+    emitln!("slashed=alice");
+    emitln!("status=passed");
+    Ok(())
+}
+
+/// Checks a message file's signature against the signer's on-chain role.
+fn cmd_verify_message(path: PathBuf) -> AppResult<()> {
+    let (message, signer) = read_verified_message(&path, false)?;
+    let signer = signer.expect("read_verified_message rejects unsigned files");
+
+    emitln!("status=verified");
+    emitln!("file={}", path.display());
+    emitln!("kind={}", message.kind());
+    match message.sender() {
+        Party::Garbler => emitln!("sender_role=alice"),
+        _ => emitln!("sender_role=buyer"),
+    }
+    emitln!("signer={}", hex_prefixed(&signer));
+    Ok(())
+}
+
+/// Commands in protocol order, listed by `print_help` under the phase they drive.
+const COMMAND_PHASES: &[(Phase, &[&str])] = &[
+    (Phase::Deposit, &["deposit"]),
+    (
+        Phase::Commit,
+        &[
+            "derive-anchors",
+            "export-artifacts",
+            "publish-ipfs",
+            "verify-commitments",
+            "submit-commitments",
+            "submit-core-commitments",
+            "submit-ot-roots",
+        ],
+    ),
+    (Phase::Choose, &["verify-choice", "verify-beacon-m"]),
+    (Phase::Open, &["reveal-openings", "prepare-eval", "ot-respond"]),
+    (Phase::Eval, &["reveal-labels"]),
+];
+
+/// Session flags restored by `--session <dir>` when a command omits them.
+const SESSION_FLAGS: &[&str] = &[
+    "--circuit",
+    "--bit-width",
+    "--circuit-id",
+    "--n",
+    "--winner-formula",
+    "--verifier-seed",
+    "--m",
+];
+
+/// Secret flags read from stdin or a hidden prompt when given as `-`.
+const SECRET_FLAGS: &[&str] = &["--master-seed"];
+
+/// Alice's master seed is drawn once per session instead of defaulting to the fixed test seed.
+const SESSION_SEEDS: &[SessionSeed] = &[
+    SessionSeed {
+        // The first command of a session draws the master seed ...
+        commands: &["derive-anchors"],
+        flag: "--master-seed",
+        name: "master-seed",
+        generate: true,
+    },
+    SessionSeed {
+        // ... and the later ones must reuse it: a fresh seed would not match the commitments.
+        commands: &[
+            "submit-commitments",
+            "submit-core-commitments",
+            "submit-ot-roots",
+            "export-artifacts",
+            "verify-artifacts",
+            "verify-commitments",
+            "prepare-eval",
+            "ot-respond",
+            "reveal-labels",
+            "reveal-openings",
+        ],
+        flag: "--master-seed",
+        name: "master-seed",
+        generate: false,
+    },
+];
+
+fn cmd_resume(session: Option<&SessionStore>) -> AppResult<()> {
+    let store = session.ok_or("Missing required argument: --session")?;
+    print_session_resume(store, COMMAND_PHASES);
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct ReplayArgs {
+    /// Transcript to replay [default: the --session transcript]
+    #[arg(long, value_name = "PATH")]
+    transcript: Option<PathBuf>,
+    /// Cut-and-choose instances of the session
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Also check the recorded transactions against the chain
+    #[arg(long)]
+    check_chain: bool,
+}
+
+fn cmd_replay(args: ReplayArgs, session: Option<&SessionStore>) -> AppResult<()> {
+    let path = match (args.transcript, session) {
+        (Some(path), _) => path,
+        (None, Some(store)) => store.dir().join(TRANSCRIPT_FILE),
+        (None, None) => return Err("Provide --session <dir> or --transcript <path>".into()),
+    };
+    replay_transcript(&path, args.params, args.check_chain)
+}
+
+fn cmd_completions(args: CompletionsArgs) -> AppResult<()> {
+    let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+    io::stdout().write_all(completion_script(cli, args.shell).as_bytes())?;
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct DirArgs {
+    /// Export directory
+    #[arg(long, value_name = "PATH")]
+    dir: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct DiffArtifactsArgs {
+    #[arg(value_name = "DIR_A")]
+    dir_a: PathBuf,
+    #[arg(value_name = "DIR_B")]
+    dir_b: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct VerifyMessageArgs {
+    /// Signed message file
+    #[arg(long, value_name = "PATH")]
+    file: PathBuf,
+}
+
+/// Garbler side of the privacy-preserving auction.
+#[derive(Debug, Parser)]
+#[command(name = "off-chain-alice")]
+struct Cli {
+    /// Print the result as one JSON object instead of key=value lines
+    #[arg(long, global = true)]
+    json: bool,
+    /// Network profile to run against, from the config file (ppa.toml or PPA_CONFIG)
+    #[arg(long, global = true, value_enum)]
+    profile: Option<ProfileName>,
+    /// Print every transaction (target, value, calldata) instead of sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Log to stderr: -v commands and transactions, -vv cast calls and retries (or RUST_LOG)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Read the private key from stdin, or a hidden prompt, instead of ALICE_PRIVATE_KEY
+    #[arg(long, global = true)]
+    key_stdin: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Deposit DEPOSIT_WEI into the auction
+    Deposit,
+    /// Print the output anchors h0/h1 of every instance
+    DeriveAnchors(DeriveAnchorsArgs),
+    /// Submit the core commitments and the buyer's OT roots
+    SubmitCommitments(SubmitCommitmentsArgs),
+    /// Submit the core commitments (comSeed, rootGC, blobHashGC, hOut)
+    SubmitCoreCommitments(SubmitCoreCommitmentsArgs),
+    /// Submit the OT roots for a buyer
+    SubmitOtRoots(SubmitOtRootsArgs),
+    /// Garble every instance and write its files, resumably
+    ExportArtifacts(ExportArtifactsArgs),
+    /// Check an export against its manifest and the re-derived instances
+    VerifyArtifacts(VerifyArtifactsArgs),
+    /// Check the commitments to submit, or already submitted, against re-derived instances
+    VerifyCommitments(VerifyCommitmentsArgs),
+    /// Pin eval blobs to IPFS (IPFS_API_URL, default http://127.0.0.1:5001)
+    PublishIpfs(PublishIpfsArgs),
+    /// Check Bob's opening of his committed choice
+    VerifyChoice(VerifyChoiceArgs),
+    /// Check that the m Bob claims is the one the block-hash or VRF beacon selects
+    VerifyBeaconM(VerifyBeaconMArgs),
+    /// Write the eval package of instance m for Bob
+    PrepareEval(PrepareEvalArgs),
+    /// Answer Bob's OT choices for his input labels, one choice set per instance
+    OtRespond(OtRespondArgs),
+    /// Reveal the seeds of every instance but m
+    RevealOpenings(RevealOpeningsArgs),
+    /// Reveal the garbler labels
+    RevealLabels(RevealLabelsArgs),
+    /// Send files over the authenticated channel
+    SendFiles(SendFilesArgs),
+    /// Receive files over the authenticated channel
+    ReceiveFiles(ReceiveFilesArgs),
+    /// Claim the timeout or refund path of the current stage
+    ClaimTimeout(ClaimTimeoutOptions),
+    /// Print the contract's stage, parties, commitments, m, deadlines and vault balances
+    Status,
+    /// Print stage transitions, parties' actions and approaching deadlines as they happen
+    WatchEvents(WatchOptions),
+    /// Check a signed message file against the sender's on-chain role
+    VerifyMessage(VerifyMessageArgs),
+    /// Check an export against its manifest and signature
+    VerifyManifest(DirArgs),
+    /// Upgrade an export directory to the current layout
+    MigrateArtifacts(DirArgs),
+    /// Compare the instances of two export directories
+    DiffArtifacts(DiffArtifactsArgs),
+    /// Serve derive-anchors, export-artifacts, prepare-eval and ot-respond over JSON-RPC
+    Serve(ServeArgs),
+    /// Pay the gas of other parties' calls to CONTRACT_ADDRESS from RELAYER_PRIVATE_KEY
+    Relay(RelayArgs),
+    /// Show where the --session stopped and what runs next
+    Resume,
+    /// Replay a session transcript
+    Replay(ReplayArgs),
+    /// Estimate the gas and fees of the session's transactions
+    GasReport(GasReportArgs),
+    /// Run the whole happy path against a local node with Alice's and the buyers' keys
+    Orchestrate(OrchestrateArgs),
+    /// Corrupt one opened instance and run through the dispute that slashes Alice
+    OrchestrateDispute(OrchestrateDisputeArgs),
+    /// Print the completion script for bash, zsh, fish, elvish or powershell
+    Completions(CompletionsArgs),
+}
+
+fn main() -> AppResult<()> {
+    let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+    let argv = resolve_secret_args(&env::args().collect::<Vec<_>>(), SECRET_FLAGS)?;
+    let (command, argv, session) =
+        restore_session_argv(&cli, &argv, "deposit", SESSION_FLAGS, SESSION_SEEDS)?;
+    let cli: Cli = parse_cli(cli, &argv);
+    init_logging(cli.verbose);
+    let _span = tracing::info_span!("command", name = %command).entered();
+    tracing::info!("starting");
+    if let Some(profile) = cli.profile {
+        activate_profile(&load_profile(&config_path(), profile)?);
+    }
+    if cli.json {
+        start_json_output();
+    }
+    if cli.dry_run {
+        start_dry_run();
+    }
+    if cli.key_stdin {
+        start_key_stdin()?;
+    }
+    if let Some(path) = transcript_path(session.as_ref()) {
+        start_transcript(&path)?;
+    }
+
+    let result = match cli.command.unwrap_or(Command::Deposit) {
+        Command::Deposit => cmd_deposit(),
+        Command::DeriveAnchors(args) => cmd_derive_anchors(args),
+        Command::SubmitCommitments(args) => cmd_submit_commitments(args),
+        Command::SubmitCoreCommitments(args) => cmd_submit_core_commitments(args),
+        Command::SubmitOtRoots(args) => cmd_submit_ot_roots(args),
+        Command::ExportArtifacts(args) => cmd_export_artifacts(args),
+        Command::VerifyArtifacts(args) => cmd_verify_artifacts(args),
+        Command::VerifyCommitments(args) => cmd_verify_commitments(args),
+        Command::PublishIpfs(args) => cmd_publish_ipfs(args),
+        Command::VerifyChoice(args) => cmd_verify_choice(args),
+        Command::VerifyBeaconM(args) => cmd_verify_beacon_m(args),
+        Command::PrepareEval(args) => cmd_prepare_eval(args),
+        Command::OtRespond(args) => cmd_ot_respond(args),
+        Command::RevealOpenings(args) => cmd_reveal_openings(args),
+        Command::RevealLabels(args) => cmd_reveal_labels(args),
+        Command::SendFiles(args) => cmd_send_files(args),
+        Command::ReceiveFiles(args) => cmd_receive_files(args),
+        Command::ClaimTimeout(options) => cmd_claim_timeout(options),
+        Command::Status => cmd_status(),
+        Command::WatchEvents(options) => cmd_watch_events(&options),
+        Command::VerifyMessage(args) => cmd_verify_message(args.file),
+        Command::VerifyManifest(args) => verify_manifest_dir(&args.dir),
+        Command::MigrateArtifacts(args) => migrate_artifact_dir(&args.dir),
+        Command::DiffArtifacts(args) => print_artifact_diff(&args.dir_a, &args.dir_b),
+        Command::Serve(args) => cmd_serve(args, session.as_ref()),
+        Command::Relay(args) => serve_relayer(&args.listen),
+        Command::Resume => cmd_resume(session.as_ref()),
+        Command::Replay(args) => cmd_replay(args, session.as_ref()),
+        Command::GasReport(args) => cmd_gas_report(args),
+        Command::Orchestrate(args) => cmd_orchestrate(args),
+        Command::OrchestrateDispute(args) => cmd_orchestrate_dispute(args),
+        Command::Completions(args) => cmd_completions(args),
+    };
+    if let (Ok(()), Some(mut session)) = (&result, session)
+        && !is_dry_run()
+        && COMMAND_PHASES
+            .iter()
+            .any(|(_, commands)| commands.contains(&command.as_str()))
+    {
+        session.mark_completed(&command)?;
+    }
+    let error = result.as_ref().err().map(|e| e.to_string());
+    print_json_output(&command, error.as_deref());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use off_chain_common::cli::parse_bytes32_list_csv;
+    use off_chain_common::evaluation::derive_alice_input_labels;
+    use off_chain_common::registry::lookup_circuit;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn test_config() -> SessionConfig {
+        SessionConfig {
+            bit_width: 4,
+            circuit_id: keccak256(&[b"millionaires-yao-v1"]),
+            master_seed: keccak256(&[b"master-seed-v1"]),
+            winner_formula: HIGHER_BID_WINS,
+            params: SessionParams::default(),
+            circuit: lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit"),
+        }
+    }
+
+    #[test]
+    fn builds_all_instances() {
+        let instances = build_instances(&test_config());
+        assert_eq!(instances.len(), 10);
+        assert!(instances.iter().all(|i| i.root_gc != [0u8; 32]));
+        assert!(instances.iter().all(|i| i.com_seed != [0u8; 32]));
+    }
+
+    #[test]
+    fn openings_exclude_m() {
+        let instances = build_instances(&test_config());
+        let (indices, seeds) =
+            opened_indices_and_seeds(&instances, 7, SessionParams::default()).expect("openings");
+        assert_eq!(indices.len(), 9);
+        assert_eq!(seeds.len(), 9);
+        assert!(!indices.contains(&7));
+    }
+
+    #[test]
+    fn custom_n_sizes_instances_openings_and_signatures() {
+        let mut config = test_config();
+        config.params = SessionParams::new(4).expect("n");
+        let instances = build_instances(&config);
+        assert_eq!(instances.len(), 4);
+
+        let (indices, _) =
+            opened_indices_and_seeds(&instances, 2, config.params).expect("openings");
+        assert_eq!(indices, vec![0, 1, 3]);
+        assert!(opened_indices_and_seeds(&instances, 4, config.params).is_err());
+        assert!(opened_indices_and_seeds(&instances, 0, SessionParams::default()).is_err());
+
+        let zero = vec![[0u8; 32]; config.params.n];
+        let commitments = build_commitments(&instances, &zero, &zero, &zero);
+        assert_eq!(
+            submit_commitments_call(&commitments).signature,
+            "submitCommitments((bytes32,bytes32,bytes32,bytes32)[4])"
+        );
+        assert_eq!(
+            submit_ot_roots_call([0x11; 20], &zero).signature,
+            "submitOtRootsForBuyer(address,bytes32[4])"
+        );
+    }
+
+    #[test]
     fn parses_bytes32_list() {
         let raw = "[0x1111111111111111111111111111111111111111111111111111111111111111,0x2222222222222222222222222222222222222222222222222222222222222222]";
         let parsed = parse_bytes32_list_csv(raw).expect("parse csv");
@@ -1281,6 +3181,10 @@ mod tests {
     fn parses_u64_bids_csv() {
         let parsed = parse_u64_csv("10, 30, 20", "--bids").expect("parse bids");
         assert_eq!(parsed, vec![10, 30, 20]);
+        let parsed = parse_u64_csv("[0x1e, 70_000]", "--bids").expect("parse bids");
+        assert_eq!(parsed, vec![30, 70_000]);
+        let err = parse_u64_csv("1,0x1_0000_0000_0000_0000", "--bids").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid --bids[1]"), "{err}");
     }
 
     #[test]
@@ -1320,7 +3224,7 @@ mod tests {
         let verifier_seed = [0x42u8; 32];
 
         let roots = derive_ot_root_lists(&config, &instances, verifier_seed).expect("root ots");
-        assert_eq!(roots.len(), CUT_AND_CHOOSE_N);
+        assert_eq!(roots.len(), config.params.n);
         assert!(roots.iter().all(|root| *root != [0u8; 32]));
     }
 
@@ -1337,7 +3241,15 @@ mod tests {
             env::temp_dir().join(format!("alice-artifacts-{millis}"))
         };
 
-        write_instance_files(&path, &config, &instances, Some(verifier_seed)).expect("export");
+        write_instance_files(
+            &path,
+            &config,
+            &instances,
+            Some(verifier_seed),
+            ArtifactCompression::None,
+            None,
+        )
+        .expect("export");
         let root_ot_path = path.join("instance-0-root-ot.txt");
         let payloads_path = path.join("instance-0-ot-payloads.txt");
         let eval_blob_path = path.join("instance-0-eval-blob.bin");
@@ -1355,24 +3267,186 @@ mod tests {
     }
 
     #[test]
-    fn commitment_tuple_builder_uses_core_slots() {
+    fn verify_commitments_accepts_an_export_and_flags_drift() {
+        let config = test_config();
+        let path = {
+            let millis = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("time")
+                .as_millis();
+            env::temp_dir().join(format!("alice-commitments-{millis}"))
+        };
+        write_instance_files(
+            &path,
+            &config,
+            &build_instances(&config),
+            None,
+            ArtifactCompression::None,
+            None,
+        )
+        .expect("export");
+        let exported = exported_commitment_values(&path, config.params.n).expect("exported");
+        let source = path.display().to_string();
+
+        let derived = derive_commitments(&config).expect("derive");
+        assert_eq!(
+            check_commitments(&derived, &source, &exported).expect("check"),
+            vec![]
+        );
+
+        let drifted = derive_commitments(&SessionConfig {
+            bit_width: 8,
+            ..config.clone()
+        })
+        .expect("derive");
+        let mismatches = check_commitments(&drifted, &source, &exported).expect("check");
+        assert_eq!(mismatches.len(), 4 * config.params.n);
+        assert_eq!(
+            drift_hint(&mismatches),
+            Some("garbled circuits differ: check --circuit and --bit-width")
+        );
+
+        let reseeded = derive_commitments(&SessionConfig {
+            master_seed: [0x42; 32],
+            ..config
+        })
+        .expect("derive");
+        let mismatches = check_commitments(&reseeded, &source, &exported).expect("check");
+        assert!(
+            mismatches
+                .iter()
+                .any(|m| m.field == CommitmentField::ComSeed)
+        );
+        let _ = fs::remove_dir_all(path);
+    }
+
+    #[test]
+    fn commitment_calldata_uses_core_slots() {
         let config = test_config();
         let instances = build_instances(&config);
         let root_gcs = instances.iter().map(|inst| inst.root_gc).collect::<Vec<_>>();
-        let blob_hashes = vec![[0x11u8; 32]; CUT_AND_CHOOSE_N];
-        let h_out = vec![[0x22u8; 32]; CUT_AND_CHOOSE_N];
+        let blob_hashes = vec![[0x11u8; 32]; config.params.n];
+        let h_out = vec![[0x22u8; 32]; config.params.n];
 
-        let commitments_arg = build_commitments_arg(&instances, &root_gcs, &blob_hashes, &h_out);
+        let commitments = build_commitments(&instances, &root_gcs, &blob_hashes, &h_out);
+        let calldata = submit_commitments_call(&commitments).calldata();
 
+        // A fixed array of static tuples is laid out inline: four words per instance.
+        assert_eq!(calldata.len(), 4 + 128 * config.params.n);
         for inst in &instances {
-            let expected_tuple = format!(
-                "({},{},{},{})",
-                hex32(inst.com_seed),
-                hex32(root_gcs[inst.instance_id]),
-                hex32(blob_hashes[inst.instance_id]),
-                hex32(h_out[inst.instance_id]),
-            );
-            assert!(commitments_arg.contains(&expected_tuple));
+            let slot = &calldata[4 + 128 * inst.instance_id..][..128];
+            assert_eq!(slot[..32], inst.com_seed);
+            assert_eq!(slot[32..64], root_gcs[inst.instance_id]);
+            assert_eq!(slot[64..96], blob_hashes[inst.instance_id]);
+            assert_eq!(slot[96..], h_out[inst.instance_id]);
+        }
+    }
+
+    #[test]
+    fn revealed_labels_are_the_x_labels_of_instance_m() {
+        let config = test_config();
+        let instances = build_instances(&config);
+        let mut x_value = [0u8; 32];
+        x_value[31] = 9;
+        let labels = revealed_labels(&config, 3, x_value).expect("labels");
+        let expected =
+            derive_alice_input_labels(instances[3].seed, config.circuit_id, 3, config.bit_width, 9);
+        assert_eq!(labels.len(), config.bit_width);
+        for (label, expected) in labels.iter().zip(expected) {
+            assert_eq!(*label, label16_to_bytes32(expected));
+        }
+
+        x_value[31] = 16;
+        assert!(revealed_labels(&config, 3, x_value).is_err());
+        assert!(revealed_labels(&config, config.params.n, [0u8; 32]).is_err());
+    }
+
+    #[test]
+    fn reveal_labels_takes_one_labels_source() {
+        let parse = |source: &[&str]| {
+            let mut argv = vec!["off-chain-alice", "reveal-labels"];
+            argv.extend(source);
+            Cli::try_parse_from(argv)
+        };
+        assert!(parse(&["--m", "3", "--x", "9"]).is_ok());
+        assert!(parse(&["--labels-file", "labels.txt"]).is_ok());
+        assert!(parse(&["--m", "3"]).is_err());
+        assert!(parse(&["--x", "9"]).is_err());
+        assert!(parse(&["--labels-file", "labels.txt", "--m", "3", "--x", "9"]).is_err());
+        assert!(parse(&[]).is_err());
+    }
+
+    #[test]
+    fn gas_report_prepares_every_session_call() {
+        let config = test_config();
+        let calls = gas_report_calls(&config, &[41, 17, 99, 5], [0xab; 32], 3).unwrap();
+        let names = calls
+            .iter()
+            .map(|call| (call.name, call.sender))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ("submit_commitments", Party::Garbler),
+                ("reveal_openings", Party::Garbler),
+                ("reveal_labels", Party::Garbler),
+                ("settle", Party::Evaluator),
+                ("dispute", Party::Evaluator),
+            ]
+        );
+        let outcome = evaluate_first_price_outcome(&[41, 17, 99, 5]).unwrap();
+        let output_bytes =
+            encode_auction_output_bytes(outcome.winner_id, outcome.winning_bid, [0xab; 32]);
+        assert_eq!(
+            calls[3].call.calldata(),
+            settle_call(&output_bytes).calldata()
+        );
+        assert_eq!(
+            calls[2].call.calldata().len(),
+            4 + 64 + 32 * config.bit_width
+        );
+        assert!(gas_report_calls(&config, &[41, 17, 99, 5], [0xab; 32], 10).is_err());
+    }
+
+    #[test]
+    fn cli_definition_covers_every_phase_command() {
+        let mut cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+        cli.clone().debug_assert();
+        cli.build();
+        for (_, commands) in COMMAND_PHASES {
+            for name in *commands {
+                assert!(cli.find_subcommand(name).is_some(), "no subcommand {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn command_phases_follow_protocol_order() {
+        let phases = COMMAND_PHASES.iter().map(|(phase, _)| *phase).collect::<Vec<_>>();
+        assert!(phases.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(COMMAND_PHASES.iter().all(|(_, commands)| !commands.is_empty()));
+    }
+
+    #[test]
+    fn event_watcher_is_named_like_bobs() {
+        let cli = Cli::try_parse_from(["off-chain-alice", "watch-events", "--once"]).unwrap();
+        let Some(Command::WatchEvents(options)) = cli.command else {
+            panic!("watch-events did not parse as WatchEvents");
+        };
+        assert!(options.once);
+        assert!(Cli::try_parse_from(["off-chain-alice", "watch"]).is_err());
+    }
+
+    #[test]
+    fn completions_offer_every_command_and_its_flags() {
+        let cli = Cli::try_parse_from(["off-chain-alice", "completions", "bash"]).unwrap();
+        let Some(Command::Completions(args)) = cli.command else {
+            panic!("parsed {:?}", cli.command);
+        };
+        let script = completion_script(cli_command::<Cli>(COMMAND_PHASES, "deposit"), args.shell);
+        for word in ["off-chain-alice", "export-artifacts", "--master-seed", "--session"] {
+            assert!(script.contains(word), "completions lack {word}");
         }
+        assert!(Cli::try_parse_from(["off-chain-alice", "completions", "tcsh"]).is_err());
     }
 }
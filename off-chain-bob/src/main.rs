@@ -1,58 +1,87 @@
 use off_chain_common::cli::{
-    bytes32_vec_literal, hex_prefixed, hex32, parse_bytes16, parse_bytes32, parse_bytes32_list_csv,
-    parse_flag_value, parse_leaf71, parse_u8, parse_u16, parse_u64, print_tx_summary, required_env,
-    required_flag_value, rpc_url, run_cast,
+    ClaimTimeoutOptions, SessionSeed, WatchOptions, bytes32_vec_literal, cast_call_values,
+    claim_timeout, config_var, confirm, deposit_wei, encode_signed_message, ensure_contract_n,
+    hex_prefixed, hex32, is_dry_run, latest_block_timestamp, migrate_artifact_dir, parse_bytes16,
+    parse_bytes32, parse_fixed_bytes, parse_leaf71, parse_number_u64, parse_u8, parse_u64,
+    print_artifact_diff, print_contract_status, print_session_resume, print_tx_summary,
+    private_key, read_beacon_binding, read_deadlines, read_instance_commitment,
+    read_verified_message, replay_transcript, required_env, rpc_url, run_cast, send_call_or_print,
+    serve_relayer, serve_session, start_dry_run, start_key_stdin, start_transcript,
+    transcript_path, u256_to_decimal, verify_manifest_dir, verify_manifest_signature,
+    watch_contract,
 };
+use off_chain_common::cli_args::{
+    BeaconArgs, CompletionsArgs, ValueList, address_arg, bytes32_arg, bytes32_list_arg,
+    circuit_arg, cli_command, completion_script, parse_cli, restore_session_argv,
+    session_params_arg, u256_arg,
+};
+use off_chain_common::emitln;
+use off_chain_common::abi::{AbiCall, dispute_garbled_table_call};
 use off_chain_common::auction_outcome::evaluate_first_price_outcome;
-use off_chain_common::consensus::{keccak256, layout_leaf_hash};
+use off_chain_common::audit::audit_claimed_leaf_source;
+use off_chain_common::base_ot::{OT_POINT_LEN, OtReceiver};
+use off_chain_common::blob_sidecar::{BeaconClient, BlobTx, decode_blob_payload, parse_blob_file};
+use off_chain_common::canonical_json::to_canonical_json;
+use off_chain_common::compression::{find_artifact, read_artifact, read_artifact_to_string};
+use off_chain_common::consensus::keccak256;
+use off_chain_common::csv_export::{write_gates_csv, write_labels_csv};
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::dispute::watchdog::{
+    ContractSnapshot, DISPUTE_STAGE, InstanceVerdict, OpenedInstance, WatchEvent, Watchdog,
+    WatchdogConfig,
+};
+use off_chain_common::dispute::scan::{ScanVerdict, scan_disputes};
+use off_chain_common::dispute::{
+    PrepareDisputeConfig, is_json_packet_path, prepare_dispute_packet, read_dispute_packet_file,
+};
+use off_chain_common::eip4844::eval_payload_versioned_blob_hash;
 use off_chain_common::eval_blob::CanonicalEvalBlobPayload;
+use off_chain_common::eval_check::{EvalPackageParts, check_eval_package};
+use off_chain_common::eval_meta::EvalMeta;
 use off_chain_common::evaluation::{
-    NotGateHint, evaluate_garbled_circuit, label16_to_bytes32, u64_to_bits_le,
+    NotGateHint, evaluate_garbled_circuit, label16_to_bytes32, u256_bit_length, u256_to_bits_le,
 };
-use off_chain_common::garble::garble_circuit;
-use off_chain_common::ih::{gc_block_hash, ih_proof_from_hashes, incremental_root_from_hashes};
-use off_chain_common::merkle::{merkle_proof_from_hashes, merkle_root_from_hashes};
+use off_chain_common::identity::EthIdentity;
+use off_chain_common::ih::incremental_root;
+use off_chain_common::ipfs::{Cid, IpfsClient};
+use off_chain_common::leaf_file::LeafFile;
+use off_chain_common::line_reader::read_value_lines;
+use off_chain_common::logging::init_logging;
+use off_chain_common::manifest::ArtifactManifest;
+use off_chain_common::messages::{DisputePacketMessage, Message};
 use off_chain_common::ot::{
     ot_leaf_index, ot_message_author, ot_root_from_payload_hashes, recompute_ot_payload_hashes,
 };
-use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::output::{print_json_output, start_json_output};
+use off_chain_common::profile::{ProfileName, activate_profile, config_path, load_profile};
+use off_chain_common::protocol::{Party, Phase};
+use off_chain_common::scenario::beacon::{
+    BeaconEvidence, RandomnessSource, derive_beacon_m, verify_beacon_m, vrf_alpha, vrf_prove,
+    vrf_public_key,
+};
+use off_chain_common::registry::{CircuitVersion, DEFAULT_CIRCUIT};
+use off_chain_common::scenario::{SessionParams, choice_commitment};
+use off_chain_common::secrets::{random_bytes32, resolve_secret_args};
+use off_chain_common::session_store::SessionStore;
 use off_chain_common::settlement::{
-    default_circuit_id, encode_auction_output_bytes, output_anchor_hash, output_commitment_hash,
+    encode_auction_output_bytes, output_commitment_hash,
 };
-use off_chain_common::types::{CircuitLayout, GateDesc};
+use off_chain_common::retry::RetryPolicy;
+use off_chain_common::secure_channel::{receive_files_on, send_files_to};
+use off_chain_common::transport::list_dir_files;
+use off_chain_common::transcript::TRANSCRIPT_FILE;
+use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
+use clap::{ArgAction, Args, Parser, Subcommand};
 use std::env;
 use std::error::Error;
 use std::fs;
-use std::io::Read;
+use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 type AppResult<T> = Result<T, Box<dyn Error>>;
 
-#[derive(Debug, Clone)]
-struct PrepareDisputeConfig {
-    bit_width: usize,
-    circuit_id: [u8; 32],
-    instance_id: u64,
-    seed: [u8; 32],
-    claimed_leaves: Vec<[u8; 71]>,
-    gate_index: Option<usize>,
-    allow_false_challenge: bool,
-    expected_root_gc: Option<[u8; 32]>,
-}
-
-#[derive(Debug, Clone)]
-struct PreparedDispute {
-    gate_index: usize,
-    gate: GateDesc,
-    claimed_leaf: [u8; 71],
-    expected_leaf: [u8; 71],
-    mismatch_indices: Vec<usize>,
-    root_gc: [u8; 32],
-    layout_root: [u8; 32],
-    ih_proof: Vec<[u8; 32]>,
-    layout_proof: Vec<[u8; 32]>,
-}
-
 #[derive(Debug, Clone)]
 struct PrepareOtDisputeConfig {
     bit_width: usize,
@@ -76,42 +105,18 @@ struct PreparedOtDispute {
     root_match: Option<bool>,
 }
 
-fn read_claimed_leaves_file(path: &Path) -> AppResult<Vec<[u8; 71]>> {
-    let raw = fs::read_to_string(path)?;
-    let mut leaves = Vec::new();
-
-    for (line_idx, line) in raw.lines().enumerate() {
-        let mut value = line
-            .split('#')
-            .next()
-            .unwrap_or("")
-            .trim()
-            .trim_end_matches(',')
-            .trim()
-            .trim_matches('"')
-            .trim();
-
-        if value.is_empty() {
-            continue;
-        }
-
-        // Allow a bracket-wrapped one-leaf-per-line format.
-        value = value.trim_start_matches('[').trim_end_matches(']');
-        if value.is_empty() {
-            continue;
-        }
-
-        let leaf = parse_leaf71(value).map_err(|e| {
-            format!(
-                "invalid claimed leaf at {}:{}: {}",
-                path.display(),
-                line_idx + 1,
-                e
-            )
-        })?;
-        leaves.push(leaf);
+/// Opens a claimed-leaf file for `prepare-dispute`; plain files are memory-mapped rather than
+/// read into memory.
+fn open_claimed_leaves_file(path: &Path) -> AppResult<LeafFile> {
+    let leaves = LeafFile::open(path)?;
+    if leaves.is_empty() {
+        return Err(format!("No claimed leaves found in {}", path.display()).into());
     }
+    Ok(leaves)
+}
 
+fn read_claimed_leaves_file(path: &Path) -> AppResult<Vec<[u8; 71]>> {
+    let leaves = read_value_lines(path, "claimed leaf", parse_leaf71)?;
     if leaves.is_empty() {
         return Err(format!("No claimed leaves found in {}", path.display()).into());
     }
@@ -120,75 +125,17 @@ fn read_claimed_leaves_file(path: &Path) -> AppResult<Vec<[u8; 71]>> {
 
 #[allow(dead_code)]
 fn read_bytes32_lines_file(path: &Path) -> AppResult<Vec<[u8; 32]>> {
-    let raw = fs::read_to_string(path)?;
-    let mut out = Vec::new();
-
-    for (line_idx, line) in raw.lines().enumerate() {
-        let mut value = line
-            .split('#')
-            .next()
-            .unwrap_or("")
-            .trim()
-            .trim_end_matches(',')
-            .trim()
-            .trim_matches('"')
-            .trim();
-
-        if value.is_empty() {
-            continue;
-        }
-
-        value = value.trim_start_matches('[').trim_end_matches(']');
-        if value.is_empty() {
-            continue;
-        }
-
-        let parsed = parse_bytes32(value).map_err(|e| {
-            format!(
-                "invalid bytes32 line at {}:{}: {}",
-                path.display(),
-                line_idx + 1,
-                e
-            )
-        })?;
-        out.push(parsed);
-    }
-
+    let out = read_value_lines(path, "bytes32 line", parse_bytes32)?;
     if out.is_empty() {
         return Err(format!("No bytes32 values found in {}", path.display()).into());
     }
     Ok(out)
 }
 
-fn random_bytes32() -> AppResult<[u8; 32]> {
-    let mut file = fs::File::open("/dev/urandom")
-        .map_err(|e| format!("failed to open /dev/urandom (provide --seed explicitly): {e}"))?;
-    let mut seed = [0u8; 32];
-    file.read_exact(&mut seed)
-        .map_err(|e| format!("failed to read verifier seed from /dev/urandom: {e}"))?;
-    Ok(seed)
-}
-
 fn verifier_seed_commitment_with_salt(seed: [u8; 32], salt: [u8; 32]) -> [u8; 32] {
     keccak256(&[&seed, &salt])
 }
 
-fn parse_winner_formula(args: &[String]) -> AppResult<u8> {
-    let winner_formula = if let Some(raw) = parse_flag_value(args, "--winner-formula") {
-        parse_u64(&raw, "winner-formula")?
-    } else if let Ok(raw) = env::var("WINNER_FORMULA") {
-        parse_u64(&raw, "WINNER_FORMULA")?
-    } else {
-        0
-    };
-
-    if winner_formula > 1 {
-        return Err("winner-formula must be 0 (HigherBidWins) or 1 (LowerBidWins)".into());
-    }
-
-    Ok(winner_formula as u8)
-}
-
 fn parse_u64_csv(value: &str, flag_name: &str) -> AppResult<Vec<u64>> {
     let normalized = value.trim().trim_start_matches('[').trim_end_matches(']').trim();
     if normalized.is_empty() {
@@ -204,7 +151,7 @@ fn parse_u64_csv(value: &str, flag_name: &str) -> AppResult<Vec<u64>> {
                 return Err(format!("{flag_name} contains an empty item at position {}", idx + 1).into());
             }
             let field = format!("{flag_name}[{idx}]");
-            parse_u64(trimmed, &field)
+            parse_number_u64(trimmed, &field)
         })
         .collect()
 }
@@ -231,91 +178,8 @@ fn settle_auction_summary_lines(
     ])
 }
 
-#[derive(Debug, Clone)]
-struct EvalMeta {
-    bit_width: usize,
-    circuit_id: [u8; 32],
-    instance_id: u64,
-    output_wire: u16,
-    h0: [u8; 32],
-    h1: [u8; 32],
-    lout_true: [u8; 32],
-    lout_false: [u8; 32],
-}
-
-fn parse_key_value_file(path: &Path) -> AppResult<Vec<(String, String)>> {
-    let raw = fs::read_to_string(path)?;
-    let mut out = Vec::new();
-    for (line_idx, line) in raw.lines().enumerate() {
-        let trimmed = line.trim();
-        if trimmed.is_empty() || trimmed.starts_with('#') {
-            continue;
-        }
-        let Some((k, v)) = trimmed.split_once('=') else {
-            return Err(format!("invalid key=value at {}:{}", path.display(), line_idx + 1).into());
-        };
-        out.push((k.trim().to_string(), v.trim().to_string()));
-    }
-    Ok(out)
-}
-
-fn key_value_get<'a>(entries: &'a [(String, String)], key: &str) -> AppResult<&'a str> {
-    entries
-        .iter()
-        .find_map(|(k, v)| if k == key { Some(v.as_str()) } else { None })
-        .ok_or_else(|| format!("missing key '{key}'").into())
-}
-
-fn read_eval_meta(path: &Path) -> AppResult<EvalMeta> {
-    let entries = parse_key_value_file(path)?;
-
-    let bit_width = parse_u64(key_value_get(&entries, "bit_width")?, "bit_width")? as usize;
-    let circuit_id = parse_bytes32(key_value_get(&entries, "circuit_id")?)?;
-    let instance_id = parse_u64(key_value_get(&entries, "instance_id")?, "instance_id")?;
-    let output_wire = parse_u16(key_value_get(&entries, "output_wire")?, "output_wire")?;
-    let h0 = parse_bytes32(key_value_get(&entries, "h0")?)?;
-    let h1 = parse_bytes32(key_value_get(&entries, "h1")?)?;
-    let lout_true = parse_bytes32(key_value_get(&entries, "lout_true")?)?;
-    let lout_false = parse_bytes32(key_value_get(&entries, "lout_false")?)?;
-
-    Ok(EvalMeta {
-        bit_width,
-        circuit_id,
-        instance_id,
-        output_wire,
-        h0,
-        h1,
-        lout_true,
-        lout_false,
-    })
-}
-
 fn read_label16_lines(path: &Path) -> AppResult<Vec<[u8; 16]>> {
-    let raw = fs::read_to_string(path)?;
-    let mut out = Vec::new();
-    for (line_idx, line) in raw.lines().enumerate() {
-        let value = line
-            .split('#')
-            .next()
-            .unwrap_or("")
-            .trim()
-            .trim_end_matches(',')
-            .trim()
-            .trim_matches('"')
-            .trim();
-        if value.is_empty() {
-            continue;
-        }
-        let parsed = parse_bytes16(value).map_err(|e| {
-            format!(
-                "invalid 16-byte label at {}:{}: {}",
-                path.display(),
-                line_idx + 1,
-                e
-            )
-        })?;
-        out.push(parsed);
-    }
+    let out = read_value_lines(path, "16-byte label", parse_bytes16)?;
     if out.is_empty() {
         return Err(format!("No 16-byte labels found in {}", path.display()).into());
     }
@@ -326,9 +190,10 @@ fn read_leaf71_lines(path: &Path) -> AppResult<Vec<[u8; 71]>> {
     read_claimed_leaves_file(path)
 }
 
-fn read_y_offers(path: &Path, bit_width: usize) -> AppResult<Vec<([u8; 16], [u8; 16])>> {
-    let raw = fs::read_to_string(path)?;
-    let mut out = vec![None::<([u8; 16], [u8; 16])>; bit_width];
+/// Alice's `ot-respond` rows `wire,e0,e1`, one per y-wire.
+fn read_ot_responses(path: &Path, bit_width: usize) -> AppResult<Vec<[[u8; 16]; 2]>> {
+    let raw = read_artifact_to_string(path)?;
+    let mut out = vec![None::<[[u8; 16]; 2]>; bit_width];
 
     for (line_idx, line) in raw.lines().enumerate() {
         let trimmed = line.trim();
@@ -338,7 +203,7 @@ fn read_y_offers(path: &Path, bit_width: usize) -> AppResult<Vec<([u8; 16], [u8;
         let parts = trimmed.split(',').map(|s| s.trim()).collect::<Vec<_>>();
         if parts.len() != 3 {
             return Err(format!(
-                "invalid offer row at {}:{} (expected wire,label0,label1)",
+                "invalid OT response row at {}:{} (expected wire,e0,e1)",
                 path.display(),
                 line_idx + 1
             )
@@ -347,7 +212,7 @@ fn read_y_offers(path: &Path, bit_width: usize) -> AppResult<Vec<([u8; 16], [u8;
         let wire_id = parse_u64(parts[0], "wire_id")? as usize;
         if wire_id < bit_width || wire_id >= 2 * bit_width {
             return Err(format!(
-                "offer wire_id {} out of expected y range [{}, {})",
+                "OT response wire_id {} out of expected y range [{}, {})",
                 wire_id,
                 bit_width,
                 2 * bit_width
@@ -355,17 +220,37 @@ fn read_y_offers(path: &Path, bit_width: usize) -> AppResult<Vec<([u8; 16], [u8;
             .into());
         }
         let idx = wire_id - bit_width;
-        out[idx] = Some((parse_bytes16(parts[1])?, parse_bytes16(parts[2])?));
+        out[idx] = Some([parse_bytes16(parts[1])?, parse_bytes16(parts[2])?]);
     }
 
     out.into_iter()
         .enumerate()
-        .map(|(idx, maybe)| maybe.ok_or_else(|| format!("missing offer for y-bit {idx}").into()))
+        .map(|(idx, maybe)| {
+            maybe.ok_or_else(|| format!("missing OT response for y-bit {idx}").into())
+        })
         .collect()
 }
 
+fn read_ot_setup(path: &Path) -> AppResult<[u8; OT_POINT_LEN]> {
+    let raw = read_artifact_to_string(path)?;
+    parse_fixed_bytes::<OT_POINT_LEN>(raw.trim())
+        .map_err(|e| format!("invalid OT setup point in {}: {e}", path.display()).into())
+}
+
+/// Bob's y-labels, decrypted with `BOB_OT_SECRET` from Alice's `ot-respond` rows. OT is the
+/// only way Bob gets them: a package handing him both labels of a wire is never evaluated.
+fn ot_y_labels(
+    setup: &[u8; OT_POINT_LEN],
+    responses: &[[[u8; 16]; 2]],
+    y_bits: &[u8],
+) -> AppResult<Vec<[u8; 16]>> {
+    let secret = parse_bytes32(&required_env("BOB_OT_SECRET")?)?;
+    let receiver = OtReceiver::new(secret, setup, y_bits)?;
+    Ok(receiver.decrypt(responses)?)
+}
+
 fn read_not_hints(path: &Path) -> AppResult<Vec<NotGateHint>> {
-    let raw = fs::read_to_string(path)?;
+    let raw = read_artifact_to_string(path)?;
     let mut out = Vec::new();
 
     for (line_idx, line) in raw.lines().enumerate() {
@@ -395,102 +280,6 @@ fn read_not_hints(path: &Path) -> AppResult<Vec<NotGateHint>> {
     Ok(out)
 }
 
-fn prepare_dispute_packet(config: &PrepareDisputeConfig) -> AppResult<PreparedDispute> {
-    let gates = build_millionaires_layout(config.bit_width);
-    if config.claimed_leaves.len() != gates.len() {
-        return Err(format!(
-            "claimed leaves count ({}) does not match circuit gate count ({})",
-            config.claimed_leaves.len(),
-            gates.len()
-        )
-        .into());
-    }
-
-    let layout = CircuitLayout {
-        circuit_id: config.circuit_id,
-        instance_id: config.instance_id,
-        gates: gates.clone(),
-    };
-
-    let expected_leaves = garble_circuit(config.seed, &layout);
-    let mismatch_indices = config
-        .claimed_leaves
-        .iter()
-        .zip(expected_leaves.iter())
-        .enumerate()
-        .filter_map(
-            |(idx, (claimed, expected))| {
-                if claimed != expected { Some(idx) } else { None }
-            },
-        )
-        .collect::<Vec<_>>();
-
-    if mismatch_indices.is_empty() && config.gate_index.is_none() {
-        return Err(
-            "No mismatches found between claimed and expected leaves; dispute packet not created"
-                .into(),
-        );
-    }
-
-    let selected_gate_index = config.gate_index.unwrap_or_else(|| mismatch_indices[0]);
-    if selected_gate_index >= gates.len() {
-        return Err(format!(
-            "gate index {} out of range, total gates {}",
-            selected_gate_index,
-            gates.len()
-        )
-        .into());
-    }
-
-    let selected_is_mismatch = mismatch_indices.contains(&selected_gate_index);
-    if !selected_is_mismatch && !config.allow_false_challenge {
-        return Err(format!(
-            "selected gate {} matches expected leaf; refusing false challenge (use --allow-false-challenge to override)",
-            selected_gate_index
-        )
-        .into());
-    }
-
-    let block_hashes = config
-        .claimed_leaves
-        .iter()
-        .enumerate()
-        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
-        .collect::<Vec<_>>();
-    let root_gc = incremental_root_from_hashes(&block_hashes);
-    if let Some(expected_root_gc) = config.expected_root_gc {
-        if root_gc != expected_root_gc {
-            return Err(format!(
-                "computed rootGC {} does not match expected {}",
-                hex32(root_gc),
-                hex32(expected_root_gc)
-            )
-            .into());
-        }
-    }
-
-    let ih_proof = ih_proof_from_hashes(&block_hashes, selected_gate_index);
-    let layout_leaf_hashes = gates
-        .iter()
-        .enumerate()
-        .map(|(idx, gate)| layout_leaf_hash(config.circuit_id, idx as u64, *gate))
-        .collect::<Vec<_>>();
-    let layout_root = merkle_root_from_hashes(&layout_leaf_hashes);
-    let layout_proof = merkle_proof_from_hashes(&layout_leaf_hashes, selected_gate_index);
-
-    Ok(PreparedDispute {
-        gate_index: selected_gate_index,
-        gate: gates[selected_gate_index],
-        claimed_leaf: config.claimed_leaves[selected_gate_index],
-        expected_leaf: expected_leaves[selected_gate_index],
-        mismatch_indices,
-        root_gc,
-        layout_root,
-        ih_proof,
-        layout_proof,
-    })
-}
-
 fn prepare_ot_dispute_packet(config: &PrepareOtDisputeConfig) -> AppResult<PreparedOtDispute> {
     let expected_payload_hashes = recompute_ot_payload_hashes(
         config.circuit_id,
@@ -535,8 +324,8 @@ fn prepare_ot_dispute_packet(config: &PrepareOtDisputeConfig) -> AppResult<Prepa
 fn cmd_deposit() -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
-    let deposit_wei = env::var("DEPOSIT_WEI").unwrap_or_else(|_| "1000000000000000000".to_string());
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
+    let deposit_wei = deposit_wei()?;
 
     let stage_before = run_cast(&[
         "call".to_string(),
@@ -545,7 +334,7 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url.clone(),
     ])?;
-    println!("stage_before={stage_before}");
+    emitln!("stage_before={stage_before}");
 
     let signer_bob = run_cast(&[
         "wallet".to_string(),
@@ -559,10 +348,10 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url.clone(),
     ])?;
-    println!("signer_buyer={signer_bob}");
-    println!("bob_wallet_before={wallet_before}");
+    emitln!("signer_buyer={signer_bob}");
+    emitln!("bob_wallet_before={wallet_before}");
 
-    println!(
+    emitln!(
         "sending deposit() to {} with value={} wei",
         contract_address, deposit_wei
     );
@@ -584,7 +373,7 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url.clone(),
     ])?;
-    println!("bob_wallet_after={wallet_after}");
+    emitln!("bob_wallet_after={wallet_after}");
 
     let bob_vault = run_cast(&[
         "call".to_string(),
@@ -601,30 +390,31 @@ fn cmd_deposit() -> AppResult<()> {
         "--rpc-url".to_string(),
         rpc_url,
     ])?;
-    println!("bob_vault={bob_vault}");
-    println!("stage_after={stage_after}");
+    emitln!("bob_vault={bob_vault}");
+    emitln!("stage_after={stage_after}");
 
     Ok(())
 }
 
-fn cmd_commit_verifier_seed(args: &[String]) -> AppResult<()> {
+#[derive(Debug, Args)]
+struct CommitVerifierSeedArgs {
+    /// Verifier seed [default: random]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    seed: Option<[u8; 32]>,
+    /// Salt of the commitment [default: random]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    salt: Option<[u8; 32]>,
+    /// Commitment to submit instead of one built from --seed and --salt
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    commitment: Option<[u8; 32]>,
+}
+
+fn cmd_commit_verifier_seed(args: CommitVerifierSeedArgs) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
-
-    let commitment_override = parse_flag_value(args, "--commitment")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?;
-    let seed = parse_flag_value(args, "--seed")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?;
-    let salt = parse_flag_value(args, "--salt")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?;
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
 
+    let (commitment_override, seed, salt) = (args.commitment, args.seed, args.salt);
     let (commitment, used_seed, used_salt) = if let Some(commitment) = commitment_override {
         (commitment, seed, salt)
     } else {
@@ -649,21 +439,30 @@ fn cmd_commit_verifier_seed(args: &[String]) -> AppResult<()> {
     ])?;
     print_tx_summary("commit_verifier_seed", &tx_result);
     if let Some(seed) = used_seed {
-        println!("verifier_seed={}", hex32(seed));
+        emitln!("verifier_seed={}", hex32(seed));
     }
     if let Some(salt) = used_salt {
-        println!("verifier_salt={}", hex32(salt));
+        emitln!("verifier_salt={}", hex32(salt));
     }
-    println!("verifier_seed_commitment={}", hex32(commitment));
+    emitln!("verifier_seed_commitment={}", hex32(commitment));
     Ok(())
 }
 
-fn cmd_reveal_verifier_seed(args: &[String]) -> AppResult<()> {
+#[derive(Debug, Args)]
+struct RevealVerifierSeedArgs {
+    /// Committed verifier seed
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    seed: [u8; 32],
+    /// Salt of the commitment
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    salt: [u8; 32],
+}
+
+fn cmd_reveal_verifier_seed(args: RevealVerifierSeedArgs) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
-    let seed = parse_bytes32(&required_flag_value(args, "--seed")?)?;
-    let salt = parse_bytes32(&required_flag_value(args, "--salt")?)?;
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
+    let (seed, salt) = (args.seed, args.salt);
     let commitment = verifier_seed_commitment_with_salt(seed, salt);
 
     let tx_result = run_cast(&[
@@ -678,24 +477,167 @@ fn cmd_reveal_verifier_seed(args: &[String]) -> AppResult<()> {
         rpc_url,
     ])?;
     print_tx_summary("reveal_verifier_seed", &tx_result);
-    println!("verifier_seed={}", hex32(seed));
-    println!("verifier_salt={}", hex32(salt));
-    println!("verifier_seed_commitment={}", hex32(commitment));
+    emitln!("verifier_seed={}", hex32(seed));
+    emitln!("verifier_salt={}", hex32(salt));
+    emitln!("verifier_seed_commitment={}", hex32(commitment));
+    Ok(())
+}
+
+/// Circuit flags of the commands that need the circuit id or rebuild the circuit.
+#[derive(Debug, Clone, Args)]
+struct CircuitArgs {
+    /// Circuit from the registry
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_CIRCUIT, value_parser = circuit_arg)]
+    circuit: &'static CircuitVersion,
+    /// Input bits per party
+    #[arg(long, value_name = "BITS", default_value_t = 8)]
+    bit_width: usize,
+    /// Circuit id [default: derived from --circuit, --bit-width and --winner-formula]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    circuit_id: Option<[u8; 32]>,
+    /// 0 (HigherBidWins) or 1 (LowerBidWins)
+    #[arg(
+        long,
+        value_name = "0|1",
+        env = "WINNER_FORMULA",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(0..=1)
+    )]
+    winner_formula: u8,
+}
+
+impl CircuitArgs {
+    /// `--circuit-id`, or the id of `--circuit` at `--bit-width` under `--winner-formula`.
+    fn circuit_id(&self) -> AppResult<[u8; 32]> {
+        if let Some(circuit_id) = self.circuit_id {
+            return Ok(circuit_id);
+        }
+        self.circuit.check_bit_width(self.bit_width)?;
+        Ok(self.circuit.circuit_id(self.bit_width, self.winner_formula))
+    }
+}
+
+#[derive(Debug, Args)]
+struct CommitChoiceArgs {
+    /// Instance to evaluate
+    #[arg(long, value_name = "INDEX")]
+    m: usize,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Salt of the commitment [default: random]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    salt: Option<[u8; 32]>,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+fn cmd_commit_choice(args: CommitChoiceArgs) -> AppResult<()> {
+    let (params, m) = (args.params, args.m);
+    params.check_m(m)?;
+    let circuit_id = args.circuit.circuit_id()?;
+    let salt = match args.salt {
+        Some(salt) => salt,
+        None => random_bytes32()?,
+    };
+
+    // Send `choice_commitment` to Alice now; `choice_m` and `choice_salt` once m is on-chain.
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("choice_commitment={}", hex32(choice_commitment(circuit_id, m, salt)));
+    emitln!("choice_m={m}");
+    emitln!("choice_salt={}", hex32(salt));
     Ok(())
 }
 
-fn cmd_choose(args: &[String]) -> AppResult<()> {
+#[derive(Debug, Args)]
+struct BeaconMArgs {
+    #[command(flatten)]
+    beacon: BeaconArgs,
+    /// Claimed m to check against the beacon output
+    #[arg(long, value_name = "INDEX")]
+    m: Option<usize>,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+fn cmd_beacon_m(args: BeaconMArgs) -> AppResult<()> {
+    let params = args.params;
+    let circuit_id = args.circuit.circuit_id()?;
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-
-    let expected_m = if let Some(value) = parse_flag_value(args, "--m") {
-        Some(parse_u64(&value, "m")?)
-    } else if let Some(first) = args.first() {
-        Some(parse_u64(first, "m")?)
-    } else {
-        None
+    let binding = read_beacon_binding(&contract_address, &rpc_url, params)?;
+
+    let (source, evidence) = match args.beacon.evidence(&rpc_url)? {
+        Some(found) => found,
+        None => {
+            let secret = parse_bytes32(&required_env("BOB_VRF_SECRET")?)?;
+            let public_key = vrf_public_key(secret)?;
+            if args.beacon.vrf_key.is_some_and(|key| key != public_key) {
+                return Err("BOB_VRF_SECRET does not match --vrf-key".into());
+            }
+            let (proof, _) = vrf_prove(secret, &vrf_alpha(circuit_id, binding))?;
+            (
+                RandomnessSource::Vrf { public_key },
+                BeaconEvidence::Vrf { proof },
+            )
+        }
     };
 
+    let beacon_m = derive_beacon_m(params, circuit_id, binding, source, &evidence)?;
+    emitln!("binding={}", hex32(binding));
+    if let RandomnessSource::Vrf { public_key } = source {
+        emitln!("vrf_public_key={}", hex_prefixed(&public_key));
+    }
+    match evidence {
+        BeaconEvidence::BlockHash { hash, .. } => emitln!("block_hash={}", hex32(hash)),
+        BeaconEvidence::Vrf { proof } => emitln!("vrf_proof={}", hex_prefixed(&proof)),
+    }
+    emitln!("beacon_m={beacon_m}");
+    if let Some(claimed) = args.m {
+        verify_beacon_m(params, circuit_id, binding, source, &evidence, claimed)?;
+        emitln!("match_expected=true");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct ChooseArgs {
+    /// Expected m, also accepted as --m
+    #[arg(value_name = "M")]
+    expected_m: Option<u64>,
+    /// Expected m; takes precedence over the positional form
+    #[arg(long, value_name = "INDEX")]
+    m: Option<u64>,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+}
+
+fn cmd_choose(args: ChooseArgs) -> AppResult<()> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let params = args.params;
+    let expected_m = args.m.or(args.expected_m);
+
+    ensure_contract_n(&contract_address, &rpc_url, params)?;
     let onchain_m = run_cast(&[
         "call".to_string(),
         contract_address,
@@ -704,7 +646,8 @@ fn cmd_choose(args: &[String]) -> AppResult<()> {
         rpc_url,
     ])?;
     let selected_m = parse_u64(onchain_m.trim(), "m")?;
-    println!("selected_m={selected_m}");
+    params.check_m(selected_m as usize)?;
+    emitln!("selected_m={selected_m}");
 
     if let Some(expected) = expected_m {
         if selected_m != expected {
@@ -713,7 +656,7 @@ fn cmd_choose(args: &[String]) -> AppResult<()> {
             )
             .into());
         }
-        println!("match_expected=true");
+        emitln!("match_expected=true");
     }
 
     Ok(())
@@ -722,7 +665,7 @@ fn cmd_choose(args: &[String]) -> AppResult<()> {
 fn cmd_buyer_ready() -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
 
     let tx_result = run_cast(&[
         "send".to_string(),
@@ -740,7 +683,7 @@ fn cmd_buyer_ready() -> AppResult<()> {
 fn cmd_close_dispute() -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
 
     let tx_result = run_cast(&[
         "send".to_string(),
@@ -755,27 +698,49 @@ fn cmd_close_dispute() -> AppResult<()> {
     Ok(())
 }
 
-fn cmd_settle_auction(args: &[String]) -> AppResult<()> {
+fn bids_arg(raw: &str) -> Result<Vec<u64>, String> {
+    parse_u64_csv(raw, "--bids").map_err(|e| e.to_string())
+}
+
+#[derive(Debug, Args)]
+struct SettleAuctionArgs {
+    /// Bids the first-price auction outcome is computed from
+    #[arg(
+        long,
+        value_name = "u64,u64,...",
+        value_parser = bids_arg,
+        required_unless_present_any = ["winner_id", "winning_bid"]
+    )]
+    bids: Option<ValueList<u64>>,
+    /// Namehash of the auctioned name, committed into the output
+    #[arg(
+        long,
+        value_name = "0x..32",
+        value_parser = bytes32_arg,
+        required_unless_present_any = ["winner_id", "winning_bid"]
+    )]
+    chosen_namehash: Option<[u8; 32]>,
+    /// Removed in favour of --bids; kept to point old scripts at it
+    #[arg(long, hide = true)]
+    winner_id: Option<String>,
+    /// Removed in favour of --bids; kept to point old scripts at it
+    #[arg(long, hide = true)]
+    winning_bid: Option<String>,
+}
+
+fn cmd_settle_auction(args: SettleAuctionArgs) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
-    let chosen_namehash = parse_bytes32(&required_flag_value(args, "--chosen-namehash")?)?;
-    let dry_run = args.iter().any(|arg| arg == "--dry-run");
-    if parse_flag_value(args, "--winner-id").is_some()
-        || parse_flag_value(args, "--winning-bid").is_some()
-    {
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
+    if args.winner_id.is_some() || args.winning_bid.is_some() {
         return Err(
             "--winner-id/--winning-bid are no longer supported; use --bids + --chosen-namehash"
                 .into(),
         );
     }
-
-    let raw_bids = if let Some(raw) = parse_flag_value(args, "--bids") {
-        raw
-    } else {
-        return Err("--bids is required for settle-auction".into());
+    let (Some(bids), Some(chosen_namehash)) = (args.bids, args.chosen_namehash) else {
+        return Err("--bids and --chosen-namehash are required for settle-auction".into());
     };
-    let bids = parse_u64_csv(&raw_bids, "--bids")?;
     let circuit_id_raw = run_cast(&[
         "call".to_string(),
         contract_address.clone(),
@@ -800,21 +765,19 @@ fn cmd_settle_auction(args: &[String]) -> AppResult<()> {
         .ok_or("missing output_bytes in settle summary")?
         .to_string();
 
-    if !dry_run {
-        let tx_result = run_cast(&[
-            "send".to_string(),
-            contract_address,
-            "settle(bytes)".to_string(),
-            output_hex.clone(),
-            "--private-key".to_string(),
-            bob_private_key,
-            "--rpc-url".to_string(),
-            rpc_url,
-        ])?;
-        print_tx_summary("settle_auction", &tx_result);
-    }
+    let tx_result = run_cast(&[
+        "send".to_string(),
+        contract_address,
+        "settle(bytes)".to_string(),
+        output_hex.clone(),
+        "--private-key".to_string(),
+        bob_private_key,
+        "--rpc-url".to_string(),
+        rpc_url,
+    ])?;
+    print_tx_summary("settle_auction", &tx_result);
     for line in output_lines {
-        println!("{line}");
+        emitln!("{line}");
     }
     Ok(())
 }
@@ -822,7 +785,7 @@ fn cmd_settle_auction(args: &[String]) -> AppResult<()> {
 fn cmd_finalize_assignment() -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
 
     let tx_result = run_cast(&[
         "send".to_string(),
@@ -837,80 +800,142 @@ fn cmd_finalize_assignment() -> AppResult<()> {
     Ok(())
 }
 
-fn cmd_evaluate_m(args: &[String]) -> AppResult<()> {
-    let y_value = parse_u64(&required_flag_value(args, "--y")?, "y")?;
-    let eval_dir = parse_flag_value(args, "--eval-dir").map(|dir| Path::new(&dir).to_path_buf());
-    let payload_file = parse_flag_value(args, "--payload-file").map(PathBuf::from);
-    let alice_labels_file = parse_flag_value(args, "--alice-labels-file").map(PathBuf::from);
+#[derive(Debug, Args)]
+struct EvaluateMArgs {
+    /// Bob's private input
+    #[arg(long, value_name = "uint256", value_parser = u256_arg)]
+    y: [u8; 32],
+    /// Circuit from the registry
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_CIRCUIT, value_parser = circuit_arg)]
+    circuit: &'static CircuitVersion,
+    /// Signed eval package from Alice's prepare-eval
+    #[arg(long, value_name = "PATH")]
+    package_file: Option<PathBuf>,
+    /// Alice's OT responses [default: bob-y-ot.txt in --eval-dir]
+    #[arg(long, value_name = "PATH")]
+    y_ot_file: Option<PathBuf>,
+    /// Accept an unsigned --package-file
+    #[arg(long, requires = "package_file")]
+    allow_unsigned: bool,
+    /// Eval blob payload [default: eval-m-blob.bin in --eval-dir]
+    #[arg(long, value_name = "PATH")]
+    payload_file: Option<PathBuf>,
+    /// Eval directory written by Alice's prepare-eval
+    #[arg(long, value_name = "PATH")]
+    eval_dir: Option<PathBuf>,
+    /// Alice's input labels [default: alice-x-labels16.txt in --eval-dir]
+    #[arg(long, value_name = "PATH")]
+    alice_labels_file: Option<PathBuf>,
+}
+
+fn cmd_evaluate_m(args: EvaluateMArgs) -> AppResult<()> {
+    let circuit = args.circuit;
+    let y_value = args.y;
+    let eval_dir = args.eval_dir;
+    let payload_file = args.payload_file;
+    let package_file = args.package_file;
+    let alice_labels_file = args.alice_labels_file;
 
     let payload_path = if let Some(path) = payload_file {
         Some(path)
     } else {
-        eval_dir.as_ref().and_then(|dir| {
-            let p = dir.join("eval-m-blob.bin");
-            if p.exists() { Some(p) } else { None }
-        })
+        eval_dir
+            .as_ref()
+            .and_then(|dir| find_artifact(&dir.join("eval-m-blob.bin")))
     };
 
-    let (bit_width, circuit_id, instance_id, output_wire, h0, h1, lout_true, lout_false, leaves, y_offers, not_hints) =
-        if let Some(path) = payload_path {
-            let bytes = fs::read(&path)
-                .map_err(|e| format!("failed to read eval payload {}: {e}", path.display()))?;
-            let payload = CanonicalEvalBlobPayload::decode(&bytes)
-                .map_err(|e| format!("invalid eval payload {}: {e}", path.display()))?;
-            (
-                payload.bit_width as usize,
-                payload.circuit_id,
-                payload.instance_id,
-                payload.output_wire,
-                payload.h0,
-                payload.h1,
-                payload.lout_true,
-                payload.lout_false,
-                payload.gc_leaves,
-                payload.y_offers,
-                payload.not_hints,
-            )
-        } else {
-            let Some(dir) = eval_dir.as_ref() else {
-                return Err(
-                    "Provide --payload-file <path> or --eval-dir <path> for evaluate-m".into(),
-                );
-            };
-            let meta = read_eval_meta(&dir.join("eval-meta.txt"))?;
-            (
-                meta.bit_width,
-                meta.circuit_id,
-                meta.instance_id,
-                meta.output_wire,
-                meta.h0,
-                meta.h1,
-                meta.lout_true,
-                meta.lout_false,
-                read_leaf71_lines(&dir.join("gc-m-leaves.txt"))?,
-                read_y_offers(&dir.join("bob-y-offers.txt"), meta.bit_width)?,
-                read_not_hints(&dir.join("not-hints.txt"))?,
+    let mut package_alice_labels = None;
+    let mut package_signer = None;
+    let mut package_setup = None;
+    let (bit_width, decoding, leaves, not_hints) = if let Some(path) = package_file {
+        let (message, signer) = read_verified_message(&path, args.allow_unsigned)?;
+        let Message::EvalPackage(package) = message else {
+            return Err(format!("{} is not an eval package message", path.display()).into());
+        };
+        package_signer = signer;
+        package_setup = Some(
+            package
+                .ot_setup
+                .ok_or_else(|| format!("eval package {} has no OT setup", path.display()))?,
+        );
+        package_alice_labels = Some(package.alice_labels);
+        (
+            package.bit_width as usize,
+            package.decoding,
+            package.gc_leaves,
+            package.not_hints,
+        )
+    } else if let Some(path) = payload_path {
+        let bytes =
+            read_artifact(&path).map_err(|e| format!("failed to read eval payload: {e}"))?;
+        let payload = CanonicalEvalBlobPayload::decode(&bytes)
+            .map_err(|e| format!("invalid eval payload {}: {e}", path.display()))?;
+        if !payload.y_offers.is_empty() {
+            return Err(format!(
+                "eval payload {} publishes both labels of Bob's wires; refusing to evaluate it",
+                path.display()
             )
+            .into());
+        }
+        let decoding = OutputDecodingTable::from_openings(
+            payload.circuit_id,
+            payload.instance_id,
+            payload.output_wire,
+            [payload.h0, payload.h1],
+            [payload.lout_true, payload.lout_false],
+        );
+        (
+            payload.bit_width as usize,
+            decoding,
+            payload.gc_leaves,
+            payload.not_hints,
+        )
+    } else {
+        let Some(dir) = eval_dir.as_ref() else {
+            return Err("Provide --payload-file <path> or --eval-dir <path> for evaluate-m".into());
         };
+        let meta = EvalMeta::read(dir)?;
+        (
+            meta.bit_width as usize,
+            meta.decoding,
+            read_leaf71_lines(&dir.join("gc-m-leaves.txt"))?,
+            read_not_hints(&dir.join("not-hints.txt"))?,
+        )
+    };
+    let ot_setup = match (package_setup, eval_dir.as_ref()) {
+        (Some(setup), _) => setup,
+        (None, Some(dir)) => read_ot_setup(&dir.join("ot-setup.txt"))?,
+        (None, None) => {
+            return Err("Provide --eval-dir <path> with Alice's ot-setup.txt".into());
+        }
+    };
+    let ot_responses = match (args.y_ot_file, eval_dir.as_ref()) {
+        (Some(path), _) => read_ot_responses(&path, bit_width)?,
+        (None, Some(dir)) => read_ot_responses(&dir.join("bob-y-ot.txt"), bit_width)?,
+        (None, None) => return Err("Provide --y-ot-file <path> or --eval-dir <path>".into()),
+    };
+    let (circuit_id, instance_id, output_wire) =
+        (decoding.circuit_id, decoding.instance_id, decoding.output_wire);
 
-    if bit_width < 64 && y_value >= (1u64 << bit_width) {
+    if u256_bit_length(y_value) > bit_width {
         return Err(format!(
-            "y={} does not fit bit-width {} (max={})",
-            y_value,
+            "y={} does not fit bit-width {} (needs {} bits)",
+            u256_to_decimal(y_value),
             bit_width,
-            (1u64 << bit_width) - 1
+            u256_bit_length(y_value)
         )
         .into());
     }
 
-    let alice_labels_path = if let Some(path) = alice_labels_file {
-        path
+    let alice_labels = if let Some(labels) = package_alice_labels {
+        labels
+    } else if let Some(path) = alice_labels_file {
+        read_label16_lines(&path)?
     } else if let Some(dir) = eval_dir.as_ref() {
-        dir.join("alice-x-labels16.txt")
+        read_label16_lines(&dir.join("alice-x-labels16.txt"))?
     } else {
         return Err("Provide --alice-labels-file <path> when evaluating from --payload-file".into());
     };
-    let alice_labels = read_label16_lines(&alice_labels_path)?;
     if alice_labels.len() != bit_width {
         return Err(format!(
             "alice label count {} does not match bit-width {}",
@@ -920,25 +945,13 @@ fn cmd_evaluate_m(args: &[String]) -> AppResult<()> {
         .into());
     }
 
-    let y_bits = u64_to_bits_le(y_value, bit_width);
-    let bob_labels = y_bits
-        .iter()
-        .enumerate()
-        .map(|(idx, bit)| {
-            if *bit == 0 {
-                y_offers[idx].0
-            } else {
-                y_offers[idx].1
-            }
-        })
-        .collect::<Vec<_>>();
+    let y_bits = u256_to_bits_le(y_value, bit_width);
+    let bob_labels = ot_y_labels(&ot_setup, &ot_responses, &y_bits)?;
 
-    let gates = build_millionaires_layout(bit_width);
-    let layout = CircuitLayout {
-        circuit_id,
-        instance_id,
-        gates,
-    };
+    circuit.check_bit_width(bit_width)?;
+    let gates = circuit.build(bit_width);
+    let layout =
+        CircuitLayout::new(circuit_id, instance_id, gates).with_scheme_id(circuit.params.scheme_id);
 
     let evaluated_label16 = evaluate_garbled_circuit(
         &layout,
@@ -950,76 +963,239 @@ fn cmd_evaluate_m(args: &[String]) -> AppResult<()> {
     )
     .map_err(|e| format!("evaluate-m failed: {e}"))?;
     let evaluated_label32 = label16_to_bytes32(evaluated_label16);
-
-    let decoded_bit = if evaluated_label32 == lout_true {
-        Some(1u8)
-    } else if evaluated_label32 == lout_false {
-        Some(0u8)
-    } else {
-        None
-    };
-
-    println!("status=evaluated");
-    println!("instance_id={instance_id}");
-    println!("bit_width={bit_width}");
-    println!("y_value={y_value}");
-    println!("selected_y_labels={}", bob_labels.len());
-    println!("not_hint_count={}", not_hints.len());
-    println!("output_wire={output_wire}");
-    println!("output_label={}", hex32(evaluated_label32));
-    println!("h0={}", hex32(h0));
-    println!("h1={}", hex32(h1));
-    println!(
-        "matches_h0={}",
-        output_anchor_hash(circuit_id, instance_id, true, evaluated_label32) == h0
-    );
-    println!(
-        "matches_h1={}",
-        output_anchor_hash(circuit_id, instance_id, false, evaluated_label32) == h1
-    );
+    let decoded_bit = decoding.decode(evaluated_label16);
+    let (matches_h0, matches_h1) = decoding.anchor_matches(evaluated_label16);
+
+    emitln!("status=evaluated");
+    emitln!("instance_id={instance_id}");
+    emitln!("bit_width={bit_width}");
+    emitln!("y_value={}", u256_to_decimal(y_value));
+    emitln!("selected_y_labels={}", bob_labels.len());
+    emitln!("not_hint_count={}", not_hints.len());
+    if let Some(signer) = package_signer {
+        emitln!("eval_package_signer={}", hex_prefixed(&signer));
+    }
+    emitln!("output_wire={output_wire}");
+    emitln!("output_label={}", hex32(evaluated_label32));
+    emitln!("h0={}", hex32(decoding.h0));
+    emitln!("h1={}", hex32(decoding.h1));
+    emitln!("matches_h0={matches_h0}");
+    emitln!("matches_h1={matches_h1}");
     if let Some(bit) = decoded_bit {
-        println!("decoded_bit={bit}");
+        emitln!("decoded_bit={bit}");
     } else {
-        println!("decoded_bit=unknown");
+        emitln!("decoded_bit=unknown");
     }
 
     Ok(())
 }
 
-fn cmd_prepare_ot_dispute(args: &[String]) -> AppResult<()> {
-    let bit_width = parse_flag_value(args, "--bit-width")
-        .as_deref()
-        .map(|v| parse_u64(v, "bit-width"))
-        .transpose()?
-        .unwrap_or(8) as usize;
-    let winner_formula = parse_winner_formula(args)?;
-    let instance_id = parse_u64(&required_flag_value(args, "--instance-id")?, "instance-id")?;
-    let garbler_seed = if let Some(raw) = parse_flag_value(args, "--garbler-seed") {
-        parse_bytes32(&raw)?
+#[derive(Debug, Args)]
+struct VerifyEvalPackageArgs {
+    /// Eval directory written by Alice's prepare-eval
+    #[arg(long, value_name = "PATH")]
+    eval_dir: PathBuf,
+    /// Circuit from the registry
+    #[arg(long, value_name = "NAME", default_value = DEFAULT_CIRCUIT, value_parser = circuit_arg)]
+    circuit: &'static CircuitVersion,
+    /// rootGC the leaves must hash to [default: the on-chain one]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    expected_root_gc: Option<[u8; 32]>,
+}
+
+fn cmd_verify_eval_package(args: VerifyEvalPackageArgs) -> AppResult<()> {
+    let eval_dir = args.eval_dir;
+    let circuit = args.circuit;
+
+    // The same inputs `evaluate-m --eval-dir` reads: the blob payload, else the split files.
+    let payload_path = find_artifact(&eval_dir.join("eval-m-blob.bin"));
+    let (source, bit_width, decoding, leaves, not_hints, y_offers) = if let Some(path) =
+        &payload_path
+    {
+        let bytes = read_artifact(path).map_err(|e| format!("failed to read eval payload: {e}"))?;
+        let payload = CanonicalEvalBlobPayload::decode(&bytes)
+            .map_err(|e| format!("invalid eval payload {}: {e}", path.display()))?;
+        let decoding = OutputDecodingTable::from_openings(
+            payload.circuit_id,
+            payload.instance_id,
+            payload.output_wire,
+            [payload.h0, payload.h1],
+            [payload.lout_true, payload.lout_false],
+        );
+        (
+            "blob",
+            payload.bit_width as usize,
+            decoding,
+            payload.gc_leaves,
+            payload.not_hints,
+            payload.y_offers,
+        )
     } else {
-        parse_bytes32(&required_flag_value(args, "--seed")?)?
+        let meta = EvalMeta::read(&eval_dir)?;
+        (
+            "files",
+            meta.bit_width as usize,
+            meta.decoding,
+            read_leaf71_lines(&eval_dir.join("gc-m-leaves.txt"))?,
+            read_not_hints(&eval_dir.join("not-hints.txt"))?,
+            Vec::new(),
+        )
+    };
+    let alice_labels = read_label16_lines(&eval_dir.join("alice-x-labels16.txt"))?;
+    let instance_id = decoding.instance_id;
+
+    circuit.check_bit_width(bit_width)?;
+    let layout = CircuitLayout::new(decoding.circuit_id, instance_id, circuit.build(bit_width))
+        .with_scheme_id(circuit.params.scheme_id);
+    let committed_root_gc = match args.expected_root_gc {
+        Some(root_gc) => root_gc,
+        None => onchain_root_gc(&required_env("CONTRACT_ADDRESS")?, &rpc_url(), instance_id)?,
     };
-    let verifier_seed = parse_bytes32(&required_flag_value(args, "--verifier-seed")?)?;
-    let input_bit = parse_flag_value(args, "--input-bit")
-        .as_deref()
-        .map(|v| parse_u16(v, "input-bit"))
-        .transpose()?;
-    let round = parse_flag_value(args, "--round")
-        .as_deref()
-        .map(|v| parse_u8(v, "round"))
-        .transpose()?;
-    let expected_root_ot = parse_flag_value(args, "--expected-root-ot")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?;
-    let circuit_id = parse_flag_value(args, "--circuit-id")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?
-        .unwrap_or_else(|| default_circuit_id(bit_width, winner_formula));
-
-    let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let rpc_url = rpc_url();
+
+    let parts = EvalPackageParts {
+        bit_width,
+        decoding: &decoding,
+        gc_leaves: &leaves,
+        alice_labels: &alice_labels,
+        not_hints: &not_hints,
+        y_offers: &y_offers,
+    };
+    let report = check_eval_package(&parts, &layout, committed_root_gc);
+
+    let status = if report.is_consistent() {
+        "verified"
+    } else {
+        "mismatch"
+    };
+    emitln!("status={status}");
+    emitln!("eval_dir={}", eval_dir.display());
+    emitln!("source={source}");
+    emitln!("instance_id={instance_id}");
+    emitln!("bit_width={bit_width}");
+    emitln!("root_gc={}", hex32(committed_root_gc));
+    for check in &report.checked {
+        emitln!("checked={check}");
+    }
+    for failure in &report.failures {
+        emitln!("mismatch={failure}");
+    }
+    if !report.is_consistent() {
+        return Err(format!(
+            "eval package in {} failed {} check(s)",
+            eval_dir.display(),
+            report.failures.len()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct OtChooseArgs {
+    /// Bob's private input
+    #[arg(long, value_name = "uint256", value_parser = u256_arg)]
+    y: [u8; 32],
+    /// Eval directory written by Alice's prepare-eval
+    #[arg(long, value_name = "PATH")]
+    eval_dir: PathBuf,
+    /// Where the OT choices go [default: bob-ot-choices.txt in --eval-dir]
+    #[arg(long, value_name = "PATH")]
+    choices_file: Option<PathBuf>,
+}
+
+fn cmd_ot_choose(args: OtChooseArgs) -> AppResult<()> {
+    let y_value = args.y;
+    let eval_dir = args.eval_dir;
+    let secret = parse_bytes32(&required_env("BOB_OT_SECRET")?)?;
+
+    let meta = EvalMeta::read(&eval_dir)?;
+    let bit_width = meta.bit_width as usize;
+    if u256_bit_length(y_value) > bit_width {
+        return Err(format!(
+            "y={} does not fit bit-width {} (needs {} bits)",
+            u256_to_decimal(y_value),
+            bit_width,
+            u256_bit_length(y_value)
+        )
+        .into());
+    }
+    let setup = read_ot_setup(&eval_dir.join("ot-setup.txt"))?;
+    let receiver = OtReceiver::new(secret, &setup, &u256_to_bits_le(y_value, bit_width))?;
+
+    // An eval dir inside a bundle is read-only, so the choices may go elsewhere.
+    let choices_file = args
+        .choices_file
+        .unwrap_or_else(|| eval_dir.join("bob-ot-choices.txt"));
+    let mut choices_raw = String::new();
+    for (idx, point) in receiver.choice_messages().iter().enumerate() {
+        let wire_id = bit_width + idx;
+        choices_raw.push_str(&format!("{wire_id},{}\n", hex_prefixed(point)));
+    }
+    fs::write(&choices_file, choices_raw)?;
+
+    emitln!("status=ot_choices_written");
+    emitln!("instance_id={}", meta.decoding.instance_id);
+    emitln!("bit_width={bit_width}");
+    emitln!("ot_choices_file={}", choices_file.display());
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct PrepareOtDisputeArgs {
+    /// Opened instance whose OT root is disputed
+    #[arg(long, value_name = "ID")]
+    instance_id: u64,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Bob's revealed verifier seed
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    verifier_seed: [u8; 32],
+    /// Alice's revealed seed of the instance
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    garbler_seed: Option<[u8; 32]>,
+    /// Same as --garbler-seed
+    #[arg(
+        long,
+        value_name = "0x..32",
+        value_parser = bytes32_arg,
+        required_unless_present = "garbler_seed"
+    )]
+    seed: Option<[u8; 32]>,
+    /// Input bit of the OT leaf [default: the first leaf]
+    #[arg(long, value_name = "N", requires = "round")]
+    input_bit: Option<u16>,
+    /// Round of the OT leaf
+    #[arg(long, value_name = "0|1|2", requires = "input_bit")]
+    round: Option<u8>,
+    /// On-chain rootOT to compare the recomputed root with
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    expected_root_ot: Option<[u8; 32]>,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+fn cmd_prepare_ot_dispute(args: PrepareOtDisputeArgs) -> AppResult<()> {
+    let bit_width = args.circuit.bit_width;
+    args.circuit.circuit.check_bit_width(bit_width)?;
+    let instance_id = args.instance_id;
+    args.params.check_m(instance_id as usize)?;
+    let garbler_seed = args
+        .garbler_seed
+        .or(args.seed)
+        .ok_or("Missing required flag: --seed")?;
+    let verifier_seed = args.verifier_seed;
+    let (input_bit, round) = (args.input_bit, args.round);
+    let expected_root_ot = args.expected_root_ot;
+    let circuit_id = args.circuit.circuit_id()?;
+
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let rpc_url = rpc_url();
 
     let config = PrepareOtDisputeConfig {
         bit_width,
@@ -1033,31 +1209,31 @@ fn cmd_prepare_ot_dispute(args: &[String]) -> AppResult<()> {
     };
     let prepared = prepare_ot_dispute_packet(&config)?;
 
-    println!("status=prepared");
-    println!("source=recomputed");
-    println!("bit_width={bit_width}");
-    println!("circuit_id={}", hex32(circuit_id));
-    println!("instance_id={instance_id}");
-    println!("garbler_seed={}", hex32(garbler_seed));
-    println!("verifier_seed={}", hex32(verifier_seed));
-    println!("selected_input_bit={}", prepared.input_bit);
-    println!("selected_round={}", prepared.round);
-    println!("selected_author={}", prepared.author);
-    println!("root_ot={}", hex32(prepared.root_ot));
+    emitln!("status=prepared");
+    emitln!("source=recomputed");
+    emitln!("bit_width={bit_width}");
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("instance_id={instance_id}");
+    emitln!("garbler_seed={}", hex32(garbler_seed));
+    emitln!("verifier_seed={}", hex32(verifier_seed));
+    emitln!("selected_input_bit={}", prepared.input_bit);
+    emitln!("selected_round={}", prepared.round);
+    emitln!("selected_author={}", prepared.author);
+    emitln!("root_ot={}", hex32(prepared.root_ot));
     if let Some(expected_root) = prepared.expected_root_ot {
-        println!("expected_root_ot={}", hex32(expected_root));
+        emitln!("expected_root_ot={}", hex32(expected_root));
     }
     if let Some(root_match) = prepared.root_match {
-        println!("root_match={root_match}");
+        emitln!("root_match={root_match}");
     }
-    println!(
+    emitln!(
         "expected_payload_hash={}",
         hex32(prepared.expected_payload_hash)
     );
 
-    println!();
-    println!("cast send template:");
-    println!(
+    emitln!();
+    emitln!("cast send template:");
+    emitln!(
         "cast send {} \"disputeObliviousTransferRoot(uint256)\" {} --private-key <BOB_PRIVATE_KEY> --rpc-url {}",
         contract_address,
         instance_id,
@@ -1067,34 +1243,57 @@ fn cmd_prepare_ot_dispute(args: &[String]) -> AppResult<()> {
     Ok(())
 }
 
-fn cmd_prepare_dispute(args: &[String]) -> AppResult<()> {
-    let bit_width = parse_flag_value(args, "--bit-width")
-        .as_deref()
-        .map(|v| parse_u64(v, "bit-width"))
-        .transpose()?
-        .unwrap_or(8) as usize;
-    let winner_formula = parse_winner_formula(args)?;
-    let instance_id = parse_u64(&required_flag_value(args, "--instance-id")?, "instance-id")?;
-    let seed = parse_bytes32(&required_flag_value(args, "--seed")?)?;
-    let leaves_file = required_flag_value(args, "--claimed-leaves-file")?;
-    let gate_index = parse_flag_value(args, "--gate-index")
-        .as_deref()
-        .map(|v| parse_u64(v, "gate-index"))
-        .transpose()?
-        .map(|v| v as usize);
-    let allow_false_challenge = args.iter().any(|arg| arg == "--allow-false-challenge");
-    let expected_root_gc = parse_flag_value(args, "--expected-root-gc")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?;
-    let circuit_id = parse_flag_value(args, "--circuit-id")
-        .as_deref()
-        .map(parse_bytes32)
-        .transpose()?
-        .unwrap_or_else(|| default_circuit_id(bit_width, winner_formula));
+#[derive(Debug, Args)]
+struct PrepareDisputeArgs {
+    /// Opened instance to audit
+    #[arg(long, value_name = "ID")]
+    instance_id: u64,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Alice's revealed seed of the instance
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    seed: [u8; 32],
+    /// Leaves Alice published for the instance
+    #[arg(long, value_name = "PATH")]
+    claimed_leaves_file: PathBuf,
+    /// Gate to dispute [default: the first mismatch]
+    #[arg(long, value_name = "K")]
+    gate_index: Option<usize>,
+    /// On-chain rootGC the claimed leaves must hash to
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    expected_root_gc: Option<[u8; 32]>,
+    /// Prepare a dispute even for a gate whose leaf matches
+    #[arg(long)]
+    allow_false_challenge: bool,
+    /// Write the dispute packet, signed CBOR or JSON by extension
+    #[arg(long, value_name = "file.cbor|file.json")]
+    out: Option<PathBuf>,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
 
-    let claimed_leaves = read_claimed_leaves_file(Path::new(&leaves_file))?;
+fn cmd_prepare_dispute(args: PrepareDisputeArgs) -> AppResult<()> {
+    let (circuit, bit_width) = (args.circuit.circuit, args.circuit.bit_width);
+    circuit.check_bit_width(bit_width)?;
+    let instance_id = args.instance_id;
+    args.params.check_m(instance_id as usize)?;
+    let seed = args.seed;
+    let gate_index = args.gate_index;
+    let allow_false_challenge = args.allow_false_challenge;
+    let expected_root_gc = args.expected_root_gc;
+    let circuit_id = args.circuit.circuit_id()?;
+
+    let out_file = args.out;
+
+    let claimed_leaves = open_claimed_leaves_file(&args.claimed_leaves_file)?;
     let config = PrepareDisputeConfig {
+        circuit,
         bit_width,
         circuit_id,
         instance_id,
@@ -1106,108 +1305,458 @@ fn cmd_prepare_dispute(args: &[String]) -> AppResult<()> {
     };
     let prepared = prepare_dispute_packet(&config)?;
 
-    let selected_is_mismatch = prepared.mismatch_indices.contains(&prepared.gate_index);
-    println!("status=prepared");
-    println!("bit_width={}", bit_width);
-    println!("circuit_id={}", hex32(circuit_id));
-    println!("instance_id={}", instance_id);
-    println!("selected_gate_index={}", prepared.gate_index);
-    println!("selected_gate_mismatch={selected_is_mismatch}");
-    println!("mismatch_count={}", prepared.mismatch_indices.len());
-    println!("mismatch_indices={:?}", prepared.mismatch_indices);
-    println!("root_gc={}", hex32(prepared.root_gc));
-    println!("layout_root={}", hex32(prepared.layout_root));
-    println!("seed={}", hex32(seed));
-    println!("gate_type={}", prepared.gate.gate_type as u8);
-    println!("wire_a={}", prepared.gate.wire_a);
-    println!("wire_b={}", prepared.gate.wire_b);
-    println!("wire_c={}", prepared.gate.wire_c);
-    println!("claimed_leaf={}", hex_prefixed(&prepared.claimed_leaf));
-    println!("expected_leaf={}", hex_prefixed(&prepared.expected_leaf));
-    println!("ih_proof={}", bytes32_vec_literal(&prepared.ih_proof));
-    println!(
+    let selected_audit = prepared.audit.gate(prepared.gate_index);
+    emitln!("status=prepared");
+    emitln!("bit_width={}", bit_width);
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("instance_id={}", instance_id);
+    emitln!("selected_gate_index={}", prepared.gate_index);
+    emitln!("selected_gate_mismatch={}", selected_audit.is_some());
+    if let Some(gate_audit) = selected_audit {
+        emitln!("selected_gate_corruption={}", gate_audit.corruption());
+        emitln!("selected_gate_differing_rows={:?}", gate_audit.differing_rows);
+    }
+    emitln!("mismatch_count={}", prepared.audit.mismatches.len());
+    emitln!("mismatch_indices={:?}", prepared.audit.mismatch_indices());
+    emitln!("root_gc={}", hex32(prepared.root_gc));
+    emitln!("layout_root={}", hex32(prepared.layout_root));
+    emitln!("seed={}", hex32(seed));
+    emitln!("gate_type={}", prepared.gate.gate_type as u8);
+    emitln!("wire_a={}", prepared.gate.wire_a);
+    emitln!("wire_b={}", prepared.gate.wire_b);
+    emitln!("wire_c={}", prepared.gate.wire_c);
+    emitln!("claimed_leaf={}", hex_prefixed(&prepared.claimed_leaf));
+    emitln!("expected_leaf={}", hex_prefixed(&prepared.expected_leaf));
+    emitln!("ih_proof={}", bytes32_vec_literal(&prepared.ih_proof));
+    emitln!(
         "layout_proof={}",
         bytes32_vec_literal(&prepared.layout_proof)
     );
+    if let Some(out_file) = out_file {
+        let signer = write_dispute_packet(&out_file, prepared.packet(&config))?;
+        emitln!("packet_file={}", out_file.display());
+        match signer {
+            Some(signer) => emitln!("packet_signer={}", hex_prefixed(&signer)),
+            None => emitln!("packet_signer=none"),
+        }
+    }
 
-    let contract_for_template =
-        env::var("CONTRACT_ADDRESS").unwrap_or_else(|_| "<CONTRACT_ADDRESS>".to_string());
-    let rpc_for_template = rpc_url();
-    let gate_tuple = format!(
-        "({},{},{},{})",
-        prepared.gate.gate_type as u8,
-        prepared.gate.wire_a,
-        prepared.gate.wire_b,
-        prepared.gate.wire_c
-    );
-    println!();
-    println!("cast send template:");
-    println!(
-        "cast send {} \"disputeGarbledTable(uint256,bytes32,uint256,(uint8,uint16,uint16,uint16),bytes,bytes32[],bytes32[])\" {} {} {} \"{}\" {} \"{}\" \"{}\" --private-key <BOB_PRIVATE_KEY> --rpc-url {}",
-        contract_for_template,
+    let call = dispute_garbled_table_call(
         instance_id,
-        hex32(seed),
-        prepared.gate_index,
-        gate_tuple,
-        hex_prefixed(&prepared.claimed_leaf),
-        bytes32_vec_literal(&prepared.ih_proof),
-        bytes32_vec_literal(&prepared.layout_proof),
-        rpc_for_template
+        seed,
+        prepared.gate_index as u64,
+        prepared.gate,
+        &prepared.claimed_leaf,
+        &prepared.ih_proof,
+        &prepared.layout_proof,
     );
+    emitln!("dispute_calldata={}", call.calldata_hex());
+
+    let contract_for_template =
+        config_var("CONTRACT_ADDRESS").unwrap_or_else(|| "<CONTRACT_ADDRESS>".to_string());
+    emitln!();
+    emitln!("cast send template:");
+    emitln!(
+        "cast send {contract_for_template} {} --private-key <BOB_PRIVATE_KEY> --rpc-url {}",
+        call.calldata_hex(),
+        rpc_url()
+    );
+
+    Ok(())
+}
+
+/// Writes `packet` to `out_file`, as canonical JSON for a `.json` name and otherwise as CBOR,
+/// and returns the signer of a CBOR packet.
+fn write_dispute_packet(
+    out_file: &Path,
+    packet: DisputePacketMessage,
+) -> AppResult<Option<[u8; 20]>> {
+    // CBOR packets are signed like other messages Bob sends, so they can serve as evidence.
+    let (bytes, signer) = if is_json_packet_path(out_file) {
+        (to_canonical_json(&packet)?.into_bytes(), None)
+    } else {
+        encode_signed_message(Message::DisputePacket(packet), &["BOB_PRIVATE_KEY"])?
+    };
+    fs::write(out_file, bytes)?;
+    Ok(signer)
+}
+
+/// Parses `--opened`: comma-separated `<instance id>=<seed>` pairs.
+fn opened_seeds_arg(raw: &str) -> Result<Vec<(u64, [u8; 32])>, String> {
+    raw.split(',')
+        .map(|pair| {
+            let (id, seed) = pair
+                .trim()
+                .split_once('=')
+                .ok_or_else(|| format!("expected <instance id>=<seed>, got {pair:?}"))?;
+            let id = id
+                .parse::<u64>()
+                .map_err(|_| format!("invalid instance id {id:?}"))?;
+            Ok((id, bytes32_arg(seed)?))
+        })
+        .collect()
+}
+
+#[derive(Debug, Args)]
+struct ScanDisputesArgs {
+    /// Alice's revealed seed of every opened instance
+    #[arg(
+        long,
+        value_name = "ID=0x..32,...",
+        value_parser = opened_seeds_arg,
+        required_unless_present = "onchain"
+    )]
+    opened: Option<ValueList<(u64, [u8; 32])>>,
+    /// Read the opened instances, their seeds and rootGCs from CONTRACT_ADDRESS
+    #[arg(long, conflicts_with = "opened")]
+    onchain: bool,
+    /// Alice's export, holding the claimed leaves of every opened instance
+    #[arg(long, value_name = "PATH")]
+    artifacts_dir: PathBuf,
+    /// Write a dispute packet for every instance with mismatches to this directory
+    #[arg(long, value_name = "PATH")]
+    out_dir: Option<PathBuf>,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+/// Audits every opened instance in parallel against Alice's claimed leaves, prints one line
+/// per instance and writes a packet for each instance with mismatches to `--out-dir`.
+fn cmd_scan_disputes(args: ScanDisputesArgs) -> AppResult<()> {
+    let (circuit, bit_width) = (args.circuit.circuit, args.circuit.bit_width);
+    circuit.check_bit_width(bit_width)?;
+    let circuit_id = args.circuit.circuit_id()?;
+    let opened = match args.opened {
+        Some(opened) => opened
+            .into_iter()
+            .map(|(instance_id, seed)| (instance_id, seed, None))
+            .collect::<Vec<_>>(),
+        None => {
+            let snapshot = read_watch_snapshot(&required_env("CONTRACT_ADDRESS")?, &rpc_url())?;
+            if snapshot.opened.is_empty() {
+                return Err("No opened instances: the contract is not in the dispute stage".into());
+            }
+            snapshot
+                .opened
+                .into_iter()
+                .map(|opened| (opened.instance_id, opened.seed, Some(opened.root_gc)))
+                .collect()
+        }
+    };
+
+    let mut configs = Vec::with_capacity(opened.len());
+    for (instance_id, seed, expected_root_gc) in opened {
+        args.params.check_m(instance_id as usize)?;
+        if configs
+            .iter()
+            .any(|config: &PrepareDisputeConfig<LeafFile>| config.instance_id == instance_id)
+        {
+            return Err(format!("instance {instance_id} is listed twice").into());
+        }
+        let leaves_path = args
+            .artifacts_dir
+            .join(format!("instance-{instance_id}-leaves.txt"));
+        configs.push(PrepareDisputeConfig {
+            circuit,
+            bit_width,
+            circuit_id,
+            instance_id,
+            seed,
+            claimed_leaves: open_claimed_leaves_file(&leaves_path)?,
+            gate_index: None,
+            allow_false_challenge: false,
+            expected_root_gc,
+        });
+    }
+    if let Some(out_dir) = &args.out_dir {
+        fs::create_dir_all(out_dir)?;
+    }
 
+    let scans = scan_disputes(&configs);
+    let count = |matches: fn(&ScanVerdict) -> bool| {
+        scans.iter().filter(|scan| matches(&scan.verdict)).count()
+    };
+    let disputes = count(|verdict| matches!(verdict, ScanVerdict::Dispute(_)));
+    let failed = count(|verdict| matches!(verdict, ScanVerdict::Failed(_)));
+    let status = match (disputes, failed) {
+        (0, 0) => "clean",
+        (0, _) => "incomplete",
+        _ => "disputes_found",
+    };
+    emitln!("status={status}");
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("scanned={}", scans.len());
+    emitln!("clean={}", scans.len() - disputes - failed);
+    emitln!("disputes={disputes}");
+    emitln!("failed={failed}");
+    for (config, scan) in configs.iter().zip(&scans) {
+        let instance_id = scan.instance_id;
+        match &scan.verdict {
+            ScanVerdict::Clean => emitln!("instance={instance_id} verdict=clean"),
+            ScanVerdict::Failed(reason) => {
+                emitln!("instance={instance_id} verdict=failed");
+                tracing::warn!(instance_id, "{reason}");
+            }
+            ScanVerdict::Dispute(prepared) => {
+                let mut line = format!(
+                    "instance={instance_id} verdict=dispute mismatch_count={} gate_index={}",
+                    prepared.audit.mismatches.len(),
+                    prepared.gate_index
+                );
+                if let Some(out_dir) = &args.out_dir {
+                    let packet_file = out_dir.join(format!("instance-{instance_id}-dispute.cbor"));
+                    write_dispute_packet(&packet_file, prepared.packet(config))?;
+                    line.push_str(&format!(" packet_file={}", packet_file.display()));
+                }
+                emitln!("{line}");
+            }
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{failed} opened instances could not be audited").into());
+    }
     Ok(())
 }
 
-fn cmd_dispute(args: &[String]) -> AppResult<()> {
+/// Sends a `disputeGarbledTable` call and prints its `dispute_*` summary, or under
+/// `--dry-run` the transaction.
+fn send_dispute_garbled_table(call: &AbiCall) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
-
-    let instance_id = parse_u64(&required_flag_value(args, "--instance-id")?, "instance-id")?;
-    let seed = parse_bytes32(&required_flag_value(args, "--seed")?)?;
-    let gate_index = parse_u64(&required_flag_value(args, "--gate-index")?, "gate-index")?;
-    let gate_type = parse_u8(&required_flag_value(args, "--gate-type")?, "gate-type")?;
-    if gate_type > 2 {
-        return Err(format!("gate-type must be 0, 1, or 2; got {gate_type}").into());
-    }
-    let wire_a = parse_u16(&required_flag_value(args, "--wire-a")?, "wire-a")?;
-    let wire_b = parse_u16(&required_flag_value(args, "--wire-b")?, "wire-b")?;
-    let wire_c = parse_u16(&required_flag_value(args, "--wire-c")?, "wire-c")?;
-    let leaf_bytes = parse_leaf71(&required_flag_value(args, "--leaf-bytes")?)?;
-    let ih_proof = parse_bytes32_list_csv(&required_flag_value(args, "--ih-proof")?)?;
-    let layout_proof = parse_bytes32_list_csv(&required_flag_value(args, "--layout-proof")?)?;
-
-    let gate_tuple = format!("({gate_type},{wire_a},{wire_b},{wire_c})");
-    let ih_literal = bytes32_vec_literal(&ih_proof);
-    let layout_literal = bytes32_vec_literal(&layout_proof);
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
 
-    let tx_result = run_cast(&[
-        "send".to_string(),
-        contract_address,
-        "disputeGarbledTable(uint256,bytes32,uint256,(uint8,uint16,uint16,uint16),bytes,bytes32[],bytes32[])".to_string(),
-        instance_id.to_string(),
-        hex32(seed),
-        gate_index.to_string(),
-        gate_tuple,
-        hex_prefixed(&leaf_bytes),
-        ih_literal,
-        layout_literal,
-        "--private-key".to_string(),
-        bob_private_key,
-        "--rpc-url".to_string(),
-        rpc_url,
-    ])?;
+    send_call_or_print(
+        "dispute",
+        &contract_address,
+        call,
+        &[
+            "--private-key".to_string(),
+            bob_private_key,
+            "--rpc-url".to_string(),
+            rpc_url,
+        ],
+    )?;
+    Ok(())
+}
+
+fn leaf_arg(raw: &str) -> Result<[u8; 71], String> {
+    parse_leaf71(raw).map_err(|e| e.to_string())
+}
+
+/// The disputed gate given flag by flag, as `prepare-dispute` prints it.
+#[derive(Debug, Args)]
+struct DisputeGateArgs {
+    /// Opened instance the gate belongs to
+    #[arg(long, value_name = "ID")]
+    instance_id: u64,
+    /// Alice's revealed seed of the instance
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    seed: [u8; 32],
+    /// Index of the disputed gate
+    #[arg(long, value_name = "K")]
+    gate_index: u64,
+    /// 0 (XOR), 1 (AND) or 2 (NOT)
+    #[arg(long, value_name = "0|1|2", value_parser = clap::value_parser!(u8).range(0..=2))]
+    gate_type: u8,
+    #[arg(long, value_name = "u16")]
+    wire_a: u16,
+    #[arg(long, value_name = "u16")]
+    wire_b: u16,
+    #[arg(long, value_name = "u16")]
+    wire_c: u16,
+    /// Alice's claimed leaf of the gate
+    #[arg(long, value_name = "0x..71", value_parser = leaf_arg)]
+    leaf_bytes: [u8; 71],
+    /// Incremental-hash proof of the leaf
+    #[arg(long, value_name = "0x..,0x..", value_parser = bytes32_list_arg)]
+    ih_proof: ValueList<[u8; 32]>,
+    /// Merkle proof of the gate in the circuit layout
+    #[arg(long, value_name = "0x..,0x..", value_parser = bytes32_list_arg)]
+    layout_proof: ValueList<[u8; 32]>,
+}
+
+#[derive(Debug, Args)]
+struct DisputeArgs {
+    /// Packet file written by prepare-dispute --out
+    #[arg(
+        long,
+        value_name = "PATH",
+        required_unless_present = "instance_id",
+        conflicts_with = "DisputeGateArgs"
+    )]
+    packet: Option<PathBuf>,
+    #[command(flatten)]
+    gate: Option<DisputeGateArgs>,
+}
+
+fn cmd_dispute(args: DisputeArgs) -> AppResult<()> {
+    if let Some(packet_file) = args.packet {
+        return dispute_from_packet(&packet_file);
+    }
+    let args = args.gate.ok_or("Provide --packet <file> or the disputed gate")?;
+    let gate = GateDesc {
+        gate_type: GateType::from_u8(args.gate_type).ok_or("gate-type checked by clap")?,
+        wire_a: args.wire_a,
+        wire_b: args.wire_b,
+        wire_c: args.wire_c,
+    };
+    let call = dispute_garbled_table_call(
+        args.instance_id,
+        args.seed,
+        args.gate_index,
+        gate,
+        &args.leaf_bytes,
+        &args.ih_proof,
+        &args.layout_proof,
+    );
+    send_dispute_garbled_table(&call)
+}
+
+/// `dispute --packet`: submits a packet file from `prepare-dispute --out` after checking
+/// its proofs against the roots it carries.
+fn dispute_from_packet(packet_file: &Path) -> AppResult<()> {
+    let packet = read_dispute_packet_file(packet_file)?;
+    let call = dispute_garbled_table_call(
+        packet.instance_id,
+        packet.seed,
+        packet.gate_index,
+        packet.gate,
+        &packet.leaf,
+        &packet.ih_proof,
+        &packet.layout_proof,
+    );
+
+    emitln!("packet_file={}", packet_file.display());
+    emitln!("instance_id={}", packet.instance_id);
+    emitln!("gate_index={}", packet.gate_index);
+    send_dispute_garbled_table(&call)
+}
 
-    print_tx_summary("dispute", &tx_result);
+#[derive(Debug, Args)]
+struct AutoDisputeArgs {
+    /// Opened instance to audit
+    #[arg(long, value_name = "ID")]
+    instance_id: u64,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Alice's revealed seed of the instance
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    seed: [u8; 32],
+    /// Leaves Alice published for the instance
+    #[arg(
+        long,
+        value_name = "PATH",
+        required_unless_present = "artifacts_dir",
+        conflicts_with = "artifacts_dir"
+    )]
+    claimed_leaves_file: Option<PathBuf>,
+    /// Alice's export, read as instance-<id>-leaves.txt
+    #[arg(long, value_name = "PATH")]
+    artifacts_dir: Option<PathBuf>,
+    /// rootGC the claimed leaves must hash to [default: the on-chain one]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    expected_root_gc: Option<[u8; 32]>,
+    /// Submit without asking for confirmation
+    #[arg(long)]
+    yes: bool,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+/// `prepare-dispute` and `dispute` in one go: audits the instance and, once confirmed,
+/// disputes its first mismatching gate.
+fn cmd_auto_dispute(args: AutoDisputeArgs) -> AppResult<()> {
+    let (circuit, bit_width) = (args.circuit.circuit, args.circuit.bit_width);
+    circuit.check_bit_width(bit_width)?;
+    let instance_id = args.instance_id;
+    args.params.check_m(instance_id as usize)?;
+    let circuit_id = args.circuit.circuit_id()?;
+    let leaves_path = match (args.claimed_leaves_file, args.artifacts_dir) {
+        (Some(path), _) => path,
+        (None, Some(dir)) => dir.join(format!("instance-{instance_id}-leaves.txt")),
+        (None, None) => return Err("Provide --claimed-leaves-file or --artifacts-dir".into()),
+    };
+    // A packet whose leaves miss the committed rootGC is rejected on-chain; check it first.
+    let expected_root_gc = match args.expected_root_gc {
+        Some(root_gc) => root_gc,
+        None => onchain_root_gc(&required_env("CONTRACT_ADDRESS")?, &rpc_url(), instance_id)?,
+    };
+
+    let config = PrepareDisputeConfig {
+        circuit,
+        bit_width,
+        circuit_id,
+        instance_id,
+        seed: args.seed,
+        claimed_leaves: open_claimed_leaves_file(&leaves_path)?,
+        gate_index: None,
+        allow_false_challenge: false,
+        expected_root_gc: Some(expected_root_gc),
+    };
+    let prepared = prepare_dispute_packet(&config)?;
+
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("instance_id={instance_id}");
+    emitln!("claimed_leaves_file={}", leaves_path.display());
+    emitln!("mismatch_count={}", prepared.audit.mismatches.len());
+    emitln!("selected_gate_index={}", prepared.gate_index);
+    if let Some(gate_audit) = prepared.audit.gate(prepared.gate_index) {
+        emitln!("selected_gate_corruption={}", gate_audit.corruption());
+    }
+    emitln!("root_gc={}", hex32(prepared.root_gc));
+
+    let call = dispute_garbled_table_call(
+        instance_id,
+        args.seed,
+        prepared.gate_index as u64,
+        prepared.gate,
+        &prepared.claimed_leaf,
+        &prepared.ih_proof,
+        &prepared.layout_proof,
+    );
+    let dry_run = is_dry_run();
+    if !dry_run && !args.yes {
+        let question = format!(
+            "Submit disputeGarbledTable for instance {instance_id}, gate {}?",
+            prepared.gate_index
+        );
+        if !confirm(&question, "--yes")? {
+            emitln!("dispute_submitted=false");
+            return Ok(());
+        }
+    }
+    send_dispute_garbled_table(&call)?;
+    emitln!("dispute_submitted={}", !dry_run);
     Ok(())
 }
 
-fn cmd_dispute_ot(args: &[String]) -> AppResult<()> {
+#[derive(Debug, Args)]
+struct DisputeOtArgs {
+    /// Opened instance whose OT root is disputed
+    #[arg(long, value_name = "ID")]
+    instance_id: u64,
+}
+
+fn cmd_dispute_ot(args: DisputeOtArgs) -> AppResult<()> {
     let rpc_url = rpc_url();
     let contract_address = required_env("CONTRACT_ADDRESS")?;
-    let bob_private_key = required_env("BOB_PRIVATE_KEY")?;
+    let bob_private_key = private_key(&["BOB_PRIVATE_KEY"])?;
 
-    let instance_id = parse_u64(&required_flag_value(args, "--instance-id")?, "instance-id")?;
+    let instance_id = args.instance_id;
 
     let tx_result = run_cast(&[
         "send".to_string(),
@@ -1224,65 +1773,919 @@ fn cmd_dispute_ot(args: &[String]) -> AppResult<()> {
     Ok(())
 }
 
-fn print_help() {
-    println!("off-chain-bob commands:");
-    println!("  deposit");
-    println!("  commit-verifier-seed [--seed <0x..32> --salt <0x..32> | --commitment <0x..32>]");
-    println!("  reveal-verifier-seed --seed <0x..32> --salt <0x..32>");
-    println!("  choose --m <index>");
-    println!("  buyer-ready");
-    println!("  close-dispute");
-    println!("  settle-auction --bids <u64,u64,...> --chosen-namehash <0x..32> [--dry-run]");
-    println!("  finalize-assignment");
-    println!(
-        "  evaluate-m --y <u64> [--payload-file <path>] [--eval-dir <path>] [--alice-labels-file <path>]"
-    );
-    println!(
-        "  prepare-dispute --instance-id <id> --seed <0x..32> --claimed-leaves-file <path> [--bit-width <bits>] [--winner-formula <0|1>] [--gate-index <k>] [--circuit-id <0x..32>] [--expected-root-gc <0x..32>] [--allow-false-challenge]"
-    );
-    println!(
-        "  prepare-ot-dispute --instance-id <id> --verifier-seed <0x..32> [--garbler-seed <0x..32> | --seed <0x..32>] [--bit-width <bits>] [--winner-formula <0|1>] [--input-bit <n> --round <0|1|2>] [--circuit-id <0x..32>] [--expected-root-ot <0x..32>]"
-    );
-    println!(
-        "  dispute --instance-id <id> --seed <0x..32> --gate-index <k> --gate-type <0|1|2> --wire-a <u16> --wire-b <u16> --wire-c <u16> --leaf-bytes <0x..71> --ih-proof <0x..,0x..> --layout-proof <0x..,0x..>"
-    );
-    println!(
-        "  dispute-ot --instance-id <id>"
-    );
-    println!();
-    println!("Default command with no args: deposit");
+/// `cast call` returning the first token of each output line (drops cast's `[1e9]` hints).
+fn read_watch_snapshot(contract_address: &str, rpc_url: &str) -> AppResult<ContractSnapshot> {
+    let call = |args: &[&str]| cast_call_values(contract_address, rpc_url, args);
+    let first = |values: Vec<String>, name: &str| -> AppResult<String> {
+        values
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("empty cast output for {name}").into())
+    };
+
+    let stage = parse_u8(
+        &first(call(&["currentStage()(uint8)"])?, "currentStage")?,
+        "stage",
+    )?;
+    let now = latest_block_timestamp(rpc_url)?;
+    let dispute_deadline = read_deadlines(contract_address, rpc_url)?.dispute;
+
+    let mut opened = Vec::new();
+    if stage == DISPUTE_STAGE {
+        let open_count = parse_u64(
+            &first(call(&["getSOpenLength()(uint256)"])?, "getSOpenLength")?,
+            "sOpen length",
+        )?;
+        for position in 0..open_count {
+            let position = position.to_string();
+            let instance_id = parse_u64(
+                &first(call(&["sOpen(uint256)(uint256)", &position])?, "sOpen")?,
+                "sOpen",
+            )?;
+            let id = instance_id.to_string();
+            let seed = parse_bytes32(&first(
+                call(&["revealedSeeds(uint256)(bytes32)", &id])?,
+                "revealedSeeds",
+            )?)?;
+            let root_gc = onchain_root_gc(contract_address, rpc_url, instance_id)?;
+            opened.push(OpenedInstance {
+                instance_id,
+                seed,
+                root_gc,
+            });
+        }
+    }
+
+    Ok(ContractSnapshot {
+        stage,
+        now,
+        dispute_deadline,
+        opened,
+    })
 }
 
-fn main() -> AppResult<()> {
-    let args: Vec<String> = env::args().skip(1).collect();
-    let command = args.first().map(String::as_str).unwrap_or("deposit");
-    let tail = if args.is_empty() { &[][..] } else { &args[1..] };
-
-    match command {
-        "deposit" => cmd_deposit(),
-        "commit-verifier-seed" => cmd_commit_verifier_seed(tail),
-        "reveal-verifier-seed" => cmd_reveal_verifier_seed(tail),
-        "choose" => cmd_choose(tail),
-        "buyer-ready" => cmd_buyer_ready(),
-        "close-dispute" => cmd_close_dispute(),
-        "settle-auction" => cmd_settle_auction(tail),
-        "finalize-assignment" => cmd_finalize_assignment(),
-        "evaluate-m" => cmd_evaluate_m(tail),
-        "prepare-dispute" => cmd_prepare_dispute(tail),
-        "prepare-ot-dispute" => cmd_prepare_ot_dispute(tail),
-        "dispute" => cmd_dispute(tail),
-        "dispute-ot" => cmd_dispute_ot(tail),
-        "-h" | "--help" | "help" => {
-            print_help();
-            Ok(())
+/// `rootGC` Alice committed to for `instance_id`, which a dispute's IH proof must reach.
+fn onchain_root_gc(contract_address: &str, rpc_url: &str, instance_id: u64) -> AppResult<[u8; 32]> {
+    Ok(read_instance_commitment(contract_address, rpc_url, instance_id)?.root_gc)
+}
+
+#[derive(Debug, Args)]
+struct WatchArgs {
+    /// Alice's export, for the claimed leaves of opened instances
+    #[arg(long, value_name = "PATH")]
+    artifacts_dir: Option<PathBuf>,
+    /// Seconds between polls
+    #[arg(long, value_name = "SECS", default_value_t = 12)]
+    interval: u64,
+    /// Warn when a dispute is pending this close to the window's end
+    #[arg(long, value_name = "SECS", default_value_t = 600)]
+    margin: u64,
+    /// Poll once and exit
+    #[arg(long)]
+    once: bool,
+    /// Report a provable dispute without submitting it
+    #[arg(long)]
+    no_submit: bool,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+fn cmd_watch(args: WatchArgs) -> AppResult<()> {
+    let rpc_url = rpc_url();
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let (circuit, bit_width) = (args.circuit.circuit, args.circuit.bit_width);
+    circuit.check_bit_width(bit_width)?;
+    let circuit_id = args.circuit.circuit_id()?;
+    let interval = args.interval;
+    let margin_secs = args.margin;
+    let artifacts_dir = args.artifacts_dir;
+    let (once, no_submit) = (args.once, args.no_submit);
+    // Claimed leaves as written by Alice's `export-artifacts`.
+    let claimed_leaves = |instance_id: u64| {
+        let path = artifacts_dir
+            .as_ref()?
+            .join(format!("instance-{instance_id}-leaves.txt"));
+        read_claimed_leaves_file(&path).ok()
+    };
+
+    let mut watchdog = Watchdog::new(WatchdogConfig {
+        circuit,
+        bit_width,
+        circuit_id,
+        margin_secs,
+    });
+    emitln!("status=watching");
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("interval_secs={interval}");
+    loop {
+        let snapshot = read_watch_snapshot(&contract_address, &rpc_url)?;
+        tracing::debug!(
+            stage = snapshot.stage,
+            now = snapshot.now,
+            opened = snapshot.opened.len(),
+            "polled contract"
+        );
+        for event in watchdog.poll(&snapshot, claimed_leaves)? {
+            match event {
+                WatchEvent::Waiting(phase) => emitln!("event=waiting phase={phase}"),
+                WatchEvent::Audited {
+                    instance_id,
+                    verdict: InstanceVerdict::Honest,
+                } => emitln!("event=audited instance_id={instance_id} verdict=honest"),
+                WatchEvent::Audited {
+                    instance_id,
+                    verdict: InstanceVerdict::Unprovable(reason),
+                } => {
+                    emitln!("event=audited instance_id={instance_id} verdict=unprovable");
+                    tracing::warn!(instance_id, "{reason}");
+                }
+                WatchEvent::Audited {
+                    instance_id,
+                    verdict: InstanceVerdict::Dispute(prepared),
+                } => {
+                    emitln!("event=audited instance_id={instance_id} verdict=dispute");
+                    emitln!("selected_gate_index={}", prepared.gate_index);
+                    emitln!("mismatch_count={}", prepared.audit.mismatches.len());
+                    if no_submit {
+                        emitln!("dispute_submitted=false");
+                        return Ok(());
+                    }
+                    let seed = snapshot
+                        .opened
+                        .iter()
+                        .find(|opened| opened.instance_id == instance_id)
+                        .map(|opened| opened.seed)
+                        .ok_or("audited instance missing from snapshot")?;
+                    let call = dispute_garbled_table_call(
+                        instance_id,
+                        seed,
+                        prepared.gate_index as u64,
+                        prepared.gate,
+                        &prepared.claimed_leaf,
+                        &prepared.ih_proof,
+                        &prepared.layout_proof,
+                    );
+                    send_dispute_garbled_table(&call)?;
+                    // A successful dispute slashes Alice and closes the session.
+                    return Ok(());
+                }
+                WatchEvent::NothingToDispute { seconds_left } => {
+                    emitln!("event=nothing_to_dispute seconds_left={seconds_left}");
+                    return Ok(());
+                }
+                WatchEvent::Urgent { seconds_left } => {
+                    tracing::warn!(seconds_left, "dispute pending near the end of the window");
+                }
+                WatchEvent::WindowClosed => {
+                    emitln!("event=window_closed");
+                    return Ok(());
+                }
+            }
+        }
+        if once {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(interval));
+    }
+}
+
+/// Own key for the secure channel.
+fn channel_identity() -> AppResult<EthIdentity> {
+    let private_key = parse_bytes32(&private_key(&["BOB_PRIVATE_KEY"])?)?;
+    Ok(EthIdentity::from_private_key(private_key)?)
+}
+
+#[derive(Debug, Args)]
+struct SendFilesArgs {
+    /// Peer to connect to
+    #[arg(long, value_name = "HOST:PORT")]
+    connect: String,
+    /// Address the peer must prove
+    #[arg(long, value_name = "0x..", value_parser = address_arg)]
+    peer_address: [u8; 20],
+    /// Directory the files are read from
+    #[arg(long, value_name = "PATH")]
+    dir: PathBuf,
+    /// Files to send [default: every file in --dir]
+    #[arg(long, value_name = "NAME,NAME", value_delimiter = ',')]
+    files: Option<Vec<String>>,
+}
+
+fn cmd_send_files(args: SendFilesArgs) -> AppResult<()> {
+    let addr = args.connect;
+    let dir = args.dir;
+    let names = match args.files {
+        Some(names) => names
+            .iter()
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .collect(),
+        None => list_dir_files(&dir)?,
+    };
+    let (identity, peer_address) = (channel_identity()?, args.peer_address);
+    let policy = RetryPolicy::from_env()?;
+    let byte_count = send_files_to(&addr, &identity, peer_address, &dir, &names, &policy)?;
+
+    emitln!("status=sent");
+    emitln!("peer={addr}");
+    emitln!("peer_address={}", hex_prefixed(&peer_address));
+    emitln!("file_count={}", names.len());
+    emitln!("byte_count={byte_count}");
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct ReceiveFilesArgs {
+    /// Address to accept the peer on
+    #[arg(long, value_name = "HOST:PORT")]
+    listen: String,
+    /// Address the peer must prove
+    #[arg(long, value_name = "0x..", value_parser = address_arg)]
+    peer_address: [u8; 20],
+    /// Directory the files are written to
+    #[arg(long, value_name = "PATH")]
+    out_dir: PathBuf,
+}
+
+fn cmd_receive_files(args: ReceiveFilesArgs) -> AppResult<()> {
+    let listen = args.listen;
+    let out_dir = args.out_dir;
+    let (identity, peer_address) = (channel_identity()?, args.peer_address);
+    let policy = RetryPolicy::from_env()?;
+    let (peer, batch) = receive_files_on(&listen, &identity, peer_address, &out_dir, &policy)?;
+
+    emitln!("status=received");
+    emitln!("peer={peer}");
+    emitln!("peer_address={}", hex_prefixed(&peer_address));
+    emitln!("out_dir={}", out_dir.display());
+    emitln!("file_count={}", batch.names.len());
+    emitln!("files={}", batch.names.join(","));
+    emitln!("duplicate_batch={}", batch.duplicate);
+    Ok(())
+}
+
+/// Commands `serve` exposes over JSON-RPC: local computations only, no transactions.
+const SERVE_METHODS: &[&str] = &["ot-choose", "evaluate-m", "prepare-dispute", "prepare-ot-dispute"];
+
+#[derive(Debug, Args)]
+struct ServeArgs {
+    /// Address of the JSON-RPC endpoint
+    #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:8702")]
+    listen: String,
+}
+
+fn cmd_serve(args: ServeArgs, session: Option<&SessionStore>) -> AppResult<()> {
+    let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+    serve_session(&cli, &args.listen, SERVE_METHODS, session)
+}
+
+#[derive(Debug, Args)]
+struct RelayArgs {
+    /// Address of the relayer endpoint; may be public, behind a TLS proxy
+    #[arg(long, value_name = "HOST:PORT", default_value = "127.0.0.1:8703")]
+    listen: String,
+}
+
+fn cmd_claim_timeout(options: ClaimTimeoutOptions) -> AppResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let private_key = private_key(&["BOB_PRIVATE_KEY"])?;
+    claim_timeout(&contract_address, &rpc_url(), Party::Evaluator, &private_key, options)
+}
+
+fn cmd_status() -> AppResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    print_contract_status(&contract_address, &rpc_url())
+}
+
+fn cmd_watch_events(options: &WatchOptions) -> AppResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    watch_contract(&contract_address, &rpc_url(), options)
+}
+
+/// Checks a message file's signature against the signer's on-chain role.
+fn cmd_verify_message(path: PathBuf) -> AppResult<()> {
+    let (message, signer) = read_verified_message(&path, false)?;
+    let signer = signer.expect("read_verified_message rejects unsigned files");
+
+    emitln!("status=verified");
+    emitln!("file={}", path.display());
+    emitln!("kind={}", message.kind());
+    match message.sender() {
+        Party::Garbler => emitln!("sender_role=alice"),
+        _ => emitln!("sender_role=buyer"),
+    }
+    emitln!("signer={}", hex_prefixed(&signer));
+    Ok(())
+}
+
+/// Commands in protocol order, listed by `print_help` under the phase they drive.
+const COMMAND_PHASES: &[(Phase, &[&str])] = &[
+    (Phase::Deposit, &["deposit"]),
+    (Phase::Commit, &["commit-verifier-seed", "reveal-verifier-seed"]),
+    (Phase::Choose, &["commit-choice", "beacon-m", "choose", "buyer-ready"]),
+    (Phase::Open, &["ot-choose"]),
+    (Phase::Eval, &["verify-eval-package", "evaluate-m"]),
+    (
+        Phase::Dispute,
+        &[
+            "watch",
+            "import-leaves",
+            "prepare-dispute",
+            "scan-disputes",
+            "prepare-ot-dispute",
+            "export-csv",
+            "dispute",
+            "auto-dispute",
+            "dispute-ot",
+            "close-dispute",
+        ],
+    ),
+    (Phase::Settle, &["settle-auction", "finalize-assignment"]),
+];
+
+/// Session flags restored by `--session <dir>` when a command omits them.
+const SESSION_FLAGS: &[&str] = &[
+    "--circuit",
+    "--bit-width",
+    "--circuit-id",
+    "--n",
+    "--winner-formula",
+    "--m",
+    "--eval-dir",
+    "--artifacts-dir",
+];
+
+/// Secret flags read from stdin or a hidden prompt when given as `-`.
+const SECRET_FLAGS: &[&str] = &["--seed", "--salt", "--verifier-seed", "--garbler-seed"];
+
+/// Seeds and salts Bob draws himself, kept so the later reveal uses the committed values.
+const SESSION_SEEDS: &[SessionSeed] = &[
+    SessionSeed {
+        commands: &["commit-verifier-seed"],
+        flag: "--seed",
+        name: "verifier-seed",
+        generate: true,
+    },
+    SessionSeed {
+        commands: &["commit-verifier-seed"],
+        flag: "--salt",
+        name: "verifier-salt",
+        generate: true,
+    },
+    SessionSeed {
+        commands: &["reveal-verifier-seed"],
+        flag: "--seed",
+        name: "verifier-seed",
+        generate: false,
+    },
+    SessionSeed {
+        commands: &["reveal-verifier-seed"],
+        flag: "--salt",
+        name: "verifier-salt",
+        generate: false,
+    },
+    SessionSeed {
+        commands: &["prepare-ot-dispute"],
+        flag: "--verifier-seed",
+        name: "verifier-seed",
+        generate: false,
+    },
+    SessionSeed {
+        commands: &["commit-choice"],
+        flag: "--salt",
+        name: "choice-salt",
+        generate: true,
+    },
+];
+
+fn cmd_resume(session: Option<&SessionStore>) -> AppResult<()> {
+    let store = session.ok_or("Missing required argument: --session")?;
+    print_session_resume(store, COMMAND_PHASES);
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct ReplayArgs {
+    /// Transcript to replay [default: the --session transcript]
+    #[arg(long, value_name = "PATH")]
+    transcript: Option<PathBuf>,
+    /// Cut-and-choose instances of the session
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Also check the recorded transactions against the chain
+    #[arg(long)]
+    check_chain: bool,
+}
+
+fn cmd_replay(args: ReplayArgs, session: Option<&SessionStore>) -> AppResult<()> {
+    let path = match (args.transcript, session) {
+        (Some(path), _) => path,
+        (None, Some(store)) => store.dir().join(TRANSCRIPT_FILE),
+        (None, None) => return Err("Provide --session <dir> or --transcript <path>".into()),
+    };
+    replay_transcript(&path, args.params, args.check_chain)
+}
+
+fn cmd_completions(args: CompletionsArgs) -> AppResult<()> {
+    let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+    io::stdout().write_all(completion_script(cli, args.shell).as_bytes())?;
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct DirArgs {
+    /// Export directory
+    #[arg(long, value_name = "PATH")]
+    dir: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct DiffArtifactsArgs {
+    #[arg(value_name = "DIR_A")]
+    dir_a: PathBuf,
+    #[arg(value_name = "DIR_B")]
+    dir_b: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct VerifyMessageArgs {
+    /// Signed message file
+    #[arg(long, value_name = "PATH")]
+    file: PathBuf,
+}
+
+#[derive(Debug, Args)]
+struct FetchIpfsArgs {
+    /// CID of the eval blob
+    #[arg(long, value_name = "CID", required_unless_present = "dir")]
+    cid: Option<String>,
+    /// Export whose manifest records the CID of --instance-id
+    #[arg(long, value_name = "PATH", conflicts_with = "cid", requires = "instance_id")]
+    dir: Option<PathBuf>,
+    /// Instance whose blob is fetched
+    #[arg(long, value_name = "ID", requires = "dir")]
+    instance_id: Option<u64>,
+    /// File the blob is written to
+    #[arg(long, value_name = "PATH")]
+    out: PathBuf,
+    /// Gateway to fetch from [default: the IPFS API at IPFS_API_URL]
+    #[arg(long, value_name = "URL")]
+    gateway: Option<String>,
+    /// blobHashGC the blob must match [default: the manifest's, with --dir]
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    blob_hash_gc: Option<[u8; 32]>,
+}
+
+/// Fetches an eval blob from IPFS by `--cid`, or by the CID an artifact manifest records for
+/// `--instance-id`, and checks it against `blobHashGC` before writing it out.
+fn cmd_fetch_ipfs(args: FetchIpfsArgs) -> AppResult<()> {
+    let out = args.out;
+    let mut expected_blob_hash = args.blob_hash_gc;
+    let cid = match (args.cid, args.dir, args.instance_id) {
+        (Some(cid), _, _) => Cid::parse(&cid)?,
+        (None, Some(dir), Some(instance_id)) => {
+            let manifest = ArtifactManifest::read(&dir)?;
+            let instance = manifest.instance(instance_id).ok_or_else(|| {
+                format!("{} has no instance {instance_id} in its manifest", dir.display())
+            })?;
+            let cid = instance.cid.as_deref().ok_or_else(|| {
+                format!("manifest records no CID for instance {instance_id}; run publish-ipfs")
+            })?;
+            expected_blob_hash.get_or_insert(instance.blob_hash_gc);
+            Cid::parse(cid)?
         }
-        _ => Err(format!("Unknown command: {command}. Use --help.").into()),
+        _ => return Err("Provide --cid <cid> or --dir <path> --instance-id <id>".into()),
+    };
+    let client = match args.gateway {
+        Some(gateway) => IpfsClient::gateway(&gateway, RetryPolicy::from_env()?),
+        None => IpfsClient::from_env()?,
+    };
+
+    let blob = client.fetch(&cid)?;
+    let blob_hash = eval_payload_versioned_blob_hash(&blob)?;
+    if let Some(expected) = expected_blob_hash
+        && blob_hash != expected
+    {
+        return Err(format!(
+            "blob fetched for {cid} hashes to {}, expected blobHashGC {}",
+            hex32(blob_hash),
+            hex32(expected)
+        )
+        .into());
+    }
+    fs::write(&out, &blob)?;
+
+    emitln!("status=fetched");
+    emitln!("cid={cid}");
+    emitln!("source={}", client.url());
+    emitln!("bytes={}", blob.len());
+    emitln!("cid_verified={}", cid.is_raw());
+    emitln!("blob_hash_gc={}", hex32(blob_hash));
+    emitln!("out={}", out.display());
+    Ok(())
+}
+
+/// Reads an eval blob published on-chain (or a copy of it) and writes its garbled-circuit
+/// leaves as a claimed-leaves file, checked against the `blobHashGC` and `rootGC` the contract
+/// holds for the evaluated instance `m`.
+#[derive(Debug, Args)]
+struct ImportLeavesArgs {
+    /// Blob transaction that published the eval blob
+    #[arg(
+        long,
+        value_name = "HASH",
+        required_unless_present_any = ["blob_file", "payload_file"],
+        conflicts_with_all = ["blob_file", "payload_file"]
+    )]
+    tx: Option<String>,
+    /// Beacon node the blobs of --tx are read from [default: BEACON_API_URL]
+    #[arg(long, value_name = "URL", requires = "tx")]
+    beacon_url: Option<String>,
+    /// Copy of the blobs
+    #[arg(long, value_name = "PATH", conflicts_with = "payload_file")]
+    blob_file: Option<PathBuf>,
+    /// Decoded eval payload
+    #[arg(long, value_name = "PATH")]
+    payload_file: Option<PathBuf>,
+    /// Claimed-leaves file to write
+    #[arg(long, value_name = "PATH")]
+    out: PathBuf,
+}
+
+fn cmd_import_leaves(args: ImportLeavesArgs) -> AppResult<()> {
+    let out = args.out;
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let rpc_url = rpc_url();
+    // Every source is checked against what Alice committed for the evaluated instance.
+    let m = cast_call_values(&contract_address, &rpc_url, &["m()(uint256)"])?
+        .into_iter()
+        .next()
+        .ok_or("m() returned nothing")?;
+    let m = parse_u64(&m, "m")?;
+    let commitment = read_instance_commitment(&contract_address, &rpc_url, m)?;
+
+    let (source, payload) = if let Some(tx_hash) = args.tx {
+        let tx = BlobTx::from_cast_json(&run_cast(&[
+            "tx".to_string(),
+            tx_hash.clone(),
+            "--json".to_string(),
+            "--rpc-url".to_string(),
+            rpc_url.clone(),
+        ])?)
+        .map_err(|e| format!("{tx_hash}: {e}"))?;
+        let timestamp = run_cast(&[
+            "block".to_string(),
+            tx.block_number.to_string(),
+            "--field".to_string(),
+            "timestamp".to_string(),
+            "--rpc-url".to_string(),
+            rpc_url.clone(),
+        ])?;
+        let timestamp = parse_u64(
+            timestamp.split_whitespace().next().unwrap_or(""),
+            "block timestamp",
+        )?;
+        let beacon = match args.beacon_url {
+            Some(url) => BeaconClient::new(&url, RetryPolicy::from_env()?),
+            None => BeaconClient::from_env()?,
+        };
+        let slot = beacon.slot_at(timestamp)?;
+        let blobs = beacon.blobs_for(slot, &tx.versioned_hashes)?;
+        emitln!("tx={tx_hash}");
+        emitln!("block_number={}", tx.block_number);
+        emitln!("slot={slot}");
+        emitln!("blob_count={}", blobs.len());
+        (beacon.url().to_string(), decode_blob_payload(&blobs)?)
+    } else if let Some(path) = args.blob_file {
+        let blobs = parse_blob_file(&fs::read(&path)?)?;
+        emitln!("blob_count={}", blobs.len());
+        (path.display().to_string(), decode_blob_payload(&blobs)?)
+    } else if let Some(path) = args.payload_file {
+        let payload = read_artifact(&path)?;
+        (path.display().to_string(), payload)
+    } else {
+        return Err("Provide --tx <hash>, --blob-file <path> or --payload-file <path>".into());
+    };
+
+    let blob_hash = eval_payload_versioned_blob_hash(&payload)?;
+    if blob_hash != commitment.blob_hash_gc {
+        return Err(format!(
+            "eval blob from {source} hashes to {}, but instance {m} committed blobHashGC {}",
+            hex32(blob_hash),
+            hex32(commitment.blob_hash_gc)
+        )
+        .into());
+    }
+    let payload = CanonicalEvalBlobPayload::decode(&payload)
+        .map_err(|e| format!("invalid eval payload from {source}: {e}"))?;
+    if payload.instance_id != m {
+        return Err(format!(
+            "eval blob from {source} is for instance {}, but the contract evaluates m={m}",
+            payload.instance_id
+        )
+        .into());
+    }
+    if payload.gc_leaves.is_empty() {
+        return Err(format!("eval blob from {source} carries no leaves").into());
+    }
+    let root_gc = incremental_root(&payload.gc_leaves);
+    if root_gc != payload.root_gc || root_gc != commitment.root_gc {
+        return Err(format!(
+            "leaves from {source} hash to rootGC {}, but the blob claims {} and instance {m} \
+             committed {}",
+            hex32(root_gc),
+            hex32(payload.root_gc),
+            hex32(commitment.root_gc)
+        )
+        .into());
+    }
+    let lines = payload
+        .gc_leaves
+        .iter()
+        .map(|leaf| format!("{}\n", hex_prefixed(leaf)))
+        .collect::<String>();
+    fs::write(&out, lines)?;
+
+    emitln!("status=imported");
+    emitln!("source={source}");
+    emitln!("instance_id={}", payload.instance_id);
+    emitln!("circuit_id={}", hex32(payload.circuit_id));
+    emitln!("blob_hash_gc={}", hex32(blob_hash));
+    emitln!("root_gc={}", hex32(root_gc));
+    emitln!("leaves={}", payload.gc_leaves.len());
+    emitln!("out={}", out.display());
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+struct ExportCsvArgs {
+    /// Instance the leaves belong to
+    #[arg(long, value_name = "ID")]
+    instance_id: u64,
+    /// Cut-and-choose instances; must match the contract's N()
+    #[arg(
+        long = "n",
+        value_name = "INSTANCES",
+        default_value_t = SessionParams::default(),
+        value_parser = session_params_arg
+    )]
+    params: SessionParams,
+    /// Leaves Alice published for the instance
+    #[arg(long, value_name = "PATH")]
+    claimed_leaves_file: PathBuf,
+    /// Directory gates.csv (and labels.csv) are written to
+    #[arg(long, value_name = "PATH")]
+    out_dir: PathBuf,
+    /// Alice's revealed seed; adds the audit verdict and labels.csv
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg)]
+    seed: Option<[u8; 32]>,
+    #[command(flatten)]
+    circuit: CircuitArgs,
+}
+
+fn cmd_export_csv(args: ExportCsvArgs) -> AppResult<()> {
+    let (circuit, bit_width) = (args.circuit.circuit, args.circuit.bit_width);
+    circuit.check_bit_width(bit_width)?;
+    let instance_id = args.instance_id;
+    args.params.check_m(instance_id as usize)?;
+    let out_dir = args.out_dir;
+    let seed = args.seed;
+    let circuit_id = args.circuit.circuit_id()?;
+
+    let leaves = open_claimed_leaves_file(&args.claimed_leaves_file)?;
+    let layout = CircuitLayout::new(circuit_id, instance_id, circuit.build(bit_width))
+        .with_scheme_id(circuit.params.scheme_id);
+    // With the opened seed, rows carry the audit verdict and the labels table is written too.
+    let audit = seed
+        .map(|seed| audit_claimed_leaf_source(seed, &layout, &leaves))
+        .transpose()?;
+
+    fs::create_dir_all(&out_dir)?;
+    let gates_csv = out_dir.join("gates.csv");
+    let mut writer = BufWriter::new(fs::File::create(&gates_csv)?);
+    let gate_rows = write_gates_csv(&mut writer, &layout, &leaves, audit.as_ref())?;
+    writer.flush()?;
+
+    emitln!("status=exported");
+    emitln!("circuit_id={}", hex32(circuit_id));
+    emitln!("instance_id={instance_id}");
+    emitln!("gates_csv={}", gates_csv.display());
+    emitln!("gate_rows={gate_rows}");
+    if let (Some(seed), Some(audit)) = (seed, audit) {
+        let labels_csv = out_dir.join("labels.csv");
+        let mut writer = BufWriter::new(fs::File::create(&labels_csv)?);
+        let label_rows = write_labels_csv(&mut writer, seed, &layout)?;
+        writer.flush()?;
+        emitln!("labels_csv={}", labels_csv.display());
+        emitln!("label_rows={label_rows}");
+        emitln!("mismatch_count={}", audit.mismatches.len());
+        emitln!("mismatch_indices={:?}", audit.mismatch_indices());
+    }
+    Ok(())
+}
+
+/// Evaluator (buyer) side of the privacy-preserving auction.
+#[derive(Debug, Parser)]
+#[command(name = "off-chain-bob")]
+struct Cli {
+    /// Print the result as one JSON object instead of key=value lines
+    #[arg(long, global = true)]
+    json: bool,
+    /// Network profile to run against, from the config file (ppa.toml or PPA_CONFIG)
+    #[arg(long, global = true, value_enum)]
+    profile: Option<ProfileName>,
+    /// Print every transaction (target, value, calldata) instead of sending it
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// Log to stderr: -v commands and transactions, -vv cast calls and retries (or RUST_LOG)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Read the private key from stdin, or a hidden prompt, instead of BOB_PRIVATE_KEY
+    #[arg(long, global = true)]
+    key_stdin: bool,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Deposit DEPOSIT_WEI into the auction
+    Deposit,
+    /// Commit to the verifier seed
+    CommitVerifierSeed(CommitVerifierSeedArgs),
+    /// Reveal the committed verifier seed
+    RevealVerifierSeed(RevealVerifierSeedArgs),
+    /// Commit to the instance to evaluate
+    CommitChoice(CommitChoiceArgs),
+    /// Derive m from a block hash or a VRF proof
+    BeaconM(BeaconMArgs),
+    /// Read the on-chain m and check it against the expected one
+    Choose(ChooseArgs),
+    /// Signal that the buyer is ready
+    BuyerReady,
+    /// Close the dispute window
+    CloseDispute,
+    /// Settle the auction with the output computed from the bids
+    SettleAuction(SettleAuctionArgs),
+    /// Finalize the name assignment
+    FinalizeAssignment,
+    /// Write the OT choices for the input labels (BOB_OT_SECRET)
+    OtChoose(OtChooseArgs),
+    /// Check an eval directory's leaves, labels, NOT hints and anchors before evaluating
+    VerifyEvalPackage(VerifyEvalPackageArgs),
+    /// Evaluate instance m on Bob's input
+    EvaluateM(EvaluateMArgs),
+    /// Audit an opened instance and prepare a garbled-table dispute
+    PrepareDispute(PrepareDisputeArgs),
+    /// Audit every opened instance at once and prepare a dispute packet for each bad one
+    ScanDisputes(ScanDisputesArgs),
+    /// Recompute an opened instance's OT root and prepare its dispute
+    PrepareOtDispute(PrepareOtDisputeArgs),
+    /// Dispute a garbled table
+    Dispute(DisputeArgs),
+    /// Audit an opened instance and, once confirmed, dispute its first bad gate
+    AutoDispute(AutoDisputeArgs),
+    /// Dispute an OT root
+    DisputeOt(DisputeOtArgs),
+    /// Audit opened instances during the dispute window and dispute the first bad one
+    Watch(WatchArgs),
+    /// Send files over the authenticated channel
+    SendFiles(SendFilesArgs),
+    /// Receive files over the authenticated channel
+    ReceiveFiles(ReceiveFilesArgs),
+    /// Claim the timeout or refund path of the current stage
+    ClaimTimeout(ClaimTimeoutOptions),
+    /// Print the contract's stage, parties, commitments, m, deadlines and vault balances
+    Status,
+    /// Print stage transitions, parties' actions and approaching deadlines as they happen
+    WatchEvents(WatchOptions),
+    /// Check a signed message file against the sender's on-chain role
+    VerifyMessage(VerifyMessageArgs),
+    /// Check an export against its manifest
+    VerifyManifest(DirArgs),
+    /// Upgrade an export directory to the current layout
+    MigrateArtifacts(DirArgs),
+    /// Compare the instances of two export directories
+    DiffArtifacts(DiffArtifactsArgs),
+    /// Check Alice's manifest signature against the contract's alice()
+    VerifySignature(DirArgs),
+    /// Fetch an eval blob from IPFS and check it against blobHashGC
+    FetchIpfs(FetchIpfsArgs),
+    /// Write the leaves of a published eval blob as a claimed-leaves file
+    ImportLeaves(ImportLeavesArgs),
+    /// Export the gates (and labels) of an instance as CSV
+    ExportCsv(ExportCsvArgs),
+    /// Serve ot-choose, evaluate-m, prepare-dispute and prepare-ot-dispute over JSON-RPC
+    Serve(ServeArgs),
+    /// Pay the gas of other parties' calls to CONTRACT_ADDRESS from RELAYER_PRIVATE_KEY
+    Relay(RelayArgs),
+    /// Show where the --session stopped and what runs next
+    Resume,
+    /// Replay a session transcript
+    Replay(ReplayArgs),
+    /// Print the completion script for bash, zsh, fish, elvish or powershell
+    Completions(CompletionsArgs),
+}
+
+fn main() -> AppResult<()> {
+    let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+    let argv = resolve_secret_args(&env::args().collect::<Vec<_>>(), SECRET_FLAGS)?;
+    // A given commitment was built from seeds the session never saw.
+    let seeds = if argv
+        .iter()
+        .any(|arg| arg == "--commitment" || arg.starts_with("--commitment="))
+    {
+        &[][..]
+    } else {
+        SESSION_SEEDS
+    };
+    let (command, argv, session) =
+        restore_session_argv(&cli, &argv, "deposit", SESSION_FLAGS, seeds)?;
+    let cli: Cli = parse_cli(cli, &argv);
+    init_logging(cli.verbose);
+    let _span = tracing::info_span!("command", name = %command).entered();
+    tracing::info!("starting");
+    if let Some(profile) = cli.profile {
+        activate_profile(&load_profile(&config_path(), profile)?);
+    }
+    if cli.json {
+        start_json_output();
+    }
+    if cli.dry_run {
+        start_dry_run();
+    }
+    if cli.key_stdin {
+        start_key_stdin()?;
+    }
+    if let Some(path) = transcript_path(session.as_ref()) {
+        start_transcript(&path)?;
     }
+
+    let result = match cli.command.unwrap_or(Command::Deposit) {
+        Command::Deposit => cmd_deposit(),
+        Command::CommitVerifierSeed(args) => cmd_commit_verifier_seed(args),
+        Command::RevealVerifierSeed(args) => cmd_reveal_verifier_seed(args),
+        Command::CommitChoice(args) => cmd_commit_choice(args),
+        Command::BeaconM(args) => cmd_beacon_m(args),
+        Command::Choose(args) => cmd_choose(args),
+        Command::BuyerReady => cmd_buyer_ready(),
+        Command::CloseDispute => cmd_close_dispute(),
+        Command::SettleAuction(args) => cmd_settle_auction(args),
+        Command::FinalizeAssignment => cmd_finalize_assignment(),
+        Command::OtChoose(args) => cmd_ot_choose(args),
+        Command::VerifyEvalPackage(args) => cmd_verify_eval_package(args),
+        Command::EvaluateM(args) => cmd_evaluate_m(args),
+        Command::PrepareDispute(args) => cmd_prepare_dispute(args),
+        Command::ScanDisputes(args) => cmd_scan_disputes(args),
+        Command::PrepareOtDispute(args) => cmd_prepare_ot_dispute(args),
+        Command::Dispute(args) => cmd_dispute(args),
+        Command::AutoDispute(args) => cmd_auto_dispute(args),
+        Command::DisputeOt(args) => cmd_dispute_ot(args),
+        Command::Watch(args) => cmd_watch(args),
+        Command::SendFiles(args) => cmd_send_files(args),
+        Command::ReceiveFiles(args) => cmd_receive_files(args),
+        Command::ClaimTimeout(options) => cmd_claim_timeout(options),
+        Command::Status => cmd_status(),
+        Command::WatchEvents(options) => cmd_watch_events(&options),
+        Command::VerifyMessage(args) => cmd_verify_message(args.file),
+        Command::VerifyManifest(args) => verify_manifest_dir(&args.dir),
+        Command::MigrateArtifacts(args) => migrate_artifact_dir(&args.dir),
+        Command::DiffArtifacts(args) => print_artifact_diff(&args.dir_a, &args.dir_b),
+        Command::VerifySignature(args) => verify_manifest_signature(&args.dir),
+        Command::FetchIpfs(args) => cmd_fetch_ipfs(args),
+        Command::ImportLeaves(args) => cmd_import_leaves(args),
+        Command::ExportCsv(args) => cmd_export_csv(args),
+        Command::Serve(args) => cmd_serve(args, session.as_ref()),
+        Command::Relay(args) => serve_relayer(&args.listen),
+        Command::Resume => cmd_resume(session.as_ref()),
+        Command::Replay(args) => cmd_replay(args, session.as_ref()),
+        Command::Completions(args) => cmd_completions(args),
+    };
+    if let (Ok(()), Some(mut session)) = (&result, session)
+        && !is_dry_run()
+        && COMMAND_PHASES
+            .iter()
+            .any(|(_, commands)| commands.contains(&command.as_str()))
+    {
+        session.mark_completed(&command)?;
+    }
+    let error = result.as_ref().err().map(|e| e.to_string());
+    print_json_output(&command, error.as_deref());
+    result
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use off_chain_common::audit::LeafCorruption;
+    use off_chain_common::garble::garble_circuit;
+    use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+    use off_chain_common::scenario::build_millionaires_layout;
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -1347,15 +2750,16 @@ mod tests {
         let seed = [0x22u8; 32];
         let bit_width = 4usize;
         let instance_id = 0u64;
-        let layout = CircuitLayout {
+        let layout = CircuitLayout::new(
             circuit_id,
             instance_id,
-            gates: build_millionaires_layout(bit_width),
-        };
+            build_millionaires_layout(bit_width).gates,
+        );
 
         let mut claimed = garble_circuit(seed, &layout);
         claimed[0][0] ^= 1;
         let config = PrepareDisputeConfig {
+            circuit: lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit"),
             bit_width,
             circuit_id,
             instance_id,
@@ -1368,7 +2772,11 @@ mod tests {
 
         let prepared = prepare_dispute_packet(&config).expect("prepare dispute");
         assert_eq!(prepared.gate_index, 0);
-        assert!(prepared.mismatch_indices.contains(&0));
+        assert!(prepared.audit.mismatch_indices().contains(&0));
+        assert_eq!(
+            prepared.audit.gate(0).map(|gate| gate.corruption()),
+            Some(LeafCorruption::Header)
+        );
         assert_ne!(prepared.claimed_leaf, prepared.expected_leaf);
     }
 
@@ -1378,15 +2786,16 @@ mod tests {
         let seed = [0x33u8; 32];
         let bit_width = 4usize;
         let instance_id = 0u64;
-        let layout = CircuitLayout {
+        let layout = CircuitLayout::new(
             circuit_id,
             instance_id,
-            gates: build_millionaires_layout(bit_width),
-        };
+            build_millionaires_layout(bit_width).gates,
+        );
 
         let mut claimed = garble_circuit(seed, &layout);
         claimed[0][0] ^= 1;
         let config = PrepareDisputeConfig {
+            circuit: lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit"),
             bit_width,
             circuit_id,
             instance_id,
@@ -1539,4 +2948,103 @@ mod tests {
         assert_eq!(prepared.root_match, Some(true));
     }
 
+    #[test]
+    fn cli_definition_covers_every_phase_command() {
+        let mut cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+        cli.clone().debug_assert();
+        cli.build();
+        for (_, commands) in COMMAND_PHASES {
+            for name in *commands {
+                assert!(cli.find_subcommand(name).is_some(), "no subcommand {name}");
+            }
+        }
+    }
+
+    #[test]
+    fn opened_seeds_parse_as_id_seed_pairs() {
+        let seed = |byte: u8| format!("0x{}", format!("{byte:02x}").repeat(32));
+        assert_eq!(
+            opened_seeds_arg(&format!("1={},4={}", seed(0x11), seed(0x22))),
+            Ok(vec![(1, [0x11; 32]), (4, [0x22; 32])])
+        );
+        assert!(opened_seeds_arg(&seed(0x11)).is_err());
+        assert!(opened_seeds_arg(&format!("x={}", seed(0x11))).is_err());
+        assert!(opened_seeds_arg("1=0x11").is_err());
+    }
+
+    #[test]
+    fn choose_prefers_the_m_flag_over_the_positional_m() {
+        let cli = Cli::try_parse_from(["off-chain-bob", "choose", "4", "--m", "5"]).unwrap();
+        let Some(Command::Choose(args)) = cli.command else {
+            panic!("parsed {:?}", cli.command);
+        };
+        assert_eq!(args.m.or(args.expected_m), Some(5));
+        assert!(Cli::try_parse_from(["off-chain-bob", "dispute"]).is_err());
+        assert!(Cli::try_parse_from(["off-chain-bob", "dispute", "--packet", "p.cbor"]).is_ok());
+    }
+
+    #[test]
+    fn auto_dispute_takes_one_leaves_source() {
+        let seed = format!("0x{}", "11".repeat(32));
+        let parse = |source: &[&str]| {
+            let mut argv = vec!["off-chain-bob", "auto-dispute", "--instance-id", "2"];
+            argv.extend(["--seed", &seed]);
+            argv.extend(source);
+            Cli::try_parse_from(argv)
+        };
+        let cli = parse(&["--artifacts-dir", "export", "--yes"]).unwrap();
+        let Some(Command::AutoDispute(args)) = cli.command else {
+            panic!("parsed {:?}", cli.command);
+        };
+        assert_eq!(args.artifacts_dir, Some(PathBuf::from("export")));
+        assert!(args.yes);
+        assert!(parse(&["--claimed-leaves-file", "leaves.txt"]).is_ok());
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--claimed-leaves-file", "l.txt", "--artifacts-dir", "export"]).is_err());
+    }
+
+    #[test]
+    fn watch_events_is_separate_from_the_dispute_watchdog() {
+        let cli = Cli::try_parse_from(["off-chain-bob", "watch-events", "--hook", "notify"]);
+        let Some(Command::WatchEvents(options)) = cli.unwrap().command else {
+            panic!("watch-events did not parse as WatchEvents");
+        };
+        assert_eq!(options.hook.as_deref(), Some("notify"));
+        assert_eq!((options.interval_secs, options.margin_secs), (12, 600));
+        let cli = Cli::try_parse_from(["off-chain-bob", "watch", "--no-submit"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Watch(_))));
+        assert!(Cli::try_parse_from(["off-chain-bob", "watch-events", "--interval", "0"]).is_err());
+    }
+
+    #[test]
+    fn secret_flags_are_flags_of_some_command() {
+        let cli = cli_command::<Cli>(COMMAND_PHASES, "deposit");
+        for flag in SECRET_FLAGS {
+            let accepted = cli.get_subcommands().any(|command| {
+                command
+                    .get_arguments()
+                    .any(|arg| arg.get_long() == flag.strip_prefix("--"))
+            });
+            assert!(accepted, "no command takes {flag}");
+        }
+        let key_stdin = |argv: &[&str]| Cli::try_parse_from(argv).unwrap().key_stdin;
+        assert!(key_stdin(&["off-chain-bob", "--key-stdin", "deposit"]));
+        assert!(key_stdin(&["off-chain-bob", "status", "--key-stdin"]));
+        assert!(!key_stdin(&["off-chain-bob", "status"]));
+    }
+
+    #[test]
+    fn verbosity_counts_before_or_after_the_command() {
+        let verbose = |argv: &[&str]| Cli::try_parse_from(argv).unwrap().verbose;
+        assert_eq!(verbose(&["off-chain-bob", "-vv", "status"]), 2);
+        assert_eq!(verbose(&["off-chain-bob", "status", "--verbose"]), 1);
+        assert_eq!(verbose(&["off-chain-bob", "status"]), 0);
+    }
+
+    #[test]
+    fn command_phases_follow_protocol_order() {
+        let phases = COMMAND_PHASES.iter().map(|(phase, _)| *phase).collect::<Vec<_>>();
+        assert!(phases.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(COMMAND_PHASES.iter().all(|(_, commands)| !commands.is_empty()));
+    }
 }
@@ -0,0 +1,39 @@
+//! Garbling throughput per `ConsensusHasher` backend.
+//!
+//! Run with `cargo bench --bench consensus_hashers`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use off_chain_common::consensus::{ConsensusHasher, Keccak, Sha256Hasher, keccak256};
+use off_chain_common::garble::garble_circuit_with_hasher;
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::CircuitLayout;
+
+const ITERATIONS: u32 = 50;
+
+fn main() {
+    let layout = CircuitLayout::new(
+        keccak256(&[b"millionaires-yao-v1"]),
+        0,
+        build_millionaires_layout(64).gates,
+    );
+    let backends: [&dyn ConsensusHasher; 2] = [&Keccak, &Sha256Hasher];
+    for hasher in backends {
+        let garble = || {
+            garble_circuit_with_hasher(hasher, black_box([0x42u8; 32]), &layout)
+                .expect("classic layout")
+        };
+        black_box(garble());
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            black_box(garble());
+        }
+        println!(
+            "backend={} gates={} per_circuit={:?}",
+            hasher.name(),
+            layout.gates.len(),
+            start.elapsed() / ITERATIONS
+        );
+    }
+}
@@ -0,0 +1,69 @@
+//! Batch garbling of all cut-and-choose instances against the per-instance loop.
+//!
+//! Run with `cargo bench --bench garble_all_instances`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::garble::{garble_all_instances, garble_circuit};
+use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::scenario::{
+    CUT_AND_CHOOSE_N, build_millionaires_layout, derive_instance_seed,
+};
+use off_chain_common::types::{CircuitLayout, GateDesc};
+
+const ITERATIONS: u32 = 20;
+
+fn naive(master_seed: [u8; 32], circuit_id: [u8; 32], gates: &[GateDesc]) -> Vec<[u8; 32]> {
+    (0..CUT_AND_CHOOSE_N as u64)
+        .map(|instance_id| {
+            let seed = derive_instance_seed(master_seed, circuit_id, instance_id);
+            let layout = CircuitLayout::new(circuit_id, instance_id, gates.to_vec());
+            let block_hashes = garble_circuit(seed, &layout)
+                .iter()
+                .enumerate()
+                .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+                .collect::<Vec<_>>();
+            incremental_root_from_hashes(&block_hashes)
+        })
+        .collect()
+}
+
+fn time(mut run: impl FnMut()) -> Duration {
+    run();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        run();
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let circuit_id = keccak256(&[b"millionaires-yao-v1"]);
+    let master_seed = [0x42u8; 32];
+    for bit_width in [8, 32, 64] {
+        let gates = build_millionaires_layout(bit_width).gates;
+        let batch = garble_all_instances(master_seed, circuit_id, &gates, CUT_AND_CHOOSE_N)
+            .iter()
+            .map(|inst| inst.root_gc)
+            .collect::<Vec<_>>();
+        assert_eq!(batch, naive(master_seed, circuit_id, &gates));
+
+        let naive_time = time(|| {
+            black_box(naive(master_seed, circuit_id, black_box(&gates)));
+        });
+        let batch_time = time(|| {
+            black_box(garble_all_instances(
+                master_seed,
+                circuit_id,
+                black_box(&gates),
+                CUT_AND_CHOOSE_N,
+            ));
+        });
+        println!(
+            "bit_width={bit_width} gates={} n={CUT_AND_CHOOSE_N} naive={naive_time:?} batch={batch_time:?}",
+            gates.len()
+        );
+    }
+}
@@ -0,0 +1,44 @@
+//! Transferring y-labels for many wires: plain base OT versus IKNP extension.
+//!
+//! Run with `cargo bench --bench ot_extension`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use off_chain_common::base_ot::{OtReceiver, OtSender};
+use off_chain_common::consensus::keccak256;
+use off_chain_common::evaluation::derive_bob_label_offers;
+use off_chain_common::ot_extension::{OtExtReceiver, OtExtSender};
+
+const WIRE_COUNTS: [usize; 3] = [64, 512, 4096];
+
+fn main() {
+    for wire_count in WIRE_COUNTS {
+        let offers = derive_bob_label_offers([0x71u8; 32], keccak256(&[b"circuit"]), 0, wire_count);
+        let bits = (0..wire_count)
+            .map(|idx| (idx % 3 == 0) as u8)
+            .collect::<Vec<_>>();
+
+        let start = Instant::now();
+        let sender = OtSender::new([0xa1u8; 32]);
+        let receiver =
+            OtReceiver::new([0xb2u8; 32], &sender.setup_message(), &bits).expect("receiver");
+        let responses = sender
+            .respond(&receiver.choice_messages(), &offers)
+            .expect("respond");
+        black_box(receiver.decrypt(&responses).expect("decrypt"));
+        let base = start.elapsed();
+
+        let start = Instant::now();
+        let mut receiver = OtExtReceiver::new([0xb2u8; 32], [0x5eu8; 32], &bits);
+        let sender = OtExtSender::new([0xa1u8; 32], &receiver.setup_message()).expect("sender");
+        let request = receiver
+            .extend(&sender.base_choice_messages())
+            .expect("extend");
+        let responses = sender.respond(&request, &offers).expect("respond");
+        black_box(receiver.decrypt(&responses).expect("decrypt"));
+        let extension = start.elapsed();
+
+        println!("wires={wire_count} base_ot={base:?} iknp={extension:?}");
+    }
+}
@@ -0,0 +1,306 @@
+//! Solidity ABI encoding of the contract calls whose arguments are arrays and tuples.
+//!
+//! `cast` can encode calls itself from a signature and argument strings, but tuple and array
+//! arguments then have to be written as `"[(0x..,0x..),(..)]"` literals, where one misplaced
+//! bracket or quote becomes a revert or, worse, a different call. `AbiCall` encodes the
+//! arguments directly (head/tail layout, 32-byte words, padded `bytes`), and the binaries send
+//! the resulting calldata as is (`cast send <to> 0x<calldata>`), or print it under
+//! `--dry-run`.
+
+use crate::cli::{decode_hex, hex_prefixed};
+use crate::consensus::keccak256;
+use crate::messages::InstanceCommitment;
+use crate::types::GateDesc;
+
+pub const DISPUTE_GARBLED_TABLE_SIGNATURE: &str = "disputeGarbledTable(uint256,bytes32,uint256,\
+     (uint8,uint16,uint16,uint16),bytes,bytes32[],bytes32[])";
+
+pub const REVEAL_OPENINGS_SIGNATURE: &str = "revealOpenings(uint256[],bytes32[])";
+
+/// One ABI-encodable argument.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiValue {
+    /// Any `uintN` (and `bool` as `0`/`1`): one big-endian word.
+    Uint(u128),
+    Address([u8; 20]),
+    Bytes32([u8; 32]),
+    /// Dynamic `bytes`.
+    Bytes(Vec<u8>),
+    /// Dynamic `T[]`.
+    Array(Vec<AbiValue>),
+    /// Fixed-length `T[k]`.
+    FixedArray(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+fn word(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+impl AbiValue {
+    /// Whether the value is referenced by an offset from its enclosing head.
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            Self::Bytes(_) | Self::Array(_) => true,
+            Self::FixedArray(items) | Self::Tuple(items) => items.iter().any(Self::is_dynamic),
+            Self::Uint(_) | Self::Address(_) | Self::Bytes32(_) => false,
+        }
+    }
+
+    /// Bytes the value takes in its enclosing head: one offset word if dynamic.
+    fn head_len(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => 32,
+            Self::FixedArray(items) | Self::Tuple(items) => items.iter().map(Self::head_len).sum(),
+            _ => 32,
+        }
+    }
+
+    /// Standalone encoding: the head of a static value, the tail of a dynamic one.
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Uint(value) => word(*value).to_vec(),
+            Self::Address(address) => {
+                let mut out = vec![0u8; 12];
+                out.extend_from_slice(address);
+                out
+            }
+            Self::Bytes32(value) => value.to_vec(),
+            Self::Bytes(bytes) => {
+                let mut out = word(bytes.len() as u128).to_vec();
+                out.extend_from_slice(bytes);
+                out.resize(32 + bytes.len().div_ceil(32) * 32, 0);
+                out
+            }
+            Self::Array(items) => {
+                let mut out = word(items.len() as u128).to_vec();
+                out.extend(encode_sequence(items));
+                out
+            }
+            Self::FixedArray(items) | Self::Tuple(items) => encode_sequence(items),
+        }
+    }
+}
+
+/// Encodes `values` as a tuple: static values and offsets in the head, dynamic values after.
+pub fn encode_sequence(values: &[AbiValue]) -> Vec<u8> {
+    let head_len = values.iter().map(AbiValue::head_len).sum::<usize>();
+    let mut head = Vec::with_capacity(head_len);
+    let mut tail = Vec::new();
+    for value in values {
+        if value.is_dynamic() {
+            head.extend_from_slice(&word((head_len + tail.len()) as u128));
+            tail.extend(value.encode());
+        } else {
+            head.extend(value.encode());
+        }
+    }
+    head.extend(tail);
+    head
+}
+
+/// First four bytes of `keccak256(signature)`.
+pub fn function_selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(&[signature.as_bytes()]);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// A contract call: the canonical signature (no spaces or parameter names) and its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AbiCall {
+    pub signature: String,
+    pub args: Vec<AbiValue>,
+}
+
+impl AbiCall {
+    pub fn new(signature: impl Into<String>, args: Vec<AbiValue>) -> Self {
+        Self {
+            signature: signature.into(),
+            args,
+        }
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        function_selector(&self.signature)
+    }
+
+    /// Selector followed by the encoded arguments.
+    pub fn calldata(&self) -> Vec<u8> {
+        let mut out = self.selector().to_vec();
+        out.extend(encode_sequence(&self.args));
+        out
+    }
+
+    /// `0x`-prefixed calldata, as `cast send <to>` accepts in place of a signature.
+    pub fn calldata_hex(&self) -> String {
+        hex_prefixed(&self.calldata())
+    }
+}
+
+/// The call `cast send <to> <signature> <args..>` sends, encoded from the same argument
+/// strings, so `--dry-run` shows calldata for the calls the binaries send by signature. Takes
+/// the types those calls use: `uintN`, `bool`, `address`, `bytes32`, `bytes` and `T[]` lists
+/// written `[a,b]`; calls with tuples are built as `AbiCall`s in the first place.
+pub fn call_from_cast_args(signature: &str, args: &[String]) -> Result<AbiCall, String> {
+    let params = signature
+        .strip_suffix(')')
+        .and_then(|rest| rest.split_once('('))
+        .map(|(_, params)| params)
+        .filter(|params| !params.contains(['(', ')']))
+        .ok_or_else(|| format!("unsupported function signature {signature}"))?;
+    let types = params
+        .split(',')
+        .filter(|ty| !ty.is_empty())
+        .collect::<Vec<_>>();
+    if types.len() != args.len() {
+        return Err(format!(
+            "{signature} takes {} arguments, got {}",
+            types.len(),
+            args.len()
+        ));
+    }
+    let values = types
+        .iter()
+        .zip(args)
+        .map(|(ty, arg)| cast_arg_value(ty, arg.trim()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(AbiCall::new(signature, values))
+}
+
+fn cast_arg_value(ty: &str, arg: &str) -> Result<AbiValue, String> {
+    if let Some(item_ty) = ty.strip_suffix("[]") {
+        let items = arg
+            .strip_prefix('[')
+            .and_then(|items| items.strip_suffix(']'))
+            .ok_or_else(|| format!("{ty} argument must be a [..] list, got {arg}"))?;
+        return items
+            .split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| cast_arg_value(item_ty, item))
+            .collect::<Result<Vec<_>, _>>()
+            .map(AbiValue::Array);
+    }
+    let hex_bytes = || decode_hex(arg).map_err(|e| format!("invalid {ty} {arg}: {e}"));
+    match ty {
+        "bool" => match arg {
+            "true" => Ok(AbiValue::Uint(1)),
+            "false" => Ok(AbiValue::Uint(0)),
+            _ => Err(format!("invalid bool {arg}")),
+        },
+        "address" => hex_bytes()?
+            .try_into()
+            .map(AbiValue::Address)
+            .map_err(|_| format!("address {arg} is not 20 bytes")),
+        "bytes32" => hex_bytes()?
+            .try_into()
+            .map(AbiValue::Bytes32)
+            .map_err(|_| format!("bytes32 {arg} is not 32 bytes")),
+        "bytes" => hex_bytes().map(AbiValue::Bytes),
+        _ if ty
+            .strip_prefix("uint")
+            .is_some_and(|bits| bits.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            arg.parse::<u128>()
+                .map(AbiValue::Uint)
+                .map_err(|_| format!("invalid {ty} {arg}"))
+        }
+        _ => Err(format!("unsupported argument type {ty}")),
+    }
+}
+
+fn bytes32_values(values: &[[u8; 32]]) -> Vec<AbiValue> {
+    values.iter().copied().map(AbiValue::Bytes32).collect()
+}
+
+/// `submitCommitments((bytes32,bytes32,bytes32,bytes32)[N])`, with `N` the number of
+/// commitments (the contract's `N()`), each `(comSeed, rootGC, blobHashGC, hOut)`.
+pub fn submit_commitments_call(commitments: &[InstanceCommitment]) -> AbiCall {
+    let items = commitments
+        .iter()
+        .map(|c| {
+            AbiValue::Tuple(bytes32_values(&[
+                c.com_seed,
+                c.root_gc,
+                c.blob_hash_gc,
+                c.h_out,
+            ]))
+        })
+        .collect();
+    AbiCall::new(
+        format!(
+            "submitCommitments((bytes32,bytes32,bytes32,bytes32)[{}])",
+            commitments.len()
+        ),
+        vec![AbiValue::FixedArray(items)],
+    )
+}
+
+/// `submitOtRootsForBuyer(address,bytes32[N])`.
+pub fn submit_ot_roots_call(buyer: [u8; 20], root_ots: &[[u8; 32]]) -> AbiCall {
+    AbiCall::new(
+        format!("submitOtRootsForBuyer(address,bytes32[{}])", root_ots.len()),
+        vec![
+            AbiValue::Address(buyer),
+            AbiValue::FixedArray(bytes32_values(root_ots)),
+        ],
+    )
+}
+
+/// `revealOpenings(uint256[],bytes32[])` for the opened instances and their seeds.
+pub fn reveal_openings_call(indices: &[usize], seeds: &[[u8; 32]]) -> AbiCall {
+    AbiCall::new(
+        REVEAL_OPENINGS_SIGNATURE,
+        vec![
+            AbiValue::Array(indices.iter().map(|i| AbiValue::Uint(*i as u128)).collect()),
+            AbiValue::Array(bytes32_values(seeds)),
+        ],
+    )
+}
+
+/// `revealGarblerLabels(bytes32[])` with Alice's x-wire labels.
+pub fn reveal_garbler_labels_call(labels: &[[u8; 32]]) -> AbiCall {
+    AbiCall::new(
+        "revealGarblerLabels(bytes32[])",
+        vec![AbiValue::Array(bytes32_values(labels))],
+    )
+}
+
+/// `settle(bytes)` with the encoded auction output.
+pub fn settle_call(output_bytes: &[u8]) -> AbiCall {
+    AbiCall::new(
+        "settle(bytes)",
+        vec![AbiValue::Bytes(output_bytes.to_vec())],
+    )
+}
+
+/// `disputeGarbledTable(instanceId, seed, gateIndex, gate, leafBytes, ihProof, layoutProof)`.
+pub fn dispute_garbled_table_call(
+    instance_id: u64,
+    seed: [u8; 32],
+    gate_index: u64,
+    gate: GateDesc,
+    leaf: &[u8],
+    ih_proof: &[[u8; 32]],
+    layout_proof: &[[u8; 32]],
+) -> AbiCall {
+    AbiCall::new(
+        DISPUTE_GARBLED_TABLE_SIGNATURE,
+        vec![
+            AbiValue::Uint(instance_id.into()),
+            AbiValue::Bytes32(seed),
+            AbiValue::Uint(gate_index.into()),
+            AbiValue::Tuple(vec![
+                AbiValue::Uint(u128::from(gate.gate_type as u8)),
+                AbiValue::Uint(gate.wire_a.into()),
+                AbiValue::Uint(gate.wire_b.into()),
+                AbiValue::Uint(gate.wire_c.into()),
+            ]),
+            AbiValue::Bytes(leaf.to_vec()),
+            AbiValue::Array(bytes32_values(ih_proof)),
+            AbiValue::Array(bytes32_values(layout_proof)),
+        ],
+    )
+}
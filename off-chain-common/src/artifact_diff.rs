@@ -0,0 +1,277 @@
+//! Instance-by-instance comparison of two export directories, for finding out why a locally
+//! computed `rootGC` does not match the counterparty's.
+//!
+//! Files are matched by name with any `.zst` suffix dropped, and compared decoded (decrypted
+//! and decompressed), so a compressed and a plain export of the same instance are equal.
+//! Leaf and OT payload files are compared entry by entry and report the differing indices.
+//! For every instance the `rootGC` is recomputed from each side's leaves, which separates
+//! "the leaves differ" from "a recorded root does not match its own leaves".
+
+use std::fmt;
+use std::path::Path;
+
+use crate::cli::{hex_prefixed, parse_bytes32};
+use crate::compression::{ZSTD_SUFFIX, read_artifact};
+use crate::ih::incremental_root_from_hashes;
+use crate::leaf_file::LeafFile;
+use crate::line_reader::read_value_lines;
+use crate::transport::list_dir_files;
+
+/// Differing indices listed by `Display`; the count is always given.
+const MAX_LISTED_INDICES: usize = 16;
+
+/// One of the two compared directories.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::A => "a",
+            Self::B => "b",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArtifactDifference {
+    /// A file only one directory has.
+    Missing { name: String, missing_in: Side },
+    /// A single-value file (seed, commitment, root) or the eval blob differs.
+    Contents { name: String },
+    /// Entry-wise comparison of a leaf or OT payload file: the entry counts of both sides
+    /// and the indices, below both counts, whose entries differ.
+    Entries {
+        name: String,
+        counts: [usize; 2],
+        indices: Vec<usize>,
+    },
+    /// The `rootGC` recomputed from the leaves of both sides.
+    ComputedRootGc {
+        instance_id: u64,
+        roots: [[u8; 32]; 2],
+    },
+    /// A side's `root-gc.txt` is not the root of its own leaves.
+    StaleRootGc { instance_id: u64, side: Side },
+}
+
+impl fmt::Display for ArtifactDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing { name, missing_in } => write!(f, "{name}: missing in {missing_in}"),
+            Self::Contents { name } => write!(f, "{name}: contents differ"),
+            Self::Entries {
+                name,
+                counts,
+                indices,
+            } => {
+                write!(f, "{name}:")?;
+                if counts[0] != counts[1] {
+                    write!(f, " {} entries in a, {} in b", counts[0], counts[1])?;
+                    if indices.is_empty() {
+                        return Ok(());
+                    }
+                    f.write_str(";")?;
+                }
+                let listed = &indices[..indices.len().min(MAX_LISTED_INDICES)];
+                write!(f, " entries {listed:?} differ")?;
+                if listed.len() < indices.len() {
+                    write!(f, " (first {} of {})", listed.len(), indices.len())?;
+                }
+                Ok(())
+            }
+            Self::ComputedRootGc { instance_id, roots } => write!(
+                f,
+                "instance {instance_id}: rootGC from leaves is {} in a, {} in b",
+                hex_prefixed(&roots[0]),
+                hex_prefixed(&roots[1])
+            ),
+            Self::StaleRootGc { instance_id, side } => write!(
+                f,
+                "instance {instance_id}: root-gc.txt in {side} is not the root of its leaves"
+            ),
+        }
+    }
+}
+
+/// Differences of one instance, in file name order followed by the root checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceDiff {
+    pub instance_id: u64,
+    pub differences: Vec<ArtifactDifference>,
+}
+
+/// Outcome of `diff_artifact_dirs`: every instance either directory has, by id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtifactDiff {
+    pub instances: Vec<InstanceDiff>,
+}
+
+impl ArtifactDiff {
+    pub fn is_identical(&self) -> bool {
+        self.differences().next().is_none()
+    }
+
+    pub fn differences(&self) -> impl Iterator<Item = &ArtifactDifference> {
+        self.instances
+            .iter()
+            .flat_map(|instance| &instance.differences)
+    }
+
+    /// Ids of the instances with at least one difference.
+    pub fn differing_instances(&self) -> Vec<u64> {
+        self.instances
+            .iter()
+            .filter(|instance| !instance.differences.is_empty())
+            .map(|instance| instance.instance_id)
+            .collect()
+    }
+}
+
+/// Compares the instance files (`instance-<id>-*`) of the export directories `a` and `b`.
+/// Other files, such as the manifest, are not compared.
+pub fn diff_artifact_dirs(a: &Path, b: &Path) -> Result<ArtifactDiff, String> {
+    let names = [instance_files(a)?, instance_files(b)?];
+    let mut ids = names
+        .iter()
+        .flatten()
+        .map(|(id, _)| *id)
+        .collect::<Vec<_>>();
+    ids.sort_unstable();
+    ids.dedup();
+
+    let dirs = [a, b];
+    let mut instances = Vec::with_capacity(ids.len());
+    for instance_id in ids {
+        let mut files = names
+            .iter()
+            .flatten()
+            .filter(|(id, _)| *id == instance_id)
+            .map(|(_, name)| name.clone())
+            .collect::<Vec<_>>();
+        files.sort();
+        files.dedup();
+
+        let mut differences = Vec::new();
+        for name in &files {
+            let present = [0, 1].map(|side| names[side].iter().any(|(_, n)| n == name));
+            match present {
+                [true, false] | [false, true] => {
+                    differences.push(ArtifactDifference::Missing {
+                        name: name.clone(),
+                        missing_in: if present[0] { Side::B } else { Side::A },
+                    });
+                }
+                _ => differences.extend(diff_file(dirs, name)?),
+            }
+        }
+        differences.extend(diff_roots(dirs, &names, instance_id)?);
+        instances.push(InstanceDiff {
+            instance_id,
+            differences,
+        });
+    }
+    Ok(ArtifactDiff { instances })
+}
+
+/// `(instance id, name without .zst)` of every instance file in `dir`.
+fn instance_files(dir: &Path) -> Result<Vec<(u64, String)>, String> {
+    if !dir.is_dir() {
+        return Err(format!("{} is not a directory", dir.display()));
+    }
+    Ok(list_dir_files(dir)?
+        .into_iter()
+        .filter_map(|name| {
+            let name = name.strip_suffix(ZSTD_SUFFIX).unwrap_or(&name).to_string();
+            let (id, _) = name.strip_prefix("instance-")?.split_once('-')?;
+            Some((id.parse().ok()?, name))
+        })
+        .collect())
+}
+
+fn file_suffix(name: &str) -> &str {
+    name.strip_prefix("instance-")
+        .and_then(|rest| rest.split_once('-'))
+        .map_or(name, |(_, suffix)| suffix)
+}
+
+fn diff_file(dirs: [&Path; 2], name: &str) -> Result<Option<ArtifactDifference>, String> {
+    let entries = match file_suffix(name) {
+        "leaves.txt" => {
+            let [a, b] = dirs.map(|dir| LeafFile::open(&dir.join(name)));
+            let (a, b) = (a?, b?);
+            let mut indices = Vec::new();
+            for idx in 0..a.len().min(b.len()) {
+                if a.get(idx)? != b.get(idx)? {
+                    indices.push(idx);
+                }
+            }
+            Some(([a.len(), b.len()], indices))
+        }
+        "ot-payloads.txt" => {
+            let [a, b] =
+                dirs.map(|dir| read_value_lines(&dir.join(name), "payload hash", parse_bytes32));
+            let (a, b) = (a?, b?);
+            let indices = (0..a.len().min(b.len()))
+                .filter(|idx| a[*idx] != b[*idx])
+                .collect::<Vec<_>>();
+            Some(([a.len(), b.len()], indices))
+        }
+        _ => None,
+    };
+    if let Some((counts, indices)) = entries {
+        return Ok((counts[0] != counts[1] || !indices.is_empty()).then(|| {
+            ArtifactDifference::Entries {
+                name: name.to_string(),
+                counts,
+                indices,
+            }
+        }));
+    }
+    let [a, b] = dirs.map(|dir| read_artifact(&dir.join(name)));
+    let (a, b) = (a?, b?);
+    Ok(
+        (a.trim_ascii() != b.trim_ascii()).then(|| ArtifactDifference::Contents {
+            name: name.to_string(),
+        }),
+    )
+}
+
+/// Recomputes `rootGC` from each side's leaves and checks it against the other side and
+/// against the side's own `root-gc.txt`. Needs the leaves on both sides.
+fn diff_roots(
+    dirs: [&Path; 2],
+    names: &[Vec<(u64, String)>; 2],
+    instance_id: u64,
+) -> Result<Vec<ArtifactDifference>, String> {
+    let leaves = format!("instance-{instance_id}-leaves.txt");
+    let recorded = format!("instance-{instance_id}-root-gc.txt");
+    let has = |side: usize, name: &str| names[side].iter().any(|(_, n)| n == name);
+    if !has(0, &leaves) || !has(1, &leaves) {
+        return Ok(Vec::new());
+    }
+    let mut roots = [[0u8; 32]; 2];
+    let mut differences = Vec::new();
+    for (idx, side) in [Side::A, Side::B].into_iter().enumerate() {
+        let block_hashes = LeafFile::open(&dirs[idx].join(&leaves))?.block_hashes()?;
+        roots[idx] = incremental_root_from_hashes(&block_hashes);
+        if has(idx, &recorded) {
+            let path = dirs[idx].join(&recorded);
+            let raw = read_artifact(&path)?;
+            let root = std::str::from_utf8(&raw)
+                .map_err(|e| e.to_string())
+                .and_then(|text| parse_bytes32(text.trim()).map_err(|e| e.to_string()))
+                .map_err(|e| format!("invalid rootGC in {}: {e}", path.display()))?;
+            if root != roots[idx] {
+                differences.push(ArtifactDifference::StaleRootGc { instance_id, side });
+            }
+        }
+    }
+    if roots[0] != roots[1] {
+        differences.insert(0, ArtifactDifference::ComputedRootGc { instance_id, roots });
+    }
+    Ok(differences)
+}
@@ -0,0 +1,187 @@
+//! Layout versions of artifact directories (Alice's exports and eval dirs), so a directory
+//! written by an older release keeps working after file formats change.
+//!
+//! Directories are stamped with `layout-version.json`. One without the stamp predates it and
+//! is layout version 1. `migrate` upgrades a directory in place to `LAYOUT_VERSION`, one step
+//! per version. A step only adds files in the newer format and leaves the older ones where
+//! they are, so a `manifest.json` (and its signature) over the original files still
+//! verifies; the added files show up as `unlisted`.
+//!
+//! Version 1 to 2:
+//! - `eval-meta.txt` (`key=value`) becomes `eval-meta.json`.
+//! - An eval dir with only the hex text split files (`gc-m-leaves.txt`,
+//!   `alice-x-labels16.txt`, `not-hints.txt`, `ot-setup.txt`) gets the CBOR
+//!   `eval-package.cbor`. It is unsigned: Bob's `evaluate-m --package-file` then needs
+//!   `--allow-unsigned`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::base_ot::OT_POINT_LEN;
+use crate::canonical_json::{from_canonical_json, to_canonical_json};
+use crate::cli::{parse_bytes16, parse_fixed_bytes, parse_leaf71};
+use crate::compression::{find_artifact, read_artifact_to_string};
+use crate::eval_meta::{EVAL_META_FILE, EvalMeta, LEGACY_EVAL_META_FILE};
+use crate::evaluation::NotGateHint;
+use crate::line_reader::read_value_lines;
+use crate::messages::{EvalPackageMessage, Message};
+
+/// Stamp file inside an artifact directory.
+pub const LAYOUT_VERSION_FILE: &str = "layout-version.json";
+
+/// Layout version written by this release.
+pub const LAYOUT_VERSION: u32 = 2;
+
+/// Layout version of a directory without `LAYOUT_VERSION_FILE`.
+pub const UNSTAMPED_LAYOUT_VERSION: u32 = 1;
+
+/// Eval package `migrate` builds from the split files of a version 1 eval dir.
+pub const EVAL_PACKAGE_FILE: &str = "eval-package.cbor";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutStamp {
+    version: u32,
+}
+
+/// Writes `dir/layout-version.json` for `LAYOUT_VERSION`.
+pub fn write_layout_version(dir: &Path) -> Result<PathBuf, String> {
+    let path = dir.join(LAYOUT_VERSION_FILE);
+    let stamp = to_canonical_json(&LayoutStamp {
+        version: LAYOUT_VERSION,
+    })?;
+    fs::write(&path, stamp).map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    Ok(path)
+}
+
+/// Layout version of `dir` (or a directory inside a bundle). Fails for a version newer than
+/// `LAYOUT_VERSION`, which this release cannot read.
+pub fn read_layout_version(dir: &Path) -> Result<u32, String> {
+    let path = dir.join(LAYOUT_VERSION_FILE);
+    if find_artifact(&path).is_none() {
+        return Ok(UNSTAMPED_LAYOUT_VERSION);
+    }
+    let stamp: LayoutStamp = from_canonical_json(&read_artifact_to_string(&path)?)
+        .map_err(|e| format!("{}: {e}", path.display()))?;
+    if stamp.version == 0 || stamp.version > LAYOUT_VERSION {
+        return Err(format!(
+            "{}: unsupported layout version {} (this release reads up to {LAYOUT_VERSION})",
+            path.display(),
+            stamp.version
+        ));
+    }
+    Ok(stamp.version)
+}
+
+/// Outcome of `migrate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub from: u32,
+    pub to: u32,
+    /// Files the migration wrote, in order; the stamp comes last.
+    pub written: Vec<String>,
+}
+
+/// Upgrades the artifact directory `dir` in place to `LAYOUT_VERSION`. A directory that is
+/// already current is left untouched.
+pub fn migrate(dir: &Path) -> Result<Migration, String> {
+    if !dir.is_dir() {
+        return Err(format!(
+            "{} is not a directory; bundles are read-only, migrate the directory before \
+             bundling",
+            dir.display()
+        ));
+    }
+    let from = read_layout_version(dir)?;
+    let mut written = Vec::new();
+    if from == LAYOUT_VERSION {
+        return Ok(Migration {
+            from,
+            to: from,
+            written,
+        });
+    }
+    if from < 2 {
+        migrate_v1_to_v2(dir, &mut written)?;
+    }
+    write_layout_version(dir)?;
+    written.push(LAYOUT_VERSION_FILE.to_string());
+    Ok(Migration {
+        from,
+        to: LAYOUT_VERSION,
+        written,
+    })
+}
+
+fn migrate_v1_to_v2(dir: &Path, written: &mut Vec<String>) -> Result<(), String> {
+    let has = |name: &str| find_artifact(&dir.join(name)).is_some();
+    if !has(EVAL_META_FILE) && !has(LEGACY_EVAL_META_FILE) {
+        // An export dir: its instance files have not changed format.
+        return Ok(());
+    }
+    let meta = EvalMeta::read(dir)?;
+    if !has(EVAL_META_FILE) {
+        meta.write(dir)?;
+        written.push(EVAL_META_FILE.to_string());
+    }
+    if has("gc-m-leaves.txt") && !has(EVAL_PACKAGE_FILE) {
+        let package = Message::EvalPackage(read_split_eval_package(dir, meta)?);
+        let path = dir.join(EVAL_PACKAGE_FILE);
+        fs::write(&path, package.to_cbor())
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        written.push(EVAL_PACKAGE_FILE.to_string());
+    }
+    Ok(())
+}
+
+/// Eval package from the hex text files `prepare-eval` wrote next to `eval-meta`. Eval dirs
+/// from before NOT hints or the base OT lack those files; the package then has none.
+fn read_split_eval_package(dir: &Path, meta: EvalMeta) -> Result<EvalPackageMessage, String> {
+    let gc_leaves = read_value_lines(&dir.join("gc-m-leaves.txt"), "leaf", parse_leaf71)?;
+    let alice_labels = read_value_lines(
+        &dir.join("alice-x-labels16.txt"),
+        "16-byte label",
+        parse_bytes16,
+    )?;
+    let hints_path = dir.join("not-hints.txt");
+    let not_hints = match find_artifact(&hints_path) {
+        Some(_) => read_value_lines(&hints_path, "NOT hint", parse_not_hint)?,
+        None => Vec::new(),
+    };
+    let setup_path = dir.join("ot-setup.txt");
+    let ot_setup = match find_artifact(&setup_path) {
+        Some(_) => Some(
+            parse_fixed_bytes::<OT_POINT_LEN>(read_artifact_to_string(&setup_path)?.trim())
+                .map_err(|e| format!("invalid OT setup point in {}: {e}", setup_path.display()))?,
+        ),
+        None => None,
+    };
+    Ok(EvalPackageMessage {
+        bit_width: meta.bit_width,
+        decoding: meta.decoding,
+        gc_leaves,
+        alice_labels,
+        not_hints,
+        ot_setup,
+    })
+}
+
+/// `gate,in0,out0,in1,out1`, as in `not-hints.txt`.
+fn parse_not_hint(row: &str) -> Result<NotGateHint, String> {
+    let parts = row.split(',').map(str::trim).collect::<Vec<_>>();
+    let [gate_index, in_label0, out_if_in0, in_label1, out_if_in1] = parts[..] else {
+        return Err("expected gate,in0,out0,in1,out1".to_string());
+    };
+    let label = |value: &str| parse_bytes16(value).map_err(|e| e.to_string());
+    Ok(NotGateHint {
+        gate_index: gate_index
+            .parse()
+            .map_err(|_| format!("invalid gate index '{gate_index}'"))?,
+        in_label0: label(in_label0)?,
+        out_if_in0: label(out_if_in0)?,
+        in_label1: label(in_label1)?,
+        out_if_in1: label(out_if_in1)?,
+    })
+}
@@ -0,0 +1,151 @@
+//! Garbling audits: compares the leaves a garbler claimed with the leaves recomputed from the
+//! opened seed, and classifies how each mismatching leaf was corrupted.
+
+use std::fmt;
+
+use crate::consensus::LEAF_BYTES_LEN;
+use crate::garble::garble_circuit_iter;
+use crate::leaf_file::ClaimedLeaves;
+use crate::types::CircuitLayout;
+
+/// Byte offset of `row0` inside a leaf (after the 7-byte gate header).
+const ROWS_OFFSET: usize = 7;
+
+/// Difference between one claimed leaf and its recomputed leaf.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateAudit {
+    pub gate_index: usize,
+    /// Claimed header bytes differ from the layout's gate descriptor.
+    pub header_mismatch: bool,
+    /// Rows (`0..4`, Solidity row order) whose ciphertext differs.
+    pub differing_rows: Vec<usize>,
+    pub claimed_leaf: [u8; LEAF_BYTES_LEN],
+    pub expected_leaf: [u8; LEAF_BYTES_LEN],
+}
+
+impl GateAudit {
+    fn compare(
+        gate_index: usize,
+        claimed_leaf: [u8; LEAF_BYTES_LEN],
+        expected_leaf: [u8; LEAF_BYTES_LEN],
+    ) -> Option<Self> {
+        if claimed_leaf == expected_leaf {
+            return None;
+        }
+        let row = |leaf: &[u8; LEAF_BYTES_LEN], idx: usize| {
+            let start = ROWS_OFFSET + 16 * idx;
+            leaf[start..start + 16].to_vec()
+        };
+        Some(Self {
+            gate_index,
+            header_mismatch: claimed_leaf[..ROWS_OFFSET] != expected_leaf[..ROWS_OFFSET],
+            differing_rows: (0..4)
+                .filter(|idx| row(&claimed_leaf, *idx) != row(&expected_leaf, *idx))
+                .collect(),
+            claimed_leaf,
+            expected_leaf,
+        })
+    }
+
+    /// Whether the corruption touches the gate descriptor, the ciphertexts, or both.
+    pub fn corruption(&self) -> LeafCorruption {
+        match (self.header_mismatch, self.differing_rows.is_empty()) {
+            (true, true) => LeafCorruption::Header,
+            (false, _) => LeafCorruption::Rows,
+            (true, false) => LeafCorruption::HeaderAndRows,
+        }
+    }
+}
+
+impl fmt::Display for GateAudit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "gate={} {}", self.gate_index, self.corruption())?;
+        if !self.differing_rows.is_empty() {
+            write!(f, " rows={:?}", self.differing_rows)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which part of a leaf a garbler corrupted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeafCorruption {
+    /// Wrong gate descriptor with correct rows, e.g. a rewired gate.
+    Header,
+    /// Correct descriptor with wrong ciphertexts, e.g. a garbled row for a different function.
+    Rows,
+    HeaderAndRows,
+}
+
+impl fmt::Display for LeafCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LeafCorruption::Header => "header",
+            LeafCorruption::Rows => "rows",
+            LeafCorruption::HeaderAndRows => "header+rows",
+        })
+    }
+}
+
+/// Result of `audit_claimed_leaves`: one entry per mismatching gate, in gate order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditReport {
+    pub gate_count: usize,
+    pub mismatches: Vec<GateAudit>,
+}
+
+impl AuditReport {
+    /// True when every claimed leaf matches the recomputed one.
+    pub fn is_clean(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    pub fn mismatch_indices(&self) -> Vec<usize> {
+        self.mismatches.iter().map(|gate| gate.gate_index).collect()
+    }
+
+    /// Audit entry of `gate_index`, or `None` if that leaf matches.
+    pub fn gate(&self, gate_index: usize) -> Option<&GateAudit> {
+        self.mismatches
+            .iter()
+            .find(|gate| gate.gate_index == gate_index)
+    }
+}
+
+/// Recomputes every leaf of `layout` from the opened `seed` and reports per-gate differences
+/// against `claimed`. Only `LeafFormat::V1` layouts, whose leaves Solidity can recompute, are
+/// audited.
+pub fn audit_claimed_leaves(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    claimed: &[[u8; LEAF_BYTES_LEN]],
+) -> Result<AuditReport, String> {
+    audit_claimed_leaf_source(seed, layout, claimed)
+}
+
+/// `audit_claimed_leaves` over any `ClaimedLeaves`, e.g. a memory-mapped `LeafFile`; leaves
+/// are garbled and compared one gate at a time.
+pub fn audit_claimed_leaf_source(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    claimed: &(impl ClaimedLeaves + ?Sized),
+) -> Result<AuditReport, String> {
+    layout.scheme_id().check_onchain()?;
+    if claimed.leaf_count() != layout.gates.len() {
+        return Err(format!(
+            "claimed leaves count ({}) does not match circuit gate count ({})",
+            claimed.leaf_count(),
+            layout.gates.len()
+        ));
+    }
+    let mut mismatches = Vec::new();
+    for (idx, expected) in garble_circuit_iter(seed, layout).enumerate() {
+        if let Some(gate) = GateAudit::compare(idx, claimed.leaf(idx)?, expected) {
+            mismatches.push(gate);
+        }
+    }
+    Ok(AuditReport {
+        gate_count: layout.gates.len(),
+        mismatches,
+    })
+}
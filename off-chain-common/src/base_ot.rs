@@ -0,0 +1,268 @@
+//! Base oblivious transfer for Bob's input labels (Chou-Orlandi "simplest OT" over
+//! secp256k1). Alice offers both labels of every y-wire, Bob learns exactly the label of his
+//! bit and Alice learns nothing about the bit. The committed OT transcripts checked by
+//! `disputeOt` live in `ot`; this module only moves the labels.
+//!
+//! Answering two different choice sets for one instance would hand Bob both labels of the
+//! wires where they differ, so Alice records the choice set she answered per instance
+//! (`record_answered_choices`) and refuses any other.
+//!
+//! Per wire `i` with Alice's setup point `A = aG`:
+//! - Bob sends `B_i = b_i G` for bit `0` or `B_i = A + b_i G` for bit `1`,
+//! - Alice replies `e_{i,c} = L_{i,c} XOR k(i, A, B_i, a (B_i - cA))` for `c in {0, 1}`,
+//! - Bob decrypts his bit's row with `k(i, A, B_i, b_i A)`,
+//!
+//! where `k(i, A, B, P)` is the first 16 bytes of
+//! `keccak256("OT-KEY", uint256(i), A, B, P)` over compressed SEC1 points.
+//!
+//! Chou-Orlandi is used instead of Naor-Pinkas because it needs a single setup point per
+//! instance and no extra point of unknown discrete log (Naor-Pinkas' `C`): Bob sends one
+//! point per wire and nothing else has to be agreed up front. It runs on the curve `k256`
+//! already provides for signatures. What it gives up:
+//! - security rests on computational Diffie-Hellman in the random-oracle model (keccak as the
+//!   oracle) instead of Naor-Pinkas' decisional Diffie-Hellman;
+//! - the original UC proof is flawed: a simulator cannot extract a malicious Bob's choice bit
+//!   from `B_i`, so there is only standalone security against him. Hashing `A` and `B_i` into
+//!   every key and refusing a second choice set per instance limit Bob to one label per wire
+//!   in practice, but this is not a simulation-based guarantee.
+//!
+//! Bob's choice stays perfectly hidden from Alice either way (`B_i` is uniform for both
+//! bits). That needs a fresh `b_i` per setup point: Bob keeps one secret across the
+//! cut-and-choose instances and bids the same `y` in each, so with `b_i` fixed Alice would
+//! see `B_i' - B_i''` be `0` or `A' - A''` and read off every bit. `b_i` is therefore derived
+//! from Bob's secret, `A` and `i`.
+//!
+//! Neither protocol stops a malicious Alice from selective failure (corrupting only one label
+//! of a wire); that is left to cut-and-choose and the committed OT transcripts.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use k256::elliptic_curve::group::GroupEncoding;
+use k256::elliptic_curve::ops::Reduce;
+use k256::{FieldBytes, ProjectivePoint, Scalar, U256};
+
+use crate::cli::hex32;
+use crate::consensus::{keccak256, uint256_from_u64, xor16};
+
+/// Length of a compressed SEC1 secp256k1 point.
+pub const OT_POINT_LEN: usize = 33;
+
+/// File in Alice's eval directory with one `<instance>,<choices digest>` line per instance
+/// whose OT choices she answered.
+pub const ANSWERED_CHOICES_FILE: &str = ".ot-answered";
+
+/// Non-zero scalar derived from `keccak256(domain, secret, context, uint256(index), counter)`.
+fn derive_scalar(domain: &[u8], secret: [u8; 32], context: &[u8], index: u64) -> Scalar {
+    let index = uint256_from_u64(index);
+    (0u8..)
+        .map(|counter| {
+            let digest = keccak256(&[domain, &secret, context, &index, &[counter]]);
+            <Scalar as Reduce<U256>>::reduce_bytes(&FieldBytes::from(digest))
+        })
+        .find(|scalar| !bool::from(scalar.is_zero()))
+        .expect("a non-zero scalar within 256 attempts")
+}
+
+fn encode_point(point: &ProjectivePoint) -> [u8; OT_POINT_LEN] {
+    let mut out = [0u8; OT_POINT_LEN];
+    out.copy_from_slice(&point.to_bytes());
+    out
+}
+
+/// Decodes a compressed point, rejecting invalid encodings and the identity.
+pub fn decode_ot_point(bytes: &[u8]) -> Result<ProjectivePoint, String> {
+    if bytes.len() != OT_POINT_LEN {
+        return Err(format!(
+            "OT point must be {OT_POINT_LEN} bytes, got {}",
+            bytes.len()
+        ));
+    }
+    let mut repr = <ProjectivePoint as GroupEncoding>::Repr::default();
+    repr.copy_from_slice(bytes);
+    let point = Option::<ProjectivePoint>::from(ProjectivePoint::from_bytes(&repr))
+        .ok_or_else(|| "OT point is not on secp256k1".to_string())?;
+    if point == ProjectivePoint::IDENTITY {
+        return Err("OT point is the identity".to_string());
+    }
+    Ok(point)
+}
+
+fn pad_key(
+    index: usize,
+    setup: &ProjectivePoint,
+    choice: &ProjectivePoint,
+    shared: &ProjectivePoint,
+) -> [u8; 16] {
+    let digest = keccak256(&[
+        b"OT-KEY",
+        &uint256_from_u64(index as u64),
+        &encode_point(setup),
+        &encode_point(choice),
+        &encode_point(shared),
+    ]);
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&digest[..16]);
+    key
+}
+
+/// Alice's side: one setup point for a batch of y-wires.
+#[derive(Debug, Clone)]
+pub struct OtSender {
+    secret: Scalar,
+    setup: ProjectivePoint,
+}
+
+impl OtSender {
+    /// Sender keyed by a private 32-byte secret; reuse the secret only for the same batch.
+    pub fn new(secret: [u8; 32]) -> Self {
+        let secret = derive_scalar(b"OT-SENDER", secret, &[], 0);
+        Self {
+            secret,
+            setup: ProjectivePoint::GENERATOR * secret,
+        }
+    }
+
+    /// Setup point `A` sent to Bob.
+    pub fn setup_message(&self) -> [u8; OT_POINT_LEN] {
+        encode_point(&self.setup)
+    }
+
+    /// Encrypts `pairs[i] = (L0, L1)` under Bob's choice point `choices[i]`; returns
+    /// `[e0, e1]` per wire.
+    pub fn respond(
+        &self,
+        choices: &[[u8; OT_POINT_LEN]],
+        pairs: &[([u8; 16], [u8; 16])],
+    ) -> Result<Vec<[[u8; 16]; 2]>, String> {
+        if choices.len() != pairs.len() {
+            return Err(format!(
+                "got {} OT choices for {} label pairs",
+                choices.len(),
+                pairs.len()
+            ));
+        }
+        choices
+            .iter()
+            .zip(pairs)
+            .enumerate()
+            .map(|(idx, (choice, (label0, label1)))| {
+                let choice =
+                    decode_ot_point(choice).map_err(|e| format!("OT choice {idx}: {e}"))?;
+                let key0 = pad_key(idx, &self.setup, &choice, &(choice * self.secret));
+                let key1 = pad_key(
+                    idx,
+                    &self.setup,
+                    &choice,
+                    &((choice - self.setup) * self.secret),
+                );
+                Ok([xor16(*label0, key0), xor16(*label1, key1)])
+            })
+            .collect()
+    }
+}
+
+/// Bob's side: one choice point per y-wire bit.
+#[derive(Debug, Clone)]
+pub struct OtReceiver {
+    setup: ProjectivePoint,
+    choice_bits: Vec<u8>,
+    secrets: Vec<Scalar>,
+    choices: Vec<ProjectivePoint>,
+}
+
+impl OtReceiver {
+    /// Receiver for `choice_bits` (one `0`/`1` per wire) against Alice's setup point.
+    /// `secret` must stay private; wire secrets are derived from it, the setup point and the
+    /// index, so reusing `secret` against another setup point yields unrelated choice points.
+    pub fn new(secret: [u8; 32], setup: &[u8], choice_bits: &[u8]) -> Result<Self, String> {
+        let setup_point = decode_ot_point(setup).map_err(|e| format!("OT setup: {e}"))?;
+        let secrets = (0..choice_bits.len())
+            .map(|idx| derive_scalar(b"OT-RECEIVER", secret, setup, idx as u64))
+            .collect::<Vec<_>>();
+        let choices = secrets
+            .iter()
+            .zip(choice_bits)
+            .map(|(secret, bit)| {
+                let blind = ProjectivePoint::GENERATOR * secret;
+                if bit & 1 == 1 {
+                    setup_point + blind
+                } else {
+                    blind
+                }
+            })
+            .collect();
+        Ok(Self {
+            setup: setup_point,
+            choice_bits: choice_bits.iter().map(|bit| bit & 1).collect(),
+            secrets,
+            choices,
+        })
+    }
+
+    /// Choice points `B_i` sent to Alice.
+    pub fn choice_messages(&self) -> Vec<[u8; OT_POINT_LEN]> {
+        self.choices.iter().map(encode_point).collect()
+    }
+
+    /// Decrypts the label of each chosen bit from Alice's `[e0, e1]` rows.
+    pub fn decrypt(&self, responses: &[[[u8; 16]; 2]]) -> Result<Vec<[u8; 16]>, String> {
+        if responses.len() != self.choices.len() {
+            return Err(format!(
+                "got {} OT responses for {} choices",
+                responses.len(),
+                self.choices.len()
+            ));
+        }
+        Ok(responses
+            .iter()
+            .enumerate()
+            .map(|(idx, rows)| {
+                let shared = self.setup * self.secrets[idx];
+                let key = pad_key(idx, &self.setup, &self.choices[idx], &shared);
+                xor16(rows[self.choice_bits[idx] as usize], key)
+            })
+            .collect())
+    }
+}
+
+/// `keccak256` over Bob's choice points, in wire order.
+pub fn choices_digest(choices: &[[u8; OT_POINT_LEN]]) -> [u8; 32] {
+    let parts = choices.iter().map(|point| &point[..]).collect::<Vec<_>>();
+    keccak256(&parts)
+}
+
+/// Records `choices` as the choice set answered for `instance_id` in
+/// `dir/ANSWERED_CHOICES_FILE`. The same choices may be answered again (a lost response is
+/// sent anew, and responses are deterministic); a different set for the instance is refused.
+pub fn record_answered_choices(
+    dir: &Path,
+    instance_id: u64,
+    choices: &[[u8; OT_POINT_LEN]],
+) -> Result<(), String> {
+    let path = dir.join(ANSWERED_CHOICES_FILE);
+    let digest = hex32(choices_digest(choices));
+    let mut ledger = match fs::read_to_string(&path) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("failed to read {}: {e}", path.display())),
+    };
+    for line in ledger.lines() {
+        let Some((instance, answered)) = line.trim().split_once(',') else {
+            continue;
+        };
+        if instance != instance_id.to_string() {
+            continue;
+        }
+        if answered == digest {
+            return Ok(());
+        }
+        return Err(format!(
+            "instance {instance_id} already answered OT choices {answered}; refusing a \
+             second, different choice set"
+        ));
+    }
+    ledger.push_str(&format!("{instance_id},{digest}\n"));
+    fs::write(&path, ledger).map_err(|e| format!("failed to write {}: {e}", path.display()))
+}
@@ -0,0 +1,309 @@
+//! Reading an eval blob back from the chain: Alice's `reveal-labels --blob` carries the eval
+//! blob of instance `m` as EIP-4844 blob data, checked by the contract against `blobHashGC`.
+//! Execution nodes drop blob data after the transaction is included, so it is fetched from a
+//! beacon node's `blob_sidecars` endpoint for the slot of the transaction's block and matched
+//! to the transaction by versioned hash.
+//!
+//! Blobs are filled the way `cast send --blob` and `eip4844` fill them (alloy's `SimpleCoder`):
+//! every 32-byte field element keeps its top byte zero, the first one carries the payload
+//! length as a big-endian `u64` in bytes `1..9`, and the following ones 31 payload bytes each,
+//! continuing across blobs.
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::cli::{decode_hex, hex_prefixed};
+use crate::ipfs::read_response;
+use crate::retry::{RetryPolicy, retry};
+
+/// Environment variable naming the beacon node API, e.g. `http://127.0.0.1:5052`.
+pub const BEACON_API_ENV: &str = "BEACON_API_URL";
+
+pub const BYTES_PER_BLOB: usize = 131_072;
+
+pub const KZG_COMMITMENT_LEN: usize = 48;
+
+const FIELD_ELEMENT_BYTES: usize = 32;
+
+/// Payload bytes per field element; the top byte stays zero to keep it below the modulus.
+const FIELD_ELEMENT_DATA: usize = 31;
+
+/// Upper bound on one decoded payload, as `SimpleCoder` enforces.
+const MAX_PAYLOAD_BYTES: usize = 2 * 1024 * 1024;
+
+/// Upper bound on a beacon response; a slot carries at most a few MiB of blobs.
+const MAX_RESPONSE_BYTES: u64 = 64 << 20;
+
+const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Versioned hash of a KZG commitment: `0x01 || sha256(commitment)[1..]`, what `blobhash(i)`
+/// returns and what `blobHashGC` commits to.
+pub fn kzg_to_versioned_hash(commitment: &[u8; KZG_COMMITMENT_LEN]) -> [u8; 32] {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    hash
+}
+
+/// Decodes the payload `SimpleCoder` packed into `blobs`, in transaction order.
+pub fn decode_blob_payload(blobs: &[Vec<u8>]) -> Result<Vec<u8>, String> {
+    if let Some(idx) = blobs.iter().position(|blob| blob.len() != BYTES_PER_BLOB) {
+        return Err(format!(
+            "blob {idx} has {} bytes, expected {BYTES_PER_BLOB}",
+            blobs[idx].len()
+        ));
+    }
+    let mut elements = blobs
+        .iter()
+        .flat_map(|blob| blob.chunks(FIELD_ELEMENT_BYTES))
+        .enumerate();
+    let mut next = || {
+        let (idx, element) = elements
+            .next()
+            .ok_or("blob data ends before the payload does")?;
+        if element[0] != 0 {
+            return Err(format!("field element {idx} is not SimpleCoder-encoded"));
+        }
+        Ok(element)
+    };
+
+    let header = next()?;
+    let len = u64::from_be_bytes(header[1..9].try_into().expect("8 bytes")) as usize;
+    if len == 0 {
+        return Err("blob data carries no payload".to_string());
+    }
+    if len > MAX_PAYLOAD_BYTES {
+        return Err(format!(
+            "blob payload length {len} exceeds {MAX_PAYLOAD_BYTES}"
+        ));
+    }
+    let mut payload = Vec::with_capacity(len);
+    while payload.len() < len {
+        let take = FIELD_ELEMENT_DATA.min(len - payload.len());
+        payload.extend_from_slice(&next()?[1..1 + take]);
+    }
+    Ok(payload)
+}
+
+/// Reads a blob file: raw blobs back to back, or one `0x`-prefixed hex blob per line as block
+/// explorers show them.
+pub fn parse_blob_file(bytes: &[u8]) -> Result<Vec<Vec<u8>>, String> {
+    let text = std::str::from_utf8(bytes)
+        .ok()
+        .filter(|text| text.trim_start().starts_with("0x"));
+    let blobs = match text {
+        Some(text) => text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(|line| decode_hex(line).map_err(|e| format!("invalid blob hex: {e}")))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => bytes.chunks(BYTES_PER_BLOB).map(<[u8]>::to_vec).collect(),
+    };
+    if blobs.is_empty() {
+        return Err("blob file is empty".to_string());
+    }
+    if let Some(idx) = blobs.iter().position(|blob| blob.len() != BYTES_PER_BLOB) {
+        return Err(format!(
+            "blob {idx} has {} bytes, expected {BYTES_PER_BLOB}",
+            blobs[idx].len()
+        ));
+    }
+    Ok(blobs)
+}
+
+/// What a blob transaction tells about where its blobs are: the block it was included in and
+/// the versioned hashes of its blobs, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobTx {
+    pub block_number: u64,
+    pub versioned_hashes: Vec<[u8; 32]>,
+}
+
+impl BlobTx {
+    /// Reads the output of `cast tx <hash> --json`.
+    pub fn from_cast_json(text: &str) -> Result<Self, String> {
+        let tx: serde_json::Value =
+            serde_json::from_str(text).map_err(|e| format!("invalid transaction JSON: {e}"))?;
+        let block_number = match &tx["blockNumber"] {
+            serde_json::Value::String(raw) => match raw.strip_prefix("0x") {
+                Some(hex) => u64::from_str_radix(hex, 16).ok(),
+                None => raw.parse().ok(),
+            },
+            value => value.as_u64(),
+        }
+        .ok_or("transaction is not mined yet (no blockNumber)")?;
+        let versioned_hashes = tx["blobVersionedHashes"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .map(|hash| {
+                let bytes = hash
+                    .as_str()
+                    .map(decode_hex)
+                    .and_then(Result::ok)
+                    .ok_or_else(|| format!("invalid blob versioned hash {hash}"))?;
+                bytes
+                    .try_into()
+                    .map_err(|_| format!("invalid blob versioned hash {hash}"))
+            })
+            .collect::<Result<Vec<[u8; 32]>, String>>()?;
+        if versioned_hashes.is_empty() {
+            return Err("transaction carries no blobs".to_string());
+        }
+        Ok(Self {
+            block_number,
+            versioned_hashes,
+        })
+    }
+}
+
+/// One blob of a beacon block with the commitment it was included under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlobSidecar {
+    pub index: u64,
+    pub blob: Vec<u8>,
+    pub kzg_commitment: [u8; KZG_COMMITMENT_LEN],
+}
+
+impl BlobSidecar {
+    pub fn versioned_hash(&self) -> [u8; 32] {
+        kzg_to_versioned_hash(&self.kzg_commitment)
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    data: T,
+}
+
+#[derive(Deserialize)]
+struct Genesis {
+    genesis_time: String,
+}
+
+#[derive(Deserialize)]
+struct Spec {
+    #[serde(rename = "SECONDS_PER_SLOT")]
+    seconds_per_slot: String,
+}
+
+#[derive(Deserialize)]
+struct SidecarJson {
+    index: String,
+    blob: String,
+    kzg_commitment: String,
+}
+
+/// Client for the blob endpoints of a beacon node's REST API.
+#[derive(Debug, Clone)]
+pub struct BeaconClient {
+    url: String,
+    policy: RetryPolicy,
+    timeout: Duration,
+}
+
+impl BeaconClient {
+    pub fn new(url: &str, policy: RetryPolicy) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            policy,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// The node at `BEACON_API_URL` under `RetryPolicy::from_env`.
+    pub fn from_env() -> Result<Self, String> {
+        let url = std::env::var(BEACON_API_ENV)
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .ok_or_else(|| format!("{BEACON_API_ENV} must name a beacon node to fetch blobs"))?;
+        Ok(Self::new(&url, RetryPolicy::from_env()?))
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn get<T: for<'de> Deserialize<'de>>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}{path}", self.url);
+        let agent = ureq::AgentBuilder::new().timeout(self.timeout).build();
+        let body = retry(&self.policy, |_| {
+            read_response(&url, agent.get(&url).call(), MAX_RESPONSE_BYTES)
+        })?;
+        let response: Response<T> = serde_json::from_slice(&body)
+            .map_err(|e| format!("{url} returned an unexpected response: {e}"))?;
+        Ok(response.data)
+    }
+
+    /// Slot whose block has timestamp `timestamp`.
+    pub fn slot_at(&self, timestamp: u64) -> Result<u64, String> {
+        let genesis: Genesis = self.get("/eth/v1/beacon/genesis")?;
+        let spec: Spec = self.get("/eth/v1/config/spec")?;
+        let parse = |value: &str, name: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("invalid {name} from {}: {value}", self.url))
+        };
+        let genesis_time = parse(&genesis.genesis_time, "genesis_time")?;
+        let seconds_per_slot = parse(&spec.seconds_per_slot, "SECONDS_PER_SLOT")?;
+        if seconds_per_slot == 0 {
+            return Err(format!("{} reports SECONDS_PER_SLOT 0", self.url));
+        }
+        if timestamp < genesis_time {
+            return Err(format!(
+                "timestamp {timestamp} precedes genesis {genesis_time} of {}",
+                self.url
+            ));
+        }
+        Ok((timestamp - genesis_time) / seconds_per_slot)
+    }
+
+    /// Every blob of the block at `slot`.
+    pub fn blob_sidecars(&self, slot: u64) -> Result<Vec<BlobSidecar>, String> {
+        let sidecars: Vec<SidecarJson> =
+            self.get(&format!("/eth/v1/beacon/blob_sidecars/{slot}"))?;
+        sidecars
+            .into_iter()
+            .map(|sidecar| {
+                let invalid = |field: &str| format!("invalid {field} in blob sidecar at {slot}");
+                let commitment =
+                    decode_hex(&sidecar.kzg_commitment).map_err(|_| invalid("kzg_commitment"))?;
+                Ok(BlobSidecar {
+                    index: sidecar.index.parse().map_err(|_| invalid("index"))?,
+                    blob: decode_hex(&sidecar.blob).map_err(|_| invalid("blob"))?,
+                    kzg_commitment: commitment
+                        .try_into()
+                        .map_err(|_| invalid("kzg_commitment"))?,
+                })
+            })
+            .collect()
+    }
+
+    /// Blobs of the block at `slot` with `versioned_hashes`, in that order.
+    pub fn blobs_for(
+        &self,
+        slot: u64,
+        versioned_hashes: &[[u8; 32]],
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let mut sidecars = self.blob_sidecars(slot)?;
+        versioned_hashes
+            .iter()
+            .map(|hash| {
+                let idx = sidecars
+                    .iter()
+                    .position(|sidecar| sidecar.versioned_hash() == *hash)
+                    .ok_or_else(|| {
+                        format!(
+                            "no blob with versioned hash {} at slot {slot}; the node may have \
+                             pruned it",
+                            hex_prefixed(hash)
+                        )
+                    })?;
+                Ok(sidecars.swap_remove(idx).blob)
+            })
+            .collect()
+    }
+}
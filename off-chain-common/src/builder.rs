@@ -0,0 +1,274 @@
+use crate::types::{CircuitLayout, ConstantWire, GateDesc, GateSet, GateType};
+
+/// Named group of output wires registered on a `CircuitBuilder` (little-endian for words).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NamedOutput {
+    pub name: String,
+    pub wires: Vec<u16>,
+}
+
+/// Incremental gate-list builder for custom circuits.
+///
+/// Wires are allocated densely: every `input*` call and every appended gate takes the next
+/// free wire id, so allocating all inputs first keeps the repo's input convention
+/// (inputs occupy `[0 .. input_count-1]`). With the default `GateSet::V1`, composite gadgets
+/// (`or`, `nand`, `nor`, `mux`, `swap`, `compare`) expand into AND/XOR/NOT gates only;
+/// `GateSet::V2` emits native `OR`/`NAND`/`NOR` gates instead. Both can be disputed on-chain.
+#[derive(Debug, Clone, Default)]
+pub struct CircuitBuilder {
+    circuit_id: [u8; 32],
+    gate_set: GateSet,
+    gates: Vec<GateDesc>,
+    constant_wires: Vec<ConstantWire>,
+    next_wire: u16,
+    outputs: Vec<NamedOutput>,
+}
+
+impl CircuitBuilder {
+    /// Empty builder with an all-zero circuit id.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the circuit id stamped on the finished layout.
+    pub fn with_circuit_id(mut self, circuit_id: [u8; 32]) -> Self {
+        self.circuit_id = circuit_id;
+        self
+    }
+
+    /// Selects the gate set used by composite gadgets (default `GateSet::V1`).
+    pub fn with_gate_set(mut self, gate_set: GateSet) -> Self {
+        self.gate_set = gate_set;
+        self
+    }
+
+    fn alloc_wire(&mut self) -> u16 {
+        let wire = self.next_wire;
+        self.next_wire = self
+            .next_wire
+            .checked_add(1)
+            .expect("circuit exceeds u16 wire space");
+        wire
+    }
+
+    /// Allocates one input wire.
+    pub fn input(&mut self) -> u16 {
+        self.alloc_wire()
+    }
+
+    /// Allocates `count` consecutive input wires (e.g. the little-endian bits of one value).
+    pub fn inputs(&mut self, count: usize) -> Vec<u16> {
+        (0..count).map(|_| self.alloc_wire()).collect()
+    }
+
+    /// Allocates a wire fixed to the public constant `value`.
+    /// Allocate constants after the inputs to keep inputs at `[0 .. input_count-1]`.
+    pub fn constant(&mut self, value: bool) -> u16 {
+        let wire = self.alloc_wire();
+        self.constant_wires.push(ConstantWire {
+            wire,
+            value: u8::from(value),
+        });
+        wire
+    }
+
+    /// Appends one gate writing into a fresh wire and returns that wire.
+    /// Panics if `gate_type` is outside the builder's gate set.
+    pub fn gate(&mut self, gate_type: GateType, a: u16, b: u16) -> u16 {
+        assert!(
+            self.gate_set.allows(gate_type),
+            "{gate_type:?} is not in gate set {:?}",
+            self.gate_set
+        );
+        let out = self.alloc_wire();
+        self.gates.push(GateDesc::new(gate_type, a, b, out));
+        out
+    }
+
+    /// Appends an XOR gate.
+    pub fn xor(&mut self, a: u16, b: u16) -> u16 {
+        self.gate(GateType::Xor, a, b)
+    }
+
+    /// Appends an AND gate.
+    pub fn and(&mut self, a: u16, b: u16) -> u16 {
+        self.gate(GateType::And, a, b)
+    }
+
+    /// Appends a NOT gate; `NOT` gates carry `wire_b = 0` in the canonical encoding.
+    pub fn not(&mut self, a: u16) -> u16 {
+        self.gate(GateType::Not, a, 0)
+    }
+
+    /// OR: native gate in `GateSet::V2`, otherwise `(a XOR b) XOR (a AND b)`.
+    pub fn or(&mut self, a: u16, b: u16) -> u16 {
+        if self.gate_set.allows(GateType::Or) {
+            return self.gate(GateType::Or, a, b);
+        }
+        let xor_ab = self.xor(a, b);
+        let and_ab = self.and(a, b);
+        self.xor(xor_ab, and_ab)
+    }
+
+    /// NAND: native gate in `GateSet::V2`, otherwise `NOT(a AND b)`.
+    pub fn nand(&mut self, a: u16, b: u16) -> u16 {
+        if self.gate_set.allows(GateType::Nand) {
+            return self.gate(GateType::Nand, a, b);
+        }
+        let and_ab = self.and(a, b);
+        self.not(and_ab)
+    }
+
+    /// NOR: native gate in `GateSet::V2`, otherwise `NOT(a OR b)`.
+    pub fn nor(&mut self, a: u16, b: u16) -> u16 {
+        if self.gate_set.allows(GateType::Nor) {
+            return self.gate(GateType::Nor, a, b);
+        }
+        let or_ab = self.or(a, b);
+        self.not(or_ab)
+    }
+
+    /// One-bit MUX: returns `sel ? a : b` as `b XOR (sel AND (a XOR b))`.
+    pub fn mux_bit(&mut self, sel: u16, a: u16, b: u16) -> u16 {
+        let xor_ab = self.xor(a, b);
+        let masked = self.and(sel, xor_ab);
+        self.xor(b, masked)
+    }
+
+    /// Word-level MUX: selects `a_bits` when `sel` is 1, otherwise `b_bits`.
+    /// Costs 2 XOR + 1 AND per bit.
+    pub fn mux(&mut self, sel: u16, a_bits: &[u16], b_bits: &[u16]) -> Vec<u16> {
+        assert_eq!(
+            a_bits.len(),
+            b_bits.len(),
+            "mux operands must have equal width"
+        );
+        a_bits
+            .iter()
+            .zip(b_bits)
+            .map(|(a, b)| self.mux_bit(sel, *a, *b))
+            .collect()
+    }
+
+    /// Conditional swap: returns `(sel ? a : b, sel ? b : a)`.
+    /// Both outputs share one masked difference, costing 3 XOR + 1 AND per bit.
+    pub fn swap(&mut self, sel: u16, a_bits: &[u16], b_bits: &[u16]) -> (Vec<u16>, Vec<u16>) {
+        assert_eq!(
+            a_bits.len(),
+            b_bits.len(),
+            "swap operands must have equal width"
+        );
+        a_bits
+            .iter()
+            .zip(b_bits)
+            .map(|(a, b)| {
+                let xor_ab = self.xor(*a, *b);
+                let masked = self.and(sel, xor_ab);
+                (self.xor(*b, masked), self.xor(*a, masked))
+            })
+            .unzip()
+    }
+
+    /// Comparator over little-endian bit vectors.
+    /// Returns `(gt, eq)` wires for `a > b` and `a == b`, scanning from MSB to LSB.
+    pub fn compare(&mut self, a_bits: &[u16], b_bits: &[u16]) -> (u16, u16) {
+        assert_eq!(
+            a_bits.len(),
+            b_bits.len(),
+            "comparator operands must have equal width"
+        );
+        assert!(!a_bits.is_empty(), "comparator operands must not be empty");
+
+        // Running accumulators for:
+        // - gt_acc: "A > B already seen at higher bit"
+        // - eq_acc: "A == B for all higher bits"
+        let mut gt_acc: Option<u16> = None;
+        let mut eq_acc: Option<u16> = None;
+
+        // Compare from MSB to LSB.
+        for bit in (0..a_bits.len()).rev() {
+            let a = a_bits[bit];
+            let b = b_bits[bit];
+
+            // eq_bit = !(a XOR b)
+            let xor_ab = self.xor(a, b);
+            let eq_bit = self.not(xor_ab);
+
+            // gt_bit = a AND (!b)
+            let not_b = self.not(b);
+            let gt_bit = self.and(a, not_b);
+
+            match (gt_acc, eq_acc) {
+                (None, None) => {
+                    // Highest bit initializes accumulators.
+                    gt_acc = Some(gt_bit);
+                    eq_acc = Some(eq_bit);
+                }
+                (Some(gt_prev), Some(eq_prev)) => {
+                    // gt_new = gt_prev OR (eq_prev AND gt_bit)
+                    let eq_and_gt = self.and(eq_prev, gt_bit);
+                    let gt_new = self.or(gt_prev, eq_and_gt);
+                    // eq_new = eq_prev AND eq_bit
+                    let eq_new = self.and(eq_prev, eq_bit);
+                    gt_acc = Some(gt_new);
+                    eq_acc = Some(eq_new);
+                }
+                _ => unreachable!("accumulators must progress together"),
+            }
+        }
+
+        match (gt_acc, eq_acc) {
+            (Some(gt), Some(eq)) => (gt, eq),
+            _ => unreachable!("non-empty operands always produce accumulators"),
+        }
+    }
+
+    /// Registers a single-wire output under `name` (replacing an existing one).
+    pub fn output(&mut self, name: &str, wire: u16) {
+        self.output_word(name, &[wire]);
+    }
+
+    /// Registers a multi-wire output under `name` (replacing an existing one).
+    pub fn output_word(&mut self, name: &str, wires: &[u16]) {
+        self.outputs.retain(|output| output.name != name);
+        self.outputs.push(NamedOutput {
+            name: name.to_string(),
+            wires: wires.to_vec(),
+        });
+    }
+
+    /// Wires registered under `name`, if any.
+    pub fn output_wires(&self, name: &str) -> Option<&[u16]> {
+        self.outputs
+            .iter()
+            .find(|output| output.name == name)
+            .map(|output| output.wires.as_slice())
+    }
+
+    /// Named outputs in registration order.
+    pub fn outputs(&self) -> &[NamedOutput] {
+        &self.outputs
+    }
+
+    /// Gates appended so far.
+    pub fn gates(&self) -> &[GateDesc] {
+        &self.gates
+    }
+
+    /// Next wire id that would be allocated.
+    pub fn next_wire(&self) -> u16 {
+        self.next_wire
+    }
+
+    /// Finishes the circuit as instance `0`; set `instance_id` per cut-and-choose instance.
+    pub fn finish(self) -> CircuitLayout {
+        self.finish_with_outputs().0
+    }
+
+    /// Like `finish`, also returning the named outputs.
+    pub fn finish_with_outputs(self) -> (CircuitLayout, Vec<NamedOutput>) {
+        let layout = CircuitLayout::new(self.circuit_id, 0, self.gates)
+            .with_constant_wires(self.constant_wires);
+        (layout, self.outputs)
+    }
+}
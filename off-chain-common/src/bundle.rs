@@ -0,0 +1,288 @@
+//! Single-file session archives (`session.ppa`): every instance artifact and the eval package
+//! in one file, with a manifest of per-file checksums.
+//!
+//! Layout: the 8-byte magic `PPABNDL1`, a big-endian `u32` manifest length, the manifest, then
+//! the file contents back to back in manifest order. The manifest is text, one line per file:
+//! `<length> <keccak256> <name>`, names sorted. A name is a `/`-separated path of
+//! `transport::check_file_name` components (e.g. `eval/eval-package.cbor`); `.zst` files are
+//! stored as they are, so `compression` still applies inside a bundle.
+//!
+//! Readers never need to unpack: `Bundle::open` only parses the manifest, `Bundle::read`
+//! seeks to one file and checks its digest, and `compression::read_artifact` resolves a path
+//! that runs through a bundle file (`session.ppa/eval/eval-meta.json`) to the entry inside it,
+//! so `--eval-dir session.ppa/eval` works wherever a directory does.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+
+use crate::cli::{decode_hex, hex_prefixed};
+use crate::consensus::keccak256;
+use crate::transport::{check_file_name, list_dir_files};
+
+/// First bytes of every bundle.
+pub const BUNDLE_MAGIC: [u8; 8] = *b"PPABNDL1";
+
+/// Checks a bundle entry name: `/`-separated transport file names.
+pub fn check_entry_name(name: &str) -> Result<(), String> {
+    name.split('/')
+        .try_for_each(check_file_name)
+        .map_err(|_| format!("invalid bundle entry name '{name}'"))
+}
+
+/// Files collected for a new bundle, keyed by entry name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleBuilder {
+    files: BTreeMap<String, Vec<u8>>,
+}
+
+impl BundleBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, name: &str, bytes: Vec<u8>) -> Result<(), String> {
+        check_entry_name(name)?;
+        if self.files.insert(name.to_string(), bytes).is_some() {
+            return Err(format!("duplicate bundle entry '{name}'"));
+        }
+        Ok(())
+    }
+
+    /// Adds every file of `dir` (not recursing), named `<prefix>/<file>` or just `<file>`
+    /// for an empty prefix. Returns the number of files added.
+    pub fn add_dir(&mut self, prefix: &str, dir: &Path) -> Result<usize, String> {
+        let names = list_dir_files(dir)?;
+        for name in &names {
+            let path = dir.join(name);
+            let bytes =
+                fs::read(&path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+            let entry = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{prefix}/{name}")
+            };
+            self.add(&entry, bytes)?;
+        }
+        Ok(names.len())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    pub fn manifest(&self) -> String {
+        self.files
+            .iter()
+            .map(|(name, bytes)| {
+                format!(
+                    "{} {} {name}\n",
+                    bytes.len(),
+                    hex_prefixed(&keccak256(&[bytes]))
+                )
+            })
+            .collect()
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        let manifest = self.manifest();
+        let manifest_len = u32::try_from(manifest.len())
+            .map_err(|_| "bundle manifest exceeds 4 GiB".to_string())?;
+        let mut out = Vec::with_capacity(
+            12 + manifest.len() + self.files.values().map(Vec::len).sum::<usize>(),
+        );
+        out.extend_from_slice(&BUNDLE_MAGIC);
+        out.extend_from_slice(&manifest_len.to_be_bytes());
+        out.extend_from_slice(manifest.as_bytes());
+        for bytes in self.files.values() {
+            out.extend_from_slice(bytes);
+        }
+        Ok(out)
+    }
+
+    /// Writes the bundle to `path` and returns its manifest hash.
+    pub fn write(&self, path: &Path) -> Result<[u8; 32], String> {
+        let encoded = self.encode()?;
+        fs::write(path, &encoded)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        Ok(keccak256(&[self.manifest().as_bytes()]))
+    }
+}
+
+/// One manifest line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BundleEntry {
+    pub name: String,
+    pub length: u64,
+    /// `keccak256` of the stored bytes.
+    pub digest: [u8; 32],
+    /// Position of the contents from the start of the file.
+    pub offset: u64,
+}
+
+/// An opened bundle; contents are read on demand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bundle {
+    path: PathBuf,
+    manifest: String,
+    entries: Vec<BundleEntry>,
+}
+
+impl Bundle {
+    /// Reads and checks the header and manifest of the bundle at `path`.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let at = |e: String| format!("bundle {}: {e}", path.display());
+        let mut file =
+            File::open(path).map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+        let file_len = file.metadata().map_err(|e| at(e.to_string()))?.len();
+        let mut header = [0u8; 12];
+        file.read_exact(&mut header)
+            .map_err(|_| at("truncated header".to_string()))?;
+        if header[..8] != BUNDLE_MAGIC {
+            return Err(at("not a session bundle".to_string()));
+        }
+        let manifest_len = u32::from_be_bytes(header[8..].try_into().expect("4 bytes")) as u64;
+        if 12 + manifest_len > file_len {
+            return Err(at("truncated manifest".to_string()));
+        }
+        let mut manifest = vec![0u8; manifest_len as usize];
+        file.read_exact(&mut manifest)
+            .map_err(|_| at("truncated manifest".to_string()))?;
+        let manifest =
+            String::from_utf8(manifest).map_err(|_| at("manifest is not UTF-8".to_string()))?;
+
+        let mut entries: Vec<BundleEntry> = Vec::new();
+        let mut offset = 12 + manifest_len;
+        for (line_no, line) in manifest.lines().enumerate() {
+            let entry = parse_manifest_line(line, offset)
+                .map_err(|e| at(format!("manifest line {}: {e}", line_no + 1)))?;
+            if entries.last().is_some_and(|last| last.name >= entry.name) {
+                return Err(at(format!(
+                    "manifest line {}: '{}' is out of order",
+                    line_no + 1,
+                    entry.name
+                )));
+            }
+            offset = offset
+                .checked_add(entry.length)
+                .ok_or_else(|| at("entry lengths overflow".to_string()))?;
+            entries.push(entry);
+        }
+        if offset != file_len {
+            return Err(at(format!(
+                "manifest covers {offset} bytes, file has {file_len}"
+            )));
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            manifest,
+            entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[BundleEntry] {
+        &self.entries
+    }
+
+    pub fn entry(&self, name: &str) -> Option<&BundleEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.name.as_str().cmp(name))
+            .ok()
+            .map(|idx| &self.entries[idx])
+    }
+
+    /// `keccak256` of the manifest text, which commits to every entry.
+    pub fn manifest_hash(&self) -> [u8; 32] {
+        keccak256(&[self.manifest.as_bytes()])
+    }
+
+    /// Reads entry `name` and checks it against its manifest digest.
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, String> {
+        let entry = self
+            .entry(name)
+            .ok_or_else(|| format!("bundle {} has no entry '{name}'", self.path.display()))?;
+        let at = |e: String| format!("bundle {} entry '{name}': {e}", self.path.display());
+        let mut file = File::open(&self.path)
+            .map_err(|e| format!("failed to open {}: {e}", self.path.display()))?;
+        file.seek(SeekFrom::Start(entry.offset))
+            .map_err(|e| at(e.to_string()))?;
+        let mut bytes = vec![0u8; entry.length as usize];
+        file.read_exact(&mut bytes).map_err(|e| at(e.to_string()))?;
+        if keccak256(&[&bytes]) != entry.digest {
+            return Err(at("contents do not match the manifest digest".to_string()));
+        }
+        Ok(bytes)
+    }
+
+    /// Reads every entry, failing on the first digest mismatch.
+    pub fn verify(&self) -> Result<(), String> {
+        self.entries
+            .iter()
+            .try_for_each(|entry| self.read(&entry.name).map(drop))
+    }
+
+    /// Writes every entry under `dir`, creating subdirectories for `/` in names.
+    pub fn extract(&self, dir: &Path) -> Result<usize, String> {
+        for entry in &self.entries {
+            let path = dir.join(&entry.name);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("failed to create {}: {e}", parent.display()))?;
+            }
+            fs::write(&path, self.read(&entry.name)?)
+                .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        }
+        Ok(self.entries.len())
+    }
+}
+
+fn parse_manifest_line(line: &str, offset: u64) -> Result<BundleEntry, String> {
+    let mut parts = line.splitn(3, ' ');
+    let (Some(length), Some(digest), Some(name)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Err("expected `<length> <keccak256> <name>`".to_string());
+    };
+    check_entry_name(name)?;
+    let length = length
+        .parse::<u64>()
+        .map_err(|_| format!("invalid length '{length}'"))?;
+    let digest = decode_hex(digest)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| format!("expected a 32-byte digest: {digest}"))?;
+    Ok(BundleEntry {
+        name: name.to_string(),
+        length,
+        digest,
+        offset,
+    })
+}
+
+/// Splits a path that runs through a bundle file (`session.ppa/eval/eval-meta.json`) into the
+/// bundle path and the entry name. `None` when no ancestor of `path` is a regular file.
+pub fn locate_in_bundle(path: &Path) -> Option<(PathBuf, String)> {
+    for bundle in path.ancestors().skip(1) {
+        if bundle.as_os_str().is_empty() || !bundle.is_file() {
+            continue;
+        }
+        let rest = path.strip_prefix(bundle).ok()?;
+        let mut parts = Vec::new();
+        for component in rest.components() {
+            let Component::Normal(part) = component else {
+                return None;
+            };
+            parts.push(part.to_str()?.to_string());
+        }
+        return Some((bundle.to_path_buf(), parts.join("/")));
+    }
+    None
+}
@@ -0,0 +1,101 @@
+//! Canonical JSON for circuit layouts, NOT-gate hints and instance artifacts, so tooling in
+//! other languages reads the same data as the text dumps without a bespoke parser.
+//!
+//! The encoding is `serde_json` with three rules that make it byte-for-byte reproducible:
+//! object keys are sorted, output is compact (no whitespace), and every hash, label, seed or
+//! leaf is a string of lowercase `0x`-prefixed hex of its exact length. Enums are lowercase
+//! names (`"and"`, `"free_xor"`); wire and instance indices are plain numbers. Decoding is
+//! strict: unknown fields, uppercase or unprefixed hex and wrong lengths are rejected, so
+//! `to_canonical_json(from_canonical_json(s)?)? == s` for any accepted `s`.
+
+use serde::de::{DeserializeOwned, Error as _};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+
+use crate::cli::hex_prefixed;
+
+/// Encodes `value` as canonical JSON (sorted keys, compact).
+pub fn to_canonical_json<T: Serialize>(value: &T) -> Result<String, String> {
+    // `Value` objects are `BTreeMap`s, so a round trip through it sorts every key.
+    let value = serde_json::to_value(value).map_err(|e| format!("failed to encode JSON: {e}"))?;
+    serde_json::to_string(&value).map_err(|e| format!("failed to encode JSON: {e}"))
+}
+
+/// Decodes JSON produced by `to_canonical_json` (key order and whitespace are not checked).
+pub fn from_canonical_json<T: DeserializeOwned>(raw: &str) -> Result<T, String> {
+    serde_json::from_str(raw).map_err(|e| format!("invalid JSON: {e}"))
+}
+
+/// Parses lowercase `0x`-prefixed hex of exactly `N` bytes.
+pub fn parse_canonical_hex<const N: usize>(raw: &str) -> Result<[u8; N], String> {
+    let Some(digits) = raw.strip_prefix("0x") else {
+        return Err(format!("hex must start with 0x: {raw}"));
+    };
+    if digits.len() != 2 * N {
+        return Err(format!(
+            "expected {N} bytes of hex, got {} digits: {raw}",
+            digits.len()
+        ));
+    }
+    let nibble = |c: u8| match c {
+        b'0'..=b'9' => Ok(c - b'0'),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        _ => Err(format!("hex must be lowercase digits: {raw}")),
+    };
+    let mut out = [0u8; N];
+    for (byte, pair) in out.iter_mut().zip(digits.as_bytes().chunks_exact(2)) {
+        *byte = (nibble(pair[0])? << 4) | nibble(pair[1])?;
+    }
+    Ok(out)
+}
+
+/// `#[serde(with = "hex_bytes")]` for `[u8; N]` fields.
+pub mod hex_bytes {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex_prefixed(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<[u8; N], D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        parse_canonical_hex(&raw).map_err(D::Error::custom)
+    }
+}
+
+/// `#[serde(with = "hex_bytes_vec")]` for `Vec<[u8; N]>` fields (leaves, block hashes).
+pub mod hex_bytes_vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        items: &[[u8; N]],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(items.len()))?;
+        for item in items {
+            seq.serialize_element(&hex_prefixed(item))?;
+        }
+        seq.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> Result<Vec<[u8; N]>, D::Error> {
+        Vec::<String>::deserialize(deserializer)?
+            .iter()
+            .map(|raw| parse_canonical_hex(raw).map_err(D::Error::custom))
+            .collect()
+    }
+}
+
+/// Parses `raw` as a JSON value without decoding it into a type, for tools that only
+/// inspect or re-canonicalize a document.
+pub fn canonicalize(raw: &str) -> Result<String, String> {
+    to_canonical_json(&from_canonical_json::<Value>(raw)?)
+}
@@ -0,0 +1,677 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+
+use crate::builder::CircuitBuilder;
+use crate::types::{CircuitLayout, ConstantWire, GateDesc, GateType};
+
+/// Circuit loaded from Bristol Fashion text.
+/// Input/output groups keep their declared widths; wire ids are the file's own ids.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BristolCircuit {
+    /// Gate list as instance `0`; set `instance_id` per cut-and-choose instance.
+    pub layout: CircuitLayout,
+    /// Bit width of each input value (`niv` groups), laid out from wire `0` upwards.
+    pub input_sizes: Vec<usize>,
+    /// Bit width of each output value (`nov` groups).
+    pub output_sizes: Vec<usize>,
+    /// Wires carrying the outputs, in declaration order (after `EQW` aliasing).
+    pub output_wires: Vec<u16>,
+}
+
+fn parse_count(token: Option<&str>, what: &str, line_no: usize) -> Result<usize, String> {
+    let token = token.ok_or_else(|| format!("line {line_no}: missing {what}"))?;
+    token
+        .parse::<usize>()
+        .map_err(|e| format!("line {line_no}: invalid {what} '{token}': {e}"))
+}
+
+fn parse_wire(token: &str, num_wires: usize, line_no: usize) -> Result<u16, String> {
+    let wire = token
+        .parse::<usize>()
+        .map_err(|e| format!("line {line_no}: invalid wire '{token}': {e}"))?;
+    if wire >= num_wires {
+        return Err(format!(
+            "line {line_no}: wire {wire} out of range (num_wires={num_wires})"
+        ));
+    }
+    u16::try_from(wire).map_err(|_| format!("line {line_no}: wire {wire} does not fit in u16"))
+}
+
+/// Parses a `niv n_1 .. n_niv` (or `nov ...`) header line into group widths.
+fn parse_group_line(line: &str, what: &str, line_no: usize) -> Result<Vec<usize>, String> {
+    let mut tokens = line.split_whitespace();
+    let count = parse_count(tokens.next(), what, line_no)?;
+    let sizes = tokens
+        .map(|token| parse_count(Some(token), what, line_no))
+        .collect::<Result<Vec<_>, _>>()?;
+    if sizes.len() != count {
+        return Err(format!(
+            "line {line_no}: expected {count} {what} sizes, got {}",
+            sizes.len()
+        ));
+    }
+    Ok(sizes)
+}
+
+/// Parses a Bristol Fashion circuit (as published in the MPC circuit zoo).
+///
+/// Supported gates: `AND`, `XOR`, `INV` (mapped to `GateType::Not`), `MAND` (expanded into
+/// one `AND` per output) and `EQW` (wire copy, resolved by aliasing later reads).
+/// Constant assignment (`EQ`) is rejected. Every wire id must fit in `u16`, which excludes
+/// the largest zoo circuits (e.g. SHA-256).
+pub fn parse_bristol(text: &str, circuit_id: [u8; 32]) -> Result<BristolCircuit, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| (idx + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty());
+
+    let (line_no, header) = lines.next().ok_or("empty Bristol circuit")?;
+    let mut header_tokens = header.split_whitespace();
+    let num_gates = parse_count(header_tokens.next(), "gate count", line_no)?;
+    let num_wires = parse_count(header_tokens.next(), "wire count", line_no)?;
+    if num_wires > u16::MAX as usize + 1 {
+        return Err(format!(
+            "circuit has {num_wires} wires; at most {} are supported",
+            u16::MAX as usize + 1
+        ));
+    }
+
+    let (line_no, inputs_line) = lines.next().ok_or("missing input header line")?;
+    let input_sizes = parse_group_line(inputs_line, "input", line_no)?;
+    let (line_no, outputs_line) = lines.next().ok_or("missing output header line")?;
+    let output_sizes = parse_group_line(outputs_line, "output", line_no)?;
+
+    // EQW copies are resolved by pointing the destination at the source wire.
+    let mut aliases: HashMap<u16, u16> = HashMap::new();
+    let resolve = |aliases: &HashMap<u16, u16>, wire: u16| *aliases.get(&wire).unwrap_or(&wire);
+
+    let mut gates = Vec::with_capacity(num_gates);
+    let mut parsed_gates = 0usize;
+    for (line_no, line) in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        if tokens.len() < 3 {
+            return Err(format!("line {line_no}: malformed gate '{line}'"));
+        }
+        let n_in = parse_count(Some(tokens[0]), "gate input count", line_no)?;
+        let n_out = parse_count(Some(tokens[1]), "gate output count", line_no)?;
+        if tokens.len() != 2 + n_in + n_out + 1 {
+            return Err(format!(
+                "line {line_no}: expected {} tokens for {n_in} inputs and {n_out} outputs",
+                2 + n_in + n_out + 1
+            ));
+        }
+        let wires = tokens[2..2 + n_in + n_out]
+            .iter()
+            .map(|token| parse_wire(token, num_wires, line_no))
+            .collect::<Result<Vec<u16>, _>>()?;
+        let (ins, outs) = wires.split_at(n_in);
+        let op = tokens[tokens.len() - 1];
+
+        match (op, n_in, n_out) {
+            ("AND", 2, 1) | ("XOR", 2, 1) => {
+                let gate_type = if op == "AND" {
+                    GateType::And
+                } else {
+                    GateType::Xor
+                };
+                let a = resolve(&aliases, ins[0]);
+                let b = resolve(&aliases, ins[1]);
+                gates.push(GateDesc::new(gate_type, a, b, outs[0]));
+            }
+            ("INV", 1, 1) => {
+                let a = resolve(&aliases, ins[0]);
+                gates.push(GateDesc::new(GateType::Not, a, 0, outs[0]));
+            }
+            ("MAND", _, _) if n_in == 2 * n_out => {
+                for idx in 0..n_out {
+                    let a = resolve(&aliases, ins[idx]);
+                    let b = resolve(&aliases, ins[n_out + idx]);
+                    gates.push(GateDesc::new(GateType::And, a, b, outs[idx]));
+                }
+            }
+            ("EQW", 1, 1) => {
+                let source = resolve(&aliases, ins[0]);
+                aliases.insert(outs[0], source);
+            }
+            ("EQ", 1, 1) => {
+                return Err(format!(
+                    "line {line_no}: constant assignment (EQ) is not supported"
+                ));
+            }
+            _ => {
+                return Err(format!(
+                    "line {line_no}: unsupported gate '{op}' with {n_in} inputs and {n_out} outputs"
+                ));
+            }
+        }
+        parsed_gates += 1;
+    }
+
+    if parsed_gates != num_gates {
+        return Err(format!(
+            "header declares {num_gates} gates, found {parsed_gates}"
+        ));
+    }
+
+    // Bristol Fashion places outputs on the last wires.
+    let total_outputs: usize = output_sizes.iter().sum();
+    if total_outputs > num_wires {
+        return Err(format!(
+            "output widths ({total_outputs}) exceed wire count ({num_wires})"
+        ));
+    }
+    let output_wires = (num_wires - total_outputs..num_wires)
+        .map(|wire| resolve(&aliases, wire as u16))
+        .collect();
+
+    Ok(BristolCircuit {
+        layout: CircuitLayout::new(circuit_id, 0, gates),
+        input_sizes,
+        output_sizes,
+        output_wires,
+    })
+}
+
+/// Reads and parses a Bristol Fashion `.txt` file.
+pub fn load_bristol_file(path: &Path, circuit_id: [u8; 32]) -> Result<BristolCircuit, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    parse_bristol(&text, circuit_id)
+}
+
+/// Serializes a layout to Bristol Fashion text.
+///
+/// Input wires are the wires read but never written by a gate, taken in ascending order and
+/// split into `input_sizes` groups. Wires are renumbered densely (inputs, then gate outputs in
+/// gate order) and every output is copied onto the trailing wires with `EQW`, as the format
+/// requires outputs to occupy the last wire ids.
+pub fn export_bristol(
+    layout: &CircuitLayout,
+    input_sizes: &[usize],
+    output_sizes: &[usize],
+    output_wires: &[u16],
+) -> Result<String, String> {
+    if !layout.constant_wires.is_empty() {
+        return Err("Bristol Fashion has no constant wires; feed them as inputs".to_string());
+    }
+    let total_outputs: usize = output_sizes.iter().sum();
+    if total_outputs != output_wires.len() {
+        return Err(format!(
+            "output widths sum to {total_outputs} but {} output wires were given",
+            output_wires.len()
+        ));
+    }
+
+    let written: BTreeSet<u16> = layout.gates.iter().map(|gate| gate.wire_c).collect();
+    if written.len() != layout.gates.len() {
+        return Err("layout writes some wire more than once".to_string());
+    }
+    let mut read = BTreeSet::new();
+    for gate in &layout.gates {
+        read.insert(gate.wire_a);
+        if !gate.gate_type.is_unary() {
+            read.insert(gate.wire_b);
+        }
+    }
+    let input_wires: Vec<u16> = read.difference(&written).copied().collect();
+    let total_inputs: usize = input_sizes.iter().sum();
+    if total_inputs != input_wires.len() {
+        return Err(format!(
+            "input widths sum to {total_inputs} but layout reads {} input wires",
+            input_wires.len()
+        ));
+    }
+
+    // Dense renumbering: inputs first, then gate outputs in gate order.
+    let mut renumbered: HashMap<u16, usize> = HashMap::new();
+    for (idx, wire) in input_wires.iter().enumerate() {
+        renumbered.insert(*wire, idx);
+    }
+    for (idx, gate) in layout.gates.iter().enumerate() {
+        renumbered.insert(gate.wire_c, total_inputs + idx);
+    }
+    let lookup = |wire: u16| {
+        renumbered
+            .get(&wire)
+            .copied()
+            .ok_or_else(|| format!("output wire {wire} is neither an input nor a gate output"))
+    };
+
+    let body_wires = total_inputs + layout.gates.len();
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{} {}",
+        layout.gates.len() + output_wires.len(),
+        body_wires + output_wires.len()
+    );
+    let _ = writeln!(out, "{} {}", input_sizes.len(), join_sizes(input_sizes));
+    let _ = writeln!(out, "{} {}", output_sizes.len(), join_sizes(output_sizes));
+    out.push('\n');
+
+    for gate in &layout.gates {
+        let a = renumbered[&gate.wire_a];
+        let c = renumbered[&gate.wire_c];
+        match gate.gate_type {
+            GateType::And => {
+                let _ = writeln!(out, "2 1 {a} {} {c} AND", renumbered[&gate.wire_b]);
+            }
+            GateType::Xor => {
+                let _ = writeln!(out, "2 1 {a} {} {c} XOR", renumbered[&gate.wire_b]);
+            }
+            GateType::Not => {
+                let _ = writeln!(out, "1 1 {a} {c} INV");
+            }
+            GateType::Or | GateType::Nand | GateType::Nor => {
+                return Err(format!(
+                    "{:?} gates have no Bristol Fashion encoding; build with GateSet::V1",
+                    gate.gate_type
+                ));
+            }
+        }
+    }
+    for (idx, wire) in output_wires.iter().enumerate() {
+        let _ = writeln!(out, "1 1 {} {} EQW", lookup(*wire)?, body_wires + idx);
+    }
+
+    Ok(out)
+}
+
+fn join_sizes(sizes: &[usize]) -> String {
+    sizes
+        .iter()
+        .map(|size| size.to_string())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl BristolCircuit {
+    /// Serializes this circuit back to Bristol Fashion (see `export_bristol`).
+    pub fn to_bristol(&self) -> Result<String, String> {
+        export_bristol(
+            &self.layout,
+            &self.input_sizes,
+            &self.output_sizes,
+            &self.output_wires,
+        )
+    }
+}
+
+/// Layout rewritten by `canonicalize_layout`, with the wire renumbering applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalLayout {
+    pub layout: CircuitLayout,
+    /// Original wire id -> canonical wire id.
+    pub wire_map: BTreeMap<u16, u16>,
+}
+
+impl CanonicalLayout {
+    /// Canonical id of an original wire (inputs and gate outputs only).
+    pub fn map_wire(&self, wire: u16) -> Option<u16> {
+        self.wire_map.get(&wire).copied()
+    }
+}
+
+/// Topologically sorts a layout and renumbers its wires deterministically.
+///
+/// Rules (gate-index order is consensus-critical, so they are fixed):
+/// - input wires (read but never written) are renumbered `0..k` in ascending original id;
+/// - gates are emitted with Kahn's algorithm, always taking the ready gate with the lowest
+///   original index, so an already topological layout keeps its gate order;
+/// - gate outputs are renumbered `k, k+1, ..` in emitted order;
+/// - `NOT` gates get `wire_b = 0`;
+/// - constant wires count as inputs and keep their values under the new ids (unread ones
+///   are dropped).
+///
+/// Fails on invalid constant wires, cycles, wires written by more than one gate, or wire ids beyond `u16`.
+/// Applying it to its own output is a no-op.
+pub fn canonicalize_layout(layout: &CircuitLayout) -> Result<CanonicalLayout, String> {
+    layout.check_constant_wires()?;
+    let gates = &layout.gates;
+    let mut writer: HashMap<u16, usize> = HashMap::new();
+    for (idx, gate) in gates.iter().enumerate() {
+        if let Some(prev) = writer.insert(gate.wire_c, idx) {
+            return Err(format!(
+                "wire {} is written by gates {prev} and {idx}",
+                gate.wire_c
+            ));
+        }
+    }
+
+    let gate_inputs = |gate: &GateDesc| -> Vec<u16> {
+        if gate.gate_type == GateType::Not {
+            vec![gate.wire_a]
+        } else {
+            vec![gate.wire_a, gate.wire_b]
+        }
+    };
+
+    // Dependency graph: gate -> gates consuming its output.
+    let mut pending = vec![0usize; gates.len()];
+    let mut consumers: Vec<Vec<usize>> = vec![Vec::new(); gates.len()];
+    let mut input_wires = BTreeSet::new();
+    for (idx, gate) in gates.iter().enumerate() {
+        for wire in gate_inputs(gate) {
+            match writer.get(&wire) {
+                Some(source) => {
+                    pending[idx] += 1;
+                    consumers[*source].push(idx);
+                }
+                None => {
+                    input_wires.insert(wire);
+                }
+            }
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<usize>> = pending
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count == 0)
+        .map(|(idx, _)| Reverse(idx))
+        .collect();
+    let mut order = Vec::with_capacity(gates.len());
+    while let Some(Reverse(idx)) = ready.pop() {
+        order.push(idx);
+        for consumer in &consumers[idx] {
+            pending[*consumer] -= 1;
+            if pending[*consumer] == 0 {
+                ready.push(Reverse(*consumer));
+            }
+        }
+    }
+    if order.len() != gates.len() {
+        return Err(format!(
+            "layout has a cycle: only {} of {} gates can be ordered",
+            order.len(),
+            gates.len()
+        ));
+    }
+
+    if input_wires.len() + gates.len() > u16::MAX as usize + 1 {
+        return Err("canonical layout exceeds u16 wire space".to_string());
+    }
+    let mut wire_map = BTreeMap::new();
+    for (new_id, wire) in input_wires.iter().enumerate() {
+        wire_map.insert(*wire, new_id as u16);
+    }
+    for (position, idx) in order.iter().enumerate() {
+        wire_map.insert(gates[*idx].wire_c, (input_wires.len() + position) as u16);
+    }
+
+    let canonical_gates = order
+        .iter()
+        .map(|idx| {
+            let gate = gates[*idx];
+            let wire_b = if gate.gate_type == GateType::Not {
+                0
+            } else {
+                wire_map[&gate.wire_b]
+            };
+            GateDesc::new(
+                gate.gate_type,
+                wire_map[&gate.wire_a],
+                wire_b,
+                wire_map[&gate.wire_c],
+            )
+        })
+        .collect();
+    let mut constant_wires = layout
+        .constant_wires
+        .iter()
+        .filter_map(|constant| {
+            wire_map.get(&constant.wire).map(|wire| ConstantWire {
+                wire: *wire,
+                value: constant.value,
+            })
+        })
+        .collect::<Vec<_>>();
+    constant_wires.sort_by_key(|constant| constant.wire);
+
+    Ok(CanonicalLayout {
+        layout: CircuitLayout::new(layout.circuit_id, layout.instance_id, canonical_gates)
+            .with_constant_wires(constant_wires)
+            .with_scheme_id(layout.scheme_id()),
+        wire_map,
+    })
+}
+
+/// Boolean expression compiled by `compile_expr_with_inputs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledExpr {
+    /// Gate list as instance `0`.
+    pub layout: CircuitLayout,
+    /// Variable names in input-wire order (`inputs[i]` is wire `i`).
+    pub inputs: Vec<String>,
+    /// Wire carrying the expression result.
+    pub output_wire: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ExprToken {
+    Var(String),
+    Not,
+    And,
+    Xor,
+    Or,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Var(String),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+fn tokenize_expr(source: &str) -> Result<Vec<ExprToken>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some((pos, ch)) = chars.next() {
+        match ch {
+            c if c.is_whitespace() => {}
+            '!' | '~' => tokens.push(ExprToken::Not),
+            '&' => tokens.push(ExprToken::And),
+            '^' => tokens.push(ExprToken::Xor),
+            '|' => tokens.push(ExprToken::Or),
+            '(' => tokens.push(ExprToken::Open),
+            ')' => tokens.push(ExprToken::Close),
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some((_, next)) = chars.peek() {
+                    if next.is_ascii_alphanumeric() || *next == '_' {
+                        name.push(*next);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(ExprToken::Var(name));
+            }
+            other => return Err(format!("unexpected character '{other}' at offset {pos}")),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser; precedence from loosest to tightest: `|`, `^`, `&`, `!`.
+struct ExprParser {
+    tokens: Vec<ExprToken>,
+    pos: usize,
+}
+
+impl ExprParser {
+    fn peek(&self) -> Option<&ExprToken> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, token: &ExprToken) -> bool {
+        if self.peek() == Some(token) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_xor()?;
+        while self.eat(&ExprToken::Or) {
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.parse_xor()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_xor(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_and()?;
+        while self.eat(&ExprToken::Xor) {
+            lhs = Expr::Xor(Box::new(lhs), Box::new(self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.parse_unary()?;
+        while self.eat(&ExprToken::And) {
+            lhs = Expr::And(Box::new(lhs), Box::new(self.parse_unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, String> {
+        if self.eat(&ExprToken::Not) {
+            return Ok(Expr::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat(&ExprToken::Open) {
+            let inner = self.parse_or()?;
+            if !self.eat(&ExprToken::Close) {
+                return Err(format!("expected ')' at token {}", self.pos));
+            }
+            return Ok(inner);
+        }
+        match self.peek().cloned() {
+            Some(ExprToken::Var(name)) => {
+                self.pos += 1;
+                Ok(Expr::Var(name))
+            }
+            Some(other) => Err(format!("unexpected token {other:?} at {}", self.pos)),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn parse_expr(source: &str) -> Result<Expr, String> {
+    let mut parser = ExprParser {
+        tokens: tokenize_expr(source)?,
+        pos: 0,
+    };
+    let expr = parser.parse_or()?;
+    if parser.pos != parser.tokens.len() {
+        return Err(format!("unexpected trailing input at token {}", parser.pos));
+    }
+    Ok(expr)
+}
+
+fn collect_vars(expr: &Expr, out: &mut BTreeSet<(String, u64, String)>) {
+    match expr {
+        Expr::Var(name) => {
+            out.insert(var_sort_key(name));
+        }
+        Expr::Not(inner) => collect_vars(inner, out),
+        Expr::And(a, b) | Expr::Xor(a, b) | Expr::Or(a, b) => {
+            collect_vars(a, out);
+            collect_vars(b, out);
+        }
+    }
+}
+
+/// Natural ordering key: alphabetic prefix, then numeric suffix (`a2 < a10 < b0`).
+fn var_sort_key(name: &str) -> (String, u64, String) {
+    let digits_at = name
+        .rfind(|c: char| !c.is_ascii_digit())
+        .map_or(0, |idx| idx + 1);
+    let (prefix, digits) = name.split_at(digits_at);
+    let index = digits.parse::<u64>().unwrap_or(0);
+    (prefix.to_string(), index, name.to_string())
+}
+
+fn emit_expr(builder: &mut CircuitBuilder, expr: &Expr, wires: &HashMap<String, u16>) -> u16 {
+    match expr {
+        Expr::Var(name) => wires[name],
+        Expr::Not(inner) => {
+            let a = emit_expr(builder, inner, wires);
+            builder.not(a)
+        }
+        Expr::And(a, b) => {
+            let (a, b) = (emit_expr(builder, a, wires), emit_expr(builder, b, wires));
+            builder.and(a, b)
+        }
+        Expr::Xor(a, b) => {
+            let (a, b) = (emit_expr(builder, a, wires), emit_expr(builder, b, wires));
+            builder.xor(a, b)
+        }
+        Expr::Or(a, b) => {
+            let (a, b) = (emit_expr(builder, a, wires), emit_expr(builder, b, wires));
+            builder.or(a, b)
+        }
+    }
+}
+
+/// Compiles a boolean expression such as `a3 & !b3 | (a2 ^ b2)` into a circuit.
+///
+/// Operators: `!`/`~` (NOT), `&` (AND), `^` (XOR), `|` (OR, 3 gates), with C precedence
+/// (`!` > `&` > `^` > `|`) and parentheses. Variables become input wires in natural name
+/// order (`a0, a1, .., b0, ..`), matching the Alice-then-Bob, LSB-first input convention for
+/// `a*`/`b*` names. The expression must contain at least one operator.
+pub fn compile_expr_with_inputs(source: &str) -> Result<CompiledExpr, String> {
+    let expr = parse_expr(source)?;
+    if matches!(expr, Expr::Var(_)) {
+        return Err("expression must contain at least one operator".to_string());
+    }
+
+    let mut vars = BTreeSet::new();
+    collect_vars(&expr, &mut vars);
+    let inputs: Vec<String> = vars.into_iter().map(|(_, _, name)| name).collect();
+
+    let mut builder = CircuitBuilder::new();
+    let wires: HashMap<String, u16> = inputs
+        .iter()
+        .map(|name| (name.clone(), builder.input()))
+        .collect();
+    let output_wire = emit_expr(&mut builder, &expr, &wires);
+
+    Ok(CompiledExpr {
+        layout: builder.finish(),
+        inputs,
+        output_wire,
+    })
+}
+
+/// Compiles a boolean expression into a layout; see `compile_expr_with_inputs`.
+pub fn compile_expr(source: &str) -> Result<CircuitLayout, String> {
+    compile_expr_with_inputs(source).map(|compiled| compiled.layout)
+}
+
+/// Evaluates a boolean expression directly on plaintext variable values.
+pub fn evaluate_expr(source: &str, values: &HashMap<String, u8>) -> Result<u8, String> {
+    fn eval(expr: &Expr, values: &HashMap<String, u8>) -> Result<u8, String> {
+        Ok(match expr {
+            Expr::Var(name) => values
+                .get(name)
+                .map(|bit| bit & 1)
+                .ok_or_else(|| format!("missing value for variable '{name}'"))?,
+            Expr::Not(inner) => eval(inner, values)? ^ 1,
+            Expr::And(a, b) => eval(a, values)? & eval(b, values)?,
+            Expr::Xor(a, b) => eval(a, values)? ^ eval(b, values)?,
+            Expr::Or(a, b) => eval(a, values)? | eval(b, values)?,
+        })
+    }
+    eval(&parse_expr(source)?, values)
+}
@@ -1,38 +1,127 @@
+use crate::abi::{AbiCall, call_from_cast_args};
+use crate::artifact_diff::diff_artifact_dirs;
+use crate::artifact_layout::migrate;
+use crate::cli_args::{SERVE_REFUSED_FLAGS, path_flags};
+use crate::compression::{ArtifactCompression, DEFAULT_ZSTD_LEVEL, read_artifact};
+use crate::consensus::leaf_scheme_id;
+use crate::contract_status::{
+    BuyerState, ContractStatus, ContractWatch, InstanceCommitment, status_lines,
+};
+use crate::daemon::{BindPolicy, PathRoot, RpcServer, run_subcommand};
+use crate::emitln;
+use crate::encryption::{ArtifactKey, artifact_passphrase};
+use crate::identity::EthIdentity;
+use crate::manifest::{
+    MANIFEST_FILE, MANIFEST_SIGNATURE_FILE, ManifestReport, ManifestSignature, sign_manifest,
+    verify_manifest,
+};
+use crate::messages::{Envelope, Message, SignedMessage};
+use crate::profile::profile_var;
+use crate::protocol::{Party, Phase, ProtocolSession};
+use crate::relayer::{RELAY_METHOD, Relayer, SponsorLimits, relay_send};
+use crate::retry::{RetryPolicy, is_transient_error, is_unsent_error, retry_with};
+use crate::scenario::SessionParams;
+use crate::scenario::beacon::commitment_binding;
+use crate::secrets::{random_bytes32, read_secret, write_private_file};
+use crate::session_store::SessionStore;
+use crate::timeouts::{
+    Deadlines, SenderState, TimeoutSnapshot, TimeoutStatus, stage_name, timeout_status,
+};
+use crate::transcript::{TRANSCRIPT_FILE, Transcript, TranscriptEvent};
+use crate::types::SchemeId;
+use clap::Args;
 use std::env;
 use std::error::Error;
-use std::process::Command;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
 
 pub type CliResult<T> = Result<T, Box<dyn Error>>;
 
+/// Env var `name`, unless the `--profile` in use sets it (`profile::profile_var`).
+pub fn config_var(name: &str) -> Option<String> {
+    profile_var(name).or_else(|| env::var(name).ok())
+}
+
 pub fn required_env(name: &str) -> CliResult<String> {
-    env::var(name).map_err(|_| format!("Missing required env var: {name}").into())
+    config_var(name).ok_or_else(|| format!("Missing required env var: {name}").into())
 }
 
 pub fn required_env_any(names: &[&str]) -> CliResult<String> {
     for name in names {
-        if let Ok(value) = env::var(name) {
-            if !value.trim().is_empty() {
-                return Ok(value);
-            }
+        if let Some(value) = config_var(name)
+            && !value.trim().is_empty()
+        {
+            return Ok(value);
         }
     }
     Err(format!("Missing required env vars: {}", names.join(" or ")).into())
 }
 
+/// Set by `--key-stdin`: the private key read when the command started.
+static STDIN_KEY: Mutex<Option<String>> = Mutex::new(None);
+
+/// Reads the private key now, from stdin or a hidden prompt (`secrets::read_secret`), and
+/// uses it for the rest of the process instead of the key env vars.
+pub fn start_key_stdin() -> CliResult<()> {
+    let key = read_secret("Private key: ")?;
+    *STDIN_KEY.lock().expect("stdin key lock") = Some(key);
+    Ok(())
+}
+
+/// The `--key-stdin` key, else the first of `key_vars` that is set.
+pub fn configured_private_key(key_vars: &[&str]) -> Option<String> {
+    let stdin_key = STDIN_KEY.lock().expect("stdin key lock").clone();
+    stdin_key.or_else(|| required_env_any(key_vars).ok())
+}
+
+/// `configured_private_key`, prompted for on a terminal when neither is given.
+pub fn private_key(key_vars: &[&str]) -> CliResult<String> {
+    if let Some(key) = configured_private_key(key_vars) {
+        return Ok(key);
+    }
+    if !io::stdin().is_terminal() {
+        return required_env_any(key_vars);
+    }
+    Ok(read_secret(&format!("{}: ", key_vars[0]))?)
+}
+
 pub fn rpc_url() -> String {
-    env::var("RPC_URL").unwrap_or_else(|_| "http://127.0.0.1:8545".to_string())
+    config_var("RPC_URL").unwrap_or_else(|| "http://127.0.0.1:8545".to_string())
 }
 
 pub fn env_truthy(name: &str) -> bool {
-    match env::var(name) {
-        Ok(value) => {
+    match config_var(name) {
+        Some(value) => {
             let normalized = value.trim().to_ascii_lowercase();
             matches!(normalized.as_str(), "1" | "true" | "yes" | "on")
         }
-        Err(_) => false,
+        None => false,
     }
 }
 
+/// Asks `question` on the terminal and returns whether it was answered yes. Without a
+/// terminal on stdin nobody can answer, which is an error naming `skip_flag`.
+pub fn confirm(question: &str, skip_flag: &str) -> CliResult<bool> {
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        return Err(format!("confirmation needs a terminal on stdin; pass {skip_flag}").into());
+    }
+    eprint!("{question} [y/N] ");
+    io::stderr().flush()?;
+    let mut answer = String::new();
+    stdin.read_line(&mut answer)?;
+    Ok(is_yes(&answer))
+}
+
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
 pub fn cast_args_with_tx_overrides(args: &[String]) -> Vec<String> {
     let mut out = args.to_vec();
     if out.first().map(String::as_str) != Some("send") {
@@ -45,27 +134,240 @@ pub fn cast_args_with_tx_overrides(args: &[String]) -> Vec<String> {
         out.push("--legacy".to_string());
     }
 
-    if !has_blob_tx && !out.iter().any(|arg| arg == "--gas-price") {
-        if let Ok(gas_price_wei) = env::var("TX_GAS_PRICE_WEI") {
-            let trimmed = gas_price_wei.trim();
-            if !trimmed.is_empty() {
-                out.push("--gas-price".to_string());
-                out.push(trimmed.to_string());
-            }
+    if !has_blob_tx
+        && !out.iter().any(|arg| arg == "--gas-price")
+        && let Some(gas_price_wei) = config_var("TX_GAS_PRICE_WEI")
+    {
+        let trimmed = gas_price_wei.trim();
+        if !trimmed.is_empty() {
+            out.push("--gas-price".to_string());
+            out.push(trimmed.to_string());
         }
     }
 
     out
 }
 
+/// Runs `cast` with `args`. When `RELAYER_URL` is set, `send` goes through
+/// `relayer::relay_send`: the party still signs, the relayer endpoint pays the gas.
 pub fn run_cast(args: &[String]) -> CliResult<String> {
+    // `cast send <to> <signature> ...`; a plain value transfer has no signature.
+    let label = args
+        .get(2)
+        .filter(|arg| !arg.starts_with('-'))
+        .map_or("transfer", String::as_str);
+    run_cast_labeled(args, label)
+}
+
+/// Sends `call` to `to` as raw calldata, followed by `options` such as `--private-key`; the
+/// transcript still records the call's signature rather than its bytes.
+pub fn send_call(to: &str, call: &AbiCall, options: &[String]) -> CliResult<String> {
+    let mut args = vec!["send".to_string(), to.to_string(), call.calldata_hex()];
+    args.extend_from_slice(options);
+    run_cast_labeled(&args, &call.signature)
+}
+
+/// `send_call` followed by `print_tx_summary(label, ..)`; under `--dry-run` prints the
+/// transaction instead of sending it.
+pub fn send_call_or_print(
+    label: &str,
+    to: &str,
+    call: &AbiCall,
+    options: &[String],
+) -> CliResult<()> {
+    let output = send_call(to, call, options)?;
+    print_tx_summary(label, &output);
+    Ok(())
+}
+
+/// Set by `--dry-run`, for the rest of the process.
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// From now on `cast send` prints the transaction it would send (`dry_run_tx_lines`) and
+/// returns an empty output instead of sending it.
+pub fn start_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// What `cast send` would send for `args` (`send <to> <signature|calldata> <args..>
+/// [options]`): target, value, function and calldata, so the transaction can be checked or
+/// signed elsewhere. `signature` names a call given as calldata. Keys and secrets in the
+/// options are left out.
+pub fn dry_run_tx_lines(args: &[String], signature: &str) -> CliResult<Vec<String>> {
+    let [send, to, function, rest @ ..] = args else {
+        return Err(format!("not a cast send: {}", args.join(" ")).into());
+    };
+    if send != "send" {
+        return Err(format!("not a cast send: {}", args.join(" ")).into());
+    }
+    let positional = rest.iter().take_while(|arg| !arg.starts_with("--")).count();
+    let (call_args, options) = rest.split_at(positional);
+    let option = |name: &str| {
+        options
+            .iter()
+            .position(|arg| arg == name)
+            .and_then(|index| options.get(index + 1))
+    };
+
+    let (signature, calldata) = match function.strip_prefix("0x") {
+        Some(_) => (signature, decode_hex(function)?),
+        None => {
+            let call = call_from_cast_args(function, call_args)?;
+            (function.as_str(), call.calldata())
+        }
+    };
+    let value = option("--value").map_or("0", String::as_str);
+    let selector = &calldata[..calldata.len().min(4)];
+    let mut lines = vec![
+        format!("tx_to={to}"),
+        format!("tx_value_wei={value}"),
+        format!("tx_call={signature}"),
+        format!("tx_selector={}", hex_prefixed(selector)),
+        format!("tx_calldata={}", hex_prefixed(&calldata)),
+    ];
+    if options.iter().any(|arg| arg == "--blob") {
+        let path = option("--path").map_or("none", String::as_str);
+        lines.push(format!("tx_blob_file={path}"));
+    }
+    Ok(lines)
+}
+
+fn run_cast_labeled(args: &[String], label: &str) -> CliResult<String> {
+    let is_send = args.first().map(String::as_str) == Some("send");
+    if is_send && is_dry_run() {
+        for line in dry_run_tx_lines(args, label)? {
+            emitln!("{line}");
+        }
+        return Ok(String::new());
+    }
+    if is_send {
+        tracing::info!(call = label, "sending transaction");
+    }
+    let output = match config_var("RELAYER_URL") {
+        Some(relayer_url) if is_send => relay_send(
+            &cast_args_with_tx_overrides(args),
+            &relayer_url,
+            &required_env("RELAYER_TOKEN")?,
+        )?,
+        _ => run_cast_direct(args)?,
+    };
+    if is_send {
+        record_transcript(TranscriptEvent::Action {
+            label: label.to_string(),
+            tx_hash: cast_output_field(&output, "transactionHash"),
+            payload: None,
+        })?;
+    }
+    Ok(output)
+}
+
+/// Transcript this process records its messages and transactions into, once
+/// `start_transcript` opened one.
+static TRANSCRIPT: Mutex<Option<Transcript>> = Mutex::new(None);
+
+/// `<session>/transcript.log` for a session run, else the `TRANSCRIPT_PATH` env var.
+pub fn transcript_path(session: Option<&SessionStore>) -> Option<PathBuf> {
+    match session {
+        Some(store) => Some(store.dir().join(TRANSCRIPT_FILE)),
+        None => env::var("TRANSCRIPT_PATH")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .map(PathBuf::from),
+    }
+}
+
+/// Opens (and verifies) the transcript at `path`; later `record_transcript` calls append to it.
+pub fn start_transcript(path: &Path) -> CliResult<()> {
+    let transcript = Transcript::open(path)?;
+    *TRANSCRIPT.lock().map_err(|_| "transcript lock poisoned")? = Some(transcript);
+    Ok(())
+}
+
+/// Appends `event` to the transcript opened by `start_transcript`; a no-op without one.
+pub fn record_transcript(event: TranscriptEvent) -> CliResult<()> {
+    let mut guard = TRANSCRIPT.lock().map_err(|_| "transcript lock poisoned")?;
+    if let Some(transcript) = guard.as_mut() {
+        transcript.append(event)?;
+    }
+    Ok(())
+}
+
+/// Runs `cast` with `args`, never through the relayer. Transient RPC failures are retried
+/// under `RetryPolicy::from_env`; a `send` that may have reached the node is not, since it
+/// would be signed with a fresh nonce (`publish` resends the same signed transaction and is
+/// retried like a read).
+pub fn run_cast_direct(args: &[String]) -> CliResult<String> {
     let final_args = cast_args_with_tx_overrides(args);
-    let output = Command::new("cast").args(&final_args).output()?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("cast {} failed: {}", final_args.join(" "), stderr.trim()).into());
+    let retryable: fn(&str) -> bool = if args.first().map(String::as_str) == Some("send") {
+        is_unsent_error
+    } else {
+        is_transient_error
+    };
+    let (argv, private_key) = split_private_key(&final_args);
+    let output = retry_with(&RetryPolicy::from_env()?, retryable, thread::sleep, |_| {
+        tracing::debug!(command = %redacted_cast_command(&final_args), "running cast");
+        let mut command = Command::new("cast");
+        command.args(&argv);
+        if let Some(key) = &private_key {
+            command.env(PRIVATE_KEY_ENV, key);
+        }
+        let output = command
+            .output()
+            .map_err(|e| format!("failed to run cast: {e}"))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "{} failed: {}",
+                redacted_cast_command(&final_args),
+                stderr.trim()
+            ));
+        }
+        Ok(output.stdout)
+    })?;
+    Ok(String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/// Env var `cast` and `forge` read the signing key from, so it never shows up in the child's
+/// command line (`ps`, `/proc/<pid>/cmdline`).
+pub const PRIVATE_KEY_ENV: &str = "ETH_PRIVATE_KEY";
+
+/// `args` without `--private-key <key>` (or `--private-key=<key>`), and the key, which the
+/// child gets through `PRIVATE_KEY_ENV` instead.
+fn split_private_key(args: &[String]) -> (Vec<String>, Option<String>) {
+    let mut argv = Vec::with_capacity(args.len());
+    let mut private_key = None;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--private-key" {
+            private_key = iter.next().cloned();
+        } else if let Some(key) = arg.strip_prefix("--private-key=") {
+            private_key = Some(key.to_string());
+        } else {
+            argv.push(arg.clone());
+        }
+    }
+    (argv, private_key)
+}
+
+/// `cast <args>` for logs and errors, with the value of `--private-key` hidden.
+fn redacted_cast_command(args: &[String]) -> String {
+    let mut words = vec!["cast"];
+    let mut redact_next = false;
+    for arg in args {
+        if redact_next {
+            words.push("<redacted>");
+        } else if arg.starts_with("--private-key=") {
+            words.push("--private-key=<redacted>");
+        } else {
+            words.push(arg);
+        }
+        redact_next = arg == "--private-key";
     }
-    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    words.join(" ")
 }
 
 pub fn cast_output_field(output: &str, key: &str) -> Option<String> {
@@ -118,8 +420,12 @@ pub fn tx_summary_lines(label: &str, output: &str) -> Vec<String> {
 }
 
 pub fn print_tx_summary(label: &str, output: &str) {
+    // Nothing was sent; `dry_run_tx_lines` already described the transaction.
+    if is_dry_run() {
+        return;
+    }
     for line in tx_summary_lines(label, output) {
-        println!("{line}");
+        emitln!("{line}");
     }
 }
 
@@ -175,7 +481,16 @@ pub fn parse_bytes16(value: &str) -> CliResult<[u8; 16]> {
     parse_fixed_bytes::<16>(value)
 }
 
+/// Parses a 71-byte v1 leaf; versioned leaves of other schemes are named in the error.
 pub fn parse_leaf71(value: &str) -> CliResult<[u8; 71]> {
+    let decoded = decode_hex(value)?;
+    if let Ok(Some(scheme_id)) = leaf_scheme_id(&decoded) {
+        return Err(format!(
+            "leaf is encoded for garbling scheme {scheme_id}; expected a 71-byte {} leaf",
+            SchemeId::CLASSIC_V1
+        )
+        .into());
+    }
     parse_fixed_bytes::<71>(value)
 }
 
@@ -236,6 +551,944 @@ pub fn parse_u8(value: &str, name: &str) -> CliResult<u8> {
         .map_err(|_| format!("Invalid {name}: {value}").into())
 }
 
+/// `--compress` (level `--zstd-level <1..=22>`, which implies it) selects zstd for the
+/// artifacts a command writes.
+pub fn parse_artifact_compression(args: &[String]) -> CliResult<ArtifactCompression> {
+    let level = parse_flag_value(args, "--zstd-level")
+        .map(|raw| parse_u64(&raw, "zstd-level"))
+        .transpose()?;
+    if level.is_none() && !args.iter().any(|arg| arg == "--compress") {
+        return Ok(ArtifactCompression::None);
+    }
+    let level = level.map_or(DEFAULT_ZSTD_LEVEL, |level| {
+        i32::try_from(level).unwrap_or(i32::MAX)
+    });
+    Ok(ArtifactCompression::zstd(level)?)
+}
+
+/// Key for `--encrypt`, which encrypts the secret artifacts a command writes (seeds, leaves)
+/// under a key derived from the artifact passphrase (`encryption::artifact_passphrase`).
+pub fn artifact_encryption(encrypt: bool) -> CliResult<Option<ArtifactKey>> {
+    if !encrypt {
+        return Ok(None);
+    }
+    Ok(Some(ArtifactKey::generate(&artifact_passphrase(true)?)?))
+}
+
+/// Reads `N()` from the auction contract and fails if it differs from `params.n`.
+pub fn ensure_contract_n(
+    contract_address: &str,
+    rpc_url: &str,
+    params: SessionParams,
+) -> CliResult<()> {
+    let raw = run_cast(&[
+        "call".to_string(),
+        contract_address.to_string(),
+        "N()(uint256)".to_string(),
+        "--rpc-url".to_string(),
+        rpc_url.to_string(),
+    ])?;
+    // `cast` may append a scientific-notation hint, e.g. `10 [1e1]`.
+    let value = raw.split_whitespace().next().unwrap_or("");
+    params.check_contract_n(parse_u64(value, "contract N")?)?;
+    Ok(())
+}
+
+/// `cast call` on the contract, keeping the first token of each output line (`cast` may
+/// append hints such as `10 [1e1]`).
+pub fn cast_call_values(
+    contract_address: &str,
+    rpc_url: &str,
+    call: &[&str],
+) -> CliResult<Vec<String>> {
+    let mut cast_args = vec!["call".to_string(), contract_address.to_string()];
+    cast_args.extend(call.iter().map(|arg| arg.to_string()));
+    cast_args.extend(["--rpc-url".to_string(), rpc_url.to_string()]);
+    Ok(run_cast(&cast_args)?
+        .lines()
+        .filter_map(|line| line.split_whitespace().next().map(str::to_string))
+        .collect())
+}
+
+/// Timestamp of the latest block, which the contract compares deadlines against.
+pub fn latest_block_timestamp(rpc_url: &str) -> CliResult<u64> {
+    let raw = run_cast(&[
+        "block".to_string(),
+        "latest".to_string(),
+        "--field".to_string(),
+        "timestamp".to_string(),
+        "--rpc-url".to_string(),
+        rpc_url.to_string(),
+    ])?;
+    parse_u64(
+        raw.split_whitespace().next().unwrap_or(""),
+        "block timestamp",
+    )
+}
+
+/// Reads the contract's `deadlines()` struct.
+pub fn read_deadlines(contract_address: &str, rpc_url: &str) -> CliResult<Deadlines> {
+    let values = cast_call_values(
+        contract_address,
+        rpc_url,
+        &["deadlines()(uint256,uint256,uint256,uint256,uint256,uint256,uint256,uint256)"],
+    )?;
+    let fields = values
+        .iter()
+        .map(|value| parse_u64(value, "deadline"))
+        .collect::<CliResult<Vec<_>>>()?;
+    Ok(Deadlines::from_fields(&fields)?)
+}
+
+/// Reads the current stage, the latest block timestamp and every deadline.
+pub fn read_timeout_snapshot(contract_address: &str, rpc_url: &str) -> CliResult<TimeoutSnapshot> {
+    let stage = cast_call_values(contract_address, rpc_url, &["currentStage()(uint8)"])?;
+    Ok(TimeoutSnapshot {
+        stage: parse_u8(
+            stage.first().ok_or("empty cast output for currentStage")?,
+            "stage",
+        )?,
+        now: latest_block_timestamp(rpc_url)?,
+        deadlines: read_deadlines(contract_address, rpc_url)?,
+    })
+}
+
+/// `instanceCommitments(instance_id)` of the contract.
+pub fn read_instance_commitment(
+    contract_address: &str,
+    rpc_url: &str,
+    instance_id: u64,
+) -> CliResult<InstanceCommitment> {
+    let fields = cast_call_values(
+        contract_address,
+        rpc_url,
+        &[
+            "instanceCommitments(uint256)(bytes32,bytes32,bytes32,bytes32)",
+            &instance_id.to_string(),
+        ],
+    )?;
+    let [com_seed, root_gc, blob_hash_gc, h_out] = fields.as_slice() else {
+        return Err(format!(
+            "instanceCommitments({instance_id}) returned {} fields, expected 4",
+            fields.len()
+        )
+        .into());
+    };
+    Ok(InstanceCommitment {
+        com_seed: parse_bytes32(com_seed)?,
+        root_gc: parse_bytes32(root_gc)?,
+        blob_hash_gc: parse_bytes32(blob_hash_gc)?,
+        h_out: parse_bytes32(h_out)?,
+    })
+}
+
+/// Hash of block `height` on the node at `rpc_url`. A `claimed` hash, e.g. one the other
+/// party published, must be that block's.
+pub fn read_block_hash(
+    height: u64,
+    claimed: Option<[u8; 32]>,
+    rpc_url: &str,
+) -> CliResult<[u8; 32]> {
+    let raw = run_cast(&[
+        "block".to_string(),
+        height.to_string(),
+        "--field".to_string(),
+        "hash".to_string(),
+        "--rpc-url".to_string(),
+        rpc_url.to_string(),
+    ])?;
+    let hash = parse_bytes32(raw.trim())?;
+    if let Some(claimed) = claimed
+        && claimed != hash
+    {
+        return Err(format!(
+            "block hash {} is not the hash {} of block {height}",
+            hex32(claimed),
+            hex32(hash)
+        )
+        .into());
+    }
+    Ok(hash)
+}
+
+/// `scenario::beacon::commitment_binding` over the rootGC list `contract_address` holds; fails
+/// until Alice submitted the commitments of every instance.
+pub fn read_beacon_binding(
+    contract_address: &str,
+    rpc_url: &str,
+    params: SessionParams,
+) -> CliResult<[u8; 32]> {
+    ensure_contract_n(contract_address, rpc_url, params)?;
+    let mut root_gcs = Vec::with_capacity(params.n);
+    for instance_id in 0..params.n as u64 {
+        let commitment = read_instance_commitment(contract_address, rpc_url, instance_id)?;
+        if !commitment.is_submitted() {
+            return Err(format!(
+                "instance {instance_id} has no commitments on-chain; the beacon binds Alice's rootGC list"
+            )
+            .into());
+        }
+        root_gcs.push(commitment.root_gc);
+    }
+    Ok(commitment_binding(&root_gcs))
+}
+
+/// Reads everything `status` shows, one `cast call` per value.
+pub fn read_contract_status(contract_address: &str, rpc_url: &str) -> CliResult<ContractStatus> {
+    let call = |args: &[&str]| cast_call_values(contract_address, rpc_url, args);
+    let first = |args: &[&str]| -> CliResult<String> {
+        call(args)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("empty cast output for {}", args[0]).into())
+    };
+    let vault = |address: &str| parse_u256(&first(&["vault(address)(uint256)", address])?, "vault");
+    let timeouts = read_timeout_snapshot(contract_address, rpc_url)?;
+
+    let alice = first(&["alice()(address)"])?;
+    let buyer_count = parse_u64(&first(&["buyerCount()(uint256)"])?, "buyer count")?;
+    let mut buyers = Vec::new();
+    for index in 0..buyer_count {
+        let address = first(&["buyerAt(uint256)(address)", &index.to_string()])?;
+        buyers.push(BuyerState {
+            receiver: first(&["buyerReceiver(address)(address)", &address])?,
+            status: parse_u8(
+                &first(&["buyerStatus(address)(uint8)", &address])?,
+                "buyer status",
+            )?,
+            vault_wei: vault(&address)?,
+            address,
+        });
+    }
+
+    let n = parse_u64(&first(&["N()(uint256)"])?, "contract N")?;
+    let commitments = (0..n)
+        .map(|instance_id| read_instance_commitment(contract_address, rpc_url, instance_id))
+        .collect::<CliResult<Vec<_>>>()?;
+
+    let verifier_seed_finalized = first(&["verifierSeedFinalized()(bool)"])? == "true";
+    let (mut m, mut opened) = (0, Vec::new());
+    if verifier_seed_finalized {
+        m = parse_u64(&first(&["m()(uint256)"])?, "m")?;
+        let open_count = parse_u64(&first(&["getSOpenLength()(uint256)"])?, "sOpen length")?;
+        for position in 0..open_count {
+            let instance_id = first(&["sOpen(uint256)(uint256)", &position.to_string()])?;
+            opened.push(parse_u64(&instance_id, "sOpen")?);
+        }
+    }
+
+    Ok(ContractStatus {
+        contract_address: contract_address.to_string(),
+        stage: timeouts.stage,
+        now: timeouts.now,
+        deadlines: timeouts.deadlines,
+        alice_vault_wei: vault(&alice)?,
+        alice,
+        buyers,
+        circuit_id: parse_bytes32(&first(&["circuitId()(bytes32)"])?)?,
+        bit_width: parse_u16(&first(&["bitWidth()(uint16)"])?, "bit width")?,
+        verifier_seed_finalized,
+        m,
+        opened,
+        commitments,
+    })
+}
+
+/// `status` of both binaries: prints the contract snapshot of `contract_status`.
+pub fn print_contract_status(contract_address: &str, rpc_url: &str) -> CliResult<()> {
+    let status = read_contract_status(contract_address, rpc_url)?;
+    for line in status_lines(&status)? {
+        emitln!("{line}");
+    }
+    Ok(())
+}
+
+/// File in the session directory holding the `serve` daemon's bearer token.
+pub const SERVE_TOKEN_FILE: &str = "serve-token";
+
+/// `serve` of both binaries: serves `methods` of `command` on `listen`, each call run as a
+/// subcommand inside the session directory, with a fresh bearer token in `SERVE_TOKEN_FILE`.
+pub fn serve_session(
+    command: &clap::Command,
+    listen: &str,
+    methods: &[&str],
+    session: Option<&SessionStore>,
+) -> CliResult<()> {
+    let store = session.ok_or("serve needs --session <dir>: calls only read and write there")?;
+    let root = store.dir().canonicalize()?;
+    let paths = PathRoot {
+        root: root.clone(),
+        path_flags: path_flags(command, methods),
+        refused_flags: SERVE_REFUSED_FLAGS
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect(),
+    };
+    let token = strip_0x(&hex32(random_bytes32()?)).to_string();
+    let token_file = root.join(SERVE_TOKEN_FILE);
+    write_private_file(&token_file, format!("{token}\n").as_bytes())?;
+    let server = RpcServer::bind(listen, methods, &token, paths)?;
+    let exe = env::current_exe()?;
+
+    emitln!("status=serving");
+    match server.local_addr() {
+        Some(addr) => emitln!("listen={addr}"),
+        None => emitln!("listen={listen}"),
+    }
+    emitln!("methods={}", methods.join(","));
+    emitln!("token_file={}", token_file.display());
+    server.serve(&mut |method, args| run_subcommand(&exe, &root, method, args))?;
+    Ok(())
+}
+
+/// `relay` of both binaries: sponsors the gas of calls to `CONTRACT_ADDRESS` other parties
+/// sign, from `RELAYER_PRIVATE_KEY`, for clients holding `RELAYER_TOKEN`. Unlike `serve` it
+/// may listen on a public address (`BindPolicy::Public`), behind a TLS proxy.
+pub fn serve_relayer(listen: &str) -> CliResult<()> {
+    let mut relayer = Relayer::new(
+        &required_env("RELAYER_PRIVATE_KEY")?,
+        &required_env("CONTRACT_ADDRESS")?,
+        &rpc_url(),
+        SponsorLimits::from_env()?,
+    )?;
+    // The relayer runs no subcommands, so there are no path flags to confine.
+    let paths = PathRoot {
+        root: env::current_dir()?,
+        path_flags: Vec::new(),
+        refused_flags: Vec::new(),
+    };
+    let token = required_env("RELAYER_TOKEN")?;
+    let server =
+        RpcServer::bind_with_policy(listen, BindPolicy::Public, &[RELAY_METHOD], &token, paths)?;
+
+    emitln!("status=relaying");
+    match server.local_addr() {
+        Some(addr) => emitln!("listen={addr}"),
+        None => emitln!("listen={listen}"),
+    }
+    emitln!("relayer={}", hex_prefixed(&relayer.address()));
+    server.serve(&mut |_, args| relayer.relay(args).map_err(|e| e.to_string()))?;
+    Ok(())
+}
+
+/// Encodes `message` signed with the first key set in `key_vars` for `CONTRACT_ADDRESS`; with
+/// either missing the message is encoded unsigned. Returns the bytes and the signer.
+pub fn encode_signed_message(
+    message: Message,
+    key_vars: &[&str],
+) -> CliResult<(Vec<u8>, Option<[u8; 20]>)> {
+    let (Some(private_key), Ok(contract_address)) = (
+        configured_private_key(key_vars),
+        required_env("CONTRACT_ADDRESS"),
+    ) else {
+        let bytes = message.to_cbor();
+        record_transcript(TranscriptEvent::Sent(bytes.clone()))?;
+        return Ok((bytes, None));
+    };
+    let identity = EthIdentity::from_private_key(parse_bytes32(&private_key)?)?;
+    let contract = parse_fixed_bytes::<20>(&contract_address)?;
+    let signed = SignedMessage::sign(message, contract, &identity)?;
+    let bytes = signed.to_cbor();
+    record_transcript(TranscriptEvent::Sent(bytes.clone()))?;
+    Ok((bytes, Some(identity.address())))
+}
+
+fn onchain_alice(contract_address: &str, rpc_url: &str) -> CliResult<[u8; 20]> {
+    let alice = cast_call_values(contract_address, rpc_url, &["alice()(address)"])?;
+    parse_fixed_bytes::<20>(alice.first().ok_or("empty cast output for alice")?)
+}
+
+/// Checks the signer of `signed` on-chain: `alice()` for Alice's messages, `isBuyer(signer)`
+/// for Bob's. Returns the signer.
+pub fn verify_message_signer(
+    contract_address: &str,
+    rpc_url: &str,
+    signed: &SignedMessage,
+) -> CliResult<[u8; 20]> {
+    let signer = signed.signer()?;
+    let expected = if signed.message.sender() == Party::Garbler {
+        onchain_alice(contract_address, rpc_url)?
+    } else {
+        let signer_hex = hex_prefixed(&signer);
+        let is_buyer = cast_call_values(
+            contract_address,
+            rpc_url,
+            &["isBuyer(address)(bool)", &signer_hex],
+        )?;
+        if is_buyer.first().map(String::as_str) != Some("true") {
+            return Err(format!("message signer {signer_hex} is not a buyer").into());
+        }
+        signer
+    };
+    signed.verify(parse_fixed_bytes::<20>(contract_address)?, expected)?;
+    Ok(signer)
+}
+
+/// Reads a message file. A signed message is checked with `verify_message_signer` against
+/// `CONTRACT_ADDRESS`; an unsigned one is only accepted with `allow_unsigned`. Returns the
+/// message and its verified signer.
+pub fn read_verified_message(
+    path: &Path,
+    allow_unsigned: bool,
+) -> CliResult<(Message, Option<[u8; 20]>)> {
+    let bytes = read_artifact(path).map_err(|e| format!("failed to read message: {e}"))?;
+    let envelope = Envelope::from_cbor(&bytes)
+        .map_err(|e| format!("invalid message {}: {e}", path.display()))?;
+    let verified = match envelope {
+        Envelope::Signed(signed) => {
+            let contract_address = required_env("CONTRACT_ADDRESS")?;
+            let signer = verify_message_signer(&contract_address, &rpc_url(), &signed)
+                .map_err(|e| format!("{}: {e}", path.display()))?;
+            (signed.message, Some(signer))
+        }
+        Envelope::Plain(message) if allow_unsigned => (message, None),
+        Envelope::Plain(_) => return Err(format!("{} is not signed", path.display()).into()),
+    };
+    record_transcript(TranscriptEvent::Received(bytes))?;
+    Ok(verified)
+}
+
+/// `verify-manifest`: checks the artifact directory `dir` (or a directory inside a bundle)
+/// against its `manifest.json`, printing one `mismatch=` line per bad file.
+pub fn verify_manifest_dir(dir: &Path) -> CliResult<()> {
+    print_manifest_report(dir, &verify_manifest(dir)?)
+}
+
+/// Prints a `manifest::ManifestReport` as `key=value` lines and fails on any mismatch.
+pub fn print_manifest_report(dir: &Path, report: &ManifestReport) -> CliResult<()> {
+    let intact = report.is_intact();
+    emitln!("status={}", if intact { "intact" } else { "mismatch" });
+    emitln!("dir={}", dir.display());
+    emitln!("circuit={}", report.manifest.circuit);
+    emitln!("files={}", report.manifest.files.len());
+    emitln!("manifest_hash={}", hex_prefixed(&report.manifest_hash));
+    for mismatch in &report.mismatches {
+        emitln!("mismatch={mismatch}");
+    }
+    for name in &report.unlisted {
+        emitln!("unlisted={name}");
+    }
+    if !intact {
+        return Err(format!(
+            "{} mismatches against {}",
+            report.mismatches.len(),
+            dir.join(MANIFEST_FILE).display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Signs `dir/manifest.json` for `CONTRACT_ADDRESS` with the first key set in `key_vars`,
+/// like `encode_signed_message`; with either missing nothing is written. Returns the signer.
+pub fn sign_artifact_manifest(dir: &Path, key_vars: &[&str]) -> CliResult<Option<[u8; 20]>> {
+    let (Some(private_key), Ok(contract_address)) = (
+        configured_private_key(key_vars),
+        required_env("CONTRACT_ADDRESS"),
+    ) else {
+        return Ok(None);
+    };
+    let identity = EthIdentity::from_private_key(parse_bytes32(&private_key)?)?;
+    let contract = parse_fixed_bytes::<20>(&contract_address)?;
+    Ok(Some(sign_manifest(dir, contract, &identity)?.signer))
+}
+
+/// `verify-signature`: checks that `dir/manifest.sig.json` signs the manifest for
+/// `CONTRACT_ADDRESS`, recovers to the on-chain `alice()`, and that every file still matches
+/// the manifest.
+pub fn verify_manifest_signature(dir: &Path) -> CliResult<()> {
+    let contract_address = required_env("CONTRACT_ADDRESS")?;
+    let signature = ManifestSignature::read(dir)?;
+    let report = verify_manifest(dir)?;
+    let alice = onchain_alice(&contract_address, &rpc_url())?;
+    signature
+        .verify(
+            parse_fixed_bytes::<20>(&contract_address)?,
+            report.manifest_hash,
+            alice,
+        )
+        .map_err(|e| format!("{}: {e}", dir.join(MANIFEST_SIGNATURE_FILE).display()))?;
+    emitln!("signer={}", hex_prefixed(&alice));
+    emitln!("contract={contract_address}");
+    print_manifest_report(dir, &report)
+}
+
+/// `diff-artifacts`: compares the instance files of two export directories
+/// (`artifact_diff::diff_artifact_dirs`), printing one `diff=` line per difference, and fails
+/// when they differ.
+pub fn print_artifact_diff(dir_a: &Path, dir_b: &Path) -> CliResult<()> {
+    let diff = diff_artifact_dirs(dir_a, dir_b)?;
+    let identical = diff.is_identical();
+    emitln!(
+        "status={}",
+        if identical { "identical" } else { "different" }
+    );
+    emitln!("dir_a={}", dir_a.display());
+    emitln!("dir_b={}", dir_b.display());
+    emitln!("instances={}", diff.instances.len());
+    emitln!("differing_instances={:?}", diff.differing_instances());
+    for difference in diff.differences() {
+        emitln!("diff={difference}");
+    }
+    if !identical {
+        return Err(format!(
+            "{} differences between {} and {}",
+            diff.differences().count(),
+            dir_a.display(),
+            dir_b.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// `migrate-artifacts`: upgrades the artifact directory `dir` to the current layout version
+/// (`artifact_layout::migrate`) and prints the files it wrote.
+pub fn migrate_artifact_dir(dir: &Path) -> CliResult<()> {
+    let migration = migrate(dir)?;
+    let status = if migration.written.is_empty() {
+        "current"
+    } else {
+        "migrated"
+    };
+    emitln!("status={status}");
+    emitln!("dir={}", dir.display());
+    emitln!("from_layout_version={}", migration.from);
+    emitln!("layout_version={}", migration.to);
+    for name in &migration.written {
+        emitln!("written={name}");
+    }
+    Ok(())
+}
+
+/// Verifies the transcript at `path`, replays it onto a fresh session and prints what it
+/// established. With `check_chain`, every recorded transaction must have a successful
+/// receipt and every signed message must pass `verify_message_signer` on `CONTRACT_ADDRESS`.
+pub fn replay_transcript(path: &Path, params: SessionParams, check_chain: bool) -> CliResult<()> {
+    if !path.exists() {
+        return Err(format!("no transcript at {}", path.display()).into());
+    }
+    let transcript = Transcript::open(path)?;
+    let replay = transcript.replay(ProtocolSession::new(params))?;
+    emitln!("transcript={}", path.display());
+    emitln!("entries={}", transcript.entries().len());
+    emitln!("head={}", hex_prefixed(&transcript.head()));
+    emitln!("messages_sent={}", replay.sent);
+    emitln!("messages_received={}", replay.received);
+    emitln!("actions={}", replay.actions);
+    emitln!("phase={}", replay.session.phase());
+    if let Some(contract) = replay.contract {
+        emitln!("message_contract={}", hex_prefixed(&contract));
+    }
+    for (party, signer) in &replay.signers {
+        match party {
+            Party::Garbler => emitln!("alice_signer={}", hex_prefixed(signer)),
+            _ => emitln!("buyer_signer={}", hex_prefixed(signer)),
+        }
+    }
+    if check_chain {
+        let contract_address = required_env("CONTRACT_ADDRESS")?;
+        let rpc_url = rpc_url();
+        let mut receipts = 0;
+        for entry in transcript.entries() {
+            let at = |e: Box<dyn Error>| format!("transcript entry {}: {e}", entry.index + 1);
+            match &entry.event {
+                TranscriptEvent::Action {
+                    label,
+                    tx_hash: Some(tx_hash),
+                    ..
+                } => {
+                    let status = run_cast(&[
+                        "receipt".to_string(),
+                        tx_hash.clone(),
+                        "status".to_string(),
+                        "--rpc-url".to_string(),
+                        rpc_url.clone(),
+                    ])
+                    .map_err(at)?;
+                    if !status.starts_with('1') {
+                        let err = format!("{label} {tx_hash} has status {status}");
+                        return Err(at(err.into()).into());
+                    }
+                    receipts += 1;
+                }
+                TranscriptEvent::Sent(bytes) | TranscriptEvent::Received(bytes) => {
+                    if let Envelope::Signed(signed) = Envelope::from_cbor(bytes)? {
+                        verify_message_signer(&contract_address, &rpc_url, &signed).map_err(at)?;
+                    }
+                }
+                TranscriptEvent::Action { .. } => {}
+            }
+        }
+        emitln!("checked_receipts={receipts}");
+    }
+    emitln!("status=replayed");
+    Ok(())
+}
+
+/// Options of the `claim-timeout` command shared by both binaries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Args)]
+pub struct ClaimTimeoutOptions {
+    /// Keep polling until a timeout path is claimable or the contract closes
+    #[arg(long)]
+    pub wait: bool,
+    /// Seconds between polls with --wait
+    #[arg(
+        long = "interval",
+        value_name = "SECS",
+        default_value_t = 12,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub interval_secs: u64,
+}
+
+/// Checks the current stage's deadline for `party` and sends its timeout or refund call once
+/// the deadline has passed, so the contract cannot revert with "not late yet".
+pub fn claim_timeout(
+    contract_address: &str,
+    rpc_url: &str,
+    party: Party,
+    private_key: &str,
+    options: ClaimTimeoutOptions,
+) -> CliResult<()> {
+    loop {
+        let snapshot = read_timeout_snapshot(contract_address, rpc_url)?;
+        emitln!("stage={}", snapshot.stage);
+        emitln!("stage_name={}", stage_name(snapshot.stage)?);
+        emitln!("now={}", snapshot.now);
+        let delay = match timeout_status(&snapshot, party)? {
+            TimeoutStatus::Closed => {
+                emitln!("timeout_status=closed");
+                return Ok(());
+            }
+            TimeoutStatus::NoPath => {
+                emitln!("timeout_status=none");
+                options.interval_secs
+            }
+            TimeoutStatus::Waiting { path, seconds_left } => {
+                emitln!("timeout_status=waiting");
+                emitln!("timeout_call={}", path.signature);
+                emitln!("timeout_deadline={}", path.deadline);
+                emitln!("timeout_seconds_left={seconds_left}");
+                options.interval_secs.min(seconds_left)
+            }
+            TimeoutStatus::Claimable(path) => {
+                emitln!("timeout_status=claimable");
+                emitln!("timeout_call={}", path.signature);
+                emitln!("timeout_deadline={}", path.deadline);
+                emitln!("timeout_effect={}", path.effect);
+                let (sender, state) = read_sender_state(contract_address, rpc_url, private_key)?;
+                emitln!("timeout_sender={}", hex_prefixed(&sender));
+                if let Some(reason) = path.sender_blocker(&state) {
+                    return Err(format!("{} would revert: {reason}", path.signature).into());
+                }
+                let tx_result = run_cast(&[
+                    "send".to_string(),
+                    contract_address.to_string(),
+                    path.signature.to_string(),
+                    "--private-key".to_string(),
+                    private_key.to_string(),
+                    "--rpc-url".to_string(),
+                    rpc_url.to_string(),
+                ])?;
+                print_tx_summary("claim_timeout", &tx_result);
+                return Ok(());
+            }
+        };
+        if !options.wait {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(delay));
+    }
+}
+
+/// Address of `private_key` with its role and vault on the contract.
+fn read_sender_state(
+    contract_address: &str,
+    rpc_url: &str,
+    private_key: &str,
+) -> CliResult<([u8; 20], SenderState)> {
+    let sender = EthIdentity::from_private_key(parse_bytes32(private_key)?)?.address();
+    let sender_hex = hex_prefixed(&sender);
+    let first = |call: &[&str]| -> CliResult<String> {
+        cast_call_values(contract_address, rpc_url, call)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("empty cast output for {}", call[0]).into())
+    };
+    let state = SenderState {
+        is_alice: onchain_alice(contract_address, rpc_url)? == sender,
+        is_buyer: first(&["isBuyer(address)(bool)", &sender_hex])? == "true",
+        vault_wei: parse_u256(&first(&["vault(address)(uint256)", &sender_hex])?, "vault")?,
+    };
+    Ok((sender, state))
+}
+
+/// Options of the `watch` commands shared by both binaries.
+#[derive(Debug, Clone, PartialEq, Eq, Args)]
+pub struct WatchOptions {
+    /// Seconds between polls
+    #[arg(
+        long = "interval",
+        value_name = "SECS",
+        default_value_t = 12,
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub interval_secs: u64,
+    /// Report the current stage's deadline this many seconds before it passes
+    #[arg(long = "margin", value_name = "SECS", default_value_t = 600)]
+    pub margin_secs: u64,
+    /// Poll once and exit
+    #[arg(long)]
+    pub once: bool,
+    /// Shell command run on every event, with PPA_EVENT set to the event name and
+    /// PPA_EVENT_LINE to its line
+    #[arg(long, value_name = "CMD")]
+    pub hook: Option<String>,
+}
+
+/// Polls the contract and prints every `ContractEvent` as it appears, running the hook on
+/// each, until the contract closes.
+pub fn watch_contract(
+    contract_address: &str,
+    rpc_url: &str,
+    options: &WatchOptions,
+) -> CliResult<()> {
+    let mut watch = ContractWatch::new(options.margin_secs);
+    emitln!("status=watching");
+    emitln!("contract={contract_address}");
+    emitln!("interval_secs={}", options.interval_secs);
+    loop {
+        let status = read_contract_status(contract_address, rpc_url)?;
+        tracing::debug!(stage = status.stage, now = status.now, "polled contract");
+        for event in watch.poll(status) {
+            let line = event.line()?;
+            emitln!("{line}");
+            if let Some(hook) = &options.hook {
+                run_event_hook(hook, event.kind(), &line);
+            }
+        }
+        if options.once || watch.is_closed() {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_secs(options.interval_secs));
+    }
+}
+
+/// Runs `hook` with `sh -c`. Its stdout goes to stderr so it cannot mix into the event lines,
+/// and a failing hook is logged without stopping the watch.
+fn run_event_hook(hook: &str, kind: &str, line: &str) {
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .env("PPA_EVENT", kind)
+        .env("PPA_EVENT_LINE", line)
+        .stdout(Stdio::from(io::stderr()))
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => tracing::warn!(event = kind, "hook exited with {status}"),
+        Err(e) => tracing::warn!(event = kind, "failed to run hook: {e}"),
+    }
+}
+
+/// Secret flag held by a `--session` directory, e.g. Alice's `--master-seed`.
+#[derive(Debug, Clone, Copy)]
+pub struct SessionSeed {
+    /// Commands that read the flag.
+    pub commands: &'static [&'static str],
+    pub flag: &'static str,
+    /// Entry name in the session's `seeds.txt`.
+    pub name: &'static str,
+    /// Draw a fresh seed when neither the flag nor the session has one.
+    pub generate: bool,
+}
+
+/// Applies `--session <dir>` to the arguments of `command`: flags in `persisted` that are
+/// missing are restored from the session and given ones are saved; `seeds` are handled the
+/// same way through `seeds.txt`. Without `--session` the arguments are returned unchanged.
+pub fn resume_session_args(
+    command: &str,
+    args: &[String],
+    persisted: &[&str],
+    seeds: &[SessionSeed],
+) -> CliResult<(Vec<String>, Option<SessionStore>)> {
+    let Some(dir) = parse_flag_value(args, "--session") else {
+        return Ok((args.to_vec(), None));
+    };
+    let mut store = SessionStore::open(dir)?;
+    let mut args = args.to_vec();
+    for flag in persisted {
+        match parse_flag_value(&args, flag) {
+            Some(value) => store.set_flag(flag, &value)?,
+            None => {
+                if let Some(value) = store.flag(flag) {
+                    args.push(format!("{flag}={value}"));
+                }
+            }
+        }
+    }
+    for seed in seeds.iter().filter(|seed| seed.commands.contains(&command)) {
+        match parse_flag_value(&args, seed.flag) {
+            Some(value) => store.set_seed(seed.name, parse_bytes32(&value)?)?,
+            None => {
+                let value = match store.seed(seed.name) {
+                    Some(value) => value,
+                    None if seed.generate => {
+                        let value = random_bytes32()?;
+                        store.set_seed(seed.name, value)?;
+                        value
+                    }
+                    None => continue,
+                };
+                args.push(format!("{}={}", seed.flag, hex32(value)));
+            }
+        }
+    }
+    Ok((args, Some(store)))
+}
+
+/// Prints where a session stopped: saved flags and seed names (not their values), completed
+/// commands, the phase of the last one and the commands still to run, per `command_phases`.
+pub fn print_session_resume(store: &SessionStore, command_phases: &[(Phase, &[&str])]) {
+    let completed = store.completed();
+    let flags = store
+        .flags()
+        .iter()
+        .map(|(flag, value)| format!("{flag}={value}"))
+        .collect::<Vec<_>>();
+    emitln!("session_dir={}", store.dir().display());
+    emitln!("saved_flags={}", flags.join(" "));
+    emitln!("saved_seeds={}", store.seed_names().join(","));
+    emitln!("completed={}", completed.join(","));
+
+    let position = completed.iter().rev().find_map(|command| {
+        command_phases
+            .iter()
+            .position(|(_, commands)| commands.contains(&command.as_str()))
+    });
+    let next = command_phases
+        .iter()
+        .skip(position.unwrap_or(0))
+        .find_map(|(phase, commands)| {
+            let pending = commands
+                .iter()
+                .copied()
+                .filter(|command| !completed.iter().any(|done| done == command))
+                .collect::<Vec<_>>();
+            (!pending.is_empty()).then_some((*phase, pending))
+        });
+    match position {
+        Some(idx) => emitln!("phase={}", command_phases[idx].0),
+        None => emitln!("phase=none"),
+    }
+    match next {
+        Some((phase, commands)) => {
+            emitln!("next_phase={phase}");
+            emitln!("next={}", commands.join(","));
+        }
+        None => emitln!("next="),
+    }
+}
+
+/// Parses a base-10 unsigned integer up to 256 bits into big-endian `uint256` bytes.
+pub fn parse_u256(value: &str, name: &str) -> CliResult<[u8; 32]> {
+    let digits = value.trim();
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("Invalid {name}: {value}").into());
+    }
+    let mut out = [0u8; 32];
+    for digit in digits.bytes() {
+        // out = out * 10 + digit, little-endian carry over big-endian bytes.
+        let mut carry = u16::from(digit - b'0');
+        for byte in out.iter_mut().rev() {
+            let next = u16::from(*byte) * 10 + carry;
+            *byte = (next & 0xff) as u8;
+            carry = next >> 8;
+        }
+        if carry != 0 {
+            return Err(format!("Invalid {name}: {value} exceeds 256 bits").into());
+        }
+    }
+    Ok(out)
+}
+
+/// Parses a number given on the command line, such as a private input or a deposit in wei:
+/// base-10 or `0x` hex, with `_` allowed between digits (`1_000_000`, `0xde_ad`), up to 256
+/// bits. `parse_u256` stays strictly decimal for what `cast` prints.
+pub fn parse_number(value: &str, name: &str) -> CliResult<[u8; 32]> {
+    let trimmed = value.trim();
+    let (hex, body) = match trimmed
+        .strip_prefix("0x")
+        .or_else(|| trimmed.strip_prefix("0X"))
+    {
+        Some(body) => (true, body),
+        None => (false, trimmed),
+    };
+    let digits = body.replace('_', "");
+    let well_formed = !body.starts_with('_')
+        && !body.ends_with('_')
+        && !body.contains("__")
+        && !digits.is_empty()
+        && digits.bytes().all(|b| {
+            if hex {
+                b.is_ascii_hexdigit()
+            } else {
+                b.is_ascii_digit()
+            }
+        });
+    if !well_formed {
+        return Err(format!("Invalid {name}: {value}").into());
+    }
+    let too_wide = || format!("Invalid {name}: {value} exceeds 256 bits");
+    if !hex {
+        return parse_u256(&digits, name).map_err(|_| too_wide().into());
+    }
+    let significant = digits.trim_start_matches('0');
+    if significant.len() > 64 {
+        return Err(too_wide().into());
+    }
+    parse_bytes32(&format!("{significant:0>64}"))
+}
+
+/// `parse_number` for a value that must fit in a `u64`, such as a bid.
+pub fn parse_number_u64(value: &str, name: &str) -> CliResult<u64> {
+    let number = parse_number(value, name)?;
+    if number[..24].iter().any(|byte| *byte != 0) {
+        return Err(format!("Invalid {name}: {value} exceeds 64 bits").into());
+    }
+    Ok(u64::from_be_bytes(
+        number[24..].try_into().expect("8 of 32 bytes"),
+    ))
+}
+
+/// `DEPOSIT_WEI` (default 1 ether) in base 10, as `cast send --value` takes it.
+pub fn deposit_wei() -> CliResult<String> {
+    let raw = env::var("DEPOSIT_WEI").unwrap_or_else(|_| "1000000000000000000".to_string());
+    Ok(u256_to_decimal(parse_number(&raw, "DEPOSIT_WEI")?))
+}
+
+/// Formats big-endian `uint256` bytes as a base-10 string.
+pub fn u256_to_decimal(value_be: [u8; 32]) -> String {
+    let mut work = value_be;
+    let mut digits = Vec::new();
+    while work.iter().any(|byte| *byte != 0) {
+        // work = work / 10, collecting the remainder as the next digit.
+        let mut rem = 0u16;
+        for byte in work.iter_mut() {
+            let cur = (rem << 8) | u16::from(*byte);
+            *byte = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+    }
+    if digits.is_empty() {
+        return "0".to_string();
+    }
+    digits.reverse();
+    String::from_utf8(digits).expect("ascii digits")
+}
+
 pub fn hex_prefixed(bytes: &[u8]) -> String {
     let mut out = String::with_capacity(2 + bytes.len() * 2);
     out.push_str("0x");
@@ -287,6 +1540,117 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_u256_round_trips_wei_amounts() {
+        let max = "115792089237316195423570985008687907853269984665640564039457584007913129639935";
+        assert_eq!(parse_u256(max, "x").unwrap(), [0xff; 32]);
+        assert_eq!(u256_to_decimal([0xff; 32]), max);
+
+        let wei = parse_u256("1200000000000000000", "x").unwrap();
+        assert_eq!(&wei[24..], &1_200_000_000_000_000_000u64.to_be_bytes());
+        assert_eq!(u256_to_decimal(wei), "1200000000000000000");
+        assert_eq!(u256_to_decimal([0u8; 32]), "0");
+
+        let overflow =
+            "115792089237316195423570985008687907853269984665640564039457584007913129639936";
+        assert!(parse_u256(overflow, "x").is_err());
+        assert!(parse_u256("12a", "x").is_err());
+        assert!(parse_u256("", "x").is_err());
+    }
+
+    #[test]
+    fn parse_number_takes_hex_and_digit_separators() {
+        let wei = parse_u256("1200000000000000000", "x").unwrap();
+        assert_eq!(parse_number("1_200_000_000_000_000_000", "x").unwrap(), wei);
+        assert_eq!(parse_number("0x10a7_41a4_6278_0000", "x").unwrap(), wei);
+        assert_eq!(parse_number(" 0X10A741A462780000 ", "x").unwrap(), wei);
+        assert_eq!(
+            parse_number(&format!("0x00{}", "ff".repeat(32)), "x").unwrap(),
+            [0xff; 32]
+        );
+        assert_eq!(parse_number("0x0", "x").unwrap(), [0; 32]);
+
+        let err = parse_number(&format!("0x1{}", "00".repeat(32)), "x").unwrap_err();
+        assert!(err.to_string().ends_with("exceeds 256 bits"), "{err}");
+        for invalid in ["", "0x", "_1", "1_", "1__0", "0x_1", "12a", "0xfg", "-1"] {
+            assert!(parse_number(invalid, "x").is_err(), "{invalid:?}");
+        }
+
+        assert_eq!(
+            parse_number_u64("0xffff_ffff_ffff_ffff", "bid").unwrap(),
+            u64::MAX
+        );
+        assert_eq!(parse_number_u64("70_000", "bid").unwrap(), 70_000);
+        let err = parse_number_u64("18446744073709551616", "bid").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid bid: 18446744073709551616 exceeds 64 bits"
+        );
+    }
+
+    #[test]
+    fn parse_leaf71_names_versioned_leaf_schemes() {
+        let v1 = format!("0x{}", "00".repeat(71));
+        assert_eq!(parse_leaf71(&v1).unwrap(), [0u8; 71]);
+
+        let v2 = format!("0x84{}", "00".repeat(7));
+        let err = parse_leaf71(&v2).unwrap_err().to_string();
+        assert!(err.contains("garbling scheme 0x04"), "{err}");
+    }
+
+    #[test]
+    fn only_explicit_answers_confirm() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES \n"));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("n\n"));
+        assert!(!is_yes("yep\n"));
+    }
+
+    #[test]
+    fn private_keys_move_off_the_cast_command_line() {
+        let args = [
+            "send",
+            "0xc0",
+            "--private-key",
+            "0xk1",
+            "--rpc-url",
+            "http://rpc",
+        ]
+        .map(String::from);
+        let (argv, key) = split_private_key(&args);
+        assert_eq!(
+            argv,
+            ["send", "0xc0", "--rpc-url", "http://rpc"].map(String::from)
+        );
+        assert_eq!(key.as_deref(), Some("0xk1"));
+
+        let args = ["send", "--private-key=0xk2", "0xc0"].map(String::from);
+        let (argv, key) = split_private_key(&args);
+        assert_eq!(argv, ["send", "0xc0"].map(String::from));
+        assert_eq!(key.as_deref(), Some("0xk2"));
+
+        let args = ["call", "0xc0", "owner()(address)"].map(String::from);
+        assert_eq!(split_private_key(&args), (args.to_vec(), None));
+    }
+
+    #[test]
+    fn logged_cast_commands_hide_private_keys() {
+        let args = [
+            "send",
+            "0xc0",
+            "--private-key",
+            "0xk1",
+            "--private-key=0xk2",
+            "--rpc-url",
+        ]
+        .map(String::from);
+        assert_eq!(
+            redacted_cast_command(&args),
+            "cast send 0xc0 --private-key <redacted> --private-key=<redacted> --rpc-url"
+        );
+    }
+
     #[test]
     fn tx_summary_lines_preserve_legacy_fallbacks() {
         assert_eq!(
@@ -0,0 +1,265 @@
+//! Clap building blocks shared by the Alice and Bob binaries: value parsers for hex and list
+//! arguments, the artifact compression flags, shell completions, and `--session`, which has
+//! to restore saved flags into the command line before clap parses it.
+
+use std::any::TypeId;
+use std::path::PathBuf;
+
+use clap::{Arg, Args, Command, CommandFactory, FromArgMatches};
+use clap_complete::Shell;
+
+use crate::cli::{
+    CliResult, SessionSeed, parse_bytes32, parse_bytes32_list_csv, parse_fixed_bytes, parse_number,
+    parse_number_u64, read_block_hash, resume_session_args,
+};
+use crate::compression::{ArtifactCompression, DEFAULT_ZSTD_LEVEL};
+use crate::protocol::Phase;
+use crate::registry::{CircuitVersion, lookup_circuit};
+use crate::scenario::SessionParams;
+use crate::scenario::beacon::{
+    BeaconEvidence, RandomnessSource, VRF_PROOF_LEN, VRF_PUBLIC_KEY_LEN,
+};
+use crate::session_store::SessionStore;
+
+/// List argument given as one comma-separated value, e.g. `--root-gcs 0x..,0x..`. A plain
+/// `Vec` field would make clap read the flag as repeatable instead.
+pub type ValueList<T> = Vec<T>;
+
+/// Parses a 32-byte hex value such as a seed, salt or commitment.
+pub fn bytes32_arg(raw: &str) -> Result<[u8; 32], String> {
+    parse_bytes32(raw).map_err(|e| e.to_string())
+}
+
+/// Parses a 20-byte hex address.
+pub fn address_arg(raw: &str) -> Result<[u8; 20], String> {
+    parse_fixed_bytes::<20>(raw).map_err(|e| e.to_string())
+}
+
+/// Parses a comma-separated list of 32-byte hex values, optionally in brackets.
+pub fn bytes32_list_arg(raw: &str) -> Result<Vec<[u8; 32]>, String> {
+    parse_bytes32_list_csv(raw).map_err(|e| e.to_string())
+}
+
+/// Parses a decimal or `0x` hex `uint256`, such as a party's private input (`cli::parse_number`).
+pub fn u256_arg(raw: &str) -> Result<[u8; 32], String> {
+    parse_number(raw, "uint256").map_err(|e| e.to_string())
+}
+
+/// Parses a decimal or `0x` hex `u64` (`cli::parse_number_u64`).
+pub fn u64_arg(raw: &str) -> Result<u64, String> {
+    parse_number_u64(raw, "u64").map_err(|e| e.to_string())
+}
+
+/// Resolves a circuit name against the circuit registry.
+pub fn circuit_arg(raw: &str) -> Result<&'static CircuitVersion, String> {
+    lookup_circuit(raw)
+}
+
+/// Parses the cut-and-choose instance count of `--n`.
+pub fn session_params_arg(raw: &str) -> Result<SessionParams, String> {
+    let n = raw
+        .parse::<usize>()
+        .map_err(|_| format!("invalid instance count {raw:?}"))?;
+    SessionParams::new(n)
+}
+
+/// Parses a compressed secp256k1 VRF public key.
+pub fn vrf_key_arg(raw: &str) -> Result<[u8; VRF_PUBLIC_KEY_LEN], String> {
+    parse_fixed_bytes::<VRF_PUBLIC_KEY_LEN>(raw).map_err(|e| e.to_string())
+}
+
+/// Parses an ECVRF proof (`Gamma || c || s`).
+pub fn vrf_proof_arg(raw: &str) -> Result<[u8; VRF_PROOF_LEN], String> {
+    parse_fixed_bytes::<VRF_PROOF_LEN>(raw).map_err(|e| e.to_string())
+}
+
+/// Parses a zstd level within the range `ArtifactCompression::zstd` accepts.
+pub fn zstd_level_arg(raw: &str) -> Result<i32, String> {
+    let level = raw
+        .parse::<i32>()
+        .map_err(|_| format!("zstd level must be within 1..=22, got {raw}"))?;
+    ArtifactCompression::zstd(level)?;
+    Ok(level)
+}
+
+/// `--compress` and `--zstd-level` of the commands that write artifacts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Args)]
+pub struct CompressionArgs {
+    /// Write artifacts zstd-compressed (`<name>.zst`)
+    #[arg(long)]
+    pub compress: bool,
+    /// zstd level; implies --compress [default level: 3]
+    #[arg(long, value_name = "1..=22", value_parser = zstd_level_arg)]
+    pub zstd_level: Option<i32>,
+}
+
+impl CompressionArgs {
+    pub fn compression(self) -> ArtifactCompression {
+        match (self.compress, self.zstd_level) {
+            (false, None) => ArtifactCompression::None,
+            (_, level) => ArtifactCompression::Zstd {
+                level: level.unwrap_or(DEFAULT_ZSTD_LEVEL),
+            },
+        }
+    }
+}
+
+/// Randomness source of `beacon-m` and `verify-beacon-m` (`scenario::beacon`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Args)]
+pub struct BeaconArgs {
+    /// Derive m from the hash of this block
+    #[arg(long, value_name = "HEIGHT", conflicts_with_all = ["vrf_key", "vrf_proof"])]
+    pub block_height: Option<u64>,
+    /// Published hash of --block-height; must match the block on RPC_URL
+    #[arg(long, value_name = "0x..32", value_parser = bytes32_arg, requires = "block_height")]
+    pub block_hash: Option<[u8; 32]>,
+    /// VRF public key agreed in advance
+    #[arg(long, value_name = "0x..33", value_parser = vrf_key_arg)]
+    pub vrf_key: Option<[u8; VRF_PUBLIC_KEY_LEN]>,
+    /// Published VRF proof under --vrf-key
+    #[arg(long, value_name = "0x..81", value_parser = vrf_proof_arg, requires = "vrf_key")]
+    pub vrf_proof: Option<[u8; VRF_PROOF_LEN]>,
+}
+
+impl BeaconArgs {
+    /// The source and its evidence: the hash of `--block-height` read from `rpc_url`, or
+    /// `--vrf-key` with its `--vrf-proof`. `None` when no proof is given yet, which only the
+    /// VRF key holder can produce.
+    pub fn evidence(&self, rpc_url: &str) -> CliResult<Option<(RandomnessSource, BeaconEvidence)>> {
+        if let Some(height) = self.block_height {
+            let hash = read_block_hash(height, self.block_hash, rpc_url)?;
+            return Ok(Some((
+                RandomnessSource::BlockHash { height },
+                BeaconEvidence::BlockHash { height, hash },
+            )));
+        }
+        Ok(self.vrf_key.zip(self.vrf_proof).map(|(public_key, proof)| {
+            (
+                RandomnessSource::Vrf { public_key },
+                BeaconEvidence::Vrf { proof },
+            )
+        }))
+    }
+}
+
+/// Shell of the `completions` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Args)]
+pub struct CompletionsArgs {
+    /// Shell to print the completion script for
+    #[arg(value_enum)]
+    pub shell: Shell,
+}
+
+/// `C`'s command with the global `--session <dir>` flag and a help footer listing the
+/// commands by the protocol phase they drive and the command run without arguments.
+pub fn cli_command<C: CommandFactory>(
+    command_phases: &[(Phase, &[&str])],
+    default_command: &str,
+) -> Command {
+    let mut footer = String::from("Protocol phases:\n");
+    for (phase, commands) in command_phases {
+        footer.push_str(&format!("  {phase}: {}\n", commands.join(", ")));
+    }
+    footer.push_str(&format!(
+        "\nDefault command with no args: {default_command}"
+    ));
+    C::command()
+        .arg(
+            Arg::new("session")
+                .long("session")
+                .value_name("DIR")
+                .global(true)
+                .help("Save this command's session flags in DIR (e.g. .session) and reuse them"),
+        )
+        .after_help(footer)
+}
+
+/// Long names of the `PathBuf` flags of `command`'s `subcommands`, the flags the `serve`
+/// daemon confines to its directory.
+pub fn path_flags(command: &Command, subcommands: &[&str]) -> Vec<String> {
+    let mut flags = command
+        .get_subcommands()
+        .filter(|sub| subcommands.contains(&sub.get_name()))
+        .flat_map(Command::get_arguments)
+        .filter(|arg| arg.get_value_parser().type_id() == TypeId::of::<PathBuf>())
+        .filter_map(|arg| arg.get_long().map(str::to_string))
+        .collect::<Vec<_>>();
+    flags.sort();
+    flags.dedup();
+    flags
+}
+
+/// Global flags a `serve` call may not pass: they would point the command at another
+/// session directory or network profile, or wait for a key on the daemon's stdin.
+pub const SERVE_REFUSED_FLAGS: &[&str] = &["session", "profile", "key-stdin"];
+
+/// Parses `argv` (program name first) with `command`, exiting with clap's usage message on
+/// errors and after printing `--help`.
+pub fn parse_cli<C: FromArgMatches>(mut command: Command, argv: &[String]) -> C {
+    let matches = command
+        .try_get_matches_from_mut(argv)
+        .unwrap_or_else(|e| e.exit());
+    C::from_arg_matches(&matches).unwrap_or_else(|e| e.format(&mut command).exit())
+}
+
+/// Completion script of `command` for `shell`, completing the subcommands and long flags of
+/// the binary named after the command.
+pub fn completion_script(mut command: Command, shell: Shell) -> String {
+    let name = command.get_name().to_string();
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    String::from_utf8(script).expect("clap_complete writes UTF-8")
+}
+
+/// Global flags of the binaries that take a separate value, e.g. `--profile sepolia`.
+const GLOBAL_VALUE_FLAGS: &[&str] = &["--session", "--profile"];
+
+/// Name of the subcommand `argv` (program name first) invokes, or `default_command`.
+/// Only the global `--session` and `--profile` take a value before the subcommand.
+pub fn subcommand_name<'a>(argv: &'a [String], default_command: &'a str) -> &'a str {
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if GLOBAL_VALUE_FLAGS.contains(&arg.as_str()) {
+            args.next();
+        } else if !arg.starts_with('-') {
+            return arg;
+        }
+    }
+    default_command
+}
+
+/// Applies `--session <dir>` to `argv` (program name first) through `resume_session_args`,
+/// restoring only the flags and seeds the invoked subcommand of `command` accepts: a flag
+/// saved by one command must not make clap reject the arguments of another. Returns the
+/// subcommand name, the arguments to parse and the session. Unknown subcommands are left for
+/// clap to reject.
+pub fn restore_session_argv(
+    command: &Command,
+    argv: &[String],
+    default_command: &str,
+    persisted: &[&str],
+    seeds: &[SessionSeed],
+) -> CliResult<(String, Vec<String>, Option<SessionStore>)> {
+    let name = subcommand_name(argv, default_command).to_string();
+    let Some(subcommand) = command.find_subcommand(&name) else {
+        return Ok((name, argv.to_vec(), None));
+    };
+    let accepted = subcommand
+        .get_arguments()
+        .filter_map(Arg::get_long)
+        .map(|long| format!("--{long}"))
+        .collect::<Vec<_>>();
+    let accepts = |flag: &str| accepted.iter().any(|long| long == flag);
+    let persisted = persisted
+        .iter()
+        .copied()
+        .filter(|flag| accepts(flag))
+        .collect::<Vec<_>>();
+    let seeds = seeds
+        .iter()
+        .copied()
+        .filter(|seed| accepts(seed.flag))
+        .collect::<Vec<_>>();
+    let (args, session) = resume_session_args(&name, argv, &persisted, &seeds)?;
+    Ok((name, args, session))
+}
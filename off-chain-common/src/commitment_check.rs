@@ -0,0 +1,129 @@
+//! Alice's preflight for `submit-commitments`: the per-instance values re-derived from the
+//! session config, compared with the values about to be submitted or already on chain.
+//!
+//! A commitment built from the wrong parameters is only noticed once an opened instance fails
+//! its check and Alice's deposit is at risk. The mismatching fields point at the parameter
+//! that drifted: instance seeds depend on the master seed and the circuit id, garbled circuits
+//! also on the circuit and the bit width, and the output anchors `h0`/`h1` are committed to
+//! through the eval blob `blobHashGC` hashes.
+
+use std::fmt;
+
+use crate::cli::hex32;
+
+/// A per-instance value Alice commits to, directly or through the eval blob.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitmentField {
+    ComSeed,
+    RootGc,
+    BlobHashGc,
+    H0,
+    H1,
+}
+
+impl fmt::Display for CommitmentField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::ComSeed => "comSeed",
+            Self::RootGc => "rootGC",
+            Self::BlobHashGc => "blobHashGC",
+            Self::H0 => "h0",
+            Self::H1 => "h1",
+        })
+    }
+}
+
+/// Values of one field, indexed by instance id.
+pub type FieldValues = (CommitmentField, Vec<[u8; 32]>);
+
+/// The values of every instance as re-derived from the session config.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DerivedCommitments {
+    pub com_seeds: Vec<[u8; 32]>,
+    pub root_gcs: Vec<[u8; 32]>,
+    pub blob_hashes: Vec<[u8; 32]>,
+    pub h0: Vec<[u8; 32]>,
+    pub h1: Vec<[u8; 32]>,
+}
+
+impl DerivedCommitments {
+    pub fn values(&self, field: CommitmentField) -> &[[u8; 32]] {
+        match field {
+            CommitmentField::ComSeed => &self.com_seeds,
+            CommitmentField::RootGc => &self.root_gcs,
+            CommitmentField::BlobHashGc => &self.blob_hashes,
+            CommitmentField::H0 => &self.h0,
+            CommitmentField::H1 => &self.h1,
+        }
+    }
+}
+
+/// A value of `source` that differs from the re-derived one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitmentMismatch {
+    pub source: String,
+    pub field: CommitmentField,
+    pub instance_id: usize,
+    pub expected: [u8; 32],
+    pub found: [u8; 32],
+}
+
+impl fmt::Display for CommitmentMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: instance {} {} is {}, re-derived {}",
+            self.source,
+            self.instance_id,
+            self.field,
+            hex32(self.found),
+            hex32(self.expected)
+        )
+    }
+}
+
+/// Compares the lists of `source` with `derived`, instance by instance. A list of another
+/// length than the re-derived one is an error: it was built for a different `n`.
+pub fn check_commitments(
+    derived: &DerivedCommitments,
+    source: &str,
+    lists: &[FieldValues],
+) -> Result<Vec<CommitmentMismatch>, String> {
+    let mut mismatches = Vec::new();
+    for (field, values) in lists {
+        let expected = derived.values(*field);
+        if values.len() != expected.len() {
+            return Err(format!(
+                "{source} lists {} {field} values, expected {}",
+                values.len(),
+                expected.len()
+            ));
+        }
+        for (instance_id, (expected, found)) in expected.iter().zip(values).enumerate() {
+            if expected != found {
+                mismatches.push(CommitmentMismatch {
+                    source: source.to_string(),
+                    field: *field,
+                    instance_id,
+                    expected: *expected,
+                    found: *found,
+                });
+            }
+        }
+    }
+    Ok(mismatches)
+}
+
+/// The parameters most likely to have drifted, given `mismatches`.
+pub fn drift_hint(mismatches: &[CommitmentMismatch]) -> Option<&'static str> {
+    let has = |field| mismatches.iter().any(|m| m.field == field);
+    if has(CommitmentField::ComSeed) {
+        Some("instance seeds differ: check --master-seed, --circuit-id and --bit-width")
+    } else if has(CommitmentField::RootGc) {
+        Some("garbled circuits differ: check --circuit and --bit-width")
+    } else if !mismatches.is_empty() {
+        Some("eval blobs differ: check --bit-width and re-export the artifacts")
+    } else {
+        None
+    }
+}
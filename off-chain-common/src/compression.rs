@@ -0,0 +1,184 @@
+//! Optional zstd compression for exported instance artifacts and eval directories.
+//!
+//! Leaf sets of wider circuits run to megabytes per instance, and an export holds one per
+//! cut-and-choose instance. A compressed artifact is written next to where the plain one would
+//! be, as `<name>.zst`; readers go through `read_artifact`, which accepts either file and
+//! recognises zstd frames by their magic, so every consumer stays agnostic of how the producer
+//! was configured. Paths that run through a `bundle` file resolve to its entries. Hashes
+//! (`blobHashGC`, message signatures) are always taken over the uncompressed bytes. Secret
+//! artifacts may additionally be encrypted (`encryption`), which `read_artifact` undoes first.
+
+use std::fs;
+use std::io::{BufRead, BufReader, Cursor, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use crate::bundle::{Bundle, locate_in_bundle};
+use crate::encryption::{ArtifactKey, decrypt_artifact, is_encrypted};
+
+/// File-name suffix of a compressed artifact.
+pub const ZSTD_SUFFIX: &str = ".zst";
+
+/// zstd frame magic number (`0xFD2FB528`, little endian).
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Level used by `--compress` without `--zstd-level`.
+pub const DEFAULT_ZSTD_LEVEL: i32 = 3;
+
+/// How artifact files are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArtifactCompression {
+    #[default]
+    None,
+    /// zstd at the given level (`1..=22`).
+    Zstd { level: i32 },
+}
+
+impl ArtifactCompression {
+    pub fn zstd(level: i32) -> Result<Self, String> {
+        if !(1..=22).contains(&level) {
+            return Err(format!("zstd level must be within 1..=22, got {level}"));
+        }
+        Ok(Self::Zstd { level })
+    }
+
+    /// File actually written for the artifact `path`.
+    pub fn artifact_path(self, path: &Path) -> PathBuf {
+        match self {
+            Self::None => path.to_path_buf(),
+            Self::Zstd { .. } => compressed_path(path),
+        }
+    }
+}
+
+/// `path` with `ZSTD_SUFFIX` appended.
+pub fn compressed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(ZSTD_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Whether `bytes` start with a zstd frame.
+pub fn is_zstd(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ZSTD_MAGIC)
+}
+
+pub fn compress(bytes: &[u8], level: i32) -> Result<Vec<u8>, String> {
+    zstd::bulk::compress(bytes, level).map_err(|e| format!("zstd compression failed: {e}"))
+}
+
+pub fn decompress(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    zstd::stream::decode_all(bytes).map_err(|e| format!("zstd decompression failed: {e}"))
+}
+
+/// Writes the artifact `path` (as `<path>.zst` when compressing) and removes the other variant,
+/// so a re-export never leaves a stale copy for `read_artifact` to pick up. Returns the file
+/// written.
+pub fn write_artifact(
+    path: &Path,
+    bytes: &[u8],
+    compression: ArtifactCompression,
+) -> Result<PathBuf, String> {
+    write_artifact_with(path, bytes, compression, None)
+}
+
+/// `write_artifact`, encrypting the (compressed) contents under `key` when given.
+pub fn write_artifact_with(
+    path: &Path,
+    bytes: &[u8],
+    compression: ArtifactCompression,
+    key: Option<&ArtifactKey>,
+) -> Result<PathBuf, String> {
+    let (target, stale, mut contents) = match compression {
+        ArtifactCompression::None => (path.to_path_buf(), compressed_path(path), bytes.to_vec()),
+        ArtifactCompression::Zstd { level } => (
+            compressed_path(path),
+            path.to_path_buf(),
+            compress(bytes, level)?,
+        ),
+    };
+    if let Some(key) = key {
+        contents = key.encrypt(&contents)?;
+    }
+    fs::write(&target, contents)
+        .map_err(|e| format!("failed to write {}: {e}", target.display()))?;
+    match fs::remove_file(&stale) {
+        Ok(()) => Ok(target),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(target),
+        Err(e) => Err(format!("failed to remove stale {}: {e}", stale.display())),
+    }
+}
+
+/// File backing the artifact `path`: `path` itself, else `<path>.zst`. Inside a bundle
+/// (`bundle::locate_in_bundle`) the same lookup runs over the bundle's entries.
+pub fn find_artifact(path: &Path) -> Option<PathBuf> {
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+    let compressed = compressed_path(path);
+    if compressed.exists() {
+        return Some(compressed);
+    }
+    let (bundle_path, name) = locate_in_bundle(path)?;
+    let bundle = Bundle::open(&bundle_path).ok()?;
+    if bundle.entry(&name).is_some() {
+        Some(path.to_path_buf())
+    } else {
+        let compressed_name = format!("{name}{ZSTD_SUFFIX}");
+        bundle.entry(&compressed_name).map(|_| compressed)
+    }
+}
+
+/// Reads the artifact `path` or `<path>.zst`, from disk or from a bundle, decrypting
+/// encrypted files and decompressing zstd frames.
+pub fn read_artifact(path: &Path) -> Result<Vec<u8>, String> {
+    let file = find_artifact(path).unwrap_or_else(|| path.to_path_buf());
+    let bytes = match locate_in_bundle(&file) {
+        Some((bundle_path, name)) if !file.exists() => Bundle::open(&bundle_path)?.read(&name)?,
+        _ => fs::read(&file).map_err(|e| format!("failed to read {}: {e}", file.display()))?,
+    };
+    decode_artifact(&bytes).map_err(|e| format!("{}: {e}", file.display()))
+}
+
+/// Stored artifact bytes as written by the producer: decrypted, then decompressed.
+pub fn decode_artifact(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let decrypted;
+    let bytes = if is_encrypted(bytes) {
+        decrypted = decrypt_artifact(bytes)?;
+        &decrypted[..]
+    } else {
+        bytes
+    };
+    if is_zstd(bytes) {
+        decompress(bytes)
+    } else {
+        Ok(bytes.to_vec())
+    }
+}
+
+/// Opens the artifact `path` like `read_artifact`, but as a buffered reader: a plain or zstd
+/// file on disk is streamed without being read into memory first. Bundle entries and encrypted
+/// files (whose tag covers the whole ciphertext) are still loaded whole.
+pub fn open_artifact(path: &Path) -> Result<Box<dyn BufRead>, String> {
+    let file = find_artifact(path).unwrap_or_else(|| path.to_path_buf());
+    if !file.exists() && locate_in_bundle(&file).is_some() {
+        return Ok(Box::new(Cursor::new(read_artifact(path)?)));
+    }
+    let read_err = |e| format!("failed to read {}: {e}", file.display());
+    let mut reader = BufReader::new(fs::File::open(&file).map_err(read_err)?);
+    let head = reader.fill_buf().map_err(read_err)?;
+    if is_encrypted(head) {
+        return Ok(Box::new(Cursor::new(read_artifact(path)?)));
+    }
+    if is_zstd(head) {
+        let decoder = zstd::stream::read::Decoder::with_buffer(reader)
+            .map_err(|e| format!("{}: zstd decompression failed: {e}", file.display()))?;
+        return Ok(Box::new(BufReader::new(decoder)));
+    }
+    Ok(Box::new(reader))
+}
+
+/// `read_artifact` for text artifacts.
+pub fn read_artifact_to_string(path: &Path) -> Result<String, String> {
+    String::from_utf8(read_artifact(path)?)
+        .map_err(|_| format!("{} is not valid UTF-8", path.display()))
+}
@@ -1,20 +1,150 @@
+use std::sync::OnceLock;
+
+use aes::Aes128;
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use sha2::Sha256;
 use sha3::{Digest, Keccak256};
 
-use crate::types::{GateDesc, GateType};
+use crate::types::{GateDesc, GateType, LeafFormat, PadFunction, SchemeId};
 
 /// Packed gate-leaf length used by Solidity (`1 + 2 + 2 + 2 + 4*16`).
 pub const LEAF_BYTES_LEN: usize = 71;
 
+/// Hash backend of the garbling derivations. The provided methods spell out the domain
+/// separation of the classic scheme once; `Keccak` is the frozen consensus backend that
+/// Solidity recomputes, other backends only drive experimental, off-chain scheme variants.
+pub trait ConsensusHasher: std::fmt::Debug {
+    /// Short backend name used in benchmark and diagnostic output.
+    fn name(&self) -> &'static str;
+
+    /// 32-byte digest of the concatenated `parts` (`abi.encodePacked` style).
+    fn hash(&self, parts: &[&[u8]]) -> [u8; 32];
+
+    /// `H("P", circuitId, instanceId, wireId, seed) & 1`.
+    fn wire_flip_bit(
+        &self,
+        circuit_id: [u8; 32],
+        instance_id: u64,
+        wire_id: u16,
+        seed: [u8; 32],
+    ) -> u8 {
+        let instance = uint256_from_u64(instance_id);
+        // Domain "P" separates point-and-permute randomness from other hashes.
+        let h = self.hash(&[b"P", &circuit_id, &instance, &wire_id.to_be_bytes(), &seed]);
+        h[31] & 1
+    }
+
+    /// First 16 bytes of `H("L", ...)` with first-byte LSB rewritten to `flip XOR semantic`.
+    fn wire_label(
+        &self,
+        circuit_id: [u8; 32],
+        instance_id: u64,
+        wire_id: u16,
+        semantic_bit: u8,
+        seed: [u8; 32],
+    ) -> [u8; 16] {
+        let instance = uint256_from_u64(instance_id);
+        let bit = [semantic_bit & 1];
+        // Base label body comes from domain "L".
+        let h = self.hash(&[
+            b"L",
+            &circuit_id,
+            &instance,
+            &wire_id.to_be_bytes(),
+            &bit,
+            &seed,
+        ]);
+
+        let mut label = [0u8; 16];
+        label.copy_from_slice(&h[..16]);
+
+        // Force first-byte LSB to permutation bit as in Solidity.
+        let flip = self.wire_flip_bit(circuit_id, instance_id, wire_id, seed);
+        let permute = (flip ^ (semantic_bit & 1)) & 1;
+        label[0] = (label[0] & 0xFE) | permute;
+        label
+    }
+
+    /// `H("K", circuitId, instanceId, gateIndex, permA, permB, labelA, labelB)`.
+    fn row_key(
+        &self,
+        circuit_id: [u8; 32],
+        instance_id: u64,
+        gate_index: u64,
+        [perm_a, perm_b]: [u8; 2],
+        [label_a, label_b]: [[u8; 16]; 2],
+    ) -> [u8; 32] {
+        let instance = uint256_from_u64(instance_id);
+        let gate = uint256_from_u64(gate_index);
+        // `perm_a` and `perm_b` are serialized as packed uint8 values.
+        let pa = [perm_a & 1];
+        let pb = [perm_b & 1];
+        self.hash(&[
+            b"K",
+            &circuit_id,
+            &instance,
+            &gate,
+            &pa,
+            &pb,
+            &label_a,
+            &label_b,
+        ])
+    }
+
+    /// First 16 bytes of `H("PAD", rowKey)`.
+    fn pad(&self, row_key: [u8; 32]) -> [u8; 16] {
+        let h = self.hash(&[b"PAD", &row_key]);
+        let mut out = [0u8; 16];
+        out.copy_from_slice(&h[..16]);
+        out
+    }
+}
+
+/// Consensus backend: `keccak256`, as used by the contracts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Keccak;
+
+impl ConsensusHasher for Keccak {
+    fn name(&self) -> &'static str {
+        "keccak256"
+    }
+
+    fn hash(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        for part in parts {
+            // Equivalent to appending bytes in `abi.encodePacked(...)`.
+            hasher.update(part);
+        }
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&hasher.finalize());
+        out
+    }
+}
+
+/// Experimental SHA-256 backend; its leaves cannot be checked on-chain.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sha256Hasher;
+
+impl ConsensusHasher for Sha256Hasher {
+    fn name(&self) -> &'static str {
+        "sha256"
+    }
+
+    fn hash(&self, parts: &[&[u8]]) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        for part in parts {
+            hasher.update(part);
+        }
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&hasher.finalize());
+        out
+    }
+}
+
 /// Computes `keccak256` over concatenated byte slices, equivalent to `abi.encodePacked(...)`.
 pub fn keccak256(parts: &[&[u8]]) -> [u8; 32] {
-    let mut hasher = Keccak256::new();
-    for part in parts {
-        // Equivalent to appending bytes in `abi.encodePacked(...)`.
-        hasher.update(part);
-    }
-    let mut out = [0u8; 32];
-    out.copy_from_slice(&hasher.finalize());
-    out
+    Keccak.hash(parts)
 }
 
 /// Encodes a Rust `u64` into Solidity `uint256` big-endian bytes.
@@ -33,10 +163,7 @@ pub fn derive_wire_flip_bit(
     wire_id: u16,
     seed: [u8; 32],
 ) -> u8 {
-    let instance = uint256_from_u64(instance_id);
-    // Domain "P" separates point-and-permute randomness from other hashes.
-    let h = keccak256(&[b"P", &circuit_id, &instance, &wire_id.to_be_bytes(), &seed]);
-    h[31] & 1
+    Keccak.wire_flip_bit(circuit_id, instance_id, wire_id, seed)
 }
 
 /// Mirrors Solidity `deriveWireLabel`:
@@ -47,29 +174,43 @@ pub fn derive_wire_label(
     wire_id: u16,
     semantic_bit: u8,
     seed: [u8; 32],
+) -> [u8; 16] {
+    Keccak.wire_label(circuit_id, instance_id, wire_id, semantic_bit, seed)
+}
+
+/// Public label of a constant wire carrying `value`:
+/// first 16 bytes of `keccak256("C", circuitId, instanceId, wireId, value)` with first-byte LSB
+/// set to `value`, i.e. constant wires use flip bit `0`. No seed is involved, so the evaluator
+/// derives it on its own and the garbler cannot substitute the opposite constant.
+pub fn derive_constant_label(
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    wire_id: u16,
+    value: u8,
 ) -> [u8; 16] {
     let instance = uint256_from_u64(instance_id);
-    let bit = [semantic_bit & 1];
-    // Base label body comes from domain "L".
-    let h = keccak256(&[
-        b"L",
-        &circuit_id,
-        &instance,
-        &wire_id.to_be_bytes(),
-        &bit,
-        &seed,
-    ]);
+    let bit = [value & 1];
+    let h = keccak256(&[b"C", &circuit_id, &instance, &wire_id.to_be_bytes(), &bit]);
 
     let mut label = [0u8; 16];
     label.copy_from_slice(&h[..16]);
-
-    // Force first-byte LSB to permutation bit as in Solidity.
-    let flip = derive_wire_flip_bit(circuit_id, instance_id, wire_id, seed);
-    let permute = (flip ^ (semantic_bit & 1)) & 1;
-    label[0] = (label[0] & 0xFE) | permute;
+    label[0] = (label[0] & 0xFE) | (value & 1);
     label
 }
 
+/// Free-XOR global offset of one instance:
+/// first 16 bytes of `keccak256("DELTA", circuitId, instanceId, seed)` with first-byte LSB
+/// forced to `1`, so `L0` and `L1 = L0 XOR delta` always carry opposite permutation bits.
+pub fn derive_free_xor_delta(circuit_id: [u8; 32], instance_id: u64, seed: [u8; 32]) -> [u8; 16] {
+    let instance = uint256_from_u64(instance_id);
+    let h = keccak256(&[b"DELTA", &circuit_id, &instance, &seed]);
+
+    let mut delta = [0u8; 16];
+    delta.copy_from_slice(&h[..16]);
+    delta[0] |= 1;
+    delta
+}
+
 /// Mirrors Solidity `computeRowKey`:
 /// `keccak256("K", circuitId, instanceId, gateIndex, permA, permB, labelA, labelB)`.
 pub fn compute_row_key(
@@ -81,29 +222,51 @@ pub fn compute_row_key(
     label_a: [u8; 16],
     label_b: [u8; 16],
 ) -> [u8; 32] {
-    let instance = uint256_from_u64(instance_id);
-    let gate = uint256_from_u64(gate_index);
-    // `perm_a` and `perm_b` are serialized as packed uint8 values.
-    let pa = [perm_a & 1];
-    let pb = [perm_b & 1];
-    keccak256(&[
-        b"K",
-        &circuit_id,
-        &instance,
-        &gate,
-        &pa,
-        &pb,
-        &label_a,
-        &label_b,
-    ])
+    Keccak.row_key(
+        circuit_id,
+        instance_id,
+        gate_index,
+        [perm_a, perm_b],
+        [label_a, label_b],
+    )
 }
 
 /// Mirrors Solidity `expandPad`: first 16 bytes of `keccak256("PAD", rowKey)`.
 pub fn expand_pad(row_key: [u8; 32]) -> [u8; 16] {
-    let h = keccak256(&[b"PAD", &row_key]);
+    Keccak.pad(row_key)
+}
+
+/// Public key of the fixed-key AES pad: first 16 bytes of `keccak256("FIXED-AES-KEY")`.
+fn fixed_aes() -> &'static Aes128 {
+    static FIXED_AES: OnceLock<Aes128> = OnceLock::new();
+    FIXED_AES.get_or_init(|| {
+        let key = keccak256(&[b"FIXED-AES-KEY"]);
+        Aes128::new(GenericArray::from_slice(&key[..16]))
+    })
+}
+
+/// Fixed-key AES pad: folds the row key to `x = rowKey[..16] XOR rowKey[16..]` and returns
+/// `AES_k(x) XOR x` (Matyas-Meyer-Oseas with the public key `k`).
+pub fn expand_pad_aes(row_key: [u8; 32]) -> [u8; 16] {
+    let mut lo = [0u8; 16];
+    let mut hi = [0u8; 16];
+    lo.copy_from_slice(&row_key[..16]);
+    hi.copy_from_slice(&row_key[16..]);
+    let folded = xor16(lo, hi);
+
+    let mut block = GenericArray::clone_from_slice(&folded);
+    fixed_aes().encrypt_block(&mut block);
     let mut out = [0u8; 16];
-    out.copy_from_slice(&h[..16]);
-    out
+    out.copy_from_slice(&block);
+    xor16(out, folded)
+}
+
+/// Expands `row_key` with the layout's `PadFunction`.
+pub fn expand_pad_with(pad_function: PadFunction, row_key: [u8; 32]) -> [u8; 16] {
+    match pad_function {
+        PadFunction::Keccak => expand_pad(row_key),
+        PadFunction::FixedKeyAes => expand_pad_aes(row_key),
+    }
 }
 
 /// XOR helper for 16-byte labels/pads.
@@ -134,8 +297,84 @@ pub fn encode_leaf(gate: GateDesc, rows: [[u8; 16]; 4]) -> [u8; LEAF_BYTES_LEN]
     out
 }
 
+/// High bit of a versioned leaf's first byte; v1 leaves start with a gate opcode below it.
+pub const LEAF_VERSION_FLAG: u8 = 0x80;
+
+/// Byte length of one `RowAuthMode::Mac` row tag.
+pub const ROW_MAC_LEN: usize = 8;
+
+/// Tag of one garbled row: `keccak256("ROWMAC", rowKey, outLabel)[..8]`. Only holders of
+/// the row key (the garbler and an evaluator with the matching input labels) can recompute
+/// it, and it binds the row to the label it must decrypt to.
+pub fn row_mac(row_key: [u8; 32], out_label: [u8; 16]) -> [u8; ROW_MAC_LEN] {
+    let digest = keccak256(&[b"ROWMAC", &row_key, &out_label]);
+    let mut out = [0u8; ROW_MAC_LEN];
+    out.copy_from_slice(&digest[..ROW_MAC_LEN]);
+    out
+}
+
+/// Encodes a `LeafFormat::V2` leaf:
+/// `(0x80 | schemeId) || gateType || wireA || wireB || wireC || rows... || macs...`, where
+/// `rows` are the transmitted rows (`row1..`) of a garbled gate and empty for gates without
+/// ciphertexts, and `macs` holds one tag per garbled row (`row0..`) under `RowAuthMode::Mac`.
+pub fn encode_leaf_v2(
+    scheme_id: SchemeId,
+    gate: GateDesc,
+    rows: &[[u8; 16]],
+    macs: &[[u8; ROW_MAC_LEN]],
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(8 + 16 * rows.len() + ROW_MAC_LEN * macs.len());
+    out.push(LEAF_VERSION_FLAG | scheme_id.to_byte());
+    out.push(gate.gate_type as u8);
+    out.extend_from_slice(&gate.wire_a.to_be_bytes());
+    out.extend_from_slice(&gate.wire_b.to_be_bytes());
+    out.extend_from_slice(&gate.wire_c.to_be_bytes());
+    for row in rows {
+        out.extend_from_slice(row);
+    }
+    for mac in macs {
+        out.extend_from_slice(mac);
+    }
+    out
+}
+
+/// Scheme carried by a leaf's version byte, or `None` for an unversioned 71-byte v1 leaf.
+pub fn leaf_scheme_id(leaf: &[u8]) -> Result<Option<SchemeId>, String> {
+    let first = *leaf.first().ok_or_else(|| "empty leaf".to_string())?;
+    if first & LEAF_VERSION_FLAG == 0 {
+        return Ok(None);
+    }
+    let scheme_id = SchemeId::from_byte(first & !LEAF_VERSION_FLAG)?;
+    if scheme_id.leaf_format() != LeafFormat::V2 {
+        return Err(format!(
+            "leaf version byte 0x{first:02x} names non-versioned scheme {scheme_id}"
+        ));
+    }
+    Ok(Some(scheme_id))
+}
+
+/// Checks that `leaf` is encoded for `scheme_id`: versioned leaves must carry exactly that
+/// scheme, and `LeafFormat::V1` schemes require an unversioned 71-byte leaf.
+pub fn check_leaf_scheme(scheme_id: SchemeId, leaf: &[u8]) -> Result<(), String> {
+    match (scheme_id.leaf_format(), leaf_scheme_id(leaf)?) {
+        (LeafFormat::V1, None) if leaf.len() == LEAF_BYTES_LEN => Ok(()),
+        (LeafFormat::V1, None) => Err(format!(
+            "v1 leaf has {} bytes, expected {LEAF_BYTES_LEN}",
+            leaf.len()
+        )),
+        (_, Some(found)) if found == scheme_id => Ok(()),
+        (_, Some(found)) => Err(format!(
+            "leaf is encoded for scheme {found}, expected {scheme_id}"
+        )),
+        (LeafFormat::V2, None) => Err(format!(
+            "unversioned leaf where scheme {scheme_id} expects a version byte"
+        )),
+    }
+}
+
 /// Mirrors Solidity `_layoutLeafHash`:
 /// `keccak256(circuitId, gateIndex, gateType, wireA, wireB, wireC)`.
+/// `GateSet::V2` opcodes hash with their byte value, matching the Solidity enum order.
 pub fn layout_leaf_hash(circuit_id: [u8; 32], gate_index: u64, gate: GateDesc) -> [u8; 32] {
     let gate_idx = uint256_from_u64(gate_index);
     let t = [gate.gate_type as u8];
@@ -149,6 +388,33 @@ pub fn layout_leaf_hash(circuit_id: [u8; 32], gate_index: u64, gate: GateDesc) -
     ])
 }
 
+/// Winner formula `0`: the higher bid wins (`x > y`).
+pub const HIGHER_BID_WINS: u8 = 0;
+/// Winner formula `1`: the lower bid wins (`x <= y`).
+pub const LOWER_BID_WINS: u8 = 1;
+
+/// Derives a circuit id bound to the winner formula and the canonical gate encoding:
+/// `keccak256("CIRCUIT", winnerFormula, gateCount, gateType_0, wireA_0, wireB_0, ...)`,
+/// with `winnerFormula` as one byte, `gateCount` as `uint256` and each gate packed into 7
+/// bytes like `layout_leaf_hash`. Both formulas read the same layout, so the formula byte
+/// keeps a higher-wins and a lower-wins session apart; reordering, rewiring or retyping any
+/// gate yields a different id too.
+pub fn circuit_id_from_layout(gates: &[GateDesc], winner_formula: u8) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(b"CIRCUIT");
+    hasher.update([winner_formula]);
+    hasher.update(uint256_from_u64(gates.len() as u64));
+    for gate in gates {
+        hasher.update([gate.gate_type as u8]);
+        hasher.update(gate.wire_a.to_be_bytes());
+        hasher.update(gate.wire_b.to_be_bytes());
+        hasher.update(gate.wire_c.to_be_bytes());
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&hasher.finalize());
+    out
+}
+
 /// Gate truth table used during row generation.
 /// `Not` returns `0` because NOT rows are canonicalized to zero in this flow.
 pub fn truth_table(gate_type: GateType, a: u8, b: u8) -> u8 {
@@ -157,5 +423,9 @@ pub fn truth_table(gate_type: GateType, a: u8, b: u8) -> u8 {
         GateType::Xor => (a ^ b) & 1,
         // NOT does not use row table in this canonical format.
         GateType::Not => 0,
+        // Gate-set V2 extensions.
+        GateType::Or => (a | b) & 1,
+        GateType::Nand => !(a & b) & 1,
+        GateType::Nor => !(a | b) & 1,
     }
 }
@@ -0,0 +1,359 @@
+//! One-shot snapshot of the auction contract for the `status` command of both binaries:
+//! stage and deadlines, the parties and their vault balances, the instance commitments and,
+//! once the verifier seed is final, the evaluated instance `m` and the opened ones.
+//!
+//! `cli::read_contract_status` fills it with `cast call`s; `status_lines` renders it as the
+//! `key=value` lines the binaries print, so `--json` turns it into one object.
+//!
+//! `ContractWatch` compares successive snapshots for the `watch` commands and turns what
+//! changed into `ContractEvent`s: stage transitions, parties joining, buyer status and vault
+//! changes, commitments and the opened instances, and the current stage's deadline drawing
+//! near or passing.
+
+use crate::cli::{hex32, u256_to_decimal};
+use crate::timeouts::{CLOSED_STAGE, Deadlines, stage_name};
+
+/// Contract `BuyerStatus` names, indexed by `buyerStatus(address)`.
+pub const BUYER_STATUS_NAMES: [&str; 3] = ["Pending", "Ready", "Defaulted"];
+
+/// `instanceCommitments(i)` in field order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct InstanceCommitment {
+    pub com_seed: [u8; 32],
+    pub root_gc: [u8; 32],
+    pub blob_hash_gc: [u8; 32],
+    pub h_out: [u8; 32],
+}
+
+impl InstanceCommitment {
+    /// Alice has not submitted commitments while every field is zero.
+    pub fn is_submitted(&self) -> bool {
+        *self != Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BuyerState {
+    pub address: String,
+    pub receiver: String,
+    /// `buyerStatus(address)`.
+    pub status: u8,
+    pub vault_wei: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractStatus {
+    pub contract_address: String,
+    /// `currentStage()`.
+    pub stage: u8,
+    /// Timestamp of the latest block.
+    pub now: u64,
+    pub deadlines: Deadlines,
+    pub alice: String,
+    pub alice_vault_wei: [u8; 32],
+    pub buyers: Vec<BuyerState>,
+    pub circuit_id: [u8; 32],
+    pub bit_width: u16,
+    /// `verifierSeedFinalized()`; `m` and `opened` are only meaningful once it is set.
+    pub verifier_seed_finalized: bool,
+    pub m: u64,
+    /// `sOpen`, the instances Alice opens.
+    pub opened: Vec<u64>,
+    /// `instanceCommitments`, indexed by instance id.
+    pub commitments: Vec<InstanceCommitment>,
+}
+
+/// The `key=value` lines of `status`. A key such as `buyer` or `commitment` repeats once per
+/// entry, with the entry's fields following on the same line.
+pub fn status_lines(status: &ContractStatus) -> Result<Vec<String>, String> {
+    let mut lines = vec![
+        format!("contract={}", status.contract_address),
+        format!("stage={}", status.stage),
+        format!("stage_name={}", stage_name(status.stage)?),
+        format!("now={}", status.now),
+    ];
+    if let Some(deadline) = status.deadlines.for_stage(status.stage) {
+        lines.push(format!("stage_deadline={deadline}"));
+        lines.push(format!(
+            "stage_seconds_left={}",
+            deadline.saturating_sub(status.now)
+        ));
+    }
+    for (name, deadline) in status.deadlines.named() {
+        lines.push(format!("deadline_{name}={deadline}"));
+    }
+
+    lines.push(format!("alice={}", status.alice));
+    lines.push(format!(
+        "alice_vault_wei={}",
+        u256_to_decimal(status.alice_vault_wei)
+    ));
+    lines.push(format!("buyer_count={}", status.buyers.len()));
+    for buyer in &status.buyers {
+        let buyer_status = BUYER_STATUS_NAMES
+            .get(buyer.status as usize)
+            .ok_or_else(|| format!("unknown buyer status {}", buyer.status))?;
+        lines.push(format!(
+            "buyer={} status={buyer_status} receiver={} vault_wei={}",
+            buyer.address,
+            buyer.receiver,
+            u256_to_decimal(buyer.vault_wei)
+        ));
+    }
+
+    lines.push(format!("circuit_id={}", hex32(status.circuit_id)));
+    lines.push(format!("bit_width={}", status.bit_width));
+    lines.push(format!(
+        "verifier_seed_finalized={}",
+        status.verifier_seed_finalized
+    ));
+    if status.verifier_seed_finalized {
+        lines.push(format!("m={}", status.m));
+        let opened = status.opened.iter().map(u64::to_string).collect::<Vec<_>>();
+        lines.push(format!("opened=[{}]", opened.join(",")));
+    }
+
+    let submitted = status
+        .commitments
+        .iter()
+        .any(InstanceCommitment::is_submitted);
+    lines.push(format!("commitments_submitted={submitted}"));
+    if submitted {
+        for (instance_id, commitment) in status.commitments.iter().enumerate() {
+            lines.push(format!(
+                "commitment={instance_id} com_seed={} root_gc={} blob_hash_gc={} h_out={}",
+                hex32(commitment.com_seed),
+                hex32(commitment.root_gc),
+                hex32(commitment.blob_hash_gc),
+                hex32(commitment.h_out)
+            ));
+        }
+    }
+    Ok(lines)
+}
+
+/// One change `ContractWatch::poll` found between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ContractEvent {
+    /// The contract moved to `stage`; `previous` is `None` on the first poll.
+    Stage {
+        previous: Option<u8>,
+        stage: u8,
+    },
+    BuyerJoined {
+        buyer: String,
+    },
+    BuyerStatus {
+        buyer: String,
+        status: u8,
+    },
+    /// `party`'s vault went from `previous_wei` to `vault_wei`: a deposit, a payout or a slash.
+    Vault {
+        party: String,
+        previous_wei: [u8; 32],
+        vault_wei: [u8; 32],
+    },
+    CommitmentsSubmitted,
+    VerifierSeedFinalized {
+        m: u64,
+        opened: Vec<u64>,
+    },
+    /// The current stage's deadline is at most the watch margin away.
+    DeadlineApproaching {
+        stage: u8,
+        deadline: u64,
+        seconds_left: u64,
+    },
+    /// The current stage's deadline has passed, so its timeout path is open.
+    DeadlinePassed {
+        stage: u8,
+        deadline: u64,
+    },
+}
+
+impl ContractEvent {
+    /// Event name, the `event=` value of `line`.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Stage { .. } => "stage",
+            Self::BuyerJoined { .. } => "buyer_joined",
+            Self::BuyerStatus { .. } => "buyer_status",
+            Self::Vault { .. } => "vault",
+            Self::CommitmentsSubmitted => "commitments_submitted",
+            Self::VerifierSeedFinalized { .. } => "verifier_seed_finalized",
+            Self::DeadlineApproaching { .. } => "deadline_approaching",
+            Self::DeadlinePassed { .. } => "deadline_passed",
+        }
+    }
+
+    /// The event as one `event=<kind>` line with its fields.
+    pub fn line(&self) -> Result<String, String> {
+        let fields = match self {
+            Self::Stage { previous, stage } => {
+                let mut fields = format!("stage={stage} stage_name={}", stage_name(*stage)?);
+                if let Some(previous) = previous {
+                    fields.push_str(&format!(" previous={}", stage_name(*previous)?));
+                }
+                fields
+            }
+            Self::BuyerJoined { buyer } => format!("buyer={buyer}"),
+            Self::BuyerStatus { buyer, status } => {
+                let name = BUYER_STATUS_NAMES
+                    .get(*status as usize)
+                    .ok_or_else(|| format!("unknown buyer status {status}"))?;
+                format!("buyer={buyer} status={name}")
+            }
+            Self::Vault {
+                party,
+                previous_wei,
+                vault_wei,
+            } => format!(
+                "party={party} previous_wei={} vault_wei={}",
+                u256_to_decimal(*previous_wei),
+                u256_to_decimal(*vault_wei)
+            ),
+            Self::CommitmentsSubmitted => String::new(),
+            Self::VerifierSeedFinalized { m, opened } => {
+                let opened = opened.iter().map(u64::to_string).collect::<Vec<_>>();
+                format!("m={m} opened=[{}]", opened.join(","))
+            }
+            Self::DeadlineApproaching {
+                stage,
+                deadline,
+                seconds_left,
+            } => format!(
+                "stage_name={} deadline={deadline} seconds_left={seconds_left}",
+                stage_name(*stage)?
+            ),
+            Self::DeadlinePassed { stage, deadline } => {
+                format!("stage_name={} deadline={deadline}", stage_name(*stage)?)
+            }
+        };
+        if fields.is_empty() {
+            return Ok(format!("event={}", self.kind()));
+        }
+        Ok(format!("event={} {fields}", self.kind()))
+    }
+}
+
+/// Turns successive snapshots of one contract into the events between them.
+#[derive(Debug, Clone)]
+pub struct ContractWatch {
+    /// How long before the current stage's deadline `DeadlineApproaching` is reported.
+    pub margin_secs: u64,
+    last: Option<ContractStatus>,
+    /// `(stage, deadline)` already reported as approaching, and as passed.
+    approaching: Option<(u8, u64)>,
+    passed: Option<(u8, u64)>,
+}
+
+impl ContractWatch {
+    pub fn new(margin_secs: u64) -> Self {
+        Self {
+            margin_secs,
+            last: None,
+            approaching: None,
+            passed: None,
+        }
+    }
+
+    /// Events since the previous poll. The first poll reports the current stage and its
+    /// deadline only; each deadline is reported approaching and passed at most once.
+    pub fn poll(&mut self, status: ContractStatus) -> Vec<ContractEvent> {
+        let mut events = Vec::new();
+        match &self.last {
+            None => events.push(ContractEvent::Stage {
+                previous: None,
+                stage: status.stage,
+            }),
+            Some(last) => changes(last, &status, &mut events),
+        }
+        self.deadline_events(&status, &mut events);
+        self.last = Some(status);
+        events
+    }
+
+    /// The contract is closed as of the last poll, so no further events will follow.
+    pub fn is_closed(&self) -> bool {
+        self.last
+            .as_ref()
+            .is_some_and(|status| status.stage == CLOSED_STAGE)
+    }
+
+    fn deadline_events(&mut self, status: &ContractStatus, events: &mut Vec<ContractEvent>) {
+        let stage = status.stage;
+        let Some(deadline) = status.deadlines.for_stage(stage).filter(|d| *d != 0) else {
+            return;
+        };
+        let key = Some((stage, deadline));
+        if status.now > deadline {
+            if self.passed != key {
+                self.passed = key;
+                events.push(ContractEvent::DeadlinePassed { stage, deadline });
+            }
+        } else if deadline - status.now <= self.margin_secs && self.approaching != key {
+            self.approaching = key;
+            events.push(ContractEvent::DeadlineApproaching {
+                stage,
+                deadline,
+                seconds_left: deadline - status.now,
+            });
+        }
+    }
+}
+
+fn changes(last: &ContractStatus, next: &ContractStatus, events: &mut Vec<ContractEvent>) {
+    if next.stage != last.stage {
+        events.push(ContractEvent::Stage {
+            previous: Some(last.stage),
+            stage: next.stage,
+        });
+    }
+    events.extend(vault_change(
+        &next.alice,
+        last.alice_vault_wei,
+        next.alice_vault_wei,
+    ));
+    for buyer in &next.buyers {
+        let known = last
+            .buyers
+            .iter()
+            .find(|known| known.address == buyer.address);
+        if known.is_none() {
+            events.push(ContractEvent::BuyerJoined {
+                buyer: buyer.address.clone(),
+            });
+        }
+        if known.map(|known| known.status) != Some(buyer.status) {
+            events.push(ContractEvent::BuyerStatus {
+                buyer: buyer.address.clone(),
+                status: buyer.status,
+            });
+        }
+        let previous_wei = known.map_or([0; 32], |known| known.vault_wei);
+        events.extend(vault_change(&buyer.address, previous_wei, buyer.vault_wei));
+    }
+    let submitted = |status: &ContractStatus| {
+        status
+            .commitments
+            .iter()
+            .any(InstanceCommitment::is_submitted)
+    };
+    if submitted(next) && !submitted(last) {
+        events.push(ContractEvent::CommitmentsSubmitted);
+    }
+    if next.verifier_seed_finalized && !last.verifier_seed_finalized {
+        events.push(ContractEvent::VerifierSeedFinalized {
+            m: next.m,
+            opened: next.opened.clone(),
+        });
+    }
+}
+
+fn vault_change(party: &str, previous_wei: [u8; 32], vault_wei: [u8; 32]) -> Option<ContractEvent> {
+    (vault_wei != previous_wei).then(|| ContractEvent::Vault {
+        party: party.to_string(),
+        previous_wei,
+        vault_wei,
+    })
+}
@@ -0,0 +1,83 @@
+//! Size and proof-cost estimates for one garbled instance, computed from the layout alone so
+//! bid widths and circuits can be sized before anything is garbled or committed.
+
+use crate::consensus::{LEAF_BYTES_LEN, ROW_MAC_LEN};
+use crate::types::{CircuitLayout, LeafFormat, RowAuthMode};
+
+/// Calldata gas per non-zero byte (EIP-2028); zero bytes cost less, so this is an upper bound.
+pub const CALLDATA_GAS_PER_BYTE: u64 = 16;
+
+/// ABI head of `disputeGarbledTable(uint256,bytes32,uint256,(uint8,uint16,uint16,uint16),
+/// bytes,bytes32[],bytes32[])`: selector, three words, the static gate tuple and three
+/// offsets.
+const DISPUTE_HEAD_BYTES: usize = 4 + 3 * 32 + 4 * 32 + 3 * 32;
+
+/// Estimated sizes of one garbled instance and of its worst-case gate dispute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstanceCost {
+    pub gate_count: usize,
+    /// Sum of all encoded leaf lengths under the layout's `LeafFormat`.
+    pub leaf_bytes: usize,
+    /// Longest single leaf.
+    pub max_leaf_bytes: usize,
+    /// One `gc_block_hash` per gate.
+    pub block_hash_count: usize,
+    /// IH proof entries for the worst gate (gate `0`: prefix state plus every later block).
+    pub ih_proof_len: usize,
+    /// Layout Merkle proof entries (tree depth).
+    pub layout_proof_len: usize,
+    /// ABI-encoded `disputeGarbledTable` calldata for the worst gate and longest leaf.
+    pub dispute_calldata_bytes: usize,
+    /// Upper bound on that calldata's gas (`CALLDATA_GAS_PER_BYTE` per byte), excluding the
+    /// intrinsic transaction cost and execution.
+    pub dispute_calldata_gas: u64,
+}
+
+/// Encoded length of a leaf for a gate carrying `row_count` rows (see `garbled_row_count`).
+fn leaf_len(layout: &CircuitLayout, row_count: usize) -> usize {
+    match layout.leaf_format {
+        LeafFormat::V1 => LEAF_BYTES_LEN,
+        // Version byte and header, rows without the implicit row `0`, then one tag per row.
+        LeafFormat::V2 if row_count == 0 => 8,
+        LeafFormat::V2 => {
+            let macs = match layout.row_auth {
+                RowAuthMode::Mac => row_count * ROW_MAC_LEN,
+                RowAuthMode::Unauthenticated => 0,
+            };
+            8 + 16 * (row_count - 1) + macs
+        }
+    }
+}
+
+/// Estimates leaf, proof and dispute calldata sizes of one instance of `layout`.
+pub fn estimate_instance_cost(layout: &CircuitLayout) -> InstanceCost {
+    let gate_count = layout.gates.len();
+    let leaf_lens = layout
+        .gates
+        .iter()
+        .map(|gate| leaf_len(layout, layout.garbled_row_count(gate.gate_type)))
+        .collect::<Vec<_>>();
+    let max_leaf_bytes = leaf_lens.iter().copied().max().unwrap_or(0);
+    let ih_proof_len = if gate_count > 1 { gate_count } else { 0 };
+    let layout_proof_len = gate_count.next_power_of_two().trailing_zeros() as usize;
+
+    // `bytes` and `bytes32[]` tails: one length word each plus padded contents.
+    let dispute_calldata_bytes = DISPUTE_HEAD_BYTES
+        + 32
+        + max_leaf_bytes.div_ceil(32) * 32
+        + 32
+        + 32 * ih_proof_len
+        + 32
+        + 32 * layout_proof_len;
+
+    InstanceCost {
+        gate_count,
+        leaf_bytes: leaf_lens.iter().sum(),
+        max_leaf_bytes,
+        block_hash_count: gate_count,
+        ih_proof_len,
+        layout_proof_len,
+        dispute_calldata_bytes,
+        dispute_calldata_gas: dispute_calldata_bytes as u64 * CALLDATA_GAS_PER_BYTE,
+    }
+}
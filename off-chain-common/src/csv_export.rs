@@ -0,0 +1,119 @@
+//! CSV tables of one garbled instance for spreadsheet audits and for reading mismatch reports
+//! gate by gate: one row per gate with its descriptor, layout leaf hash (against
+//! `circuitLayoutRoot`) and IH block hash (against `rootGC`), plus the audit verdict when the
+//! opened seed is known, and one row per wire with its two labels.
+//!
+//! Hashes and labels are `0x`-prefixed hex, lists inside a cell are `;`-separated, and
+//! columns without a value for a row are left empty, so no cell ever needs quoting.
+
+use std::io::Write;
+
+use crate::audit::AuditReport;
+use crate::cli::hex_prefixed;
+use crate::consensus::layout_leaf_hash;
+use crate::garble::LayoutLabels;
+use crate::ih::gc_block_hash;
+use crate::leaf_file::ClaimedLeaves;
+use crate::types::{CircuitLayout, GateType};
+
+pub const GATES_CSV_HEADER: &str = "gate_index,gate_type,gate_name,wire_a,wire_b,wire_c,\
+                                    leaf_hash,block_hash,status,corruption,differing_rows,\
+                                    expected_block_hash";
+
+pub const LABELS_CSV_HEADER: &str = "wire,label0,label1,flip_bit";
+
+fn gate_name(gate_type: GateType) -> &'static str {
+    match gate_type {
+        GateType::And => "and",
+        GateType::Xor => "xor",
+        GateType::Not => "not",
+        GateType::Or => "or",
+        GateType::Nand => "nand",
+        GateType::Nor => "nor",
+    }
+}
+
+fn write_line(out: &mut impl Write, line: &str) -> Result<(), String> {
+    writeln!(out, "{line}").map_err(|e| format!("failed to write CSV row: {e}"))
+}
+
+/// Writes the gate table of `layout` with the hashes of `leaves`. With `audit` (of the same
+/// leaves), every row gets `status` `match` or `mismatch`, and mismatching rows the corruption,
+/// the differing rows and the block hash of the recomputed leaf. Returns the row count.
+pub fn write_gates_csv(
+    out: &mut impl Write,
+    layout: &CircuitLayout,
+    leaves: &(impl ClaimedLeaves + ?Sized),
+    audit: Option<&AuditReport>,
+) -> Result<usize, String> {
+    if leaves.leaf_count() != layout.gates.len() {
+        return Err(format!(
+            "leaf count ({}) does not match circuit gate count ({})",
+            leaves.leaf_count(),
+            layout.gates.len()
+        ));
+    }
+    write_line(out, GATES_CSV_HEADER)?;
+    for (idx, gate) in layout.gates.iter().enumerate() {
+        let leaf_hash = layout_leaf_hash(layout.circuit_id, idx as u64, *gate);
+        let block_hash = gc_block_hash(idx as u64, &leaves.leaf(idx)?);
+        let verdict = match audit.map(|report| report.gate(idx)) {
+            None => ",,,".to_string(),
+            Some(None) => "match,,,".to_string(),
+            Some(Some(mismatch)) => format!(
+                "mismatch,{},{},{}",
+                mismatch.corruption(),
+                mismatch
+                    .differing_rows
+                    .iter()
+                    .map(usize::to_string)
+                    .collect::<Vec<_>>()
+                    .join(";"),
+                hex_prefixed(&gc_block_hash(idx as u64, &mismatch.expected_leaf))
+            ),
+        };
+        write_line(
+            out,
+            &format!(
+                "{idx},{},{},{},{},{},{},{},{verdict}",
+                gate.gate_type as u8,
+                gate_name(gate.gate_type),
+                gate.wire_a,
+                gate.wire_b,
+                gate.wire_c,
+                hex_prefixed(&leaf_hash),
+                hex_prefixed(&block_hash)
+            ),
+        )?;
+    }
+    Ok(layout.gates.len())
+}
+
+/// Writes both labels and the flip bit of every wire of `layout`, derived from the opened
+/// `seed`. Returns the row count.
+pub fn write_labels_csv(
+    out: &mut impl Write,
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+) -> Result<usize, String> {
+    let wire_count = layout
+        .gates
+        .iter()
+        .map(|gate| gate.wire_a.max(gate.wire_b).max(gate.wire_c) as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let labels = LayoutLabels::new(seed, layout);
+    write_line(out, LABELS_CSV_HEADER)?;
+    for wire in 0..wire_count as u16 {
+        write_line(
+            out,
+            &format!(
+                "{wire},{},{},{}",
+                hex_prefixed(&labels.label(wire, 0)),
+                hex_prefixed(&labels.label(wire, 1)),
+                labels.flip_bit(wire)
+            ),
+        )?;
+    }
+    Ok(wire_count)
+}
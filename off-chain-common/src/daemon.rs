@@ -0,0 +1,490 @@
+//! Local HTTP/JSON-RPC daemon behind the `serve` command of the alice and bob binaries, so a
+//! web UI or orchestrator can drive CLI operations without shelling out.
+//!
+//! `POST /` takes a JSON-RPC 2.0 request whose `method` is a CLI command name. `params` is
+//! either an array of raw CLI arguments or an object of flags (`{"m": 2, "out-dir": "x"}`
+//! becomes `--m 2 --out-dir x`; `true` adds a bare flag, `false`/`null` omits it). The
+//! command's `key=value` output lines become the `result` object. `GET /methods` lists the
+//! exposed commands. `serve` only binds loopback addresses (`BindPolicy::Loopback`) and
+//! handles one request at a time, so commands never race on the same output directory.
+//!
+//! The `relay` endpoint is meant for other parties and may bind any address
+//! (`BindPolicy::Public`), with a bearer token of at least `MIN_PUBLIC_TOKEN_LEN` characters.
+//! The daemon speaks plain HTTP, so a public endpoint belongs behind a TLS-terminating reverse
+//! proxy (e.g. nginx or caddy forwarding to `127.0.0.1:<port>`), or the token travels in the
+//! clear.
+//!
+//! Loopback alone does not keep a browser page from posting to the port, so every route but
+//! `GET /health` needs the run's bearer token (`Authorization: Bearer <token>`), a request
+//! carrying an `Origin` header is refused, and `POST /` only takes `application/json`. Path
+//! flags must be relative paths without `..` and are resolved inside the daemon's root
+//! directory (`PathRoot`); global flags such as `--session` and `--profile` are refused.
+//! Notifications (requests without an `id`) run but get no response body, as JSON-RPC 2.0
+//! requires.
+
+use std::io::Read;
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+use serde_json::{Map, Value, json};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// JSON-RPC error codes.
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// The command ran and failed; `message` carries its error.
+pub const COMMAND_FAILED: i64 = -32000;
+
+/// Runs `(method, args)` and returns the command's stdout or its error.
+pub type CommandHandler<'a> = dyn FnMut(&str, &[String]) -> Result<String, String> + 'a;
+
+/// Upper bound on a request body.
+const MAX_BODY_LEN: u64 = 1 << 20;
+
+/// Shortest bearer token a daemon bound to a non-loopback address accepts.
+pub const MIN_PUBLIC_TOKEN_LEN: usize = 32;
+
+/// Addresses a daemon may listen on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindPolicy {
+    /// Loopback only: `serve`, whose calls run commands on the local session.
+    Loopback,
+    /// Any address: the `relay` endpoint other parties reach over the network.
+    Public,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A parsed call: the command name and its CLI arguments. `id` is `None` for a notification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RpcRequest {
+    pub id: Option<Value>,
+    pub method: String,
+    pub args: Vec<String>,
+}
+
+/// Directory the path flags of served commands are confined to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathRoot {
+    pub root: PathBuf,
+    /// Long names (without `--`) of the flags that take a path.
+    pub path_flags: Vec<String>,
+    /// Long names of flags calls may not pass at all, such as the global `--session`, which
+    /// would point the command at a directory outside `root`.
+    pub refused_flags: Vec<String>,
+}
+
+impl PathRoot {
+    /// `args` with every path flag value resolved inside `root`; absolute paths, `..`
+    /// components and refused flags are refused.
+    pub fn confine(&self, args: &[String]) -> Result<Vec<String>, RpcError> {
+        for arg in args {
+            let flag = arg.split_once('=').map_or(arg.as_str(), |(flag, _)| flag);
+            if let Some(name) = flag.strip_prefix("--")
+                && self.refused_flags.iter().any(|refused| refused == name)
+            {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    format!("{flag} cannot be passed to a served command"),
+                ));
+            }
+        }
+        let is_path_flag = |flag: &str| {
+            flag.strip_prefix("--")
+                .is_some_and(|name| self.path_flags.iter().any(|path_flag| path_flag == name))
+        };
+        let mut confined = Vec::with_capacity(args.len());
+        let mut iter = args.iter();
+        while let Some(arg) = iter.next() {
+            if let Some((flag, value)) = arg.split_once('=')
+                && is_path_flag(flag)
+            {
+                confined.push(format!("{flag}={}", self.resolve(flag, value)?));
+            } else if is_path_flag(arg) {
+                confined.push(arg.clone());
+                if let Some(value) = iter.next() {
+                    confined.push(self.resolve(arg, value)?);
+                }
+            } else {
+                confined.push(arg.clone());
+            }
+        }
+        Ok(confined)
+    }
+
+    fn resolve(&self, flag: &str, value: &str) -> Result<String, RpcError> {
+        let path = Path::new(value);
+        let relative = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+        if !relative || value.is_empty() {
+            return Err(RpcError::new(
+                INVALID_PARAMS,
+                format!(
+                    "{flag} must be a relative path inside {} without '..', got '{value}'",
+                    self.root.display()
+                ),
+            ));
+        }
+        Ok(self.root.join(path).display().to_string())
+    }
+}
+
+fn param_text(name: &str, value: &Value) -> Result<String, RpcError> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        _ => Err(RpcError::new(
+            INVALID_PARAMS,
+            format!("param '{name}' must be a string or number"),
+        )),
+    }
+}
+
+/// CLI arguments for JSON-RPC `params`.
+pub fn params_to_args(params: Option<&Value>) -> Result<Vec<String>, RpcError> {
+    match params {
+        None | Some(Value::Null) => Ok(Vec::new()),
+        Some(Value::Array(items)) => items
+            .iter()
+            .enumerate()
+            .map(|(idx, item)| param_text(&idx.to_string(), item))
+            .collect(),
+        Some(Value::Object(flags)) => {
+            let mut args = Vec::new();
+            for (name, value) in flags {
+                let flag = format!("--{}", name.trim_start_matches("--"));
+                match value {
+                    Value::Bool(false) | Value::Null => {}
+                    Value::Bool(true) => args.push(flag),
+                    other => {
+                        let text = param_text(name, other)?;
+                        args.push(flag);
+                        args.push(text);
+                    }
+                }
+            }
+            Ok(args)
+        }
+        Some(_) => Err(RpcError::new(
+            INVALID_PARAMS,
+            "params must be an array of CLI arguments or an object of flags",
+        )),
+    }
+}
+
+/// Parses a JSON-RPC 2.0 request body; the error carries the request id when one was read.
+pub fn parse_request(body: &str) -> Result<RpcRequest, (Value, RpcError)> {
+    let value: Value = serde_json::from_str(body)
+        .map_err(|e| (Value::Null, RpcError::new(PARSE_ERROR, e.to_string())))?;
+    let Value::Object(object) = value else {
+        return Err((
+            Value::Null,
+            RpcError::new(INVALID_REQUEST, "request must be an object"),
+        ));
+    };
+    let id = object.get("id").cloned();
+    let invalid = |message: &str| {
+        let id = id.clone().unwrap_or(Value::Null);
+        (id, RpcError::new(INVALID_REQUEST, message))
+    };
+    if object.get("jsonrpc").and_then(Value::as_str) != Some("2.0") {
+        return Err(invalid("jsonrpc must be \"2.0\""));
+    }
+    let Some(method) = object.get("method").and_then(Value::as_str) else {
+        return Err(invalid("method must be a string"));
+    };
+    let args =
+        params_to_args(object.get("params")).map_err(|e| (id.clone().unwrap_or(Value::Null), e))?;
+    Ok(RpcRequest {
+        id,
+        method: method.to_string(),
+        args,
+    })
+}
+
+/// Object of a command's `key=value` output lines; other lines go to `"output"`.
+pub fn key_value_output_to_json(stdout: &str) -> Value {
+    let mut fields = Map::new();
+    let mut other = Vec::new();
+    for line in stdout.lines().filter(|line| !line.trim().is_empty()) {
+        match line.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !key.contains(' ') => {
+                fields.insert(key.to_string(), Value::String(value.to_string()));
+            }
+            _ => other.push(Value::String(line.to_string())),
+        }
+    }
+    if !other.is_empty() {
+        fields.insert("output".to_string(), Value::Array(other));
+    }
+    Value::Object(fields)
+}
+
+pub fn response_json(id: Value, result: Result<Value, RpcError>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    }
+}
+
+/// Answers one JSON-RPC request body, with path flags confined to `paths`; `None` for a
+/// notification.
+pub fn dispatch(
+    body: &str,
+    methods: &[&str],
+    paths: &PathRoot,
+    handler: &mut CommandHandler<'_>,
+) -> Option<Value> {
+    let request = match parse_request(body) {
+        Ok(request) => request,
+        Err((id, error)) => return Some(response_json(id, Err(error))),
+    };
+    let result = if methods.contains(&request.method.as_str()) {
+        paths.confine(&request.args).and_then(|args| {
+            handler(&request.method, &args)
+                .map(|stdout| key_value_output_to_json(&stdout))
+                .map_err(|message| RpcError::new(COMMAND_FAILED, message))
+        })
+    } else {
+        Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("method '{}' is not served", request.method),
+        ))
+    };
+    request.id.map(|id| response_json(id, result))
+}
+
+/// Runs `exe method args...` in `dir` and returns its stdout, or its stderr when it fails. Calls
+/// run in a child process so a failing command cannot take the daemon down.
+pub fn run_subcommand(
+    exe: &Path,
+    dir: &Path,
+    method: &str,
+    args: &[String],
+) -> Result<String, String> {
+    let output = Command::new(exe)
+        .current_dir(dir)
+        .arg(method)
+        .args(args)
+        .output()
+        .map_err(|e| format!("failed to run {}: {e}", exe.display()))?;
+    if output.status.success() {
+        return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+    }
+    Err(command_failure(method, output.status, &output.stderr))
+}
+
+/// Error of a failed `method` run: its message from `stderr`, else its exit status.
+pub fn command_failure(method: &str, status: ExitStatus, stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    // `main` returning `Err` prints `Error: <Debug>`, which quotes string errors.
+    let message = stderr.trim().trim_start_matches("Error: ");
+    let message = message
+        .strip_prefix('"')
+        .and_then(|inner| inner.strip_suffix('"'))
+        .unwrap_or(message);
+    if message.is_empty() {
+        format!("{method} exited with {status}")
+    } else {
+        message.to_string()
+    }
+}
+
+/// `a == b` without an early exit on the first differing byte.
+fn tokens_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0u8, |diff, (x, y)| diff | (x ^ y))
+            == 0
+}
+
+fn header_value<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(name))
+        .map(|header| header.value.as_str())
+}
+
+/// A bound daemon serving `methods`.
+pub struct RpcServer {
+    server: Server,
+    methods: Vec<String>,
+    token: String,
+    paths: PathRoot,
+}
+
+impl std::fmt::Debug for RpcServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RpcServer")
+            .field("local_addr", &self.local_addr())
+            .field("methods", &self.methods)
+            .field("paths", &self.paths)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RpcServer {
+    /// Binds `listen_addr`, which must be a loopback address. Requests must carry `token`
+    /// and their path flags stay inside `paths.root`.
+    pub fn bind(
+        listen_addr: &str,
+        methods: &[&str],
+        token: &str,
+        paths: PathRoot,
+    ) -> Result<Self, String> {
+        Self::bind_with_policy(listen_addr, BindPolicy::Loopback, methods, token, paths)
+    }
+
+    /// `bind` on an address `policy` allows; a non-loopback address needs a token of at
+    /// least `MIN_PUBLIC_TOKEN_LEN` characters.
+    pub fn bind_with_policy(
+        listen_addr: &str,
+        policy: BindPolicy,
+        methods: &[&str],
+        token: &str,
+        paths: PathRoot,
+    ) -> Result<Self, String> {
+        if token.is_empty() {
+            return Err("the daemon needs a non-empty bearer token".to_string());
+        }
+        let addr: SocketAddr = listen_addr
+            .parse()
+            .map_err(|_| format!("invalid listen address '{listen_addr}'"))?;
+        if !addr.ip().is_loopback() {
+            if policy == BindPolicy::Loopback {
+                return Err(format!(
+                    "refusing to serve on non-loopback address {addr}; use 127.0.0.1 or ::1"
+                ));
+            }
+            if token.len() < MIN_PUBLIC_TOKEN_LEN {
+                return Err(format!(
+                    "serving on {addr} needs a bearer token of at least {MIN_PUBLIC_TOKEN_LEN} characters"
+                ));
+            }
+        }
+        let server =
+            Server::http(addr).map_err(|e| format!("failed to listen on {listen_addr}: {e}"))?;
+        Ok(Self {
+            server,
+            methods: methods.iter().map(|method| method.to_string()).collect(),
+            token: token.to_string(),
+            paths,
+        })
+    }
+
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    pub fn methods(&self) -> &[String] {
+        &self.methods
+    }
+
+    /// Serves requests until the listener fails.
+    pub fn serve(&self, handler: &mut CommandHandler<'_>) -> Result<(), String> {
+        loop {
+            self.serve_one(handler)?;
+        }
+    }
+
+    /// Waits for and answers a single HTTP request.
+    pub fn serve_one(&self, handler: &mut CommandHandler<'_>) -> Result<(), String> {
+        let mut request = self
+            .server
+            .recv()
+            .map_err(|e| format!("failed to receive request: {e}"))?;
+        let response = match self.route(&mut request, handler) {
+            (status, Some(body)) => {
+                let header = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+                    .expect("static header");
+                Response::from_string(body.to_string())
+                    .with_status_code(status)
+                    .with_header(header)
+            }
+            (_, None) => Response::from_string(String::new()).with_status_code(204),
+        };
+        request
+            .respond(response)
+            .map_err(|e| format!("failed to send response: {e}"))
+    }
+
+    /// Refuses cross-origin and unauthenticated requests; `None` lets `request` through.
+    fn reject(&self, request: &Request) -> Option<(u16, Value)> {
+        if header_value(request, "Origin").is_some() {
+            return Some((403, json!({ "error": "cross-origin requests are refused" })));
+        }
+        if (request.method(), request.url()) == (&Method::Get, "/health") {
+            return None;
+        }
+        let authorized = header_value(request, "Authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|token| tokens_match(token.trim(), &self.token));
+        if !authorized {
+            return Some((401, json!({ "error": "missing or wrong bearer token" })));
+        }
+        None
+    }
+
+    fn route(
+        &self,
+        request: &mut Request,
+        handler: &mut CommandHandler<'_>,
+    ) -> (u16, Option<Value>) {
+        if let Some((status, body)) = self.reject(request) {
+            return (status, Some(body));
+        }
+        let methods = self.methods.iter().map(String::as_str).collect::<Vec<_>>();
+        match (request.method(), request.url()) {
+            (Method::Get, "/methods") => (200, Some(json!({ "methods": methods }))),
+            (Method::Get, "/health") => (200, Some(json!({ "status": "ok" }))),
+            (Method::Post, "/") => {
+                let json_body = header_value(request, "Content-Type").is_some_and(|value| {
+                    value.split(';').next().map(str::trim) == Some("application/json")
+                });
+                if !json_body {
+                    let error = json!({ "error": "Content-Type must be application/json" });
+                    return (415, Some(error));
+                }
+                let mut body = String::new();
+                if let Err(e) = request
+                    .as_reader()
+                    .take(MAX_BODY_LEN)
+                    .read_to_string(&mut body)
+                {
+                    let error = RpcError::new(PARSE_ERROR, format!("unreadable body: {e}"));
+                    return (400, Some(response_json(Value::Null, Err(error))));
+                }
+                (200, dispatch(&body, &methods, &self.paths, handler))
+            }
+            (method, url) => (
+                404,
+                Some(json!({
+                    "error": format!("no route for {method} {url}; POST / with JSON-RPC")
+                })),
+            ),
+        }
+    }
+}
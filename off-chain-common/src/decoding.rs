@@ -0,0 +1,172 @@
+//! Output decoding tables: what an evaluator needs to turn the output label of one garbled
+//! instance into its semantic bit and to check it against the on-chain output anchors.
+
+use serde::{Deserialize, Serialize};
+
+use crate::canonical_json::hex_bytes;
+use crate::cli::{hex32, parse_bytes32, parse_u8, parse_u16, parse_u64};
+use crate::evaluation::{derive_output_labels, label16_to_bytes32};
+use crate::labels::get_permutation_bit;
+use crate::settlement::output_anchor_hash;
+use crate::types::CircuitLayout;
+
+/// Decode information for the decision wire of one instance.
+///
+/// `h0`/`h1` are the committed output anchors (`h0` opens with the semantic-1 label, `h1`
+/// with the semantic-0 label), `lout_true`/`lout_false` the labels themselves, and
+/// `lsb_true` the permutation bit of `lout_true` for point-and-permute decoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputDecodingTable {
+    #[serde(with = "hex_bytes")]
+    pub circuit_id: [u8; 32],
+    pub instance_id: u64,
+    pub output_wire: u16,
+    #[serde(with = "hex_bytes")]
+    pub h0: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub h1: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub lout_true: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub lout_false: [u8; 32],
+    pub lsb_true: u8,
+}
+
+impl OutputDecodingTable {
+    /// Derives the table for `output_wire` of the instance garbled from `seed`.
+    pub fn derive(
+        seed: [u8; 32],
+        layout: &CircuitLayout,
+        output_wire: u16,
+    ) -> Result<Self, String> {
+        let (label_false, label_true) = derive_output_labels(seed, layout, output_wire)
+            .map_err(|e| format!("failed to derive output labels: {e}"))?;
+        Ok(Self::from_labels(
+            layout.circuit_id,
+            layout.instance_id,
+            output_wire,
+            label_true,
+            label_false,
+        ))
+    }
+
+    /// Builds the table from both output labels, computing the anchors.
+    pub fn from_labels(
+        circuit_id: [u8; 32],
+        instance_id: u64,
+        output_wire: u16,
+        label_true: [u8; 16],
+        label_false: [u8; 16],
+    ) -> Self {
+        let lout_true = label16_to_bytes32(label_true);
+        let lout_false = label16_to_bytes32(label_false);
+        Self::from_openings(
+            circuit_id,
+            instance_id,
+            output_wire,
+            [
+                output_anchor_hash(circuit_id, instance_id, true, lout_true),
+                output_anchor_hash(circuit_id, instance_id, false, lout_false),
+            ],
+            [lout_true, lout_false],
+        )
+    }
+
+    /// Table from committed anchors `[h0, h1]` and label openings `[lout_true, lout_false]`,
+    /// e.g. as carried by an eval blob payload. The anchors are not re-checked.
+    pub fn from_openings(
+        circuit_id: [u8; 32],
+        instance_id: u64,
+        output_wire: u16,
+        [h0, h1]: [[u8; 32]; 2],
+        [lout_true, lout_false]: [[u8; 32]; 2],
+    ) -> Self {
+        Self {
+            circuit_id,
+            instance_id,
+            output_wire,
+            h0,
+            h1,
+            lout_true,
+            lout_false,
+            lsb_true: lout_true[0] & 1,
+        }
+    }
+
+    /// Semantic bit of an evaluated output label, or `None` if it is neither output label.
+    pub fn decode(&self, label: [u8; 16]) -> Option<u8> {
+        let label = label16_to_bytes32(label);
+        if label == self.lout_true {
+            Some(1)
+        } else if label == self.lout_false {
+            Some(0)
+        } else {
+            None
+        }
+    }
+
+    /// Point-and-permute decoding from the label's permutation bit alone. Does not detect
+    /// labels that are not one of the two output labels; use `decode` for that.
+    pub fn decode_lsb(&self, label: [u8; 16]) -> u8 {
+        (get_permutation_bit(label) == self.lsb_true) as u8
+    }
+
+    /// Whether `label` opens `h0` (semantic 1) and `h1` (semantic 0) respectively.
+    pub fn anchor_matches(&self, label: [u8; 16]) -> (bool, bool) {
+        let label = label16_to_bytes32(label);
+        (
+            output_anchor_hash(self.circuit_id, self.instance_id, true, label) == self.h0,
+            output_anchor_hash(self.circuit_id, self.instance_id, false, label) == self.h1,
+        )
+    }
+
+    /// `key=value` lines as in the legacy `eval-meta.txt` (`eval_meta`).
+    pub fn to_key_values(&self) -> String {
+        format!(
+            "circuit_id={}\ninstance_id={}\noutput_wire={}\nh0={}\nh1={}\nlout_true={}\nlout_false={}\nlsb_true={}\n",
+            hex32(self.circuit_id),
+            self.instance_id,
+            self.output_wire,
+            hex32(self.h0),
+            hex32(self.h1),
+            hex32(self.lout_true),
+            hex32(self.lout_false),
+            self.lsb_true
+        )
+    }
+
+    /// Parses the keys written by `to_key_values`; other keys are ignored. `lsb_true` is
+    /// optional for files written before it existed and must agree with `lout_true`.
+    pub fn from_key_values<'a>(
+        entries: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Result<Self, String> {
+        let entries = entries.into_iter().collect::<Vec<_>>();
+        let get = |key: &str| {
+            entries
+                .iter()
+                .find_map(|(k, v)| (*k == key).then_some(*v))
+                .ok_or_else(|| format!("missing key '{key}'"))
+        };
+        let bytes32 =
+            |key: &str| parse_bytes32(get(key)?).map_err(|e| format!("invalid {key}: {e}"));
+
+        let table = Self::from_openings(
+            bytes32("circuit_id")?,
+            parse_u64(get("instance_id")?, "instance_id").map_err(|e| e.to_string())?,
+            parse_u16(get("output_wire")?, "output_wire").map_err(|e| e.to_string())?,
+            [bytes32("h0")?, bytes32("h1")?],
+            [bytes32("lout_true")?, bytes32("lout_false")?],
+        );
+        if let Ok(raw) = get("lsb_true") {
+            let parsed = parse_u8(raw, "lsb_true").map_err(|e| e.to_string())?;
+            if parsed != table.lsb_true {
+                return Err(format!(
+                    "lsb_true={parsed} does not match the permutation bit {} of lout_true",
+                    table.lsb_true
+                ));
+            }
+        }
+        Ok(table)
+    }
+}
@@ -0,0 +1,231 @@
+//! Garbled-table dispute packets: audits the leaves a garbler claimed for an opened instance
+//! and assembles the arguments of `disputeGarbledTable` for one gate.
+//!
+//! A prepared dispute is stored or sent as a CBOR `Message::DisputePacket`
+//! (`PreparedDispute::to_cbor`), which carries everything the transaction needs; the audit
+//! is not part of it, since anyone holding the seed can recompute it. Packet files
+//! (`prepare-dispute --out`) hold that CBOR or the same fields as canonical JSON.
+
+pub mod bisection;
+pub mod scan;
+pub mod watchdog;
+
+use std::path::Path;
+
+use crate::audit::{AuditReport, audit_claimed_leaf_source};
+use crate::canonical_json::from_canonical_json;
+use crate::cli::hex32;
+use crate::compression::read_artifact;
+use crate::consensus::layout_leaf_hash;
+use crate::ih::{
+    gc_block_hash, ih_proof_from_hashes, incremental_root_from_hashes, verify_ih_proof,
+};
+use crate::leaf_file::{ClaimedLeaves, claimed_block_hashes};
+use crate::merkle::{merkle_proof_from_hashes, merkle_root_from_hashes, verify_proof};
+use crate::messages::{DisputePacketMessage, Envelope, Message};
+use crate::registry::CircuitVersion;
+use crate::types::{CircuitLayout, GateDesc, GateSet};
+
+/// Dispute inputs. `claimed_leaves` is a `Vec` by default; a `LeafFile` keeps a large
+/// instance's leaves on disk.
+#[derive(Debug, Clone)]
+pub struct PrepareDisputeConfig<L = Vec<[u8; 71]>> {
+    pub circuit: &'static CircuitVersion,
+    pub bit_width: usize,
+    pub circuit_id: [u8; 32],
+    pub instance_id: u64,
+    /// Opened seed of the disputed instance.
+    pub seed: [u8; 32],
+    pub claimed_leaves: L,
+    /// Gate to challenge; defaults to the first mismatching gate.
+    pub gate_index: Option<usize>,
+    /// Allows challenging a gate whose claimed leaf is correct.
+    pub allow_false_challenge: bool,
+    /// Committed `rootGC` the claimed leaves must hash to.
+    pub expected_root_gc: Option<[u8; 32]>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreparedDispute {
+    pub gate_index: usize,
+    pub gate: GateDesc,
+    pub claimed_leaf: [u8; 71],
+    pub expected_leaf: [u8; 71],
+    pub audit: AuditReport,
+    pub root_gc: [u8; 32],
+    pub layout_root: [u8; 32],
+    pub ih_proof: Vec<[u8; 32]>,
+    pub layout_proof: Vec<[u8; 32]>,
+}
+
+impl PreparedDispute {
+    /// The `disputeGarbledTable` arguments for the instance `config` describes.
+    pub fn packet<L>(&self, config: &PrepareDisputeConfig<L>) -> DisputePacketMessage {
+        DisputePacketMessage {
+            circuit_id: config.circuit_id,
+            instance_id: config.instance_id,
+            seed: config.seed,
+            gate_index: self.gate_index as u64,
+            gate: self.gate,
+            leaf: self.claimed_leaf,
+            root_gc: self.root_gc,
+            layout_root: self.layout_root,
+            ih_proof: self.ih_proof.clone(),
+            layout_proof: self.layout_proof.clone(),
+        }
+    }
+
+    /// `packet` as an unsigned CBOR `Message::DisputePacket`.
+    pub fn to_cbor<L>(&self, config: &PrepareDisputeConfig<L>) -> Vec<u8> {
+        Message::DisputePacket(self.packet(config)).to_cbor()
+    }
+}
+
+/// Decodes a dispute packet, plain or signed (the signature is not checked here), and
+/// checks it with `check_dispute_packet`.
+pub fn decode_dispute_packet(raw: &[u8]) -> Result<DisputePacketMessage, String> {
+    let packet = match Envelope::from_cbor(raw)?.message() {
+        Message::DisputePacket(packet) => packet.clone(),
+        other => {
+            return Err(format!(
+                "expected a dispute packet, got message kind {}",
+                other.kind()
+            ));
+        }
+    };
+    check_dispute_packet(&packet)?;
+    Ok(packet)
+}
+
+/// Whether a packet file at `path` is written as JSON (a `.json` extension) or CBOR.
+pub fn is_json_packet_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "json")
+}
+
+/// Reads a packet file written by `prepare-dispute --out`, JSON or CBOR (told apart by the
+/// first byte, not the name), and checks it with `check_dispute_packet`.
+pub fn read_dispute_packet_file(path: &Path) -> Result<DisputePacketMessage, String> {
+    let raw = read_artifact(path)?;
+    let at = |e: String| format!("dispute packet {}: {e}", path.display());
+    if raw.first() == Some(&b'{') {
+        let text = std::str::from_utf8(&raw).map_err(|_| at("not valid UTF-8".to_string()))?;
+        let packet: DisputePacketMessage = from_canonical_json(text).map_err(at)?;
+        check_dispute_packet(&packet).map_err(at)?;
+        Ok(packet)
+    } else {
+        decode_dispute_packet(&raw).map_err(at)
+    }
+}
+
+/// Checks that a packet's proofs reach the roots it carries, the same checks
+/// `disputeGarbledTable` runs before recomputing the leaf.
+pub fn check_dispute_packet(packet: &DisputePacketMessage) -> Result<(), String> {
+    let block_hash = gc_block_hash(packet.gate_index, &packet.leaf);
+    if !verify_ih_proof(block_hash, &packet.ih_proof, packet.root_gc) {
+        return Err(format!(
+            "IH proof of gate {} does not reach rootGC {}",
+            packet.gate_index,
+            hex32(packet.root_gc)
+        ));
+    }
+    let layout_leaf = layout_leaf_hash(packet.circuit_id, packet.gate_index, packet.gate);
+    if !verify_proof(layout_leaf, &packet.layout_proof, packet.layout_root) {
+        return Err(format!(
+            "layout proof of gate {} does not reach layout root {}",
+            packet.gate_index,
+            hex32(packet.layout_root)
+        ));
+    }
+    Ok(())
+}
+
+/// Audits the claimed leaves, picks the gate to challenge and builds its proofs. Leaves are
+/// read one at a time, so a `LeafFile` is never loaded whole.
+pub fn prepare_dispute_packet<L: ClaimedLeaves>(
+    config: &PrepareDisputeConfig<L>,
+) -> Result<PreparedDispute, String> {
+    let (gates, audit) = audit_instance(config)?;
+    if audit.is_clean() && config.gate_index.is_none() {
+        return Err(
+            "No mismatches found between claimed and expected leaves; dispute packet not created"
+                .to_string(),
+        );
+    }
+    prepare_audited_dispute(config, gates, audit)
+}
+
+/// The circuit's gates and the audit of the claimed leaves against the regarbled ones.
+fn audit_instance<L: ClaimedLeaves>(
+    config: &PrepareDisputeConfig<L>,
+) -> Result<(Vec<GateDesc>, AuditReport), String> {
+    let gates = config.circuit.build(config.bit_width);
+    // `disputeGarbledTable` decodes every gate-set V2 opcode...
+    GateSet::V2.check_gates(&gates)?;
+    // ...but only recomputes classic 71-byte leaves.
+    let layout = CircuitLayout::new(config.circuit_id, config.instance_id, gates.clone())
+        .with_scheme_id(config.circuit.params.scheme_id);
+    let audit = audit_claimed_leaf_source(config.seed, &layout, &config.claimed_leaves)?;
+    Ok((gates, audit))
+}
+
+/// Picks the gate to challenge given `audit` and builds its proofs.
+fn prepare_audited_dispute<L: ClaimedLeaves>(
+    config: &PrepareDisputeConfig<L>,
+    gates: Vec<GateDesc>,
+    audit: AuditReport,
+) -> Result<PreparedDispute, String> {
+    let selected_gate_index = config
+        .gate_index
+        .unwrap_or_else(|| audit.mismatches[0].gate_index);
+    if selected_gate_index >= gates.len() {
+        return Err(format!(
+            "gate index {} out of range, total gates {}",
+            selected_gate_index,
+            gates.len()
+        ));
+    }
+
+    if audit.gate(selected_gate_index).is_none() && !config.allow_false_challenge {
+        return Err(format!(
+            "selected gate {} matches expected leaf; refusing false challenge (use --allow-false-challenge to override)",
+            selected_gate_index
+        ));
+    }
+
+    let block_hashes = claimed_block_hashes(&config.claimed_leaves)?;
+    let root_gc = incremental_root_from_hashes(&block_hashes);
+    if let Some(expected_root_gc) = config.expected_root_gc
+        && root_gc != expected_root_gc
+    {
+        return Err(format!(
+            "computed rootGC {} does not match expected {}",
+            hex32(root_gc),
+            hex32(expected_root_gc)
+        ));
+    }
+
+    let ih_proof = ih_proof_from_hashes(&block_hashes, selected_gate_index);
+    let layout_leaf_hashes = gates
+        .iter()
+        .enumerate()
+        .map(|(idx, gate)| layout_leaf_hash(config.circuit_id, idx as u64, *gate))
+        .collect::<Vec<_>>();
+    let layout_root = merkle_root_from_hashes(&layout_leaf_hashes);
+    let layout_proof = merkle_proof_from_hashes(&layout_leaf_hashes, selected_gate_index);
+
+    let claimed_leaf = config.claimed_leaves.leaf(selected_gate_index)?;
+    Ok(PreparedDispute {
+        gate_index: selected_gate_index,
+        gate: gates[selected_gate_index],
+        claimed_leaf,
+        // Gates missing from the audit match their recomputed leaf.
+        expected_leaf: audit
+            .gate(selected_gate_index)
+            .map_or(claimed_leaf, |gate| gate.expected_leaf),
+        audit,
+        root_gc,
+        layout_root,
+        ih_proof,
+        layout_proof,
+    })
+}
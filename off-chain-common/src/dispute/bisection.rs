@@ -0,0 +1,287 @@
+//! Bisection over the incremental-hash chain, so Bob can locate a bad gate of a large opened
+//! instance without downloading and scanning every claimed leaf.
+//!
+//! Bob recomputes the honest states `IH_0..IH_n` from the opened seed. `IH_0` is the zero
+//! state both sides share and Alice's committed `rootGC` is her `IH_n`, so the two chains
+//! agree at `lo = 0` and differ at `hi = n`. Each round Bob asks for Alice's state at the
+//! midpoint (`BisectionQueryMessage` / `CheckpointMessage`) and keeps the half where the
+//! chains still split. After `ceil(log2 n)` rounds `hi = lo + 1`, so block `lo` is the first
+//! differing one and Bob asks only for that leaf and its IH proof (`GateRevealMessage`) to
+//! build the usual one-gate `disputeGarbledTable` packet.
+//!
+//! A reveal that does not chain from the agreed `IH_lo` through Alice's checkpoint to
+//! `rootGC` shows Alice answered inconsistently; Bob then falls back to requesting every leaf.
+
+use crate::consensus::layout_leaf_hash;
+use crate::garble::{garble_circuit_iter, regarble_range};
+use crate::ih::{gc_block_hash, inc_hash, verify_ih_proof};
+use crate::merkle::{merkle_proof_from_hashes, merkle_root_from_hashes};
+use crate::messages::{BisectionQueryMessage, CheckpointMessage, GateRevealMessage, Message};
+use crate::types::{CircuitLayout, GateDesc, LeafFormat};
+
+/// IH states after each prefix of `block_hashes`: `states[i] = IH_i`, `states[0]` is zero.
+fn chain_states(block_hashes: impl Iterator<Item = [u8; 32]>) -> Vec<[u8; 32]> {
+    let mut states = vec![[0u8; 32]];
+    for block_hash in block_hashes {
+        let next = inc_hash(*states.last().expect("non-empty"), block_hash);
+        states.push(next);
+    }
+    states
+}
+
+/// Alice's side: answers bisection queries from the leaves she committed.
+#[derive(Debug, Clone)]
+pub struct BisectionResponder {
+    instance_id: u64,
+    leaves: Vec<[u8; 71]>,
+    block_hashes: Vec<[u8; 32]>,
+    states: Vec<[u8; 32]>,
+}
+
+impl BisectionResponder {
+    pub fn new(instance_id: u64, leaves: Vec<[u8; 71]>) -> Self {
+        let block_hashes = leaves
+            .iter()
+            .enumerate()
+            .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+            .collect::<Vec<_>>();
+        let states = chain_states(block_hashes.iter().copied());
+        Self {
+            instance_id,
+            leaves,
+            block_hashes,
+            states,
+        }
+    }
+
+    /// Committed `rootGC` of the claimed leaves.
+    pub fn root_gc(&self) -> [u8; 32] {
+        *self.states.last().expect("non-empty")
+    }
+
+    /// Answers a query with a `Checkpoint` or, for `reveal`, a `GateReveal`.
+    pub fn answer(&self, query: &BisectionQueryMessage) -> Result<Message, String> {
+        if query.instance_id != self.instance_id {
+            return Err(format!(
+                "query for instance {}, responder holds instance {}",
+                query.instance_id, self.instance_id
+            ));
+        }
+        let index = query.index as usize;
+        if query.reveal {
+            if index >= self.leaves.len() {
+                return Err(format!(
+                    "reveal index {index} out of range, total gates {}",
+                    self.leaves.len()
+                ));
+            }
+            let ih_proof = if self.leaves.len() == 1 {
+                Vec::new()
+            } else {
+                std::iter::once(self.states[index])
+                    .chain(self.block_hashes[index + 1..].iter().copied())
+                    .collect()
+            };
+            return Ok(Message::GateReveal(GateRevealMessage {
+                instance_id: self.instance_id,
+                gate_index: query.index,
+                leaf: self.leaves[index],
+                ih_proof,
+            }));
+        }
+        let state = self.states.get(index).ok_or_else(|| {
+            format!(
+                "checkpoint index {index} out of range, total gates {}",
+                self.leaves.len()
+            )
+        })?;
+        Ok(Message::Checkpoint(CheckpointMessage {
+            instance_id: self.instance_id,
+            index: query.index,
+            state: *state,
+        }))
+    }
+}
+
+/// One-gate dispute found by bisection; the arguments of `disputeGarbledTable`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BisectionOutcome {
+    pub gate_index: usize,
+    pub gate: GateDesc,
+    pub claimed_leaf: [u8; 71],
+    pub expected_leaf: [u8; 71],
+    pub root_gc: [u8; 32],
+    pub layout_root: [u8; 32],
+    pub ih_proof: Vec<[u8; 32]>,
+    pub layout_proof: Vec<[u8; 32]>,
+    /// Checkpoint rounds it took to narrow down the gate.
+    pub rounds: usize,
+}
+
+/// Bob's side: narrows `[lo, hi]` until one block remains.
+#[derive(Debug, Clone)]
+pub struct Bisection {
+    seed: [u8; 32],
+    layout: CircuitLayout,
+    committed_root: [u8; 32],
+    /// Honest `IH_0..IH_n` recomputed from the opened seed.
+    expected_states: Vec<[u8; 32]>,
+    lo: usize,
+    hi: usize,
+    /// Alice's claimed state at `hi`.
+    hi_state: [u8; 32],
+    rounds: usize,
+}
+
+impl Bisection {
+    /// Starts a bisection against Alice's committed `rootGC` for an opened instance.
+    /// Errors if the honest garbling already hashes to `committed_root`.
+    pub fn new(
+        seed: [u8; 32],
+        layout: &CircuitLayout,
+        committed_root: [u8; 32],
+    ) -> Result<Self, String> {
+        if layout.gates.is_empty() {
+            return Err("cannot bisect an empty circuit".to_string());
+        }
+        if layout.leaf_format != LeafFormat::V1 {
+            return Err("bisection disputes only cover 71-byte v1 leaves".to_string());
+        }
+        let expected_states = chain_states(
+            garble_circuit_iter(seed, layout)
+                .enumerate()
+                .map(|(idx, leaf)| gc_block_hash(idx as u64, &leaf)),
+        );
+        let n = layout.gates.len();
+        if expected_states[n] == committed_root {
+            return Err(
+                "committed rootGC matches the honest garbling; nothing to dispute".to_string(),
+            );
+        }
+        Ok(Self {
+            seed,
+            layout: layout.clone(),
+            committed_root,
+            expected_states,
+            lo: 0,
+            hi: n,
+            hi_state: committed_root,
+            rounds: 0,
+        })
+    }
+
+    /// Next checkpoint query, or the reveal query once one block remains.
+    pub fn next_query(&self) -> BisectionQueryMessage {
+        let (index, reveal) = match self.disputed_gate() {
+            Some(gate_index) => (gate_index, true),
+            None => (self.lo + (self.hi - self.lo) / 2, false),
+        };
+        BisectionQueryMessage {
+            instance_id: self.layout.instance_id,
+            index: index as u64,
+            reveal,
+        }
+    }
+
+    /// Gate whose block is the first one to differ, once narrowed to a single block.
+    pub fn disputed_gate(&self) -> Option<usize> {
+        (self.hi - self.lo == 1).then_some(self.lo)
+    }
+
+    /// Applies Alice's answer to the pending checkpoint query.
+    pub fn apply_checkpoint(&mut self, checkpoint: &CheckpointMessage) -> Result<(), String> {
+        let query = self.next_query();
+        if query.reveal {
+            return Err("bisection already narrowed to one gate".to_string());
+        }
+        if checkpoint.instance_id != query.instance_id || checkpoint.index != query.index {
+            return Err(format!(
+                "expected checkpoint {} of instance {}, got {} of instance {}",
+                query.index, query.instance_id, checkpoint.index, checkpoint.instance_id
+            ));
+        }
+        let mid = query.index as usize;
+        if checkpoint.state == self.expected_states[mid] {
+            self.lo = mid;
+        } else {
+            self.hi = mid;
+            self.hi_state = checkpoint.state;
+        }
+        self.rounds += 1;
+        Ok(())
+    }
+
+    /// Checks Alice's reveal of the disputed gate and builds the one-gate packet.
+    pub fn finish(&self, reveal: &GateRevealMessage) -> Result<BisectionOutcome, String> {
+        let gate_index = self
+            .disputed_gate()
+            .ok_or("bisection has not narrowed to one gate yet")?;
+        if reveal.instance_id != self.layout.instance_id || reveal.gate_index != gate_index as u64 {
+            return Err(format!(
+                "expected reveal of gate {gate_index} of instance {}, got gate {} of instance {}",
+                self.layout.instance_id, reveal.gate_index, reveal.instance_id
+            ));
+        }
+
+        let block_hash = gc_block_hash(gate_index as u64, &reveal.leaf);
+        let prefix_state = reveal.ih_proof.first().copied().unwrap_or([0u8; 32]);
+        if prefix_state != self.expected_states[gate_index] {
+            return Err(format!(
+                "reveal IH_{gate_index} differs from the agreed checkpoint"
+            ));
+        }
+        if inc_hash(prefix_state, block_hash) != self.hi_state {
+            return Err(format!(
+                "revealed leaf does not hash to Alice's checkpoint IH_{}",
+                gate_index + 1
+            ));
+        }
+        if !verify_ih_proof(block_hash, &reveal.ih_proof, self.committed_root) {
+            return Err("revealed IH proof does not chain to the committed rootGC".to_string());
+        }
+
+        let expected_leaf =
+            regarble_range(self.seed, &self.layout, gate_index..gate_index + 1)?.leaves[0];
+        let layout_leaf_hashes = self
+            .layout
+            .gates
+            .iter()
+            .enumerate()
+            .map(|(idx, gate)| layout_leaf_hash(self.layout.circuit_id, idx as u64, *gate))
+            .collect::<Vec<_>>();
+        Ok(BisectionOutcome {
+            gate_index,
+            gate: self.layout.gates[gate_index],
+            claimed_leaf: reveal.leaf,
+            expected_leaf,
+            root_gc: self.committed_root,
+            layout_root: merkle_root_from_hashes(&layout_leaf_hashes),
+            ih_proof: reveal.ih_proof.clone(),
+            layout_proof: merkle_proof_from_hashes(&layout_leaf_hashes, gate_index),
+            rounds: self.rounds,
+        })
+    }
+
+    /// Runs the whole exchange, sending each query through `ask` (e.g. over a
+    /// `secure_channel` stream with `messages::write_message` / `read_message`).
+    pub fn run(
+        mut self,
+        mut ask: impl FnMut(&BisectionQueryMessage) -> Result<Message, String>,
+    ) -> Result<BisectionOutcome, String> {
+        loop {
+            let query = self.next_query();
+            match ask(&query)? {
+                Message::Checkpoint(checkpoint) if !query.reveal => {
+                    self.apply_checkpoint(&checkpoint)?
+                }
+                Message::GateReveal(reveal) if query.reveal => return self.finish(&reveal),
+                other => {
+                    return Err(format!(
+                        "unexpected message kind {} in bisection",
+                        other.kind()
+                    ));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+//! Dispute scan over every opened instance at once, for `scan-disputes`.
+//!
+//! `prepare_dispute_packet` audits one instance; after `revealOpenings` Bob holds the seeds
+//! of all opened instances and Alice's claimed leaves for each. Regarbling is independent per
+//! instance, so every instance is audited on its own thread and each one with mismatches gets
+//! a packet for its first differing gate. Any of those packets slashes Alice on its own; the
+//! others are kept in case one is rejected.
+
+use std::thread;
+
+use crate::cli::hex32;
+use crate::dispute::{
+    PrepareDisputeConfig, PreparedDispute, audit_instance, prepare_audited_dispute,
+};
+use crate::ih::incremental_root_from_hashes;
+use crate::leaf_file::{ClaimedLeaves, claimed_block_hashes};
+
+#[derive(Debug, Clone)]
+pub enum ScanVerdict {
+    /// The claimed leaves are the regarbled ones.
+    Clean,
+    /// The claimed leaves differ from the regarbled ones; the packet challenges the first
+    /// differing gate.
+    Dispute(Box<PreparedDispute>),
+    /// The instance could not be audited, or its leaves cannot back a dispute on-chain.
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct InstanceScan {
+    pub instance_id: u64,
+    pub verdict: ScanVerdict,
+}
+
+/// Audits every instance of `configs`, each on its own thread, and returns the verdicts in
+/// the order of `configs`. `gate_index` and `allow_false_challenge` of the configs apply as
+/// in `prepare_dispute_packet`.
+pub fn scan_disputes<L: ClaimedLeaves + Sync>(
+    configs: &[PrepareDisputeConfig<L>],
+) -> Vec<InstanceScan> {
+    thread::scope(|scope| {
+        let handles = configs
+            .iter()
+            .map(|config| scope.spawn(move || scan_instance(config)))
+            .collect::<Vec<_>>();
+        configs
+            .iter()
+            .zip(handles)
+            .map(|(config, handle)| InstanceScan {
+                instance_id: config.instance_id,
+                verdict: handle
+                    .join()
+                    .unwrap_or_else(|_| ScanVerdict::Failed("audit thread panicked".to_string())),
+            })
+            .collect()
+    })
+}
+
+fn scan_instance<L: ClaimedLeaves>(config: &PrepareDisputeConfig<L>) -> ScanVerdict {
+    let (gates, audit) = match audit_instance(config) {
+        Ok(audited) => audited,
+        Err(e) => return ScanVerdict::Failed(e),
+    };
+    if audit.is_clean() && config.gate_index.is_none() {
+        // Honest leaves hashing to another root mean the commitment itself is wrong, which
+        // no garbled-table dispute can prove.
+        if let Some(expected_root_gc) = config.expected_root_gc {
+            let root_gc = match claimed_block_hashes(&config.claimed_leaves) {
+                Ok(hashes) => incremental_root_from_hashes(&hashes),
+                Err(e) => return ScanVerdict::Failed(e),
+            };
+            if root_gc != expected_root_gc {
+                return ScanVerdict::Failed(format!(
+                    "claimed leaves match the regarbled circuit but hash to {}, not to rootGC {}",
+                    hex32(root_gc),
+                    hex32(expected_root_gc)
+                ));
+            }
+        }
+        return ScanVerdict::Clean;
+    }
+    match prepare_audited_dispute(config, gates, audit) {
+        Ok(prepared) => ScanVerdict::Dispute(Box::new(prepared)),
+        Err(e) => ScanVerdict::Failed(e),
+    }
+}
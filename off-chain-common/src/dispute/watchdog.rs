@@ -0,0 +1,192 @@
+//! Bob's dispute watchdog: polls contract snapshots, audits every opened instance as soon as
+//! Alice reveals its seed, and hands back the first dispute packet while the window is open.
+//!
+//! An opened instance is honest when regarbling its seed hashes to the committed `rootGC`.
+//! Otherwise Bob needs the leaves Alice claimed for it (her `instance-<id>-leaves.txt`): they
+//! must hash to the same `rootGC`, and the first gate that differs from the regarbled leaf is
+//! disputed with `prepare_dispute_packet`. The contract slashes Alice on the first successful
+//! `disputeGarbledTable`, so one packet per session is enough.
+
+use std::collections::BTreeSet;
+
+use crate::cli::hex32;
+use crate::dispute::{PrepareDisputeConfig, PreparedDispute, prepare_dispute_packet};
+use crate::garble::garble_circuit_iter;
+use crate::ih::{gc_block_hash, inc_hash};
+use crate::protocol::Phase;
+use crate::registry::CircuitVersion;
+use crate::types::CircuitLayout;
+
+/// Contract `Stage.Dispute`.
+pub const DISPUTE_STAGE: u8 = 7;
+
+/// Opened instance as read from `revealedSeeds` and `instanceCommitments`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OpenedInstance {
+    pub instance_id: u64,
+    pub seed: [u8; 32],
+    pub root_gc: [u8; 32],
+}
+
+/// What the watchdog reads from the contract on each poll.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractSnapshot {
+    /// `currentStage()`.
+    pub stage: u8,
+    /// Timestamp of the latest block.
+    pub now: u64,
+    /// `deadlines().dispute`; only meaningful in the dispute stage.
+    pub dispute_deadline: u64,
+    /// Opened instances; empty until Alice calls `revealOpenings`.
+    pub opened: Vec<OpenedInstance>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WatchdogConfig {
+    pub circuit: &'static CircuitVersion,
+    pub bit_width: usize,
+    pub circuit_id: [u8; 32],
+    /// Seconds before the dispute deadline from which a pending dispute is reported as urgent.
+    pub margin_secs: u64,
+}
+
+#[derive(Debug, Clone)]
+pub enum InstanceVerdict {
+    /// Regarbling the opened seed reproduces the committed `rootGC`.
+    Honest,
+    /// Claimed leaves hash to `rootGC` and differ from the regarbled ones.
+    Dispute(Box<PreparedDispute>),
+    /// `rootGC` is wrong, but Bob holds no claimed leaves that prove it.
+    Unprovable(String),
+}
+
+#[derive(Debug, Clone)]
+pub enum WatchEvent {
+    /// Openings not revealed yet, in the given contract phase.
+    Waiting(Phase),
+    Audited {
+        instance_id: u64,
+        verdict: InstanceVerdict,
+    },
+    /// Every opened instance was audited and none yields a dispute packet.
+    NothingToDispute { seconds_left: u64 },
+    /// A dispute is pending and fewer than `margin_secs` remain.
+    Urgent { seconds_left: u64 },
+    /// The dispute window has passed (or the contract moved on).
+    WindowClosed,
+}
+
+/// Audits one opened instance against its committed `rootGC`. `claimed_leaves` is only
+/// called when the regarbled root differs.
+pub fn audit_opened_instance(
+    config: &WatchdogConfig,
+    opened: &OpenedInstance,
+    claimed_leaves: impl FnOnce() -> Option<Vec<[u8; 71]>>,
+) -> Result<InstanceVerdict, String> {
+    let layout = CircuitLayout::new(
+        config.circuit_id,
+        opened.instance_id,
+        config.circuit.build(config.bit_width),
+    )
+    .with_scheme_id(config.circuit.params.scheme_id);
+    let honest_root = garble_circuit_iter(opened.seed, &layout)
+        .enumerate()
+        .fold([0u8; 32], |state, (idx, leaf)| {
+            inc_hash(state, gc_block_hash(idx as u64, &leaf))
+        });
+    if honest_root == opened.root_gc {
+        return Ok(InstanceVerdict::Honest);
+    }
+
+    let Some(claimed_leaves) = claimed_leaves() else {
+        return Ok(InstanceVerdict::Unprovable(format!(
+            "committed rootGC {} differs from the regarbled {}, but no claimed leaves are available",
+            hex32(opened.root_gc),
+            hex32(honest_root)
+        )));
+    };
+    // Leaves that do not hash to rootGC cannot back an IH proof on-chain.
+    let claimed_root = claimed_leaves
+        .iter()
+        .enumerate()
+        .fold([0u8; 32], |state, (idx, leaf)| {
+            inc_hash(state, gc_block_hash(idx as u64, leaf))
+        });
+    if claimed_root != opened.root_gc {
+        return Ok(InstanceVerdict::Unprovable(format!(
+            "claimed leaves hash to {}, not to the committed rootGC {}",
+            hex32(claimed_root),
+            hex32(opened.root_gc)
+        )));
+    }
+    let prepared = prepare_dispute_packet(&PrepareDisputeConfig {
+        circuit: config.circuit,
+        bit_width: config.bit_width,
+        circuit_id: config.circuit_id,
+        instance_id: opened.instance_id,
+        seed: opened.seed,
+        claimed_leaves,
+        gate_index: None,
+        allow_false_challenge: false,
+        expected_root_gc: Some(opened.root_gc),
+    })?;
+    Ok(InstanceVerdict::Dispute(Box::new(prepared)))
+}
+
+/// Polling state: which instances were audited and whether a dispute is pending.
+#[derive(Debug, Clone)]
+pub struct Watchdog {
+    config: WatchdogConfig,
+    audited: BTreeSet<u64>,
+    pending_dispute: bool,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            audited: BTreeSet::new(),
+            pending_dispute: false,
+        }
+    }
+
+    /// Processes one snapshot. `claimed_leaves` loads Alice's claimed leaves of an instance,
+    /// if Bob has them. Instances are audited once; a snapshot after the window yields only
+    /// `WindowClosed`.
+    pub fn poll(
+        &mut self,
+        snapshot: &ContractSnapshot,
+        mut claimed_leaves: impl FnMut(u64) -> Option<Vec<[u8; 71]>>,
+    ) -> Result<Vec<WatchEvent>, String> {
+        if snapshot.stage < DISPUTE_STAGE {
+            return Ok(vec![WatchEvent::Waiting(Phase::from_contract_stage(
+                snapshot.stage,
+            )?)]);
+        }
+        if snapshot.stage > DISPUTE_STAGE || snapshot.now > snapshot.dispute_deadline {
+            return Ok(vec![WatchEvent::WindowClosed]);
+        }
+
+        let mut events = Vec::new();
+        for opened in &snapshot.opened {
+            if !self.audited.insert(opened.instance_id) {
+                continue;
+            }
+            let verdict =
+                audit_opened_instance(&self.config, opened, || claimed_leaves(opened.instance_id))?;
+            self.pending_dispute |= matches!(verdict, InstanceVerdict::Dispute(_));
+            events.push(WatchEvent::Audited {
+                instance_id: opened.instance_id,
+                verdict,
+            });
+        }
+
+        let seconds_left = snapshot.dispute_deadline - snapshot.now;
+        if self.pending_dispute && seconds_left <= self.config.margin_secs {
+            events.push(WatchEvent::Urgent { seconds_left });
+        } else if !self.pending_dispute && !snapshot.opened.is_empty() {
+            events.push(WatchEvent::NothingToDispute { seconds_left });
+        }
+        Ok(events)
+    }
+}
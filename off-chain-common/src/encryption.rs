@@ -0,0 +1,199 @@
+//! Passphrase encryption at rest for secret artifacts: instance seeds and garbled-circuit
+//! leaves. Anyone who reads an unopened instance's seed can evaluate it with both labels of
+//! every wire, so on a shared disk these files must not sit in plaintext.
+//!
+//! An encrypted file is `ENCRYPTION_MAGIC || salt (16) || nonce (12) || AES-256-GCM
+//! ciphertext`, with the key derived from the passphrase by Argon2id (default parameters)
+//! over the salt and the 36-byte header as associated data. It keeps its usual name (so
+//! compressed files stay `<name>.zst`, compressed before encryption) and `read_artifact`
+//! recognises the magic and decrypts, asking for the passphrase once per process: from
+//! `ARTIFACT_PASSPHRASE` when set, else on the terminal. A writer uses one `ArtifactKey`, and
+//! so one salt, for a whole export, so Argon2 runs once per directory rather than per file.
+
+use std::fmt;
+use std::sync::Mutex;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+
+use crate::cli::hex_prefixed;
+use crate::secrets::random_bytes32;
+
+/// First bytes of every encrypted artifact.
+pub const ENCRYPTION_MAGIC: [u8; 8] = *b"PPAENC1\0";
+
+/// Environment variable read before prompting for the passphrase.
+pub const PASSPHRASE_ENV: &str = "ARTIFACT_PASSPHRASE";
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = ENCRYPTION_MAGIC.len() + SALT_LEN + NONCE_LEN;
+
+/// Passphrase entered in this process, reused for every later file.
+static PASSPHRASE: Mutex<Option<String>> = Mutex::new(None);
+/// Keys derived in this process, one per salt seen.
+static KEYS: Mutex<Vec<ArtifactKey>> = Mutex::new(Vec::new());
+
+/// AES-256-GCM key derived from a passphrase and salt.
+#[derive(Clone)]
+pub struct ArtifactKey {
+    salt: [u8; SALT_LEN],
+    key: [u8; 32],
+}
+
+impl fmt::Debug for ArtifactKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ArtifactKey(salt={})", hex_prefixed(&self.salt))
+    }
+}
+
+impl ArtifactKey {
+    pub fn derive(passphrase: &str, salt: [u8; SALT_LEN]) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("artifact passphrase must not be empty".to_string());
+        }
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+            .map_err(|e| format!("failed to derive artifact key: {e}"))?;
+        Ok(Self { salt, key })
+    }
+
+    /// Derives a key under a fresh random salt.
+    pub fn generate(passphrase: &str) -> Result<Self, String> {
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&random_bytes32()?[..SALT_LEN]);
+        Self::derive(passphrase, salt)
+    }
+
+    pub fn salt(&self) -> [u8; SALT_LEN] {
+        self.salt
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&self.key))
+    }
+
+    /// Encrypts `plaintext` under a fresh random nonce.
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        out.extend_from_slice(&ENCRYPTION_MAGIC);
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&random_bytes32()?[..NONCE_LEN]);
+        let nonce = Nonce::from_slice(&out[HEADER_LEN - NONCE_LEN..HEADER_LEN]);
+        let payload = Payload {
+            msg: plaintext,
+            aad: &out[..HEADER_LEN],
+        };
+        let ciphertext = self
+            .cipher()
+            .encrypt(nonce, payload)
+            .map_err(|_| "artifact encryption failed".to_string())?;
+        out.extend_from_slice(&ciphertext);
+        Ok(out)
+    }
+
+    /// Decrypts an encrypted artifact written under this key's salt.
+    pub fn decrypt(&self, bytes: &[u8]) -> Result<Vec<u8>, String> {
+        if encrypted_salt(bytes)? != self.salt {
+            return Err("artifact was encrypted under a different salt".to_string());
+        }
+        let payload = Payload {
+            msg: &bytes[HEADER_LEN..],
+            aad: &bytes[..HEADER_LEN],
+        };
+        self.cipher()
+            .decrypt(
+                Nonce::from_slice(&bytes[HEADER_LEN - NONCE_LEN..HEADER_LEN]),
+                payload,
+            )
+            .map_err(|_| "wrong passphrase or corrupted encrypted artifact".to_string())
+    }
+}
+
+/// Whether `bytes` start with `ENCRYPTION_MAGIC`.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(&ENCRYPTION_MAGIC)
+}
+
+/// Salt recorded in an encrypted artifact's header.
+pub fn encrypted_salt(bytes: &[u8]) -> Result<[u8; SALT_LEN], String> {
+    if !is_encrypted(bytes) {
+        return Err("not an encrypted artifact".to_string());
+    }
+    if bytes.len() < HEADER_LEN + 16 {
+        return Err(format!(
+            "encrypted artifact truncated to {} bytes",
+            bytes.len()
+        ));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&bytes[ENCRYPTION_MAGIC.len()..ENCRYPTION_MAGIC.len() + SALT_LEN]);
+    Ok(salt)
+}
+
+/// Decrypts an encrypted artifact with `passphrase`.
+pub fn decrypt(bytes: &[u8], passphrase: &str) -> Result<Vec<u8>, String> {
+    ArtifactKey::derive(passphrase, encrypted_salt(bytes)?)?.decrypt(bytes)
+}
+
+/// Sets the passphrase used by `decrypt_artifact` and `artifact_passphrase` for the rest of
+/// the process, e.g. in tests or when a caller read it from elsewhere.
+pub fn set_artifact_passphrase(passphrase: &str) {
+    *PASSPHRASE.lock().expect("passphrase lock") = Some(passphrase.to_string());
+    KEYS.lock().expect("key cache lock").clear();
+}
+
+/// The process's artifact passphrase: the one already entered, else `ARTIFACT_PASSPHRASE`,
+/// else read from the terminal (twice with `confirm`, for a new export).
+pub fn artifact_passphrase(confirm: bool) -> Result<String, String> {
+    let mut cached = PASSPHRASE.lock().expect("passphrase lock");
+    if let Some(passphrase) = cached.as_ref() {
+        return Ok(passphrase.clone());
+    }
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let prompt_err = |e| {
+                format!("failed to read the artifact passphrase (or set {PASSPHRASE_ENV}): {e}")
+            };
+            let entered =
+                rpassword::prompt_password("Artifact passphrase: ").map_err(prompt_err)?;
+            if confirm {
+                let repeated = rpassword::prompt_password("Repeat artifact passphrase: ")
+                    .map_err(prompt_err)?;
+                if repeated != entered {
+                    return Err("artifact passphrases do not match".to_string());
+                }
+            }
+            entered
+        }
+    };
+    if passphrase.is_empty() {
+        return Err("artifact passphrase must not be empty".to_string());
+    }
+    *cached = Some(passphrase.clone());
+    Ok(passphrase)
+}
+
+/// Decrypts an encrypted artifact with the process's passphrase, deriving each salt's key
+/// only once.
+pub fn decrypt_artifact(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let salt = encrypted_salt(bytes)?;
+    let cached = KEYS
+        .lock()
+        .expect("key cache lock")
+        .iter()
+        .find(|key| key.salt == salt)
+        .cloned();
+    let key = match cached {
+        Some(key) => key,
+        None => {
+            let key = ArtifactKey::derive(&artifact_passphrase(false)?, salt)?;
+            KEYS.lock().expect("key cache lock").push(key.clone());
+            key
+        }
+    };
+    key.decrypt(bytes)
+}
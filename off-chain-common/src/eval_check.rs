@@ -0,0 +1,246 @@
+//! Bob's consistency check of an eval package before `evaluate-m`, for `verify-eval-package`.
+//!
+//! Evaluation stops at the first gate a broken package trips over, with an error that rarely
+//! names the cause. Each check here looks at one part of the package on its own, so a bad
+//! package is reported by what is wrong with it: leaves that do not match the layout or do
+//! not hash to the committed rootGC, label counts that miss the bit width, plain y-wire label
+//! pairs that would hand Bob both labels of a wire, NOT hints that do not cover exactly the NOT
+//! gates needing one, label pairs sharing a permutation bit, and output anchors that coincide
+//! or do not open with the output labels.
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::cli::hex32;
+use crate::decoding::OutputDecodingTable;
+use crate::evaluation::{NotGateHint, check_leaf_header};
+use crate::ih::{gc_block_hash, incremental_root_from_hashes};
+use crate::labels::{check_point_and_permute, get_permutation_bit};
+use crate::types::{CircuitLayout, GarblingScheme, GateType, NotGateMode};
+
+/// One part of the package `check_eval_package` looks at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EvalCheck {
+    /// One leaf per gate, each with its gate's header.
+    Leaves,
+    /// The leaves hash to the committed rootGC.
+    RootGc,
+    /// One label per x-wire.
+    AliceLabels,
+    /// No plain y-wire label pairs; Bob gets his labels through OT only.
+    YOffers,
+    /// One hint per NOT gate needing one, each with well-formed label pairs.
+    NotHints,
+    /// Distinct anchors `h0`/`h1` that open with `lout_true`/`lout_false`.
+    Anchors,
+}
+
+impl fmt::Display for EvalCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Leaves => "leaves",
+            Self::RootGc => "root_gc",
+            Self::AliceLabels => "alice_labels",
+            Self::YOffers => "y_offers",
+            Self::NotHints => "not_hints",
+            Self::Anchors => "anchors",
+        })
+    }
+}
+
+/// A problem one check found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalCheckFailure {
+    pub check: EvalCheck,
+    pub reason: String,
+}
+
+impl fmt::Display for EvalCheckFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.check, self.reason)
+    }
+}
+
+/// The parts of an eval package the checks read, however it was stored.
+#[derive(Debug, Clone, Copy)]
+pub struct EvalPackageParts<'a> {
+    pub bit_width: usize,
+    pub decoding: &'a OutputDecodingTable,
+    pub gc_leaves: &'a [[u8; 71]],
+    pub alice_labels: &'a [[u8; 16]],
+    pub not_hints: &'a [NotGateHint],
+    /// Plain label pairs of y-wires found in the package; an honest one has none.
+    pub y_offers: &'a [([u8; 16], [u8; 16])],
+}
+
+/// The checks that ran, in order, and what they found.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EvalPackageReport {
+    pub checked: Vec<EvalCheck>,
+    pub failures: Vec<EvalCheckFailure>,
+}
+
+impl EvalPackageReport {
+    pub fn is_consistent(&self) -> bool {
+        self.failures.is_empty()
+    }
+
+    fn record(&mut self, check: EvalCheck, problems: Vec<String>) {
+        self.checked.push(check);
+        self.failures.extend(
+            problems
+                .into_iter()
+                .map(|reason| EvalCheckFailure { check, reason }),
+        );
+    }
+}
+
+/// Checks `parts` against the `layout` of the evaluated instance and the rootGC committed
+/// for it.
+pub fn check_eval_package(
+    parts: &EvalPackageParts,
+    layout: &CircuitLayout,
+    committed_root_gc: [u8; 32],
+) -> EvalPackageReport {
+    let mut report = EvalPackageReport::default();
+    report.record(EvalCheck::Leaves, check_leaves(parts.gc_leaves, layout));
+    report.record(
+        EvalCheck::RootGc,
+        check_root_gc(parts.gc_leaves, committed_root_gc),
+    );
+    report.record(
+        EvalCheck::AliceLabels,
+        check_count("x-wire labels", parts.alice_labels.len(), parts.bit_width),
+    );
+    report.record(EvalCheck::YOffers, check_y_offers(parts.y_offers));
+    report.record(
+        EvalCheck::NotHints,
+        check_not_hints(parts.not_hints, layout),
+    );
+    report.record(EvalCheck::Anchors, check_anchors(parts.decoding));
+    report
+}
+
+fn check_count(what: &str, found: usize, bit_width: usize) -> Vec<String> {
+    if found == bit_width {
+        return Vec::new();
+    }
+    vec![format!("{found} {what} for bit-width {bit_width}")]
+}
+
+fn check_leaves(leaves: &[[u8; 71]], layout: &CircuitLayout) -> Vec<String> {
+    if leaves.len() != layout.gates.len() {
+        return vec![format!(
+            "{} leaves for {} gates",
+            leaves.len(),
+            layout.gates.len()
+        )];
+    }
+    let mismatches = layout
+        .gates
+        .iter()
+        .zip(leaves)
+        .enumerate()
+        .filter_map(|(gate_index, (gate, leaf))| {
+            check_leaf_header(layout.scheme_id(), gate_index, *gate, leaf).err()
+        })
+        .collect::<Vec<_>>();
+    match mismatches.first() {
+        Some(first) => vec![format!(
+            "{} leaves do not match the layout, first {first}",
+            mismatches.len()
+        )],
+        None => Vec::new(),
+    }
+}
+
+fn check_root_gc(leaves: &[[u8; 71]], committed_root_gc: [u8; 32]) -> Vec<String> {
+    let block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    let root_gc = incremental_root_from_hashes(&block_hashes);
+    if root_gc == committed_root_gc {
+        return Vec::new();
+    }
+    vec![format!(
+        "leaves hash to {}, not to the committed rootGC {}",
+        hex32(root_gc),
+        hex32(committed_root_gc)
+    )]
+}
+
+fn check_y_offers(offers: &[([u8; 16], [u8; 16])]) -> Vec<String> {
+    if offers.is_empty() {
+        return Vec::new();
+    }
+    vec![format!(
+        "{} y-wire label pairs are published, Bob must get his labels by OT only",
+        offers.len()
+    )]
+}
+
+fn check_not_hints(hints: &[NotGateHint], layout: &CircuitLayout) -> Vec<String> {
+    let hinted =
+        layout.scheme != GarblingScheme::FreeXor && layout.not_gate_mode == NotGateMode::Hinted;
+    let needed = layout
+        .gates
+        .iter()
+        .enumerate()
+        .filter(|(_, gate)| hinted && gate.gate_type == GateType::Not)
+        .map(|(gate_index, _)| gate_index)
+        .collect::<BTreeSet<_>>();
+    let mut problems = Vec::new();
+    let mut seen = BTreeSet::new();
+    for hint in hints {
+        let gate = hint.gate_index;
+        if !seen.insert(gate) {
+            problems.push(format!("gate {gate} has more than one hint"));
+        } else if !needed.contains(&gate) {
+            problems.push(format!("gate {gate} is not a NOT gate needing a hint"));
+        }
+        let pairs = [
+            ("input", hint.in_label0, hint.in_label1),
+            ("output", hint.out_if_in0, hint.out_if_in1),
+        ];
+        for (side, label0, label1) in pairs {
+            if let Err(e) = check_point_and_permute([label0, label1]) {
+                problems.push(format!("gate {gate} {side}: {e}"));
+            }
+        }
+    }
+    let missing = needed.difference(&seen).collect::<Vec<_>>();
+    if let Some(first) = missing.first() {
+        problems.push(format!(
+            "{} NOT gates have no hint, first gate {first}",
+            missing.len()
+        ));
+    }
+    problems
+}
+
+fn check_anchors(decoding: &OutputDecodingTable) -> Vec<String> {
+    let mut problems = Vec::new();
+    if decoding.h0 == decoding.h1 {
+        problems.push(format!("h0 and h1 are both {}", hex32(decoding.h0)));
+    }
+    let label = |lout: [u8; 32]| -> [u8; 16] { lout[..16].try_into().expect("16 of 32 bytes") };
+    let (lout_true, lout_false) = (label(decoding.lout_true), label(decoding.lout_false));
+    if let Err(e) = check_point_and_permute([lout_false, lout_true]) {
+        problems.push(format!("output labels: {e}"));
+    }
+    if decoding.lsb_true != get_permutation_bit(lout_true) {
+        problems.push(format!(
+            "lsb_true={} is not the permutation bit of lout_true",
+            decoding.lsb_true
+        ));
+    }
+    if !decoding.anchor_matches(lout_true).0 {
+        problems.push("lout_true does not open h0".to_string());
+    }
+    if !decoding.anchor_matches(lout_false).1 {
+        problems.push("lout_false does not open h1".to_string());
+    }
+    problems
+}
@@ -0,0 +1,125 @@
+//! `eval-meta.json`: the versioned description of a prepared eval dir (bit width and output
+//! decoding table) that Bob's `ot-choose` and `evaluate-m --eval-dir` start from.
+//!
+//! The document is canonical JSON with a `version` field. Readers reject versions they do not
+//! know instead of guessing at renamed or added fields, and validate the decoding table on
+//! load. Eval dirs from before the JSON form only have the `key=value` `eval-meta.txt`; `read`
+//! still accepts it.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::artifact_layout::read_layout_version;
+use crate::canonical_json::{from_canonical_json, to_canonical_json};
+use crate::compression::{find_artifact, read_artifact_to_string};
+use crate::decoding::OutputDecodingTable;
+
+/// File name of the JSON document inside an eval dir.
+pub const EVAL_META_FILE: &str = "eval-meta.json";
+
+/// `key=value` file written before `EVAL_META_FILE`.
+pub const LEGACY_EVAL_META_FILE: &str = "eval-meta.txt";
+
+/// Schema version written by `EvalMeta::new`.
+pub const EVAL_META_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EvalMeta {
+    pub version: u32,
+    pub bit_width: u16,
+    pub decoding: OutputDecodingTable,
+}
+
+impl EvalMeta {
+    pub fn new(bit_width: u16, decoding: OutputDecodingTable) -> Self {
+        Self {
+            version: EVAL_META_VERSION,
+            bit_width,
+            decoding,
+        }
+    }
+
+    /// Checks the version, a non-zero bit width and that `lsb_true` is the permutation bit
+    /// of `lout_true`.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != EVAL_META_VERSION {
+            return Err(format!(
+                "unsupported eval-meta version {} (expected {EVAL_META_VERSION})",
+                self.version
+            ));
+        }
+        if self.bit_width == 0 {
+            return Err("bit_width must be positive".to_string());
+        }
+        let lsb_true = self.decoding.lout_true[0] & 1;
+        if self.decoding.lsb_true != lsb_true {
+            return Err(format!(
+                "lsb_true={} does not match the permutation bit {lsb_true} of lout_true",
+                self.decoding.lsb_true
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        to_canonical_json(self)
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let meta: Self = from_canonical_json(raw)?;
+        meta.validate()?;
+        Ok(meta)
+    }
+
+    /// Parses a legacy `eval-meta.txt`.
+    pub fn from_key_values(raw: &str) -> Result<Self, String> {
+        let mut entries = Vec::new();
+        for (line_idx, line) in raw.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let (key, value) = trimmed
+                .split_once('=')
+                .ok_or_else(|| format!("invalid key=value at line {}", line_idx + 1))?;
+            entries.push((key.trim(), value.trim()));
+        }
+        let bit_width = entries
+            .iter()
+            .find_map(|(key, value)| (*key == "bit_width").then_some(*value))
+            .ok_or_else(|| "missing key 'bit_width'".to_string())?;
+        let bit_width = bit_width
+            .parse::<u16>()
+            .map_err(|_| format!("invalid bit_width '{bit_width}'"))?;
+        let meta = Self::new(bit_width, OutputDecodingTable::from_key_values(entries)?);
+        meta.validate()?;
+        Ok(meta)
+    }
+
+    /// Writes `dir/eval-meta.json`.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = dir.join(EVAL_META_FILE);
+        fs::write(&path, self.to_json()?)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+
+    /// Reads `dir/eval-meta.json`, or the legacy `eval-meta.txt` when only that exists. Fails
+    /// for an eval dir stamped with a layout version this release does not know.
+    pub fn read(dir: &Path) -> Result<Self, String> {
+        read_layout_version(dir)?;
+        let path = dir.join(EVAL_META_FILE);
+        let legacy = dir.join(LEGACY_EVAL_META_FILE);
+        let (path, parsed) = if find_artifact(&path).is_none() && find_artifact(&legacy).is_some() {
+            let parsed = Self::from_key_values(&read_artifact_to_string(&legacy)?);
+            (legacy, parsed)
+        } else {
+            let parsed = Self::from_json(&read_artifact_to_string(&path)?);
+            (path, parsed)
+        };
+        parsed.map_err(|e| format!("invalid eval meta {}: {e}", path.display()))
+    }
+}
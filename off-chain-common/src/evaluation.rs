@@ -1,13 +1,30 @@
-use crate::consensus::{compute_row_key, derive_wire_label, expand_pad, xor16};
-use crate::types::{CircuitLayout, GateDesc, GateType};
+use serde::{Deserialize, Serialize};
+
+use crate::canonical_json::hex_bytes;
+use crate::consensus::{
+    LEAF_BYTES_LEN, ROW_MAC_LEN, check_leaf_scheme, compute_row_key, derive_constant_label,
+    derive_wire_label, expand_pad_with, row_mac, truth_table, xor16,
+};
+use crate::garble::{LayoutLabels, WireLabelProvider};
+use crate::labels::derive_label_pair;
+use crate::scenario::Comparison;
+use crate::types::{
+    CircuitLayout, ConstantWire, GarblingScheme, GateDesc, GateType, LeafFormat, NotGateMode,
+    RowAuthMode, SchemeId,
+};
 
 /// Auxiliary material for evaluating canonical `NOT` gates whose rows are zeroed.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct NotGateHint {
     pub gate_index: usize,
+    #[serde(with = "hex_bytes")]
     pub in_label0: [u8; 16],
+    #[serde(with = "hex_bytes")]
     pub out_if_in0: [u8; 16], // semantic: 0 -> 1
+    #[serde(with = "hex_bytes")]
     pub in_label1: [u8; 16],
+    #[serde(with = "hex_bytes")]
     pub out_if_in1: [u8; 16], // semantic: 1 -> 0
 }
 
@@ -26,6 +43,129 @@ pub fn u64_to_bits_le(value: u64, bit_width: usize) -> Vec<u8> {
         .collect()
 }
 
+/// Little-endian bit decomposition of a `u128`; bits past 128 are zero.
+pub fn u128_to_bits_le(value: u128, bit_width: usize) -> Vec<u8> {
+    (0..bit_width)
+        .map(|idx| {
+            if idx < 128 {
+                ((value >> idx) & 1) as u8
+            } else {
+                0
+            }
+        })
+        .collect()
+}
+
+/// Little-endian bit decomposition of a 256-bit value given as big-endian `uint256` bytes
+/// (the `uint256_from_u64` layout); bits past 256 are zero.
+pub fn u256_to_bits_le(value_be: [u8; 32], bit_width: usize) -> Vec<u8> {
+    (0..bit_width)
+        .map(|idx| {
+            if idx >= 256 {
+                return 0;
+            }
+            let byte = value_be[31 - idx / 8];
+            (byte >> (idx % 8)) & 1
+        })
+        .collect()
+}
+
+/// Inverse of `u256_to_bits_le`: returns big-endian `uint256` bytes.
+pub fn bits_le_to_u256(bits: &[u8]) -> Result<[u8; 32], String> {
+    let mut out = [0u8; 32];
+    for (idx, bit) in bits.iter().enumerate() {
+        match (*bit, idx < 256) {
+            (0, _) => {}
+            (1, true) => out[31 - idx / 8] |= 1 << (idx % 8),
+            (1, false) => return Err(format!("bit {idx} does not fit in uint256")),
+            (other, _) => return Err(format!("invalid bit value {other} at index {idx}")),
+        }
+    }
+    Ok(out)
+}
+
+/// Number of significant bits in a big-endian `uint256` value (`0` for zero).
+pub fn u256_bit_length(value_be: [u8; 32]) -> usize {
+    value_be
+        .iter()
+        .position(|byte| *byte != 0)
+        .map_or(0, |idx| {
+            (32 - idx) * 8 - value_be[idx].leading_zeros() as usize
+        })
+}
+
+/// Inverse of `u64_to_bits_le`: bit 0 is LSB. Bits beyond 64 must be zero.
+pub fn bits_le_to_u64(bits: &[u8]) -> Result<u64, String> {
+    let mut value = 0u64;
+    for (idx, bit) in bits.iter().enumerate() {
+        match (*bit, idx < 64) {
+            (0, _) => {}
+            (1, true) => value |= 1u64 << idx,
+            (1, false) => return Err(format!("bit {idx} does not fit in u64")),
+            (other, _) => return Err(format!("invalid bit value {other} at index {idx}")),
+        }
+    }
+    Ok(value)
+}
+
+/// Evaluates a gate list on plaintext bits (no garbling); `inputs[i]` is the value of wire `i`.
+/// Returns the value of every wire (`None` for wires never assigned).
+/// Useful as a reference when checking layouts and garbled evaluation results.
+pub fn evaluate_plain_circuit(
+    gates: &[GateDesc],
+    inputs: &[u8],
+) -> Result<Vec<Option<u8>>, String> {
+    evaluate_plain(gates, inputs, &[])
+}
+
+/// Like `evaluate_plain_circuit`, additionally assigning the layout's constant wires.
+pub fn evaluate_plain_layout(
+    layout: &CircuitLayout,
+    inputs: &[u8],
+) -> Result<Vec<Option<u8>>, String> {
+    layout.check_constant_wires()?;
+    evaluate_plain(&layout.gates, inputs, &layout.constant_wires)
+}
+
+fn evaluate_plain(
+    gates: &[GateDesc],
+    inputs: &[u8],
+    constants: &[ConstantWire],
+) -> Result<Vec<Option<u8>>, String> {
+    let mut max_wire = inputs.len().saturating_sub(1);
+    for constant in constants {
+        max_wire = max_wire.max(constant.wire as usize);
+    }
+    for gate in gates {
+        max_wire = max_wire
+            .max(gate.wire_a as usize)
+            .max(gate.wire_b as usize)
+            .max(gate.wire_c as usize);
+    }
+    let mut values = vec![None::<u8>; max_wire + 1];
+    for (wire, bit) in inputs.iter().enumerate() {
+        values[wire] = Some(*bit & 1);
+    }
+    for constant in constants {
+        values[constant.wire as usize] = Some(constant.value & 1);
+    }
+
+    for (gate_idx, gate) in gates.iter().enumerate() {
+        let read = |wire: u16| {
+            values[wire as usize]
+                .ok_or_else(|| format!("missing value for wire={wire} gate={gate_idx}"))
+        };
+        let a = read(gate.wire_a)?;
+        let out = match gate.gate_type {
+            GateType::Not => a ^ 1,
+            gate_type => truth_table(gate_type, a, read(gate.wire_b)?),
+        };
+        values[gate.wire_c as usize] = Some(out);
+    }
+
+    Ok(values)
+}
+
 /// Returns output wire id for a layout (the last gate output in this MVP circuit format).
 pub fn output_wire_from_layout(gates: &[GateDesc]) -> Result<u16, String> {
     gates
@@ -35,21 +175,68 @@ pub fn output_wire_from_layout(gates: &[GateDesc]) -> Result<u16, String> {
 }
 
 /// Returns the `x > y` output wire for `build_millionaires_layout(bit_width)`.
-/// Layout invariant:
-/// - `bit_width == 1`: output is the last gate (single `a & !b`)
-/// - `bit_width >= 2`: each following bit appends `gt_new` then `eq_new`,
-///   so the final `gt_new` is the penultimate gate output.
 pub fn millionaires_gt_output_wire(gates: &[GateDesc], bit_width: usize) -> Result<u16, String> {
+    comparison_output_wire(gates, bit_width, Comparison::Gt)
+}
+
+/// Returns the `x == y` output wire for `build_millionaires_layout(bit_width)`.
+pub fn millionaires_eq_output_wire(gates: &[GateDesc], bit_width: usize) -> Result<u16, String> {
+    comparison_output_wire(gates, bit_width, Comparison::Eq)
+}
+
+/// Three-way Millionaires result decoded from the `gt` and `eq` output bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MillionairesOutcome {
+    /// `x > y`
+    AliceWins,
+    /// `x < y`
+    BobWins,
+    /// `x == y`; settlement refunds both deposits.
+    Tie,
+}
+
+/// Decodes `(gt, eq)` output bits; both set at once is an invalid evaluation.
+pub fn millionaires_outcome(gt: u8, eq: u8) -> Result<MillionairesOutcome, String> {
+    match (gt, eq) {
+        (1, 0) => Ok(MillionairesOutcome::AliceWins),
+        (0, 0) => Ok(MillionairesOutcome::BobWins),
+        (0, 1) => Ok(MillionairesOutcome::Tie),
+        _ => Err(format!("invalid millionaires output bits gt={gt} eq={eq}")),
+    }
+}
+
+/// Returns the result wire for `build_comparison_layout(bit_width, comparison)`.
+/// Layout invariant of the shared comparator:
+/// - `bit_width == 1`: gates are `xor, eq, not_b, gt`, so `gt` is last and `eq` is second
+/// - `bit_width >= 2`: each following bit appends `gt_new` then `eq_new`,
+///   so the final `gt_new` is the penultimate gate output and `eq_new` the last one.
+///
+/// `Lt` has the same shape as `Gt` (operands swapped); `Le`/`Ge` end with one `NOT` gate.
+pub fn comparison_output_wire(
+    gates: &[GateDesc],
+    bit_width: usize,
+    comparison: Comparison,
+) -> Result<u16, String> {
     if gates.is_empty() {
         return Err("layout has no gates".to_string());
     }
-    if bit_width == 1 {
-        return Ok(gates[gates.len() - 1].wire_c);
-    }
-    if gates.len() < 2 {
-        return Err("layout too short for bit_width >= 2".to_string());
-    }
-    Ok(gates[gates.len() - 2].wire_c)
+    let last = gates.len() - 1;
+    let index = match (comparison, bit_width) {
+        (Comparison::Le | Comparison::Ge, _) => last,
+        (Comparison::Gt | Comparison::Lt, 1) => last,
+        (Comparison::Eq, 1) => 1,
+        (Comparison::Gt | Comparison::Lt, _) => {
+            if gates.len() < 2 {
+                return Err("layout too short for bit_width >= 2".to_string());
+            }
+            last - 1
+        }
+        (Comparison::Eq, _) => last,
+    };
+    gates
+        .get(index)
+        .map(|gate| gate.wire_c)
+        .ok_or_else(|| "layout too short for comparison output".to_string())
 }
 
 /// Derives labels for Bob's input wires (`bit_width .. 2*bit_width-1`) for one instance.
@@ -62,8 +249,7 @@ pub fn derive_bob_label_offers(
     (0..bit_width)
         .map(|bit_idx| {
             let wire = (bit_width + bit_idx) as u16;
-            let l0 = derive_wire_label(circuit_id, instance_id, wire, 0, seed);
-            let l1 = derive_wire_label(circuit_id, instance_id, wire, 1, seed);
+            let [l0, l1] = derive_label_pair(circuit_id, instance_id, wire, seed);
             (l0, l1)
         })
         .collect()
@@ -78,27 +264,78 @@ pub fn derive_alice_input_labels(
     x_value: u64,
 ) -> Vec<[u8; 16]> {
     let bits = u64_to_bits_le(x_value, bit_width);
+    derive_input_labels_for_bits(seed, circuit_id, instance_id, 0, &bits)
+}
+
+/// Same as `derive_alice_input_labels` for values up to 256 bits (big-endian `uint256` bytes).
+pub fn derive_alice_input_labels_u256(
+    seed: [u8; 32],
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    bit_width: usize,
+    x_value_be: [u8; 32],
+) -> Vec<[u8; 16]> {
+    let bits = u256_to_bits_le(x_value_be, bit_width);
+    derive_input_labels_for_bits(seed, circuit_id, instance_id, 0, &bits)
+}
+
+/// Derives the active labels for consecutive input wires starting at `first_wire`.
+pub fn derive_input_labels_for_bits(
+    seed: [u8; 32],
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    first_wire: u16,
+    bits: &[u8],
+) -> Vec<[u8; 16]> {
     bits.iter()
         .enumerate()
         .map(|(bit_idx, bit)| {
-            derive_wire_label(circuit_id, instance_id, bit_idx as u16, *bit, seed)
+            let wire = first_wire + bit_idx as u16;
+            derive_wire_label(circuit_id, instance_id, wire, *bit, seed)
         })
         .collect()
 }
 
+/// Derives the active labels for consecutive input wires starting at `first_wire`
+/// under the layout's scheme; equals `derive_input_labels_for_bits` for `Classic` layouts.
+pub fn derive_layout_input_labels(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    first_wire: u16,
+    bits: &[u8],
+) -> Vec<[u8; 16]> {
+    let labels = LayoutLabels::new(seed, layout);
+    bits.iter()
+        .enumerate()
+        .map(|(bit_idx, bit)| labels.label(first_wire + bit_idx as u16, *bit))
+        .collect()
+}
+
 /// Derives output labels (semantic 0 and semantic 1) for one layout instance.
 pub fn derive_output_labels(
     seed: [u8; 32],
     layout: &CircuitLayout,
     output_wire: u16,
 ) -> Result<([u8; 16], [u8; 16]), String> {
-    let l0 = derive_wire_label(layout.circuit_id, layout.instance_id, output_wire, 0, seed);
-    let l1 = derive_wire_label(layout.circuit_id, layout.instance_id, output_wire, 1, seed);
-    Ok((l0, l1))
+    let labels = LayoutLabels::new(seed, layout);
+    Ok((labels.label(output_wire, 0), labels.label(output_wire, 1)))
 }
 
 /// Derives per-NOT-gate hints required for evaluation when NOT rows are canonical zeros.
+/// `GarblingScheme::FreeXor` NOT gates pass the input label through and `NotGateMode::Garbled`
+/// NOT gates carry their own rows, so neither needs hints.
 pub fn derive_not_gate_hints(seed: [u8; 32], layout: &CircuitLayout) -> Vec<NotGateHint> {
+    derive_not_gate_hints_from(&LayoutLabels::new(seed, layout), layout)
+}
+
+/// `derive_not_gate_hints` for a circuit garbled with `garble_circuit_with_labels`.
+pub fn derive_not_gate_hints_from(
+    labels: &dyn WireLabelProvider,
+    layout: &CircuitLayout,
+) -> Vec<NotGateHint> {
+    if layout.scheme == GarblingScheme::FreeXor || layout.not_gate_mode == NotGateMode::Garbled {
+        return Vec::new();
+    }
     layout
         .gates
         .iter()
@@ -108,14 +345,11 @@ pub fn derive_not_gate_hints(seed: [u8; 32], layout: &CircuitLayout) -> Vec<NotG
                 return None;
             }
 
-            let in0 =
-                derive_wire_label(layout.circuit_id, layout.instance_id, gate.wire_a, 0, seed);
-            let in1 =
-                derive_wire_label(layout.circuit_id, layout.instance_id, gate.wire_a, 1, seed);
-            let out_if_in0 =
-                derive_wire_label(layout.circuit_id, layout.instance_id, gate.wire_c, 1, seed);
-            let out_if_in1 =
-                derive_wire_label(layout.circuit_id, layout.instance_id, gate.wire_c, 0, seed);
+            // Layout-aware labels: a NOT may read a constant wire.
+            let in0 = labels.label(gate.wire_a, 0);
+            let in1 = labels.label(gate.wire_a, 1);
+            let out_if_in0 = labels.label(gate.wire_c, 1);
+            let out_if_in1 = labels.label(gate.wire_c, 0);
             Some(NotGateHint {
                 gate_index,
                 in_label0: in0,
@@ -127,39 +361,104 @@ pub fn derive_not_gate_hints(seed: [u8; 32], layout: &CircuitLayout) -> Vec<NotG
         .collect()
 }
 
-fn row_ct_from_leaf(leaf: &[u8; 71], row_index: usize) -> Result<[u8; 16], String> {
-    if row_index > 3 {
+/// Checks a leaf's scheme/version byte and that its header encodes `gate`.
+pub(crate) fn check_leaf_header(
+    scheme_id: SchemeId,
+    gate_idx: usize,
+    gate: GateDesc,
+    leaf: &[u8],
+) -> Result<(), String> {
+    check_leaf_scheme(scheme_id, leaf).map_err(|e| format!("gate={gate_idx}: {e}"))?;
+    let start = match scheme_id.leaf_format() {
+        LeafFormat::V1 => 0,
+        LeafFormat::V2 => 1,
+    };
+    let mut header = [0u8; 7];
+    header[0] = gate.gate_type as u8;
+    header[1..3].copy_from_slice(&gate.wire_a.to_be_bytes());
+    header[3..5].copy_from_slice(&gate.wire_b.to_be_bytes());
+    header[5..7].copy_from_slice(&gate.wire_c.to_be_bytes());
+    if leaf.get(start..start + 7) != Some(&header[..]) {
+        return Err(format!("leaf header does not match layout gate={gate_idx}"));
+    }
+    Ok(())
+}
+
+/// Offset of the first row tag in a v2 leaf carrying `row_count` garbled rows.
+fn row_macs_offset(row_count: usize) -> usize {
+    8 + 16 * (row_count - 1)
+}
+
+/// Reads row `row_index` of a gate carrying `row_count` rows (see `garbled_row_count`).
+fn row_ct_from_leaf(
+    scheme_id: SchemeId,
+    leaf: &[u8],
+    row_count: usize,
+    row_index: usize,
+) -> Result<[u8; 16], String> {
+    if row_index >= row_count {
         return Err(format!("row index out of range: {row_index}"));
     }
-    let start = 7 + 16 * row_index;
+    let leaf_format = scheme_id.leaf_format();
+    let expected_len = match (leaf_format, scheme_id.row_auth()) {
+        (LeafFormat::V1, _) => LEAF_BYTES_LEN,
+        (LeafFormat::V2, RowAuthMode::Unauthenticated) => row_macs_offset(row_count),
+        (LeafFormat::V2, RowAuthMode::Mac) => row_macs_offset(row_count) + ROW_MAC_LEN * row_count,
+    };
+    if leaf.len() != expected_len {
+        return Err(format!(
+            "{leaf_format:?} leaf has {} bytes, expected {expected_len}",
+            leaf.len()
+        ));
+    }
+    let start = match leaf_format {
+        LeafFormat::V1 => 7 + 16 * row_index,
+        // GRR3: row 0 is implicit zero.
+        LeafFormat::V2 if row_index == 0 => return Ok([0u8; 16]),
+        LeafFormat::V2 => 8 + 16 * (row_index - 1),
+    };
     let end = start + 16;
     let mut out = [0u8; 16];
     out.copy_from_slice(&leaf[start..end]);
     Ok(out)
 }
 
+/// Under `RowAuthMode::Mac`, checks the tag of the decrypted row against its row key and
+/// output label. Expects a leaf already length-checked by `row_ct_from_leaf`.
+fn check_row_mac(
+    scheme_id: SchemeId,
+    leaf: &[u8],
+    gate_idx: usize,
+    row_count: usize,
+    row_index: usize,
+    row_key: [u8; 32],
+    out_label: [u8; 16],
+) -> Result<(), String> {
+    if scheme_id.row_auth() == RowAuthMode::Unauthenticated {
+        return Ok(());
+    }
+    let start = row_macs_offset(row_count) + ROW_MAC_LEN * row_index;
+    if leaf[start..start + ROW_MAC_LEN] != row_mac(row_key, out_label) {
+        return Err(format!(
+            "row MAC mismatch gate={gate_idx} row={row_index}: leaf was tampered"
+        ));
+    }
+    Ok(())
+}
+
 /// Evaluates one garbled circuit instance from:
-/// - full leaf list for that instance (`leaves`),
+/// - full leaf list for that instance (`leaves`, encoded per `layout.leaf_format`),
 /// - Alice labels for x wires,
 /// - Bob-selected labels for y wires,
 /// - NOT hints.
 pub fn evaluate_garbled_circuit(
     layout: &CircuitLayout,
-    leaves: &[[u8; 71]],
+    leaves: &[impl AsRef<[u8]>],
     alice_input_labels: &[[u8; 16]],
     bob_input_labels: &[[u8; 16]],
     not_hints: &[NotGateHint],
     output_wire: u16,
 ) -> Result<[u8; 16], String> {
-    let gates = &layout.gates;
-    if leaves.len() != gates.len() {
-        return Err(format!(
-            "leaves count {} does not match gate count {}",
-            leaves.len(),
-            gates.len()
-        ));
-    }
-
     let bit_width = alice_input_labels.len();
     if bob_input_labels.len() != bit_width {
         return Err(format!(
@@ -169,20 +468,80 @@ pub fn evaluate_garbled_circuit(
         ));
     }
 
-    let mut max_wire = (2 * bit_width).saturating_sub(1) as u16;
+    let input_labels: Vec<(u16, [u8; 16])> = alice_input_labels
+        .iter()
+        .chain(bob_input_labels)
+        .enumerate()
+        .map(|(wire, label)| (wire as u16, *label))
+        .collect();
+    let wire_labels = evaluate_garbled_wires(layout, leaves, &input_labels, not_hints)?;
+
+    if output_wire as usize >= wire_labels.len() {
+        return Err(format!(
+            "output wire {} is out of range (max={})",
+            output_wire,
+            wire_labels.len().saturating_sub(1)
+        ));
+    }
+    wire_labels[output_wire as usize]
+        .ok_or_else(|| format!("missing output wire label for wire={output_wire}"))
+}
+
+/// Evaluates one garbled circuit instance for arbitrary input wires and returns the active
+/// label of every wire (`None` for wires never assigned).
+/// `input_labels` pairs an input wire id with the label the evaluator holds for it.
+/// Constant wires are seeded with their public labels (`derive_constant_label`).
+/// Under `GarblingScheme::FreeXor`, `XOR` outputs are `labelA XOR labelB` and `NOT` outputs
+/// reuse the input label; no leaf rows or hints are read for them.
+/// `NotGateMode::Garbled` NOT gates decrypt their single-input row instead of using hints.
+/// Under `RowAuthMode::Mac`, every decrypted row is checked against its tag first.
+/// Every leaf must carry the layout's `SchemeId` (see `consensus::check_leaf_scheme`) and the
+/// header of its gate.
+pub fn evaluate_garbled_wires(
+    layout: &CircuitLayout,
+    leaves: &[impl AsRef<[u8]>],
+    input_labels: &[(u16, [u8; 16])],
+    not_hints: &[NotGateHint],
+) -> Result<Vec<Option<[u8; 16]>>, String> {
+    let gates = &layout.gates;
+    layout.check_constant_wires()?;
+    if leaves.len() != gates.len() {
+        return Err(format!(
+            "leaves count {} does not match gate count {}",
+            leaves.len(),
+            gates.len()
+        ));
+    }
+
+    let mut max_wire = input_labels
+        .iter()
+        .map(|(wire, _)| *wire)
+        .max()
+        .unwrap_or(0);
     for gate in gates {
         max_wire = max_wire.max(gate.wire_a).max(gate.wire_b).max(gate.wire_c);
     }
+    for constant in &layout.constant_wires {
+        max_wire = max_wire.max(constant.wire);
+    }
     let mut wire_labels = vec![None::<[u8; 16]>; max_wire as usize + 1];
 
-    for (idx, label) in alice_input_labels.iter().enumerate() {
-        wire_labels[idx] = Some(*label);
+    for (wire, label) in input_labels {
+        wire_labels[*wire as usize] = Some(*label);
     }
-    for (idx, label) in bob_input_labels.iter().enumerate() {
-        wire_labels[bit_width + idx] = Some(*label);
+    // Constant labels are public, so the evaluator derives them itself.
+    for constant in &layout.constant_wires {
+        wire_labels[constant.wire as usize] = Some(derive_constant_label(
+            layout.circuit_id,
+            layout.instance_id,
+            constant.wire,
+            constant.value,
+        ));
     }
 
+    let scheme_id = layout.scheme_id();
     for (gate_idx, gate) in gates.iter().enumerate() {
+        check_leaf_header(scheme_id, gate_idx, *gate, leaves[gate_idx].as_ref())?;
         let label_a = wire_labels[gate.wire_a as usize].ok_or_else(|| {
             format!(
                 "missing wire label for wireA={} gate={}",
@@ -190,8 +549,36 @@ pub fn evaluate_garbled_circuit(
             )
         })?;
 
+        let free_xor = layout.scheme == GarblingScheme::FreeXor;
         let out_label = match gate.gate_type {
-            GateType::And | GateType::Xor => {
+            GateType::Xor if free_xor => {
+                let label_b = wire_labels[gate.wire_b as usize].ok_or_else(|| {
+                    format!(
+                        "missing wire label for wireB={} gate={}",
+                        gate.wire_b, gate_idx
+                    )
+                })?;
+                xor16(label_a, label_b)
+            }
+            GateType::Not if free_xor => label_a,
+            GateType::Not if layout.not_gate_mode == NotGateMode::Garbled => {
+                let leaf = leaves[gate_idx].as_ref();
+                let perm_a = label_a[0] & 1;
+                let ct = row_ct_from_leaf(scheme_id, leaf, 2, perm_a as usize)?;
+                let row_key = compute_row_key(
+                    layout.circuit_id,
+                    layout.instance_id,
+                    gate_idx as u64,
+                    perm_a,
+                    0,
+                    label_a,
+                    [0u8; 16],
+                );
+                let out = xor16(ct, expand_pad_with(layout.pad_function, row_key));
+                check_row_mac(scheme_id, leaf, gate_idx, 2, perm_a as usize, row_key, out)?;
+                out
+            }
+            GateType::And | GateType::Xor | GateType::Or | GateType::Nand | GateType::Nor => {
                 let label_b = wire_labels[gate.wire_b as usize].ok_or_else(|| {
                     format!(
                         "missing wire label for wireB={} gate={}",
@@ -201,7 +588,8 @@ pub fn evaluate_garbled_circuit(
                 let perm_a = label_a[0] & 1;
                 let perm_b = label_b[0] & 1;
                 let row_index = (2 * perm_a + perm_b) as usize;
-                let ct = row_ct_from_leaf(&leaves[gate_idx], row_index)?;
+                let leaf = leaves[gate_idx].as_ref();
+                let ct = row_ct_from_leaf(scheme_id, leaf, 4, row_index)?;
 
                 let row_key = compute_row_key(
                     layout.circuit_id,
@@ -212,8 +600,10 @@ pub fn evaluate_garbled_circuit(
                     label_a,
                     label_b,
                 );
-                let pad = expand_pad(row_key);
-                xor16(ct, pad)
+                let pad = expand_pad_with(layout.pad_function, row_key);
+                let out = xor16(ct, pad);
+                check_row_mac(scheme_id, leaf, gate_idx, 4, row_index, row_key, out)?;
+                out
             }
             GateType::Not => {
                 let hint = not_hints
@@ -236,13 +626,5 @@ pub fn evaluate_garbled_circuit(
         wire_labels[gate.wire_c as usize] = Some(out_label);
     }
 
-    if output_wire as usize >= wire_labels.len() {
-        return Err(format!(
-            "output wire {} is out of range (max={})",
-            output_wire,
-            wire_labels.len().saturating_sub(1)
-        ));
-    }
-    wire_labels[output_wire as usize]
-        .ok_or_else(|| format!("missing output wire label for wire={output_wire}"))
+    Ok(wire_labels)
 }
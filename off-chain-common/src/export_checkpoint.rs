@@ -0,0 +1,190 @@
+//! Checkpoints of an interrupted export, so garbling a very large circuit resumes where it
+//! stopped instead of redoing hours of keccak work.
+//!
+//! `garble_leaf_file` garbles one instance into a plain text leaf file (one hex leaf per line)
+//! and reports its progress every few gates: the gates written, the incremental-hash state
+//! over their block hashes and the byte length of the file holding them. The export stores
+//! that progress, together with the manifest of the instances it has finished, in
+//! `export-checkpoint.json`. To resume, the leaf file is cut back to the recorded length,
+//! garbling continues at the recorded gate and the hash chain at the recorded state, so the
+//! result equals an uninterrupted run.
+
+use std::fs::{self, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::canonical_json::{from_canonical_json, hex_bytes, to_canonical_json};
+use crate::cli::hex_prefixed;
+use crate::garble::GarbleContext;
+use crate::ih::{gc_block_hash, inc_hash};
+use crate::manifest::ArtifactManifest;
+use crate::types::{CircuitLayout, LeafFormat};
+
+/// Checkpoint file inside the export directory while an export is in progress.
+pub const EXPORT_CHECKPOINT_FILE: &str = "export-checkpoint.json";
+
+/// Written into every checkpoint; bumped on incompatible changes.
+pub const EXPORT_CHECKPOINT_VERSION: u32 = 1;
+
+/// Gates garbled between two checkpoints unless the caller asks otherwise.
+pub const DEFAULT_CHECKPOINT_GATES: u64 = 1 << 16;
+
+/// How far `garble_leaf_file` has got in one instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GarbleProgress {
+    /// Gates garbled and written, i.e. the index of the next gate.
+    pub gate_index: u64,
+    /// Incremental-hash state over the block hashes of those gates; the instance's `rootGC`
+    /// once every gate is done.
+    #[serde(with = "hex_bytes")]
+    pub ih_state: [u8; 32],
+    /// Byte length of the leaf file holding exactly those gates.
+    pub leaves_offset: u64,
+}
+
+impl GarbleProgress {
+    /// Progress before the first gate.
+    pub fn start() -> Self {
+        Self {
+            gate_index: 0,
+            ih_state: [0u8; 32],
+            leaves_offset: 0,
+        }
+    }
+}
+
+/// Contents of `export-checkpoint.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ExportCheckpoint {
+    pub version: u32,
+    /// Instances the export writes in total.
+    pub instance_count: u64,
+    /// Manifest of the instances already written completely.
+    pub manifest: ArtifactManifest,
+    /// Instance being garbled.
+    pub instance_id: u64,
+    /// `comSeed` of that instance, so a resume with another master seed is refused.
+    #[serde(with = "hex_bytes")]
+    pub com_seed: [u8; 32],
+    pub progress: GarbleProgress,
+}
+
+impl ExportCheckpoint {
+    pub fn to_json(&self) -> Result<String, String> {
+        to_canonical_json(self)
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let checkpoint: Self = from_canonical_json(raw)?;
+        if checkpoint.version != EXPORT_CHECKPOINT_VERSION {
+            return Err(format!(
+                "unsupported export checkpoint version {}, expected {EXPORT_CHECKPOINT_VERSION}",
+                checkpoint.version
+            ));
+        }
+        Ok(checkpoint)
+    }
+
+    /// Writes `dir/export-checkpoint.json` through a temporary file, so an interruption
+    /// leaves either the previous checkpoint or this one.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf, String> {
+        let path = dir.join(EXPORT_CHECKPOINT_FILE);
+        let staged = dir.join(format!("{EXPORT_CHECKPOINT_FILE}.tmp"));
+        fs::write(&staged, self.to_json()?)
+            .map_err(|e| format!("failed to write {}: {e}", staged.display()))?;
+        fs::rename(&staged, &path)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+
+    /// The checkpoint in `dir`, if an export into it was interrupted.
+    pub fn read(dir: &Path) -> Result<Option<Self>, String> {
+        let path = dir.join(EXPORT_CHECKPOINT_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let raw = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        Self::from_json(&raw)
+            .map(Some)
+            .map_err(|e| format!("{}: {e}", path.display()))
+    }
+
+    /// Removes the checkpoint once the export is complete.
+    pub fn remove(dir: &Path) -> Result<(), String> {
+        let path = dir.join(EXPORT_CHECKPOINT_FILE);
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("failed to remove {}: {e}", path.display())),
+        }
+    }
+}
+
+/// Garbles `layout` into the text leaf file `path`, starting over, or from `resume` after
+/// cutting the file back to its `leaves_offset`. Every `checkpoint_gates` gates (never for 0)
+/// the file is synced to disk and `checkpoint` receives the progress. Returns the final
+/// progress, whose `ih_state` is the `rootGC` of the leaves. Errors for `LeafFormat::V2`
+/// layouts.
+pub fn garble_leaf_file(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    path: &Path,
+    resume: Option<GarbleProgress>,
+    checkpoint_gates: u64,
+    mut checkpoint: impl FnMut(GarbleProgress) -> Result<(), String>,
+) -> Result<GarbleProgress, String> {
+    if layout.leaf_format != LeafFormat::V1 {
+        return Err(format!(
+            "garble_leaf_file writes v1 leaves but layout uses scheme {}",
+            layout.scheme_id()
+        ));
+    }
+    let gate_count = layout.gates.len() as u64;
+    let mut progress = resume.unwrap_or_else(GarbleProgress::start);
+    if progress.gate_index > gate_count {
+        return Err(format!(
+            "checkpoint is at gate {}, but the circuit has {gate_count} gates",
+            progress.gate_index
+        ));
+    }
+
+    let io_err = |e: std::io::Error| format!("{}: {e}", path.display());
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(io_err)?;
+    let len = file.metadata().map_err(io_err)?.len();
+    if len < progress.leaves_offset {
+        return Err(format!(
+            "{} has {len} bytes, but the checkpoint expects at least {}",
+            path.display(),
+            progress.leaves_offset
+        ));
+    }
+    file.set_len(progress.leaves_offset).map_err(io_err)?;
+
+    let ctx = GarbleContext::new(seed, layout);
+    let mut out = BufWriter::new(file);
+    while progress.gate_index < gate_count {
+        let leaf = ctx.gate_leaf(progress.gate_index as usize);
+        let line = format!("{}\n", hex_prefixed(&leaf));
+        out.write_all(line.as_bytes()).map_err(io_err)?;
+        progress.ih_state = inc_hash(progress.ih_state, gc_block_hash(progress.gate_index, &leaf));
+        progress.leaves_offset += line.len() as u64;
+        progress.gate_index += 1;
+        let due = progress.gate_index.is_multiple_of(checkpoint_gates);
+        if due && progress.gate_index < gate_count {
+            out.flush().map_err(io_err)?;
+            out.get_ref().sync_data().map_err(io_err)?;
+            checkpoint(progress)?;
+        }
+    }
+    out.flush().map_err(io_err)?;
+    Ok(progress)
+}
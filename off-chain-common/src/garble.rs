@@ -1,13 +1,547 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::canonical_json::{hex_bytes, hex_bytes_vec};
 use crate::consensus::{
-    compute_row_key, derive_wire_flip_bit, derive_wire_label, encode_leaf, expand_pad, truth_table,
-    xor16,
+    ConsensusHasher, ROW_MAC_LEN, compute_row_key, derive_constant_label, derive_free_xor_delta,
+    derive_wire_flip_bit, derive_wire_label, encode_leaf, encode_leaf_v2, expand_pad_with,
+    keccak256, row_mac, truth_table, uint256_from_u64, xor16,
+};
+use crate::ih::{gc_block_hash, inc_hash, incremental_root_from_hashes};
+use crate::labels::check_point_and_permute;
+use crate::scenario::{com_seed, derive_instance_seed};
+use crate::types::{
+    CircuitLayout, ConstantWire, GarblingScheme, GateDesc, GateType, LeafFormat, RowAuthMode,
+    SchemeId,
 };
-use crate::types::{CircuitLayout, GateDesc, GateType};
+
+fn constant_value(constants: &[ConstantWire], wire: u16) -> Option<u8> {
+    constants
+        .iter()
+        .find(|constant| constant.wire == wire)
+        .map(|constant| constant.value & 1)
+}
+
+/// Flip bit of `wire`; constant wires use `0`.
+fn wire_flip_bit(
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    wire: u16,
+    seed: [u8; 32],
+    constants: &[ConstantWire],
+) -> u8 {
+    match constant_value(constants, wire) {
+        Some(_) => 0,
+        None => derive_wire_flip_bit(circuit_id, instance_id, wire, seed),
+    }
+}
+
+/// Label of `(wire, semantic_bit)`. A constant wire's active label is the public
+/// `derive_constant_label`; its inactive label never reaches the evaluator and only keeps
+/// the table well-formed (permutation bit equals the semantic bit).
+fn wire_label(
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    wire: u16,
+    semantic_bit: u8,
+    seed: [u8; 32],
+    constants: &[ConstantWire],
+) -> [u8; 16] {
+    match constant_value(constants, wire) {
+        Some(value) if value == semantic_bit & 1 => {
+            derive_constant_label(circuit_id, instance_id, wire, value)
+        }
+        Some(_) => {
+            let mut label = derive_wire_label(circuit_id, instance_id, wire, semantic_bit, seed);
+            label[0] = (label[0] & 0xFE) | (semantic_bit & 1);
+            label
+        }
+        None => derive_wire_label(circuit_id, instance_id, wire, semantic_bit, seed),
+    }
+}
+
+/// Precomputed label pairs `[L0, L1]` indexed by wire id, plus the Free-XOR `delta`.
+#[derive(Debug, Clone)]
+struct LabelTable {
+    pairs: Vec<[[u8; 16]; 2]>,
+    delta: Option<[u8; 16]>,
+}
+
+impl LabelTable {
+    /// Walks the gates in index order:
+    /// - wires no gate writes (inputs) use `derive_wire_label`; Free-XOR keeps only
+    ///   `L0 = derive_wire_label(wire, 0)` and sets `L1 = L0 XOR delta`,
+    /// - constants keep their public active label,
+    /// - Free-XOR `XOR` outputs use `L0(a) XOR L0(b)` and `NOT` outputs swap `L0(a)`/`L1(a)`,
+    /// - `LeafFormat::V2` garbled gates take the label selected by permutation row `0` from
+    ///   that row's pad; the other label is `pad XOR delta` (Free-XOR) or freshly derived,
+    /// - every other gate output keeps its derived labels.
+    fn new(seed: [u8; 32], layout: &CircuitLayout) -> Self {
+        let (circuit_id, instance_id) = (layout.circuit_id, layout.instance_id);
+        let delta = (layout.scheme == GarblingScheme::FreeXor)
+            .then(|| derive_free_xor_delta(circuit_id, instance_id, seed));
+        let derived = |wire: u16| match delta {
+            Some(delta) => {
+                let zero = derive_wire_label(circuit_id, instance_id, wire, 0, seed);
+                [zero, xor16(zero, delta)]
+            }
+            None => [0, 1].map(|bit| {
+                wire_label(
+                    circuit_id,
+                    instance_id,
+                    wire,
+                    bit,
+                    seed,
+                    &layout.constant_wires,
+                )
+            }),
+        };
+        let max_wire = layout
+            .gates
+            .iter()
+            .flat_map(|gate| [gate.wire_a, gate.wire_b, gate.wire_c])
+            .chain(layout.constant_wires.iter().map(|constant| constant.wire))
+            .max()
+            .unwrap_or(0);
+
+        let mut pairs = (0..=max_wire).map(derived).collect::<Vec<_>>();
+        if let Some(delta) = delta {
+            for constant in &layout.constant_wires {
+                let value = constant.value & 1;
+                let active = derive_constant_label(circuit_id, instance_id, constant.wire, value);
+                let mut pair = [active, xor16(active, delta)];
+                if value == 1 {
+                    pair.reverse();
+                }
+                pairs[constant.wire as usize] = pair;
+            }
+        }
+
+        for (gate_index, gate) in layout.gates.iter().enumerate() {
+            let (a, b, c) = (
+                gate.wire_a as usize,
+                gate.wire_b as usize,
+                gate.wire_c as usize,
+            );
+            pairs[c] = match (gate.gate_type, delta) {
+                (GateType::Xor, Some(delta)) => {
+                    let zero = xor16(pairs[a][0], pairs[b][0]);
+                    [zero, xor16(zero, delta)]
+                }
+                (GateType::Not, Some(_)) => [pairs[a][1], pairs[a][0]],
+                (gate_type, _)
+                    if layout.leaf_format == LeafFormat::V2
+                        && layout.garbled_row_count(gate_type) > 0 =>
+                {
+                    // Permutation row 0 reads the labels whose permutation bit is 0.
+                    let bit_a = pairs[a][0][0] & 1;
+                    let (out_bit, label_b) = if gate_type.is_unary() {
+                        (bit_a ^ 1, [0u8; 16])
+                    } else {
+                        let bit_b = pairs[b][0][0] & 1;
+                        (
+                            truth_table(gate_type, bit_a, bit_b),
+                            pairs[b][bit_b as usize],
+                        )
+                    };
+                    let row_key = compute_row_key(
+                        circuit_id,
+                        instance_id,
+                        gate_index as u64,
+                        0,
+                        0,
+                        pairs[a][bit_a as usize],
+                        label_b,
+                    );
+                    let selected = expand_pad_with(layout.pad_function, row_key);
+                    let other = match delta {
+                        Some(delta) => xor16(selected, delta),
+                        None => {
+                            let mut label = derive_wire_label(
+                                circuit_id,
+                                instance_id,
+                                gate.wire_c,
+                                out_bit ^ 1,
+                                seed,
+                            );
+                            label[0] = (label[0] & 0xFE) | ((selected[0] & 1) ^ 1);
+                            label
+                        }
+                    };
+                    let mut pair = [selected, other];
+                    if out_bit == 1 {
+                        pair.reverse();
+                    }
+                    pair
+                }
+                _ => derived(gate.wire_c),
+            };
+        }
+        Self { pairs, delta }
+    }
+}
+
+/// Label table of one garbled instance under its `GarblingScheme` and `LeafFormat`.
+/// Classic v1 labels are derived on demand; Free-XOR and `LeafFormat::V2` precompute the
+/// labels of every wire once, so reuse one table when deriving many labels of an instance.
+#[derive(Debug, Clone)]
+pub struct LayoutLabels<'a> {
+    seed: [u8; 32],
+    layout: &'a CircuitLayout,
+    table: Option<LabelTable>,
+}
+
+impl<'a> LayoutLabels<'a> {
+    pub fn new(seed: [u8; 32], layout: &'a CircuitLayout) -> Self {
+        let table = (layout.scheme == GarblingScheme::FreeXor
+            || layout.leaf_format == LeafFormat::V2)
+            .then(|| LabelTable::new(seed, layout));
+        Self {
+            seed,
+            layout,
+            table,
+        }
+    }
+
+    /// Label of `(wire, semantic_bit)`.
+    pub fn label(&self, wire: u16, semantic_bit: u8) -> [u8; 16] {
+        let layout = self.layout;
+        let bit = semantic_bit & 1;
+        if let Some(table) = &self.table {
+            if let Some(pair) = table.pairs.get(wire as usize) {
+                return pair[bit as usize];
+            }
+            if let Some(delta) = table.delta {
+                let zero =
+                    derive_wire_label(layout.circuit_id, layout.instance_id, wire, 0, self.seed);
+                return if bit == 1 { xor16(zero, delta) } else { zero };
+            }
+        }
+        wire_label(
+            layout.circuit_id,
+            layout.instance_id,
+            wire,
+            bit,
+            self.seed,
+            &layout.constant_wires,
+        )
+    }
+
+    /// Permutation bit of the semantic-0 label of `wire`.
+    pub fn flip_bit(&self, wire: u16) -> u8 {
+        match &self.table {
+            Some(_) => self.label(wire, 0)[0] & 1,
+            None => wire_flip_bit(
+                self.layout.circuit_id,
+                self.layout.instance_id,
+                wire,
+                self.seed,
+                &self.layout.constant_wires,
+            ),
+        }
+    }
+
+    /// Free-XOR `delta`, or `None` for `GarblingScheme::Classic`.
+    pub fn delta(&self) -> Option<[u8; 16]> {
+        self.table.as_ref().and_then(|table| table.delta)
+    }
+}
+
+/// Source of the wire labels a garbler encrypts under, e.g. input labels precommitted through
+/// a real OT instead of derived from the instance seed. `LayoutLabels` is the seed-derived
+/// default; a provider must keep each pair's permutation bits distinct.
+pub trait WireLabelProvider: fmt::Debug {
+    /// Label of `(wire, semantic_bit)`.
+    fn label(&self, wire: u16, semantic_bit: u8) -> [u8; 16];
+
+    /// Permutation bit of the semantic-0 label of `wire`.
+    fn flip_bit(&self, wire: u16) -> u8 {
+        self.label(wire, 0)[0] & 1
+    }
+}
+
+impl WireLabelProvider for LayoutLabels<'_> {
+    fn label(&self, wire: u16, semantic_bit: u8) -> [u8; 16] {
+        LayoutLabels::label(self, wire, semantic_bit)
+    }
+
+    fn flip_bit(&self, wire: u16) -> u8 {
+        LayoutLabels::flip_bit(self, wire)
+    }
+}
+
+/// Seed-derived labels with externally supplied pairs `[L0, L1]` on some input wires.
+/// Only `Classic` v1 layouts are supported: there every gate output label is derived on its
+/// own, so replacing input labels does not change any other wire.
+#[derive(Debug, Clone)]
+pub struct InputLabelOverrides<'a> {
+    seed_labels: LayoutLabels<'a>,
+    pairs: HashMap<u16, [[u8; 16]; 2]>,
+}
+
+impl<'a> InputLabelOverrides<'a> {
+    pub fn new(seed: [u8; 32], layout: &'a CircuitLayout) -> Result<Self, String> {
+        if layout.scheme != GarblingScheme::Classic || layout.leaf_format != LeafFormat::V1 {
+            return Err(format!(
+                "input label overrides need a classic v1 layout, got scheme {}",
+                layout.scheme_id()
+            ));
+        }
+        Ok(Self {
+            seed_labels: LayoutLabels::new(seed, layout),
+            pairs: HashMap::new(),
+        })
+    }
+
+    /// Replaces the label pair of input `wire`. Rejects wires written by a gate, constant
+    /// wires and pairs whose permutation bits are equal.
+    pub fn insert(&mut self, wire: u16, pair: [[u8; 16]; 2]) -> Result<(), String> {
+        let layout = self.seed_labels.layout;
+        if layout.gates.iter().any(|gate| gate.wire_c == wire) {
+            return Err(format!("wire {wire} is a gate output, not an input wire"));
+        }
+        if constant_value(&layout.constant_wires, wire).is_some() {
+            return Err(format!("wire {wire} is a constant wire"));
+        }
+        check_point_and_permute(pair).map_err(|e| format!("label pair of wire {wire}: {e}"))?;
+        self.pairs.insert(wire, pair);
+        Ok(())
+    }
+}
+
+impl WireLabelProvider for InputLabelOverrides<'_> {
+    fn label(&self, wire: u16, semantic_bit: u8) -> [u8; 16] {
+        match self.pairs.get(&wire) {
+            Some(pair) => pair[(semantic_bit & 1) as usize],
+            None => self.seed_labels.label(wire, semantic_bit),
+        }
+    }
+
+    fn flip_bit(&self, wire: u16) -> u8 {
+        match self.pairs.get(&wire) {
+            Some(pair) => pair[0][0] & 1,
+            None => self.seed_labels.flip_bit(wire),
+        }
+    }
+}
+
+/// Where a `GarbleContext` takes labels, row keys and pads from.
+#[derive(Debug)]
+enum LabelSource<'a> {
+    Seed(LayoutLabels<'a>),
+    Provider(&'a dyn WireLabelProvider),
+    /// Non-consensus backend replacing every keccak derivation, see `with_hasher`.
+    Hasher {
+        seed: [u8; 32],
+        hasher: &'a dyn ConsensusHasher,
+    },
+}
+
+/// Memoizing garbler for one instance: every `(wire, semantic)` label and flip bit is derived
+/// once and cached, so gates sharing a wire do not re-hash its labels for every row.
+/// Leaves are identical to the uncached derivation.
+#[derive(Debug)]
+pub struct GarbleContext<'a> {
+    layout: &'a CircuitLayout,
+    labels: LabelSource<'a>,
+    /// Running `garbling_transcript` state, when recording.
+    transcript: Option<RefCell<[u8; 32]>>,
+    label_cache: RefCell<HashMap<(u16, u8), [u8; 16]>>,
+    flip_cache: RefCell<HashMap<u16, u8>>,
+}
+
+impl<'a> GarbleContext<'a> {
+    pub fn new(seed: [u8; 32], layout: &'a CircuitLayout) -> Self {
+        Self::with_caches(seed, layout, ContextCaches::default())
+    }
+
+    /// Context reusing the (cleared) cache allocations of an earlier instance.
+    fn with_caches(seed: [u8; 32], layout: &'a CircuitLayout, mut caches: ContextCaches) -> Self {
+        caches.labels.clear();
+        caches.flips.clear();
+        Self {
+            layout,
+            labels: LabelSource::Seed(LayoutLabels::new(seed, layout)),
+            transcript: None,
+            label_cache: RefCell::new(caches.labels),
+            flip_cache: RefCell::new(caches.flips),
+        }
+    }
+
+    /// Context deriving labels, flip bits, row keys and pads with `hasher` instead of
+    /// keccak, for experimental scheme variants. Only plain `Classic` v1 layouts with the
+    /// hash pad and no constant wires are supported.
+    pub fn with_hasher(
+        seed: [u8; 32],
+        layout: &'a CircuitLayout,
+        hasher: &'a dyn ConsensusHasher,
+    ) -> Result<Self, String> {
+        let scheme_id = layout.scheme_id();
+        if scheme_id != SchemeId::CLASSIC_V1 || !layout.constant_wires.is_empty() {
+            return Err(format!(
+                "{} backend supports classic v1 layouts without constants, got scheme {scheme_id} with {} constant wires",
+                hasher.name(),
+                layout.constant_wires.len()
+            ));
+        }
+        let mut ctx = Self::new(seed, layout);
+        ctx.labels = LabelSource::Hasher { seed, hasher };
+        Ok(ctx)
+    }
+
+    /// Context garbling under the labels of `provider` instead of seed-derived ones.
+    pub fn with_labels(layout: &'a CircuitLayout, provider: &'a dyn WireLabelProvider) -> Self {
+        let mut ctx = Self::new([0u8; 32], layout);
+        ctx.labels = LabelSource::Provider(provider);
+        ctx
+    }
+
+    fn into_caches(self) -> ContextCaches {
+        ContextCaches {
+            labels: self.label_cache.into_inner(),
+            flips: self.flip_cache.into_inner(),
+        }
+    }
+
+    pub fn layout(&self) -> &'a CircuitLayout {
+        self.layout
+    }
+
+    /// Label of `(wire, semantic_bit)`, derived on first use.
+    pub fn label(&self, wire: u16, semantic_bit: u8) -> [u8; 16] {
+        let key = (wire, semantic_bit & 1);
+        let layout = self.layout;
+        *self
+            .label_cache
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| match &self.labels {
+                LabelSource::Seed(labels) => labels.label(key.0, key.1),
+                LabelSource::Provider(provider) => provider.label(key.0, key.1),
+                LabelSource::Hasher { seed, hasher } => {
+                    hasher.wire_label(layout.circuit_id, layout.instance_id, key.0, key.1, *seed)
+                }
+            })
+    }
+
+    /// Permutation bit of the semantic-0 label of `wire`, derived on first use.
+    pub fn flip_bit(&self, wire: u16) -> u8 {
+        let layout = self.layout;
+        *self
+            .flip_cache
+            .borrow_mut()
+            .entry(wire)
+            .or_insert_with(|| match &self.labels {
+                LabelSource::Seed(labels) => labels.flip_bit(wire),
+                LabelSource::Provider(provider) => provider.flip_bit(wire),
+                LabelSource::Hasher { seed, hasher } => {
+                    hasher.wire_flip_bit(layout.circuit_id, layout.instance_id, wire, *seed)
+                }
+            })
+    }
+
+    /// Row key of `(gate_index, permA, permB)` under the context's hash backend.
+    fn row_key(&self, gate_index: u64, perms: [u8; 2], labels: [[u8; 16]; 2]) -> [u8; 32] {
+        let layout = self.layout;
+        match &self.labels {
+            LabelSource::Hasher { hasher, .. } => hasher.row_key(
+                layout.circuit_id,
+                layout.instance_id,
+                gate_index,
+                perms,
+                labels,
+            ),
+            _ => compute_row_key(
+                layout.circuit_id,
+                layout.instance_id,
+                gate_index,
+                perms[0],
+                perms[1],
+                labels[0],
+                labels[1],
+            ),
+        }
+    }
+
+    /// Pad of `row_key` under the layout's `PadFunction` or the context's hash backend.
+    fn pad(&self, row_key: [u8; 32]) -> [u8; 16] {
+        match &self.labels {
+            LabelSource::Hasher { hasher, .. } => hasher.pad(row_key),
+            _ => expand_pad_with(self.layout.pad_function, row_key),
+        }
+    }
+
+    /// Chains `parts` into the transcript state; no-op unless recording.
+    fn record(&self, domain: &[u8], parts: &[&[u8]]) {
+        if let Some(transcript) = &self.transcript {
+            let mut state = transcript.borrow_mut();
+            let mut input = vec![domain, &state[..]];
+            input.extend_from_slice(parts);
+            *state = keccak256(&input);
+        }
+    }
+
+    fn record_row(
+        &self,
+        perms: [u8; 2],
+        flips: [u8; 2],
+        [label_a, label_b]: [[u8; 16]; 2],
+        out_label: [u8; 16],
+        row_key: [u8; 32],
+        pad: [u8; 16],
+    ) {
+        self.record(
+            b"ROW",
+            &[
+                &perms, &flips, &label_a, &label_b, &out_label, &row_key, &pad,
+            ],
+        );
+    }
+
+    /// Number of distinct `(wire, semantic)` labels derived so far.
+    pub fn cached_label_count(&self) -> usize {
+        self.label_cache.borrow().len()
+    }
+
+    /// 71-byte v1 leaf of gate `gate_index` of the layout.
+    pub fn gate_leaf(&self, gate_index: usize) -> [u8; 71] {
+        let gate = self.layout().gates[gate_index];
+        let rows = garble_rows(self, gate_index as u64, gate).map(|garbled| garbled.rows);
+        encode_leaf(gate, rows.unwrap_or_default())
+    }
+}
+
+/// Cache allocations handed from one `GarbleContext` to the next by `garble_all_instances`.
+#[derive(Debug, Default)]
+struct ContextCaches {
+    labels: HashMap<(u16, u8), [u8; 16]>,
+    flips: HashMap<u16, u8>,
+}
+
+/// Label of `(wire, semantic_bit)` within `layout`, honoring its constant wires and scheme.
+/// Equals `derive_wire_label` for every non-constant wire of a `Classic` v1 layout.
+pub fn derive_layout_wire_label(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    wire: u16,
+    semantic_bit: u8,
+) -> [u8; 16] {
+    LayoutLabels::new(seed, layout).label(wire, semantic_bit)
+}
 
 /// Recomputes one 71-byte gate leaf from `(seed, instance, gateIndex, gateDesc)`.
 /// This mirrors Solidity `recomputeGateLeafBytes`, including:
 /// - row ordering `rowIndex = 2*permA + permB`
 /// - canonical NOT gate rows of zero.
+///
+/// `GateSet::V2` two-input gates (`OR`, `NAND`, `NOR`) use the same 4-row construction.
+/// Only valid for `GarblingScheme::Classic` v1 leaves; other layouts depend on the whole
+/// gate list.
 pub fn recompute_gate_leaf(
     seed: [u8; 32],
     circuit_id: [u8; 32],
@@ -15,68 +549,407 @@ pub fn recompute_gate_leaf(
     gate_index: u64,
     gate: GateDesc,
 ) -> [u8; 71] {
+    let layout = CircuitLayout::new(circuit_id, instance_id, Vec::new());
+    let rows = garble_rows(&GarbleContext::new(seed, &layout), gate_index, gate);
+    encode_leaf(gate, rows.map(|garbled| garbled.rows).unwrap_or_default())
+}
+
+/// Ciphertext rows of one gate in Solidity row order, with one tag per row when the
+/// layout's scheme authenticates rows (`RowAuthMode::Mac`).
+struct GarbledRows {
+    rows: [[u8; 16]; 4],
+    macs: Option<[[u8; ROW_MAC_LEN]; 4]>,
+}
+
+/// Ciphertext rows of one gate in Solidity row order, or `None` for gates without rows
+/// (hinted NOT, Free-XOR XOR/NOT). Garbled NOT gates fill rows `0` and `1` only.
+fn garble_rows(ctx: &GarbleContext, gate_index: u64, gate: GateDesc) -> Option<GarbledRows> {
+    let layout = ctx.layout();
+    if layout.garbled_row_count(gate.gate_type) == 0 {
+        return None;
+    }
     // Four ciphertext rows, each 16 bytes.
     let mut rows = [[0u8; 16]; 4];
+    let mut macs =
+        (layout.scheme_id().row_auth() == RowAuthMode::Mac).then_some([[0u8; ROW_MAC_LEN]; 4]);
 
-    if gate.gate_type != GateType::Not {
-        // Flip bits define mapping between permutation bits and semantic bits.
-        let flip_a = derive_wire_flip_bit(circuit_id, instance_id, gate.wire_a, seed);
-        let flip_b = derive_wire_flip_bit(circuit_id, instance_id, gate.wire_b, seed);
-
-        // Enumerate permutation rows in 2x2 space.
+    // Flip bits define mapping between permutation bits and semantic bits.
+    let flip_a = ctx.flip_bit(gate.wire_a);
+    if gate.gate_type.is_unary() {
+        // Single-input table: row `permA` maps input label to the negated output label.
         for perm_a in 0..=1 {
-            for perm_b in 0..=1 {
-                // Inverse mapping: semantic = permutation XOR flip.
-                let bit_a = perm_a ^ flip_a;
-                let bit_b = perm_b ^ flip_b;
-                let out_bit = truth_table(gate.gate_type, bit_a, bit_b);
-
-                // Deterministic input/output labels for this truth-table point.
-                let label_a = derive_wire_label(circuit_id, instance_id, gate.wire_a, bit_a, seed);
-                let label_b = derive_wire_label(circuit_id, instance_id, gate.wire_b, bit_b, seed);
-                let out_label =
-                    derive_wire_label(circuit_id, instance_id, gate.wire_c, out_bit, seed);
-
-                // Row encryption: ct = outLabel XOR pad(rowKey(...)).
-                let row_key = compute_row_key(
-                    circuit_id,
-                    instance_id,
-                    gate_index,
-                    perm_a,
-                    perm_b,
-                    label_a,
-                    label_b,
-                );
-                let pad = expand_pad(row_key);
-                let ct = xor16(out_label, pad);
-
-                // Solidity row order contract.
-                let row_index = (2 * perm_a + perm_b) as usize;
-                rows[row_index] = ct;
+            let bit_a = perm_a ^ flip_a;
+            let label_a = ctx.label(gate.wire_a, bit_a);
+            let out_label = ctx.label(gate.wire_c, bit_a ^ 1);
+            let row_key = ctx.row_key(gate_index, [perm_a, 0], [label_a, [0u8; 16]]);
+            let pad = ctx.pad(row_key);
+            ctx.record_row(
+                [perm_a, 0],
+                [flip_a, 0],
+                [label_a, [0u8; 16]],
+                out_label,
+                row_key,
+                pad,
+            );
+            rows[perm_a as usize] = xor16(out_label, pad);
+            if let Some(macs) = &mut macs {
+                macs[perm_a as usize] = row_mac(row_key, out_label);
+            }
+        }
+        return Some(GarbledRows { rows, macs });
+    }
+    let flip_b = ctx.flip_bit(gate.wire_b);
+
+    // Enumerate permutation rows in 2x2 space.
+    for perm_a in 0..=1 {
+        for perm_b in 0..=1 {
+            // Inverse mapping: semantic = permutation XOR flip.
+            let bit_a = perm_a ^ flip_a;
+            let bit_b = perm_b ^ flip_b;
+            let out_bit = truth_table(gate.gate_type, bit_a, bit_b);
+
+            // Deterministic input/output labels for this truth-table point.
+            let label_a = ctx.label(gate.wire_a, bit_a);
+            let label_b = ctx.label(gate.wire_b, bit_b);
+            let out_label = ctx.label(gate.wire_c, out_bit);
+
+            // Row encryption: ct = outLabel XOR pad(rowKey(...)).
+            let row_key = ctx.row_key(gate_index, [perm_a, perm_b], [label_a, label_b]);
+            let pad = ctx.pad(row_key);
+            ctx.record_row(
+                [perm_a, perm_b],
+                [flip_a, flip_b],
+                [label_a, label_b],
+                out_label,
+                row_key,
+                pad,
+            );
+            let ct = xor16(out_label, pad);
+
+            // Solidity row order contract.
+            let row_index = (2 * perm_a + perm_b) as usize;
+            rows[row_index] = ct;
+            if let Some(macs) = &mut macs {
+                macs[row_index] = row_mac(row_key, out_label);
             }
         }
-    } else {
-        // Canonical NOT: rows stay all-zero; only gate header is meaningful.
     }
+    Some(GarbledRows { rows, macs })
+}
+
+/// Chained hash over every intermediate value of one garbling run, so two implementations
+/// can be compared with one value instead of leaf by leaf. With `t` the running state:
+/// - `t = keccak256("GC-TRANSCRIPT", circuitId, uint256(instanceId), schemeId)`,
+/// - per gate: `t = keccak256("GATE", t, uint256(gateIndex), gateType, wireA, wireB, wireC)`,
+/// - per garbled row in Solidity row order: `t = keccak256("ROW", t, permA, permB, flipA,
+///   flipB, labelA, labelB, outLabel, rowKey, pad)`; unary gates use `permB = flipB = 0` and
+///   an all-zero `labelB`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GarblingTranscript {
+    /// Final state after the last gate.
+    pub root: [u8; 32],
+    /// State after each gate, to locate the first diverging gate.
+    pub gate_states: Vec<[u8; 32]>,
+}
+
+impl GarblingTranscript {
+    /// First gate index where the two transcripts disagree, or `None` if they are equal.
+    pub fn first_divergence(&self, other: &Self) -> Option<usize> {
+        if self == other {
+            return None;
+        }
+        let common = self
+            .gate_states
+            .iter()
+            .zip(&other.gate_states)
+            .position(|(a, b)| a != b);
+        Some(common.unwrap_or(self.gate_states.len().min(other.gate_states.len())))
+    }
+}
 
-    encode_leaf(gate, rows)
+/// Records the `GarblingTranscript` of garbling `layout` under `seed` (any leaf format).
+pub fn garbling_transcript(seed: [u8; 32], layout: &CircuitLayout) -> GarblingTranscript {
+    let mut ctx = GarbleContext::new(seed, layout);
+    let instance = uint256_from_u64(layout.instance_id);
+    let scheme = [layout.scheme_id().to_byte()];
+    ctx.transcript = Some(RefCell::new(keccak256(&[
+        b"GC-TRANSCRIPT",
+        &layout.circuit_id,
+        &instance,
+        &scheme,
+    ])));
+
+    let gate_states = layout
+        .gates
+        .iter()
+        .enumerate()
+        .map(|(idx, gate)| {
+            let header = encode_leaf(*gate, [[0u8; 16]; 4]);
+            ctx.record(b"GATE", &[&uint256_from_u64(idx as u64), &header[..7]]);
+            garble_rows(&ctx, idx as u64, *gate);
+            *ctx.transcript.as_ref().expect("recording").borrow()
+        })
+        .collect::<Vec<_>>();
+    let root = gate_states
+        .last()
+        .copied()
+        .unwrap_or_else(|| *ctx.transcript.as_ref().expect("recording").borrow());
+    GarblingTranscript { root, gate_states }
 }
 
-/// Garbles a full circuit in gate-index order and returns all gate leaves.
+/// Garbles a full circuit in gate-index order and returns all 71-byte gate leaves.
+/// Gates reading a constant wire encrypt under its public constant label.
+/// Under `GarblingScheme::FreeXor`, `XOR` and `NOT` leaves keep all-zero rows.
+/// Panics for `LeafFormat::V2` layouts; use `garble_leaves` for those.
 pub fn garble_circuit(seed: [u8; 32], layout: &CircuitLayout) -> Vec<[u8; 71]> {
+    garble_circuit_iter(seed, layout).collect()
+}
+
+/// `garble_circuit` with every keccak derivation replaced by `hasher`; see
+/// `GarbleContext::with_hasher` for the supported layouts. With `Keccak` the leaves equal
+/// `garble_circuit`.
+pub fn garble_circuit_with_hasher(
+    hasher: &dyn ConsensusHasher,
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+) -> Result<Vec<[u8; 71]>, String> {
+    let ctx = GarbleContext::with_hasher(seed, layout, hasher)?;
+    Ok((0..layout.gates.len())
+        .map(|idx| ctx.gate_leaf(idx))
+        .collect())
+}
+
+/// `garble_circuit` under the labels of `provider`, e.g. an `InputLabelOverrides` carrying
+/// OT-committed input labels; `garble_circuit` is this with the seed's `LayoutLabels`.
+/// Panics for `LeafFormat::V2` layouts.
+pub fn garble_circuit_with_labels(
+    layout: &CircuitLayout,
+    provider: &dyn WireLabelProvider,
+) -> Vec<[u8; 71]> {
+    assert_eq!(
+        layout.leaf_format,
+        LeafFormat::V1,
+        "garble_circuit_with_labels emits v1 leaves but layout uses scheme {}",
+        layout.scheme_id()
+    );
+    let ctx = GarbleContext::with_labels(layout, provider);
+    (0..layout.gates.len())
+        .map(|idx| ctx.gate_leaf(idx))
+        .collect()
+}
+
+/// Lazily garbles a circuit, yielding the same 71-byte leaves as `garble_circuit` one gate
+/// at a time so callers can hash or write them without holding the whole circuit; only the
+/// per-wire label cache of `GarbleContext` grows. Panics for `LeafFormat::V2` layouts.
+pub fn garble_circuit_iter(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+) -> impl Iterator<Item = [u8; 71]> + '_ {
+    assert_eq!(
+        layout.leaf_format,
+        LeafFormat::V1,
+        "garble_circuit emits v1 leaves but layout uses scheme {}; use garble_leaves",
+        layout.scheme_id()
+    );
+    let ctx = GarbleContext::new(seed, layout);
     // Index in iteration is part of consensus (`gateIndex` in hashing rules).
+    (0..layout.gates.len()).map(move |idx| ctx.gate_leaf(idx))
+}
+
+/// Leaves and block hashes of a contiguous gate range, as returned by `regarble_range`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegarbledRange {
+    /// Gate index of `leaves[0]`.
+    pub start: usize,
+    pub leaves: Vec<[u8; 71]>,
+    /// `gc_block_hash(start + i, leaves[i])`.
+    pub block_hashes: Vec<[u8; 32]>,
+}
+
+impl RegarbledRange {
+    /// Overwrites the range in an instance's full leaf and block-hash lists; the IH root is
+    /// then `incremental_root_from_hashes(block_hashes)`.
+    pub fn apply(
+        &self,
+        leaves: &mut [[u8; 71]],
+        block_hashes: &mut [[u8; 32]],
+    ) -> Result<(), String> {
+        let end = self.start + self.leaves.len();
+        if end > leaves.len() || end > block_hashes.len() {
+            return Err(format!(
+                "range {}..{end} exceeds {} leaves / {} block hashes",
+                self.start,
+                leaves.len(),
+                block_hashes.len()
+            ));
+        }
+        leaves[self.start..end].copy_from_slice(&self.leaves);
+        block_hashes[self.start..end].copy_from_slice(&self.block_hashes);
+        Ok(())
+    }
+}
+
+/// Re-garbles only the gates in `range`, e.g. after patching those gates of a layout.
+/// Leaves equal the matching slice of `garble_circuit(seed, layout)`. `Classic` layouts
+/// derive only the labels the range reads; `FreeXor` labels still depend on every earlier
+/// gate. Errors for `LeafFormat::V2` layouts and out-of-range gates.
+pub fn regarble_range(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    range: Range<usize>,
+) -> Result<RegarbledRange, String> {
+    if layout.leaf_format != LeafFormat::V1 {
+        return Err(format!(
+            "regarble_range emits v1 leaves but layout uses scheme {}",
+            layout.scheme_id()
+        ));
+    }
+    if range.start > range.end || range.end > layout.gates.len() {
+        return Err(format!(
+            "gate range {}..{} is out of bounds for {} gates",
+            range.start,
+            range.end,
+            layout.gates.len()
+        ));
+    }
+    let ctx = GarbleContext::new(seed, layout);
+    let leaves = range
+        .clone()
+        .map(|idx| ctx.gate_leaf(idx))
+        .collect::<Vec<_>>();
+    let block_hashes = leaves
+        .iter()
+        .zip(range.clone())
+        .map(|(leaf, idx)| gc_block_hash(idx as u64, leaf))
+        .collect();
+    Ok(RegarbledRange {
+        start: range.start,
+        leaves,
+        block_hashes,
+    })
+}
+
+/// One cut-and-choose instance garbled by `garble_all_instances`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GarbledInstance {
+    pub instance_id: u64,
+    /// `derive_instance_seed(master_seed, circuit_id, instance_id)`.
+    #[serde(with = "hex_bytes")]
+    pub seed: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub com_seed: [u8; 32],
+    #[serde(with = "hex_bytes_vec")]
+    pub leaves: Vec<[u8; 71]>,
+    /// `gc_block_hash(i, leaves[i])`.
+    #[serde(with = "hex_bytes_vec")]
+    pub block_hashes: Vec<[u8; 32]>,
+    #[serde(with = "hex_bytes")]
+    pub root_gc: [u8; 32],
+}
+
+/// Garbles instances `0..n` of a `Classic` v1 circuit from one master seed. Equivalent to
+/// calling `garble_circuit` once per instance with `derive_instance_seed`, but the layout is
+/// built once and the label caches are recycled between instances.
+pub fn garble_all_instances(
+    master_seed: [u8; 32],
+    circuit_id: [u8; 32],
+    gates: &[GateDesc],
+    n: usize,
+) -> Vec<GarbledInstance> {
+    garble_all_instances_with_scheme(master_seed, circuit_id, gates, SchemeId::CLASSIC_V1, n)
+}
+
+/// `garble_all_instances` for any scheme with v1 leaves. Panics for `LeafFormat::V2`.
+pub fn garble_all_instances_with_scheme(
+    master_seed: [u8; 32],
+    circuit_id: [u8; 32],
+    gates: &[GateDesc],
+    scheme_id: SchemeId,
+    n: usize,
+) -> Vec<GarbledInstance> {
+    let mut layout = CircuitLayout::new(circuit_id, 0, gates.to_vec()).with_scheme_id(scheme_id);
+    assert_eq!(
+        layout.leaf_format,
+        LeafFormat::V1,
+        "garble_all_instances emits v1 leaves but scheme is {scheme_id}"
+    );
+    let mut caches = ContextCaches::default();
+    let mut instances = Vec::with_capacity(n);
+    for instance_id in 0..n as u64 {
+        layout.instance_id = instance_id;
+        let seed = derive_instance_seed(master_seed, circuit_id, instance_id);
+        let ctx = GarbleContext::with_caches(seed, &layout, caches);
+        let leaves = (0..gates.len())
+            .map(|idx| ctx.gate_leaf(idx))
+            .collect::<Vec<_>>();
+        caches = ctx.into_caches();
+        let block_hashes = leaves
+            .iter()
+            .enumerate()
+            .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+            .collect::<Vec<_>>();
+        instances.push(GarbledInstance {
+            instance_id,
+            seed,
+            com_seed: com_seed(seed),
+            root_gc: incremental_root_from_hashes(&block_hashes),
+            leaves,
+            block_hashes,
+        });
+    }
+    instances
+}
+
+/// Garbles a circuit straight into `writer` as consecutive raw 71-byte leaf records and
+/// returns the incremental root over them (`incremental_root` of the same leaves), so large
+/// circuits can be exported to a file or blob without materializing their leaves.
+/// Panics for `LeafFormat::V2` layouts.
+pub fn garble_circuit_to_writer<W: Write>(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    writer: &mut W,
+) -> Result<[u8; 32], String> {
+    let mut state = [0u8; 32];
+    for (idx, leaf) in garble_circuit_iter(seed, layout).enumerate() {
+        writer
+            .write_all(&leaf)
+            .map_err(|e| format!("failed to write leaf {idx}: {e}"))?;
+        state = inc_hash(state, gc_block_hash(idx as u64, &leaf));
+    }
+    writer
+        .flush()
+        .map_err(|e| format!("failed to flush leaves: {e}"))?;
+    Ok(state)
+}
+
+/// Garbles a full circuit into leaves encoded per `layout.leaf_format`.
+/// `LeafFormat::V2` leaves carry the layout's `SchemeId` in their version byte and drop the
+/// always-zero row `0` of garbled gates, rows `2`/`3` of garbled NOT gates and all rows of
+/// free gates. Under `RowAuthMode::Mac` the tags of all garbled rows follow the rows.
+pub fn garble_leaves(seed: [u8; 32], layout: &CircuitLayout) -> Vec<Vec<u8>> {
+    let ctx = GarbleContext::new(seed, layout);
+    let scheme_id = layout.scheme_id();
     layout
         .gates
         .iter()
         .enumerate()
         .map(|(idx, gate)| {
-            recompute_gate_leaf(
-                seed,
-                layout.circuit_id,
-                layout.instance_id,
-                idx as u64,
-                *gate,
-            )
+            let garbled = garble_rows(&ctx, idx as u64, *gate);
+            match layout.leaf_format {
+                LeafFormat::V1 => {
+                    encode_leaf(*gate, garbled.map(|g| g.rows).unwrap_or_default()).to_vec()
+                }
+                LeafFormat::V2 => match garbled {
+                    Some(GarbledRows { rows, macs }) => {
+                        debug_assert_eq!(rows[0], [0u8; 16], "GRR3 row 0 must be zero");
+                        let row_count = layout.garbled_row_count(gate.gate_type);
+                        let macs = macs.as_ref().map_or(&[][..], |macs| &macs[..row_count]);
+                        encode_leaf_v2(scheme_id, *gate, &rows[1..row_count], macs)
+                    }
+                    None => encode_leaf_v2(scheme_id, *gate, &[], &[]),
+                },
+            }
         })
         .collect()
 }
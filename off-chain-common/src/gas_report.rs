@@ -0,0 +1,140 @@
+//! Gas estimates for the transactions of one session, for `gas-report`.
+//!
+//! Each call is prepared with the calldata it will carry and estimated with `eth_estimateGas`
+//! from the party that sends it. The contract reverts calls made outside their stage, and a
+//! blob transaction has no blob in an estimate, so some calls cannot be estimated on a given
+//! node; the report then falls back to their intrinsic gas (transaction base cost plus
+//! calldata), which is a lower bound. Blob gas is priced separately and never included.
+
+use crate::abi::{AbiCall, dispute_garbled_table_call};
+use crate::cost::CALLDATA_GAS_PER_BYTE;
+use crate::dispute::{PrepareDisputeConfig, prepare_dispute_packet};
+use crate::garble::garble_circuit;
+use crate::protocol::Party;
+use crate::registry::CircuitVersion;
+use crate::types::CircuitLayout;
+
+/// Base cost of every transaction.
+pub const TX_BASE_GAS: u64 = 21_000;
+/// Calldata gas per zero byte (EIP-2028).
+pub const CALLDATA_ZERO_BYTE_GAS: u64 = 4;
+
+/// Calldata gas of `calldata`: `CALLDATA_ZERO_BYTE_GAS` per zero byte,
+/// `CALLDATA_GAS_PER_BYTE` per other byte.
+pub fn calldata_gas(calldata: &[u8]) -> u64 {
+    calldata
+        .iter()
+        .map(|byte| match byte {
+            0 => CALLDATA_ZERO_BYTE_GAS,
+            _ => CALLDATA_GAS_PER_BYTE,
+        })
+        .sum()
+}
+
+/// Gas a transaction carrying `calldata` costs before any execution.
+pub fn intrinsic_gas(calldata: &[u8]) -> u64 {
+    TX_BASE_GAS + calldata_gas(calldata)
+}
+
+/// One transaction of the session, prepared with its calldata.
+#[derive(Debug, Clone)]
+pub struct GasCall {
+    /// Report label, e.g. `submit_commitments`.
+    pub name: &'static str,
+    pub sender: Party,
+    pub call: AbiCall,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GasEstimate {
+    Estimated(u64),
+    /// `eth_estimateGas` failed, e.g. because the contract is in another stage.
+    Unavailable(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasReportEntry {
+    pub name: &'static str,
+    pub sender: Party,
+    pub calldata_bytes: usize,
+    pub intrinsic_gas: u64,
+    pub estimate: GasEstimate,
+}
+
+impl GasReportEntry {
+    /// The estimate, else the intrinsic gas.
+    pub fn gas(&self) -> u64 {
+        match self.estimate {
+            GasEstimate::Estimated(gas) => gas,
+            GasEstimate::Unavailable(_) => self.intrinsic_gas,
+        }
+    }
+}
+
+/// Estimates every call of `calls` with `estimate`, in order.
+pub fn gas_report(
+    calls: &[GasCall],
+    mut estimate: impl FnMut(&GasCall) -> Result<u64, String>,
+) -> Vec<GasReportEntry> {
+    calls
+        .iter()
+        .map(|call| {
+            let calldata = call.call.calldata();
+            GasReportEntry {
+                name: call.name,
+                sender: call.sender,
+                calldata_bytes: calldata.len(),
+                intrinsic_gas: intrinsic_gas(&calldata),
+                estimate: match estimate(call) {
+                    Ok(gas) => GasEstimate::Estimated(gas),
+                    Err(e) => GasEstimate::Unavailable(e),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Total gas of the entries `sender` sends.
+pub fn sender_gas(entries: &[GasReportEntry], sender: Party) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| entry.sender == sender)
+        .map(GasReportEntry::gas)
+        .sum()
+}
+
+/// `disputeGarbledTable` for gate `0` of the instance garbled from `seed`: the gate with the
+/// longest IH proof, so the most expensive dispute of the instance. The leaf is the honest
+/// one, so the call is the size of a real dispute but would not slash.
+pub fn worst_case_dispute_call(
+    circuit: &'static CircuitVersion,
+    bit_width: usize,
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    seed: [u8; 32],
+) -> Result<AbiCall, String> {
+    circuit.check_bit_width(bit_width)?;
+    let layout = CircuitLayout::new(circuit_id, instance_id, circuit.build(bit_width))
+        .with_scheme_id(circuit.params.scheme_id);
+    let config = PrepareDisputeConfig {
+        circuit,
+        bit_width,
+        circuit_id,
+        instance_id,
+        seed,
+        claimed_leaves: garble_circuit(seed, &layout),
+        gate_index: Some(0),
+        allow_false_challenge: true,
+        expected_root_gc: None,
+    };
+    let prepared = prepare_dispute_packet(&config)?;
+    Ok(dispute_garbled_table_call(
+        instance_id,
+        seed,
+        prepared.gate_index as u64,
+        prepared.gate,
+        &prepared.claimed_leaf,
+        &prepared.ih_proof,
+        &prepared.layout_proof,
+    ))
+}
@@ -0,0 +1,90 @@
+//! Ethereum identities of the parties: the secp256k1 key behind `ALICE_PRIVATE_KEY` /
+//! `BOB_PRIVATE_KEY`, its address, and signer recovery for raw digests and EIP-191
+//! `personal_sign`. Shared by the secure channel, signed messages and manifests, and the
+//! relayer.
+
+use std::fmt;
+
+use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+
+use crate::cli::hex_prefixed;
+use crate::consensus::keccak256;
+
+/// Ethereum address of a secp256k1 public key: last 20 bytes of keccak256 over `x || y`.
+pub fn eth_address(key: &VerifyingKey) -> [u8; 20] {
+    let point = key.to_encoded_point(false);
+    let digest = keccak256(&[&point.as_bytes()[1..]]);
+    let mut out = [0u8; 20];
+    out.copy_from_slice(&digest[12..]);
+    out
+}
+
+/// EIP-191 `personal_sign` digest of a 32-byte message.
+fn personal_sign_digest(message: [u8; 32]) -> [u8; 32] {
+    keccak256(&[b"\x19Ethereum Signed Message:\n32", &message])
+}
+
+/// A party's Ethereum signing key (`ALICE_PRIVATE_KEY` / `BOB_PRIVATE_KEY`).
+#[derive(Clone)]
+pub struct EthIdentity {
+    key: SigningKey,
+}
+
+impl fmt::Debug for EthIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EthIdentity({})", hex_prefixed(&self.address()))
+    }
+}
+
+impl EthIdentity {
+    pub fn from_private_key(private_key: [u8; 32]) -> Result<Self, String> {
+        SigningKey::from_slice(&private_key)
+            .map(|key| Self { key })
+            .map_err(|_| "invalid secp256k1 private key".to_string())
+    }
+
+    pub fn address(&self) -> [u8; 20] {
+        eth_address(self.key.verifying_key())
+    }
+
+    /// `r || s` (low-s) and recovery id of a signature over a 32-byte digest.
+    pub fn sign_prehash(&self, digest: [u8; 32]) -> Result<([u8; 64], u8), String> {
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(&digest)
+            .map_err(|e| format!("failed to sign: {e}"))?;
+        Ok((signature.to_bytes().into(), recovery_id.to_byte()))
+    }
+
+    /// 65-byte `r || s || v` (`v` in `{27, 28}`) `personal_sign` signature over `message`.
+    pub fn personal_sign(&self, message: [u8; 32]) -> Result<[u8; 65], String> {
+        let (signature, recovery_id) = self.sign_prehash(personal_sign_digest(message))?;
+        let mut out = [0u8; 65];
+        out[..64].copy_from_slice(&signature);
+        out[64] = 27 + recovery_id;
+        Ok(out)
+    }
+}
+
+/// Address whose key produced `r || s` with `recovery_id` over `digest`.
+pub fn recover_prehash(
+    digest: [u8; 32],
+    signature: &[u8; 64],
+    recovery_id: u8,
+) -> Result<[u8; 20], String> {
+    let parsed = Signature::from_slice(signature).map_err(|_| "malformed signature".to_string())?;
+    let recovery_id = RecoveryId::from_byte(recovery_id)
+        .ok_or_else(|| format!("invalid recovery id {recovery_id}"))?;
+    let key = VerifyingKey::recover_from_prehash(&digest, &parsed, recovery_id)
+        .map_err(|_| "signature does not recover a public key".to_string())?;
+    Ok(eth_address(&key))
+}
+
+/// Address that produced `signature` (as from `EthIdentity::personal_sign`) over `message`.
+pub fn recover_personal_sign(message: [u8; 32], signature: &[u8; 65]) -> Result<[u8; 20], String> {
+    let v = signature[64]
+        .checked_sub(27)
+        .ok_or_else(|| format!("invalid signature v={}", signature[64]))?;
+    let rs: &[u8; 64] = signature[..64].try_into().expect("64-byte prefix");
+    recover_prehash(personal_sign_digest(message), rs, v)
+}
@@ -1,4 +1,5 @@
-use crate::consensus::{keccak256, uint256_from_u64};
+use crate::consensus::{check_leaf_scheme, keccak256, uint256_from_u64};
+use crate::types::{LeafFormat, SchemeId};
 
 /// Contract-consensus gate block hash:
 /// `keccak256(abi.encodePacked(gateIndex, leafBytes))`.
@@ -7,6 +8,28 @@ pub fn gc_block_hash(gate_index: u64, leaf: &[u8]) -> [u8; 32] {
     keccak256(&[&idx, leaf])
 }
 
+/// Gate block hash for `LeafFormat::V2` leaves:
+/// `keccak256("GCv2", gateIndex, leafBytes)`. The domain tag keeps variable-length v2 leaves
+/// from colliding with v1 block hashes.
+pub fn gc_block_hash_v2(gate_index: u64, leaf: &[u8]) -> [u8; 32] {
+    let idx = uint256_from_u64(gate_index);
+    keccak256(&[b"GCv2", &idx, leaf])
+}
+
+/// Block hash of `leaf` under `scheme_id`: checks the leaf's scheme/version byte, then uses
+/// `gc_block_hash` for `LeafFormat::V1` and `gc_block_hash_v2` for `LeafFormat::V2`.
+pub fn gc_block_hash_for_scheme(
+    scheme_id: SchemeId,
+    gate_index: u64,
+    leaf: &[u8],
+) -> Result<[u8; 32], String> {
+    check_leaf_scheme(scheme_id, leaf).map_err(|e| format!("gate {gate_index}: {e}"))?;
+    Ok(match scheme_id.leaf_format() {
+        LeafFormat::V1 => gc_block_hash(gate_index, leaf),
+        LeafFormat::V2 => gc_block_hash_v2(gate_index, leaf),
+    })
+}
+
 /// One incremental transition:
 /// `IH_i = keccak256(abi.encodePacked(IH_{i-1}, blockHash_i))`.
 pub fn inc_hash(prev: [u8; 32], block_hash: [u8; 32]) -> [u8; 32] {
@@ -32,6 +55,16 @@ pub fn incremental_root(leaves: &[[u8; 71]]) -> [u8; 32] {
     incremental_root_from_hashes(&block_hashes)
 }
 
+/// Terminal incremental state over `LeafFormat::V2` leaves.
+pub fn incremental_root_v2(leaves: &[Vec<u8>]) -> [u8; 32] {
+    let block_hashes: Vec<[u8; 32]> = leaves
+        .iter()
+        .enumerate()
+        .map(|(gate_index, leaf)| gc_block_hash_v2(gate_index as u64, leaf))
+        .collect();
+    incremental_root_from_hashes(&block_hashes)
+}
+
 /// Builds contract-compatible IH proof for a challenged gate block.
 ///
 /// Proof format mirrors Solidity `_processIncrementalProof`:
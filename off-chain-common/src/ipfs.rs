@@ -0,0 +1,296 @@
+//! IPFS publication of garbled circuits: Alice pins each instance's eval blob (the binary leaf
+//! blob `blobHashGC` commits to) through a Kubo node's HTTP RPC API and records its CID in
+//! `manifest.json`, so anyone can fetch the data behind an on-chain commitment by CID, from a
+//! node or from a public gateway.
+//!
+//! Blobs are added as CIDv1 with raw leaves. A blob that fits one chunk (`RAW_CHUNK_SIZE`) is
+//! then a single raw block whose CID is the sha2-256 of its bytes, which `pin` checks against
+//! the node's answer and `fetch` against the bytes received. A larger blob gets a UnixFS
+//! (`dag-pb`) CID that cannot be checked without walking the DAG, so whoever fetches it
+//! compares the bytes with `blobHashGC` instead, as Bob's `fetch-ipfs` does. Adding the same
+//! bytes twice yields the same CID, so requests are retried like other transient failures.
+
+use std::fmt;
+use std::io::Read;
+use std::time::Duration;
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::consensus::keccak256;
+use crate::retry::{RetryPolicy, retry};
+
+/// Environment variable naming the Kubo RPC API, e.g. `http://127.0.0.1:5001`.
+pub const IPFS_API_ENV: &str = "IPFS_API_URL";
+
+pub const DEFAULT_IPFS_API: &str = "http://127.0.0.1:5001";
+
+/// Kubo's default chunk size; blobs up to this length get a raw-block CID.
+pub const RAW_CHUNK_SIZE: usize = 262_144;
+
+/// Upper bound on a fetched blob, so a misbehaving node cannot stream forever.
+const MAX_FETCH_BYTES: u64 = 1 << 30;
+
+const CID_V1: u8 = 0x01;
+const CODEC_RAW: u8 = 0x55;
+const CODEC_DAG_PB: u8 = 0x70;
+const MULTIHASH_SHA2_256: u8 = 0x12;
+
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+
+/// A CIDv1 with a sha2-256 multihash, as `pin` records it: `raw` for single-block blobs,
+/// `dag-pb` for chunked ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cid {
+    pub codec: u8,
+    pub digest: [u8; 32],
+}
+
+impl Cid {
+    /// CID of `bytes` stored as one raw block.
+    pub fn raw(bytes: &[u8]) -> Self {
+        Self {
+            codec: CODEC_RAW,
+            digest: Sha256::digest(bytes).into(),
+        }
+    }
+
+    /// Parses a base32 (`b...`) CIDv1; CIDv0 (`Qm...`) and other encodings are rejected.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid CID {text}: {reason}");
+        let encoded = text
+            .strip_prefix('b')
+            .ok_or_else(|| invalid("expected a base32 CIDv1 starting with 'b'"))?;
+        let bytes = base32_decode(encoded).ok_or_else(|| invalid("not base32"))?;
+        let [version, codec, hash, hash_len, digest @ ..] = bytes.as_slice() else {
+            return Err(invalid("too short"));
+        };
+        if *version != CID_V1 {
+            return Err(invalid("not a CIDv1"));
+        }
+        if *codec != CODEC_RAW && *codec != CODEC_DAG_PB {
+            return Err(invalid(&format!("unsupported codec 0x{codec:02x}")));
+        }
+        if *hash != MULTIHASH_SHA2_256 || *hash_len != 32 || digest.len() != 32 {
+            return Err(invalid("expected a sha2-256 multihash"));
+        }
+        Ok(Self {
+            codec: *codec,
+            digest: digest.try_into().expect("checked length"),
+        })
+    }
+
+    /// Whether the CID addresses a single raw block, so its bytes can be checked locally.
+    pub fn is_raw(&self) -> bool {
+        self.codec == CODEC_RAW
+    }
+
+    /// Checks `bytes` against a raw CID; returns whether a check was possible.
+    pub fn verify(&self, bytes: &[u8]) -> Result<bool, String> {
+        if !self.is_raw() {
+            return Ok(false);
+        }
+        if Self::raw(bytes) != *self {
+            return Err(format!("fetched bytes do not hash to CID {self}"));
+        }
+        Ok(true)
+    }
+}
+
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = vec![CID_V1, self.codec, MULTIHASH_SHA2_256, 32];
+        bytes.extend_from_slice(&self.digest);
+        write!(f, "b{}", base32_encode(&bytes))
+    }
+}
+
+/// Lowercase RFC 4648 base32 without padding, the multibase `b` encoding.
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+fn base32_decode(text: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(text.len() * 5 / 8);
+    let (mut buffer, mut bits) = (0u32, 0);
+    for ch in text.bytes() {
+        let value = BASE32_ALPHABET.iter().position(|c| *c == ch)? as u32;
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((buffer >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+#[derive(Deserialize)]
+struct AddResponse {
+    #[serde(rename = "Hash")]
+    hash: String,
+}
+
+/// Where blobs are fetched from, and pinned to when it is an RPC API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Endpoint {
+    /// Kubo RPC API (`/api/v0/...`).
+    Api(String),
+    /// Read-only HTTP gateway (`/ipfs/<cid>`).
+    Gateway(String),
+}
+
+/// Client for a Kubo node or an IPFS gateway.
+#[derive(Debug, Clone)]
+pub struct IpfsClient {
+    endpoint: Endpoint,
+    policy: RetryPolicy,
+    timeout: Duration,
+}
+
+impl IpfsClient {
+    /// Client for the Kubo RPC API at `api_url`.
+    pub fn api(api_url: &str, policy: RetryPolicy) -> Self {
+        Self {
+            endpoint: Endpoint::Api(api_url.trim_end_matches('/').to_string()),
+            policy,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// Fetch-only client for an HTTP gateway such as `https://ipfs.io`.
+    pub fn gateway(gateway_url: &str, policy: RetryPolicy) -> Self {
+        Self {
+            endpoint: Endpoint::Gateway(gateway_url.trim_end_matches('/').to_string()),
+            policy,
+            timeout: Duration::from_secs(60),
+        }
+    }
+
+    /// The API at `IPFS_API_URL` (default `DEFAULT_IPFS_API`) under `RetryPolicy::from_env`.
+    pub fn from_env() -> Result<Self, String> {
+        let api_url = std::env::var(IPFS_API_ENV).unwrap_or_else(|_| DEFAULT_IPFS_API.to_string());
+        Ok(Self::api(&api_url, RetryPolicy::from_env()?))
+    }
+
+    pub fn url(&self) -> &str {
+        match &self.endpoint {
+            Endpoint::Api(url) | Endpoint::Gateway(url) => url,
+        }
+    }
+
+    fn agent(&self) -> ureq::Agent {
+        ureq::AgentBuilder::new().timeout(self.timeout).build()
+    }
+
+    /// Adds and pins `bytes` (CIDv1, raw leaves) and returns their CID. For single-chunk blobs
+    /// the node's CID must equal `Cid::raw(bytes)`.
+    pub fn pin(&self, bytes: &[u8]) -> Result<Cid, String> {
+        let Endpoint::Api(api_url) = &self.endpoint else {
+            return Err(format!("cannot pin through the gateway {}", self.url()));
+        };
+        let url = format!("{api_url}/api/v0/add");
+        // The boundary only has to be absent from the body; one derived from it is.
+        let boundary = format!("ppa-{}", base32_encode(&keccak256(&[bytes])[..10]));
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"blob\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(bytes);
+        body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+        let response = retry(&self.policy, |_| {
+            let response = self
+                .agent()
+                .post(&url)
+                .query("pin", "true")
+                .query("cid-version", "1")
+                .query("raw-leaves", "true")
+                .set(
+                    "Content-Type",
+                    &format!("multipart/form-data; boundary={boundary}"),
+                )
+                .send_bytes(&body);
+            read_response(&url, response, MAX_FETCH_BYTES)
+        })?;
+        let text = String::from_utf8(response)
+            .map_err(|_| format!("{url} returned a non-UTF-8 response"))?;
+        let line = text.lines().rev().find(|line| !line.trim().is_empty());
+        let added: AddResponse = line
+            .and_then(|line| serde_json::from_str(line).ok())
+            .ok_or_else(|| format!("{url} returned an unexpected response: {text}"))?;
+        let cid = Cid::parse(&added.hash)?;
+        if bytes.len() <= RAW_CHUNK_SIZE && cid != Cid::raw(bytes) {
+            return Err(format!(
+                "{url} returned CID {cid}, expected {} for a single-chunk blob",
+                Cid::raw(bytes)
+            ));
+        }
+        Ok(cid)
+    }
+
+    /// Fetches the bytes behind `cid`, checked against it when it is a raw CID.
+    pub fn fetch(&self, cid: &Cid) -> Result<Vec<u8>, String> {
+        let bytes = retry(&self.policy, |_| match &self.endpoint {
+            Endpoint::Api(api_url) => {
+                let url = format!("{api_url}/api/v0/cat");
+                let response = self
+                    .agent()
+                    .post(&url)
+                    .query("arg", &cid.to_string())
+                    .call();
+                read_response(&url, response, MAX_FETCH_BYTES)
+            }
+            Endpoint::Gateway(gateway_url) => {
+                let url = format!("{gateway_url}/ipfs/{cid}");
+                read_response(&url, self.agent().get(&url).call(), MAX_FETCH_BYTES)
+            }
+        })?;
+        cid.verify(&bytes)?;
+        Ok(bytes)
+    }
+}
+
+/// Body of a successful response; failures keep the status line, so `retry` recognises
+/// `503 Service Unavailable` and the like.
+pub(crate) fn read_response(
+    url: &str,
+    response: Result<ureq::Response, ureq::Error>,
+    limit: u64,
+) -> Result<Vec<u8>, String> {
+    let response = match response {
+        Ok(response) => response,
+        Err(ureq::Error::Status(code, response)) => {
+            let status = response.status_text().to_string();
+            // Kubo answers with a short JSON message; a gateway may send a whole HTML page.
+            let detail = response.into_string().unwrap_or_default();
+            let detail = detail.trim().chars().take(200).collect::<String>();
+            return Err(format!("{url} returned {code} {status}: {detail}"));
+        }
+        Err(e) => return Err(format!("request to {url} failed: {e}")),
+    };
+    let mut body = Vec::new();
+    response
+        .into_reader()
+        .take(limit + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| format!("failed to read the response of {url}: {e}"))?;
+    if body.len() as u64 > limit {
+        return Err(format!("{url} returned more than {limit} bytes"));
+    }
+    Ok(body)
+}
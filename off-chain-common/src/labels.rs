@@ -1,4 +1,55 @@
+use crate::consensus::derive_wire_label;
+use crate::garble::LayoutLabels;
+use crate::types::CircuitLayout;
+
 /// Reads the permutation bit (LSB of first label byte), same convention as Solidity.
 pub fn get_permutation_bit(label: [u8; 16]) -> u8 {
     label[0] & 1
 }
+
+/// Seed-derived label pair `[L0, L1]` of `wire`, as Solidity `deriveWireLabel` computes it.
+/// Ignores constant wires and non-classic schemes; use `derive_layout_label_pair` for those.
+pub fn derive_label_pair(
+    circuit_id: [u8; 32],
+    instance_id: u64,
+    wire: u16,
+    seed: [u8; 32],
+) -> [[u8; 16]; 2] {
+    [0, 1].map(|bit| derive_wire_label(circuit_id, instance_id, wire, bit, seed))
+}
+
+/// Label pair `[L0, L1]` of `wire` under the layout's scheme and constant wires.
+pub fn derive_layout_label_pair(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    wire: u16,
+) -> [[u8; 16]; 2] {
+    let labels = LayoutLabels::new(seed, layout);
+    [0, 1].map(|bit| labels.label(wire, bit))
+}
+
+/// Semantic bit that `label` encodes on `wire` of the layout instance garbled from `seed`,
+/// or `None` if it is neither of the wire's labels.
+pub fn semantic_bit(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    wire: u16,
+    label: [u8; 16],
+) -> Option<u8> {
+    derive_layout_label_pair(seed, layout, wire)
+        .iter()
+        .position(|candidate| *candidate == label)
+        .map(|bit| bit as u8)
+}
+
+/// Point-and-permute invariant of a label pair: `L0` and `L1` carry opposite permutation
+/// bits, so the evaluator's row selection reveals nothing about the semantic bit.
+pub fn check_point_and_permute(pair: [[u8; 16]; 2]) -> Result<(), String> {
+    if get_permutation_bit(pair[0]) == get_permutation_bit(pair[1]) {
+        return Err(format!(
+            "labels share permutation bit {}; point-and-permute needs distinct permutation bits",
+            get_permutation_bit(pair[0])
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Random access to claimed-leaf files without loading them.
+//!
+//! A dispute needs every block hash of an instance but only one or two leaves, so
+//! `prepare-dispute` should not hold a million-gate circuit's leaves as a `Vec`. `LeafFile`
+//! memory-maps a plain text leaf file, indexes where each value starts in one pass (validating
+//! every leaf on the way, so errors still name the line), and parses a leaf only when `get`
+//! asks for it. Compressed, encrypted and bundled files cannot be mapped and are decoded into
+//! memory instead, through the same `ValueLines` reader.
+//!
+//! `ClaimedLeaves` is the access both `LeafFile` and an in-memory `Vec` offer to the audit and
+//! dispute code.
+
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+
+use crate::bundle::locate_in_bundle;
+use crate::cli::parse_leaf71;
+use crate::compression::{find_artifact, is_zstd};
+use crate::encryption::is_encrypted;
+use crate::ih::gc_block_hash;
+use crate::line_reader::{clean_value_line, read_value_lines};
+
+/// Claimed 71-byte leaves of one instance, in gate order.
+pub trait ClaimedLeaves {
+    fn leaf_count(&self) -> usize;
+
+    /// Leaf of gate `index`.
+    fn leaf(&self, index: usize) -> Result<[u8; 71], String>;
+}
+
+impl ClaimedLeaves for [[u8; 71]] {
+    fn leaf_count(&self) -> usize {
+        self.len()
+    }
+
+    fn leaf(&self, index: usize) -> Result<[u8; 71], String> {
+        self.get(index).copied().ok_or_else(|| {
+            format!(
+                "leaf index {index} out of range, total leaves {}",
+                self.len()
+            )
+        })
+    }
+}
+
+impl ClaimedLeaves for Vec<[u8; 71]> {
+    fn leaf_count(&self) -> usize {
+        self.len()
+    }
+
+    fn leaf(&self, index: usize) -> Result<[u8; 71], String> {
+        self.as_slice().leaf(index)
+    }
+}
+
+/// `gc_block_hash` of every claimed leaf, computed one leaf at a time.
+pub fn claimed_block_hashes(
+    leaves: &(impl ClaimedLeaves + ?Sized),
+) -> Result<Vec<[u8; 32]>, String> {
+    (0..leaves.leaf_count())
+        .map(|idx| Ok(gc_block_hash(idx as u64, &leaves.leaf(idx)?)))
+        .collect()
+}
+
+#[derive(Debug)]
+enum Backing {
+    /// A plain text file mapped into memory, with the byte range of each leaf's value.
+    Mapped {
+        map: Mmap,
+        values: Vec<Range<usize>>,
+    },
+    /// A compressed, encrypted or bundled file, decoded up front.
+    Loaded(Vec<[u8; 71]>),
+}
+
+/// A claimed-leaf file (one hex leaf per line, as `read_value_lines` accepts) opened for
+/// random access.
+#[derive(Debug)]
+pub struct LeafFile {
+    path: PathBuf,
+    backing: Backing,
+}
+
+impl LeafFile {
+    /// Opens and validates the leaf file at `path` (or its `.zst` sibling, or a bundle entry).
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let loaded = |path: &Path| {
+            Ok(Self {
+                path: path.to_path_buf(),
+                backing: Backing::Loaded(read_value_lines(path, "claimed leaf", parse_leaf71)?),
+            })
+        };
+        let file = find_artifact(path).unwrap_or_else(|| path.to_path_buf());
+        if !file.exists() && locate_in_bundle(&file).is_some() {
+            return loaded(path);
+        }
+        let read_err = |e| format!("failed to read {}: {e}", file.display());
+        let handle = fs::File::open(&file).map_err(read_err)?;
+        if handle.metadata().map_err(read_err)?.len() == 0 {
+            return loaded(path);
+        }
+        // SAFETY: the map is only read, and leaf files are written once by export and never
+        // modified in place; a file truncated underneath us is the same hazard any reader has.
+        let map = unsafe { Mmap::map(&handle) }.map_err(read_err)?;
+        if is_encrypted(&map) || is_zstd(&map) {
+            return loaded(path);
+        }
+
+        let mut values = Vec::new();
+        let mut start = 0;
+        for (line_idx, line) in map.split(|byte| *byte == b'\n').enumerate() {
+            let at = |e: &dyn std::fmt::Display| {
+                format!(
+                    "invalid claimed leaf at {}:{}: {e}",
+                    path.display(),
+                    line_idx + 1
+                )
+            };
+            let text = std::str::from_utf8(line).map_err(|_| at(&"not valid UTF-8"))?;
+            let value = clean_value_line(text);
+            if !value.is_empty() {
+                parse_leaf71(value).map_err(|e| at(&e))?;
+                let offset = start + (value.as_ptr() as usize - text.as_ptr() as usize);
+                values.push(offset..offset + value.len());
+            }
+            start += line.len() + 1;
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            backing: Backing::Mapped { map, values },
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.backing {
+            Backing::Mapped { values, .. } => values.len(),
+            Backing::Loaded(leaves) => leaves.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether the file is served from a memory map rather than decoded into memory.
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.backing, Backing::Mapped { .. })
+    }
+
+    /// Leaf of gate `index`.
+    pub fn get(&self, index: usize) -> Result<[u8; 71], String> {
+        match &self.backing {
+            Backing::Mapped { map, values } => {
+                let range = values.get(index).ok_or_else(|| {
+                    format!(
+                        "leaf index {index} out of range, {} has {} leaves",
+                        self.path.display(),
+                        values.len()
+                    )
+                })?;
+                // Validated by `open`, so only a file rewritten since then fails here.
+                let value = std::str::from_utf8(&map[range.clone()])
+                    .map_err(|_| format!("{} changed since it was opened", self.path.display()))?;
+                parse_leaf71(value).map_err(|e| {
+                    format!(
+                        "leaf {index} of {} no longer parses: {e}",
+                        self.path.display()
+                    )
+                })
+            }
+            Backing::Loaded(leaves) => leaves.leaf(index),
+        }
+    }
+
+    /// Every leaf in gate order.
+    pub fn iter(&self) -> impl Iterator<Item = Result<[u8; 71], String>> + '_ {
+        (0..self.len()).map(|idx| self.get(idx))
+    }
+
+    /// `gc_block_hash` of every leaf, streamed from the file.
+    pub fn block_hashes(&self) -> Result<Vec<[u8; 32]>, String> {
+        claimed_block_hashes(self)
+    }
+}
+
+impl ClaimedLeaves for LeafFile {
+    fn leaf_count(&self) -> usize {
+        self.len()
+    }
+
+    fn leaf(&self, index: usize) -> Result<[u8; 71], String> {
+        self.get(index)
+    }
+}
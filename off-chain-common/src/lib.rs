@@ -1,17 +1,62 @@
 //! Off-chain garbling toolkit for the privacy-preserving auction.
 //! Modules are split by consensus rules, circuit garbling, Merkle proofs, and scenario wiring.
 
+pub mod abi;
+pub mod artifact_diff;
+pub mod artifact_layout;
 pub mod auction_outcome;
+pub mod audit;
+pub mod base_ot;
+pub mod blob_sidecar;
+pub mod builder;
+pub mod bundle;
+pub mod canonical_json;
+pub mod circuits;
 pub mod cli;
+pub mod cli_args;
+pub mod commitment_check;
+pub mod compression;
 pub mod consensus;
+pub mod contract_status;
+pub mod cost;
+pub mod csv_export;
+pub mod daemon;
+pub mod decoding;
+pub mod dispute;
 pub mod eip4844;
+pub mod encryption;
 pub mod eval_blob;
+pub mod eval_check;
+pub mod eval_meta;
 pub mod evaluation;
+pub mod export_checkpoint;
 pub mod garble;
+pub mod gas_report;
+pub mod identity;
 pub mod ih;
+pub mod ipfs;
 pub mod labels;
+pub mod leaf_file;
+pub mod line_reader;
+pub mod logging;
+pub mod manifest;
 pub mod merkle;
+pub mod messages;
+pub mod orchestrate;
 pub mod ot;
+pub mod ot_extension;
+pub mod output;
+pub mod profile;
+pub mod protocol;
+pub mod registry;
+pub mod relayer;
+pub mod retry;
 pub mod scenario;
+pub mod secrets;
+pub mod secure_channel;
+pub mod session_store;
 pub mod settlement;
+pub mod timeouts;
+pub mod transcript;
+pub mod transport;
 pub mod types;
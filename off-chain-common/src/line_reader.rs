@@ -0,0 +1,88 @@
+//! Streaming readers for one-value-per-line text artifacts: claimed leaves, bytes32 lists and
+//! label files.
+//!
+//! Leaf files of big circuits reach hundreds of megabytes. `ValueLines` reads them a line at a
+//! time through `compression::open_artifact` (so `.zst` files are decompressed on the fly)
+//! instead of loading the whole text first. Lines are cleaned the way hand-edited and
+//! pasted files need: `#` comments, surrounding quotes, trailing commas and `[`/`]` of a
+//! bracket-wrapped list are stripped, and lines left empty are skipped.
+
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+
+use crate::compression::open_artifact;
+
+/// The value on `line`, or `""` when the line holds none.
+pub fn clean_value_line(line: &str) -> &str {
+    let value = line
+        .split('#')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .trim_end_matches(',')
+        .trim()
+        .trim_matches('"')
+        .trim();
+    value.trim_start_matches('[').trim_end_matches(']').trim()
+}
+
+/// Iterator over the non-empty cleaned values of a text artifact, with 1-based line numbers.
+pub struct ValueLines {
+    reader: Box<dyn BufRead>,
+    path: PathBuf,
+    line: String,
+    line_no: usize,
+}
+
+impl ValueLines {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        Ok(Self {
+            reader: open_artifact(path)?,
+            path: path.to_path_buf(),
+            line: String::new(),
+            line_no: 0,
+        })
+    }
+}
+
+impl Iterator for ValueLines {
+    type Item = Result<(usize, String), String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.line_no += 1,
+                Err(e) => {
+                    return Some(Err(format!(
+                        "failed to read {} after line {}: {e}",
+                        self.path.display(),
+                        self.line_no
+                    )));
+                }
+            }
+            let value = clean_value_line(&self.line);
+            if !value.is_empty() {
+                return Some(Ok((self.line_no, value.to_string())));
+            }
+        }
+    }
+}
+
+/// Parses every value of `path` with `parse`; a failure names the file and line, as in
+/// `invalid <what> at <path>:<line>: <error>`. An empty file yields an empty list.
+pub fn read_value_lines<T, E: std::fmt::Display>(
+    path: &Path,
+    what: &str,
+    mut parse: impl FnMut(&str) -> Result<T, E>,
+) -> Result<Vec<T>, String> {
+    let mut out = Vec::new();
+    for entry in ValueLines::open(path)? {
+        let (line_no, value) = entry?;
+        let parsed = parse(&value)
+            .map_err(|e| format!("invalid {what} at {}:{line_no}: {e}", path.display()))?;
+        out.push(parsed);
+    }
+    Ok(out)
+}
@@ -0,0 +1,38 @@
+//! Diagnostics of the alice and bob binaries, as `tracing` events on stderr.
+//!
+//! Command results (the `emitln!` lines, or the `--json` object) are the only thing written to
+//! stdout, so a pipeline reading commitments or roots never sees a log line. Without `-v` only
+//! warnings are logged; `-v` adds the command span and sent transactions, `-vv` every `cast`
+//! invocation, retry and watch poll, `-vvv` everything. A `RUST_LOG` filter such as
+//! `off_chain_common::retry=debug` replaces the one chosen by `-v`.
+
+use std::io;
+
+use tracing_subscriber::EnvFilter;
+
+/// Filter directive for `-v` given `verbosity` times.
+pub fn verbosity_filter(verbosity: u8) -> &'static str {
+    match verbosity {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    }
+}
+
+/// The `RUST_LOG` filter when it is set and valid, else the one of `verbosity`.
+pub fn log_filter(rust_log: Option<&str>, verbosity: u8) -> EnvFilter {
+    rust_log
+        .filter(|directives| !directives.trim().is_empty())
+        .and_then(|directives| EnvFilter::try_new(directives).ok())
+        .unwrap_or_else(|| EnvFilter::new(verbosity_filter(verbosity)))
+}
+
+/// Installs the stderr subscriber for the process. Later calls keep the first subscriber.
+pub fn init_logging(verbosity: u8) {
+    let rust_log = std::env::var(EnvFilter::DEFAULT_ENV).ok();
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(log_filter(rust_log.as_deref(), verbosity))
+        .with_writer(io::stderr)
+        .try_init();
+}
@@ -1,4 +1,6 @@
-use off_chain_common::consensus::{keccak256, layout_leaf_hash};
+use off_chain_common::abi::{AbiValue, encode_sequence};
+use off_chain_common::cli::parse_flag_value;
+use off_chain_common::consensus::{circuit_id_from_layout, keccak256, layout_leaf_hash};
 use off_chain_common::garble::garble_circuit;
 use off_chain_common::ih::{
     gc_block_hash, ih_proof_from_hashes, incremental_root_from_hashes, verify_ih_proof,
@@ -7,8 +9,8 @@ use off_chain_common::merkle::{merkle_proof_from_hashes, merkle_root_from_hashes
 use off_chain_common::scenario::{
     CUT_AND_CHOOSE_N, build_millionaires_layout, com_seed, derive_instance_seed,
 };
-use off_chain_common::settlement::default_circuit_id;
 use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
+use serde_json::json;
 
 /// Per-instance artifacts used to print Solidity-ready challenge data.
 #[derive(Debug)]
@@ -100,13 +102,233 @@ fn gate_type_label(g: GateType) -> &'static str {
         GateType::And => "And",
         GateType::Xor => "Xor",
         GateType::Not => "Not",
+        GateType::Or => "Or",
+        GateType::Nand => "Nand",
+        GateType::Nor => "Nor",
     }
 }
 
+/// Hex-encodes each `bytes32` of a list for the JSON vector file.
+fn hex32_list(values: &[[u8; 32]]) -> Vec<String> {
+    values.iter().map(|v| hex32(*v)).collect()
+}
+
+/// ABI `bytes32[]` of `values`.
+fn bytes32_array(values: impl Iterator<Item = [u8; 32]>) -> AbiValue {
+    AbiValue::Array(values.map(AbiValue::Bytes32).collect())
+}
+
+/// Values of one `RustGateChallengeVector`, the struct the Solidity tests declare.
+struct SolidityVector<'a> {
+    circuit_id: [u8; 32],
+    circuit_layout_root: [u8; 32],
+    m: usize,
+    challenge_instance: usize,
+    gate_index: usize,
+    gate: GateDesc,
+    leaf: [u8; 71],
+    instances: &'a [InstanceArtifacts],
+    open_indices: &'a [usize],
+    ih_proof: &'a [[u8; 32]],
+    layout_proof: &'a [[u8; 32]],
+}
+
+impl SolidityVector<'_> {
+    /// `function <name>() internal pure returns (RustGateChallengeVector memory v) { ... }`,
+    /// every non-empty line prefixed with `indent`.
+    fn function(&self, name: &str, indent: &str) -> String {
+        let gate = self.gate;
+        let mut lines = vec![
+            format!(
+                "function {name}() internal pure returns (RustGateChallengeVector memory v) {{"
+            ),
+            format!(
+                "    v.circuitId = {};",
+                solidity_hex_literal(&self.circuit_id)
+            ),
+            format!(
+                "    v.circuitLayoutRoot = {};",
+                solidity_hex_literal(&self.circuit_layout_root)
+            ),
+            String::new(),
+            format!("    v.mChoice = {};", self.m),
+            format!("    v.challengeInstanceId = {};", self.challenge_instance),
+            format!("    v.gateIndex = {};", self.gate_index),
+            format!(
+                "    v.gateType = {}; // {}",
+                gate.gate_type as u8,
+                gate_type_label(gate.gate_type).to_uppercase()
+            ),
+            format!("    v.wireA = {};", gate.wire_a),
+            format!("    v.wireB = {};", gate.wire_b),
+            format!("    v.wireC = {};", gate.wire_c),
+            "    v.expectMatch = true;".to_string(),
+            String::new(),
+            format!("    v.leafBytes = {};", solidity_hex_literal(&self.leaf)),
+            String::new(),
+        ];
+
+        lines.push(format!(
+            "    v.comSeeds = new bytes32[]({});",
+            self.instances.len()
+        ));
+        for a in self.instances {
+            lines.push(format!(
+                "    v.comSeeds[{}] = {};",
+                a.instance_id,
+                solidity_hex_literal(&a.com_seed)
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push(format!(
+            "    v.rootGCs = new bytes32[]({});",
+            self.instances.len()
+        ));
+        for a in self.instances {
+            lines.push(format!(
+                "    v.rootGCs[{}] = {};",
+                a.instance_id,
+                solidity_hex_literal(&a.root_gc)
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push(format!(
+            "    v.openIndices = new uint256[]({});",
+            self.open_indices.len()
+        ));
+        for (i, idx) in self.open_indices.iter().enumerate() {
+            lines.push(format!("    v.openIndices[{i}] = {idx};"));
+        }
+        lines.push(String::new());
+
+        lines.push(format!(
+            "    v.openSeeds = new bytes32[]({});",
+            self.open_indices.len()
+        ));
+        for (i, idx) in self.open_indices.iter().enumerate() {
+            lines.push(format!(
+                "    v.openSeeds[{i}] = {};",
+                solidity_hex_literal(&self.instances[*idx].seed)
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push(format!(
+            "    v.ihProof = new bytes32[]({});",
+            self.ih_proof.len()
+        ));
+        for (i, hash) in self.ih_proof.iter().enumerate() {
+            lines.push(format!(
+                "    v.ihProof[{i}] = {};",
+                solidity_hex_literal(hash)
+            ));
+        }
+        lines.push(String::new());
+
+        lines.push(format!(
+            "    v.layoutProof = new bytes32[]({});",
+            self.layout_proof.len()
+        ));
+        for (i, hash) in self.layout_proof.iter().enumerate() {
+            lines.push(format!(
+                "    v.layoutProof[{i}] = {};",
+                solidity_hex_literal(hash)
+            ));
+        }
+        lines.push("}".to_string());
+
+        lines
+            .iter()
+            .map(|line| {
+                if line.is_empty() {
+                    "\n".to_string()
+                } else {
+                    format!("{indent}{line}\n")
+                }
+            })
+            .collect()
+    }
+
+    /// `abi.encode(v)` of the vector, decodable with `abi.decode(out, (RustGateChallengeVector))`.
+    fn abi_encode(&self) -> Vec<u8> {
+        let uint = |value: usize| AbiValue::Uint(value as u128);
+        let vector = AbiValue::Tuple(vec![
+            AbiValue::Bytes32(self.circuit_id),
+            AbiValue::Bytes32(self.circuit_layout_root),
+            uint(self.m),
+            uint(self.challenge_instance),
+            uint(self.gate_index),
+            AbiValue::Uint(u128::from(self.gate.gate_type as u8)),
+            AbiValue::Uint(self.gate.wire_a.into()),
+            AbiValue::Uint(self.gate.wire_b.into()),
+            AbiValue::Uint(self.gate.wire_c.into()),
+            AbiValue::Bytes(self.leaf.to_vec()),
+            bytes32_array(self.instances.iter().map(|a| a.com_seed)),
+            bytes32_array(self.instances.iter().map(|a| a.root_gc)),
+            AbiValue::Array(self.open_indices.iter().copied().map(uint).collect()),
+            bytes32_array(self.open_indices.iter().map(|i| self.instances[*i].seed)),
+            bytes32_array(self.ih_proof.iter().copied()),
+            bytes32_array(self.layout_proof.iter().copied()),
+            // expectMatch
+            AbiValue::Uint(1),
+        ]);
+        encode_sequence(&[vector])
+    }
+
+    /// Self-contained fixture file: `library <library>` with the vector struct and `function`,
+    /// headed by the command that regenerates it.
+    fn fixture_file(&self, library: &str, name: &str, command: &str) -> String {
+        format!(
+            "// SPDX-License-Identifier: MIT\n\
+             // Generated by `{command}`; regenerate instead of editing.\n\
+             pragma solidity ^0.8.20;\n\
+             \n\
+             library {library} {{\n\
+             {VECTOR_STRUCT}\n\
+             {}}}\n",
+            self.function(name, "    ")
+        )
+    }
+}
+
+/// `RustGateChallengeVector` as declared in `contract/test/MillionairesProblem.t.sol`,
+/// indented for a library body.
+const VECTOR_STRUCT: &str = "    struct RustGateChallengeVector {
+        bytes32 circuitId;
+        bytes32 circuitLayoutRoot;
+        uint256 mChoice;
+        uint256 challengeInstanceId;
+        uint256 gateIndex;
+        uint8 gateType;
+        uint16 wireA;
+        uint16 wireB;
+        uint16 wireC;
+        bytes leafBytes;
+        bytes32[] comSeeds;
+        bytes32[] rootGCs;
+        uint256[] openIndices;
+        bytes32[] openSeeds;
+        bytes32[] ihProof;
+        bytes32[] layoutProof;
+        bool expectMatch;
+    }
+";
+
+/// Library name of `--format sol` fixture files unless `--library` is given.
+const DEFAULT_FIXTURE_LIBRARY: &str = "RustGateVectors";
+
 /// CLI entrypoint that generates:
 /// - phase-2 commitments for `N=10`,
 /// - phase-4 openings (`N-1` seeds),
 /// - one `challengeGateLeaf` packet (leaf + proofs) for a selected gate.
+///
+/// Prints them as text with a Solidity paste snippet, or with `--format json` as one vector
+/// file (to `--out <path>` or stdout) for Foundry's `vm.parseJson` and other consumers. With
+/// `--format sol` the snippet becomes a fixture library file (`--library`, `--fn-name`). With
+/// `--format abi` the only output is one `0x` line, the ABI-encoded vector, for Foundry tests
+/// that run the generator through `vm.ffi` and `abi.decode` its result.
 #[tokio::main]
 async fn main() {
     // CLI knobs for reproducible vector generation.
@@ -116,18 +338,33 @@ async fn main() {
     let m = parse_usize_arg(&args, "--m", 7);
     let gate_index = parse_usize_arg(&args, "--gate-index", 3);
     let challenge_instance_arg = parse_usize_arg(&args, "--challenge-instance", usize::MAX);
+    let format = parse_flag_value(&args, "--format").unwrap_or_else(|| "text".to_string());
+    let out = parse_flag_value(&args, "--out");
+    let fn_name_arg = parse_flag_value(&args, "--fn-name");
+    let library =
+        parse_flag_value(&args, "--library").unwrap_or_else(|| DEFAULT_FIXTURE_LIBRARY.into());
+    assert!(
+        matches!(format.as_str(), "text" | "json" | "sol" | "abi"),
+        "format must be text, json, sol or abi"
+    );
+    assert!(
+        out.is_none() || matches!(format.as_str(), "json" | "sol"),
+        "--out requires --format json or sol"
+    );
 
-    let n = CUT_AND_CHOOSE_N;
+    let n = parse_usize_arg(&args, "--n", CUT_AND_CHOOSE_N);
+    assert!(n >= 2, "n must be >= 2");
     assert!(m < n, "m must be in [0, N)");
 
     assert!(
         winner_formula <= 1,
         "winner-formula must be 0 (HigherBidWins) or 1 (LowerBidWins)"
     );
-    let circuit_id = default_circuit_id(bit_width, winner_formula);
     let master_seed = keccak256(&[b"master-seed-v1"]);
     // Deterministic layout so Solidity/Rust vectors are stable across runs.
-    let gates = build_millionaires_layout(bit_width);
+    let gates = build_millionaires_layout(bit_width).gates;
+    // Circuit id commits to the winner formula and the exact gate list being garbled.
+    let circuit_id = circuit_id_from_layout(&gates, winner_formula);
     assert!(
         gate_index < gates.len(),
         "gate_index={} out of range; layout has {} gates",
@@ -147,11 +384,7 @@ async fn main() {
     let instances: Vec<InstanceArtifacts> = (0..n)
         .map(|instance_id| {
             let seed = derive_instance_seed(master_seed, circuit_id, instance_id as u64);
-            let layout = CircuitLayout {
-                circuit_id,
-                instance_id: instance_id as u64,
-                gates: gates.clone(),
-            };
+            let layout = CircuitLayout::new(circuit_id, instance_id as u64, gates.clone());
             // One full GC table (all leaves) per instance.
             let leaves = garble_circuit(seed, &layout);
             let block_hashes: Vec<[u8; 32]> = leaves
@@ -199,6 +432,115 @@ async fn main() {
     let proof_ok = verify_ih_proof(block_hash_value, &ih_proof, inst.root_gc);
     let layout_proof_ok = verify_proof(layout_leaf, &layout_proof, circuit_layout_root);
 
+    // Direct copy-paste helper for Solidity tests, or a whole fixture file with `--format sol`.
+    let fn_name = fn_name_arg.unwrap_or_else(|| {
+        format!(
+            "_rustVectorDefault{}Gate{}",
+            gate_type_label(gate.gate_type),
+            gate_index
+        )
+    });
+    let vector = SolidityVector {
+        circuit_id,
+        circuit_layout_root,
+        m,
+        challenge_instance,
+        gate_index,
+        gate,
+        leaf,
+        instances: &instances,
+        open_indices: &open_indices,
+        ih_proof: &ih_proof,
+        layout_proof: &layout_proof,
+    };
+
+    if format == "abi" {
+        // `vm.ffi` decodes a `0x` hex line into bytes; nothing else may reach stdout.
+        println!("{}", hex_prefixed(&vector.abi_encode()));
+        return;
+    }
+
+    if format == "sol" {
+        let command = std::iter::once("off-chain-common")
+            .chain(args.iter().skip(1).map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let text = vector.fixture_file(&library, &fn_name, &command);
+        match out {
+            Some(path) => {
+                std::fs::write(&path, text)
+                    .unwrap_or_else(|e| panic!("failed to write {path}: {e}"));
+                println!("out={path}");
+                println!("library={library}");
+                println!("function={fn_name}");
+            }
+            None => print!("{text}"),
+        }
+        return;
+    }
+
+    if format == "json" {
+        // Same values as the text output, keyed like the Solidity vector struct.
+        let vectors = json!({
+            "n": n,
+            "bitWidth": bit_width,
+            "gateCount": gates.len(),
+            "winnerFormula": winner_formula,
+            "mChoice": m,
+            "masterSeed": hex32(master_seed),
+            "circuitId": hex32(circuit_id),
+            "circuitLayoutRoot": hex32(circuit_layout_root),
+            "commitments": instances
+                .iter()
+                .map(|a| json!({
+                    "instanceId": a.instance_id,
+                    "comSeed": hex32(a.com_seed),
+                    "rootGC": hex32(a.root_gc),
+                }))
+                .collect::<Vec<_>>(),
+            "openings": {
+                "indices": open_indices,
+                "seeds": open_indices
+                    .iter()
+                    .map(|idx| hex32(instances[*idx].seed))
+                    .collect::<Vec<_>>(),
+            },
+            "challenge": {
+                "instanceId": challenge_instance,
+                "gateIndex": gate_index,
+                "gate": {
+                    "gateType": gate.gate_type as u8,
+                    "wireA": gate.wire_a,
+                    "wireB": gate.wire_b,
+                    "wireC": gate.wire_c,
+                },
+                "leafBytes": hex_prefixed(&leaf),
+                "leafHash": hex32(block_hash_value),
+                "ihProof": hex32_list(&ih_proof),
+                "layoutLeaf": hex32(layout_leaf),
+                "layoutProof": hex32_list(&layout_proof),
+            },
+            "expectedRoots": {
+                "circuitLayoutRoot": hex32(circuit_layout_root),
+                "rootGCs": instances.iter().map(|a| hex32(a.root_gc)).collect::<Vec<_>>(),
+            },
+            "proofSanity": {
+                "gcIhProofValid": proof_ok,
+                "layoutProofValid": layout_proof_ok,
+            },
+        });
+        let text = serde_json::to_string_pretty(&vectors).expect("JSON encoding");
+        match out {
+            Some(path) => {
+                std::fs::write(&path, format!("{text}\n"))
+                    .unwrap_or_else(|e| panic!("failed to write {path}: {e}"));
+                println!("out={path}");
+            }
+            None => println!("{text}"),
+        }
+        return;
+    }
+
     println!("=== Cut-and-Choose Snapshot ===");
     println!("N = {}", n);
     println!("bitWidth = {}", bit_width);
@@ -256,85 +598,7 @@ async fn main() {
     println!("gcIhProofValid = {}", proof_ok);
     println!("layoutProofValid = {}", layout_proof_ok);
 
-    // Direct copy-paste helper for Solidity tests.
-    let fn_name = format!(
-        "_rustVectorDefault{}Gate{}",
-        gate_type_label(gate.gate_type),
-        gate_index
-    );
     println!();
     println!("=== Solidity Paste Snippet ===");
-    println!(
-        "function {}() internal pure returns (RustGateChallengeVector memory v) {{",
-        fn_name
-    );
-    println!("    v.circuitId = {};", solidity_hex_literal(&circuit_id));
-    println!(
-        "    v.circuitLayoutRoot = {};",
-        solidity_hex_literal(&circuit_layout_root)
-    );
-    println!();
-    println!("    v.mChoice = {};", m);
-    println!("    v.challengeInstanceId = {};", challenge_instance);
-    println!("    v.gateIndex = {};", gate_index);
-    println!(
-        "    v.gateType = {}; // {}",
-        gate.gate_type as u8,
-        gate_type_label(gate.gate_type).to_uppercase()
-    );
-    println!("    v.wireA = {};", gate.wire_a);
-    println!("    v.wireB = {};", gate.wire_b);
-    println!("    v.wireC = {};", gate.wire_c);
-    println!("    v.expectMatch = true;");
-    println!();
-    println!("    v.leafBytes = {};", solidity_hex_literal(&leaf));
-    println!();
-
-    println!("    v.comSeeds = new bytes32[]({});", instances.len());
-    for a in &instances {
-        println!(
-            "    v.comSeeds[{}] = {};",
-            a.instance_id,
-            solidity_hex_literal(&a.com_seed)
-        );
-    }
-    println!();
-
-    println!("    v.rootGCs = new bytes32[]({});", instances.len());
-    for a in &instances {
-        println!(
-            "    v.rootGCs[{}] = {};",
-            a.instance_id,
-            solidity_hex_literal(&a.root_gc)
-        );
-    }
-    println!();
-
-    println!("    v.openIndices = new uint256[]({});", open_indices.len());
-    for (i, idx) in open_indices.iter().enumerate() {
-        println!("    v.openIndices[{}] = {};", i, idx);
-    }
-    println!();
-
-    println!("    v.openSeeds = new bytes32[]({});", open_indices.len());
-    for (i, idx) in open_indices.iter().enumerate() {
-        println!(
-            "    v.openSeeds[{}] = {};",
-            i,
-            solidity_hex_literal(&instances[*idx].seed)
-        );
-    }
-    println!();
-
-    println!("    v.ihProof = new bytes32[]({});", ih_proof.len());
-    for (i, hash) in ih_proof.iter().enumerate() {
-        println!("    v.ihProof[{}] = {};", i, solidity_hex_literal(hash));
-    }
-    println!();
-
-    println!("    v.layoutProof = new bytes32[]({});", layout_proof.len());
-    for (i, hash) in layout_proof.iter().enumerate() {
-        println!("    v.layoutProof[{}] = {};", i, solidity_hex_literal(hash));
-    }
-    println!("}}");
+    print!("{}", vector.function(&fn_name, ""));
 }
@@ -0,0 +1,503 @@
+//! `manifest.json`: a machine-readable index of an artifact directory (Alice's export or eval
+//! dir) with the role, byte length and keccak256 of every file, so either party can tell a
+//! complete, untouched artifact set from a corrupted or edited one.
+//!
+//! The manifest is canonical JSON (`canonical_json`). Lengths and digests cover the bytes as
+//! stored, so a `.zst` file is checked in its compressed form. `verify_manifest` works on
+//! directories and on directories inside a `bundle`. A digest only shows the files are the
+//! ones the manifest was written for; `verify_artifacts` additionally compares them with
+//! contents the producer re-derives from its seeds.
+//!
+//! Alice can sign the manifest: `manifest.sig.json` holds her EIP-191 `personal_sign` signature
+//! over `keccak256("PPA-MANIFEST-SIG-V1" || contract || keccak256(manifest.json))`. Since the
+//! manifest pins every file's digest, a signature that recovers to the contract's `alice()`
+//! makes the whole directory attributable to her, e.g. as evidence in a dispute.
+
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::bundle::{Bundle, locate_in_bundle};
+use crate::canonical_json::{from_canonical_json, hex_bytes, to_canonical_json};
+use crate::cli::hex_prefixed;
+use crate::compression::{ZSTD_SUFFIX, decode_artifact};
+use crate::consensus::keccak256;
+use crate::identity::{EthIdentity, recover_personal_sign};
+use crate::transport::list_dir_files;
+
+/// Manifest file name inside an artifact directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Written into every manifest; bumped on incompatible changes.
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// Signature file next to `MANIFEST_FILE`.
+pub const MANIFEST_SIGNATURE_FILE: &str = "manifest.sig.json";
+
+/// What an artifact file holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtifactRole {
+    Seed,
+    ComSeed,
+    RootGc,
+    Leaves,
+    EvalBlob,
+    RootOt,
+    OtPayloads,
+    EvalPackage,
+    EvalMeta,
+    AliceLabels16,
+    AliceLabels32,
+    OtSetup,
+    NotHints,
+    LayoutVersion,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestFile {
+    /// File name relative to the manifest's directory.
+    pub name: String,
+    pub role: ArtifactRole,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance_id: Option<u64>,
+    pub length: u64,
+    #[serde(with = "hex_bytes")]
+    pub keccak256: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ArtifactManifest {
+    pub version: u32,
+    /// Registry name of the circuit.
+    pub circuit: String,
+    /// `SchemeId` byte.
+    pub scheme: u8,
+    #[serde(with = "hex_bytes")]
+    pub circuit_id: [u8; 32],
+    pub bit_width: u16,
+    pub instances: Vec<ManifestInstance>,
+    pub files: Vec<ManifestFile>,
+}
+
+/// Per-instance values that are not themselves files.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestInstance {
+    pub instance_id: u64,
+    /// `blobHashGC` of the instance's eval blob.
+    #[serde(with = "hex_bytes")]
+    pub blob_hash_gc: [u8; 32],
+    /// IPFS CID of the eval blob once `publish-ipfs` has pinned it (`ipfs::Cid`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cid: Option<String>,
+}
+
+impl ArtifactManifest {
+    pub fn new(circuit: &str, scheme: u8, circuit_id: [u8; 32], bit_width: u16) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            circuit: circuit.to_string(),
+            scheme,
+            circuit_id,
+            bit_width,
+            instances: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Records the file `dir/name` as it is on disk now.
+    pub fn add_file(
+        &mut self,
+        dir: &Path,
+        name: &str,
+        role: ArtifactRole,
+        instance_id: Option<u64>,
+    ) -> Result<(), String> {
+        let bytes = read_stored(&dir.join(name))?;
+        self.files.push(ManifestFile {
+            name: name.to_string(),
+            role,
+            instance_id,
+            length: bytes.len() as u64,
+            keccak256: keccak256(&[&bytes]),
+        });
+        Ok(())
+    }
+
+    pub fn file(&self, name: &str) -> Option<&ManifestFile> {
+        self.files.iter().find(|file| file.name == name)
+    }
+
+    pub fn instance(&self, instance_id: u64) -> Option<&ManifestInstance> {
+        self.instances
+            .iter()
+            .find(|instance| instance.instance_id == instance_id)
+    }
+
+    /// The recorded file of `role` for `instance_id`, e.g. its eval blob.
+    pub fn instance_file(&self, instance_id: u64, role: ArtifactRole) -> Option<&ManifestFile> {
+        self.files
+            .iter()
+            .find(|file| file.role == role && file.instance_id == Some(instance_id))
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        to_canonical_json(self)
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let manifest: Self = from_canonical_json(raw)?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(format!(
+                "unsupported manifest version {}, expected {MANIFEST_VERSION}",
+                manifest.version
+            ));
+        }
+        Ok(manifest)
+    }
+
+    /// Writes `dir/manifest.json` with instances sorted by id and files by name.
+    pub fn write(&mut self, dir: &Path) -> Result<(), String> {
+        self.instances.sort_by_key(|instance| instance.instance_id);
+        self.files.sort_by(|a, b| a.name.cmp(&b.name));
+        let path = dir.join(MANIFEST_FILE);
+        fs::write(&path, self.to_json()?)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+
+    pub fn read(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_FILE);
+        let raw = String::from_utf8(read_stored(&path)?)
+            .map_err(|_| format!("{} is not valid UTF-8", path.display()))?;
+        Self::from_json(&raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+/// How one file differs from the manifest (or from its re-derived contents).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ManifestMismatch {
+    Missing {
+        name: String,
+    },
+    Length {
+        name: String,
+        expected: u64,
+        actual: u64,
+    },
+    Digest {
+        name: String,
+    },
+    /// Decompressed contents differ from the re-derived ones from byte `offset` on.
+    Content {
+        name: String,
+        offset: u64,
+    },
+    /// A re-derived file the manifest does not list.
+    Unrecorded {
+        name: String,
+    },
+    /// The manifest's `blobHashGC` of an instance is absent or differs from the re-derived one.
+    BlobHash {
+        instance_id: u64,
+    },
+}
+
+impl ManifestMismatch {
+    /// The file concerned, if any.
+    pub fn file_name(&self) -> Option<&str> {
+        match self {
+            Self::Missing { name }
+            | Self::Length { name, .. }
+            | Self::Digest { name }
+            | Self::Content { name, .. }
+            | Self::Unrecorded { name } => Some(name),
+            Self::BlobHash { .. } => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ManifestMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing { name } => write!(f, "{name}: missing"),
+            Self::Length {
+                name,
+                expected,
+                actual,
+            } => write!(f, "{name}: length {actual}, manifest has {expected}"),
+            Self::Digest { name } => write!(f, "{name}: keccak256 does not match"),
+            Self::Content { name, offset } => {
+                write!(
+                    f,
+                    "{name}: differs from the re-derived contents at byte {offset}"
+                )
+            }
+            Self::Unrecorded { name } => write!(f, "{name}: re-derived but not in the manifest"),
+            Self::BlobHash { instance_id } => write!(
+                f,
+                "instance {instance_id}: blobHashGC differs from the re-derived eval blob"
+            ),
+        }
+    }
+}
+
+/// Outcome of `verify_manifest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestReport {
+    pub manifest: ArtifactManifest,
+    /// Keccak256 of the manifest file itself.
+    pub manifest_hash: [u8; 32],
+    pub mismatches: Vec<ManifestMismatch>,
+    /// Files next to the manifest that it does not list, such as Bob's OT choices in an eval
+    /// dir. Reported, but not a mismatch.
+    pub unlisted: Vec<String>,
+}
+
+impl ManifestReport {
+    pub fn is_intact(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+}
+
+/// Checks every file listed in `dir/manifest.json` against its length and digest, and for a
+/// plain directory collects the files it does not list. Fails only if the manifest itself
+/// cannot be read.
+pub fn verify_manifest(dir: &Path) -> Result<ManifestReport, String> {
+    let manifest_bytes = read_stored(&dir.join(MANIFEST_FILE))?;
+    let manifest = ArtifactManifest::read(dir)?;
+    let mut mismatches = Vec::new();
+    for file in &manifest.files {
+        let name = file.name.clone();
+        let Ok(bytes) = read_stored(&dir.join(&file.name)) else {
+            mismatches.push(ManifestMismatch::Missing { name });
+            continue;
+        };
+        if bytes.len() as u64 != file.length {
+            mismatches.push(ManifestMismatch::Length {
+                name,
+                expected: file.length,
+                actual: bytes.len() as u64,
+            });
+        } else if keccak256(&[&bytes]) != file.keccak256 {
+            mismatches.push(ManifestMismatch::Digest { name });
+        }
+    }
+    let unlisted = if dir.is_dir() {
+        list_dir_files(dir)?
+            .into_iter()
+            .filter(|name| {
+                name != MANIFEST_FILE
+                    && name != MANIFEST_SIGNATURE_FILE
+                    && manifest.file(name).is_none()
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(ManifestReport {
+        manifest,
+        manifest_hash: keccak256(&[&manifest_bytes]),
+        mismatches,
+        unlisted,
+    })
+}
+
+/// An artifact as its producer re-derives it: the plain file name (without `.zst`) and the
+/// uncompressed contents.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpectedArtifact {
+    pub name: String,
+    pub bytes: Vec<u8>,
+}
+
+/// `verify_manifest`, then compares each re-derived file that passed its digest check
+/// byte-for-byte (after decryption and decompression) and each instance's `blobHashGC` with
+/// `instances`. Manifest files without a counterpart in `expected` are only digest-checked.
+pub fn verify_artifacts(
+    dir: &Path,
+    expected: &[ExpectedArtifact],
+    instances: &[ManifestInstance],
+) -> Result<ManifestReport, String> {
+    let mut report = verify_manifest(dir)?;
+    let failed = report
+        .mismatches
+        .iter()
+        .filter_map(|mismatch| mismatch.file_name().map(str::to_string))
+        .collect::<Vec<_>>();
+    for artifact in expected {
+        let compressed = format!("{}{ZSTD_SUFFIX}", artifact.name);
+        let Some(file) = report
+            .manifest
+            .file(&artifact.name)
+            .or_else(|| report.manifest.file(&compressed))
+        else {
+            report.mismatches.push(ManifestMismatch::Unrecorded {
+                name: artifact.name.clone(),
+            });
+            continue;
+        };
+        if failed.contains(&file.name) {
+            continue;
+        }
+        let stored = read_stored(&dir.join(&file.name))?;
+        let actual = decode_artifact(&stored).map_err(|e| format!("{}: {e}", file.name))?;
+        if actual != artifact.bytes {
+            let offset = actual
+                .iter()
+                .zip(&artifact.bytes)
+                .position(|(a, b)| a != b)
+                .unwrap_or(actual.len().min(artifact.bytes.len()));
+            report.mismatches.push(ManifestMismatch::Content {
+                name: file.name.clone(),
+                offset: offset as u64,
+            });
+        }
+    }
+    for instance in instances {
+        // A CID recorded by `publish-ipfs` is not re-derived, only the blob hash.
+        let recorded = report.manifest.instance(instance.instance_id);
+        if recorded.map(|recorded| recorded.blob_hash_gc) != Some(instance.blob_hash_gc) {
+            report.mismatches.push(ManifestMismatch::BlobHash {
+                instance_id: instance.instance_id,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Digest Alice signs for a manifest: its keccak256 bound to the auction contract, so the
+/// signature cannot be presented for another session.
+pub fn manifest_signing_digest(contract: [u8; 20], manifest_hash: [u8; 32]) -> [u8; 32] {
+    keccak256(&[b"PPA-MANIFEST-SIG-V1", &contract, &manifest_hash])
+}
+
+/// Contents of `manifest.sig.json`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestSignature {
+    pub version: u32,
+    /// Auction contract the artifacts belong to.
+    #[serde(with = "hex_bytes")]
+    pub contract: [u8; 20],
+    /// Keccak256 of `manifest.json` as stored.
+    #[serde(with = "hex_bytes")]
+    pub manifest_hash: [u8; 32],
+    /// Claimed signer; informational, `verify` recovers the address from `signature`.
+    #[serde(with = "hex_bytes")]
+    pub signer: [u8; 20],
+    /// 65-byte `r || s || v`.
+    #[serde(with = "hex_bytes")]
+    pub signature: [u8; 65],
+}
+
+impl ManifestSignature {
+    pub fn sign(
+        contract: [u8; 20],
+        manifest_hash: [u8; 32],
+        identity: &EthIdentity,
+    ) -> Result<Self, String> {
+        let signature = identity.personal_sign(manifest_signing_digest(contract, manifest_hash))?;
+        Ok(Self {
+            version: MANIFEST_VERSION,
+            contract,
+            manifest_hash,
+            signer: identity.address(),
+            signature,
+        })
+    }
+
+    /// Address that produced `signature`.
+    pub fn recover(&self) -> Result<[u8; 20], String> {
+        recover_personal_sign(
+            manifest_signing_digest(self.contract, self.manifest_hash),
+            &self.signature,
+        )
+    }
+
+    /// Succeeds once the signature covers `manifest_hash` for `contract` and recovers to
+    /// `expected_signer`.
+    pub fn verify(
+        &self,
+        contract: [u8; 20],
+        manifest_hash: [u8; 32],
+        expected_signer: [u8; 20],
+    ) -> Result<(), String> {
+        if self.contract != contract {
+            return Err(format!(
+                "manifest is signed for contract {}, expected {}",
+                hex_prefixed(&self.contract),
+                hex_prefixed(&contract)
+            ));
+        }
+        if self.manifest_hash != manifest_hash {
+            return Err(format!(
+                "signature covers manifest {}, but manifest.json hashes to {}",
+                hex_prefixed(&self.manifest_hash),
+                hex_prefixed(&manifest_hash)
+            ));
+        }
+        let signer = self.recover()?;
+        if signer != expected_signer {
+            return Err(format!(
+                "manifest is signed by {}, expected {}",
+                hex_prefixed(&signer),
+                hex_prefixed(&expected_signer)
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        to_canonical_json(self)
+    }
+
+    pub fn from_json(raw: &str) -> Result<Self, String> {
+        let signature: Self = from_canonical_json(raw)?;
+        if signature.version != MANIFEST_VERSION {
+            return Err(format!(
+                "unsupported manifest signature version {}, expected {MANIFEST_VERSION}",
+                signature.version
+            ));
+        }
+        Ok(signature)
+    }
+
+    pub fn write(&self, dir: &Path) -> Result<(), String> {
+        let path = dir.join(MANIFEST_SIGNATURE_FILE);
+        fs::write(&path, self.to_json()?)
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+
+    pub fn read(dir: &Path) -> Result<Self, String> {
+        let path = dir.join(MANIFEST_SIGNATURE_FILE);
+        let raw = String::from_utf8(read_stored(&path)?)
+            .map_err(|_| format!("{} is not valid UTF-8", path.display()))?;
+        Self::from_json(&raw).map_err(|e| format!("{}: {e}", path.display()))
+    }
+}
+
+/// Signs `dir/manifest.json` as stored and writes `dir/manifest.sig.json`.
+pub fn sign_manifest(
+    dir: &Path,
+    contract: [u8; 20],
+    identity: &EthIdentity,
+) -> Result<ManifestSignature, String> {
+    let manifest_hash = keccak256(&[&read_stored(&dir.join(MANIFEST_FILE))?]);
+    let signature = ManifestSignature::sign(contract, manifest_hash, identity)?;
+    signature.write(dir)?;
+    Ok(signature)
+}
+
+/// Stored bytes of `path`, which may lie inside a bundle; never decompressed.
+fn read_stored(path: &Path) -> Result<Vec<u8>, String> {
+    if !path.exists()
+        && let Some((bundle, name)) = locate_in_bundle(path)
+    {
+        return Bundle::open(&bundle)?.read(&name);
+    }
+    fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))
+}
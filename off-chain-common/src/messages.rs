@@ -0,0 +1,639 @@
+//! Versioned off-chain protocol messages with a CBOR encoding, so Alice, Bob and other
+//! tooling exchange one wire format instead of directories of text files.
+//!
+//! Every message is the CBOR array `[version, kind, body]` where `body` is an array of the
+//! struct fields in declaration order: hashes, labels and leaves are byte strings, counters
+//! are unsigned integers and absent optionals are `null`. On a stream each message is framed
+//! with `transport::write_length_prefixed`.
+//!
+//! A `SignedMessage` wraps the encoded message with the sender's Ethereum signature and the
+//! auction contract address. The receiver checks the signer against the contract's `alice()`
+//! or `isBuyer(..)`, so a stored artifact is attributable evidence and cannot be spoofed by
+//! whoever relays it.
+
+use std::io::{Read, Write};
+
+use ciborium::value::Value;
+use serde::{Deserialize, Serialize};
+
+use crate::canonical_json::{hex_bytes, hex_bytes_vec};
+use crate::cli::hex_prefixed;
+use crate::consensus::keccak256;
+use crate::decoding::OutputDecodingTable;
+use crate::evaluation::NotGateHint;
+use crate::identity::{EthIdentity, recover_personal_sign};
+use crate::protocol::Party;
+use crate::transport::{read_length_prefixed, write_length_prefixed};
+use crate::types::{GateDesc, GateType};
+
+/// Wire format version written into every message.
+pub const MESSAGE_VERSION: u64 = 1;
+
+/// Envelope kind of a `SignedMessage`.
+pub const SIGNED_KIND: u64 = 8;
+
+/// On-chain `InstanceCommitment` of one garbled instance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstanceCommitment {
+    #[serde(with = "hex_bytes")]
+    pub com_seed: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub root_gc: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub blob_hash_gc: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub h_out: [u8; 32],
+}
+
+/// Alice's commitments for all `n` instances, indexed by instance id.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CommitmentsMessage {
+    #[serde(with = "hex_bytes")]
+    pub circuit_id: [u8; 32],
+    pub instances: Vec<InstanceCommitment>,
+}
+
+/// What Bob needs to evaluate instance `m`, except his own y-labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalPackageMessage {
+    pub bit_width: u16,
+    pub decoding: OutputDecodingTable,
+    pub gc_leaves: Vec<[u8; 71]>,
+    pub alice_labels: Vec<[u8; 16]>,
+    pub not_hints: Vec<NotGateHint>,
+    /// Base-OT setup point for Bob's y-labels (`ot-setup.txt`).
+    pub ot_setup: Option<[u8; 33]>,
+}
+
+/// `[row0, row1]` per y-wire starting at `first_wire`: plain label offers, or OT-encrypted
+/// rows when `encrypted` (`bob-y-ot.txt`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LabelOfferMessage {
+    pub instance_id: u64,
+    pub first_wire: u16,
+    pub encrypted: bool,
+    pub rows: Vec<[[u8; 16]; 2]>,
+}
+
+/// Evidence for `disputeGarbledTable` on one opened instance.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisputePacketMessage {
+    #[serde(with = "hex_bytes")]
+    pub circuit_id: [u8; 32],
+    pub instance_id: u64,
+    #[serde(with = "hex_bytes")]
+    pub seed: [u8; 32],
+    pub gate_index: u64,
+    pub gate: GateDesc,
+    #[serde(with = "hex_bytes")]
+    pub leaf: [u8; 71],
+    #[serde(with = "hex_bytes")]
+    pub root_gc: [u8; 32],
+    #[serde(with = "hex_bytes")]
+    pub layout_root: [u8; 32],
+    #[serde(with = "hex_bytes_vec")]
+    pub ih_proof: Vec<[u8; 32]>,
+    #[serde(with = "hex_bytes_vec")]
+    pub layout_proof: Vec<[u8; 32]>,
+}
+
+/// Bisection request from Bob: the IH state after `index` blocks, or with `reveal` the leaf
+/// at gate `index` and its IH proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BisectionQueryMessage {
+    pub instance_id: u64,
+    pub index: u64,
+    pub reveal: bool,
+}
+
+/// Alice's IH state after the first `index` claimed blocks (`IH_index`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CheckpointMessage {
+    pub instance_id: u64,
+    pub index: u64,
+    pub state: [u8; 32],
+}
+
+/// Alice's claimed leaf at `gate_index` with its IH proof against the committed `rootGC`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GateRevealMessage {
+    pub instance_id: u64,
+    pub gate_index: u64,
+    pub leaf: [u8; 71],
+    pub ih_proof: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Commitments(CommitmentsMessage),
+    EvalPackage(EvalPackageMessage),
+    LabelOffer(LabelOfferMessage),
+    DisputePacket(DisputePacketMessage),
+    BisectionQuery(BisectionQueryMessage),
+    Checkpoint(CheckpointMessage),
+    GateReveal(GateRevealMessage),
+}
+
+fn uint(value: u64) -> Value {
+    Value::Integer(value.into())
+}
+
+fn bytes(value: &[u8]) -> Value {
+    Value::Bytes(value.to_vec())
+}
+
+fn list<T>(items: &[T], encode: impl Fn(&T) -> Value) -> Value {
+    Value::Array(items.iter().map(encode).collect())
+}
+
+/// Field cursor over one decoded CBOR array.
+struct Fields<'a> {
+    what: &'static str,
+    items: std::slice::Iter<'a, Value>,
+}
+
+impl<'a> Fields<'a> {
+    fn new(value: &'a Value, what: &'static str, len: usize) -> Result<Self, String> {
+        let items = value
+            .as_array()
+            .ok_or_else(|| format!("{what} must be a CBOR array"))?;
+        if items.len() != len {
+            return Err(format!("{what} has {} fields, expected {len}", items.len()));
+        }
+        Ok(Self {
+            what,
+            items: items.iter(),
+        })
+    }
+
+    fn next(&mut self) -> &'a Value {
+        self.items.next().expect("length checked in Fields::new")
+    }
+
+    fn uint(&mut self) -> Result<u64, String> {
+        decode_uint(self.next(), self.what)
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        decode_bytes(self.next(), self.what)
+    }
+
+    fn list<T>(
+        &mut self,
+        decode: impl Fn(&'a Value, &'static str) -> Result<T, String>,
+    ) -> Result<Vec<T>, String> {
+        let what = self.what;
+        self.next()
+            .as_array()
+            .ok_or_else(|| format!("{what} list must be a CBOR array"))?
+            .iter()
+            .map(|item| decode(item, what))
+            .collect()
+    }
+}
+
+fn decode_uint(value: &Value, what: &str) -> Result<u64, String> {
+    value
+        .as_integer()
+        .and_then(|int| u64::try_from(int).ok())
+        .ok_or_else(|| format!("{what}: expected an unsigned integer"))
+}
+
+fn decode_bytes<const N: usize>(value: &Value, what: &str) -> Result<[u8; N], String> {
+    let raw = value
+        .as_bytes()
+        .ok_or_else(|| format!("{what}: expected a byte string"))?;
+    raw.as_slice()
+        .try_into()
+        .map_err(|_| format!("{what}: expected {N} bytes, got {}", raw.len()))
+}
+
+fn narrow<T: TryFrom<u64>>(value: u64, what: &str) -> Result<T, String> {
+    T::try_from(value).map_err(|_| format!("{what}: value {value} out of range"))
+}
+
+fn commitment_to_value(commitment: &InstanceCommitment) -> Value {
+    list(
+        &[
+            commitment.com_seed,
+            commitment.root_gc,
+            commitment.blob_hash_gc,
+            commitment.h_out,
+        ],
+        |hash| bytes(hash),
+    )
+}
+
+fn commitment_from_value(value: &Value, _: &'static str) -> Result<InstanceCommitment, String> {
+    let mut f = Fields::new(value, "instance commitment", 4)?;
+    Ok(InstanceCommitment {
+        com_seed: f.bytes()?,
+        root_gc: f.bytes()?,
+        blob_hash_gc: f.bytes()?,
+        h_out: f.bytes()?,
+    })
+}
+
+fn hint_to_value(hint: &NotGateHint) -> Value {
+    Value::Array(vec![
+        uint(hint.gate_index as u64),
+        bytes(&hint.in_label0),
+        bytes(&hint.out_if_in0),
+        bytes(&hint.in_label1),
+        bytes(&hint.out_if_in1),
+    ])
+}
+
+fn hint_from_value(value: &Value, _: &'static str) -> Result<NotGateHint, String> {
+    let mut f = Fields::new(value, "NOT-gate hint", 5)?;
+    Ok(NotGateHint {
+        gate_index: narrow(f.uint()?, "gate_index")?,
+        in_label0: f.bytes()?,
+        out_if_in0: f.bytes()?,
+        in_label1: f.bytes()?,
+        out_if_in1: f.bytes()?,
+    })
+}
+
+fn gate_to_value(gate: &GateDesc) -> Value {
+    Value::Array(vec![
+        uint(gate.gate_type as u64),
+        uint(gate.wire_a as u64),
+        uint(gate.wire_b as u64),
+        uint(gate.wire_c as u64),
+    ])
+}
+
+fn gate_from_value(value: &Value) -> Result<GateDesc, String> {
+    let mut f = Fields::new(value, "gate", 4)?;
+    let opcode = f.uint()?;
+    let gate_type = u8::try_from(opcode)
+        .ok()
+        .and_then(GateType::from_u8)
+        .ok_or_else(|| format!("gate: unknown opcode {opcode}"))?;
+    Ok(GateDesc::new(
+        gate_type,
+        narrow(f.uint()?, "wire_a")?,
+        narrow(f.uint()?, "wire_b")?,
+        narrow(f.uint()?, "wire_c")?,
+    ))
+}
+
+impl Message {
+    /// Numeric kind written after the version.
+    pub fn kind(&self) -> u64 {
+        match self {
+            Message::Commitments(_) => 1,
+            Message::EvalPackage(_) => 2,
+            Message::LabelOffer(_) => 3,
+            Message::DisputePacket(_) => 4,
+            Message::BisectionQuery(_) => 5,
+            Message::Checkpoint(_) => 6,
+            Message::GateReveal(_) => 7,
+        }
+    }
+
+    /// Party expected to send this kind: Alice sends commitments, packages, label offers and
+    /// bisection answers; Bob sends dispute packets and bisection queries.
+    pub fn sender(&self) -> Party {
+        match self {
+            Message::DisputePacket(_) | Message::BisectionQuery(_) => Party::Evaluator,
+            _ => Party::Garbler,
+        }
+    }
+
+    fn body(&self) -> Value {
+        match self {
+            Message::Commitments(m) => Value::Array(vec![
+                bytes(&m.circuit_id),
+                list(&m.instances, commitment_to_value),
+            ]),
+            Message::EvalPackage(m) => {
+                let d = &m.decoding;
+                Value::Array(vec![
+                    uint(m.bit_width as u64),
+                    Value::Array(vec![
+                        bytes(&d.circuit_id),
+                        uint(d.instance_id),
+                        uint(d.output_wire as u64),
+                        bytes(&d.h0),
+                        bytes(&d.h1),
+                        bytes(&d.lout_true),
+                        bytes(&d.lout_false),
+                    ]),
+                    list(&m.gc_leaves, |leaf| bytes(leaf)),
+                    list(&m.alice_labels, |label| bytes(label)),
+                    list(&m.not_hints, hint_to_value),
+                    m.ot_setup.map_or(Value::Null, |point| bytes(&point)),
+                ])
+            }
+            Message::LabelOffer(m) => Value::Array(vec![
+                uint(m.instance_id),
+                uint(m.first_wire as u64),
+                Value::Bool(m.encrypted),
+                list(&m.rows, |[row0, row1]| {
+                    Value::Array(vec![bytes(row0), bytes(row1)])
+                }),
+            ]),
+            Message::DisputePacket(m) => Value::Array(vec![
+                bytes(&m.circuit_id),
+                uint(m.instance_id),
+                bytes(&m.seed),
+                uint(m.gate_index),
+                gate_to_value(&m.gate),
+                bytes(&m.leaf),
+                bytes(&m.root_gc),
+                bytes(&m.layout_root),
+                list(&m.ih_proof, |hash| bytes(hash)),
+                list(&m.layout_proof, |hash| bytes(hash)),
+            ]),
+            Message::BisectionQuery(m) => Value::Array(vec![
+                uint(m.instance_id),
+                uint(m.index),
+                Value::Bool(m.reveal),
+            ]),
+            Message::Checkpoint(m) => {
+                Value::Array(vec![uint(m.instance_id), uint(m.index), bytes(&m.state)])
+            }
+            Message::GateReveal(m) => Value::Array(vec![
+                uint(m.instance_id),
+                uint(m.gate_index),
+                bytes(&m.leaf),
+                list(&m.ih_proof, |hash| bytes(hash)),
+            ]),
+        }
+    }
+
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let envelope = Value::Array(vec![uint(MESSAGE_VERSION), uint(self.kind()), self.body()]);
+        let mut out = Vec::new();
+        ciborium::into_writer(&envelope, &mut out).expect("CBOR encoding into a Vec");
+        out
+    }
+
+    /// Decodes one message, rejecting other versions, unknown kinds and trailing bytes.
+    pub fn from_cbor(raw: &[u8]) -> Result<Self, String> {
+        let envelope = parse_envelope(raw)?;
+        let (kind, body) = envelope_parts(&envelope)?;
+        Self::from_body(kind, body)
+    }
+
+    fn from_body(kind: u64, body: &Value) -> Result<Self, String> {
+        match kind {
+            1 => {
+                let mut f = Fields::new(body, "commitments", 2)?;
+                Ok(Message::Commitments(CommitmentsMessage {
+                    circuit_id: f.bytes()?,
+                    instances: f.list(commitment_from_value)?,
+                }))
+            }
+            2 => {
+                let mut f = Fields::new(body, "eval package", 6)?;
+                let bit_width = narrow(f.uint()?, "bit_width")?;
+                let mut d = Fields::new(f.next(), "decoding table", 7)?;
+                let decoding = OutputDecodingTable::from_openings(
+                    d.bytes()?,
+                    d.uint()?,
+                    narrow(d.uint()?, "output_wire")?,
+                    [d.bytes()?, d.bytes()?],
+                    [d.bytes()?, d.bytes()?],
+                );
+                Ok(Message::EvalPackage(EvalPackageMessage {
+                    bit_width,
+                    decoding,
+                    gc_leaves: f.list(decode_bytes)?,
+                    alice_labels: f.list(decode_bytes)?,
+                    not_hints: f.list(hint_from_value)?,
+                    ot_setup: match f.next() {
+                        Value::Null => None,
+                        point => Some(decode_bytes(point, "ot_setup")?),
+                    },
+                }))
+            }
+            3 => {
+                let mut f = Fields::new(body, "label offer", 4)?;
+                Ok(Message::LabelOffer(LabelOfferMessage {
+                    instance_id: f.uint()?,
+                    first_wire: narrow(f.uint()?, "first_wire")?,
+                    encrypted: f
+                        .next()
+                        .as_bool()
+                        .ok_or_else(|| "label offer: expected a bool".to_string())?,
+                    rows: f.list(|row, _| {
+                        let mut r = Fields::new(row, "label offer row", 2)?;
+                        Ok([r.bytes()?, r.bytes()?])
+                    })?,
+                }))
+            }
+            4 => {
+                let mut f = Fields::new(body, "dispute packet", 10)?;
+                Ok(Message::DisputePacket(DisputePacketMessage {
+                    circuit_id: f.bytes()?,
+                    instance_id: f.uint()?,
+                    seed: f.bytes()?,
+                    gate_index: f.uint()?,
+                    gate: gate_from_value(f.next())?,
+                    leaf: f.bytes()?,
+                    root_gc: f.bytes()?,
+                    layout_root: f.bytes()?,
+                    ih_proof: f.list(decode_bytes)?,
+                    layout_proof: f.list(decode_bytes)?,
+                }))
+            }
+            5 => {
+                let mut f = Fields::new(body, "bisection query", 3)?;
+                Ok(Message::BisectionQuery(BisectionQueryMessage {
+                    instance_id: f.uint()?,
+                    index: f.uint()?,
+                    reveal: f
+                        .next()
+                        .as_bool()
+                        .ok_or_else(|| "bisection query: expected a bool".to_string())?,
+                }))
+            }
+            6 => {
+                let mut f = Fields::new(body, "checkpoint", 3)?;
+                Ok(Message::Checkpoint(CheckpointMessage {
+                    instance_id: f.uint()?,
+                    index: f.uint()?,
+                    state: f.bytes()?,
+                }))
+            }
+            7 => {
+                let mut f = Fields::new(body, "gate reveal", 4)?;
+                Ok(Message::GateReveal(GateRevealMessage {
+                    instance_id: f.uint()?,
+                    gate_index: f.uint()?,
+                    leaf: f.bytes()?,
+                    ih_proof: f.list(decode_bytes)?,
+                }))
+            }
+            other => Err(format!("unknown message kind {other}")),
+        }
+    }
+}
+
+fn parse_envelope(raw: &[u8]) -> Result<Value, String> {
+    let mut rest = raw;
+    let envelope: Value =
+        ciborium::from_reader(&mut rest).map_err(|e| format!("invalid CBOR message: {e}"))?;
+    if !rest.is_empty() {
+        return Err(format!("{} trailing bytes after CBOR message", rest.len()));
+    }
+    Ok(envelope)
+}
+
+/// Checks the version of `[version, kind, body]` and returns the kind and body.
+fn envelope_parts(envelope: &Value) -> Result<(u64, &Value), String> {
+    let mut f = Fields::new(envelope, "message envelope", 3)?;
+    let version = f.uint()?;
+    if version != MESSAGE_VERSION {
+        return Err(format!(
+            "unsupported message version {version} (expected {MESSAGE_VERSION})"
+        ));
+    }
+    let kind = f.uint()?;
+    Ok((kind, f.next()))
+}
+
+/// Digest a sender signs: the encoded message bound to the auction contract, so a signed
+/// artifact cannot be replayed against another session.
+pub fn signing_digest(contract: [u8; 20], message_cbor: &[u8]) -> [u8; 32] {
+    keccak256(&[b"PPA-MESSAGE-SIG-V1", &contract, message_cbor])
+}
+
+/// A message with the sender's Ethereum `personal_sign` signature over
+/// `signing_digest(contract, message.to_cbor())`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedMessage {
+    pub message: Message,
+    /// Auction contract the message belongs to.
+    pub contract: [u8; 20],
+    /// 65-byte `r || s || v`.
+    pub signature: [u8; 65],
+}
+
+impl SignedMessage {
+    pub fn sign(
+        message: Message,
+        contract: [u8; 20],
+        identity: &EthIdentity,
+    ) -> Result<Self, String> {
+        let signature = identity.personal_sign(signing_digest(contract, &message.to_cbor()))?;
+        Ok(Self {
+            message,
+            contract,
+            signature,
+        })
+    }
+
+    /// Address that signed the message.
+    pub fn signer(&self) -> Result<[u8; 20], String> {
+        recover_personal_sign(
+            signing_digest(self.contract, &self.message.to_cbor()),
+            &self.signature,
+        )
+    }
+
+    /// Returns the message once it is bound to `contract` and signed by `expected_signer`.
+    pub fn verify(
+        &self,
+        contract: [u8; 20],
+        expected_signer: [u8; 20],
+    ) -> Result<&Message, String> {
+        if self.contract != contract {
+            return Err(format!(
+                "message is signed for contract {}, expected {}",
+                hex_prefixed(&self.contract),
+                hex_prefixed(&contract)
+            ));
+        }
+        let signer = self.signer()?;
+        if signer != expected_signer {
+            return Err(format!(
+                "kind {} message is signed by {}, expected {}",
+                self.message.kind(),
+                hex_prefixed(&signer),
+                hex_prefixed(&expected_signer)
+            ));
+        }
+        Ok(&self.message)
+    }
+
+    /// `[version, SIGNED_KIND, [contract, signature, message]]` with the inner message as its
+    /// own CBOR byte string, so the signed bytes are kept verbatim.
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let body = Value::Array(vec![
+            bytes(&self.contract),
+            bytes(&self.signature),
+            bytes(&self.message.to_cbor()),
+        ]);
+        let envelope = Value::Array(vec![uint(MESSAGE_VERSION), uint(SIGNED_KIND), body]);
+        let mut out = Vec::new();
+        ciborium::into_writer(&envelope, &mut out).expect("CBOR encoding into a Vec");
+        out
+    }
+
+    pub fn from_cbor(raw: &[u8]) -> Result<Self, String> {
+        match Envelope::from_cbor(raw)? {
+            Envelope::Signed(signed) => Ok(signed),
+            Envelope::Plain(message) => {
+                Err(format!("kind {} message is not signed", message.kind()))
+            }
+        }
+    }
+}
+
+/// A decoded message file or frame, signed or not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Envelope {
+    Plain(Message),
+    Signed(SignedMessage),
+}
+
+impl Envelope {
+    pub fn from_cbor(raw: &[u8]) -> Result<Self, String> {
+        let envelope = parse_envelope(raw)?;
+        let (kind, body) = envelope_parts(&envelope)?;
+        if kind != SIGNED_KIND {
+            return Message::from_body(kind, body).map(Envelope::Plain);
+        }
+        let mut f = Fields::new(body, "signed message", 3)?;
+        let contract = f.bytes()?;
+        let signature = f.bytes()?;
+        let inner = f
+            .next()
+            .as_bytes()
+            .ok_or_else(|| "signed message: expected the message bytes".to_string())?;
+        let message = Message::from_cbor(inner)?;
+        // Only the canonical encoding is accepted, so the signature covers exactly `inner`.
+        if message.to_cbor() != *inner {
+            return Err("signed message is not canonically encoded".to_string());
+        }
+        Ok(Envelope::Signed(SignedMessage {
+            message,
+            contract,
+            signature,
+        }))
+    }
+
+    pub fn message(&self) -> &Message {
+        match self {
+            Envelope::Plain(message) => message,
+            Envelope::Signed(signed) => &signed.message,
+        }
+    }
+}
+
+/// Writes one length-prefixed CBOR message.
+pub fn write_message(writer: &mut impl Write, message: &Message) -> Result<(), String> {
+    write_length_prefixed(writer, &message.to_cbor())
+}
+
+/// Reads one length-prefixed CBOR message.
+pub fn read_message(reader: &mut impl Read) -> Result<Message, String> {
+    Message::from_cbor(&read_length_prefixed(reader)?)
+}
@@ -0,0 +1,625 @@
+//! Happy path of the whole protocol against a local node, for `off-chain-alice orchestrate`.
+//!
+//! Every step is one command of the alice or bob binary, run in a child process with the key
+//! of the party it acts for, so a run exercises the toolkit the way two operators would:
+//! deposits, verifier seeds, core commitments and OT roots, `choose`, the openings, the eval
+//! package and its OT, evaluation, labels, settlement and the name assignment. Values a later
+//! step depends on, such as `m`, are read from the `key=value` output of an earlier one.
+//!
+//! `off-chain-alice orchestrate-dispute` runs the cheating path instead: once the verifier
+//! seeds fix `m`, one instance that will be opened is garbled with a `Fault`, its rootGC is
+//! committed in place of the honest one, and after the openings buyer 0 disputes it with
+//! `auto-dispute`, which must slash Alice.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::cli::{PRIVATE_KEY_ENV, hex_prefixed, hex32, parse_bytes32};
+use crate::consensus::{HIGHER_BID_WINS, keccak256};
+use crate::daemon::command_failure;
+use crate::garble::garble_circuit;
+use crate::identity::EthIdentity;
+use crate::ih::{gc_block_hash, incremental_root_from_hashes};
+use crate::protocol::Party;
+use crate::registry::CircuitVersion;
+use crate::scenario::faults::{Fault, HeaderWire, inject_fault};
+use crate::scenario::{SessionParams, derive_instance_seed};
+use crate::types::CircuitLayout;
+
+/// Private keys of the first anvil dev accounts: Alice's, then three buyers'.
+pub const ANVIL_KEYS: [&str; 4] = [
+    "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+    "0x59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d",
+    "0x5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a",
+    "0x7c852118294e51e653712a81e05800f419141751be58f605c371e15141b007a6",
+];
+
+/// Names the deployed contract offers; the run settles on the first.
+pub const OFFERED_NAMES: [&str; 3] = ["tuwien.eth", "tuvienna.eth", "technicalvienna.eth"];
+
+/// Contracts deployed from the Foundry project, as `forge create` names them.
+pub const ENS_ADAPTER_CONTRACT: &str = "src/EnsAuctionAdapterMock.sol:EnsAuctionAdapterMock";
+pub const AUCTION_CONTRACT: &str = "src/MillionairesProblem.sol:MillionairesProblem";
+
+pub fn offered_namehashes() -> [[u8; 32]; 3] {
+    OFFERED_NAMES.map(|name| keccak256(&[name.as_bytes()]))
+}
+
+/// Verifier seed and salt buyer `index` commits to.
+pub fn buyer_seed(index: usize) -> ([u8; 32], [u8; 32]) {
+    let index = (index as u64).to_be_bytes();
+    (
+        keccak256(&[b"orchestrate-seed", &index]),
+        keccak256(&[b"orchestrate-salt", &index]),
+    )
+}
+
+/// XOR of the seeds of `buyers` buyers, the contract's `verifierSeed` once all revealed.
+pub fn verifier_seed(buyers: usize) -> [u8; 32] {
+    (0..buyers).fold([0u8; 32], |mut aggregate, index| {
+        let (seed, _) = buyer_seed(index);
+        aggregate.iter_mut().zip(seed).for_each(|(a, b)| *a ^= b);
+        aggregate
+    })
+}
+
+/// One command of the alice (`Party::Garbler`) or bob (`Party::Bidder`) binary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub party: Party,
+    pub command: &'static str,
+    pub args: Vec<String>,
+}
+
+impl Step {
+    fn new(party: Party, command: &'static str, args: &[&str]) -> Self {
+        Self {
+            party,
+            command,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// `alice` or `buyer<i>`.
+    pub fn party_name(&self) -> String {
+        match self.party {
+            Party::Bidder(index) => format!("buyer{index}"),
+            _ => "alice".to_string(),
+        }
+    }
+}
+
+/// Parties, inputs and directories of a run. Buyer 0 is the one that evaluates and settles.
+#[derive(Debug, Clone)]
+pub struct OrchestratePlan {
+    pub bit_width: usize,
+    pub alice_key: String,
+    pub alice: [u8; 20],
+    pub buyer_keys: Vec<String>,
+    pub buyers: Vec<[u8; 20]>,
+    pub bids: Vec<u64>,
+    /// Alice's input to the evaluated instance.
+    pub x: u64,
+    pub work_dir: PathBuf,
+}
+
+impl OrchestratePlan {
+    pub fn new(
+        bit_width: usize,
+        alice_key: String,
+        buyer_keys: Vec<String>,
+        bids: Vec<u64>,
+        x: u64,
+        work_dir: PathBuf,
+    ) -> Result<Self, String> {
+        if buyer_keys.is_empty() {
+            return Err("orchestrate needs at least one buyer key".to_string());
+        }
+        if bids.len() != buyer_keys.len() {
+            return Err(format!(
+                "--bids lists {} bids for {} buyers",
+                bids.len(),
+                buyer_keys.len()
+            ));
+        }
+        for (name, value) in bids.iter().map(|bid| ("bid", bid)).chain([("x", &x)]) {
+            if bit_width < 64 && value >> bit_width != 0 {
+                return Err(format!("{name} {value} does not fit bit-width {bit_width}"));
+            }
+        }
+        let alice = key_address(&alice_key)?;
+        let buyers = buyer_keys
+            .iter()
+            .map(|key| key_address(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self {
+            bit_width,
+            alice_key,
+            alice,
+            buyer_keys,
+            buyers,
+            bids,
+            x,
+            work_dir,
+        })
+    }
+
+    /// Where `submit-core-commitments` exports the instances.
+    pub fn export_dir(&self) -> PathBuf {
+        self.work_dir.join("export")
+    }
+
+    /// Where `prepare-eval` writes the eval package of instance `m`.
+    pub fn eval_dir(&self) -> PathBuf {
+        self.work_dir.join("eval")
+    }
+
+    /// Variables `party` runs with, on top of the shared `RPC_URL` and `CONTRACT_ADDRESS`.
+    pub fn party_env(&self, party: Party) -> Vec<(&'static str, String)> {
+        match party {
+            Party::Bidder(index) => vec![
+                ("BOB_PRIVATE_KEY", self.buyer_keys[index].clone()),
+                (
+                    "BOB_OT_SECRET",
+                    hex32(keccak256(&[
+                        b"orchestrate-ot",
+                        &(index as u64).to_be_bytes(),
+                    ])),
+                ),
+            ],
+            _ => vec![("ALICE_PRIVATE_KEY", self.alice_key.clone())],
+        }
+    }
+
+    /// `MillionairesProblem` constructor arguments: buyer 0 is the initial buyer and its own
+    /// receiver.
+    pub fn constructor_args(
+        &self,
+        ens_adapter: &str,
+        circuit_id: [u8; 32],
+        layout_root: [u8; 32],
+    ) -> Vec<String> {
+        let initial_buyer = hex_prefixed(&self.buyers[0]);
+        let offered = offered_namehashes().map(hex32).join(",");
+        vec![
+            initial_buyer.clone(),
+            initial_buyer,
+            format!("[{offered}]"),
+            ens_adapter.to_string(),
+            hex32(circuit_id),
+            hex32(layout_root),
+            self.bit_width.to_string(),
+        ]
+    }
+
+    /// `registerBuyers(address[],address[])` arguments for every buyer after the first, each
+    /// its own receiver; `None` with a single buyer.
+    pub fn register_buyers_args(&self) -> Option<[String; 2]> {
+        let others = self.buyers[1..]
+            .iter()
+            .map(|buyer| hex_prefixed(buyer))
+            .collect::<Vec<_>>();
+        let list = format!("[{}]", others.join(","));
+        (!others.is_empty()).then(|| [list.clone(), list])
+    }
+
+    /// Deposits through `choose`, whose `selected_m=` is the instance left closed.
+    pub fn steps_until_choose(&self) -> Vec<Step> {
+        let mut steps = self.setup_steps();
+        steps.extend(self.commitment_steps(None));
+        steps
+    }
+
+    /// Deposits and the verifier seeds, which fix the contract's `m`.
+    pub fn setup_steps(&self) -> Vec<Step> {
+        let buyers = 0..self.buyers.len();
+        let mut steps = vec![Step::new(Party::Garbler, "deposit", &[])];
+        steps.extend(
+            buyers
+                .clone()
+                .map(|index| Step::new(Party::Bidder(index), "deposit", &[])),
+        );
+        for command in ["commit-verifier-seed", "reveal-verifier-seed"] {
+            steps.extend(buyers.clone().map(|index| {
+                let (seed, salt) = buyer_seed(index);
+                let (seed, salt) = (hex32(seed), hex32(salt));
+                Step::new(
+                    Party::Bidder(index),
+                    command,
+                    &["--seed", &seed, "--salt", &salt],
+                )
+            }));
+        }
+        steps
+    }
+
+    /// Core commitments through `choose`. `root_gcs` replaces the roots Alice garbles, as a
+    /// cheating Alice would.
+    pub fn commitment_steps(&self, root_gcs: Option<&[[u8; 32]]>) -> Vec<Step> {
+        let bit_width = self.bit_width.to_string();
+        let buyers = 0..self.buyers.len();
+        let mut commit = Step::new(
+            Party::Garbler,
+            "submit-core-commitments",
+            &[
+                "--bit-width",
+                &bit_width,
+                "--bids",
+                &self.bids_csv(),
+                "--chosen-namehash",
+                &hex32(offered_namehashes()[0]),
+                "--export-dir",
+                &self.export_dir().display().to_string(),
+            ],
+        );
+        if let Some(root_gcs) = root_gcs {
+            let root_gcs = root_gcs.iter().map(|root| hex32(*root)).collect::<Vec<_>>();
+            commit
+                .args
+                .extend(["--root-gcs".to_string(), root_gcs.join(",")]);
+        }
+        let mut steps = vec![commit];
+        let verifier_seed = hex32(verifier_seed(self.buyers.len()));
+        steps.extend(self.buyers.iter().map(|buyer| {
+            Step::new(
+                Party::Garbler,
+                "submit-ot-roots",
+                &[
+                    "--buyer",
+                    &hex_prefixed(buyer),
+                    "--bit-width",
+                    &bit_width,
+                    "--verifier-seed",
+                    &verifier_seed,
+                ],
+            )
+        }));
+        steps.extend(buyers.map(|index| Step::new(Party::Bidder(index), "buyer-ready", &[])));
+        steps.push(Step::new(Party::Bidder(0), "choose", &[]));
+        steps
+    }
+
+    /// The openings of every instance but `m` through the name assignment.
+    pub fn steps_from_open(&self, m: usize) -> Vec<Step> {
+        let (bit_width, m) = (self.bit_width.to_string(), m.to_string());
+        let eval_dir = self.eval_dir().display().to_string();
+        let y = self.bids[0].to_string();
+        let mut steps = vec![Step::new(
+            Party::Garbler,
+            "reveal-openings",
+            &["--m", &m, "--bit-width", &bit_width],
+        )];
+        steps.extend(
+            (0..self.buyers.len())
+                .map(|index| Step::new(Party::Bidder(index), "close-dispute", &[])),
+        );
+        steps.extend([
+            Step::new(
+                Party::Garbler,
+                "prepare-eval",
+                &[
+                    "--m",
+                    &m,
+                    "--x",
+                    &self.x.to_string(),
+                    "--out-dir",
+                    &eval_dir,
+                    "--bit-width",
+                    &bit_width,
+                ],
+            ),
+            Step::new(
+                Party::Bidder(0),
+                "ot-choose",
+                &["--y", &y, "--eval-dir", &eval_dir],
+            ),
+            Step::new(
+                Party::Garbler,
+                "ot-respond",
+                &[
+                    "--m",
+                    &m,
+                    "--eval-dir",
+                    &eval_dir,
+                    "--bit-width",
+                    &bit_width,
+                ],
+            ),
+            Step::new(
+                Party::Bidder(0),
+                "evaluate-m",
+                &[
+                    "--y",
+                    &y,
+                    "--eval-dir",
+                    &eval_dir,
+                    "--package-file",
+                    &self
+                        .eval_dir()
+                        .join("eval-package.cbor")
+                        .display()
+                        .to_string(),
+                ],
+            ),
+            Step::new(
+                Party::Garbler,
+                "reveal-labels",
+                &[
+                    "--m",
+                    &m,
+                    "--x",
+                    &self.x.to_string(),
+                    "--bit-width",
+                    &bit_width,
+                    "--blob",
+                    "--path",
+                    &self
+                        .export_dir()
+                        .join(format!("instance-{m}-eval-blob.bin"))
+                        .display()
+                        .to_string(),
+                ],
+            ),
+            Step::new(
+                Party::Bidder(0),
+                "settle-auction",
+                &[
+                    "--bids",
+                    &self.bids_csv(),
+                    "--chosen-namehash",
+                    &hex32(offered_namehashes()[0]),
+                ],
+            ),
+            Step::new(Party::Bidder(0), "finalize-assignment", &[]),
+        ]);
+        steps
+    }
+
+    /// The openings, then buyer 0 disputing the first corrupted gate of `corrupted`.
+    pub fn dispute_steps(&self, m: usize, corrupted: &CorruptedInstance) -> Vec<Step> {
+        let bit_width = self.bit_width.to_string();
+        vec![
+            Step::new(
+                Party::Garbler,
+                "reveal-openings",
+                &["--m", &m.to_string(), "--bit-width", &bit_width],
+            ),
+            Step::new(
+                Party::Bidder(0),
+                "auto-dispute",
+                &[
+                    "--instance-id",
+                    &corrupted.instance_id.to_string(),
+                    "--seed",
+                    &hex32(corrupted.seed),
+                    "--artifacts-dir",
+                    &self.dispute_dir().display().to_string(),
+                    "--bit-width",
+                    &bit_width,
+                    "--yes",
+                ],
+            ),
+        ]
+    }
+
+    /// Where the leaves Alice claims for the corrupted instance are written.
+    pub fn dispute_dir(&self) -> PathBuf {
+        self.work_dir.join("dispute")
+    }
+
+    fn bids_csv(&self) -> String {
+        self.bids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+/// Corruptions `orchestrate-dispute --fault` applies to one gate.
+pub const FAULT_KINDS: [&str; 3] = ["flip-row", "swap-rows", "wrong-header"];
+
+/// The `Fault` `kind` names at `gate_index`: one bit of row 0 flipped, rows 0 and 1 swapped,
+/// or the header's output wire set to `u16::MAX`.
+pub fn fault_of_kind(kind: &str, gate_index: usize) -> Result<Fault, String> {
+    match kind {
+        "flip-row" => Ok(Fault::FlipRowByte {
+            gate_index,
+            row: 0,
+            byte: 0,
+            mask: 0x01,
+        }),
+        "swap-rows" => Ok(Fault::SwapRows {
+            gate_index,
+            row_a: 0,
+            row_b: 1,
+        }),
+        "wrong-header" => Ok(Fault::WrongWireHeader {
+            gate_index,
+            wire: HeaderWire::C,
+            value: u16::MAX,
+        }),
+        _ => Err(format!(
+            "unknown fault {kind}, expected one of {}",
+            FAULT_KINDS.join(", ")
+        )),
+    }
+}
+
+/// Instance Alice corrupts once `m` is fixed: the first one `m` leaves to be opened.
+pub fn corrupted_instance_id(m: usize) -> usize {
+    usize::from(m == 0)
+}
+
+/// An opened instance garbled with a fault, and the rootGC Alice commits for it.
+#[derive(Debug, Clone)]
+pub struct CorruptedInstance {
+    pub instance_id: usize,
+    /// The honest seed, which `reveal-openings` reveals.
+    pub seed: [u8; 32],
+    pub leaves: Vec<[u8; 71]>,
+    pub root_gc: [u8; 32],
+    pub description: String,
+}
+
+impl CorruptedInstance {
+    /// Writes the leaves as `instance-<id>-leaves.txt` in `dir`, where `auto-dispute
+    /// --artifacts-dir` reads them.
+    pub fn write_leaves(&self, dir: &Path) -> Result<PathBuf, String> {
+        fs::create_dir_all(dir).map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+        let path = dir.join(format!("instance-{}-leaves.txt", self.instance_id));
+        let lines = self
+            .leaves
+            .iter()
+            .map(|leaf| hex_prefixed(leaf) + "\n")
+            .collect::<String>();
+        fs::write(&path, lines).map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+        Ok(path)
+    }
+}
+
+/// Garbles instance `instance_id` of `circuit` from `master_seed`, as Alice's commands do
+/// under the default higher-bid-wins formula.
+pub fn instance_leaves(
+    circuit: &CircuitVersion,
+    bit_width: usize,
+    master_seed: [u8; 32],
+    instance_id: usize,
+) -> ([u8; 32], Vec<[u8; 71]>) {
+    let circuit_id = circuit.circuit_id(bit_width, HIGHER_BID_WINS);
+    let seed = derive_instance_seed(master_seed, circuit_id, instance_id as u64);
+    let layout = CircuitLayout::new(circuit_id, instance_id as u64, circuit.build(bit_width))
+        .with_scheme_id(circuit.params.scheme_id);
+    (seed, garble_circuit(seed, &layout))
+}
+
+/// Applies `fault` to instance `instance_id`. Truncated leaves are refused, since the contract
+/// rejects them before comparing anything and so never slashes.
+pub fn corrupt_instance(
+    circuit: &CircuitVersion,
+    bit_width: usize,
+    master_seed: [u8; 32],
+    instance_id: usize,
+    fault: Fault,
+) -> Result<CorruptedInstance, String> {
+    if let Fault::TruncateLeaf { .. } = fault {
+        return Err("a truncated leaf is rejected on-chain without slashing Alice".to_string());
+    }
+    let (seed, leaves) = instance_leaves(circuit, bit_width, master_seed, instance_id);
+    let faulty = inject_fault(&leaves, fault)?;
+    let leaves = faulty
+        .leaves
+        .iter()
+        .map(|leaf| <[u8; 71]>::try_from(leaf.as_slice()).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(CorruptedInstance {
+        instance_id,
+        seed,
+        root_gc: root_gc(&leaves),
+        leaves,
+        description: faulty.description,
+    })
+}
+
+/// rootGC of every instance, with the corrupted one's replaced.
+pub fn root_gcs_with(
+    circuit: &CircuitVersion,
+    bit_width: usize,
+    master_seed: [u8; 32],
+    params: SessionParams,
+    corrupted: &CorruptedInstance,
+) -> Vec<[u8; 32]> {
+    (0..params.n)
+        .map(|instance_id| {
+            if instance_id == corrupted.instance_id {
+                corrupted.root_gc
+            } else {
+                root_gc(&instance_leaves(circuit, bit_width, master_seed, instance_id).1)
+            }
+        })
+        .collect()
+}
+
+fn root_gc(leaves: &[[u8; 71]]) -> [u8; 32] {
+    let block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    incremental_root_from_hashes(&block_hashes)
+}
+
+fn key_address(private_key: &str) -> Result<[u8; 20], String> {
+    let private_key =
+        parse_bytes32(private_key).map_err(|e| format!("invalid private key: {e}"))?;
+    Ok(EthIdentity::from_private_key(private_key)?.address())
+}
+
+/// Value of the last `key=` line of a command's output.
+pub fn output_value(stdout: &str, key: &str) -> Option<String> {
+    stdout
+        .lines()
+        .rev()
+        .find_map(|line| line.strip_prefix(key)?.strip_prefix('='))
+        .map(str::to_string)
+}
+
+/// Runs `step` with `exe`, adding `env` to the inherited environment, and returns its stdout.
+pub fn run_step(exe: &Path, step: &Step, env: &[(&str, String)]) -> Result<String, String> {
+    let output = Command::new(exe)
+        .arg(step.command)
+        .args(&step.args)
+        .envs(env.iter().map(|(name, value)| (name, value)))
+        .output()
+        .map_err(|e| format!("failed to run {}: {e}", exe.display()))?;
+    if !output.status.success() {
+        return Err(command_failure(step.command, output.status, &output.stderr));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Deploys `contract` from the Foundry project in `contract_dir` with `forge create` and
+/// returns its address.
+pub fn forge_create(
+    contract_dir: &Path,
+    contract: &str,
+    rpc_url: &str,
+    private_key: &str,
+    constructor_args: &[String],
+) -> Result<String, String> {
+    let mut command = Command::new("forge");
+    command.current_dir(contract_dir).args([
+        "create",
+        contract,
+        "--rpc-url",
+        rpc_url,
+        "--broadcast",
+        "--json",
+    ]);
+    command.env(PRIVATE_KEY_ENV, private_key);
+    if !constructor_args.is_empty() {
+        command.arg("--constructor-args").args(constructor_args);
+    }
+    let output = command
+        .output()
+        .map_err(|e| format!("failed to run forge: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "forge create {contract} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    deployed_to(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| format!("forge create {contract} printed no deployedTo address"))
+}
+
+/// `deployedTo` of the JSON object `forge create --json` prints among its other lines.
+pub fn deployed_to(output: &str) -> Option<String> {
+    output.lines().rev().find_map(|line| {
+        let value = serde_json::from_str::<Value>(line.trim()).ok()?;
+        Some(value.get("deployedTo")?.as_str()?.to_string())
+    })
+}
@@ -0,0 +1,282 @@
+//! IKNP OT extension on top of `base_ot`: `OT_EXTENSION_K` base OTs (with the roles
+//! reversed) plus hashing transfer labels for any number of y-wires, so wide bids cost a
+//! fixed number of point multiplications.
+//!
+//! Flow for `m` wires and Bob's choice bits `r`:
+//! - Bob (`OtExtReceiver`) sends the base-OT setup point; he is the base sender of seed pairs
+//!   `(k0_j, k1_j)` for `j < K`,
+//! - Alice (`OtExtSender`) answers with base-OT choice points for her secret bits `s`,
+//! - Bob sends an `OtExtensionRequest`: the base-OT rows and columns
+//!   `u_j = G(k0_j) XOR G(k1_j) XOR r`,
+//! - Alice recovers `q_j = G(k_{s_j, j}) XOR s_j u_j` and replies per wire `i` with
+//!   `e_{i,c} = L_{i,c} XOR H(i, q_i XOR c s)`,
+//! - Bob opens his bit's row with `H(i, t_i)` where `t_j = G(k0_j)`,
+//!
+//! where `q_i`/`t_i` are row `i` of the `m x K` bit matrices, `G` is keccak256 in counter
+//! mode over `"OT-EXT-PRG"` and `H(i, x)` is the first 16 bytes of
+//! `keccak256("OT-EXT-KEY", uint256(i), x)`.
+//!
+//! Bob's seed pairs are derived in `extend` from his secret, a session id and the digest of
+//! Alice's base choice points. With seeds fixed per secret, an Alice running two extensions
+//! with different `s` gets `q XOR q' = r (s XOR s')` and reads Bob's choice bits off it; Bob
+//! keeps one secret across instances, so the seeds must change with every run.
+//!
+//! There is no KOS/ALSZ consistency check on Bob's columns, so the extension is secure
+//! against a semi-honest Bob only: a malicious one can send inconsistent `u_j` and probe bits
+//! of `s`, so `OtExtSender` derives a fresh `s` for every setup point.
+
+use crate::base_ot::{OT_POINT_LEN, OtReceiver, OtSender, choices_digest};
+use crate::consensus::{keccak256, uint256_from_u64, xor16};
+
+/// Number of base OTs, i.e. the computational security parameter in bits.
+pub const OT_EXTENSION_K: usize = 128;
+
+/// Stretches a base-OT seed into `len` pseudorandom bytes.
+fn prg(seed: [u8; 16], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len.next_multiple_of(32));
+    let mut block = 0u64;
+    while out.len() < len {
+        out.extend_from_slice(&keccak256(&[
+            b"OT-EXT-PRG",
+            &seed,
+            &uint256_from_u64(block),
+        ]));
+        block += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn pad_key(index: usize, row: [u8; 16]) -> [u8; 16] {
+    let digest = keccak256(&[b"OT-EXT-KEY", &uint256_from_u64(index as u64), &row]);
+    let mut key = [0u8; 16];
+    key.copy_from_slice(&digest[..16]);
+    key
+}
+
+fn xor_in_place(acc: &mut [u8], other: &[u8]) {
+    for (a, b) in acc.iter_mut().zip(other) {
+        *a ^= b;
+    }
+}
+
+fn bit(bytes: &[u8], idx: usize) -> u8 {
+    (bytes[idx / 8] >> (idx % 8)) & 1
+}
+
+/// Packs `0`/`1` bits little-endian into bytes.
+fn pack_bits(bits: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; bits.len().div_ceil(8)];
+    for (idx, b) in bits.iter().enumerate() {
+        out[idx / 8] |= (b & 1) << (idx % 8);
+    }
+    out
+}
+
+/// Transposes `K` columns of `m` bits into `m` rows of `K` bits.
+fn transpose(columns: &[Vec<u8>], wire_count: usize) -> Vec<[u8; 16]> {
+    (0..wire_count)
+        .map(|row| {
+            let mut out = [0u8; 16];
+            for (col, column) in columns.iter().enumerate() {
+                out[col / 8] |= bit(column, row) << (col % 8);
+            }
+            out
+        })
+        .collect()
+}
+
+/// Bob's second message: base-OT rows carrying his seed pairs and the correction columns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OtExtensionRequest {
+    pub base_responses: Vec<[[u8; 16]; 2]>,
+    /// `OT_EXTENSION_K` columns of `ceil(m / 8)` bytes each.
+    pub columns: Vec<Vec<u8>>,
+}
+
+/// Bob's side of the extension: base-OT sender, extension receiver of one label per wire.
+#[derive(Debug, Clone)]
+pub struct OtExtReceiver {
+    secret: [u8; 32],
+    session: [u8; 32],
+    base: OtSender,
+    choice_bits: Vec<u8>,
+    /// Rows of `t`, known once `extend` has fixed the seed pairs.
+    t_rows: Option<Vec<[u8; 16]>>,
+}
+
+impl OtExtReceiver {
+    /// Receiver for `choice_bits` (one `0`/`1` per wire) in `session` (e.g. a digest of the
+    /// circuit id and instance); `secret` must stay private.
+    pub fn new(secret: [u8; 32], session: [u8; 32], choice_bits: &[u8]) -> Self {
+        Self {
+            secret,
+            session,
+            base: OtSender::new(keccak256(&[b"OT-EXT-BASE", &secret, &session])),
+            choice_bits: choice_bits.iter().map(|b| b & 1).collect(),
+            t_rows: None,
+        }
+    }
+
+    /// Seed pairs `(k0_j, k1_j)` for Alice's base choice points.
+    fn seeds(&self, base_choices: &[[u8; OT_POINT_LEN]]) -> Vec<([u8; 16], [u8; 16])> {
+        let choices = choices_digest(base_choices);
+        (0..OT_EXTENSION_K)
+            .map(|j| {
+                let seed = |c: u8| {
+                    let digest = keccak256(&[
+                        b"OT-EXT-SEED",
+                        &self.secret,
+                        &self.session,
+                        &choices,
+                        &uint256_from_u64(j as u64),
+                        &[c],
+                    ]);
+                    let mut out = [0u8; 16];
+                    out.copy_from_slice(&digest[..16]);
+                    out
+                };
+                (seed(0), seed(1))
+            })
+            .collect()
+    }
+
+    /// Base-OT setup point sent to Alice.
+    pub fn setup_message(&self) -> [u8; OT_POINT_LEN] {
+        self.base.setup_message()
+    }
+
+    /// Answers Alice's `OT_EXTENSION_K` base-OT choice points; the seed pairs, and so the
+    /// rows used by `decrypt`, are bound to them.
+    pub fn extend(
+        &mut self,
+        base_choices: &[[u8; OT_POINT_LEN]],
+    ) -> Result<OtExtensionRequest, String> {
+        if base_choices.len() != OT_EXTENSION_K {
+            return Err(format!(
+                "got {} base OT choices, expected {OT_EXTENSION_K}",
+                base_choices.len()
+            ));
+        }
+        let seeds = self.seeds(base_choices);
+        let base_responses = self.base.respond(base_choices, &seeds)?;
+        let packed_choices = pack_bits(&self.choice_bits);
+        let t_columns = seeds
+            .iter()
+            .map(|(k0, _)| prg(*k0, packed_choices.len()))
+            .collect::<Vec<_>>();
+        self.t_rows = Some(transpose(&t_columns, self.choice_bits.len()));
+        let columns = seeds
+            .iter()
+            .map(|(k0, k1)| {
+                let mut column = prg(*k0, packed_choices.len());
+                xor_in_place(&mut column, &prg(*k1, packed_choices.len()));
+                xor_in_place(&mut column, &packed_choices);
+                column
+            })
+            .collect();
+        Ok(OtExtensionRequest {
+            base_responses,
+            columns,
+        })
+    }
+
+    /// Decrypts the label of each chosen bit from Alice's `[e0, e1]` rows.
+    pub fn decrypt(&self, responses: &[[[u8; 16]; 2]]) -> Result<Vec<[u8; 16]>, String> {
+        let t_rows = self
+            .t_rows
+            .as_ref()
+            .ok_or_else(|| "OT extension responses before extend".to_string())?;
+        if responses.len() != self.choice_bits.len() {
+            return Err(format!(
+                "got {} OT extension responses for {} choices",
+                responses.len(),
+                self.choice_bits.len()
+            ));
+        }
+        Ok(responses
+            .iter()
+            .enumerate()
+            .map(|(idx, rows)| {
+                xor16(
+                    rows[self.choice_bits[idx] as usize],
+                    pad_key(idx, t_rows[idx]),
+                )
+            })
+            .collect())
+    }
+}
+
+/// Alice's side of the extension: base-OT receiver, extension sender of both labels.
+#[derive(Debug, Clone)]
+pub struct OtExtSender {
+    base: OtReceiver,
+    s: [u8; 16],
+}
+
+impl OtExtSender {
+    /// Sender against Bob's base-OT setup point; `secret` must stay private. `s` and the
+    /// base-OT secret are derived from `secret` and `setup` together: a fixed `s` chosen
+    /// through base OTs against two setup points would show in the difference of the choice
+    /// points, and `s` opens both labels of every wire.
+    pub fn new(secret: [u8; 32], setup: &[u8]) -> Result<Self, String> {
+        let digest = keccak256(&[b"OT-EXT-S", &secret, setup]);
+        let mut s = [0u8; 16];
+        s.copy_from_slice(&digest[..16]);
+        let s_bits = (0..OT_EXTENSION_K).map(|j| bit(&s, j)).collect::<Vec<_>>();
+        let base = OtReceiver::new(keccak256(&[b"OT-EXT-BASE", &secret, setup]), setup, &s_bits)?;
+        Ok(Self { base, s })
+    }
+
+    /// Base-OT choice points sent to Bob.
+    pub fn base_choice_messages(&self) -> Vec<[u8; OT_POINT_LEN]> {
+        self.base.choice_messages()
+    }
+
+    /// Encrypts `pairs[i] = (L0, L1)` under Bob's extension request; returns `[e0, e1]` per
+    /// wire.
+    pub fn respond(
+        &self,
+        request: &OtExtensionRequest,
+        pairs: &[([u8; 16], [u8; 16])],
+    ) -> Result<Vec<[[u8; 16]; 2]>, String> {
+        if request.columns.len() != OT_EXTENSION_K {
+            return Err(format!(
+                "got {} OT extension columns, expected {OT_EXTENSION_K}",
+                request.columns.len()
+            ));
+        }
+        let column_len = pairs.len().div_ceil(8);
+        if let Some(column) = request.columns.iter().find(|c| c.len() != column_len) {
+            return Err(format!(
+                "OT extension column has {} bytes, expected {column_len} for {} label pairs",
+                column.len(),
+                pairs.len()
+            ));
+        }
+        let seeds = self.base.decrypt(&request.base_responses)?;
+        let q_columns = seeds
+            .iter()
+            .zip(&request.columns)
+            .enumerate()
+            .map(|(j, (seed, u))| {
+                let mut column = prg(*seed, column_len);
+                if bit(&self.s, j) == 1 {
+                    xor_in_place(&mut column, u);
+                }
+                column
+            })
+            .collect::<Vec<_>>();
+        Ok(transpose(&q_columns, pairs.len())
+            .into_iter()
+            .zip(pairs)
+            .enumerate()
+            .map(|(idx, (q, (label0, label1)))| {
+                [
+                    xor16(*label0, pad_key(idx, q)),
+                    xor16(*label1, pad_key(idx, xor16(q, self.s))),
+                ]
+            })
+            .collect())
+    }
+}
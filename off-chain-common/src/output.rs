@@ -0,0 +1,118 @@
+//! Command output of the alice and bob binaries. Commands print `key=value` lines through
+//! `emitln!`; with `--json` the lines are buffered instead and printed once the command
+//! finishes, as one JSON object scripts can read without scraping text.
+//!
+//! The object holds the command name, every `key=value` line as a field and `status`, which
+//! a command's own `status=` line sets and which is `"ok"` otherwise, or `"error"` next to
+//! an `error` message when the command failed. Values stay strings, since hex values and
+//! `uint256` decimals do not fit JSON numbers, except `true`/`false` and bracketed lists
+//! such as proofs (`[0x..,0x..]`), which become booleans and arrays. A key printed more than
+//! once (e.g. `watch` events) becomes an array of its values. Other lines, like `cast send`
+//! templates, are collected under `output`.
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use serde_json::{Map, Value};
+
+/// Lines buffered since `start_json_output`, or `None` while lines go straight to stdout.
+static JSON_LINES: Mutex<Option<Vec<String>>> = Mutex::new(None);
+
+/// Prints a `key=value` line, or buffers it once `start_json_output` switched to `--json`.
+#[macro_export]
+macro_rules! emitln {
+    () => {
+        $crate::output::emit(String::new())
+    };
+    ($($arg:tt)*) => {
+        $crate::output::emit(format!($($arg)*))
+    };
+}
+
+/// Buffers the lines of `emitln!` from now on, for `print_json_output`.
+pub fn start_json_output() {
+    if let Ok(mut lines) = JSON_LINES.lock() {
+        lines.get_or_insert_with(Vec::new);
+    }
+}
+
+/// Backs `emitln!`.
+pub fn emit(line: String) {
+    match JSON_LINES.lock() {
+        Ok(mut guard) => match guard.as_mut() {
+            Some(lines) => lines.push(line),
+            None => println!("{line}"),
+        },
+        Err(_) => println!("{line}"),
+    }
+}
+
+/// Prints the lines buffered since `start_json_output` as the JSON object of `command`,
+/// failed with `error` if given; a no-op without `--json`.
+pub fn print_json_output(command: &str, error: Option<&str>) {
+    let lines = JSON_LINES.lock().ok().and_then(|mut guard| guard.take());
+    if let Some(lines) = lines {
+        println!("{}", command_output_json(command, &lines, error));
+    }
+}
+
+/// JSON object of `command`'s output `lines`, as `--json` prints it.
+pub fn command_output_json(command: &str, lines: &[String], error: Option<&str>) -> Value {
+    let mut values = BTreeMap::<&str, Vec<Value>>::new();
+    let mut other = Vec::new();
+    for line in lines.iter().filter(|line| !line.trim().is_empty()) {
+        match line.split_once('=') {
+            Some((key, value)) if !key.is_empty() && !key.contains(' ') => {
+                values.entry(key).or_default().push(output_value(value));
+            }
+            _ => other.push(Value::String(line.clone())),
+        }
+    }
+
+    let mut fields = Map::new();
+    fields.insert("command".to_string(), Value::String(command.to_string()));
+    for (key, mut key_values) in values {
+        let value = if key_values.len() == 1 {
+            key_values.remove(0)
+        } else {
+            Value::Array(key_values)
+        };
+        fields.insert(key.to_string(), value);
+    }
+    if !other.is_empty() {
+        fields.insert("output".to_string(), Value::Array(other));
+    }
+    match error {
+        Some(message) => {
+            fields.insert("status".to_string(), Value::String("error".to_string()));
+            fields.insert("error".to_string(), Value::String(message.to_string()));
+        }
+        None => {
+            fields
+                .entry("status")
+                .or_insert_with(|| Value::String("ok".to_string()));
+        }
+    }
+    Value::Object(fields)
+}
+
+fn output_value(raw: &str) -> Value {
+    match raw {
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        _ => match raw
+            .strip_prefix('[')
+            .and_then(|inner| inner.strip_suffix(']'))
+        {
+            Some(inner) => Value::Array(
+                inner
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(|item| Value::String(item.to_string()))
+                    .collect(),
+            ),
+            None => Value::String(raw.to_string()),
+        },
+    }
+}
@@ -0,0 +1,144 @@
+//! Named network profiles for `--profile dev|sepolia|mainnet`.
+//!
+//! The config file (`ppa.toml` in the working directory, or the path in `PPA_CONFIG`) holds
+//! one `[profile.<name>]` table per network with the settings the binaries otherwise read
+//! from env vars:
+//!
+//! ```toml
+//! [profile.dev]
+//! rpc_url = "http://127.0.0.1:8545"
+//! contract_address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+//! legacy = true
+//! gas_price_wei = 0
+//!
+//! [profile.sepolia]
+//! rpc_url = "https://sepolia.example.org"
+//! contract_address = "0x..."
+//! ```
+//!
+//! A selected profile takes precedence over `RPC_URL`, `CONTRACT_ADDRESS`, `TX_LEGACY` and
+//! `TX_GAS_PRICE_WEI`; settings it leaves out still come from the environment. Private keys
+//! are never read from the config file.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Env var overriding the config file path.
+pub const CONFIG_ENV: &str = "PPA_CONFIG";
+pub const DEFAULT_CONFIG_FILE: &str = "ppa.toml";
+
+/// Network a command runs against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProfileName {
+    /// Local anvil node
+    Dev,
+    Sepolia,
+    Mainnet,
+}
+
+impl ProfileName {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProfileName::Dev => "dev",
+            ProfileName::Sepolia => "sepolia",
+            ProfileName::Mainnet => "mainnet",
+        }
+    }
+}
+
+impl fmt::Display for ProfileName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// One `[profile.<name>]` table of the config file.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NetworkProfile {
+    pub rpc_url: Option<String>,
+    pub contract_address: Option<String>,
+    /// Send legacy (type 0) transactions, as `TX_LEGACY=1` does.
+    pub legacy: Option<bool>,
+    pub gas_price_wei: Option<u64>,
+}
+
+impl NetworkProfile {
+    /// The env vars this profile sets, with their values.
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = Vec::new();
+        if let Some(rpc_url) = &self.rpc_url {
+            vars.push(("RPC_URL", rpc_url.clone()));
+        }
+        if let Some(contract_address) = &self.contract_address {
+            vars.push(("CONTRACT_ADDRESS", contract_address.clone()));
+        }
+        if let Some(legacy) = self.legacy {
+            vars.push(("TX_LEGACY", if legacy { "1" } else { "0" }.to_string()));
+        }
+        if let Some(gas_price_wei) = self.gas_price_wei {
+            vars.push(("TX_GAS_PRICE_WEI", gas_price_wei.to_string()));
+        }
+        vars
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfileConfig {
+    #[serde(default)]
+    profile: BTreeMap<String, NetworkProfile>,
+}
+
+/// Settings of the profile selected with `activate_profile`.
+static ACTIVE_PROFILE: Mutex<Vec<(&'static str, String)>> = Mutex::new(Vec::new());
+
+/// Path of the config file: `PPA_CONFIG`, or `ppa.toml` in the working directory.
+pub fn config_path() -> PathBuf {
+    env::var(CONFIG_ENV)
+        .ok()
+        .filter(|path| !path.trim().is_empty())
+        .map_or_else(|| PathBuf::from(DEFAULT_CONFIG_FILE), PathBuf::from)
+}
+
+/// Profile `name` of the config file contents `config`.
+pub fn parse_profile(config: &str, name: ProfileName) -> Result<NetworkProfile, String> {
+    let config: ProfileConfig =
+        toml::from_str(config).map_err(|e| format!("invalid config file: {e}"))?;
+    config
+        .profile
+        .get(name.as_str())
+        .cloned()
+        .ok_or_else(|| format!("config file has no [profile.{name}] table"))
+}
+
+/// Profile `name` of the config file at `path`.
+pub fn load_profile(path: &Path, name: ProfileName) -> Result<NetworkProfile, String> {
+    let config = fs::read_to_string(path)
+        .map_err(|e| format!("failed to read config file {}: {e}", path.display()))?;
+    parse_profile(&config, name).map_err(|e| format!("{}: {e}", path.display()))
+}
+
+/// Makes `profile`'s settings take precedence over the env vars they replace, for the rest
+/// of the process.
+pub fn activate_profile(profile: &NetworkProfile) {
+    if let Ok(mut active) = ACTIVE_PROFILE.lock() {
+        *active = profile.env_vars();
+    }
+}
+
+/// Value the active profile sets for env var `name`, if any.
+pub fn profile_var(name: &str) -> Option<String> {
+    let active = ACTIVE_PROFILE.lock().ok()?;
+    active
+        .iter()
+        .find(|(var, _)| *var == name)
+        .map(|(_, value)| value.clone())
+}
@@ -0,0 +1,491 @@
+//! Protocol session state machine shared by the alice and bob binaries: the phases of one
+//! cut-and-choose session, which phase may follow which, and the payload that moves a
+//! session into each phase.
+
+use std::fmt;
+
+use crate::cli::{decode_hex, hex_prefixed, hex32};
+use crate::scenario::participants::ParticipantLayout;
+use crate::scenario::{SessionParams, com_seed};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// Both parties lock their deposits.
+    Deposit,
+    /// Alice commits `comSeed`/`rootGC` per instance (Bob commits his verifier seed).
+    Commit,
+    /// Bob picks the evaluated instance `m`.
+    Choose,
+    /// Alice opens the seeds of every instance except `m`.
+    Open,
+    /// Bob evaluates instance `m`.
+    Eval,
+    /// Bob challenges one opened instance.
+    Dispute,
+    /// The auction output is settled.
+    Settle,
+}
+
+impl Phase {
+    pub const ALL: [Phase; 7] = [
+        Phase::Deposit,
+        Phase::Commit,
+        Phase::Choose,
+        Phase::Open,
+        Phase::Eval,
+        Phase::Dispute,
+        Phase::Settle,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::Deposit => "deposit",
+            Phase::Commit => "commit",
+            Phase::Choose => "choose",
+            Phase::Open => "open",
+            Phase::Eval => "eval",
+            Phase::Dispute => "dispute",
+            Phase::Settle => "settle",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Result<Self, String> {
+        Phase::ALL
+            .into_iter()
+            .find(|phase| phase.name() == name)
+            .ok_or_else(|| format!("unknown protocol phase '{name}'"))
+    }
+
+    /// Phases a session may move to from `self`. Opened instances may be disputed before or
+    /// after evaluating `m`; `Settle` is terminal.
+    pub fn next_phases(self) -> &'static [Phase] {
+        match self {
+            Phase::Deposit => &[Phase::Commit],
+            Phase::Commit => &[Phase::Choose],
+            Phase::Choose => &[Phase::Open],
+            Phase::Open => &[Phase::Eval, Phase::Dispute],
+            Phase::Eval => &[Phase::Dispute, Phase::Settle],
+            Phase::Dispute => &[Phase::Eval, Phase::Settle],
+            Phase::Settle => &[],
+        }
+    }
+
+    pub fn can_transition_to(self, next: Phase) -> bool {
+        self.next_phases().contains(&next)
+    }
+
+    /// Phase of the contract's `Stage` enum value (`currentStage()`).
+    pub fn from_contract_stage(stage: u8) -> Result<Self, String> {
+        match stage {
+            0 => Ok(Phase::Deposit),
+            // BuyerSeedCommit, CommitmentsCore, BuyerSeedReveal, CommitmentsOT
+            1..=4 => Ok(Phase::Commit),
+            // BuyerInputOT: Bob's choice of m and his input OT
+            5 => Ok(Phase::Choose),
+            6 => Ok(Phase::Open),
+            7 => Ok(Phase::Dispute),
+            // Labels
+            8 => Ok(Phase::Eval),
+            // Settle, Assignment, Closed
+            9..=11 => Ok(Phase::Settle),
+            other => Err(format!("unknown contract stage {other}")),
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Party {
+    /// Alice.
+    Garbler,
+    /// Bob, the only evaluator of a two-party session.
+    Evaluator,
+    /// Bidder `i` of a multi-bidder auction (`ParticipantLayout::auction`).
+    Bidder(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositPayload {
+    pub party: Party,
+}
+
+/// Alice's per-instance commitments, indexed by instance id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitPayload {
+    pub com_seeds: Vec<[u8; 32]>,
+    pub root_gcs: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoosePayload {
+    pub m: usize,
+}
+
+/// Opened `(instance_id, seed)` pairs for every instance except `m`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenPayload {
+    pub seeds: Vec<(usize, [u8; 32])>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EvalPayload {
+    pub output_label: [u8; 32],
+    /// `None` if the label matched neither output anchor.
+    pub decoded_bit: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DisputePayload {
+    pub instance_id: usize,
+    pub gate_index: usize,
+}
+
+/// Encoded auction output as passed to `settle`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettlePayload {
+    pub output: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PhasePayload {
+    Deposit(DepositPayload),
+    Commit(CommitPayload),
+    Choose(ChoosePayload),
+    Open(OpenPayload),
+    Eval(EvalPayload),
+    Dispute(DisputePayload),
+    Settle(SettlePayload),
+}
+
+impl PhasePayload {
+    /// Phase this payload belongs to.
+    pub fn phase(&self) -> Phase {
+        match self {
+            PhasePayload::Deposit(_) => Phase::Deposit,
+            PhasePayload::Commit(_) => Phase::Commit,
+            PhasePayload::Choose(_) => Phase::Choose,
+            PhasePayload::Open(_) => Phase::Open,
+            PhasePayload::Eval(_) => Phase::Eval,
+            PhasePayload::Dispute(_) => Phase::Dispute,
+            PhasePayload::Settle(_) => Phase::Settle,
+        }
+    }
+}
+
+/// One session driven through `apply`; starts in `Phase::Deposit` with no deposits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolSession {
+    params: SessionParams,
+    phase: Phase,
+    /// Parties that must deposit before `Commit`.
+    parties: Vec<Party>,
+    deposits: Vec<Party>,
+    commitments: Option<CommitPayload>,
+    m: Option<usize>,
+    opened: Vec<(usize, [u8; 32])>,
+    eval: Option<EvalPayload>,
+    disputes: Vec<DisputePayload>,
+    settlement: Option<SettlePayload>,
+}
+
+impl ProtocolSession {
+    /// Two-party session between Alice and Bob.
+    pub fn new(params: SessionParams) -> Self {
+        Self::with_participants(params, &ParticipantLayout::two_party(1))
+    }
+
+    /// Session in which the garbler serves every bidder of `participants`.
+    pub fn with_participants(params: SessionParams, participants: &ParticipantLayout) -> Self {
+        Self {
+            params,
+            phase: Phase::Deposit,
+            parties: participants.parties(),
+            deposits: Vec::new(),
+            commitments: None,
+            m: None,
+            opened: Vec::new(),
+            eval: None,
+            disputes: Vec::new(),
+            settlement: None,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn params(&self) -> SessionParams {
+        self.params
+    }
+
+    pub fn parties(&self) -> &[Party] {
+        &self.parties
+    }
+
+    pub fn commitments(&self) -> Option<&CommitPayload> {
+        self.commitments.as_ref()
+    }
+
+    /// Instance chosen for evaluation, once in `Phase::Choose` or later.
+    pub fn chosen_instance(&self) -> Option<usize> {
+        self.m
+    }
+
+    pub fn opened_seed(&self, instance_id: usize) -> Option<[u8; 32]> {
+        self.opened
+            .iter()
+            .find_map(|(idx, seed)| (*idx == instance_id).then_some(*seed))
+    }
+
+    pub fn eval(&self) -> Option<&EvalPayload> {
+        self.eval.as_ref()
+    }
+
+    pub fn disputes(&self) -> &[DisputePayload] {
+        &self.disputes
+    }
+
+    pub fn settlement(&self) -> Option<&SettlePayload> {
+        self.settlement.as_ref()
+    }
+
+    /// Applies `payload` and returns the resulting phase. A `Deposit` payload stays in
+    /// `Phase::Deposit`; every other payload must enter a phase allowed after the current one.
+    pub fn apply(&mut self, payload: PhasePayload) -> Result<Phase, String> {
+        let target = payload.phase();
+        if target == Phase::Deposit && self.phase == Phase::Deposit {
+            let PhasePayload::Deposit(DepositPayload { party }) = payload else {
+                unreachable!("phase() of a deposit payload");
+            };
+            if !self.parties.contains(&party) {
+                return Err(format!("{party:?} is not a party of this session"));
+            }
+            if self.deposits.contains(&party) {
+                return Err(format!("{party:?} already deposited"));
+            }
+            self.deposits.push(party);
+            return Ok(self.phase);
+        }
+        if !self.phase.can_transition_to(target) {
+            let allowed = self
+                .phase
+                .next_phases()
+                .iter()
+                .map(|phase| phase.name())
+                .collect::<Vec<_>>();
+            return Err(format!(
+                "cannot move from {} to {target} (allowed: [{}])",
+                self.phase,
+                allowed.join(", ")
+            ));
+        }
+
+        match payload {
+            PhasePayload::Deposit(_) => unreachable!("no phase moves back to deposit"),
+            PhasePayload::Commit(commit) => {
+                if self.deposits.len() != self.parties.len() {
+                    return Err(match self.parties.len() {
+                        2 => "commit requires deposits from both parties".to_string(),
+                        count => format!(
+                            "commit requires deposits from all {count} parties, got {}",
+                            self.deposits.len()
+                        ),
+                    });
+                }
+                let n = self.params.n;
+                if commit.com_seeds.len() != n || commit.root_gcs.len() != n {
+                    return Err(format!(
+                        "commit carries {} comSeeds and {} rootGCs, expected n={n}",
+                        commit.com_seeds.len(),
+                        commit.root_gcs.len()
+                    ));
+                }
+                self.commitments = Some(commit);
+            }
+            PhasePayload::Choose(ChoosePayload { m }) => {
+                self.params.check_m(m)?;
+                self.m = Some(m);
+            }
+            PhasePayload::Open(OpenPayload { seeds }) => self.check_openings(&seeds)?,
+            PhasePayload::Eval(eval) => self.eval = Some(eval),
+            PhasePayload::Dispute(dispute) => {
+                if self.opened_seed(dispute.instance_id).is_none() {
+                    return Err(format!(
+                        "instance {} was not opened and cannot be disputed",
+                        dispute.instance_id
+                    ));
+                }
+                self.disputes.push(dispute);
+            }
+            PhasePayload::Settle(settle) => self.settlement = Some(settle),
+        }
+        self.phase = target;
+        Ok(target)
+    }
+
+    fn check_openings(&mut self, seeds: &[(usize, [u8; 32])]) -> Result<(), String> {
+        let (Some(m), Some(commitments)) = (self.m, self.commitments.as_ref()) else {
+            unreachable!("open follows commit and choose");
+        };
+        let mut expected = (0..self.params.n)
+            .filter(|idx| *idx != m)
+            .collect::<Vec<_>>();
+        let mut got = seeds.iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+        got.sort_unstable();
+        expected.sort_unstable();
+        if got != expected {
+            return Err(format!(
+                "open must reveal exactly the instances {expected:?} (all but m={m}), got {got:?}"
+            ));
+        }
+        if let Some((idx, _)) = seeds
+            .iter()
+            .find(|(idx, seed)| com_seed(*seed) != commitments.com_seeds[*idx])
+        {
+            return Err(format!(
+                "opened seed of instance {idx} does not match comSeed"
+            ));
+        }
+        self.opened = seeds.to_vec();
+        Ok(())
+    }
+}
+
+fn parse_hash(value: &str) -> Result<[u8; 32], String> {
+    decode_hex(value)
+        .map_err(|e| e.to_string())?
+        .try_into()
+        .map_err(|_| format!("expected 32 bytes: {value}"))
+}
+
+fn party_name(party: Party) -> String {
+    match party {
+        Party::Garbler => "garbler".to_string(),
+        Party::Evaluator => "evaluator".to_string(),
+        Party::Bidder(idx) => format!("bidder:{idx}"),
+    }
+}
+
+fn parse_party(value: &str) -> Result<Party, String> {
+    match value {
+        "garbler" => Ok(Party::Garbler),
+        "evaluator" => Ok(Party::Evaluator),
+        other => other
+            .strip_prefix("bidder:")
+            .and_then(|idx| idx.parse().ok())
+            .map(Party::Bidder)
+            .ok_or_else(|| format!("unknown party '{other}'")),
+    }
+}
+
+fn hash_list(hashes: &[[u8; 32]]) -> String {
+    hashes
+        .iter()
+        .map(|hash| hex32(*hash))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn parse_hash_list(value: &str) -> Result<Vec<[u8; 32]>, String> {
+    if value.is_empty() {
+        return Ok(Vec::new());
+    }
+    value.split(',').map(parse_hash).collect()
+}
+
+fn parse_index(value: &str, name: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {name} '{value}'"))
+}
+
+/// `<phase> <fields...>`, space separated; lists are comma separated.
+pub fn encode_payload(payload: &PhasePayload) -> String {
+    let fields = match payload {
+        PhasePayload::Deposit(DepositPayload { party }) => party_name(*party),
+        PhasePayload::Commit(commit) => format!(
+            "{} {}",
+            hash_list(&commit.com_seeds),
+            hash_list(&commit.root_gcs)
+        ),
+        PhasePayload::Choose(ChoosePayload { m }) => m.to_string(),
+        PhasePayload::Open(OpenPayload { seeds }) => seeds
+            .iter()
+            .map(|(idx, seed)| format!("{idx}:{}", hex32(*seed)))
+            .collect::<Vec<_>>()
+            .join(","),
+        PhasePayload::Eval(eval) => format!(
+            "{} {}",
+            hex32(eval.output_label),
+            eval.decoded_bit
+                .map_or("none".to_string(), |bit| bit.to_string())
+        ),
+        PhasePayload::Dispute(dispute) => {
+            format!("{} {}", dispute.instance_id, dispute.gate_index)
+        }
+        PhasePayload::Settle(SettlePayload { output }) => hex_prefixed(output),
+    };
+    format!("{} {fields}", payload.phase())
+}
+
+pub fn decode_payload(line: &str) -> Result<PhasePayload, String> {
+    let mut parts = line.split(' ');
+    let phase = Phase::from_name(parts.next().unwrap_or(""))?;
+    let fields = parts.collect::<Vec<_>>();
+    let expected = match phase {
+        Phase::Commit | Phase::Eval | Phase::Dispute => 2,
+        _ => 1,
+    };
+    if fields.len() != expected {
+        return Err(format!(
+            "{phase} entry has {} fields, expected {expected}",
+            fields.len()
+        ));
+    }
+    Ok(match phase {
+        Phase::Deposit => PhasePayload::Deposit(DepositPayload {
+            party: parse_party(fields[0])?,
+        }),
+        Phase::Commit => PhasePayload::Commit(CommitPayload {
+            com_seeds: parse_hash_list(fields[0])?,
+            root_gcs: parse_hash_list(fields[1])?,
+        }),
+        Phase::Choose => PhasePayload::Choose(ChoosePayload {
+            m: parse_index(fields[0], "m")?,
+        }),
+        Phase::Open => PhasePayload::Open(OpenPayload {
+            seeds: fields[0]
+                .split(',')
+                .filter(|item| !item.is_empty())
+                .map(|item| {
+                    let (idx, seed) = item
+                        .split_once(':')
+                        .ok_or_else(|| format!("invalid opening '{item}'"))?;
+                    Ok((parse_index(idx, "instance id")?, parse_hash(seed)?))
+                })
+                .collect::<Result<_, String>>()?,
+        }),
+        Phase::Eval => PhasePayload::Eval(EvalPayload {
+            output_label: parse_hash(fields[0])?,
+            decoded_bit: match fields[1] {
+                "none" => None,
+                bit => Some(
+                    bit.parse()
+                        .map_err(|_| format!("invalid decoded bit '{bit}'"))?,
+                ),
+            },
+        }),
+        Phase::Dispute => PhasePayload::Dispute(DisputePayload {
+            instance_id: parse_index(fields[0], "instance id")?,
+            gate_index: parse_index(fields[1], "gate index")?,
+        }),
+        Phase::Settle => PhasePayload::Settle(SettlePayload {
+            output: decode_hex(fields[0]).map_err(|e| e.to_string())?,
+        }),
+    })
+}
@@ -0,0 +1,119 @@
+use crate::consensus::{circuit_id_from_layout, layout_leaf_hash};
+use crate::evaluation::millionaires_gt_output_wire;
+use crate::merkle::merkle_root_from_hashes;
+use crate::scenario::{CUT_AND_CHOOSE_N, build_millionaires_layout, build_vickrey_layout};
+use crate::types::{GateDesc, GateSet, SchemeId};
+
+/// Circuit selected when a CLI is not given `--circuit`.
+pub const DEFAULT_CIRCUIT: &str = "millionaires-yao-v1";
+
+/// Consensus parameters both parties must agree on for a circuit version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsensusParams {
+    /// Gate set the layout is restricted to.
+    pub gate_set: GateSet,
+    /// Cut-and-choose instance count the version was specified with.
+    pub cut_and_choose_n: usize,
+    /// Largest supported per-party input width.
+    pub max_bit_width: usize,
+    /// Garbling scheme of every instance (`SchemeId::CLASSIC_V1` for on-chain disputes).
+    pub scheme_id: SchemeId,
+}
+
+/// Named, versioned two-party circuit: Alice's bits are `[0 .. bit_width-1]`, Bob's bits
+/// follow. The decision wire is the single output bit committed through the output anchors.
+#[derive(Debug, Clone, Copy)]
+pub struct CircuitVersion {
+    pub name: &'static str,
+    pub description: &'static str,
+    builder: fn(usize) -> Vec<GateDesc>,
+    decision_wire: fn(&[GateDesc], usize) -> Result<u16, String>,
+    pub params: ConsensusParams,
+}
+
+static CIRCUITS: [CircuitVersion; 2] = [
+    CircuitVersion {
+        name: "millionaires-yao-v1",
+        description: "x > y comparator; decision bit is 1 when Alice's input is larger",
+        builder: |bit_width| build_millionaires_layout(bit_width).gates,
+        decision_wire: millionaires_gt_output_wire,
+        params: ConsensusParams {
+            gate_set: GateSet::V1,
+            cut_and_choose_n: CUT_AND_CHOOSE_N,
+            max_bit_width: 256,
+            scheme_id: SchemeId::CLASSIC_V1,
+        },
+    },
+    CircuitVersion {
+        name: "vickrey-v1",
+        description: "two-bidder second-price auction; decision bit is the winner index",
+        builder: |bit_width| build_vickrey_layout(bit_width, 2).gates,
+        decision_wire: |_, bit_width| Ok(build_vickrey_layout(bit_width, 2).winner_index_wires[0]),
+        params: ConsensusParams {
+            gate_set: GateSet::V1,
+            cut_and_choose_n: CUT_AND_CHOOSE_N,
+            max_bit_width: 256,
+            scheme_id: SchemeId::CLASSIC_V1,
+        },
+    },
+];
+
+/// All registered circuit versions.
+pub fn circuit_versions() -> &'static [CircuitVersion] {
+    &CIRCUITS
+}
+
+/// Looks up a circuit version by name.
+pub fn lookup_circuit(name: &str) -> Result<&'static CircuitVersion, String> {
+    CIRCUITS
+        .iter()
+        .find(|circuit| circuit.name == name)
+        .ok_or_else(|| {
+            let known = CIRCUITS.iter().map(|c| c.name).collect::<Vec<_>>();
+            format!("unknown circuit {name:?}; known: {}", known.join(", "))
+        })
+}
+
+impl CircuitVersion {
+    /// Checks `bit_width` against the version's supported range.
+    pub fn check_bit_width(&self, bit_width: usize) -> Result<(), String> {
+        if bit_width == 0 || bit_width > self.params.max_bit_width {
+            return Err(format!(
+                "{} supports bit widths 1..={}, got {bit_width}",
+                self.name, self.params.max_bit_width
+            ));
+        }
+        Ok(())
+    }
+
+    /// Builds the deterministic gate list. Panics on an unsupported `bit_width`;
+    /// call `check_bit_width` first for user input.
+    pub fn build(&self, bit_width: usize) -> Vec<GateDesc> {
+        self.check_bit_width(bit_width)
+            .unwrap_or_else(|e| panic!("{e}"));
+        let gates = (self.builder)(bit_width);
+        debug_assert!(self.params.gate_set.check_gates(&gates).is_ok());
+        gates
+    }
+
+    /// Output wire carrying the decision bit for `build(bit_width)`.
+    pub fn decision_wire(&self, gates: &[GateDesc], bit_width: usize) -> Result<u16, String> {
+        (self.decision_wire)(gates, bit_width)
+    }
+
+    /// Layout-bound circuit id under `winner_formula` (`circuit_id_from_layout`).
+    pub fn circuit_id(&self, bit_width: usize, winner_formula: u8) -> [u8; 32] {
+        circuit_id_from_layout(&self.build(bit_width), winner_formula)
+    }
+
+    /// Merkle root over `layout_leaf_hash` entries, as passed to the contract constructor.
+    pub fn layout_root(&self, circuit_id: [u8; 32], bit_width: usize) -> [u8; 32] {
+        let hashes = self
+            .build(bit_width)
+            .iter()
+            .enumerate()
+            .map(|(idx, gate)| layout_leaf_hash(circuit_id, idx as u64, *gate))
+            .collect::<Vec<_>>();
+        merkle_root_from_hashes(&hashes)
+    }
+}
@@ -0,0 +1,691 @@
+//! Relayer mode: a third-party account pays the gas of Alice's or Bob's transactions so a
+//! party without ETH for gas can still take part.
+//!
+//! The contract authenticates parties by `msg.sender`, so the party still signs its own
+//! EIP-155 legacy transaction and sends it with the call it prepared to the relayer's
+//! endpoint (`RELAYER_URL`, a `relay` daemon holding `RELAYER_PRIVATE_KEY` in its own
+//! process). The relayer decodes the signed bytes, recovers the signer and checks it,
+//! target, value, chain and calldata against the prepared call, only sponsors calls to its
+//! own contract and chain, tops up exactly the party's gas shortfall (never the call value,
+//! e.g. a deposit) and broadcasts the raw bytes. The transaction hash commits to the signed
+//! calldata, so the party checks the mined hash against its own bytes.
+//!
+//! The signer picks the gas price and limit and can replace a sponsored transaction at the
+//! same nonce to keep the top-up, so the relayer only accepts gas terms close to its own
+//! `cast gas-price` and `cast estimate` (`check_gas_terms`) and caps each top-up and their
+//! total (`SponsorLimits`).
+
+use serde_json::{Value, json};
+
+use crate::cli::{
+    CliResult, cast_output_field, config_var, decode_hex, hex_prefixed, parse_bytes32,
+    parse_fixed_bytes, parse_flag_value, parse_number, required_flag_value, run_cast_direct,
+};
+use crate::consensus::keccak256;
+use crate::emitln;
+use crate::identity::{EthIdentity, recover_prehash};
+use crate::ipfs::read_response;
+
+/// JSON-RPC method of a relayer endpoint.
+pub const RELAY_METHOD: &str = "relay";
+
+/// Largest relayer response read.
+const MAX_RELAY_RESPONSE: u64 = 1 << 16;
+
+/// Most a relayed transaction may bid, as a multiple of the relayer's `cast gas-price`.
+pub const MAX_GAS_PRICE_FACTOR: u128 = 2;
+
+/// Default `RELAYER_MAX_TOPUP_WEI`: 0.01 ETH.
+pub const DEFAULT_MAX_TOPUP_WEI: u128 = 10_000_000_000_000_000;
+
+/// Default `RELAYER_TOTAL_TOPUP_WEI`: 0.1 ETH.
+pub const DEFAULT_TOTAL_TOPUP_WEI: u128 = 100_000_000_000_000_000;
+
+/// EIP-155 legacy transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegacyTx {
+    pub nonce: u64,
+    pub gas_price: u128,
+    pub gas_limit: u64,
+    pub to: [u8; 20],
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub chain_id: u64,
+}
+
+/// A decoded signed transaction with its recovered sender.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedTx {
+    pub tx: LegacyTx,
+    pub from: [u8; 20],
+    /// `keccak256(raw)`, the hash the network reports.
+    pub hash: [u8; 32],
+}
+
+/// The call a party prepared; the relayer must submit exactly this.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreparedCall {
+    pub from: [u8; 20],
+    pub to: [u8; 20],
+    pub value: u128,
+    pub data: Vec<u8>,
+    pub chain_id: u64,
+}
+
+impl PreparedCall {
+    /// `params` of a `RELAY_METHOD` call submitting `raw` for this call.
+    pub fn relay_params(&self, raw: &[u8]) -> Value {
+        json!({
+            "raw": hex_prefixed(raw),
+            "from": hex_prefixed(&self.from),
+            "to": hex_prefixed(&self.to),
+            "value": self.value.to_string(),
+            "data": hex_prefixed(&self.data),
+            "chain-id": self.chain_id,
+        })
+    }
+
+    /// The call and raw transaction of `relay_params`, as the daemon passes them on
+    /// (`--raw 0x.. --from 0x..` ...).
+    pub fn from_relay_args(args: &[String]) -> CliResult<(Self, Vec<u8>)> {
+        let value = required_flag_value(args, "--value")?;
+        let chain_id = required_flag_value(args, "--chain-id")?;
+        let call = Self {
+            from: parse_fixed_bytes::<20>(&required_flag_value(args, "--from")?)?,
+            to: parse_fixed_bytes::<20>(&required_flag_value(args, "--to")?)?,
+            value: value
+                .parse()
+                .map_err(|_| format!("invalid --value {value}"))?,
+            data: decode_hex(&parse_flag_value(args, "--data").unwrap_or_default())?,
+            chain_id: chain_id
+                .parse()
+                .map_err(|_| format!("invalid --chain-id {chain_id}"))?,
+        };
+        Ok((call, decode_hex(&required_flag_value(args, "--raw")?)?))
+    }
+}
+
+fn rlp_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    match bytes {
+        [single] if *single < 0x80 => out.push(*single),
+        _ => {
+            rlp_length(out, 0x80, bytes.len());
+            out.extend_from_slice(bytes);
+        }
+    }
+}
+
+fn rlp_uint(out: &mut Vec<u8>, value: u128) {
+    let be = value.to_be_bytes();
+    let start = be.iter().position(|b| *b != 0).unwrap_or(be.len());
+    rlp_bytes(out, &be[start..]);
+}
+
+fn rlp_length(out: &mut Vec<u8>, offset: u8, len: usize) {
+    if len <= 55 {
+        out.push(offset + len as u8);
+    } else {
+        let be = len.to_be_bytes();
+        let start = be.iter().position(|b| *b != 0).unwrap_or(be.len());
+        out.push(offset + 55 + (be.len() - start) as u8);
+        out.extend_from_slice(&be[start..]);
+    }
+}
+
+fn rlp_list(payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 9);
+    rlp_length(&mut out, 0xc0, payload.len());
+    out.extend_from_slice(payload);
+    out
+}
+
+fn rlp_long_length(raw: &[u8], pos: usize, len_len: usize) -> Result<(usize, usize), String> {
+    let len_bytes = raw
+        .get(pos + 1..pos + 1 + len_len)
+        .ok_or("truncated RLP length")?;
+    if len_len > 8 || len_bytes[0] == 0 {
+        return Err("non-canonical RLP length".to_string());
+    }
+    let len = len_bytes
+        .iter()
+        .fold(0usize, |acc, b| (acc << 8) | *b as usize);
+    Ok((pos + 1 + len_len, len))
+}
+
+/// Reads the RLP header at `raw[pos]`: `(is_list, payload_start, payload_len)`.
+fn rlp_header(raw: &[u8], pos: usize) -> Result<(bool, usize, usize), String> {
+    let first = *raw.get(pos).ok_or("truncated RLP")?;
+    let (is_list, (start, len)) = match first {
+        0x00..=0x7f => (false, (pos, 1)),
+        0x80..=0xb7 => (false, (pos + 1, (first - 0x80) as usize)),
+        0xb8..=0xbf => (false, rlp_long_length(raw, pos, (first - 0xb7) as usize)?),
+        0xc0..=0xf7 => (true, (pos + 1, (first - 0xc0) as usize)),
+        0xf8..=0xff => (true, rlp_long_length(raw, pos, (first - 0xf7) as usize)?),
+    };
+    if start.checked_add(len).is_none_or(|end| end > raw.len()) {
+        return Err("truncated RLP item".to_string());
+    }
+    Ok((is_list, start, len))
+}
+
+/// Splits an RLP list of byte strings into its items.
+fn rlp_decode_flat_list(raw: &[u8]) -> Result<Vec<&[u8]>, String> {
+    let (is_list, start, len) = rlp_header(raw, 0)?;
+    if !is_list || start + len != raw.len() {
+        return Err("signed transaction must be a single RLP list".to_string());
+    }
+    let mut items = Vec::new();
+    let mut pos = start;
+    while pos < start + len {
+        let (nested, item_start, item_len) = rlp_header(raw, pos)?;
+        if nested {
+            return Err("unexpected nested RLP list".to_string());
+        }
+        items.push(&raw[item_start..item_start + item_len]);
+        pos = item_start + item_len;
+    }
+    if pos != start + len {
+        return Err("RLP item overruns its list".to_string());
+    }
+    Ok(items)
+}
+
+fn decode_uint(bytes: &[u8], name: &str, max_len: usize) -> Result<u128, String> {
+    if bytes.len() > max_len || bytes.first() == Some(&0) {
+        return Err(format!("invalid transaction {name}"));
+    }
+    Ok(bytes
+        .iter()
+        .fold(0u128, |acc, b| (acc << 8) | u128::from(*b)))
+}
+
+impl LegacyTx {
+    fn encode(&self, signature: Option<(u128, &[u8], &[u8])>) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(self.data.len() + 128);
+        rlp_uint(&mut payload, self.nonce.into());
+        rlp_uint(&mut payload, self.gas_price);
+        rlp_uint(&mut payload, self.gas_limit.into());
+        rlp_bytes(&mut payload, &self.to);
+        rlp_uint(&mut payload, self.value);
+        rlp_bytes(&mut payload, &self.data);
+        match signature {
+            Some((v, r, s)) => {
+                rlp_uint(&mut payload, v);
+                for part in [r, s] {
+                    let start = part.iter().position(|b| *b != 0).unwrap_or(part.len());
+                    rlp_bytes(&mut payload, &part[start..]);
+                }
+            }
+            None => {
+                rlp_uint(&mut payload, self.chain_id.into());
+                rlp_uint(&mut payload, 0);
+                rlp_uint(&mut payload, 0);
+            }
+        }
+        rlp_list(&payload)
+    }
+
+    /// EIP-155 signing hash:
+    /// `keccak256(rlp([nonce, gasPrice, gas, to, value, data, chainId, 0, 0]))`.
+    pub fn signing_hash(&self) -> [u8; 32] {
+        keccak256(&[&self.encode(None)])
+    }
+
+    /// Signed raw transaction bytes, ready for `eth_sendRawTransaction`.
+    pub fn sign(&self, identity: &EthIdentity) -> Result<Vec<u8>, String> {
+        let (signature, recovery_id) = identity.sign_prehash(self.signing_hash())?;
+        let v = u128::from(self.chain_id) * 2 + 35 + u128::from(recovery_id);
+        Ok(self.encode(Some((v, &signature[..32], &signature[32..]))))
+    }
+
+    /// Most the sender can be charged: `gasLimit * gasPrice + value`.
+    pub fn max_cost(&self) -> Result<u128, String> {
+        self.gas_price
+            .checked_mul(self.gas_limit.into())
+            .and_then(|gas| gas.checked_add(self.value))
+            .ok_or_else(|| "transaction cost overflows u128".to_string())
+    }
+}
+
+/// Decodes an EIP-155 legacy transaction and recovers its sender.
+pub fn decode_signed_tx(raw: &[u8]) -> Result<SignedTx, String> {
+    let items = rlp_decode_flat_list(raw)?;
+    let [nonce, gas_price, gas_limit, to, value, data, v, r, s] = items[..] else {
+        return Err(format!(
+            "expected 9 legacy transaction fields, got {} (typed transactions are not relayed)",
+            items.len()
+        ));
+    };
+    let to: [u8; 20] = to
+        .try_into()
+        .map_err(|_| "relayed transactions must call a contract address".to_string())?;
+    let v = decode_uint(v, "v", 8)?;
+    if v < 35 {
+        return Err(format!("transaction v={v} is not EIP-155 replay-protected"));
+    }
+    let tx = LegacyTx {
+        nonce: decode_uint(nonce, "nonce", 8)? as u64,
+        gas_price: decode_uint(gas_price, "gas price", 16)?,
+        gas_limit: decode_uint(gas_limit, "gas limit", 8)? as u64,
+        to,
+        value: decode_uint(value, "value", 16)?,
+        data: data.to_vec(),
+        chain_id: ((v - 35) / 2) as u64,
+    };
+    if r.len() > 32 || s.len() > 32 {
+        return Err("invalid transaction signature".to_string());
+    }
+    let mut signature = [0u8; 64];
+    signature[32 - r.len()..32].copy_from_slice(r);
+    signature[64 - s.len()..].copy_from_slice(s);
+    let from = recover_prehash(tx.signing_hash(), &signature, ((v - 35) % 2) as u8)?;
+    Ok(SignedTx {
+        tx,
+        from,
+        hash: keccak256(&[raw]),
+    })
+}
+
+/// Decodes `raw`, recovers its signer and checks that it performs exactly `prepared`.
+pub fn check_signed_call(prepared: &PreparedCall, raw: &[u8]) -> Result<SignedTx, String> {
+    let signed = decode_signed_tx(raw)?;
+    let tx = &signed.tx;
+    if signed.from != prepared.from {
+        return Err(format!(
+            "transaction signed by {}, prepared for {}",
+            hex_prefixed(&signed.from),
+            hex_prefixed(&prepared.from)
+        ));
+    }
+    if tx.to != prepared.to {
+        return Err(format!(
+            "transaction calls {}, prepared for {}",
+            hex_prefixed(&tx.to),
+            hex_prefixed(&prepared.to)
+        ));
+    }
+    if tx.chain_id != prepared.chain_id {
+        return Err(format!(
+            "transaction is for chain {}, prepared for chain {}",
+            tx.chain_id, prepared.chain_id
+        ));
+    }
+    if tx.value != prepared.value {
+        return Err(format!(
+            "transaction value {} differs from prepared value {}",
+            tx.value, prepared.value
+        ));
+    }
+    if tx.data != prepared.data {
+        return Err("transaction calldata differs from the prepared calldata".to_string());
+    }
+    Ok(signed)
+}
+
+/// Wei the relayer must send so `signed` can pay for gas. The relayer never funds the call
+/// value: a party that cannot cover it itself is rejected.
+pub fn gas_shortfall(signed: &SignedTx, balance: u128) -> Result<u128, String> {
+    if balance < signed.tx.value {
+        return Err(format!(
+            "sender balance {balance} wei does not cover the call value {} wei",
+            signed.tx.value
+        ));
+    }
+    Ok(signed.tx.max_cost()?.saturating_sub(balance))
+}
+
+/// Most gas a relayed call may reserve for the relayer's `estimate`: the party adds 20%, the
+/// relayer allows 50% so a slightly different estimate on the party's side still passes.
+pub fn gas_limit_cap(estimate: u64) -> u64 {
+    estimate.saturating_add(estimate / 2)
+}
+
+/// Checks the gas terms the signer picked against the relayer's own view of the chain: a gas
+/// price of at most `MAX_GAS_PRICE_FACTOR` times `node_gas_price` and a gas limit of at most
+/// `gas_limit_cap(estimate)`.
+pub fn check_gas_terms(tx: &LegacyTx, node_gas_price: u128, estimate: u64) -> Result<(), String> {
+    let max_gas_price = node_gas_price.saturating_mul(MAX_GAS_PRICE_FACTOR);
+    if tx.gas_price > max_gas_price {
+        return Err(format!(
+            "gas price {} wei exceeds the relayer's limit of {max_gas_price} wei",
+            tx.gas_price
+        ));
+    }
+    let max_gas_limit = gas_limit_cap(estimate);
+    if tx.gas_limit > max_gas_limit {
+        return Err(format!(
+            "gas limit {} exceeds the relayer's limit of {max_gas_limit} (estimate {estimate})",
+            tx.gas_limit
+        ));
+    }
+    Ok(())
+}
+
+/// Bounds on the wei a relayer tops up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SponsorLimits {
+    /// Most wei sent for one call (`RELAYER_MAX_TOPUP_WEI`).
+    pub max_topup_wei: u128,
+    /// Most wei sent over the relayer's run (`RELAYER_TOTAL_TOPUP_WEI`).
+    pub total_topup_wei: u128,
+}
+
+impl Default for SponsorLimits {
+    fn default() -> Self {
+        Self {
+            max_topup_wei: DEFAULT_MAX_TOPUP_WEI,
+            total_topup_wei: DEFAULT_TOTAL_TOPUP_WEI,
+        }
+    }
+}
+
+impl SponsorLimits {
+    /// `RELAYER_MAX_TOPUP_WEI` and `RELAYER_TOTAL_TOPUP_WEI`, each defaulting as in `default`.
+    pub fn from_env() -> CliResult<Self> {
+        let defaults = Self::default();
+        Ok(Self {
+            max_topup_wei: wei_var("RELAYER_MAX_TOPUP_WEI", defaults.max_topup_wei)?,
+            total_topup_wei: wei_var("RELAYER_TOTAL_TOPUP_WEI", defaults.total_topup_wei)?,
+        })
+    }
+
+    /// Checks a `topup` after `spent` wei were already topped up.
+    pub fn check(&self, topup: u128, spent: u128) -> Result<(), String> {
+        if topup > self.max_topup_wei {
+            return Err(format!(
+                "top-up of {topup} wei exceeds the relayer's per-call limit of {} wei",
+                self.max_topup_wei
+            ));
+        }
+        if spent.saturating_add(topup) > self.total_topup_wei {
+            return Err(format!(
+                "top-up of {topup} wei exceeds the relayer's remaining budget of {} wei",
+                self.total_topup_wei.saturating_sub(spent)
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn wei_var(name: &str, default: u128) -> CliResult<u128> {
+    let Some(raw) = config_var(name) else {
+        return Ok(default);
+    };
+    let number = parse_number(&raw, name)?;
+    let (high, low) = number.split_at(16);
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(format!("Invalid {name}: {raw} exceeds 128 bits").into());
+    }
+    Ok(u128::from_be_bytes(low.try_into().expect("16 bytes")))
+}
+
+fn cast_uint(args: &[&str], name: &str) -> CliResult<u128> {
+    let raw = run_cast_direct(&args.iter().map(|a| a.to_string()).collect::<Vec<_>>())?;
+    // `cast` may append a scientific-notation hint, e.g. `10 [1e1]`.
+    let value = raw.split_whitespace().next().unwrap_or("");
+    value
+        .parse::<u128>()
+        .map_err(|_| format!("invalid {name} from cast: {raw}").into())
+}
+
+/// Relayer account sponsoring the gas of calls to one contract, run by the `relay` daemon.
+pub struct Relayer {
+    identity: EthIdentity,
+    private_key: String,
+    contract: [u8; 20],
+    chain_id: u64,
+    rpc_url: String,
+    limits: SponsorLimits,
+    /// Wei topped up so far, checked against `limits.total_topup_wei`.
+    topped_up: u128,
+}
+
+impl std::fmt::Debug for Relayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Relayer")
+            .field("address", &hex_prefixed(&self.identity.address()))
+            .field("contract", &hex_prefixed(&self.contract))
+            .field("chain_id", &self.chain_id)
+            .field("limits", &self.limits)
+            .field("topped_up", &self.topped_up)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Relayer {
+    pub fn new(
+        private_key: &str,
+        contract_address: &str,
+        rpc_url: &str,
+        limits: SponsorLimits,
+    ) -> CliResult<Self> {
+        Ok(Self {
+            identity: EthIdentity::from_private_key(parse_bytes32(private_key)?)?,
+            private_key: private_key.to_string(),
+            contract: parse_fixed_bytes::<20>(contract_address)?,
+            chain_id: cast_uint(&["chain-id", "--rpc-url", rpc_url], "chain id")? as u64,
+            rpc_url: rpc_url.to_string(),
+            limits,
+            topped_up: 0,
+        })
+    }
+
+    pub fn address(&self) -> [u8; 20] {
+        self.identity.address()
+    }
+
+    /// Checks `raw` against `prepared`, which must call this relayer's contract on its
+    /// chain, and its gas terms against the node; tops up the signer's gas shortfall within
+    /// `limits` and publishes `raw`. Returns the transaction hash and the top-up in wei.
+    pub fn sponsor(&mut self, prepared: &PreparedCall, raw: &[u8]) -> CliResult<([u8; 32], u128)> {
+        if prepared.to != self.contract {
+            return Err(format!(
+                "relayer only sponsors calls to {}, not {}",
+                hex_prefixed(&self.contract),
+                hex_prefixed(&prepared.to)
+            )
+            .into());
+        }
+        if prepared.chain_id != self.chain_id {
+            return Err(format!(
+                "relayer runs on chain {}, not chain {}",
+                self.chain_id, prepared.chain_id
+            )
+            .into());
+        }
+        let signed = check_signed_call(prepared, raw)?;
+        let from = hex_prefixed(&signed.from);
+        let node_gas_price = cast_uint(&["gas-price", "--rpc-url", &self.rpc_url], "gas price")?;
+        let estimate = cast_uint(
+            &[
+                "estimate",
+                "--from",
+                &from,
+                &hex_prefixed(&signed.tx.to),
+                &hex_prefixed(&signed.tx.data),
+                "--value",
+                &signed.tx.value.to_string(),
+                "--rpc-url",
+                &self.rpc_url,
+            ],
+            "gas estimate",
+        )?;
+        check_gas_terms(&signed.tx, node_gas_price, estimate as u64)?;
+        let balance = cast_uint(&["balance", &from, "--rpc-url", &self.rpc_url], "balance")?;
+        let topup = gas_shortfall(&signed, balance)?;
+        self.limits.check(topup, self.topped_up)?;
+        if topup > 0 {
+            run_cast_direct(&[
+                "send".to_string(),
+                from,
+                "--value".to_string(),
+                topup.to_string(),
+                "--private-key".to_string(),
+                self.private_key.clone(),
+                "--rpc-url".to_string(),
+                self.rpc_url.clone(),
+            ])?;
+            self.topped_up += topup;
+        }
+        run_cast_direct(&[
+            "publish".to_string(),
+            hex_prefixed(raw),
+            "--rpc-url".to_string(),
+            self.rpc_url.clone(),
+        ])?;
+        Ok((signed.hash, topup))
+    }
+
+    /// Answers a `RELAY_METHOD` call with `key=value` lines.
+    pub fn relay(&mut self, args: &[String]) -> CliResult<String> {
+        let (prepared, raw) = PreparedCall::from_relay_args(args)?;
+        let (tx_hash, topup) = self.sponsor(&prepared, &raw)?;
+        Ok(format!(
+            "status=relayed\nrelayer={}\ntopup_wei={topup}\ntx_hash={}\n",
+            hex_prefixed(&self.address()),
+            hex_prefixed(&tx_hash)
+        ))
+    }
+}
+
+/// Submits `params` to the relayer endpoint at `url` with its bearer `token`; returns the
+/// call's `result` object.
+fn call_relayer(url: &str, token: &str, params: Value) -> CliResult<Value> {
+    let body = json!({ "jsonrpc": "2.0", "id": 1, "method": RELAY_METHOD, "params": params });
+    let response = ureq::post(url)
+        .set("Authorization", &format!("Bearer {token}"))
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string());
+    let response = read_response(url, response, MAX_RELAY_RESPONSE)?;
+    let response: Value = serde_json::from_slice(&response)
+        .map_err(|e| format!("relayer {url} returned invalid JSON: {e}"))?;
+    if let Some(error) = response.get("error") {
+        return Err(format!("relayer {url} refused the transaction: {error}").into());
+    }
+    response
+        .get("result")
+        .cloned()
+        .ok_or_else(|| format!("relayer {url} returned no result").into())
+}
+
+/// Runs `cast send <to> <sig> [args..] [--value v] --private-key <party> --rpc-url <url>`
+/// (or `<to> 0x<calldata>`) through the relayer endpoint at `relayer_url`: the party signs,
+/// the relayer checks, funds gas and broadcasts.
+/// Returns the `cast receipt` output, so callers read it like `cast send` output.
+pub fn relay_send(
+    send_args: &[String],
+    relayer_url: &str,
+    relayer_token: &str,
+) -> CliResult<String> {
+    let flag = |name: &str| {
+        send_args
+            .iter()
+            .position(|arg| arg == name)
+            .and_then(|idx| send_args.get(idx + 1))
+            .cloned()
+    };
+    if send_args.iter().any(|arg| arg == "--blob") {
+        return Err("blob transactions cannot be relayed".into());
+    }
+    let rpc_url = flag("--rpc-url").ok_or("relayed send needs --rpc-url")?;
+    let party_key = flag("--private-key").ok_or("relayed send needs --private-key")?;
+    let positional = send_args[1..]
+        .iter()
+        .take_while(|arg| !arg.starts_with("--"))
+        .cloned()
+        .collect::<Vec<_>>();
+    let [to, signature, call_args @ ..] = &positional[..] else {
+        return Err("relayed send needs <to> <signature> [args..]".into());
+    };
+    let value = flag("--value")
+        .map(|v| {
+            v.parse::<u128>()
+                .map_err(|_| format!("invalid --value {v}"))
+        })
+        .transpose()?
+        .unwrap_or(0);
+
+    let identity = EthIdentity::from_private_key(parse_bytes32(&party_key)?)?;
+    let from = hex_prefixed(&identity.address());
+    // `send_call` passes encoded calldata where a signature would go; `cast` accepts both.
+    let data = if signature.starts_with("0x") && call_args.is_empty() {
+        decode_hex(signature)?
+    } else {
+        let mut calldata_args = vec!["calldata".to_string(), signature.clone()];
+        calldata_args.extend(call_args.iter().cloned());
+        decode_hex(&run_cast_direct(&calldata_args)?)?
+    };
+    let mut estimate_args = vec![
+        "estimate".to_string(),
+        "--from".to_string(),
+        from.clone(),
+        to.clone(),
+        signature.clone(),
+    ];
+    estimate_args.extend(call_args.iter().cloned());
+    estimate_args.extend([
+        "--value".to_string(),
+        value.to_string(),
+        "--rpc-url".to_string(),
+        rpc_url.clone(),
+    ]);
+    let estimate = cast_uint(
+        &estimate_args.iter().map(String::as_str).collect::<Vec<_>>(),
+        "gas estimate",
+    )?;
+    let gas_price = match flag("--gas-price") {
+        Some(price) => price
+            .parse::<u128>()
+            .map_err(|_| format!("invalid --gas-price {price}"))?,
+        None => cast_uint(&["gas-price", "--rpc-url", &rpc_url], "gas price")?,
+    };
+    let prepared = PreparedCall {
+        from: identity.address(),
+        to: parse_fixed_bytes::<20>(to)?,
+        value,
+        data,
+        chain_id: cast_uint(&["chain-id", "--rpc-url", &rpc_url], "chain id")? as u64,
+    };
+    let tx = LegacyTx {
+        nonce: cast_uint(&["nonce", &from, "--rpc-url", &rpc_url], "nonce")? as u64,
+        gas_price,
+        // 20% headroom over the estimate.
+        gas_limit: (estimate + estimate / 5) as u64,
+        to: prepared.to,
+        value,
+        data: prepared.data.clone(),
+        chain_id: prepared.chain_id,
+    };
+    let raw = tx.sign(&identity)?;
+
+    let result = call_relayer(relayer_url, relayer_token, prepared.relay_params(&raw))?;
+    let field = |key: &str| {
+        result
+            .get(key)
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| format!("relayer {relayer_url} returned no {key}"))
+    };
+    let hash = keccak256(&[&raw]);
+    if !field("tx_hash")?.eq_ignore_ascii_case(&hex_prefixed(&hash)) {
+        return Err(format!(
+            "relayer {relayer_url} reported another transaction than the one signed"
+        )
+        .into());
+    }
+    let tx_hash = hex_prefixed(&hash);
+    let receipt = run_cast_direct(&[
+        "receipt".to_string(),
+        tx_hash.clone(),
+        "--rpc-url".to_string(),
+        rpc_url,
+    ])?;
+    let mined_from = cast_output_field(&receipt, "from");
+    if mined_from.is_some_and(|mined| !mined.eq_ignore_ascii_case(&from)) {
+        return Err(format!("mined transaction {tx_hash} was not sent by {from}").into());
+    }
+    emitln!("relayer={}", field("relayer")?);
+    emitln!("relayer_topup_wei={}", field("topup_wei")?);
+    emitln!("relayed_tx_hash={tx_hash}");
+    Ok(receipt)
+}
@@ -0,0 +1,133 @@
+//! Retry with exponential backoff for the transport and the chain RPC layer.
+//!
+//! A multi-phase run spends minutes talking to the peer and to the node; a dropped TCP
+//! connection or a rate-limited RPC call should cost one backoff, not the command. Only errors
+//! that `is_transient_error` recognises are retried, everything else (reverts, bad input,
+//! authentication failures) is returned at once. Resends stay safe through idempotency: file
+//! batches carry a `transport::batch_key` the receiver deduplicates on, and `cast send` is only
+//! retried when the node was never reached (`is_unsent_error`).
+
+use std::env;
+use std::thread;
+use std::time::Duration;
+
+/// How often and how patiently an operation is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first; `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the second attempt; doubled for every further attempt.
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A single attempt.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Default policy overridden by `RETRY_ATTEMPTS` and `RETRY_BACKOFF_MS`.
+    pub fn from_env() -> Result<Self, String> {
+        let mut policy = Self::default();
+        if let Ok(raw) = env::var("RETRY_ATTEMPTS") {
+            policy.max_attempts = raw
+                .trim()
+                .parse()
+                .ok()
+                .filter(|attempts| *attempts > 0)
+                .ok_or_else(|| format!("RETRY_ATTEMPTS must be a positive integer, got '{raw}'"))?;
+        }
+        if let Ok(raw) = env::var("RETRY_BACKOFF_MS") {
+            let millis = raw
+                .trim()
+                .parse()
+                .map_err(|_| format!("RETRY_BACKOFF_MS must be milliseconds, got '{raw}'"))?;
+            policy.initial_delay = Duration::from_millis(millis);
+        }
+        Ok(policy)
+    }
+
+    /// Delay after failed attempt `attempt` (1-based): `initial_delay * 2^(attempt-1)`, capped.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+/// Connection drops, timeouts and rate limits: failures that a later attempt may not hit.
+pub fn is_transient_error(err: &str) -> bool {
+    const TRANSIENT: &[&str] = &[
+        "connection refused",
+        "connection reset",
+        "connection aborted",
+        "broken pipe",
+        "timed out",
+        "timeout",
+        "temporarily unavailable",
+        "unexpected end of file",
+        "failed to fill whole buffer",
+        "error sending request",
+        "too many requests",
+        "rate limit",
+        "error 429",
+        "502 bad gateway",
+        "503 service unavailable",
+        "504 gateway timeout",
+    ];
+    let err = err.to_ascii_lowercase();
+    TRANSIENT.iter().any(|pattern| err.contains(pattern))
+}
+
+/// Failures that happened before the request reached the node, so resending a transaction
+/// cannot broadcast it twice.
+pub fn is_unsent_error(err: &str) -> bool {
+    err.to_ascii_lowercase().contains("connection refused")
+}
+
+/// Runs `op` (given the 1-based attempt number) until it succeeds, fails with an error
+/// `retryable` rejects, or `policy.max_attempts` is used up. `sleep` waits out the backoff.
+pub fn retry_with<T>(
+    policy: &RetryPolicy,
+    retryable: impl Fn(&str) -> bool,
+    mut sleep: impl FnMut(Duration),
+    mut op: impl FnMut(u32) -> Result<T, String>,
+) -> Result<T, String> {
+    let mut attempt = 1;
+    loop {
+        match op(attempt) {
+            Ok(value) => return Ok(value),
+            Err(err) if attempt < policy.max_attempts && retryable(&err) => {
+                let delay = policy.delay(attempt);
+                tracing::debug!(attempt, ?delay, error = %err, "retrying");
+                sleep(delay);
+                attempt += 1;
+            }
+            Err(err) if attempt > 1 => return Err(format!("{err} (after {attempt} attempts)")),
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// `retry_with` on transient errors, sleeping the calling thread between attempts.
+pub fn retry<T>(
+    policy: &RetryPolicy,
+    op: impl FnMut(u32) -> Result<T, String>,
+) -> Result<T, String> {
+    retry_with(policy, is_transient_error, thread::sleep, op)
+}
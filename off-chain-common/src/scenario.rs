@@ -1,97 +1,368 @@
+pub mod beacon;
+pub mod faults;
+pub mod participants;
+pub mod simulation;
+
+pub use simulation::{
+    SimulatedEvaluation, SimulatedInstance, SimulatedSession, SimulationConfig, simulate_session,
+};
+
+use std::fmt;
+
+use crate::builder::CircuitBuilder;
 use crate::consensus::{keccak256, uint256_from_u64};
-use crate::types::{GateDesc, GateType};
+use crate::types::GateDesc;
 
 /// Number of circuit instances used in cut-and-choose for this MVP flow.
 pub const CUT_AND_CHOOSE_N: usize = 10;
 
-/// Internal helper: append one gate and allocate a fresh output wire.
-fn push_gate(
-    gates: &mut Vec<GateDesc>,
-    next_wire: &mut u16,
-    gate_type: GateType,
-    a: u16,
-    b: u16,
-) -> u16 {
-    let out = *next_wire;
-    // New gate writes into the next free wire index.
-    gates.push(GateDesc::new(gate_type, a, b, out));
-    *next_wire = next_wire.saturating_add(1);
-    out
+/// Cut-and-choose session parameters shared by both parties.
+/// `n` must equal the contract-configured `N()`, which also fixes the `[N]` array length
+/// in the commitment calldata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionParams {
+    /// Number of garbled instances committed per session.
+    pub n: usize,
 }
 
-/// Internal helper for XOR gate creation.
-fn push_xor(gates: &mut Vec<GateDesc>, next_wire: &mut u16, a: u16, b: u16) -> u16 {
-    push_gate(gates, next_wire, GateType::Xor, a, b)
+impl Default for SessionParams {
+    fn default() -> Self {
+        Self {
+            n: CUT_AND_CHOOSE_N,
+        }
+    }
 }
 
-/// Internal helper for AND gate creation.
-fn push_and(gates: &mut Vec<GateDesc>, next_wire: &mut u16, a: u16, b: u16) -> u16 {
-    push_gate(gates, next_wire, GateType::And, a, b)
+/// Formats as the instance count, the form `--n` takes.
+impl fmt::Display for SessionParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.n)
+    }
 }
 
-/// Internal helper for NOT gate creation.
-fn push_not(gates: &mut Vec<GateDesc>, next_wire: &mut u16, a: u16) -> u16 {
-    push_gate(gates, next_wire, GateType::Not, a, 0)
+impl SessionParams {
+    /// Validates `n`: one instance is evaluated and the rest are opened, so `n >= 2`.
+    pub fn new(n: usize) -> Result<Self, String> {
+        if n < 2 {
+            return Err(format!("cut-and-choose n must be >= 2, got {n}"));
+        }
+        Ok(Self { n })
+    }
+
+    /// Checks that `m` selects one of the `n` instances.
+    pub fn check_m(&self, m: usize) -> Result<(), String> {
+        if m >= self.n {
+            return Err(format!("m={m} out of range [0, {})", self.n));
+        }
+        Ok(())
+    }
+
+    /// Checks `n` against the `N()` value reported by the contract.
+    pub fn check_contract_n(&self, contract_n: u64) -> Result<(), String> {
+        if contract_n != self.n as u64 {
+            return Err(format!(
+                "cut-and-choose n={} does not match contract N={contract_n}",
+                self.n
+            ));
+        }
+        Ok(())
+    }
 }
 
-/// Internal OR helper implemented as `(a XOR b) XOR (a AND b)`.
-fn push_or(gates: &mut Vec<GateDesc>, next_wire: &mut u16, a: u16, b: u16) -> u16 {
-    let xor_ab = push_xor(gates, next_wire, a, b);
-    let and_ab = push_and(gates, next_wire, a, b);
-    push_xor(gates, next_wire, xor_ab, and_ab)
+/// Gate list plus output wires of the Millionaires comparison circuit.
+/// Together the two bits distinguish `x > y` (`gt`), `x == y` (`eq`) and `x < y` (neither).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MillionairesLayout {
+    pub gates: Vec<GateDesc>,
+    /// `x > y`
+    pub gt_wire: u16,
+    /// `x == y`
+    pub eq_wire: u16,
 }
 
 /// Builds a deterministic Millionaires-comparison circuit layout for `bit_width`-bit inputs.
 /// Input wire convention:
 /// - Alice bits: `[0 .. bit_width-1]`
 /// - Bob bits: `[bit_width .. 2*bit_width-1]`
-pub fn build_millionaires_layout(bit_width: usize) -> Vec<GateDesc> {
+pub fn build_millionaires_layout(bit_width: usize) -> MillionairesLayout {
+    assert!(bit_width > 0, "bit_width must be > 0");
+    assert!(bit_width <= (u16::MAX as usize) / 4, "bit_width too large");
+
+    let mut builder = CircuitBuilder::new();
+    // Reserve input wires first: A bits then B bits.
+    let a_bits = builder.inputs(bit_width);
+    let b_bits = builder.inputs(bit_width);
+    let (gt_wire, eq_wire) = builder.compare(&a_bits, &b_bits);
+
+    MillionairesLayout {
+        gates: builder.finish().gates,
+        gt_wire,
+        eq_wire,
+    }
+}
+
+/// Gate list plus output wires of a max-of-two circuit that reveals the winning bid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MaxOfTwoLayout {
+    pub gates: Vec<GateDesc>,
+    /// `x > y`, i.e. Alice wins.
+    pub gt_wire: u16,
+    /// Little-endian bits of `max(x, y)`, the clearing price.
+    pub max_wires: Vec<u16>,
+}
+
+/// Builds a deterministic max-of-two layout: the Millionaires comparator followed by a
+/// bitwise MUX of `x`/`y` on the `gt` wire. Uses the same input wire convention as
+/// `build_millionaires_layout`; on ties the (equal) `y` bits are selected.
+pub fn build_max_of_two_layout(bit_width: usize) -> MaxOfTwoLayout {
+    assert!(bit_width > 0, "bit_width must be > 0");
+    assert!(bit_width <= (u16::MAX as usize) / 16, "bit_width too large");
+
+    let mut builder = CircuitBuilder::new();
+    let a_bits = builder.inputs(bit_width);
+    let b_bits = builder.inputs(bit_width);
+    let (gt_wire, _) = builder.compare(&a_bits, &b_bits);
+    let max_wires = builder.mux(gt_wire, &a_bits, &b_bits);
+
+    MaxOfTwoLayout {
+        gates: builder.finish().gates,
+        gt_wire,
+        max_wires,
+    }
+}
+
+/// Gate list plus output wires of a two-bidder second-price circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecondPriceLayout {
+    pub gates: Vec<GateDesc>,
+    /// `x > y`, i.e. Alice wins.
+    pub gt_wire: u16,
+    /// Little-endian bits of `max(x, y)`, the winning bid.
+    pub winner_bid_wires: Vec<u16>,
+    /// Little-endian bits of `min(x, y)`, the price the winner pays.
+    pub payment_wires: Vec<u16>,
+}
+
+/// Builds a deterministic two-bidder Vickrey layout computing the winner flag, the winning
+/// bid and the second-price payment in one circuit: the comparator's `gt` wire drives a
+/// conditional swap of `x`/`y`. Uses the same input wire convention as
+/// `build_millionaires_layout`; on ties both outputs equal the common bid.
+pub fn build_second_price_layout(bit_width: usize) -> SecondPriceLayout {
+    assert!(bit_width > 0, "bit_width must be > 0");
+    assert!(bit_width <= (u16::MAX as usize) / 16, "bit_width too large");
+
+    let mut builder = CircuitBuilder::new();
+    let a_bits = builder.inputs(bit_width);
+    let b_bits = builder.inputs(bit_width);
+    let (gt_wire, _) = builder.compare(&a_bits, &b_bits);
+    let (winner_bid_wires, payment_wires) = builder.swap(gt_wire, &a_bits, &b_bits);
+
+    SecondPriceLayout {
+        gates: builder.finish().gates,
+        gt_wire,
+        winner_bid_wires,
+        payment_wires,
+    }
+}
+
+/// Two-party comparison computed by `build_comparison_layout` (`x` = Alice, `y` = Bob).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    /// `x > y` (the canonical Millionaires layout).
+    Gt,
+    /// `x >= y`
+    Ge,
+    /// `x < y`
+    Lt,
+    /// `x <= y`
+    Le,
+    /// `x == y`
+    Eq,
+}
+
+/// Builds a deterministic two-party comparison layout for `bit_width`-bit inputs.
+/// Uses the same input wire convention as `build_millionaires_layout`; `Comparison::Gt`
+/// yields exactly the Millionaires layout.
+///
+/// Variants reuse the MSB-to-LSB comparator: `Lt` swaps operands, `Le`/`Ge` append one
+/// `NOT` over `Gt`/`Lt`, and `Eq` reads the equality accumulator.
+/// Use `evaluation::comparison_output_wire` to locate the result wire.
+pub fn build_comparison_layout(bit_width: usize, comparison: Comparison) -> Vec<GateDesc> {
     assert!(bit_width > 0, "bit_width must be > 0");
     assert!(bit_width <= (u16::MAX as usize) / 4, "bit_width too large");
 
-    let mut gates = Vec::new();
+    let mut builder = CircuitBuilder::new();
     // Reserve input wires first: A bits then B bits.
-    let mut next_wire = (bit_width * 2) as u16;
-
-    // Running accumulators for:
-    // - gt_acc: "A > B already seen at higher bit"
-    // - eq_acc: "A == B for all higher bits"
-    let mut gt_acc: Option<u16> = None;
-    let mut eq_acc: Option<u16> = None;
-
-    // Compare from MSB to LSB.
-    for bit in (0..bit_width).rev() {
-        let a = bit as u16;
-        let b = (bit + bit_width) as u16;
-
-        // eq_bit = !(a XOR b)
-        let xor_ab = push_xor(&mut gates, &mut next_wire, a, b);
-        let eq_bit = push_not(&mut gates, &mut next_wire, xor_ab);
-
-        // gt_bit = a AND (!b)
-        let not_b = push_not(&mut gates, &mut next_wire, b);
-        let gt_bit = push_and(&mut gates, &mut next_wire, a, not_b);
-
-        match (gt_acc, eq_acc) {
-            (None, None) => {
-                // Highest bit initializes accumulators.
-                gt_acc = Some(gt_bit);
-                eq_acc = Some(eq_bit);
-            }
-            (Some(gt_prev), Some(eq_prev)) => {
-                // gt_new = gt_prev OR (eq_prev AND gt_bit)
-                let eq_and_gt = push_and(&mut gates, &mut next_wire, eq_prev, gt_bit);
-                let gt_new = push_or(&mut gates, &mut next_wire, gt_prev, eq_and_gt);
-                // eq_new = eq_prev AND eq_bit
-                let eq_new = push_and(&mut gates, &mut next_wire, eq_prev, eq_bit);
-                gt_acc = Some(gt_new);
-                eq_acc = Some(eq_new);
-            }
-            _ => unreachable!("accumulators must progress together"),
+    let a_bits = builder.inputs(bit_width);
+    let b_bits = builder.inputs(bit_width);
+    match comparison {
+        Comparison::Gt | Comparison::Eq => {
+            builder.compare(&a_bits, &b_bits);
+        }
+        Comparison::Lt => {
+            builder.compare(&b_bits, &a_bits);
+        }
+        Comparison::Le => {
+            let (gt, _) = builder.compare(&a_bits, &b_bits);
+            builder.not(gt);
         }
+        Comparison::Ge => {
+            let (lt, _) = builder.compare(&b_bits, &a_bits);
+            builder.not(lt);
+        }
+    }
+
+    builder.finish().gates
+}
+
+/// Gate list plus output wires of a highest-bid selection circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgmaxLayout {
+    pub gates: Vec<GateDesc>,
+    /// Little-endian bits of the maximum bid.
+    pub max_wires: Vec<u16>,
+    /// Little-endian bits of the index of the maximum bid (`index_bit_width` wires).
+    pub index_wires: Vec<u16>,
+}
+
+/// Gate list plus output wires of a second-price (Vickrey) auction circuit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VickreyLayout {
+    pub gates: Vec<GateDesc>,
+    /// Little-endian bits of the winning bidder index (`index_bit_width` wires).
+    pub winner_index_wires: Vec<u16>,
+    /// Little-endian bits of the second-highest bid, i.e. the price the winner pays.
+    pub second_price_wires: Vec<u16>,
+}
+
+/// Number of bits needed to encode a bidder index in `0 .. n_bidders` (at least 1).
+pub fn index_bit_width(n_bidders: usize) -> usize {
+    let mut width = 1usize;
+    while (1usize << width) < n_bidders {
+        width += 1;
+    }
+    width
+}
+
+/// Wires produced by `scan_bids`.
+struct BidScan {
+    best: Vec<u16>,
+    best_index: Vec<u16>,
+    second: Option<Vec<u16>>,
+}
+
+/// Internal helper shared by the multi-bidder builders.
+/// Input wire convention: bidder `i` bits are `[i*bit_width .. (i+1)*bit_width-1]`.
+///
+/// Bids are scanned in bidder order; a later bid only takes the lead when it is strictly
+/// greater, so ties resolve to the lowest bidder index. The runner-up is tracked only when
+/// `track_second` is set.
+fn scan_bids(
+    builder: &mut CircuitBuilder,
+    bit_width: usize,
+    n_bidders: usize,
+    track_second: bool,
+) -> BidScan {
+    // Reserve all bidder input wires first.
+    let bids: Vec<Vec<u16>> = (0..n_bidders).map(|_| builder.inputs(bit_width)).collect();
+
+    let mut best = bids[0].clone();
+    let mut second: Option<Vec<u16>> = None;
+    // `None` marks an index bit that is still the constant 0.
+    let mut best_index: Vec<Option<u16>> = vec![None; index_bit_width(n_bidders)];
+
+    for (bidder, bid) in bids.iter().enumerate().skip(1) {
+        let (takes_lead, _) = builder.compare(bid, &best);
+
+        if track_second {
+            // Runner-up when the lead holds: max(second, bid); on a lead change: old best.
+            let runner_up = match &second {
+                None => bid.clone(),
+                Some(prev) => {
+                    let (beats_second, _) = builder.compare(bid, prev);
+                    builder.mux(beats_second, bid, prev)
+                }
+            };
+            second = Some(builder.mux(takes_lead, &best, &runner_up));
+        }
+        best = builder.mux(takes_lead, bid, &best);
+
+        // index_bit = takes_lead ? bit(bidder) : index_bit, folded against the constant.
+        let mut keeps_lead: Option<u16> = None;
+        for (bit, slot) in best_index.iter_mut().enumerate() {
+            let constant_bit = (bidder >> bit) & 1 == 1;
+            *slot = match (*slot, constant_bit) {
+                (None, true) => Some(takes_lead),
+                (None, false) => None,
+                (Some(prev), true) => Some(builder.or(prev, takes_lead)),
+                (Some(prev), false) => {
+                    let keep = *keeps_lead.get_or_insert_with(|| builder.not(takes_lead));
+                    Some(builder.and(prev, keep))
+                }
+            };
+        }
+    }
+
+    let mut zero_wire: Option<u16> = None;
+    let best_index = best_index
+        .into_iter()
+        .map(|slot| {
+            slot.unwrap_or_else(|| {
+                // Constant 0 as `x XOR x` on the first input wire.
+                *zero_wire.get_or_insert_with(|| builder.xor(0, 0))
+            })
+        })
+        .collect();
+
+    BidScan {
+        best,
+        best_index,
+        second,
     }
+}
 
-    gates
+/// Shared argument checks for the multi-bidder builders.
+fn assert_multi_bidder_shape(bit_width: usize, n_bidders: usize) {
+    assert!(bit_width > 0, "bit_width must be > 0");
+    assert!(n_bidders >= 2, "n_bidders must be >= 2");
+    assert!(
+        bit_width.saturating_mul(n_bidders) <= (u16::MAX as usize) / 64,
+        "bit_width * n_bidders too large"
+    );
+}
+
+/// Builds a deterministic circuit selecting the maximum of `n` bids of `bit_width` bits and
+/// its index. Input wire convention:
+/// - Bidder `i` bits: `[i*bit_width .. (i+1)*bit_width-1]` (little-endian)
+///
+/// Ties resolve to the lowest bidder index.
+pub fn build_argmax_layout(bit_width: usize, n: usize) -> ArgmaxLayout {
+    assert_multi_bidder_shape(bit_width, n);
+
+    let mut builder = CircuitBuilder::new();
+    let scan = scan_bids(&mut builder, bit_width, n, false);
+
+    ArgmaxLayout {
+        gates: builder.finish().gates,
+        max_wires: scan.best,
+        index_wires: scan.best_index,
+    }
+}
+
+/// Builds a deterministic second-price sealed-bid auction layout for `n_bidders` bids of
+/// `bit_width` bits each. Uses the same input convention and tie rule as
+/// `build_argmax_layout`.
+pub fn build_vickrey_layout(bit_width: usize, n_bidders: usize) -> VickreyLayout {
+    assert_multi_bidder_shape(bit_width, n_bidders);
+
+    let mut builder = CircuitBuilder::new();
+    let scan = scan_bids(&mut builder, bit_width, n_bidders, true);
+
+    VickreyLayout {
+        gates: builder.finish().gates,
+        winner_index_wires: scan.best_index,
+        second_price_wires: scan.second.expect("n_bidders >= 2 always sets a runner-up"),
+    }
 }
 
 /// Derives one per-instance seed from a master seed and circuit context.
@@ -109,3 +380,34 @@ pub fn derive_instance_seed(
 pub fn com_seed(seed: [u8; 32]) -> [u8; 32] {
     keccak256(&[&seed])
 }
+
+/// Off-chain commitment to Bob's instance choice, sent to Alice before she publishes eval
+/// material: `keccak256("CHOICE", circuitId, uint256(m), salt)`.
+pub fn choice_commitment(circuit_id: [u8; 32], m: usize, salt: [u8; 32]) -> [u8; 32] {
+    let m = uint256_from_u64(m as u64);
+    keccak256(&[b"CHOICE", &circuit_id, &m, &salt])
+}
+
+/// Checks Bob's opening `(m, salt)` against his earlier `commitment` and against the `m`
+/// fixed on-chain, so a choice made after seeing eval material is rejected.
+pub fn verify_choice_opening(
+    params: SessionParams,
+    commitment: [u8; 32],
+    circuit_id: [u8; 32],
+    m: usize,
+    salt: [u8; 32],
+    onchain_m: usize,
+) -> Result<(), String> {
+    params.check_m(m)?;
+    if choice_commitment(circuit_id, m, salt) != commitment {
+        return Err(format!(
+            "choice opening m={m} does not match the commitment"
+        ));
+    }
+    if m != onchain_m {
+        return Err(format!(
+            "committed choice m={m} differs from on-chain m={onchain_m}"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! Selection of the evaluation index `m` from public randomness instead of Bob's input.
+//!
+//! Two sources are supported:
+//! - `RandomnessSource::BlockHash`: the hash of a block whose height was agreed before Alice's
+//!   commitments were final. Each party fetches the hash from its own node.
+//! - `RandomnessSource::Vrf`: an ECVRF output (`ECVRF-SECP256K1-SHA256-TAI`, RFC 9381 with
+//!   try-and-increment encoding) under a public key agreed in advance. The VRF input is bound
+//!   to the circuit id and Alice's commitments, so the key holder cannot grind it.
+//!
+//! Both parties call `verify_beacon_m` on the same evidence; the derived index is
+//! `uint256(keccak256("M-BEACON", randomness, circuitId, binding)) % N`, where `binding` is
+//! `commitment_binding` over the rootGC list Alice submitted on-chain. Each party computes it
+//! from the contract and reads the block hash from its own node, so neither takes the other's
+//! word for either.
+
+use k256::elliptic_curve::PrimeField;
+use k256::elliptic_curve::ops::Reduce;
+use k256::elliptic_curve::sec1::{FromEncodedPoint, ToEncodedPoint};
+use k256::{AffinePoint, EncodedPoint, FieldBytes, ProjectivePoint, Scalar, U256};
+use sha2::{Digest, Sha256};
+
+use super::SessionParams;
+use crate::consensus::keccak256;
+
+/// Suite byte of `ECVRF-SECP256K1-SHA256-TAI`.
+const SUITE: u8 = 0xfe;
+/// Challenge length in bytes (`cLen`).
+const CHALLENGE_LEN: usize = 16;
+/// Compressed SEC1 point length.
+pub const VRF_PUBLIC_KEY_LEN: usize = 33;
+/// `Gamma (33) || c (16) || s (32)`.
+pub const VRF_PROOF_LEN: usize = 81;
+
+/// Public randomness that fixes `m`, agreed before Alice commits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomnessSource {
+    /// Hash of the block at `height`.
+    BlockHash { height: u64 },
+    /// VRF output under a compressed secp256k1 public key.
+    Vrf {
+        public_key: [u8; VRF_PUBLIC_KEY_LEN],
+    },
+}
+
+/// Evidence published once the randomness is available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeaconEvidence {
+    /// Block hash observed at the committed height.
+    BlockHash { height: u64, hash: [u8; 32] },
+    /// VRF proof over `vrf_alpha(circuit_id, binding)`.
+    Vrf { proof: [u8; VRF_PROOF_LEN] },
+}
+
+/// `keccak256("M-BINDING", rootGC_0, .., rootGC_{N-1})`: binds the beacon output to the
+/// rootGC of every instance, in instance order.
+pub fn commitment_binding(root_gcs: &[[u8; 32]]) -> [u8; 32] {
+    let mut parts: Vec<&[u8]> = vec![b"M-BINDING"];
+    parts.extend(root_gcs.iter().map(|root_gc| &root_gc[..]));
+    keccak256(&parts)
+}
+
+/// VRF input: `keccak256("M-VRF", circuitId, binding)`, where `binding` is the
+/// `commitment_binding` of Alice's on-chain commitments.
+pub fn vrf_alpha(circuit_id: [u8; 32], binding: [u8; 32]) -> [u8; 32] {
+    keccak256(&[b"M-VRF", &circuit_id, &binding])
+}
+
+/// Maps 32 bytes of randomness to an instance index in `[0, n)`.
+pub fn m_from_randomness(
+    params: SessionParams,
+    circuit_id: [u8; 32],
+    binding: [u8; 32],
+    randomness: [u8; 32],
+) -> usize {
+    let digest = keccak256(&[b"M-BEACON", &randomness, &circuit_id, &binding]);
+    // Big-endian `uint256 % n` without a bignum type.
+    let n = params.n as u128;
+    digest
+        .iter()
+        .fold(0u128, |acc, byte| (acc * 256 + u128::from(*byte)) % n) as usize
+}
+
+/// Checks `evidence` against the agreed `source` and returns the selected `m`.
+pub fn derive_beacon_m(
+    params: SessionParams,
+    circuit_id: [u8; 32],
+    binding: [u8; 32],
+    source: RandomnessSource,
+    evidence: &BeaconEvidence,
+) -> Result<usize, String> {
+    let randomness = match (source, evidence) {
+        (
+            RandomnessSource::BlockHash { height },
+            BeaconEvidence::BlockHash { height: got, hash },
+        ) => {
+            if *got != height {
+                return Err(format!(
+                    "block hash is for height {got}, committed height is {height}"
+                ));
+            }
+            *hash
+        }
+        (RandomnessSource::Vrf { public_key }, BeaconEvidence::Vrf { proof }) => {
+            vrf_verify(&public_key, &vrf_alpha(circuit_id, binding), proof)?
+        }
+        _ => return Err("beacon evidence does not match the committed source".to_string()),
+    };
+    Ok(m_from_randomness(params, circuit_id, binding, randomness))
+}
+
+/// Checks that `claimed_m` is the index fixed by `evidence`.
+pub fn verify_beacon_m(
+    params: SessionParams,
+    circuit_id: [u8; 32],
+    binding: [u8; 32],
+    source: RandomnessSource,
+    evidence: &BeaconEvidence,
+    claimed_m: usize,
+) -> Result<(), String> {
+    let m = derive_beacon_m(params, circuit_id, binding, source, evidence)?;
+    if m != claimed_m {
+        return Err(format!("claimed m={claimed_m} but beacon selects m={m}"));
+    }
+    Ok(())
+}
+
+fn secret_scalar(secret: [u8; 32]) -> Result<Scalar, String> {
+    let scalar = Option::<Scalar>::from(Scalar::from_repr(FieldBytes::from(secret)))
+        .ok_or("VRF secret is not below the curve order")?;
+    if bool::from(scalar.is_zero()) {
+        return Err("VRF secret must be non-zero".to_string());
+    }
+    Ok(scalar)
+}
+
+fn compress(point: &ProjectivePoint) -> Vec<u8> {
+    point.to_affine().to_encoded_point(true).as_bytes().to_vec()
+}
+
+fn decompress(bytes: &[u8]) -> Option<ProjectivePoint> {
+    let encoded = EncodedPoint::from_bytes(bytes).ok()?;
+    Option::<AffinePoint>::from(AffinePoint::from_encoded_point(&encoded)).map(Into::into)
+}
+
+/// `ECVRF_encode_to_curve_try_and_increment`.
+fn encode_to_curve(public_key: &[u8], alpha: &[u8]) -> Result<ProjectivePoint, String> {
+    for ctr in 0u8..=255 {
+        let hash = Sha256::new()
+            .chain_update([SUITE, 0x01])
+            .chain_update(public_key)
+            .chain_update(alpha)
+            .chain_update([ctr, 0x00])
+            .finalize();
+        let mut candidate = [0u8; VRF_PUBLIC_KEY_LEN];
+        candidate[0] = 0x02;
+        candidate[1..].copy_from_slice(&hash);
+        if let Some(point) = decompress(&candidate) {
+            return Ok(point);
+        }
+    }
+    Err("VRF encode-to-curve found no point".to_string())
+}
+
+fn challenge(points: [&ProjectivePoint; 5]) -> [u8; CHALLENGE_LEN] {
+    let mut hasher = Sha256::new().chain_update([SUITE, 0x02]);
+    for point in points {
+        hasher.update(compress(point));
+    }
+    let digest = hasher.chain_update([0x00]).finalize();
+    digest[..CHALLENGE_LEN]
+        .try_into()
+        .expect("challenge length")
+}
+
+fn challenge_scalar(c: &[u8; CHALLENGE_LEN]) -> Scalar {
+    let mut bytes = [0u8; 32];
+    bytes[32 - CHALLENGE_LEN..].copy_from_slice(c);
+    <Scalar as Reduce<U256>>::reduce_bytes(&FieldBytes::from(bytes))
+}
+
+fn proof_to_hash(gamma: &ProjectivePoint) -> [u8; 32] {
+    Sha256::new()
+        .chain_update([SUITE, 0x03])
+        .chain_update(compress(gamma))
+        .chain_update([0x00])
+        .finalize()
+        .into()
+}
+
+/// Compressed public key of a VRF secret.
+pub fn vrf_public_key(secret: [u8; 32]) -> Result<[u8; VRF_PUBLIC_KEY_LEN], String> {
+    let point = ProjectivePoint::GENERATOR * secret_scalar(secret)?;
+    Ok(compress(&point).try_into().expect("compressed point"))
+}
+
+/// Proves `alpha` under `secret`; returns the proof and the VRF output `beta`.
+/// The nonce is `SHA256(secret || H)` reduced mod q instead of RFC 6979; verifiers do not
+/// depend on how the nonce was derived.
+pub fn vrf_prove(
+    secret: [u8; 32],
+    alpha: &[u8],
+) -> Result<([u8; VRF_PROOF_LEN], [u8; 32]), String> {
+    let x = secret_scalar(secret)?;
+    let public_key = vrf_public_key(secret)?;
+    let h = encode_to_curve(&public_key, alpha)?;
+    let gamma = h * x;
+    let nonce_bytes: [u8; 32] = Sha256::new()
+        .chain_update(secret)
+        .chain_update(compress(&h))
+        .finalize()
+        .into();
+    let k = <Scalar as Reduce<U256>>::reduce_bytes(&FieldBytes::from(nonce_bytes));
+    let y = ProjectivePoint::GENERATOR * x;
+    let c = challenge([&y, &h, &gamma, &(ProjectivePoint::GENERATOR * k), &(h * k)]);
+    let s = k + challenge_scalar(&c) * x;
+
+    let mut proof = [0u8; VRF_PROOF_LEN];
+    proof[..33].copy_from_slice(&compress(&gamma));
+    proof[33..33 + CHALLENGE_LEN].copy_from_slice(&c);
+    proof[33 + CHALLENGE_LEN..].copy_from_slice(&s.to_bytes());
+    Ok((proof, proof_to_hash(&gamma)))
+}
+
+/// Verifies a VRF proof of `alpha` and returns the output `beta`.
+pub fn vrf_verify(
+    public_key: &[u8; VRF_PUBLIC_KEY_LEN],
+    alpha: &[u8],
+    proof: &[u8; VRF_PROOF_LEN],
+) -> Result<[u8; 32], String> {
+    let y = decompress(public_key).ok_or("invalid VRF public key")?;
+    if y == ProjectivePoint::IDENTITY {
+        return Err("invalid VRF public key".to_string());
+    }
+    let gamma = decompress(&proof[..33]).ok_or("invalid VRF proof point")?;
+    let c: [u8; CHALLENGE_LEN] = proof[33..33 + CHALLENGE_LEN]
+        .try_into()
+        .expect("challenge length");
+    let s_bytes: [u8; 32] = proof[33 + CHALLENGE_LEN..]
+        .try_into()
+        .expect("scalar length");
+    let s = Option::<Scalar>::from(Scalar::from_repr(FieldBytes::from(s_bytes)))
+        .ok_or("VRF proof scalar is not below the curve order")?;
+
+    let h = encode_to_curve(public_key, alpha)?;
+    let c_scalar = challenge_scalar(&c);
+    let u = ProjectivePoint::GENERATOR * s - y * c_scalar;
+    let v = h * s - gamma * c_scalar;
+    if challenge([&y, &h, &gamma, &u, &v]) != c {
+        return Err("VRF proof does not verify".to_string());
+    }
+    Ok(proof_to_hash(&gamma))
+}
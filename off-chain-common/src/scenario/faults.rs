@@ -0,0 +1,188 @@
+//! Fault injection for negative dispute vectors.
+//!
+//! Each `Fault` corrupts one leaf of a correctly garbled instance. The mutated leaves can be
+//! committed through the IH chain as usual, so `challengeGateLeaf` accepts the inclusion proof
+//! and must slash Alice (or reject the call with `Bad leaf length` for truncated leaves).
+
+use std::fmt;
+
+use crate::consensus::LEAF_BYTES_LEN;
+use crate::garble::garble_circuit;
+use crate::types::CircuitLayout;
+
+/// Byte offset of `row0` inside a leaf (after the 7-byte gate header).
+const ROWS_OFFSET: usize = 7;
+
+/// Wire slot of the 7-byte gate header.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderWire {
+    A,
+    B,
+    C,
+}
+
+impl HeaderWire {
+    fn offset(self) -> usize {
+        match self {
+            HeaderWire::A => 1,
+            HeaderWire::B => 3,
+            HeaderWire::C => 5,
+        }
+    }
+}
+
+/// Corruption applied to the leaf at `gate_index`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// XORs `mask` (non-zero) into byte `byte` of row `row`.
+    FlipRowByte {
+        gate_index: usize,
+        row: usize,
+        byte: usize,
+        mask: u8,
+    },
+    /// Exchanges rows `row_a` and `row_b`; the rows must differ in content.
+    SwapRows {
+        gate_index: usize,
+        row_a: usize,
+        row_b: usize,
+    },
+    /// Overwrites one header wire id with `value` (must differ from the real id).
+    WrongWireHeader {
+        gate_index: usize,
+        wire: HeaderWire,
+        value: u16,
+    },
+    /// Cuts the leaf down to `len` bytes (`len < 71`).
+    TruncateLeaf { gate_index: usize, len: usize },
+}
+
+impl Fault {
+    /// Gate index whose leaf is corrupted.
+    pub fn gate_index(&self) -> usize {
+        match *self {
+            Fault::FlipRowByte { gate_index, .. }
+            | Fault::SwapRows { gate_index, .. }
+            | Fault::WrongWireHeader { gate_index, .. }
+            | Fault::TruncateLeaf { gate_index, .. } => gate_index,
+        }
+    }
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Fault::FlipRowByte {
+                gate_index,
+                row,
+                byte,
+                mask,
+            } => write!(
+                f,
+                "gate {gate_index}: row{row} byte {byte} xor 0x{mask:02x}"
+            ),
+            Fault::SwapRows {
+                gate_index,
+                row_a,
+                row_b,
+            } => write!(f, "gate {gate_index}: swapped row{row_a} and row{row_b}"),
+            Fault::WrongWireHeader {
+                gate_index,
+                wire,
+                value,
+            } => write!(f, "gate {gate_index}: header wire {wire:?} set to {value}"),
+            Fault::TruncateLeaf { gate_index, len } => {
+                write!(
+                    f,
+                    "gate {gate_index}: leaf truncated to {len}/{LEAF_BYTES_LEN} bytes"
+                )
+            }
+        }
+    }
+}
+
+/// Leaves of one instance after a fault was applied.
+/// `leaves` are variable-length because `Fault::TruncateLeaf` shortens one of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FaultyInstance {
+    pub leaves: Vec<Vec<u8>>,
+    pub gate_index: usize,
+    pub description: String,
+}
+
+/// Applies `fault` to a copy of `leaves`.
+/// Rejects faults that would leave the target leaf unchanged (e.g. swapping two equal NOT rows).
+pub fn inject_fault(leaves: &[[u8; 71]], fault: Fault) -> Result<FaultyInstance, String> {
+    let gate_index = fault.gate_index();
+    if gate_index >= leaves.len() {
+        return Err(format!(
+            "gate_index {gate_index} out of range for {} leaves",
+            leaves.len()
+        ));
+    }
+    let mut mutated = leaves.iter().map(|leaf| leaf.to_vec()).collect::<Vec<_>>();
+    let leaf = &mut mutated[gate_index];
+
+    match fault {
+        Fault::FlipRowByte {
+            row, byte, mask, ..
+        } => {
+            if row >= 4 || byte >= 16 {
+                return Err(format!(
+                    "row {row} byte {byte} out of range (4 rows x 16 bytes)"
+                ));
+            }
+            if mask == 0 {
+                return Err("flip mask must be non-zero".to_string());
+            }
+            leaf[ROWS_OFFSET + row * 16 + byte] ^= mask;
+        }
+        Fault::SwapRows { row_a, row_b, .. } => {
+            if row_a >= 4 || row_b >= 4 {
+                return Err(format!("rows {row_a}/{row_b} out of range (4 rows)"));
+            }
+            let a = ROWS_OFFSET + row_a * 16;
+            let b = ROWS_OFFSET + row_b * 16;
+            if leaf[a..a + 16] == leaf[b..b + 16] {
+                return Err(format!(
+                    "gate {gate_index}: row{row_a} and row{row_b} are identical; swap is a no-op"
+                ));
+            }
+            for i in 0..16 {
+                leaf.swap(a + i, b + i);
+            }
+        }
+        Fault::WrongWireHeader { wire, value, .. } => {
+            let offset = wire.offset();
+            if leaf[offset..offset + 2] == value.to_be_bytes() {
+                return Err(format!(
+                    "gate {gate_index}: header wire {wire:?} already is {value}"
+                ));
+            }
+            leaf[offset..offset + 2].copy_from_slice(&value.to_be_bytes());
+        }
+        Fault::TruncateLeaf { len, .. } => {
+            if len >= LEAF_BYTES_LEN {
+                return Err(format!(
+                    "truncated length must be < {LEAF_BYTES_LEN}, got {len}"
+                ));
+            }
+            leaf.truncate(len);
+        }
+    }
+
+    Ok(FaultyInstance {
+        leaves: mutated,
+        gate_index,
+        description: fault.to_string(),
+    })
+}
+
+/// Garbles `layout` honestly under `seed`, then applies `fault`.
+pub fn garble_with_fault(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    fault: Fault,
+) -> Result<FaultyInstance, String> {
+    inject_fault(&garble_circuit(seed, layout), fault)
+}
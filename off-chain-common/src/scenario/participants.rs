@@ -0,0 +1,198 @@
+//! Participant model for one garbler serving several bidders.
+//!
+//! Input wires are assigned in participant order: the garbler's own inputs first (none for a
+//! plain auctioneer), then `bit_width` wires per bidder. The two-party Millionaires flow is
+//! the special case of `garbler_inputs = bit_width` and a single bidder (Bob's y-wires); the
+//! argmax and Vickrey builders use `garbler_inputs = 0` with bidder `i` on
+//! `[i*bit_width .. (i+1)*bit_width)`.
+//!
+//! Every bidder gets its own eval package: the shared material of instance `m` (leaves,
+//! garbler labels, NOT hints, decoding table) plus a `LabelOfferMessage` covering only its
+//! wire range. The evaluator then joins the selected labels with `input_labels`.
+
+use std::ops::Range;
+
+use crate::messages::{EvalPackageMessage, LabelOfferMessage};
+use crate::protocol::Party;
+
+/// Who provides which input wires of a circuit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParticipantLayout {
+    /// Bits of each bidder's input.
+    pub bit_width: usize,
+    /// Input wires the garbler fills itself, before the bidders' wires.
+    pub garbler_inputs: usize,
+    pub n_bidders: usize,
+}
+
+/// Eval package for one bidder: shared instance material plus its own input labels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidderEvalPackage {
+    pub bidder: usize,
+    pub package: EvalPackageMessage,
+    pub inputs: LabelOfferMessage,
+}
+
+impl ParticipantLayout {
+    /// Alice's x-wires followed by Bob's y-wires, as in the Millionaires circuit.
+    pub fn two_party(bit_width: usize) -> Self {
+        Self {
+            bit_width,
+            garbler_inputs: bit_width,
+            n_bidders: 1,
+        }
+    }
+
+    /// Auctioneer without inputs garbling for `n_bidders` bids (argmax / Vickrey layouts).
+    pub fn auction(bit_width: usize, n_bidders: usize) -> Result<Self, String> {
+        if bit_width == 0 {
+            return Err("bit_width must be > 0".to_string());
+        }
+        if n_bidders < 2 {
+            return Err(format!(
+                "an auction needs at least 2 bidders, got {n_bidders}"
+            ));
+        }
+        let layout = Self {
+            bit_width,
+            garbler_inputs: 0,
+            n_bidders,
+        };
+        if layout.input_wire_count() > u16::MAX as usize {
+            return Err(format!(
+                "{n_bidders} bidders of {bit_width} bits exceed the u16 wire range"
+            ));
+        }
+        Ok(layout)
+    }
+
+    pub fn input_wire_count(&self) -> usize {
+        self.garbler_inputs + self.n_bidders * self.bit_width
+    }
+
+    /// Parties that deposit and take part in a session of this layout. A single bidder is
+    /// the two-party `Party::Evaluator`.
+    pub fn parties(&self) -> Vec<Party> {
+        std::iter::once(Party::Garbler)
+            .chain((0..self.n_bidders).map(|bidder| self.bidder_party(bidder)))
+            .collect()
+    }
+
+    fn bidder_party(&self, bidder: usize) -> Party {
+        if self.n_bidders == 1 {
+            Party::Evaluator
+        } else {
+            Party::Bidder(bidder)
+        }
+    }
+
+    pub fn garbler_wires(&self) -> Range<u16> {
+        0..self.garbler_inputs as u16
+    }
+
+    pub fn bidder_wires(&self, bidder: usize) -> Result<Range<u16>, String> {
+        if bidder >= self.n_bidders {
+            return Err(format!(
+                "bidder {bidder} out of range [0, {})",
+                self.n_bidders
+            ));
+        }
+        let first = self.garbler_inputs + bidder * self.bit_width;
+        Ok(first as u16..(first + self.bit_width) as u16)
+    }
+
+    /// Party that provides input `wire`, or `None` for non-input wires.
+    pub fn owner(&self, wire: u16) -> Option<Party> {
+        let wire = wire as usize;
+        if wire < self.garbler_inputs {
+            return Some(Party::Garbler);
+        }
+        let bidder = (wire - self.garbler_inputs).checked_div(self.bit_width)?;
+        (bidder < self.n_bidders).then(|| self.bidder_party(bidder))
+    }
+
+    /// Splits `rows` (`[label0, label1]` or OT rows for every bidder wire, in wire order) into
+    /// one eval package per bidder around the shared `package` of `instance_id`.
+    pub fn bidder_packages(
+        &self,
+        instance_id: u64,
+        package: &EvalPackageMessage,
+        rows: &[[[u8; 16]; 2]],
+        encrypted: bool,
+    ) -> Result<Vec<BidderEvalPackage>, String> {
+        if package.alice_labels.len() != self.garbler_inputs {
+            return Err(format!(
+                "package carries {} garbler labels, layout expects {}",
+                package.alice_labels.len(),
+                self.garbler_inputs
+            ));
+        }
+        if package.bit_width as usize != self.bit_width {
+            return Err(format!(
+                "package bit_width {} does not match layout bit_width {}",
+                package.bit_width, self.bit_width
+            ));
+        }
+        let expected_rows = self.n_bidders * self.bit_width;
+        if rows.len() != expected_rows {
+            return Err(format!(
+                "got {} label rows for {expected_rows} bidder wires",
+                rows.len()
+            ));
+        }
+        (0..self.n_bidders)
+            .map(|bidder| {
+                let wires = self.bidder_wires(bidder)?;
+                let offset = bidder * self.bit_width;
+                Ok(BidderEvalPackage {
+                    bidder,
+                    package: package.clone(),
+                    inputs: LabelOfferMessage {
+                        instance_id,
+                        first_wire: wires.start,
+                        encrypted,
+                        rows: rows[offset..offset + self.bit_width].to_vec(),
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Joins the garbler's labels and every bidder's selected labels (indexed by bidder) into
+    /// the `(wire, label)` inputs of `evaluation::evaluate_garbled_wires`.
+    pub fn input_labels(
+        &self,
+        garbler_labels: &[[u8; 16]],
+        bidder_labels: &[Vec<[u8; 16]>],
+    ) -> Result<Vec<(u16, [u8; 16])>, String> {
+        if garbler_labels.len() != self.garbler_inputs {
+            return Err(format!(
+                "got {} garbler labels, layout expects {}",
+                garbler_labels.len(),
+                self.garbler_inputs
+            ));
+        }
+        if bidder_labels.len() != self.n_bidders {
+            return Err(format!(
+                "got labels from {} bidders, layout expects {}",
+                bidder_labels.len(),
+                self.n_bidders
+            ));
+        }
+        let mut out = self
+            .garbler_wires()
+            .zip(garbler_labels.iter().copied())
+            .collect::<Vec<_>>();
+        for (bidder, labels) in bidder_labels.iter().enumerate() {
+            if labels.len() != self.bit_width {
+                return Err(format!(
+                    "bidder {bidder} sent {} labels, expected {}",
+                    labels.len(),
+                    self.bit_width
+                ));
+            }
+            out.extend(self.bidder_wires(bidder)?.zip(labels.iter().copied()));
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,252 @@
+//! In-process run of a whole Alice + Bob session: no files, no chain.
+//!
+//! `simulate_session` plays both parties through `protocol::ProtocolSession`. Alice garbles
+//! every instance from her master seed (applying any configured `Fault` to the leaves she
+//! claims) and commits `comSeed`/`rootGC`; Bob picks `m`, audits each opened instance with
+//! `dispute::watchdog::audit_opened_instance` and disputes the first cheating gate. Without a
+//! dispute Bob evaluates instance `m` on both inputs (his labels are handed over directly,
+//! standing in for the OT) and the decoded decision bit is settled.
+
+use crate::consensus::HIGHER_BID_WINS;
+use crate::decoding::OutputDecodingTable;
+use crate::dispute::watchdog::{
+    InstanceVerdict, OpenedInstance, WatchdogConfig, audit_opened_instance,
+};
+use crate::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, evaluate_garbled_wires, label16_to_bytes32,
+    u64_to_bits_le,
+};
+use crate::garble::garble_circuit;
+use crate::ih::{gc_block_hash, incremental_root_from_hashes};
+use crate::protocol::{
+    ChoosePayload, CommitPayload, DepositPayload, DisputePayload, EvalPayload, OpenPayload, Party,
+    PhasePayload, ProtocolSession, SettlePayload,
+};
+use crate::registry::CircuitVersion;
+use crate::scenario::faults::{Fault, inject_fault};
+use crate::scenario::{SessionParams, com_seed, derive_instance_seed};
+use crate::types::CircuitLayout;
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub circuit: &'static CircuitVersion,
+    /// Input width; at most 64 so that `x`/`y` fit.
+    pub bit_width: usize,
+    pub params: SessionParams,
+    pub master_seed: [u8; 32],
+    /// Evaluated instance, as fixed on-chain.
+    pub m: usize,
+    /// Alice's input (wires `0..bit_width`).
+    pub x: u64,
+    /// Bob's input (wires `bit_width..2*bit_width`).
+    pub y: u64,
+    /// Faults Alice applies to the leaves she claims, by instance id.
+    pub faults: Vec<(usize, Fault)>,
+}
+
+/// Alice's material for one instance and Bob's audit of it.
+#[derive(Debug, Clone)]
+pub struct SimulatedInstance {
+    pub instance_id: usize,
+    pub seed: [u8; 32],
+    pub com_seed: [u8; 32],
+    /// Root over the claimed leaves, as committed.
+    pub root_gc: [u8; 32],
+    pub claimed_leaves: Vec<[u8; 71]>,
+    /// Bob's audit; `None` for the evaluated instance `m`.
+    pub verdict: Option<InstanceVerdict>,
+}
+
+/// Bob's evaluation of instance `m`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimulatedEvaluation {
+    pub decoding: OutputDecodingTable,
+    pub output_label: [u8; 16],
+    pub output_bit: u8,
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulatedSession {
+    pub circuit_id: [u8; 32],
+    pub instances: Vec<SimulatedInstance>,
+    /// Dispute Bob raised against the first cheating instance; the contract slashes Alice on
+    /// it, so later mismatches only show up in the verdicts.
+    pub dispute: Option<DisputePayload>,
+    /// `None` when a dispute ended the session before evaluation.
+    pub evaluation: Option<SimulatedEvaluation>,
+    /// State machine after the last applied payload.
+    pub session: ProtocolSession,
+}
+
+impl SimulatedSession {
+    /// Settled decision bit, if the session got that far.
+    pub fn output_bit(&self) -> Option<u8> {
+        self.evaluation.as_ref().map(|eval| eval.output_bit)
+    }
+}
+
+/// Runs one session in memory and returns the settled output plus every intermediate artifact.
+pub fn simulate_session(config: &SimulationConfig) -> Result<SimulatedSession, String> {
+    config.circuit.check_bit_width(config.bit_width)?;
+    if config.bit_width > 64 {
+        return Err(format!(
+            "simulation inputs are u64, bit_width {} is too wide",
+            config.bit_width
+        ));
+    }
+    config.params.check_m(config.m)?;
+    let gates = config.circuit.build(config.bit_width);
+    let circuit_id = config.circuit.circuit_id(config.bit_width, HIGHER_BID_WINS);
+    let layout_of = |instance_id: usize| {
+        CircuitLayout::new(circuit_id, instance_id as u64, gates.clone())
+            .with_scheme_id(config.circuit.params.scheme_id)
+    };
+
+    let mut session = ProtocolSession::new(config.params);
+    for party in [Party::Garbler, Party::Evaluator] {
+        session.apply(PhasePayload::Deposit(DepositPayload { party }))?;
+    }
+
+    // Alice: garble, tamper where configured, commit.
+    let mut instances = (0..config.params.n)
+        .map(|instance_id| {
+            let seed = derive_instance_seed(config.master_seed, circuit_id, instance_id as u64);
+            let mut claimed_leaves = garble_circuit(seed, &layout_of(instance_id));
+            for (_, fault) in config.faults.iter().filter(|(id, _)| *id == instance_id) {
+                claimed_leaves = inject_fault(&claimed_leaves, *fault)?
+                    .leaves
+                    .into_iter()
+                    .map(|leaf| {
+                        leaf.try_into().map_err(|_| {
+                            format!("instance {instance_id}: truncated leaves cannot be committed")
+                        })
+                    })
+                    .collect::<Result<_, String>>()?;
+            }
+            let block_hashes = claimed_leaves
+                .iter()
+                .enumerate()
+                .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+                .collect::<Vec<_>>();
+            Ok(SimulatedInstance {
+                instance_id,
+                seed,
+                com_seed: com_seed(seed),
+                root_gc: incremental_root_from_hashes(&block_hashes),
+                claimed_leaves,
+                verdict: None,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    session.apply(PhasePayload::Commit(CommitPayload {
+        com_seeds: instances.iter().map(|inst| inst.com_seed).collect(),
+        root_gcs: instances.iter().map(|inst| inst.root_gc).collect(),
+    }))?;
+
+    session.apply(PhasePayload::Choose(ChoosePayload { m: config.m }))?;
+    session.apply(PhasePayload::Open(OpenPayload {
+        seeds: instances
+            .iter()
+            .filter(|inst| inst.instance_id != config.m)
+            .map(|inst| (inst.instance_id, inst.seed))
+            .collect(),
+    }))?;
+
+    // Bob: audit every opened instance against its commitment.
+    let watchdog = WatchdogConfig {
+        circuit: config.circuit,
+        bit_width: config.bit_width,
+        circuit_id,
+        margin_secs: 0,
+    };
+    let mut dispute = None;
+    for inst in instances
+        .iter_mut()
+        .filter(|inst| inst.instance_id != config.m)
+    {
+        let opened = OpenedInstance {
+            instance_id: inst.instance_id as u64,
+            seed: inst.seed,
+            root_gc: inst.root_gc,
+        };
+        let verdict =
+            audit_opened_instance(&watchdog, &opened, || Some(inst.claimed_leaves.clone()))?;
+        if let (None, InstanceVerdict::Dispute(prepared)) = (&dispute, &verdict) {
+            let payload = DisputePayload {
+                instance_id: inst.instance_id,
+                gate_index: prepared.gate_index,
+            };
+            session.apply(PhasePayload::Dispute(payload.clone()))?;
+            dispute = Some(payload);
+        }
+        inst.verdict = Some(verdict);
+    }
+    if dispute.is_some() {
+        return Ok(SimulatedSession {
+            circuit_id,
+            instances,
+            dispute,
+            evaluation: None,
+            session,
+        });
+    }
+
+    // Bob: evaluate instance m on Alice's x-labels and his own y-labels.
+    let evaluated = &instances[config.m];
+    let layout = layout_of(config.m);
+    let bit_width = config.bit_width as u16;
+    let x_labels = derive_layout_input_labels(
+        evaluated.seed,
+        &layout,
+        0,
+        &u64_to_bits_le(config.x, config.bit_width),
+    );
+    let y_labels = derive_layout_input_labels(
+        evaluated.seed,
+        &layout,
+        bit_width,
+        &u64_to_bits_le(config.y, config.bit_width),
+    );
+    let inputs = (0..)
+        .zip(x_labels.into_iter().chain(y_labels))
+        .collect::<Vec<(u16, _)>>();
+    let wires = evaluate_garbled_wires(
+        &layout,
+        &evaluated.claimed_leaves,
+        &inputs,
+        &derive_not_gate_hints(evaluated.seed, &layout),
+    )
+    .map_err(|e| format!("evaluating instance {}: {e}", config.m))?;
+    let output_wire = config.circuit.decision_wire(&gates, config.bit_width)?;
+    let output_label = wires
+        .get(output_wire as usize)
+        .copied()
+        .flatten()
+        .ok_or_else(|| format!("no label on decision wire {output_wire}"))?;
+    let decoding = OutputDecodingTable::derive(evaluated.seed, &layout, output_wire)?;
+    let output_bit = decoding.decode(output_label).ok_or_else(|| {
+        format!(
+            "instance {} decision label is neither output label",
+            config.m
+        )
+    })?;
+    session.apply(PhasePayload::Eval(EvalPayload {
+        output_label: label16_to_bytes32(output_label),
+        decoded_bit: Some(output_bit),
+    }))?;
+    session.apply(PhasePayload::Settle(SettlePayload {
+        output: vec![output_bit],
+    }))?;
+
+    Ok(SimulatedSession {
+        circuit_id,
+        instances,
+        dispute,
+        evaluation: Some(SimulatedEvaluation {
+            decoding,
+            output_label,
+            output_bit,
+        }),
+        session,
+    })
+}
@@ -0,0 +1,95 @@
+//! Secrets kept off the command line, where `ps` and the shell history would show them.
+//!
+//! A secret flag given as `-` (`--master-seed -`) is read before the arguments are parsed: at
+//! a hidden prompt when stdin is a terminal, else as the next line of stdin, so several
+//! secrets can be piped in the order their flags appear. `--key-stdin` reads the private key
+//! the same way, after those flags (`cli::start_key_stdin`).
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::Path;
+
+/// Value of a secret flag that reads the secret from stdin instead.
+pub const FROM_STDIN: &str = "-";
+
+/// Reads one secret: hidden after `prompt` on a terminal, else the next line of stdin.
+pub fn read_secret(prompt: &str) -> Result<String, String> {
+    let what = prompt.trim_end_matches(": ");
+    let read_err = |e: io::Error| format!("failed to read {what}: {e}");
+    let secret = if io::stdin().is_terminal() {
+        rpassword::prompt_password(prompt).map_err(read_err)?
+    } else {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line).map_err(read_err)?;
+        line
+    };
+    let secret = secret.trim();
+    if secret.is_empty() {
+        return Err(format!("{what} must not be empty"));
+    }
+    Ok(secret.to_string())
+}
+
+/// `args` (program name first) with every `flag -` or `flag=-` of `secret_flags` replaced by
+/// the secret `read` returns for it, in argument order.
+pub fn resolve_secret_args_with(
+    args: &[String],
+    secret_flags: &[&str],
+    mut read: impl FnMut(&str) -> Result<String, String>,
+) -> Result<Vec<String>, String> {
+    let mut resolved = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if let Some(flag) = secret_flags.iter().find(|flag| **flag == arg) {
+            resolved.push(arg.clone());
+            if let Some(value) = iter.next() {
+                resolved.push(match value.as_str() {
+                    FROM_STDIN => read(flag)?,
+                    _ => value.clone(),
+                });
+            }
+            continue;
+        }
+        let from_stdin = secret_flags
+            .iter()
+            .find(|flag| *arg == format!("{flag}={FROM_STDIN}"));
+        match from_stdin {
+            Some(flag) => resolved.push(format!("{flag}={}", read(flag)?)),
+            None => resolved.push(arg.clone()),
+        }
+    }
+    Ok(resolved)
+}
+
+/// `resolve_secret_args_with` reading each secret with `read_secret`, prompted as `<flag>: `.
+pub fn resolve_secret_args(args: &[String], secret_flags: &[&str]) -> Result<Vec<String>, String> {
+    resolve_secret_args_with(args, secret_flags, |flag| read_secret(&format!("{flag}: ")))
+}
+
+/// 32 bytes from `/dev/urandom`, for seeds, salts, nonces and tokens.
+pub fn random_bytes32() -> Result<[u8; 32], String> {
+    let mut bytes = [0u8; 32];
+    fs::File::open("/dev/urandom")
+        .and_then(|mut file| file.read_exact(&mut bytes))
+        .map_err(|e| format!("failed to read /dev/urandom: {e}"))?;
+    Ok(bytes)
+}
+
+/// Writes `contents` to `path` readable by the owner only (mode 0600 on unix), replacing the
+/// file through a temporary sibling so a reader never sees it half written.
+pub fn write_private_file(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let tmp = path.with_extension("tmp");
+    let write_err = |e: io::Error| format!("failed to write {}: {e}", path.display());
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let _ = fs::remove_file(&tmp);
+    let mut file = options.open(&tmp).map_err(write_err)?;
+    file.write_all(contents).map_err(write_err)?;
+    file.sync_all().map_err(write_err)?;
+    fs::rename(&tmp, path).map_err(write_err)
+}
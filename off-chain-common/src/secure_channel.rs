@@ -0,0 +1,278 @@
+//! Encrypted, authenticated channel for Alice↔Bob messages: a Noise XX handshake
+//! (`Noise_XX_25519_ChaChaPoly_BLAKE2s`, fresh static keys per connection) bound to the
+//! parties' Ethereum keys.
+//!
+//! After the handshake each side sends, inside the channel, its address and an EIP-191
+//! `personal_sign` signature over `keccak256("PPA-NOISE-BIND-V1", handshake_hash)`. The
+//! handshake hash covers both Noise keys, so a relay that runs separate handshakes with each
+//! party cannot forward the signatures. Each side rejects the peer unless the recovered
+//! signer equals the expected peer address (e.g. the on-chain `alice`/buyer).
+//!
+//! `SecureStream` implements `Read`/`Write`: writes are buffered and sent as Noise records
+//! (`u16` big-endian length, ciphertext) on `flush`, so `transport` frames and `messages`
+//! run over it unchanged.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+
+use snow::{Builder, TransportState};
+
+use crate::cli::hex_prefixed;
+use crate::consensus::keccak256;
+use crate::identity::{EthIdentity, recover_personal_sign};
+use crate::retry::{RetryPolicy, retry};
+use crate::transport::{ReceivedBatch, receive_batch, retry_on_listener, send_files};
+
+const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+/// Largest Noise message, ciphertext including the 16-byte tag.
+const MAX_NOISE_MESSAGE: usize = 65535;
+const MAX_RECORD_PLAINTEXT: usize = MAX_NOISE_MESSAGE - 16;
+/// Address (20 bytes) plus `r || s || v` signature (65 bytes).
+const BINDING_LEN: usize = 85;
+
+fn binding_message(handshake_hash: &[u8]) -> [u8; 32] {
+    keccak256(&[b"PPA-NOISE-BIND-V1", handshake_hash])
+}
+
+fn io_err(context: &str, e: impl fmt::Display) -> String {
+    format!("{context}: {e}")
+}
+
+fn write_record(stream: &mut impl Write, record: &[u8]) -> io::Result<()> {
+    stream.write_all(&(record.len() as u16).to_be_bytes())?;
+    stream.write_all(record)
+}
+
+fn read_record(stream: &mut impl Read) -> io::Result<Vec<u8>> {
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len)?;
+    let mut record = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut record)?;
+    Ok(record)
+}
+
+/// Noise-encrypted stream to a peer whose Ethereum address was verified.
+pub struct SecureStream<S> {
+    stream: S,
+    noise: TransportState,
+    peer_address: [u8; 20],
+    read_buf: Vec<u8>,
+    read_pos: usize,
+    write_buf: Vec<u8>,
+}
+
+impl<S> fmt::Debug for SecureStream<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureStream")
+            .field("peer_address", &hex_prefixed(&self.peer_address))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Runs the initiator side (the connecting party) over `stream`.
+pub fn connect<S: Read + Write>(
+    stream: S,
+    identity: &EthIdentity,
+    expected_peer: [u8; 20],
+) -> Result<SecureStream<S>, String> {
+    SecureStream::handshake(stream, identity, expected_peer, true)
+}
+
+/// Runs the responder side (the listening party) over `stream`.
+pub fn accept<S: Read + Write>(
+    stream: S,
+    identity: &EthIdentity,
+    expected_peer: [u8; 20],
+) -> Result<SecureStream<S>, String> {
+    SecureStream::handshake(stream, identity, expected_peer, false)
+}
+
+impl<S: Read + Write> SecureStream<S> {
+    fn handshake(
+        mut stream: S,
+        identity: &EthIdentity,
+        expected_peer: [u8; 20],
+        initiator: bool,
+    ) -> Result<Self, String> {
+        let params = NOISE_PARAMS.parse().expect("valid Noise parameters");
+        let builder = Builder::new(params);
+        let static_key = builder
+            .generate_keypair()
+            .map_err(|e| io_err("failed to generate Noise key", e))?;
+        let builder = builder.local_private_key(&static_key.private);
+        let mut noise = if initiator {
+            builder.build_initiator()
+        } else {
+            builder.build_responder()
+        }
+        .map_err(|e| io_err("failed to start Noise handshake", e))?;
+
+        // XX: -> e, <- e ee s es, -> s se
+        let mut buf = vec![0u8; MAX_NOISE_MESSAGE];
+        let mut sending = initiator;
+        while !noise.is_handshake_finished() {
+            if sending {
+                let len = noise
+                    .write_message(&[], &mut buf)
+                    .map_err(|e| io_err("Noise handshake failed", e))?;
+                write_record(&mut stream, &buf[..len])
+                    .and_then(|_| stream.flush())
+                    .map_err(|e| io_err("failed to send handshake message", e))?;
+            } else {
+                let record = read_record(&mut stream)
+                    .map_err(|e| io_err("failed to read handshake message", e))?;
+                noise
+                    .read_message(&record, &mut buf)
+                    .map_err(|e| io_err("Noise handshake failed", e))?;
+            }
+            sending = !sending;
+        }
+        let binding = binding_message(noise.get_handshake_hash());
+        let noise = noise
+            .into_transport_mode()
+            .map_err(|e| io_err("Noise handshake failed", e))?;
+
+        let mut secure = Self {
+            stream,
+            noise,
+            peer_address: [0u8; 20],
+            read_buf: Vec::new(),
+            read_pos: 0,
+            write_buf: Vec::new(),
+        };
+        let mut own = Vec::with_capacity(BINDING_LEN);
+        own.extend_from_slice(&identity.address());
+        own.extend_from_slice(&identity.personal_sign(binding)?);
+        let mut peer = [0u8; BINDING_LEN];
+        let send = |secure: &mut Self| {
+            secure
+                .write_all(&own)
+                .and_then(|_| secure.flush())
+                .map_err(|e| io_err("failed to send identity binding", e))
+        };
+        if initiator {
+            send(&mut secure)?;
+        }
+        secure
+            .read_exact(&mut peer)
+            .map_err(|e| io_err("failed to read identity binding", e))?;
+        if !initiator {
+            send(&mut secure)?;
+        }
+
+        let claimed: [u8; 20] = peer[..20].try_into().expect("20-byte prefix");
+        let signature: [u8; 65] = peer[20..].try_into().expect("65-byte suffix");
+        let signer = recover_personal_sign(binding, &signature)?;
+        if signer != claimed || signer != expected_peer {
+            return Err(format!(
+                "peer identity {} does not match expected {}",
+                hex_prefixed(&signer),
+                hex_prefixed(&expected_peer)
+            ));
+        }
+        secure.peer_address = signer;
+        Ok(secure)
+    }
+
+    /// Ethereum address the peer proved control of.
+    pub fn peer_address(&self) -> [u8; 20] {
+        self.peer_address
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+
+    fn send_record(&mut self, plaintext_len: usize) -> io::Result<()> {
+        let mut record = vec![0u8; plaintext_len + 16];
+        let len = self
+            .noise
+            .write_message(&self.write_buf[..plaintext_len], &mut record)
+            .map_err(|e| io::Error::other(format!("Noise encryption failed: {e}")))?;
+        write_record(&mut self.stream, &record[..len])?;
+        self.write_buf.drain(..plaintext_len);
+        Ok(())
+    }
+}
+
+impl<S: Read + Write> Write for SecureStream<S> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.write_buf.extend_from_slice(data);
+        while self.write_buf.len() >= MAX_RECORD_PLAINTEXT {
+            self.send_record(MAX_RECORD_PLAINTEXT)?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.write_buf.is_empty() {
+            self.send_record(self.write_buf.len())?;
+        }
+        self.stream.flush()
+    }
+}
+
+impl<S: Read + Write> Read for SecureStream<S> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.read_pos == self.read_buf.len() {
+            let record = match read_record(&mut self.stream) {
+                Ok(record) => record,
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                Err(e) => return Err(e),
+            };
+            let mut plaintext = vec![0u8; record.len()];
+            let len = self
+                .noise
+                .read_message(&record, &mut plaintext)
+                .map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Noise decryption failed: {e}"),
+                    )
+                })?;
+            plaintext.truncate(len);
+            self.read_buf = plaintext;
+            self.read_pos = 0;
+        }
+        let n = out.len().min(self.read_buf.len() - self.read_pos);
+        out[..n].copy_from_slice(&self.read_buf[self.read_pos..self.read_pos + n]);
+        self.read_pos += n;
+        Ok(n)
+    }
+}
+
+/// `transport::send_files_to` over a secure channel to `expected_peer`, redoing the handshake
+/// on every attempt.
+pub fn send_files_to(
+    addr: &str,
+    identity: &EthIdentity,
+    expected_peer: [u8; 20],
+    dir: &Path,
+    names: &[String],
+    policy: &RetryPolicy,
+) -> Result<usize, String> {
+    retry(policy, |_| {
+        let stream =
+            TcpStream::connect(addr).map_err(|e| format!("failed to connect to {addr}: {e}"))?;
+        let mut secure = connect(stream, identity, expected_peer)?;
+        send_files(&mut secure, dir, names)
+    })
+}
+
+/// `transport::receive_files_on` over a secure channel from `expected_peer`.
+pub fn receive_files_on(
+    listen_addr: &str,
+    identity: &EthIdentity,
+    expected_peer: [u8; 20],
+    out_dir: &Path,
+    policy: &RetryPolicy,
+) -> Result<(SocketAddr, ReceivedBatch), String> {
+    let listener = TcpListener::bind(listen_addr)
+        .map_err(|e| format!("failed to listen on {listen_addr}: {e}"))?;
+    retry_on_listener(&listener, policy, |stream| {
+        let mut secure = accept(stream, identity, expected_peer)?;
+        receive_batch(&mut secure, out_dir)
+    })
+}
@@ -0,0 +1,187 @@
+//! Persisted session state, so an interrupted run resumes where it stopped instead of being
+//! rebuilt from flags.
+//!
+//! A session directory (conventionally `.session`) holds plain-text files that are rewritten
+//! on every change:
+//! - `flags.txt`: `--flag=value` lines the binaries reuse when a flag is not given again,
+//! - `seeds.txt`: `name=0x..` secrets derived or drawn for this session (e.g. Alice's master
+//!   seed),
+//! - `completed.txt`: commands that finished successfully, in order; with the binaries'
+//!   `COMMAND_PHASES` it gives the phase the session reached and what runs next.
+//!
+//! `seeds.txt` is only readable by its owner. The protocol payloads of a session run, which
+//! replay its state machine position, are kept in the session's transcript
+//! (`transcript::Transcript`) next to these files.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{hex32, parse_bytes32};
+use crate::secrets::write_private_file;
+
+/// Conventional session directory name.
+pub const DEFAULT_SESSION_DIR: &str = ".session";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStore {
+    dir: PathBuf,
+    flags: Vec<(String, String)>,
+    seeds: Vec<(String, [u8; 32])>,
+    completed: Vec<String>,
+}
+
+impl SessionStore {
+    /// Opens the session in `dir`, creating an empty one if the directory does not exist.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, String> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)
+            .map_err(|e| format!("failed to create session dir {}: {e}", dir.display()))?;
+        let flags = read_lines(&dir, "flags.txt")?
+            .iter()
+            .map(|line| {
+                let (flag, value) = line
+                    .split_once('=')
+                    .ok_or_else(|| format!("invalid flags.txt line '{line}'"))?;
+                Ok((flag.to_string(), value.to_string()))
+            })
+            .collect::<Result<_, String>>()?;
+        let seeds = read_hash_entries(&dir, "seeds.txt")?;
+        let completed = read_lines(&dir, "completed.txt")?;
+        Ok(Self {
+            dir,
+            flags,
+            seeds,
+            completed,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn flags(&self) -> &[(String, String)] {
+        &self.flags
+    }
+
+    pub fn flag(&self, flag: &str) -> Option<&str> {
+        self.flags
+            .iter()
+            .find_map(|(name, value)| (name == flag).then_some(value.as_str()))
+    }
+
+    pub fn set_flag(&mut self, flag: &str, value: &str) -> Result<(), String> {
+        if !flag.starts_with("--") || flag.contains('=') || value.contains('\n') {
+            return Err(format!("cannot persist flag '{flag}'"));
+        }
+        upsert(&mut self.flags, flag, value.to_string());
+        let lines = self
+            .flags
+            .iter()
+            .map(|(flag, value)| format!("{flag}={value}"))
+            .collect::<Vec<_>>();
+        self.write_lines("flags.txt", &lines)
+    }
+
+    pub fn seed(&self, name: &str) -> Option<[u8; 32]> {
+        lookup(&self.seeds, name)
+    }
+
+    /// Names of the saved seeds, without their values.
+    pub fn seed_names(&self) -> Vec<&str> {
+        self.seeds.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    pub fn set_seed(&mut self, name: &str, seed: [u8; 32]) -> Result<(), String> {
+        check_name(name)?;
+        upsert(&mut self.seeds, name, seed);
+        let mut raw = hash_lines(&self.seeds).join("\n");
+        raw.push('\n');
+        write_private_file(&self.dir.join("seeds.txt"), raw.as_bytes())
+    }
+
+    /// Commands that completed, in order.
+    pub fn completed(&self) -> &[String] {
+        &self.completed
+    }
+
+    pub fn mark_completed(&mut self, command: &str) -> Result<(), String> {
+        check_name(command)?;
+        self.completed.push(command.to_string());
+        self.write_lines("completed.txt", &self.completed.clone())
+    }
+
+    /// Writes `file` through a temporary file and a rename, so a crash leaves either the old
+    /// or the new contents.
+    fn write_lines(&self, file: &str, lines: &[String]) -> Result<(), String> {
+        let path = self.dir.join(file);
+        let tmp = self.dir.join(format!("{file}.tmp"));
+        let mut raw = lines.join("\n");
+        if !raw.is_empty() {
+            raw.push('\n');
+        }
+        fs::write(&tmp, raw).map_err(|e| format!("failed to write {}: {e}", tmp.display()))?;
+        fs::rename(&tmp, &path).map_err(|e| format!("failed to write {}: {e}", path.display()))
+    }
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        && !name.starts_with('.');
+    if !valid {
+        return Err(format!("invalid session entry name '{name}'"));
+    }
+    Ok(())
+}
+
+fn upsert<T>(entries: &mut Vec<(String, T)>, name: &str, value: T) {
+    match entries.iter_mut().find(|(entry, _)| entry == name) {
+        Some((_, slot)) => *slot = value,
+        None => entries.push((name.to_string(), value)),
+    }
+}
+
+fn lookup(entries: &[(String, [u8; 32])], name: &str) -> Option<[u8; 32]> {
+    entries
+        .iter()
+        .find_map(|(entry, hash)| (entry == name).then_some(*hash))
+}
+
+fn hash_lines(entries: &[(String, [u8; 32])]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|(name, hash)| format!("{name}={}", hex32(*hash)))
+        .collect()
+}
+
+fn read_lines(dir: &Path, file: &str) -> Result<Vec<String>, String> {
+    let path = dir.join(file);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let raw =
+        fs::read_to_string(&path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+    Ok(raw
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn read_hash_entries(dir: &Path, file: &str) -> Result<Vec<(String, [u8; 32])>, String> {
+    read_lines(dir, file)?
+        .iter()
+        .map(|line| {
+            let (name, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("invalid {file} line '{line}'"))?;
+            Ok((
+                name.to_string(),
+                parse_bytes32(value).map_err(|e| e.to_string())?,
+            ))
+        })
+        .collect()
+}
@@ -0,0 +1,269 @@
+//! Deadline tracking for the contract's timeout and refund paths.
+//!
+//! Every contract stage has one deadline slot in `deadlines()`. Once it has passed without
+//! the stalling party acting, the waiting party may call the stage's liveness function:
+//! `refund()` for missing deposits, the buyer-seed and buyer-input finalizers, the
+//! `abortPhase*` penalties against Alice, `closeDispute()` after the dispute window, and
+//! `abortPhase6()` for Alice when no settlement arrives. `timeout_status` picks the path open
+//! to a party at a snapshot, so a caller only has to send it once it is claimable. Some paths
+//! also restrict the sender (`SenderRequirement`), e.g. `refund()` only pays back a non-empty
+//! vault, which `TimeoutPath::sender_blocker` checks before anything is sent.
+
+use crate::protocol::Party;
+
+/// Contract `Stage.Closed`.
+pub const CLOSED_STAGE: u8 = 11;
+
+/// Contract `Stage` names, indexed by `currentStage()`.
+pub const STAGE_NAMES: [&str; 12] = [
+    "Deposits",
+    "BuyerSeedCommit",
+    "CommitmentsCore",
+    "BuyerSeedReveal",
+    "CommitmentsOT",
+    "BuyerInputOT",
+    "Open",
+    "Dispute",
+    "Labels",
+    "Settle",
+    "Assignment",
+    "Closed",
+];
+
+pub fn stage_name(stage: u8) -> Result<&'static str, String> {
+    STAGE_NAMES
+        .get(stage as usize)
+        .copied()
+        .ok_or_else(|| format!("unknown contract stage {stage}"))
+}
+
+/// `deadlines()` in field order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Deadlines {
+    pub deposit: u64,
+    pub verifier_seed: u64,
+    pub commit: u64,
+    pub buyer_input_ot: u64,
+    pub open: u64,
+    pub dispute: u64,
+    pub labels: u64,
+    pub settle: u64,
+}
+
+impl Deadlines {
+    /// Builds the struct from the eight `deadlines()` return values.
+    pub fn from_fields(fields: &[u64]) -> Result<Self, String> {
+        let [
+            deposit,
+            verifier_seed,
+            commit,
+            buyer_input_ot,
+            open,
+            dispute,
+            labels,
+            settle,
+        ] = fields
+        else {
+            return Err(format!("deadlines() has 8 fields, got {}", fields.len()));
+        };
+        Ok(Self {
+            deposit: *deposit,
+            verifier_seed: *verifier_seed,
+            commit: *commit,
+            buyer_input_ot: *buyer_input_ot,
+            open: *open,
+            dispute: *dispute,
+            labels: *labels,
+            settle: *settle,
+        })
+    }
+
+    /// Every slot with its `deadlines()` field name, in field order.
+    pub fn named(&self) -> [(&'static str, u64); 8] {
+        [
+            ("deposit", self.deposit),
+            ("verifierSeed", self.verifier_seed),
+            ("commit", self.commit),
+            ("buyerInputOt", self.buyer_input_ot),
+            ("open", self.open),
+            ("dispute", self.dispute),
+            ("labels", self.labels),
+            ("settle", self.settle),
+        ]
+    }
+
+    /// Deadline slot that bounds `stage`; `None` once the contract is closed.
+    pub fn for_stage(&self, stage: u8) -> Option<u64> {
+        match stage {
+            0 => Some(self.deposit),
+            1 | 3 => Some(self.verifier_seed),
+            2 | 4 => Some(self.commit),
+            5 => Some(self.buyer_input_ot),
+            6 => Some(self.open),
+            7 => Some(self.dispute),
+            8 => Some(self.labels),
+            // Assignment reuses the settle slot.
+            9 | 10 => Some(self.settle),
+            _ => None,
+        }
+    }
+}
+
+/// Who the contract lets send a timeout call, checked next to the deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SenderRequirement {
+    Anyone,
+    /// Alice or a registered buyer.
+    Participant,
+    /// `refund()` pays back the sender's own vault, which must not be empty.
+    OwnDeposit,
+    /// The penalties against Alice are claimed by a registered buyer.
+    Buyer,
+    Alice,
+}
+
+/// The sender's on-chain role and vault, as `claim_timeout` reads them before sending.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SenderState {
+    /// `alice() == sender`.
+    pub is_alice: bool,
+    /// `isBuyer(sender)`.
+    pub is_buyer: bool,
+    /// `vault(sender)`.
+    pub vault_wei: [u8; 32],
+}
+
+/// Liveness function a party may call once a stage's deadline has passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutPath {
+    pub stage: u8,
+    /// Function signature for `cast send`.
+    pub signature: &'static str,
+    pub deadline: u64,
+    pub effect: &'static str,
+    pub sender: SenderRequirement,
+}
+
+impl TimeoutPath {
+    /// Why the contract would revert the call from `sender` even past the deadline, if it
+    /// would.
+    pub fn sender_blocker(&self, sender: &SenderState) -> Option<&'static str> {
+        match self.sender {
+            SenderRequirement::Anyone => None,
+            SenderRequirement::Participant if !sender.is_alice && !sender.is_buyer => {
+                Some("the sender is neither Alice nor a buyer")
+            }
+            SenderRequirement::OwnDeposit if sender.vault_wei == [0; 32] => {
+                Some("the sender's vault is empty, nothing to refund")
+            }
+            SenderRequirement::Buyer if !sender.is_buyer => Some("the sender is not a buyer"),
+            SenderRequirement::Alice if !sender.is_alice => Some("the sender is not Alice"),
+            _ => None,
+        }
+    }
+}
+
+/// What the contract reads say about the current stage's deadline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutSnapshot {
+    /// `currentStage()`.
+    pub stage: u8,
+    /// Timestamp of the latest block.
+    pub now: u64,
+    pub deadlines: Deadlines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutStatus {
+    /// The contract is closed; nothing left to claim.
+    Closed,
+    /// `party` has no timeout path in this stage (it is the one expected to act).
+    NoPath,
+    /// Deadline not reached yet; the contract would revert.
+    Waiting {
+        path: TimeoutPath,
+        /// Seconds until the path becomes claimable.
+        seconds_left: u64,
+    },
+    /// The deadline has passed and `path` can be sent.
+    Claimable(TimeoutPath),
+}
+
+/// Timeout function open to `party` in `stage`, whether or not its deadline has passed.
+pub fn timeout_path(stage: u8, party: Party, deadlines: &Deadlines) -> Option<TimeoutPath> {
+    let buyer = party != Party::Garbler;
+    let (signature, effect, sender) = match stage {
+        0 => (
+            "refund()",
+            "withdraw the own deposit when a party did not deposit",
+            SenderRequirement::OwnDeposit,
+        ),
+        1 => (
+            "finalizeBuyerSeedCommitAfterDeadline()",
+            "slash buyers without a seed commitment and move to seed reveal",
+            SenderRequirement::Participant,
+        ),
+        2 | 4 if buyer => (
+            "abortPhase2()",
+            "Alice missed her commitments: reclaim the deposit plus her collateral",
+            SenderRequirement::Buyer,
+        ),
+        3 => (
+            "finalizeBuyerSeedRevealAfterDeadline()",
+            "slash buyers that did not reveal their seed and move to commitments",
+            SenderRequirement::Participant,
+        ),
+        5 => (
+            "finalizeBuyerInputAfterDeadline()",
+            "default buyers that did not confirm their input and open the instances",
+            SenderRequirement::Anyone,
+        ),
+        6 if buyer => (
+            "abortPhase4()",
+            "Alice did not reveal the openings: claim her collateral",
+            SenderRequirement::Buyer,
+        ),
+        7 => (
+            "closeDispute()",
+            "close the dispute window and move to labels",
+            SenderRequirement::Participant,
+        ),
+        8 if buyer => (
+            "abortPhase5()",
+            "Alice did not reveal her input labels: claim her collateral",
+            SenderRequirement::Buyer,
+        ),
+        9 | 10 if !buyer => (
+            "abortPhase6()",
+            "no settlement arrived: Alice claims every vault",
+            SenderRequirement::Alice,
+        ),
+        _ => return None,
+    };
+    Some(TimeoutPath {
+        stage,
+        signature,
+        deadline: deadlines.for_stage(stage)?,
+        effect,
+        sender,
+    })
+}
+
+/// Where `party` stands at `snapshot`. The contract requires `block.timestamp > deadline`.
+pub fn timeout_status(snapshot: &TimeoutSnapshot, party: Party) -> Result<TimeoutStatus, String> {
+    stage_name(snapshot.stage)?;
+    if snapshot.stage == CLOSED_STAGE {
+        return Ok(TimeoutStatus::Closed);
+    }
+    let Some(path) = timeout_path(snapshot.stage, party, &snapshot.deadlines) else {
+        return Ok(TimeoutStatus::NoPath);
+    };
+    if snapshot.now > path.deadline {
+        Ok(TimeoutStatus::Claimable(path))
+    } else {
+        Ok(TimeoutStatus::Waiting {
+            path,
+            seconds_left: path.deadline - snapshot.now + 1,
+        })
+    }
+}
@@ -0,0 +1,286 @@
+//! Append-only, hash-chained transcript of a session: every protocol message a party sent or
+//! received and every on-chain action it took, in order.
+//!
+//! The transcript is a text file with one entry per line:
+//! `<index> <prev_hash> <hash> <event>`, where `hash = keccak256("PPA-TRANSCRIPT-V1" ||
+//! index || prev_hash || event)` and `prev_hash` is the previous entry's hash (zero for the
+//! first). Events are:
+//! - `sent <0x cbor>` / `received <0x cbor>`: a `messages` encoding, signed or not,
+//! - `action <label> <tx_hash|-> [<phase> <fields...>]`: a transaction, optionally with the
+//!   `protocol::PhasePayload` it carried (in `protocol::encode_payload`'s text form).
+//!
+//! Editing, dropping or reordering a line breaks the chain, so `Transcript::open` rejects it;
+//! `Transcript::replay` then re-checks every message and payload against a fresh
+//! `ProtocolSession`.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{decode_hex, hex_prefixed, hex32};
+use crate::consensus::keccak256;
+use crate::messages::Envelope;
+use crate::protocol::{Party, PhasePayload, ProtocolSession, decode_payload, encode_payload};
+
+/// Transcript file name inside a session directory.
+pub const TRANSCRIPT_FILE: &str = "transcript.log";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptEvent {
+    /// Encoded message handed to the peer.
+    Sent(Vec<u8>),
+    /// Encoded message taken from the peer.
+    Received(Vec<u8>),
+    /// On-chain action.
+    Action {
+        label: String,
+        /// Transaction hash as reported by `cast`, if any.
+        tx_hash: Option<String>,
+        payload: Option<PhasePayload>,
+    },
+}
+
+impl TranscriptEvent {
+    fn encode(&self) -> Result<String, String> {
+        match self {
+            TranscriptEvent::Sent(bytes) => Ok(format!("sent {}", hex_prefixed(bytes))),
+            TranscriptEvent::Received(bytes) => Ok(format!("received {}", hex_prefixed(bytes))),
+            TranscriptEvent::Action {
+                label,
+                tx_hash,
+                payload,
+            } => {
+                for token in std::iter::once(label).chain(tx_hash) {
+                    if token.is_empty() || token == "-" || token.contains(char::is_whitespace) {
+                        return Err(format!("invalid transcript token '{token}'"));
+                    }
+                }
+                let mut out = format!("action {label} {}", tx_hash.as_deref().unwrap_or("-"));
+                if let Some(payload) = payload {
+                    out.push(' ');
+                    out.push_str(&encode_payload(payload));
+                }
+                Ok(out)
+            }
+        }
+    }
+
+    fn decode(text: &str) -> Result<Self, String> {
+        let (kind, rest) = text.split_once(' ').unwrap_or((text, ""));
+        let hex = |raw: &str| decode_hex(raw).map_err(|e| e.to_string());
+        match kind {
+            "sent" => Ok(TranscriptEvent::Sent(hex(rest)?)),
+            "received" => Ok(TranscriptEvent::Received(hex(rest)?)),
+            "action" => {
+                let mut parts = rest.splitn(3, ' ');
+                let label = parts.next().filter(|label| !label.is_empty());
+                let (Some(label), Some(tx_hash)) = (label, parts.next()) else {
+                    return Err(format!("invalid action '{rest}'"));
+                };
+                Ok(TranscriptEvent::Action {
+                    label: label.to_string(),
+                    tx_hash: (tx_hash != "-").then(|| tx_hash.to_string()),
+                    payload: parts.next().map(decode_payload).transpose()?,
+                })
+            }
+            other => Err(format!("unknown transcript event '{other}'")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptEntry {
+    pub index: u64,
+    pub prev_hash: [u8; 32],
+    pub hash: [u8; 32],
+    pub event: TranscriptEvent,
+}
+
+fn entry_hash(index: u64, prev_hash: [u8; 32], event_text: &str) -> [u8; 32] {
+    keccak256(&[
+        b"PPA-TRANSCRIPT-V1",
+        &index.to_be_bytes(),
+        &prev_hash,
+        event_text.as_bytes(),
+    ])
+}
+
+/// What replaying a transcript established.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptReplay {
+    /// State machine after every recorded payload.
+    pub session: ProtocolSession,
+    pub sent: usize,
+    pub received: usize,
+    pub actions: usize,
+    /// Contract the signed messages are bound to.
+    pub contract: Option<[u8; 20]>,
+    /// Recovered signer per sending role, in order of first appearance.
+    pub signers: Vec<(Party, [u8; 20])>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    path: PathBuf,
+    entries: Vec<TranscriptEntry>,
+}
+
+impl Transcript {
+    /// Opens the transcript at `path` (empty if missing), verifying the hash chain.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let raw = match fs::read_to_string(&path) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("failed to read {}: {e}", path.display())),
+        };
+        let mut transcript = Self {
+            path,
+            entries: Vec::new(),
+        };
+        for (line_no, line) in raw.lines().enumerate() {
+            let entry = transcript
+                .parse_entry(line)
+                .map_err(|e| format!("transcript entry {}: {e}", line_no + 1))?;
+            transcript.entries.push(entry);
+        }
+        Ok(transcript)
+    }
+
+    fn parse_entry(&self, line: &str) -> Result<TranscriptEntry, String> {
+        let mut parts = line.splitn(4, ' ');
+        let (Some(index), Some(prev_hash), Some(hash), Some(event_text)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err("expected `<index> <prev_hash> <hash> <event>`".to_string());
+        };
+        let index = index
+            .parse::<u64>()
+            .map_err(|_| format!("invalid index '{index}'"))?;
+        let parse_hash = |raw: &str| -> Result<[u8; 32], String> {
+            decode_hex(raw)
+                .map_err(|e| e.to_string())?
+                .try_into()
+                .map_err(|_| format!("expected a 32-byte hash: {raw}"))
+        };
+        let (prev_hash, hash) = (parse_hash(prev_hash)?, parse_hash(hash)?);
+        if index != self.entries.len() as u64 {
+            return Err(format!("index {index} out of order"));
+        }
+        if prev_hash != self.head() {
+            return Err("previous hash does not match the chain".to_string());
+        }
+        if hash != entry_hash(index, prev_hash, event_text) {
+            return Err("hash does not match its contents".to_string());
+        }
+        Ok(TranscriptEntry {
+            index,
+            prev_hash,
+            hash,
+            event: TranscriptEvent::decode(event_text)?,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn entries(&self) -> &[TranscriptEntry] {
+        &self.entries
+    }
+
+    /// Hash of the last entry; zero for an empty transcript.
+    pub fn head(&self) -> [u8; 32] {
+        self.entries.last().map_or([0; 32], |entry| entry.hash)
+    }
+
+    /// Appends `event` to the file and the chain.
+    pub fn append(&mut self, event: TranscriptEvent) -> Result<&TranscriptEntry, String> {
+        let event_text = event.encode()?;
+        let index = self.entries.len() as u64;
+        let prev_hash = self.head();
+        let hash = entry_hash(index, prev_hash, &event_text);
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("failed to create {}: {e}", dir.display()))?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| format!("failed to open {}: {e}", self.path.display()))?;
+        writeln!(
+            file,
+            "{index} {} {} {event_text}",
+            hex32(prev_hash),
+            hex32(hash)
+        )
+        .map_err(|e| format!("failed to write {}: {e}", self.path.display()))?;
+        self.entries.push(TranscriptEntry {
+            index,
+            prev_hash,
+            hash,
+            event,
+        });
+        Ok(self.entries.last().expect("just pushed"))
+    }
+
+    /// Re-checks the session: every message decodes, signed messages share one contract and
+    /// one signer per role, and the recorded payloads drive `session` without a rejection.
+    pub fn replay(&self, mut session: ProtocolSession) -> Result<TranscriptReplay, String> {
+        let (mut sent, mut received, mut actions) = (0, 0, 0);
+        let mut contract = None;
+        let mut signers: Vec<(Party, [u8; 20])> = Vec::new();
+        for entry in &self.entries {
+            let at = |e: String| format!("transcript entry {}: {e}", entry.index + 1);
+            let bytes = match &entry.event {
+                TranscriptEvent::Sent(bytes) => {
+                    sent += 1;
+                    bytes
+                }
+                TranscriptEvent::Received(bytes) => {
+                    received += 1;
+                    bytes
+                }
+                TranscriptEvent::Action { payload, .. } => {
+                    actions += 1;
+                    if let Some(payload) = payload {
+                        session.apply(payload.clone()).map_err(at)?;
+                    }
+                    continue;
+                }
+            };
+            let Envelope::Signed(signed) = Envelope::from_cbor(bytes).map_err(at)? else {
+                continue;
+            };
+            if *contract.get_or_insert(signed.contract) != signed.contract {
+                return Err(at(format!(
+                    "message signed for contract {}, earlier ones for {}",
+                    hex_prefixed(&signed.contract),
+                    hex_prefixed(&contract.unwrap_or_default())
+                )));
+            }
+            let role = signed.message.sender();
+            let signer = signed.signer().map_err(at)?;
+            match signers.iter().find(|(known, _)| *known == role) {
+                Some((_, known)) if *known != signer => {
+                    return Err(at(format!(
+                        "{role:?} message signed by {}, earlier ones by {}",
+                        hex_prefixed(&signer),
+                        hex_prefixed(known)
+                    )));
+                }
+                Some(_) => {}
+                None => signers.push((role, signer)),
+            }
+        }
+        Ok(TranscriptReplay {
+            session,
+            sent,
+            received,
+            actions,
+            contract,
+            signers,
+        })
+    }
+}
@@ -0,0 +1,333 @@
+//! Peer-to-peer transport between the alice and bob binaries: length-prefixed frames over a
+//! byte stream (TCP in the CLIs), used to ship eval packages, OT messages and dispute data
+//! as named files instead of copying directories by hand.
+//!
+//! Wire format of one frame: `u32` big-endian body length, then the body
+//! `tag:u8 || fields`. Tags:
+//! - `0x01` file: `name_len:u16 || name || bytes`,
+//! - `0x02` done: no fields, ends a batch,
+//! - `0x03` begin: `key:[u8; 32]`, opens a batch with its `batch_key`.
+//!
+//! The begin key makes resends idempotent: the receiver records the keys of completed batches
+//! in `BATCH_LEDGER` and drains a batch it already has without touching its files again, so a
+//! sender retrying after a dropped connection cannot clobber files the receiver has moved on
+//! with.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::cli::hex_prefixed;
+use crate::consensus::keccak256;
+use crate::retry::{RetryPolicy, retry};
+
+/// Upper bound on one frame body; larger length prefixes are rejected before allocating.
+pub const MAX_FRAME_LEN: usize = 64 << 20;
+
+const TAG_FILE: u8 = 0x01;
+const TAG_DONE: u8 = 0x02;
+const TAG_BEGIN: u8 = 0x03;
+
+/// File in the receiving directory listing the keys of completed batches, one per line.
+pub const BATCH_LEDGER: &str = ".transport-batches";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+    /// One file of a batch; `name` is a bare file name without directories.
+    File { name: String, bytes: Vec<u8> },
+    /// End of a batch.
+    Done,
+    /// Start of a batch; `key` identifies its content across resends.
+    Begin { key: [u8; 32] },
+}
+
+/// Accepts plain file names only, so a peer cannot write outside the target directory. Hidden
+/// names are the receiver's own bookkeeping (`BATCH_LEDGER`, `base_ot::ANSWERED_CHOICES_FILE`)
+/// and are refused too.
+pub fn check_file_name(name: &str) -> Result<(), String> {
+    if name.is_empty()
+        || name.starts_with('.')
+        || name.contains(['/', '\\', '\0'])
+        || name.len() > u16::MAX as usize
+    {
+        return Err(format!("invalid transport file name '{name}'"));
+    }
+    Ok(())
+}
+
+impl Frame {
+    /// Frame body (without the length prefix).
+    pub fn encode(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Frame::File { name, bytes } => {
+                check_file_name(name)?;
+                let mut out = Vec::with_capacity(3 + name.len() + bytes.len());
+                out.push(TAG_FILE);
+                out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+                out.extend_from_slice(name.as_bytes());
+                out.extend_from_slice(bytes);
+                Ok(out)
+            }
+            Frame::Done => Ok(vec![TAG_DONE]),
+            Frame::Begin { key } => {
+                let mut out = vec![TAG_BEGIN];
+                out.extend_from_slice(key);
+                Ok(out)
+            }
+        }
+    }
+
+    pub fn decode(body: &[u8]) -> Result<Self, String> {
+        let Some((tag, rest)) = body.split_first() else {
+            return Err("empty transport frame".to_string());
+        };
+        match *tag {
+            TAG_FILE => {
+                if rest.len() < 2 {
+                    return Err("truncated file frame header".to_string());
+                }
+                let name_len = u16::from_be_bytes([rest[0], rest[1]]) as usize;
+                let rest = &rest[2..];
+                if rest.len() < name_len {
+                    return Err("truncated file frame name".to_string());
+                }
+                let name = String::from_utf8(rest[..name_len].to_vec())
+                    .map_err(|_| "file frame name is not UTF-8".to_string())?;
+                check_file_name(&name)?;
+                Ok(Frame::File {
+                    name,
+                    bytes: rest[name_len..].to_vec(),
+                })
+            }
+            TAG_DONE if rest.is_empty() => Ok(Frame::Done),
+            TAG_DONE => Err("done frame carries trailing bytes".to_string()),
+            TAG_BEGIN => rest
+                .try_into()
+                .map(|key| Frame::Begin { key })
+                .map_err(|_| format!("begin frame has {} key bytes, expected 32", rest.len())),
+            other => Err(format!("unknown transport frame tag 0x{other:02x}")),
+        }
+    }
+}
+
+/// Writes `body` behind a `u32` big-endian length prefix.
+pub fn write_length_prefixed(writer: &mut impl Write, body: &[u8]) -> Result<(), String> {
+    if body.len() > MAX_FRAME_LEN {
+        return Err(format!(
+            "transport frame of {} bytes exceeds MAX_FRAME_LEN ({MAX_FRAME_LEN})",
+            body.len()
+        ));
+    }
+    writer
+        .write_all(&(body.len() as u32).to_be_bytes())
+        .and_then(|_| writer.write_all(body))
+        .and_then(|_| writer.flush())
+        .map_err(|e| format!("failed to write transport frame: {e}"))
+}
+
+/// Reads one body written by `write_length_prefixed`.
+pub fn read_length_prefixed(reader: &mut impl Read) -> Result<Vec<u8>, String> {
+    let mut len = [0u8; 4];
+    reader
+        .read_exact(&mut len)
+        .map_err(|e| format!("failed to read transport frame length: {e}"))?;
+    let len = u32::from_be_bytes(len) as usize;
+    if len > MAX_FRAME_LEN {
+        return Err(format!(
+            "transport frame of {len} bytes exceeds MAX_FRAME_LEN ({MAX_FRAME_LEN})"
+        ));
+    }
+    let mut body = vec![0u8; len];
+    reader
+        .read_exact(&mut body)
+        .map_err(|e| format!("failed to read transport frame body: {e}"))?;
+    Ok(body)
+}
+
+pub fn write_frame(writer: &mut impl Write, frame: &Frame) -> Result<(), String> {
+    write_length_prefixed(writer, &frame.encode()?)
+}
+
+pub fn read_frame(reader: &mut impl Read) -> Result<Frame, String> {
+    Frame::decode(&read_length_prefixed(reader)?)
+}
+
+/// Sorted names of the regular, non-hidden files directly inside `dir`.
+pub fn list_dir_files(dir: &Path) -> Result<Vec<String>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("failed to read {}: {e}", dir.display()))?;
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|e| format!("failed to read {}: {e}", dir.display()))?;
+        if entry.file_type().map(|t| t.is_file()).unwrap_or(false)
+            && !entry.file_name().to_string_lossy().starts_with('.')
+        {
+            names.push(entry.file_name().to_string_lossy().into_owned());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Idempotency key of a batch: keccak over the names and contents in send order.
+pub fn batch_key(files: &[(String, Vec<u8>)]) -> [u8; 32] {
+    let mut parts: Vec<&[u8]> = vec![b"PPA-TRANSPORT-BATCH-V1"];
+    let lens = files
+        .iter()
+        .map(|(name, bytes)| {
+            (
+                (name.len() as u64).to_be_bytes(),
+                (bytes.len() as u64).to_be_bytes(),
+            )
+        })
+        .collect::<Vec<_>>();
+    for ((name, bytes), (name_len, bytes_len)) in files.iter().zip(&lens) {
+        parts.extend([&name_len[..], name.as_bytes(), &bytes_len[..], bytes]);
+    }
+    keccak256(&parts)
+}
+
+/// Sends `names` from `dir` as one batch between `Frame::Begin` and `Frame::Done`; returns
+/// the bytes sent.
+pub fn send_files(writer: &mut impl Write, dir: &Path, names: &[String]) -> Result<usize, String> {
+    let mut files = Vec::with_capacity(names.len());
+    for name in names {
+        check_file_name(name)?;
+        let path = dir.join(name);
+        let bytes =
+            fs::read(&path).map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        files.push((name.clone(), bytes));
+    }
+    write_frame(
+        writer,
+        &Frame::Begin {
+            key: batch_key(&files),
+        },
+    )?;
+    let mut total = 0;
+    for (name, bytes) in files {
+        total += bytes.len();
+        write_frame(writer, &Frame::File { name, bytes })?;
+    }
+    write_frame(writer, &Frame::Done)?;
+    Ok(total)
+}
+
+fn ledger_keys(out_dir: &Path) -> Result<Vec<String>, String> {
+    let path = out_dir.join(BATCH_LEDGER);
+    match fs::read_to_string(&path) {
+        Ok(raw) => Ok(raw.lines().map(str::to_string).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("failed to read {}: {e}", path.display())),
+    }
+}
+
+/// Outcome of `receive_batch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedBatch {
+    /// File names in arrival order.
+    pub names: Vec<String>,
+    /// `Frame::Begin` key, absent for senders that do not announce one.
+    pub key: Option<[u8; 32]>,
+    /// The batch was received before; its files were left untouched.
+    pub duplicate: bool,
+}
+
+/// Receives one batch into `out_dir` (created if missing), overwriting existing files unless
+/// the batch key is already in `BATCH_LEDGER`.
+pub fn receive_batch(reader: &mut impl Read, out_dir: &Path) -> Result<ReceivedBatch, String> {
+    fs::create_dir_all(out_dir)
+        .map_err(|e| format!("failed to create {}: {e}", out_dir.display()))?;
+    let mut batch = ReceivedBatch {
+        names: Vec::new(),
+        key: None,
+        duplicate: false,
+    };
+    loop {
+        match read_frame(reader)? {
+            Frame::Begin { key } if batch.key.is_none() && batch.names.is_empty() => {
+                batch.key = Some(key);
+                batch.duplicate = ledger_keys(out_dir)?.contains(&hex_prefixed(&key));
+            }
+            Frame::Begin { .. } => return Err("unexpected begin frame inside a batch".to_string()),
+            Frame::File { name, bytes } => {
+                if batch.names.contains(&name) {
+                    return Err(format!("file '{name}' received twice in one batch"));
+                }
+                if !batch.duplicate {
+                    let path = out_dir.join(&name);
+                    fs::write(&path, bytes)
+                        .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+                }
+                batch.names.push(name);
+            }
+            Frame::Done => break,
+        }
+    }
+    if let (Some(key), false) = (batch.key, batch.duplicate) {
+        let path = out_dir.join(BATCH_LEDGER);
+        let mut ledger = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+        writeln!(ledger, "{}", hex_prefixed(&key))
+            .map_err(|e| format!("failed to write {}: {e}", path.display()))?;
+    }
+    Ok(batch)
+}
+
+/// `receive_batch` returning only the received names.
+pub fn receive_files(reader: &mut impl Read, out_dir: &Path) -> Result<Vec<String>, String> {
+    receive_batch(reader, out_dir).map(|batch| batch.names)
+}
+
+/// Connects to `addr` and sends one batch of files, reconnecting on transient failures;
+/// returns the bytes sent.
+pub fn send_files_to(
+    addr: &str,
+    dir: &Path,
+    names: &[String],
+    policy: &RetryPolicy,
+) -> Result<usize, String> {
+    retry(policy, |_| {
+        let mut stream =
+            TcpStream::connect(addr).map_err(|e| format!("failed to connect to {addr}: {e}"))?;
+        send_files(&mut stream, dir, names)
+    })
+}
+
+/// Listens on `listen_addr` and receives one batch into `out_dir`. A connection that drops
+/// mid-batch is replaced by the sender's next attempt, up to `policy.max_attempts`.
+pub fn receive_files_on(
+    listen_addr: &str,
+    out_dir: &Path,
+    policy: &RetryPolicy,
+) -> Result<(SocketAddr, ReceivedBatch), String> {
+    let listener = TcpListener::bind(listen_addr)
+        .map_err(|e| format!("failed to listen on {listen_addr}: {e}"))?;
+    retry_on_listener(&listener, policy, |mut stream| {
+        receive_batch(&mut stream, out_dir)
+    })
+}
+
+/// Accepts connections on `listener` until `handle` succeeds on one, retrying on transient
+/// errors without backing off (the peer paces its own reconnects).
+pub(crate) fn retry_on_listener<T>(
+    listener: &TcpListener,
+    policy: &RetryPolicy,
+    mut handle: impl FnMut(TcpStream) -> Result<T, String>,
+) -> Result<(SocketAddr, T), String> {
+    let accept_policy = RetryPolicy {
+        initial_delay: Duration::ZERO,
+        ..*policy
+    };
+    retry(&accept_policy, |_| {
+        let (stream, peer) = listener
+            .accept()
+            .map_err(|e| format!("failed to accept a connection: {e}"))?;
+        Ok((peer, handle(stream)?))
+    })
+}
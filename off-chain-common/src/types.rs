@@ -1,16 +1,82 @@
-/// Supported gate opcodes; numeric values match Solidity `GateType`.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+use crate::canonical_json::hex_bytes;
+
+/// Supported gate opcodes; numeric values `0..=2` match Solidity `GateType`.
+/// Opcodes from `3` on belong to `GateSet::V2`; the contract decodes both sets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 #[repr(u8)]
 pub enum GateType {
     And = 0,
     Xor = 1,
     Not = 2,
+    Or = 3,
+    Nand = 4,
+    Nor = 5,
+}
+
+impl GateType {
+    /// Decodes an opcode byte (any gate set).
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::And),
+            1 => Some(Self::Xor),
+            2 => Some(Self::Not),
+            3 => Some(Self::Or),
+            4 => Some(Self::Nand),
+            5 => Some(Self::Nor),
+            _ => None,
+        }
+    }
+
+    /// `NOT` is the only single-input gate; it has no row table.
+    pub fn is_unary(self) -> bool {
+        self == Self::Not
+    }
+
+    /// Oldest gate-set version containing this opcode.
+    pub fn gate_set(self) -> GateSet {
+        match self {
+            Self::And | Self::Xor | Self::Not => GateSet::V1,
+            Self::Or | Self::Nand | Self::Nor => GateSet::V2,
+        }
+    }
+}
+
+/// Gate-set version flag.
+/// `V1` is the original opcode set (`AND`, `XOR`, `NOT`); `V2` adds native `OR`, `NAND`,
+/// `NOR`. Both are garbled, evaluated and recomputed by `disputeGarbledTable` alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum GateSet {
+    #[default]
+    V1 = 1,
+    V2 = 2,
+}
+
+impl GateSet {
+    /// Whether `gate_type` may appear in a layout of this gate set.
+    pub fn allows(self, gate_type: GateType) -> bool {
+        gate_type.gate_set() <= self
+    }
+
+    /// Checks every gate of a layout against this gate set.
+    pub fn check_gates(self, gates: &[GateDesc]) -> Result<(), String> {
+        match gates.iter().position(|gate| !self.allows(gate.gate_type)) {
+            Some(idx) => Err(format!(
+                "gate {idx} uses {:?}, which is not in gate set {self:?}",
+                gates[idx].gate_type
+            )),
+            None => Ok(()),
+        }
+    }
 }
 
 /// One gate descriptor from circuit layout.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct GateDesc {
-    /// Gate opcode (`AND`, `XOR`, `NOT`).
+    /// Gate opcode (`AND`, `XOR`, `NOT`, plus `GateSet::V2` extensions).
     pub gate_type: GateType,
     /// Left input wire index.
     pub wire_a: u16,
@@ -32,13 +98,369 @@ impl GateDesc {
     }
 }
 
+/// Wire fixed to a public constant bit (e.g. padding for narrower bids).
+/// Its active label is public (`consensus::derive_constant_label`), so the evaluator needs no
+/// input label for it. The Solidity dispute path does not model constants, so layouts using
+/// them are off-chain only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConstantWire {
+    pub wire: u16,
+    /// Constant semantic bit (`0` or `1`).
+    pub value: u8,
+}
+
+/// Garbling scheme of an instance; part of consensus for both parties.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GarblingScheme {
+    /// Independent labels per wire and 4 rows for every two-input gate.
+    /// The only scheme the Solidity dispute path can recompute.
+    #[default]
+    Classic,
+    /// Free-XOR: per-instance global `delta` with `L1 = L0 XOR delta`. XOR and NOT gates carry
+    /// no ciphertexts (all-zero rows) and need no hints. Off-chain only, because output labels
+    /// of XOR gates depend on the gate topology rather than on one gate's descriptor.
+    FreeXor,
+}
+
+/// Gate-leaf encoding of an instance.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafFormat {
+    /// Fixed 71-byte leaves (`header || row0..row3`), as recomputed by Solidity.
+    #[default]
+    V1,
+    /// Garbled row reduction (GRR3): the output label selected by permutation row `0` is
+    /// that row's pad, so row `0` is always zero and dropped. Leaves start with a version
+    /// byte `0x80 | schemeId` (never a gate opcode), then the 7-byte header. Garbled gates
+    /// add `row1 || row2 || row3` (56 bytes), garbled NOT gates `row1` (24 bytes); gates
+    /// without rows stop after the header (8 bytes). `RowAuthMode::Mac` appends one tag per
+    /// garbled row.
+    /// Off-chain only; hashed with `ih::gc_block_hash_v2`.
+    V2,
+}
+
+/// How `GarblingScheme::Classic` handles NOT gates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotGateMode {
+    /// Canonical all-zero rows; Alice sends a `NotGateHint` per NOT gate out of band.
+    /// The only mode the Solidity dispute path can recompute.
+    #[default]
+    Hinted,
+    /// Single-input table: rows `permA = 0, 1` encrypt the negated output label under
+    /// `computeRowKey(..., permA, 0, labelA, 0^16)`, so evaluation needs no hints.
+    /// Off-chain only.
+    Garbled,
+}
+
+/// Pad expansion applied to a 32-byte row key.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadFunction {
+    /// `keccak256("PAD", rowKey)[..16]`, as computed by Solidity.
+    #[default]
+    Keccak,
+    /// Fixed-key AES-128 in MMO mode over the folded row key
+    /// (`consensus::expand_pad_aes`). Much cheaper to garble; off-chain only.
+    FixedKeyAes,
+}
+
+/// Per-row authentication of garbled rows, for active-security experiments.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RowAuthMode {
+    /// Rows carry no tags; a tampered row decrypts to a wrong label undetected.
+    #[default]
+    Unauthenticated,
+    /// Every garbled row (including the implicit GRR3 row `0`) gets an 8-byte tag
+    /// `consensus::row_mac(rowKey, outLabel)` appended after the rows, so the evaluator
+    /// rejects a tampered row before using its label. Requires `LeafFormat::V2`; ignored
+    /// for v1 leaves, which have no room for tags. Off-chain only.
+    Mac,
+}
+
+/// One-byte identifier of a layout's garbling parameters, agreed on by both parties and
+/// carried in every `LeafFormat::V2` leaf:
+/// bit 0 = `GarblingScheme::FreeXor`, bit 1 = `NotGateMode::Garbled`, bit 2 = `LeafFormat::V2`,
+/// bit 3 = `PadFunction::FixedKeyAes`, bit 4 = `RowAuthMode::Mac`.
+/// `NotGateMode` does not apply under Free-XOR, so bits 0 and 1 are never set together, and
+/// row MACs only exist in v2 leaves, so bit 4 requires bit 2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SchemeId(u8);
+
+impl SchemeId {
+    /// Classic labels, hinted NOT gates, 71-byte leaves: the only scheme Solidity recomputes.
+    pub const CLASSIC_V1: SchemeId = SchemeId(0x00);
+
+    const FREE_XOR: u8 = 0x01;
+    const GARBLED_NOT: u8 = 0x02;
+    const LEAF_V2: u8 = 0x04;
+    const AES_PAD: u8 = 0x08;
+    const ROW_MAC: u8 = 0x10;
+
+    pub fn new(
+        scheme: GarblingScheme,
+        leaf_format: LeafFormat,
+        not_gate_mode: NotGateMode,
+        pad_function: PadFunction,
+        row_auth: RowAuthMode,
+    ) -> Self {
+        let mut id = 0u8;
+        match scheme {
+            GarblingScheme::Classic => {
+                if not_gate_mode == NotGateMode::Garbled {
+                    id |= Self::GARBLED_NOT;
+                }
+            }
+            GarblingScheme::FreeXor => id |= Self::FREE_XOR,
+        }
+        if leaf_format == LeafFormat::V2 {
+            id |= Self::LEAF_V2;
+            if row_auth == RowAuthMode::Mac {
+                id |= Self::ROW_MAC;
+            }
+        }
+        if pad_function == PadFunction::FixedKeyAes {
+            id |= Self::AES_PAD;
+        }
+        SchemeId(id)
+    }
+
+    /// Parses a scheme byte, rejecting unknown bits and non-canonical combinations.
+    pub fn from_byte(value: u8) -> Result<Self, String> {
+        let known =
+            Self::FREE_XOR | Self::GARBLED_NOT | Self::LEAF_V2 | Self::AES_PAD | Self::ROW_MAC;
+        if value & !known != 0 {
+            return Err(format!("unknown garbling scheme id 0x{value:02x}"));
+        }
+        if value & Self::FREE_XOR != 0 && value & Self::GARBLED_NOT != 0 {
+            return Err(format!(
+                "garbling scheme id 0x{value:02x} combines Free-XOR with garbled NOT gates"
+            ));
+        }
+        if value & Self::ROW_MAC != 0 && value & Self::LEAF_V2 == 0 {
+            return Err(format!(
+                "garbling scheme id 0x{value:02x} sets row MACs without leaf format v2"
+            ));
+        }
+        Ok(SchemeId(value))
+    }
+
+    pub fn to_byte(self) -> u8 {
+        self.0
+    }
+
+    pub fn scheme(self) -> GarblingScheme {
+        if self.0 & Self::FREE_XOR != 0 {
+            GarblingScheme::FreeXor
+        } else {
+            GarblingScheme::Classic
+        }
+    }
+
+    pub fn leaf_format(self) -> LeafFormat {
+        if self.0 & Self::LEAF_V2 != 0 {
+            LeafFormat::V2
+        } else {
+            LeafFormat::V1
+        }
+    }
+
+    pub fn not_gate_mode(self) -> NotGateMode {
+        if self.0 & Self::GARBLED_NOT != 0 {
+            NotGateMode::Garbled
+        } else {
+            NotGateMode::Hinted
+        }
+    }
+
+    pub fn pad_function(self) -> PadFunction {
+        if self.0 & Self::AES_PAD != 0 {
+            PadFunction::FixedKeyAes
+        } else {
+            PadFunction::Keccak
+        }
+    }
+
+    pub fn row_auth(self) -> RowAuthMode {
+        if self.0 & Self::ROW_MAC != 0 {
+            RowAuthMode::Mac
+        } else {
+            RowAuthMode::Unauthenticated
+        }
+    }
+
+    /// Errors unless leaves of this scheme can be disputed on-chain.
+    pub fn check_onchain(self) -> Result<(), String> {
+        if self != Self::CLASSIC_V1 {
+            return Err(format!(
+                "garbling scheme {self} is off-chain only; the contract recomputes {}",
+                Self::CLASSIC_V1
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Default for SchemeId {
+    fn default() -> Self {
+        Self::CLASSIC_V1
+    }
+}
+
+impl std::fmt::Display for SchemeId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "0x{:02x}", self.0)
+    }
+}
+
 /// Full circuit description passed into the garbler.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct CircuitLayout {
     /// Circuit identifier used in all domain-separated hashes.
+    #[serde(with = "hex_bytes")]
     pub circuit_id: [u8; 32],
     /// Cut-and-choose instance index (`0..N-1`).
     pub instance_id: u64,
     /// Ordered gate list; position in this vector is the `gateIndex`.
     pub gates: Vec<GateDesc>,
+    /// Wires carrying public constants; never written by a gate.
+    pub constant_wires: Vec<ConstantWire>,
+    /// Label/row scheme used to garble and evaluate this layout.
+    pub scheme: GarblingScheme,
+    /// Leaf encoding; `LeafFormat::V2` also changes how gate output labels are chosen.
+    pub leaf_format: LeafFormat,
+    /// NOT-gate handling; ignored under `GarblingScheme::FreeXor`, where NOT gates are free.
+    pub not_gate_mode: NotGateMode,
+    /// Row-key pad expansion.
+    pub pad_function: PadFunction,
+    /// Per-row tags; only applies to `LeafFormat::V2`.
+    pub row_auth: RowAuthMode,
+}
+
+impl CircuitLayout {
+    /// `SchemeId::CLASSIC_V1` layout without constant wires.
+    pub fn new(circuit_id: [u8; 32], instance_id: u64, gates: Vec<GateDesc>) -> Self {
+        Self {
+            circuit_id,
+            instance_id,
+            gates,
+            constant_wires: Vec::new(),
+            scheme: GarblingScheme::Classic,
+            leaf_format: LeafFormat::V1,
+            not_gate_mode: NotGateMode::Hinted,
+            pad_function: PadFunction::Keccak,
+            row_auth: RowAuthMode::Unauthenticated,
+        }
+    }
+
+    /// Selects the garbling scheme.
+    pub fn with_scheme(mut self, scheme: GarblingScheme) -> Self {
+        self.scheme = scheme;
+        self
+    }
+
+    /// Selects the leaf encoding.
+    pub fn with_leaf_format(mut self, leaf_format: LeafFormat) -> Self {
+        self.leaf_format = leaf_format;
+        self
+    }
+
+    /// Identifier of the layout's scheme, leaf format, NOT-gate mode, pad function and row
+    /// authentication.
+    pub fn scheme_id(&self) -> SchemeId {
+        SchemeId::new(
+            self.scheme,
+            self.leaf_format,
+            self.not_gate_mode,
+            self.pad_function,
+            self.row_auth,
+        )
+    }
+
+    /// Applies all parameters encoded in `scheme_id`.
+    pub fn with_scheme_id(self, scheme_id: SchemeId) -> Self {
+        self.with_scheme(scheme_id.scheme())
+            .with_leaf_format(scheme_id.leaf_format())
+            .with_not_gate_mode(scheme_id.not_gate_mode())
+            .with_pad_function(scheme_id.pad_function())
+            .with_row_auth(scheme_id.row_auth())
+    }
+
+    /// Selects the row-key pad expansion.
+    pub fn with_pad_function(mut self, pad_function: PadFunction) -> Self {
+        self.pad_function = pad_function;
+        self
+    }
+
+    /// Selects per-row authentication of `LeafFormat::V2` leaves.
+    pub fn with_row_auth(mut self, row_auth: RowAuthMode) -> Self {
+        self.row_auth = row_auth;
+        self
+    }
+
+    /// Selects the NOT-gate handling.
+    pub fn with_not_gate_mode(mut self, not_gate_mode: NotGateMode) -> Self {
+        self.not_gate_mode = not_gate_mode;
+        self
+    }
+
+    /// Ciphertext rows a gate of `gate_type` carries before any row reduction:
+    /// `0` for free gates (hinted NOT, Free-XOR XOR/NOT), `2` for garbled NOT, else `4`.
+    pub fn garbled_row_count(&self, gate_type: GateType) -> usize {
+        match (self.scheme, gate_type) {
+            (GarblingScheme::FreeXor, GateType::Xor | GateType::Not) => 0,
+            (GarblingScheme::Classic, GateType::Not) => match self.not_gate_mode {
+                NotGateMode::Hinted => 0,
+                NotGateMode::Garbled => 2,
+            },
+            _ => 4,
+        }
+    }
+
+    /// Replaces the layout's constant wires.
+    pub fn with_constant_wires(mut self, constant_wires: Vec<ConstantWire>) -> Self {
+        self.constant_wires = constant_wires;
+        self
+    }
+
+    /// Constant bit assigned to `wire`, if it is a constant wire.
+    pub fn constant_value(&self, wire: u16) -> Option<u8> {
+        self.constant_wires
+            .iter()
+            .find(|constant| constant.wire == wire)
+            .map(|constant| constant.value)
+    }
+
+    /// Checks that constants are bits, assigned once, and never written by a gate.
+    pub fn check_constant_wires(&self) -> Result<(), String> {
+        for (idx, constant) in self.constant_wires.iter().enumerate() {
+            if constant.value > 1 {
+                return Err(format!(
+                    "constant wire {} has non-bit value {}",
+                    constant.wire, constant.value
+                ));
+            }
+            if self.constant_wires[..idx]
+                .iter()
+                .any(|prev| prev.wire == constant.wire)
+            {
+                return Err(format!("constant wire {} assigned twice", constant.wire));
+            }
+            if let Some(gate_idx) = self
+                .gates
+                .iter()
+                .position(|gate| gate.wire_c == constant.wire)
+            {
+                return Err(format!(
+                    "constant wire {} is written by gate {gate_idx}",
+                    constant.wire
+                ));
+            }
+        }
+        Ok(())
+    }
 }
@@ -0,0 +1,181 @@
+//! Native ABI encoding: selectors, the head/tail layout of static and dynamic arguments, and
+//! the calldata of the contract calls built with it.
+
+use off_chain_common::abi::{
+    AbiCall, AbiValue, DISPUTE_GARBLED_TABLE_SIGNATURE, dispute_garbled_table_call,
+    function_selector, reveal_openings_call, submit_commitments_call, submit_ot_roots_call,
+};
+use off_chain_common::cli::decode_hex;
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::cost::estimate_instance_cost;
+use off_chain_common::messages::InstanceCommitment;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
+
+fn word(value: u64) -> Vec<u8> {
+    let mut out = vec![0u8; 24];
+    out.extend_from_slice(&value.to_be_bytes());
+    out
+}
+
+#[test]
+fn selectors_match_known_functions() {
+    assert_eq!(
+        function_selector("transfer(address,uint256)"),
+        [0xa9, 0x05, 0x9c, 0xbb]
+    );
+    assert_eq!(
+        function_selector("baz(uint32,bool)"),
+        [0xcd, 0xcd, 0x77, 0xc0]
+    );
+    let call = reveal_openings_call(&[], &[]);
+    assert_eq!(call.signature, "revealOpenings(uint256[],bytes32[])");
+    assert_eq!(
+        DISPUTE_GARBLED_TABLE_SIGNATURE,
+        "disputeGarbledTable(uint256,bytes32,uint256,(uint8,uint16,uint16,uint16),bytes,\
+         bytes32[],bytes32[])"
+    );
+}
+
+#[test]
+fn encodes_the_solidity_documentation_example() {
+    // `sam(bytes,bool,uint256[])` with `("dave", true, [1, 2, 3])`, from the ABI spec.
+    let call = AbiCall::new(
+        "sam(bytes,bool,uint256[])",
+        vec![
+            AbiValue::Bytes(b"dave".to_vec()),
+            AbiValue::Uint(1),
+            AbiValue::Array(vec![
+                AbiValue::Uint(1),
+                AbiValue::Uint(2),
+                AbiValue::Uint(3),
+            ]),
+        ],
+    );
+    let expected = decode_hex(concat!(
+        "0xa5643bf2",
+        "0000000000000000000000000000000000000000000000000000000000000060",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "00000000000000000000000000000000000000000000000000000000000000a0",
+        "0000000000000000000000000000000000000000000000000000000000000004",
+        "6461766500000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000003",
+        "0000000000000000000000000000000000000000000000000000000000000001",
+        "0000000000000000000000000000000000000000000000000000000000000002",
+        "0000000000000000000000000000000000000000000000000000000000000003",
+    ))
+    .unwrap();
+    assert_eq!(call.calldata(), expected);
+    assert_eq!(
+        call.calldata_hex(),
+        off_chain_common::cli::hex_prefixed(&expected)
+    );
+
+    // Static composites are inline; one dynamic member makes the whole value an offset.
+    let static_tuple = AbiValue::Tuple(vec![AbiValue::Uint(7), AbiValue::Bytes32([1; 32])]);
+    assert!(!static_tuple.is_dynamic());
+    assert_eq!(static_tuple.encode().len(), 64);
+    assert!(AbiValue::FixedArray(vec![AbiValue::Bytes(vec![])]).is_dynamic());
+    assert_eq!(AbiValue::Bytes(vec![]).encode(), word(0));
+}
+
+#[test]
+fn dispute_calldata_follows_the_head_tail_layout() {
+    let leaf = [0x5a; 71];
+    let ih_proof = [[0x11; 32], [0x22; 32]];
+    let layout_proof = [[0x33; 32]];
+    let gate = GateDesc {
+        gate_type: GateType::Xor,
+        wire_a: 1,
+        wire_b: 2,
+        wire_c: 300,
+    };
+    let call = dispute_garbled_table_call(4, [0x44; 32], 9, gate, &leaf, &ih_proof, &layout_proof);
+    let data = call.calldata();
+    assert_eq!(
+        data[..4],
+        function_selector(DISPUTE_GARBLED_TABLE_SIGNATURE)
+    );
+    let words = data[4..].chunks(32).collect::<Vec<_>>();
+
+    assert_eq!(words[0], word(4));
+    assert_eq!(words[1], [0x44; 32]);
+    assert_eq!(words[2], word(9));
+    // The gate tuple is static, so its four fields sit in the head.
+    assert_eq!(words[3], word(GateType::Xor as u64));
+    assert_eq!(words[4..7], [word(1), word(2), word(300)]);
+    // Offsets of `bytes`, `bytes32[]` and `bytes32[]`, from the start of the arguments.
+    assert_eq!(
+        words[7..10],
+        [word(320), word(320 + 128), word(320 + 128 + 96)]
+    );
+    assert_eq!(words[10], word(71));
+    assert_eq!(words[11..13].concat(), leaf[..64]);
+    assert_eq!(words[13][..7], leaf[64..]);
+    assert_eq!(words[13][7..], [0; 25]);
+    assert_eq!(words[14..17], [word(2), vec![0x11; 32], vec![0x22; 32]]);
+    assert_eq!(words[17..], [word(1), vec![0x33; 32]]);
+}
+
+#[test]
+fn dispute_calldata_size_matches_the_cost_estimate() {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let layout = CircuitLayout::new(circuit.circuit_id(4, HIGHER_BID_WINS), 0, circuit.build(4));
+    let cost = estimate_instance_cost(&layout);
+    let call = dispute_garbled_table_call(
+        0,
+        [0; 32],
+        0,
+        layout.gates[0],
+        &vec![0; cost.max_leaf_bytes],
+        &vec![[0; 32]; cost.ih_proof_len],
+        &vec![[0; 32]; cost.layout_proof_len],
+    );
+    assert_eq!(call.calldata().len(), cost.dispute_calldata_bytes);
+}
+
+#[test]
+fn commitment_and_opening_calls_size_their_arrays() {
+    let commitments = (0..3u8)
+        .map(|i| InstanceCommitment {
+            com_seed: [i; 32],
+            root_gc: [0x10 + i; 32],
+            blob_hash_gc: [0x20 + i; 32],
+            h_out: [0x30 + i; 32],
+        })
+        .collect::<Vec<_>>();
+    let call = submit_commitments_call(&commitments);
+    assert_eq!(
+        call.signature,
+        "submitCommitments((bytes32,bytes32,bytes32,bytes32)[3])"
+    );
+    let data = call.calldata();
+    assert_eq!(data.len(), 4 + 3 * 128);
+    assert_eq!(data[4 + 128 + 64..][..32], [0x21; 32]);
+
+    let ot = submit_ot_roots_call([0xab; 20], &[[1; 32], [2; 32]]);
+    assert_eq!(ot.signature, "submitOtRootsForBuyer(address,bytes32[2])");
+    let data = ot.calldata();
+    assert_eq!(data[4..16], [0; 12]);
+    assert_eq!(data[16..36], [0xab; 20]);
+    assert_eq!(data.len(), 4 + 3 * 32);
+
+    let reveal = reveal_openings_call(&[0, 2], &[[7; 32], [8; 32]]);
+    let words = reveal.calldata()[4..]
+        .chunks(32)
+        .map(<[u8]>::to_vec)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        words,
+        [
+            word(64),
+            word(160),
+            word(2),
+            word(0),
+            word(2),
+            word(2),
+            vec![7; 32],
+            vec![8; 32]
+        ]
+    );
+}
@@ -0,0 +1,92 @@
+//! Fixed-key AES pad: determinism, separation from the keccak pad and end-to-end evaluation.
+
+use off_chain_common::consensus::{
+    expand_pad, expand_pad_aes, expand_pad_with, keccak256, leaf_scheme_id,
+};
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, derive_output_labels,
+    evaluate_garbled_circuit,
+};
+use off_chain_common::garble::garble_leaves;
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{
+    GarblingScheme, LeafFormat, NotGateMode, PadFunction, RowAuthMode, SchemeId,
+};
+
+mod common;
+
+use common::millionaires_layout;
+
+#[test]
+fn aes_pad_is_deterministic_and_distinct_from_keccak() {
+    let row_key = keccak256(&[b"row-key"]);
+    assert_eq!(expand_pad_aes(row_key), expand_pad_aes(row_key));
+    assert_ne!(expand_pad_aes(row_key), expand_pad(row_key));
+    assert_eq!(
+        expand_pad_with(PadFunction::FixedKeyAes, row_key),
+        expand_pad_aes(row_key)
+    );
+    assert_eq!(
+        expand_pad_with(PadFunction::Keccak, row_key),
+        expand_pad(row_key)
+    );
+
+    let mut other = row_key;
+    other[31] ^= 1;
+    assert_ne!(expand_pad_aes(row_key), expand_pad_aes(other));
+}
+
+#[test]
+fn aes_pad_sets_its_scheme_bit() {
+    let layout = millionaires_layout(1, 4).with_pad_function(PadFunction::FixedKeyAes);
+    assert_eq!(layout.scheme_id().to_byte(), 0x08);
+    assert_eq!(layout.scheme_id().pad_function(), PadFunction::FixedKeyAes);
+    assert!(layout.scheme_id().check_onchain().is_err());
+    assert_eq!(millionaires_layout(1, 4).scheme_id(), SchemeId::CLASSIC_V1);
+
+    let v2 = layout.with_leaf_format(LeafFormat::V2);
+    let leaves = garble_leaves([4u8; 32], &v2);
+    assert_eq!(leaf_scheme_id(&leaves[0]), Ok(Some(v2.scheme_id())));
+}
+
+#[test]
+fn aes_pad_layouts_evaluate_millionaires() {
+    let seed = [4u8; 32];
+    let circuit = build_millionaires_layout(4);
+    for scheme in [GarblingScheme::Classic, GarblingScheme::FreeXor] {
+        for leaf_format in [LeafFormat::V1, LeafFormat::V2] {
+            for not_gate_mode in [NotGateMode::Hinted, NotGateMode::Garbled] {
+                let layout = millionaires_layout(1, 4).with_scheme_id(SchemeId::new(
+                    scheme,
+                    leaf_format,
+                    not_gate_mode,
+                    PadFunction::FixedKeyAes,
+                    RowAuthMode::Unauthenticated,
+                ));
+                let keccak = layout.clone().with_pad_function(PadFunction::Keccak);
+                let leaves = garble_leaves(seed, &layout);
+                assert_ne!(leaves, garble_leaves(seed, &keccak));
+
+                let hints = derive_not_gate_hints(seed, &layout);
+                let (out0, out1) = derive_output_labels(seed, &layout, circuit.gt_wire).unwrap();
+                for (x, y) in [(9u8, 4u8), (3, 12), (7, 7)] {
+                    let x_bits = (0..4).map(|i| (x >> i) & 1).collect::<Vec<_>>();
+                    let y_bits = (0..4).map(|i| (y >> i) & 1).collect::<Vec<_>>();
+                    let alice = derive_layout_input_labels(seed, &layout, 0, &x_bits);
+                    let bob = derive_layout_input_labels(seed, &layout, 4, &y_bits);
+                    let out = evaluate_garbled_circuit(
+                        &layout,
+                        &leaves,
+                        &alice,
+                        &bob,
+                        &hints,
+                        circuit.gt_wire,
+                    )
+                    .unwrap();
+                    let expected = if x > y { out1 } else { out0 };
+                    assert_eq!(out, expected, "{} x={x} y={y}", layout.scheme_id());
+                }
+            }
+        }
+    }
+}
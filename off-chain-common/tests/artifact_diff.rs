@@ -0,0 +1,154 @@
+//! Comparison of export directories: decoded file contents, leaf-by-leaf differences and
+//! rootGC checks against each side's own leaves.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use off_chain_common::artifact_diff::{ArtifactDifference, Side, diff_artifact_dirs};
+use off_chain_common::cli::{hex_prefixed, hex32};
+use off_chain_common::compression::{ArtifactCompression, write_artifact};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::incremental_root;
+
+mod common;
+
+use common::{millionaires_layout, temp_dir};
+
+fn leaves(instance_id: u64) -> Vec<[u8; 71]> {
+    let layout = millionaires_layout(instance_id, 4);
+    garble_circuit([instance_id as u8 + 1; 32], &layout)
+}
+
+fn write_leaves(dir: &Path, instance_id: u64, leaves: &[[u8; 71]], zstd: bool) {
+    let raw = leaves
+        .iter()
+        .map(|leaf| hex_prefixed(leaf) + "\n")
+        .collect::<String>();
+    let compression = if zstd {
+        ArtifactCompression::zstd(3).unwrap()
+    } else {
+        ArtifactCompression::None
+    };
+    let path = dir.join(format!("instance-{instance_id}-leaves.txt"));
+    write_artifact(&path, raw.as_bytes(), compression).unwrap();
+}
+
+/// An export of instances `0..n` like Alice's: seed, root and leaves per instance.
+fn export(prefix: &str, n: u64, zstd: bool) -> PathBuf {
+    let dir = temp_dir(prefix);
+    for id in 0..n {
+        let leaves = leaves(id);
+        let root = format!("{}\n", hex32(incremental_root(&leaves)));
+        fs::write(dir.join(format!("instance-{id}-root-gc.txt")), root).unwrap();
+        fs::write(
+            dir.join(format!("instance-{id}-seed.txt")),
+            format!("0x0{id}\n"),
+        )
+        .unwrap();
+        write_leaves(&dir, id, &leaves, zstd);
+    }
+    fs::write(dir.join("manifest.json"), prefix).unwrap();
+    dir
+}
+
+#[test]
+fn compressed_and_plain_exports_are_identical() {
+    let a = export("diff-plain", 2, false);
+    let b = export("diff-zstd", 2, true);
+    let diff = diff_artifact_dirs(&a, &b).expect("diff");
+    assert!(diff.is_identical(), "{:?}", diff.instances);
+    assert_eq!(diff.instances.len(), 2);
+    assert!(diff.differing_instances().is_empty());
+    let _ = fs::remove_dir_all(a);
+    let _ = fs::remove_dir_all(b);
+}
+
+#[test]
+fn reports_differing_leaves_and_roots() {
+    let a = export("diff-leaves-a", 3, false);
+    let b = export("diff-leaves-b", 3, false);
+    let mut edited = leaves(1);
+    edited[2][10] ^= 1;
+    edited[7][0] ^= 1;
+    write_leaves(&b, 1, &edited, false);
+
+    let diff = diff_artifact_dirs(&a, &b).expect("diff");
+    assert_eq!(diff.differing_instances(), [1]);
+    let differences = diff.differences().cloned().collect::<Vec<_>>();
+    assert_eq!(
+        differences,
+        [
+            ArtifactDifference::Entries {
+                name: "instance-1-leaves.txt".to_string(),
+                counts: [edited.len(); 2],
+                indices: vec![2, 7],
+            },
+            ArtifactDifference::ComputedRootGc {
+                instance_id: 1,
+                roots: [incremental_root(&leaves(1)), incremental_root(&edited)],
+            },
+            // b still records the root of the original leaves.
+            ArtifactDifference::StaleRootGc {
+                instance_id: 1,
+                side: Side::B,
+            },
+        ]
+    );
+    assert_eq!(
+        differences[0].to_string(),
+        "instance-1-leaves.txt: entries [2, 7] differ"
+    );
+    let _ = fs::remove_dir_all(a);
+    let _ = fs::remove_dir_all(b);
+}
+
+#[test]
+fn reports_missing_files_and_instances() {
+    let a = export("diff-missing-a", 3, false);
+    let b = export("diff-missing-b", 2, false);
+    fs::remove_file(b.join("instance-0-seed.txt")).unwrap();
+    fs::write(b.join("instance-1-seed.txt"), "0x42\n").unwrap();
+
+    let diff = diff_artifact_dirs(&a, &b).expect("diff");
+    assert_eq!(diff.differing_instances(), [0, 1, 2]);
+    let lines = diff
+        .differences()
+        .map(|d| d.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "instance-0-seed.txt: missing in b",
+            "instance-1-seed.txt: contents differ",
+            "instance-2-leaves.txt: missing in b",
+            "instance-2-root-gc.txt: missing in b",
+            "instance-2-seed.txt: missing in b",
+        ]
+    );
+    assert!(diff_artifact_dirs(&a, &b.join("absent")).is_err());
+    let _ = fs::remove_dir_all(a);
+    let _ = fs::remove_dir_all(b);
+}
+
+#[test]
+fn truncated_leaf_files_report_both_counts() {
+    let a = export("diff-count-a", 1, false);
+    let b = export("diff-count-b", 1, false);
+    let mut edited = leaves(0);
+    edited.truncate(edited.len() - 1);
+    for leaf in edited.iter_mut().take(20) {
+        leaf[70] ^= 0xff;
+    }
+    write_leaves(&b, 0, &edited, true);
+
+    let diff = diff_artifact_dirs(&a, &b).expect("diff");
+    let first = diff.differences().next().unwrap().to_string();
+    let counts = format!("{} entries in a, {} in b", edited.len() + 1, edited.len());
+    let listed = format!(
+        "entries {:?} differ (first 16 of 20)",
+        (0..16).collect::<Vec<_>>()
+    );
+    assert_eq!(first, format!("instance-0-leaves.txt: {counts}; {listed}"));
+    let _ = fs::remove_dir_all(a);
+    let _ = fs::remove_dir_all(b);
+}
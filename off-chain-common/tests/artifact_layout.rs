@@ -0,0 +1,165 @@
+//! Layout versions of artifact directories: the stamp, rejection of newer layouts and the
+//! in-place migration of version 1 eval dirs.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use off_chain_common::artifact_layout::{
+    EVAL_PACKAGE_FILE, LAYOUT_VERSION, LAYOUT_VERSION_FILE, UNSTAMPED_LAYOUT_VERSION, migrate,
+    read_layout_version, write_layout_version,
+};
+use off_chain_common::cli::{hex_prefixed, hex16};
+use off_chain_common::consensus::keccak256;
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::eval_meta::{EVAL_META_FILE, EvalMeta, LEGACY_EVAL_META_FILE};
+use off_chain_common::evaluation::NotGateHint;
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::manifest::{ArtifactManifest, ArtifactRole, verify_manifest};
+use off_chain_common::messages::{EvalPackageMessage, Message};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::CircuitLayout;
+
+mod common;
+
+use common::temp_dir;
+
+fn lines(values: impl IntoIterator<Item = String>) -> String {
+    values.into_iter().map(|value| value + "\n").collect()
+}
+
+/// The package a version 1 eval dir holds as split hex text files, and the dir itself.
+fn legacy_eval_dir(prefix: &str) -> (PathBuf, EvalPackageMessage) {
+    let built = build_millionaires_layout(4);
+    let layout = CircuitLayout::new(keccak256(&[b"millionaires-yao-v1"]), 2, built.gates);
+    let seed = [0x31; 32];
+    let package = EvalPackageMessage {
+        bit_width: 4,
+        decoding: OutputDecodingTable::derive(seed, &layout, built.gt_wire).unwrap(),
+        gc_leaves: garble_circuit(seed, &layout),
+        alice_labels: (0..4u8).map(|i| [i; 16]).collect(),
+        not_hints: vec![NotGateHint {
+            gate_index: 5,
+            in_label0: [1; 16],
+            out_if_in0: [2; 16],
+            in_label1: [3; 16],
+            out_if_in1: [4; 16],
+        }],
+        ot_setup: Some([0x02; 33]),
+    };
+
+    let dir = temp_dir(prefix);
+    let meta = format!("bit_width=4\n{}", package.decoding.to_key_values());
+    let leaves = package.gc_leaves.iter().map(|leaf| hex_prefixed(leaf));
+    let labels = package.alice_labels.iter().map(|label| hex16(*label));
+    let hints = package.not_hints.iter().map(|hint| {
+        format!(
+            "{},{},{},{},{}",
+            hint.gate_index,
+            hex16(hint.in_label0),
+            hex16(hint.out_if_in0),
+            hex16(hint.in_label1),
+            hex16(hint.out_if_in1)
+        )
+    });
+    let setup = hex_prefixed(&package.ot_setup.unwrap());
+    fs::write(dir.join(LEGACY_EVAL_META_FILE), meta).unwrap();
+    fs::write(dir.join("gc-m-leaves.txt"), lines(leaves)).unwrap();
+    fs::write(dir.join("alice-x-labels16.txt"), lines(labels)).unwrap();
+    fs::write(dir.join("not-hints.txt"), lines(hints)).unwrap();
+    fs::write(dir.join("ot-setup.txt"), setup + "\n").unwrap();
+    (dir, package)
+}
+
+fn read(dir: &Path, name: &str) -> Vec<u8> {
+    fs::read(dir.join(name)).expect(name)
+}
+
+#[test]
+fn unstamped_dirs_are_version_one_and_newer_ones_are_rejected() {
+    let dir = temp_dir("layout-version");
+    assert_eq!(read_layout_version(&dir), Ok(UNSTAMPED_LAYOUT_VERSION));
+    let path = write_layout_version(&dir).expect("stamp");
+    assert_eq!(path, dir.join(LAYOUT_VERSION_FILE));
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        format!(r#"{{"version":{LAYOUT_VERSION}}}"#)
+    );
+    assert_eq!(read_layout_version(&dir), Ok(LAYOUT_VERSION));
+
+    fs::write(&path, r#"{"version":99}"#).unwrap();
+    let err = read_layout_version(&dir).unwrap_err();
+    assert!(err.contains("unsupported layout version 99"), "{err}");
+    // Eval dir readers refuse it before looking at the meta file.
+    let err = EvalMeta::read(&dir).unwrap_err();
+    assert!(err.contains("unsupported layout version 99"), "{err}");
+    assert!(migrate(&dir).is_err());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn migrates_a_version_one_eval_dir() {
+    let (dir, package) = legacy_eval_dir("layout-migrate-eval");
+    let migration = migrate(&dir).expect("migrate");
+    assert_eq!(migration.from, 1);
+    assert_eq!(migration.to, LAYOUT_VERSION);
+    assert_eq!(
+        migration.written,
+        [EVAL_META_FILE, EVAL_PACKAGE_FILE, LAYOUT_VERSION_FILE]
+    );
+
+    let meta = EvalMeta::from_json(&fs::read_to_string(dir.join(EVAL_META_FILE)).unwrap());
+    assert_eq!(meta, Ok(EvalMeta::new(4, package.decoding)));
+    let cbor = read(&dir, EVAL_PACKAGE_FILE);
+    assert_eq!(Message::from_cbor(&cbor), Ok(Message::EvalPackage(package)));
+    // The legacy files stay for older readers.
+    assert!(dir.join(LEGACY_EVAL_META_FILE).exists());
+    assert!(dir.join("gc-m-leaves.txt").exists());
+    assert_eq!(read_layout_version(&dir), Ok(LAYOUT_VERSION));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn current_dirs_are_left_alone() {
+    let (dir, _) = legacy_eval_dir("layout-migrate-twice");
+    migrate(&dir).expect("first");
+    let package = read(&dir, EVAL_PACKAGE_FILE);
+    let again = migrate(&dir).expect("second");
+    assert_eq!((again.from, again.to), (LAYOUT_VERSION, LAYOUT_VERSION));
+    assert!(again.written.is_empty());
+    assert_eq!(read(&dir, EVAL_PACKAGE_FILE), package);
+    let _ = fs::remove_dir_all(dir);
+
+    // An export dir has no eval files to convert and only gets the stamp.
+    let dir = temp_dir("layout-migrate-export");
+    fs::write(dir.join("instance-0-seed.txt"), "0x01\n").unwrap();
+    let migration = migrate(&dir).expect("migrate");
+    assert_eq!(migration.written, [LAYOUT_VERSION_FILE]);
+    assert_eq!(read(&dir, "instance-0-seed.txt"), b"0x01\n");
+    assert!(migrate(&dir.join("missing")).is_err());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn migration_keeps_an_existing_manifest_intact() {
+    let (dir, package) = legacy_eval_dir("layout-migrate-manifest");
+    let mut manifest = ArtifactManifest::new("millionaires", 0, package.decoding.circuit_id, 4);
+    for (name, role) in [
+        ("gc-m-leaves.txt", ArtifactRole::Leaves),
+        ("alice-x-labels16.txt", ArtifactRole::AliceLabels16),
+        ("not-hints.txt", ArtifactRole::NotHints),
+        ("ot-setup.txt", ArtifactRole::OtSetup),
+        (LEGACY_EVAL_META_FILE, ArtifactRole::EvalMeta),
+    ] {
+        manifest.add_file(&dir, name, role, Some(2)).unwrap();
+    }
+    manifest.write(&dir).unwrap();
+
+    migrate(&dir).expect("migrate");
+    let report = verify_manifest(&dir).expect("report");
+    assert!(report.is_intact(), "{:?}", report.mismatches);
+    assert_eq!(
+        report.unlisted,
+        [EVAL_META_FILE, EVAL_PACKAGE_FILE, LAYOUT_VERSION_FILE]
+    );
+    let _ = fs::remove_dir_all(dir);
+}
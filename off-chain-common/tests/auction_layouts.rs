@@ -0,0 +1,346 @@
+//! Auction layouts and gadgets: plaintext reference checks and garbled evaluation.
+
+use off_chain_common::builder::CircuitBuilder;
+use off_chain_common::consensus::{derive_wire_label, keccak256};
+use off_chain_common::evaluation::{
+    MillionairesOutcome, bits_le_to_u64, comparison_output_wire, derive_not_gate_hints,
+    evaluate_garbled_wires, evaluate_plain_circuit, millionaires_eq_output_wire,
+    millionaires_gt_output_wire, millionaires_outcome, u64_to_bits_le,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::scenario::{
+    Comparison, build_argmax_layout, build_comparison_layout, build_max_of_two_layout,
+    build_millionaires_layout, build_second_price_layout, build_vickrey_layout, index_bit_width,
+};
+use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
+
+/// Reference second-price outcome: (winner index, second-highest bid), ties to lowest index.
+fn expected_vickrey(bids: &[u64]) -> (u64, u64) {
+    let mut winner = 0usize;
+    for (idx, bid) in bids.iter().enumerate() {
+        if *bid > bids[winner] {
+            winner = idx;
+        }
+    }
+    let second = bids
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| *idx != winner)
+        .map(|(_, bid)| *bid)
+        .max()
+        .unwrap();
+    (winner as u64, second)
+}
+
+fn read_plain_word(values: &[Option<u8>], wires: &[u16]) -> u64 {
+    let bits: Vec<u8> = wires
+        .iter()
+        .map(|wire| values[*wire as usize].expect("wire evaluated"))
+        .collect();
+    bits_le_to_u64(&bits).unwrap()
+}
+
+#[test]
+fn comparison_layouts_match_operators_for_small_widths() {
+    let variants = [
+        Comparison::Gt,
+        Comparison::Ge,
+        Comparison::Lt,
+        Comparison::Le,
+        Comparison::Eq,
+    ];
+    let expected = |comparison: Comparison, x: u64, y: u64| match comparison {
+        Comparison::Gt => x > y,
+        Comparison::Ge => x >= y,
+        Comparison::Lt => x < y,
+        Comparison::Le => x <= y,
+        Comparison::Eq => x == y,
+    };
+
+    for bit_width in 1..=4usize {
+        assert_eq!(
+            build_comparison_layout(bit_width, Comparison::Gt),
+            build_millionaires_layout(bit_width).gates
+        );
+        for comparison in variants {
+            let gates = build_comparison_layout(bit_width, comparison);
+            let output = comparison_output_wire(&gates, bit_width, comparison).unwrap();
+            for x in 0..(1u64 << bit_width) {
+                for y in 0..(1u64 << bit_width) {
+                    let mut inputs = u64_to_bits_le(x, bit_width);
+                    inputs.extend(u64_to_bits_le(y, bit_width));
+                    let values = evaluate_plain_circuit(&gates, &inputs).unwrap();
+                    assert_eq!(
+                        values[output as usize],
+                        Some(expected(comparison, x, y) as u8),
+                        "{comparison:?} bit_width={bit_width} x={x} y={y}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn millionaires_layout_exposes_gt_and_eq_outcomes() {
+    for bit_width in 1..=4usize {
+        let layout = build_millionaires_layout(bit_width);
+        assert_eq!(
+            Ok(layout.gt_wire),
+            millionaires_gt_output_wire(&layout.gates, bit_width)
+        );
+        assert_eq!(
+            Ok(layout.eq_wire),
+            millionaires_eq_output_wire(&layout.gates, bit_width)
+        );
+
+        for x in 0..(1u64 << bit_width) {
+            for y in 0..(1u64 << bit_width) {
+                let mut inputs = u64_to_bits_le(x, bit_width);
+                inputs.extend(u64_to_bits_le(y, bit_width));
+                let values = evaluate_plain_circuit(&layout.gates, &inputs).unwrap();
+                let outcome = millionaires_outcome(
+                    values[layout.gt_wire as usize].unwrap(),
+                    values[layout.eq_wire as usize].unwrap(),
+                )
+                .unwrap();
+                let expected = match x.cmp(&y) {
+                    std::cmp::Ordering::Greater => MillionairesOutcome::AliceWins,
+                    std::cmp::Ordering::Less => MillionairesOutcome::BobWins,
+                    std::cmp::Ordering::Equal => MillionairesOutcome::Tie,
+                };
+                assert_eq!(outcome, expected, "bit_width={bit_width} x={x} y={y}");
+            }
+        }
+    }
+    assert!(millionaires_outcome(1, 1).is_err());
+}
+
+#[test]
+fn max_of_two_layout_reveals_winning_bid() {
+    let bit_width = 4;
+    let layout = build_max_of_two_layout(bit_width);
+    assert_eq!(layout.max_wires.len(), bit_width);
+
+    for x in 0..16u64 {
+        for y in 0..16u64 {
+            let mut inputs = u64_to_bits_le(x, bit_width);
+            inputs.extend(u64_to_bits_le(y, bit_width));
+            let values = evaluate_plain_circuit(&layout.gates, &inputs).unwrap();
+            assert_eq!(values[layout.gt_wire as usize], Some((x > y) as u8));
+            assert_eq!(read_plain_word(&values, &layout.max_wires), x.max(y));
+        }
+    }
+}
+
+#[test]
+fn second_price_layout_computes_winner_bid_and_payment() {
+    let bit_width = 4;
+    let layout = build_second_price_layout(bit_width);
+    // Comparator plus one AND per bit for the shared swap.
+    let and_count = |gates: &[GateDesc]| {
+        gates
+            .iter()
+            .filter(|gate| gate.gate_type == GateType::And)
+            .count()
+    };
+    assert_eq!(
+        and_count(&layout.gates),
+        and_count(&build_millionaires_layout(bit_width).gates) + bit_width
+    );
+
+    for x in 0..16u64 {
+        for y in 0..16u64 {
+            let mut inputs = u64_to_bits_le(x, bit_width);
+            inputs.extend(u64_to_bits_le(y, bit_width));
+            let values = evaluate_plain_circuit(&layout.gates, &inputs).unwrap();
+            assert_eq!(values[layout.gt_wire as usize], Some((x > y) as u8));
+            assert_eq!(read_plain_word(&values, &layout.winner_bid_wires), x.max(y));
+            assert_eq!(read_plain_word(&values, &layout.payment_wires), x.min(y));
+        }
+    }
+}
+
+/// Two bids plus a flag wire: outputs the losing bid when the flag is 1, else the winner.
+/// Inputs are `x` bits, `y` bits, then the flag; returns `(output_wires, gates)`.
+fn build_flagged_bid_layout(bit_width: usize) -> (Vec<u16>, Vec<GateDesc>) {
+    let mut builder = CircuitBuilder::new();
+    let x_bits = builder.inputs(bit_width);
+    let y_bits = builder.inputs(bit_width);
+    let flag = builder.input();
+
+    let (gt, _) = builder.compare(&x_bits, &y_bits);
+    let winner = builder.mux(gt, &x_bits, &y_bits);
+    let loser = builder.mux(gt, &y_bits, &x_bits);
+    let out = builder.mux(flag, &loser, &winner);
+    builder.output_word("bid", &out);
+
+    let (layout, outputs) = builder.finish_with_outputs();
+    assert_eq!(outputs[0].name, "bid");
+    (outputs[0].wires.clone(), layout.gates)
+}
+
+fn flagged_bid_inputs(bit_width: usize, x: u64, y: u64, flag: u8) -> Vec<u8> {
+    let mut bits = u64_to_bits_le(x, bit_width);
+    bits.extend(u64_to_bits_le(y, bit_width));
+    bits.push(flag);
+    bits
+}
+
+#[test]
+fn mux_outputs_losing_bid_when_flag_set() {
+    let bit_width = 3;
+    let (out_wires, gates) = build_flagged_bid_layout(bit_width);
+
+    for x in 0..8u64 {
+        for y in 0..8u64 {
+            for flag in 0..2u8 {
+                let inputs = flagged_bid_inputs(bit_width, x, y, flag);
+                let values = evaluate_plain_circuit(&gates, &inputs).unwrap();
+                let expected = if flag == 1 { x.min(y) } else { x.max(y) };
+                assert_eq!(read_plain_word(&values, &out_wires), expected);
+            }
+        }
+    }
+}
+
+#[test]
+fn mux_evaluates_through_garbling() {
+    let bit_width = 3;
+    let (out_wires, gates) = build_flagged_bid_layout(bit_width);
+    let circuit_id = keccak256(&[b"mux-test"]);
+    let seed = keccak256(&[b"mux-seed"]);
+    let layout = CircuitLayout::new(circuit_id, 0, gates);
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+
+    for (x, y, flag, expected) in [
+        (5u64, 2u64, 1u8, 2u64),
+        (5, 2, 0, 5),
+        (1, 6, 1, 1),
+        (4, 4, 1, 4),
+    ] {
+        let input_labels: Vec<(u16, [u8; 16])> = flagged_bid_inputs(bit_width, x, y, flag)
+            .into_iter()
+            .enumerate()
+            .map(|(wire, bit)| {
+                let wire = wire as u16;
+                (wire, derive_wire_label(circuit_id, 0, wire, bit, seed))
+            })
+            .collect();
+        let labels = evaluate_garbled_wires(&layout, &leaves, &input_labels, &hints).unwrap();
+
+        let bits: Vec<u8> = out_wires
+            .iter()
+            .map(|wire| {
+                let label = labels[*wire as usize].unwrap();
+                u8::from(label == derive_wire_label(circuit_id, 0, *wire, 1, seed))
+            })
+            .collect();
+        assert_eq!(
+            bits_le_to_u64(&bits).unwrap(),
+            expected,
+            "x={x} y={y} flag={flag}"
+        );
+    }
+}
+
+#[test]
+fn argmax_layout_selects_max_and_lowest_index_on_ties() {
+    let bit_width = 6;
+    let cases: [&[u64]; 4] = [
+        &[5, 9],
+        &[63, 0, 63, 12],
+        &[1, 2, 3, 4, 5, 6, 7],
+        &[0, 0, 0, 0, 0, 0, 0, 0, 1],
+    ];
+
+    for bids in cases {
+        let layout = build_argmax_layout(bit_width, bids.len());
+        assert_eq!(layout.index_wires.len(), index_bit_width(bids.len()));
+        let inputs: Vec<u8> = bids
+            .iter()
+            .flat_map(|bid| u64_to_bits_le(*bid, bit_width))
+            .collect();
+
+        let values = evaluate_plain_circuit(&layout.gates, &inputs).unwrap();
+        let (winner, _) = expected_vickrey(bids);
+        assert_eq!(
+            read_plain_word(&values, &layout.index_wires),
+            winner,
+            "bids={bids:?}"
+        );
+        assert_eq!(
+            read_plain_word(&values, &layout.max_wires),
+            bids[winner as usize]
+        );
+    }
+}
+
+#[test]
+fn vickrey_layout_matches_reference_for_all_3bit_bids() {
+    let bit_width = 3;
+    let n_bidders = 3;
+    let layout = build_vickrey_layout(bit_width, n_bidders);
+    assert_eq!(layout.winner_index_wires.len(), index_bit_width(n_bidders));
+    assert_eq!(layout.second_price_wires.len(), bit_width);
+
+    for packed in 0..(1u64 << (bit_width * n_bidders)) {
+        let bids: Vec<u64> = (0..n_bidders)
+            .map(|idx| (packed >> (idx * bit_width)) & 0b111)
+            .collect();
+        let inputs: Vec<u8> = bids
+            .iter()
+            .flat_map(|bid| u64_to_bits_le(*bid, bit_width))
+            .collect();
+
+        let values = evaluate_plain_circuit(&layout.gates, &inputs).unwrap();
+        let winner = read_plain_word(&values, &layout.winner_index_wires);
+        let price = read_plain_word(&values, &layout.second_price_wires);
+        assert_eq!((winner, price), expected_vickrey(&bids), "bids={bids:?}");
+    }
+}
+
+#[test]
+fn vickrey_layout_evaluates_through_garbling() {
+    let bit_width = 8;
+    let bids = [41u64, 200, 17, 199, 200];
+    let vickrey = build_vickrey_layout(bit_width, bids.len());
+
+    let circuit_id = keccak256(&[b"vickrey-test"]);
+    let seed = keccak256(&[b"vickrey-seed"]);
+    let layout = CircuitLayout::new(circuit_id, 3, vickrey.gates.clone());
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+
+    let input_labels: Vec<(u16, [u8; 16])> = bids
+        .iter()
+        .flat_map(|bid| u64_to_bits_le(*bid, bit_width))
+        .enumerate()
+        .map(|(wire, bit)| {
+            let wire = wire as u16;
+            (wire, derive_wire_label(circuit_id, 3, wire, bit, seed))
+        })
+        .collect();
+    let wire_labels = evaluate_garbled_wires(&layout, &leaves, &input_labels, &hints).unwrap();
+
+    // Decode output labels back to bits by matching the garbler's semantic labels.
+    let decode = |wires: &[u16]| -> u64 {
+        let bits: Vec<u8> = wires
+            .iter()
+            .map(|wire| {
+                let label = wire_labels[*wire as usize].expect("output label");
+                if label == derive_wire_label(circuit_id, 3, *wire, 0, seed) {
+                    0
+                } else {
+                    assert_eq!(label, derive_wire_label(circuit_id, 3, *wire, 1, seed));
+                    1
+                }
+            })
+            .collect();
+        bits_le_to_u64(&bits).unwrap()
+    };
+
+    assert_eq!(decode(&vickrey.winner_index_wires), 1);
+    assert_eq!(decode(&vickrey.second_price_wires), 200);
+}
@@ -0,0 +1,127 @@
+//! Base OT: Bob obtains exactly the label of each of his bits.
+
+use std::fs;
+
+use k256::ProjectivePoint;
+use off_chain_common::base_ot::{
+    ANSWERED_CHOICES_FILE, OT_POINT_LEN, OtReceiver, OtSender, decode_ot_point,
+    record_answered_choices,
+};
+use off_chain_common::consensus::keccak256;
+use off_chain_common::evaluation::derive_bob_label_offers;
+
+mod common;
+
+use common::temp_dir;
+
+const BIT_WIDTH: usize = 8;
+
+fn offers() -> Vec<([u8; 16], [u8; 16])> {
+    derive_bob_label_offers([0x71u8; 32], keccak256(&[b"circuit"]), 3, BIT_WIDTH)
+}
+
+fn transfer(choice_bits: &[u8]) -> (Vec<[[u8; 16]; 2]>, Vec<[u8; 16]>) {
+    let sender = OtSender::new([0xa1u8; 32]);
+    let receiver =
+        OtReceiver::new([0xb2u8; 32], &sender.setup_message(), choice_bits).expect("receiver");
+    let responses = sender
+        .respond(&receiver.choice_messages(), &offers())
+        .expect("respond");
+    let labels = receiver.decrypt(&responses).expect("decrypt");
+    (responses, labels)
+}
+
+#[test]
+fn receiver_learns_the_chosen_labels() {
+    let choice_bits = [1u8, 0, 0, 1, 1, 1, 0, 1];
+    let (_, labels) = transfer(&choice_bits);
+    for ((label, bit), (l0, l1)) in labels.iter().zip(choice_bits).zip(offers()) {
+        assert_eq!(*label, if bit == 1 { l1 } else { l0 });
+    }
+}
+
+#[test]
+fn responses_hide_both_labels_and_the_other_row_stays_opaque() {
+    let choice_bits = [0u8; BIT_WIDTH];
+    let (responses, labels) = transfer(&choice_bits);
+    for ((rows, label), (l0, l1)) in responses.iter().zip(&labels).zip(offers()) {
+        assert_eq!(*label, l0);
+        assert!(rows[0] != l0 && rows[1] != l1);
+        // Bob's key for row 0 does not open row 1.
+        let key = std::array::from_fn::<u8, 16, _>(|i| rows[0][i] ^ label[i]);
+        let other = std::array::from_fn::<u8, 16, _>(|i| rows[1][i] ^ key[i]);
+        assert_ne!(other, l1);
+    }
+}
+
+#[test]
+fn choice_points_do_not_repeat_across_bits() {
+    let sender = OtSender::new([0xa1u8; 32]);
+    let zeros = OtReceiver::new([0xb2u8; 32], &sender.setup_message(), &[0; 4]).expect("zeros");
+    let ones = OtReceiver::new([0xb2u8; 32], &sender.setup_message(), &[1; 4]).expect("ones");
+    let zeros = zeros.choice_messages();
+    let ones = ones.choice_messages();
+    assert!(zeros.iter().zip(&ones).all(|(a, b)| a != b));
+    assert!(zeros.iter().all(|point| decode_ot_point(point).is_ok()));
+}
+
+#[test]
+fn a_reused_secret_hides_the_bits_across_setup_points() {
+    let (first, second) = (OtSender::new([0xa1u8; 32]), OtSender::new([0xa2u8; 32]));
+    let point = |bytes: &[u8; OT_POINT_LEN]| decode_ot_point(bytes).expect("point");
+    let setup_gap = point(&first.setup_message()) - point(&second.setup_message());
+    for bit in [0u8, 1] {
+        let choices = |sender: &OtSender| {
+            OtReceiver::new([0xb2u8; 32], &sender.setup_message(), &[bit; 4])
+                .expect("receiver")
+                .choice_messages()
+        };
+        for (a, b) in choices(&first).iter().zip(&choices(&second)) {
+            let gap = point(a) - point(b);
+            // With setup-independent wire secrets the gap is 0 for bit 0 and A' - A'' for 1.
+            assert_ne!(gap, ProjectivePoint::IDENTITY);
+            assert_ne!(gap, setup_gap);
+        }
+    }
+}
+
+#[test]
+fn rejects_malformed_messages() {
+    let sender = OtSender::new([0xa1u8; 32]);
+    assert!(decode_ot_point(&[0u8; OT_POINT_LEN]).is_err());
+    assert!(decode_ot_point(&sender.setup_message()[..32]).is_err());
+    // x = 2^256 - 1 exceeds the field modulus.
+    let mut off_curve = [0xffu8; OT_POINT_LEN];
+    off_curve[0] = 0x02;
+    assert!(OtReceiver::new([0xb2u8; 32], &off_curve, &[0]).is_err());
+
+    let err = sender
+        .respond(&[sender.setup_message()], &offers())
+        .unwrap_err();
+    assert!(err.contains("1 OT choices for 8 label pairs"), "{err}");
+    let receiver = OtReceiver::new([0xb2u8; 32], &sender.setup_message(), &[0, 1]).expect("ok");
+    assert!(receiver.decrypt(&[[[0u8; 16]; 2]]).is_err());
+}
+
+#[test]
+fn only_the_first_choice_set_of_an_instance_is_answered() {
+    let dir = temp_dir("base-ot-answered");
+    let setup = OtSender::new([0xa1u8; 32]).setup_message();
+    let choices = |bits: &[u8]| {
+        OtReceiver::new([0xb2u8; 32], &setup, bits)
+            .expect("receiver")
+            .choice_messages()
+    };
+    let zeros = choices(&[0; BIT_WIDTH]);
+    let ones = choices(&[1; BIT_WIDTH]);
+
+    record_answered_choices(&dir, 3, &zeros).expect("first choices");
+    record_answered_choices(&dir, 3, &zeros).expect("same choices again");
+    let err = record_answered_choices(&dir, 3, &ones).unwrap_err();
+    assert!(err.contains("instance 3 already answered"), "{err}");
+    record_answered_choices(&dir, 4, &ones).expect("another instance");
+
+    let ledger = fs::read_to_string(dir.join(ANSWERED_CHOICES_FILE)).expect("ledger");
+    assert_eq!(ledger.lines().count(), 2);
+    let _ = fs::remove_dir_all(dir);
+}
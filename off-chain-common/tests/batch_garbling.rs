@@ -0,0 +1,94 @@
+//! Batch garbling: `garble_all_instances` matches garbling each instance on its own.
+
+use off_chain_common::garble::{
+    garble_all_instances, garble_all_instances_with_scheme, garble_circuit,
+};
+use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::scenario::{
+    CUT_AND_CHOOSE_N, build_millionaires_layout, com_seed, derive_instance_seed,
+};
+use off_chain_common::types::{
+    GarblingScheme, LeafFormat, NotGateMode, PadFunction, RowAuthMode, SchemeId,
+};
+
+mod common;
+
+use common::{millionaires_circuit_id, millionaires_layout};
+
+const MASTER_SEED: [u8; 32] = [0x24u8; 32];
+
+#[test]
+fn batch_matches_per_instance_garbling() {
+    let gates = build_millionaires_layout(8).gates;
+    let instances = garble_all_instances(
+        MASTER_SEED,
+        millionaires_circuit_id(),
+        &gates,
+        CUT_AND_CHOOSE_N,
+    );
+    assert_eq!(instances.len(), CUT_AND_CHOOSE_N);
+
+    for (instance_id, inst) in instances.iter().enumerate() {
+        let instance_id = instance_id as u64;
+        let seed = derive_instance_seed(MASTER_SEED, millionaires_circuit_id(), instance_id);
+        let layout = millionaires_layout(instance_id, 8);
+        let leaves = garble_circuit(seed, &layout);
+        let block_hashes = leaves
+            .iter()
+            .enumerate()
+            .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+            .collect::<Vec<_>>();
+
+        assert_eq!(inst.instance_id, instance_id);
+        assert_eq!(inst.seed, seed);
+        assert_eq!(inst.com_seed, com_seed(seed));
+        assert_eq!(inst.leaves, leaves);
+        assert_eq!(inst.block_hashes, block_hashes);
+        assert_eq!(inst.root_gc, incremental_root_from_hashes(&block_hashes));
+    }
+}
+
+#[test]
+fn recycled_caches_do_not_leak_between_instances() {
+    // Free-XOR precomputes per-instance label tables; stale cache entries would surface here.
+    let scheme_id = SchemeId::new(
+        GarblingScheme::FreeXor,
+        LeafFormat::V1,
+        NotGateMode::Hinted,
+        PadFunction::FixedKeyAes,
+        RowAuthMode::Unauthenticated,
+    );
+    let gates = build_millionaires_layout(4).gates;
+    let instances = garble_all_instances_with_scheme(
+        MASTER_SEED,
+        millionaires_circuit_id(),
+        &gates,
+        scheme_id,
+        3,
+    );
+    for inst in &instances {
+        let layout = millionaires_layout(inst.instance_id, 4).with_scheme_id(scheme_id);
+        assert_eq!(inst.leaves, garble_circuit(inst.seed, &layout));
+    }
+    assert_ne!(instances[0].root_gc, instances[1].root_gc);
+}
+
+#[test]
+fn empty_batch_has_no_instances() {
+    let gates = build_millionaires_layout(4).gates;
+    assert!(garble_all_instances(MASTER_SEED, millionaires_circuit_id(), &gates, 0).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "emits v1 leaves")]
+fn v2_schemes_are_rejected() {
+    let v2 = SchemeId::new(
+        GarblingScheme::Classic,
+        LeafFormat::V2,
+        NotGateMode::Hinted,
+        PadFunction::Keccak,
+        RowAuthMode::Unauthenticated,
+    );
+    let gates = build_millionaires_layout(4).gates;
+    garble_all_instances_with_scheme(MASTER_SEED, millionaires_circuit_id(), &gates, v2, 1);
+}
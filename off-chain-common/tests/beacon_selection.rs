@@ -0,0 +1,101 @@
+//! Selection of `m` from a block hash or a VRF output, checked by both parties.
+
+use off_chain_common::scenario::SessionParams;
+use off_chain_common::scenario::beacon::{
+    BeaconEvidence, RandomnessSource, commitment_binding, derive_beacon_m, m_from_randomness,
+    verify_beacon_m, vrf_alpha, vrf_prove, vrf_public_key, vrf_verify,
+};
+
+const CIRCUIT_ID: [u8; 32] = [0x31u8; 32];
+const BINDING: [u8; 32] = [0x42u8; 32];
+const VRF_SECRET: [u8; 32] = [0x07u8; 32];
+
+#[test]
+fn block_hash_selects_m_in_range_and_binds_height() {
+    let params = SessionParams::new(7).expect("params");
+    let source = RandomnessSource::BlockHash { height: 1200 };
+    let mut seen = [false; 7];
+    for byte in 0u8..64 {
+        let evidence = BeaconEvidence::BlockHash {
+            height: 1200,
+            hash: [byte; 32],
+        };
+        let m = derive_beacon_m(params, CIRCUIT_ID, BINDING, source, &evidence).expect("m");
+        assert!(m < params.n);
+        assert_eq!(
+            m,
+            m_from_randomness(params, CIRCUIT_ID, BINDING, [byte; 32])
+        );
+        seen[m] = true;
+    }
+    assert!(seen.iter().all(|hit| *hit), "every index reachable");
+
+    let late = BeaconEvidence::BlockHash {
+        height: 1201,
+        hash: [0u8; 32],
+    };
+    let err = derive_beacon_m(params, CIRCUIT_ID, BINDING, source, &late).unwrap_err();
+    assert!(err.contains("committed height is 1200"), "{err}");
+}
+
+#[test]
+fn vrf_proof_round_trips() {
+    let public_key = vrf_public_key(VRF_SECRET).expect("public key");
+    let alpha = vrf_alpha(CIRCUIT_ID, BINDING);
+    let (proof, beta) = vrf_prove(VRF_SECRET, &alpha).expect("prove");
+    assert_eq!(vrf_verify(&public_key, &alpha, &proof), Ok(beta));
+    // Unique output: proving again yields the same beta.
+    assert_eq!(vrf_prove(VRF_SECRET, &alpha).expect("prove").1, beta);
+
+    let other_alpha = vrf_alpha(CIRCUIT_ID, [0x43u8; 32]);
+    assert!(vrf_verify(&public_key, &other_alpha, &proof).is_err());
+    let other_key = vrf_public_key([0x08u8; 32]).expect("public key");
+    assert!(vrf_verify(&other_key, &alpha, &proof).is_err());
+    let mut tampered = proof;
+    tampered[40] ^= 0x01;
+    assert!(vrf_verify(&public_key, &alpha, &tampered).is_err());
+}
+
+#[test]
+fn both_parties_agree_on_vrf_selected_m() {
+    let params = SessionParams::default();
+    let source = RandomnessSource::Vrf {
+        public_key: vrf_public_key(VRF_SECRET).expect("public key"),
+    };
+    let (proof, beta) = vrf_prove(VRF_SECRET, &vrf_alpha(CIRCUIT_ID, BINDING)).expect("prove");
+    let evidence = BeaconEvidence::Vrf { proof };
+    let m = m_from_randomness(params, CIRCUIT_ID, BINDING, beta);
+
+    verify_beacon_m(params, CIRCUIT_ID, BINDING, source, &evidence, m).expect("selected m");
+    let err =
+        verify_beacon_m(params, CIRCUIT_ID, BINDING, source, &evidence, (m + 1) % 10).unwrap_err();
+    assert!(err.contains(&format!("beacon selects m={m}")), "{err}");
+}
+
+#[test]
+fn rejects_evidence_of_another_source() {
+    let source = RandomnessSource::BlockHash { height: 5 };
+    let evidence = BeaconEvidence::Vrf { proof: [0u8; 81] };
+    assert!(
+        derive_beacon_m(
+            SessionParams::default(),
+            CIRCUIT_ID,
+            BINDING,
+            source,
+            &evidence
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn binding_commits_to_every_root_gc_in_order() {
+    let roots = [[0x01u8; 32], [0x02u8; 32], [0x03u8; 32]];
+    let binding = commitment_binding(&roots);
+    assert_eq!(binding, commitment_binding(&roots));
+    assert_ne!(binding, commitment_binding(&[roots[1], roots[0], roots[2]]));
+    assert_ne!(binding, commitment_binding(&roots[..2]));
+    let mut changed = roots;
+    changed[2][31] ^= 1;
+    assert_ne!(binding, commitment_binding(&changed));
+}
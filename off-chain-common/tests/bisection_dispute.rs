@@ -0,0 +1,108 @@
+//! Bisection disputes: narrowing over IH checkpoints down to the one-gate packet.
+
+use std::io::Cursor;
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::dispute::bisection::{Bisection, BisectionResponder};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::{gc_block_hash, ih_proof_from_hashes};
+use off_chain_common::messages::{CheckpointMessage, Message, read_message, write_message};
+use off_chain_common::scenario::derive_instance_seed;
+use off_chain_common::types::CircuitLayout;
+
+mod common;
+
+use common::millionaires_layout;
+
+fn instance() -> ([u8; 32], CircuitLayout) {
+    let layout = millionaires_layout(4, 8);
+    let seed = derive_instance_seed(keccak256(&[b"master-seed-v1"]), layout.circuit_id, 4);
+    (seed, layout)
+}
+
+#[test]
+fn narrows_to_tampered_gate_in_log_rounds() {
+    let (seed, layout) = instance();
+    let honest = garble_circuit(seed, &layout);
+    let n = honest.len();
+    for gate_index in [0, 1, n / 3, n - 1] {
+        let mut claimed = honest.clone();
+        claimed[gate_index][20] ^= 0x04;
+        let responder = BisectionResponder::new(4, claimed.clone());
+        let outcome = Bisection::new(seed, &layout, responder.root_gc())
+            .expect("start")
+            .run(|query| responder.answer(query))
+            .expect("bisection");
+
+        assert_eq!(outcome.gate_index, gate_index);
+        assert_eq!(outcome.claimed_leaf, claimed[gate_index]);
+        assert_eq!(outcome.expected_leaf, honest[gate_index]);
+        assert_eq!(outcome.gate, layout.gates[gate_index]);
+        assert!(outcome.rounds <= (usize::BITS - (n - 1).leading_zeros()) as usize);
+        let block_hashes = claimed
+            .iter()
+            .enumerate()
+            .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcome.ih_proof,
+            ih_proof_from_hashes(&block_hashes, gate_index)
+        );
+    }
+}
+
+#[test]
+fn exchanges_framed_messages() {
+    let (seed, layout) = instance();
+    let mut claimed = garble_circuit(seed, &layout);
+    claimed[7][30] ^= 0x01;
+    claimed[40][30] ^= 0x01;
+    let responder = BisectionResponder::new(4, claimed);
+
+    let outcome = Bisection::new(seed, &layout, responder.root_gc())
+        .expect("start")
+        .run(|query| {
+            let mut to_alice = Vec::new();
+            write_message(&mut to_alice, &Message::BisectionQuery(*query))?;
+            let Message::BisectionQuery(received) = read_message(&mut Cursor::new(to_alice))?
+            else {
+                return Err("expected a query".to_string());
+            };
+            let mut to_bob = Vec::new();
+            write_message(&mut to_bob, &responder.answer(&received)?)?;
+            read_message(&mut Cursor::new(to_bob))
+        })
+        .expect("bisection");
+    // The first differing block wins.
+    assert_eq!(outcome.gate_index, 7);
+}
+
+#[test]
+fn refuses_honest_root() {
+    let (seed, layout) = instance();
+    let responder = BisectionResponder::new(4, garble_circuit(seed, &layout));
+    let err = Bisection::new(seed, &layout, responder.root_gc()).unwrap_err();
+    assert!(err.contains("nothing to dispute"), "{err}");
+}
+
+#[test]
+fn detects_inconsistent_checkpoints() {
+    let (seed, layout) = instance();
+    let mut claimed = garble_circuit(seed, &layout);
+    claimed[12][9] ^= 0x10;
+    let responder = BisectionResponder::new(4, claimed);
+    let lie = [0xeeu8; 32];
+
+    // Alice reports a made-up state for every checkpoint.
+    let err = Bisection::new(seed, &layout, responder.root_gc())
+        .expect("start")
+        .run(|query| match responder.answer(query)? {
+            Message::Checkpoint(checkpoint) => Ok(Message::Checkpoint(CheckpointMessage {
+                state: lie,
+                ..checkpoint
+            })),
+            reveal => Ok(reveal),
+        })
+        .unwrap_err();
+    assert!(err.contains("does not hash to Alice's checkpoint"), "{err}");
+}
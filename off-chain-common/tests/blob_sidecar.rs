@@ -0,0 +1,173 @@
+//! Eval blobs read back from blob transactions: SimpleCoder unpacking, blob files, `cast tx`
+//! output, and blob sidecars fetched from a mocked beacon node.
+
+use std::thread;
+use std::time::Duration;
+
+use off_chain_common::blob_sidecar::{
+    BYTES_PER_BLOB, BeaconClient, BlobTx, decode_blob_payload, kzg_to_versioned_hash,
+    parse_blob_file,
+};
+use off_chain_common::cli::hex_prefixed;
+use off_chain_common::retry::RetryPolicy;
+
+/// Packs `payload` the way alloy's `SimpleCoder` (and `cast send --blob`) does.
+fn encode_blobs(payload: &[u8]) -> Vec<Vec<u8>> {
+    let mut elements = vec![[0u8; 32]];
+    elements[0][1..9].copy_from_slice(&(payload.len() as u64).to_be_bytes());
+    for chunk in payload.chunks(31) {
+        let mut element = [0u8; 32];
+        element[1..1 + chunk.len()].copy_from_slice(chunk);
+        elements.push(element);
+    }
+    elements
+        .concat()
+        .chunks(BYTES_PER_BLOB)
+        .map(|chunk| {
+            let mut blob = chunk.to_vec();
+            blob.resize(BYTES_PER_BLOB, 0);
+            blob
+        })
+        .collect()
+}
+
+fn payload(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 % 251) as u8).collect()
+}
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 2,
+        initial_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(1),
+    }
+}
+
+#[test]
+fn blob_payloads_round_trip_across_blobs() {
+    for len in [1, 31, 32, 4_096, 200_000] {
+        let payload = payload(len);
+        let blobs = encode_blobs(&payload);
+        assert_eq!(blobs.len(), if len > 126_000 { 2 } else { 1 });
+        assert_eq!(decode_blob_payload(&blobs), Ok(payload), "len {len}");
+    }
+
+    let mut blobs = encode_blobs(&payload(100));
+    blobs[0][32] = 0x80;
+    let err = decode_blob_payload(&blobs).unwrap_err();
+    assert!(err.contains("field element 1"), "{err}");
+
+    let blobs = encode_blobs(&payload(200_000));
+    let err = decode_blob_payload(&blobs[..1]).unwrap_err();
+    assert_eq!(err, "blob data ends before the payload does");
+    let err = decode_blob_payload(&[vec![0; BYTES_PER_BLOB]]).unwrap_err();
+    assert_eq!(err, "blob data carries no payload");
+    let err = decode_blob_payload(&[vec![0; 10]]).unwrap_err();
+    assert!(err.starts_with("blob 0 has 10 bytes"), "{err}");
+}
+
+#[test]
+fn blob_files_are_raw_or_hex_lines() {
+    let blobs = encode_blobs(&payload(200_000));
+    assert_eq!(parse_blob_file(&blobs.concat()), Ok(blobs.clone()));
+    let text = blobs
+        .iter()
+        .map(|blob| format!("{}\n", hex_prefixed(blob)))
+        .collect::<String>();
+    assert_eq!(parse_blob_file(text.as_bytes()), Ok(blobs.clone()));
+
+    let err = parse_blob_file(&blobs[0][..1000]).unwrap_err();
+    assert!(err.starts_with("blob 0 has 1000 bytes"), "{err}");
+    assert_eq!(parse_blob_file(b"").unwrap_err(), "blob file is empty");
+    let err = parse_blob_file(b"0x12zz\n").unwrap_err();
+    assert!(err.starts_with("invalid blob hex"), "{err}");
+}
+
+#[test]
+fn blob_transactions_are_read_from_cast_json() {
+    let hash = format!("0x01{}", "ab".repeat(31));
+    let json = format!(
+        r#"{{"hash":"0x{}","blockNumber":"0x1a","type":"0x3","blobVersionedHashes":["{hash}"]}}"#,
+        "11".repeat(32)
+    );
+    let tx = BlobTx::from_cast_json(&json).expect("blob tx");
+    assert_eq!(tx.block_number, 26);
+    let mut expected = [0xab; 32];
+    expected[0] = 0x01;
+    assert_eq!(tx.versioned_hashes, [expected]);
+
+    let err = BlobTx::from_cast_json(r#"{"blockNumber":"0x1a","input":"0x"}"#).unwrap_err();
+    assert_eq!(err, "transaction carries no blobs");
+    let err =
+        BlobTx::from_cast_json(r#"{"blockNumber":null,"blobVersionedHashes":[]}"#).unwrap_err();
+    assert!(err.contains("not mined"), "{err}");
+    let err =
+        BlobTx::from_cast_json(r#"{"blockNumber":5,"blobVersionedHashes":["0x12"]}"#).unwrap_err();
+    assert!(err.starts_with("invalid blob versioned hash"), "{err}");
+}
+
+#[test]
+fn beacon_sidecars_are_matched_by_versioned_hash() {
+    let payload = payload(150_000);
+    let blobs = encode_blobs(&payload);
+    let commitments = [[0x0a; 48], [0x0b; 48], [0x0c; 48]];
+    // The slot holds another transaction's blob too, listed first.
+    let sidecars = [
+        (0, vec![0xee; BYTES_PER_BLOB], commitments[2]),
+        (1, blobs[1].clone(), commitments[1]),
+        (2, blobs[0].clone(), commitments[0]),
+    ]
+    .iter()
+    .map(|(index, blob, commitment)| {
+        format!(
+            r#"{{"index":"{index}","blob":"{}","kzg_commitment":"{}"}}"#,
+            hex_prefixed(blob),
+            hex_prefixed(commitment)
+        )
+    })
+    .collect::<Vec<_>>()
+    .join(",");
+    let body = format!(r#"{{"data":[{sidecars}]}}"#);
+
+    let server = tiny_http::Server::http("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}", server.server_addr().to_ip().expect("ip"));
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let reply = match request.url() {
+                "/eth/v1/beacon/genesis" => {
+                    r#"{"data":{"genesis_time":"1000","genesis_fork_version":"0x00"}}"#.to_string()
+                }
+                "/eth/v1/config/spec" => {
+                    r#"{"data":{"SECONDS_PER_SLOT":"12","SLOTS_PER_EPOCH":"32"}}"#.to_string()
+                }
+                "/eth/v1/beacon/blob_sidecars/25" => body.clone(),
+                _ => {
+                    let _ = request.respond(tiny_http::Response::empty(404));
+                    continue;
+                }
+            };
+            let _ = request.respond(tiny_http::Response::from_string(reply));
+        }
+    });
+
+    let beacon = BeaconClient::new(&format!("{url}/"), policy());
+    assert_eq!(beacon.url(), url);
+    let slot = beacon.slot_at(1000 + 25 * 12 + 5).expect("slot");
+    assert_eq!(slot, 25);
+    assert!(beacon.slot_at(999).is_err());
+
+    let hashes = [
+        kzg_to_versioned_hash(&commitments[0]),
+        kzg_to_versioned_hash(&commitments[1]),
+    ];
+    assert!(hashes.iter().all(|hash| hash[0] == 0x01));
+    let fetched = beacon.blobs_for(slot, &hashes).expect("blobs");
+    assert_eq!(decode_blob_payload(&fetched), Ok(payload));
+
+    let err = beacon
+        .blobs_for(slot, &[kzg_to_versioned_hash(&[0x0d; 48])])
+        .unwrap_err();
+    assert!(err.starts_with("no blob with versioned hash"), "{err}");
+    let err = beacon.blobs_for(26, &hashes).unwrap_err();
+    assert!(err.contains("404"), "{err}");
+}
@@ -0,0 +1,119 @@
+//! Bristol Fashion import/export: parsing, gate mapping, round trips, and evaluation.
+
+use off_chain_common::circuits::{export_bristol, parse_bristol};
+use off_chain_common::consensus::{derive_wire_label, keccak256};
+use off_chain_common::evaluation::{comparison_output_wire, u64_to_bits_le};
+use off_chain_common::evaluation::{
+    derive_not_gate_hints, evaluate_garbled_wires, evaluate_plain_circuit,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::scenario::{Comparison, build_millionaires_layout};
+use off_chain_common::types::{CircuitLayout, GateType};
+
+/// One-bit full adder with three 1-bit inputs (a, b, carry) and outputs (sum, carry_out).
+/// Exercises XOR/AND/INV plus `EQW` copies onto the output wires.
+const FULL_ADDER: &str = "9 12
+3 1 1 1
+2 1 1
+
+2 1 0 1 3 XOR
+2 1 0 1 4 AND
+2 1 2 3 5 AND
+2 1 3 2 6 XOR
+1 1 6 7 INV
+1 1 7 8 INV
+2 1 4 5 9 XOR
+1 1 8 10 EQW
+1 1 9 11 EQW
+";
+
+#[test]
+fn parses_full_adder_and_maps_gate_types() {
+    let circuit = parse_bristol(FULL_ADDER, keccak256(&[b"bristol-adder"])).unwrap();
+    assert_eq!(circuit.input_sizes, vec![1, 1, 1]);
+    assert_eq!(circuit.output_sizes, vec![1, 1]);
+    // EQW gates are aliased away, outputs point at their sources.
+    assert_eq!(circuit.layout.gates.len(), 7);
+    assert_eq!(circuit.output_wires, vec![8, 9]);
+    assert_eq!(circuit.layout.gates[4].gate_type, GateType::Not);
+    assert_eq!(circuit.layout.gates[4].wire_b, 0);
+
+    for packed in 0..8u8 {
+        let inputs = [packed & 1, (packed >> 1) & 1, (packed >> 2) & 1];
+        let values = evaluate_plain_circuit(&circuit.layout.gates, &inputs).unwrap();
+        let total = inputs.iter().sum::<u8>();
+        assert_eq!(values[circuit.output_wires[0] as usize], Some(total & 1));
+        assert_eq!(values[circuit.output_wires[1] as usize], Some(total >> 1));
+    }
+}
+
+#[test]
+fn imported_circuit_garbles_and_evaluates() {
+    let circuit_id = keccak256(&[b"bristol-adder"]);
+    let seed = keccak256(&[b"bristol-seed"]);
+    let mut layout = parse_bristol(FULL_ADDER, circuit_id).unwrap().layout;
+    layout.instance_id = 2;
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+
+    // a=1, b=1, carry=1 -> sum=1, carry_out=1
+    let inputs: Vec<(u16, [u8; 16])> = (0..3u16)
+        .map(|wire| (wire, derive_wire_label(circuit_id, 2, wire, 1, seed)))
+        .collect();
+    let labels = evaluate_garbled_wires(&layout, &leaves, &inputs, &hints).unwrap();
+    assert_eq!(
+        labels[8],
+        Some(derive_wire_label(circuit_id, 2, 8, 1, seed))
+    );
+    assert_eq!(
+        labels[9],
+        Some(derive_wire_label(circuit_id, 2, 9, 1, seed))
+    );
+}
+
+#[test]
+fn rejects_constants_and_gate_count_mismatch() {
+    let with_constant = "1 2\n1 1\n1 1\n1 1 0 1 EQ\n";
+    let err = parse_bristol(with_constant, [0u8; 32]).unwrap_err();
+    assert!(err.contains("EQ"), "{err}");
+
+    let short = "2 3\n2 1 1\n1 1\n2 1 0 1 2 AND\n";
+    let err = parse_bristol(short, [0u8; 32]).unwrap_err();
+    assert!(err.contains("declares 2 gates"), "{err}");
+}
+
+#[test]
+fn exported_layouts_round_trip_through_parser() {
+    let circuit = parse_bristol(FULL_ADDER, [0u8; 32]).unwrap();
+    let text = circuit.to_bristol().unwrap();
+    let reparsed = parse_bristol(&text, [0u8; 32]).unwrap();
+    assert_eq!(reparsed.input_sizes, circuit.input_sizes);
+    assert_eq!(reparsed.output_sizes, circuit.output_sizes);
+    // Already dense, so the gate list survives unchanged.
+    assert_eq!(reparsed.layout.gates, circuit.layout.gates);
+    assert_eq!(reparsed.output_wires, circuit.output_wires);
+}
+
+#[test]
+fn exported_millionaires_layout_preserves_semantics() {
+    let bit_width = 3;
+    let gates = build_millionaires_layout(bit_width).gates;
+    let gt_wire = comparison_output_wire(&gates, bit_width, Comparison::Gt).unwrap();
+    let layout = CircuitLayout::new([0u8; 32], 0, gates.clone());
+    let text = export_bristol(&layout, &[bit_width, bit_width], &[1], &[gt_wire]).unwrap();
+    assert!(text.starts_with(&format!("{} ", gates.len() + 1)));
+
+    let imported = parse_bristol(&text, [0u8; 32]).unwrap();
+    for x in 0..8u64 {
+        for y in 0..8u64 {
+            let mut inputs = u64_to_bits_le(x, bit_width);
+            inputs.extend(u64_to_bits_le(y, bit_width));
+            let values = evaluate_plain_circuit(&imported.layout.gates, &inputs).unwrap();
+            let out = imported.output_wires[0] as usize;
+            assert_eq!(values[out], Some(u8::from(x > y)), "x={x} y={y}");
+        }
+    }
+
+    let err = export_bristol(&layout, &[bit_width], &[1], &[gt_wire]).unwrap_err();
+    assert!(err.contains("input widths"), "{err}");
+}
@@ -0,0 +1,149 @@
+//! Session bundles: manifest and checksums, reads through bundle paths and tamper detection.
+
+use std::fs;
+
+use off_chain_common::bundle::{BUNDLE_MAGIC, Bundle, BundleBuilder, locate_in_bundle};
+use off_chain_common::compression::{compress, find_artifact, read_artifact_to_string};
+use off_chain_common::consensus::keccak256;
+
+mod common;
+
+use common::temp_dir;
+
+fn session_bundle() -> BundleBuilder {
+    let mut bundle = BundleBuilder::new();
+    bundle
+        .add("instance-0-seed.txt", b"0x11\n".to_vec())
+        .expect("seed");
+    bundle
+        .add("eval/eval-meta.txt", b"bit_width=8\n".to_vec())
+        .expect("meta");
+    bundle
+        .add(
+            "eval/gc-m-leaves.txt.zst",
+            compress(b"0xaa\n0xbb\n", 3).expect("compress"),
+        )
+        .expect("leaves");
+    bundle
+}
+
+#[test]
+fn bundles_round_trip_with_a_sorted_checksummed_manifest() {
+    let dir = temp_dir("bundle-round-trip");
+    let path = dir.join("session.ppa");
+    let builder = session_bundle();
+    let manifest_hash = builder.write(&path).expect("write");
+
+    let raw = fs::read(&path).expect("raw");
+    assert_eq!(raw[..8], BUNDLE_MAGIC);
+    let bundle = Bundle::open(&path).expect("open");
+    assert_eq!(bundle.manifest_hash(), manifest_hash);
+    let names = bundle
+        .entries()
+        .iter()
+        .map(|entry| entry.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "eval/eval-meta.txt",
+            "eval/gc-m-leaves.txt.zst",
+            "instance-0-seed.txt"
+        ]
+    );
+    let meta = bundle.entry("eval/eval-meta.txt").expect("meta entry");
+    assert_eq!(
+        (meta.length, meta.digest),
+        (12, keccak256(&[b"bit_width=8\n"]))
+    );
+    assert_eq!(bundle.read("instance-0-seed.txt"), Ok(b"0x11\n".to_vec()));
+    assert_eq!(bundle.verify(), Ok(()));
+
+    let out = dir.join("extracted");
+    assert_eq!(bundle.extract(&out), Ok(3));
+    assert_eq!(
+        fs::read(out.join("eval/eval-meta.txt")).expect("extracted"),
+        b"bit_width=8\n"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn artifact_paths_resolve_into_bundles() {
+    let dir = temp_dir("bundle-paths");
+    let path = dir.join("session.ppa");
+    session_bundle().write(&path).expect("write");
+    let eval_dir = path.join("eval");
+
+    assert_eq!(
+        locate_in_bundle(&eval_dir.join("eval-meta.txt")),
+        Some((path.clone(), "eval/eval-meta.txt".to_string()))
+    );
+    assert_eq!(locate_in_bundle(&dir.join("missing/eval-meta.txt")), None);
+    assert_eq!(
+        read_artifact_to_string(&eval_dir.join("eval-meta.txt")),
+        Ok("bit_width=8\n".to_string())
+    );
+    // `.zst` entries decompress just like files next to the plain path.
+    let leaves = eval_dir.join("gc-m-leaves.txt");
+    assert!(find_artifact(&leaves).is_some());
+    assert_eq!(
+        read_artifact_to_string(&leaves),
+        Ok("0xaa\n0xbb\n".to_string())
+    );
+    assert_eq!(find_artifact(&eval_dir.join("not-hints.txt")), None);
+    let err = read_artifact_to_string(&eval_dir.join("not-hints.txt")).unwrap_err();
+    assert!(err.contains("has no entry 'eval/not-hints.txt'"), "{err}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn tampered_or_truncated_bundles_are_rejected() {
+    let dir = temp_dir("bundle-tamper");
+    let path = dir.join("session.ppa");
+    session_bundle().write(&path).expect("write");
+    let original = fs::read(&path).expect("raw");
+
+    let mut tampered = original.clone();
+    *tampered.last_mut().unwrap() ^= 0x01;
+    fs::write(&path, &tampered).expect("tamper");
+    let bundle = Bundle::open(&path).expect("manifest still parses");
+    let err = bundle.read("instance-0-seed.txt").unwrap_err();
+    assert!(err.contains("do not match the manifest digest"), "{err}");
+    assert!(bundle.verify().is_err());
+
+    let cases = [
+        (original[..original.len() - 1].to_vec(), "manifest covers"),
+        ([&original[..], b"x"].concat(), "manifest covers"),
+        (
+            [b"PPABNDL0", &original[8..]].concat(),
+            "not a session bundle",
+        ),
+        (original[..10].to_vec(), "truncated header"),
+    ];
+    for (raw, expected) in cases {
+        fs::write(&path, raw).expect("write case");
+        let err = Bundle::open(&path).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+    }
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn builder_rejects_bad_and_duplicate_names() {
+    let mut bundle = BundleBuilder::new();
+    for name in ["", "../seed.txt", "eval//meta.txt", "eval/", "/abs"] {
+        let err = bundle.add(name, Vec::new()).unwrap_err();
+        assert!(err.contains("invalid bundle entry name"), "{name}: {err}");
+    }
+    bundle.add("eval/meta.txt", Vec::new()).expect("first");
+    let err = bundle.add("eval/meta.txt", Vec::new()).unwrap_err();
+    assert_eq!(err, "duplicate bundle entry 'eval/meta.txt'");
+
+    let dir = temp_dir("bundle-dir");
+    fs::write(dir.join("instance-0-leaves.txt"), "0x01\n").expect("leaves");
+    fs::create_dir_all(dir.join("nested")).expect("nested");
+    assert_eq!(bundle.add_dir("", &dir), Ok(1));
+    assert_eq!(bundle.len(), 2);
+    let _ = fs::remove_dir_all(dir);
+}
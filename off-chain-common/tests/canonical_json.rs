@@ -0,0 +1,118 @@
+//! Canonical JSON: exact encodings of the core types, round trips and strict decoding.
+
+use off_chain_common::canonical_json::{
+    canonicalize, from_canonical_json, parse_canonical_hex, to_canonical_json,
+};
+use off_chain_common::cli::hex_prefixed;
+use off_chain_common::evaluation::NotGateHint;
+use off_chain_common::garble::{GarbledInstance, garble_all_instances};
+use off_chain_common::messages::{CommitmentsMessage, InstanceCommitment};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{
+    CircuitLayout, ConstantWire, GarblingScheme, GateDesc, GateType, LeafFormat, RowAuthMode,
+};
+
+const CIRCUIT_ID: [u8; 32] = [0xab; 32];
+
+#[test]
+fn gates_and_layouts_have_one_fixed_encoding() {
+    let gate = GateDesc::new(GateType::Nand, 1, 2, 3);
+    assert_eq!(
+        to_canonical_json(&gate),
+        Ok(r#"{"gate_type":"nand","wire_a":1,"wire_b":2,"wire_c":3}"#.to_string())
+    );
+
+    let layout = CircuitLayout::new(CIRCUIT_ID, 2, vec![GateDesc::new(GateType::Not, 0, 0, 1)])
+        .with_scheme(GarblingScheme::FreeXor)
+        .with_leaf_format(LeafFormat::V2)
+        .with_row_auth(RowAuthMode::Mac)
+        .with_constant_wires(vec![ConstantWire { wire: 0, value: 1 }]);
+    let json = to_canonical_json(&layout).expect("encode");
+    assert_eq!(
+        json,
+        format!(
+            concat!(
+                r#"{{"circuit_id":"0x{}","constant_wires":[{{"value":1,"wire":0}}],"#,
+                r#""gates":[{{"gate_type":"not","wire_a":0,"wire_b":0,"wire_c":1}}],"#,
+                r#""instance_id":2,"leaf_format":"v2","not_gate_mode":"hinted","#,
+                r#""pad_function":"keccak","row_auth":"mac","scheme":"free_xor"}}"#
+            ),
+            "ab".repeat(32)
+        )
+    );
+    assert_eq!(from_canonical_json::<CircuitLayout>(&json), Ok(layout));
+}
+
+#[test]
+fn hints_and_instance_artifacts_round_trip() {
+    let hint = NotGateHint {
+        gate_index: 7,
+        in_label0: [0x01; 16],
+        out_if_in0: [0x02; 16],
+        in_label1: [0x03; 16],
+        out_if_in1: [0x04; 16],
+    };
+    let json = to_canonical_json(&hint).expect("hint");
+    assert!(json.contains(&format!(r#""in_label0":"0x{}""#, "01".repeat(16))));
+    assert_eq!(from_canonical_json::<NotGateHint>(&json), Ok(hint));
+
+    let gates = build_millionaires_layout(4).gates;
+    let instances = garble_all_instances([0x24; 32], CIRCUIT_ID, &gates, 2);
+    let json = to_canonical_json(&instances).expect("instances");
+    assert!(json.contains(&format!(
+        r#""root_gc":"{}""#,
+        hex_prefixed(&instances[1].root_gc)
+    )));
+    assert_eq!(
+        from_canonical_json::<Vec<GarbledInstance>>(&json),
+        Ok(instances.clone())
+    );
+
+    let commitments = CommitmentsMessage {
+        circuit_id: CIRCUIT_ID,
+        instances: instances
+            .iter()
+            .map(|instance| InstanceCommitment {
+                com_seed: instance.com_seed,
+                root_gc: instance.root_gc,
+                blob_hash_gc: [0; 32],
+                h_out: [0x11; 32],
+            })
+            .collect(),
+    };
+    let json = to_canonical_json(&commitments).expect("commitments");
+    assert_eq!(from_canonical_json(&json), Ok(commitments));
+}
+
+#[test]
+fn decoding_rejects_non_canonical_hex_and_unknown_fields() {
+    let good = format!(
+        r#"{{"blob_hash_gc":"0x{0}","com_seed":"0x{0}","h_out":"0x{0}","root_gc":"0x{0}"}}"#,
+        "00".repeat(32)
+    );
+    assert!(from_canonical_json::<InstanceCommitment>(&good).is_ok());
+    let cases = [
+        (good.replacen("0x00", "0x0A", 1), "lowercase"),
+        (good.replacen("\"0x00", "\"00", 1), "must start with 0x"),
+        (good.replacen("0x00", "0x", 1), "expected 32 bytes"),
+        (good.replacen('}', r#","extra":1}"#, 1), "unknown field"),
+    ];
+    for (raw, expected) in cases {
+        let err = from_canonical_json::<InstanceCommitment>(&raw).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+    }
+    let err =
+        from_canonical_json::<GateDesc>(r#"{"gate_type":"maj","wire_a":1,"wire_b":2,"wire_c":3}"#)
+            .unwrap_err();
+    assert!(err.contains("unknown variant"), "{err}");
+}
+
+#[test]
+fn canonicalize_sorts_keys_and_strips_whitespace() {
+    let gate = GateDesc::new(GateType::Xor, 4, 5, 6);
+    let loose = "{ \"wire_c\": 6,\n  \"gate_type\": \"xor\", \"wire_b\": 5, \"wire_a\": 4 }";
+    assert_eq!(canonicalize(loose), to_canonical_json(&gate));
+    assert_eq!(from_canonical_json::<GateDesc>(loose), Ok(gate));
+    assert_eq!(parse_canonical_hex::<2>("0xbeef"), Ok([0xbe, 0xef]));
+    assert!(parse_canonical_hex::<2>("0xbeefbe").is_err());
+}
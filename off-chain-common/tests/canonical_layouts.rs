@@ -0,0 +1,94 @@
+//! Layout canonicalization: deterministic topological order and dense wire renumbering.
+
+use off_chain_common::circuits::canonicalize_layout;
+use off_chain_common::evaluation::{evaluate_plain_circuit, u64_to_bits_le};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
+
+fn layout_of(gates: Vec<GateDesc>) -> CircuitLayout {
+    CircuitLayout::new([7u8; 32], 0, gates)
+}
+
+#[test]
+fn topological_dense_layout_is_unchanged() {
+    let layout = layout_of(build_millionaires_layout(8).gates);
+    let canonical = canonicalize_layout(&layout).unwrap();
+    assert_eq!(canonical.layout, layout);
+    assert!(canonical.wire_map.iter().all(|(old, new)| old == new));
+}
+
+#[test]
+fn out_of_order_layout_has_pinned_canonical_form() {
+    // (x AND y) XOR NOT(x), written out of order with sparse wire ids:
+    // inputs x=40, y=10; t=90 (AND), n=70 (NOT), out=55 (XOR).
+    let layout = layout_of(vec![
+        GateDesc::new(GateType::Xor, 90, 70, 55),
+        GateDesc::new(GateType::Not, 40, 3, 70),
+        GateDesc::new(GateType::And, 40, 10, 90),
+    ]);
+    let canonical = canonicalize_layout(&layout).unwrap();
+
+    // Inputs sorted by old id: y(10)->0, x(40)->1. Ready gates by lowest index: NOT, AND, XOR.
+    assert_eq!(
+        canonical.layout.gates,
+        vec![
+            GateDesc::new(GateType::Not, 1, 0, 2),
+            GateDesc::new(GateType::And, 1, 0, 3),
+            GateDesc::new(GateType::Xor, 3, 2, 4),
+        ]
+    );
+    assert_eq!(canonical.map_wire(55), Some(4));
+    assert_eq!(canonical.map_wire(3), None);
+
+    // Idempotent and semantics-preserving.
+    let again = canonicalize_layout(&canonical.layout).unwrap();
+    assert_eq!(again.layout, canonical.layout);
+    for (x, y) in [(0u8, 0u8), (0, 1), (1, 0), (1, 1)] {
+        let values = evaluate_plain_circuit(&canonical.layout.gates, &[y, x]).unwrap();
+        assert_eq!(values[4], Some((x & y) ^ (x ^ 1)));
+    }
+}
+
+#[test]
+fn shuffled_millionaires_layout_keeps_semantics() {
+    let bit_width = 3;
+    let mut gates = build_millionaires_layout(bit_width).gates;
+    let output = gates[gates.len() - 2].wire_c;
+    gates.reverse();
+    let canonical = canonicalize_layout(&layout_of(gates)).unwrap();
+    let output = canonical.map_wire(output).unwrap();
+
+    // Every gate only reads inputs or earlier gate outputs.
+    let inputs = 2 * bit_width as u16;
+    for (idx, gate) in canonical.layout.gates.iter().enumerate() {
+        assert_eq!(gate.wire_c, inputs + idx as u16);
+        assert!(gate.wire_a < gate.wire_c && gate.wire_b < gate.wire_c);
+    }
+    for x in 0..8u64 {
+        for y in 0..8u64 {
+            let mut bits = u64_to_bits_le(x, bit_width);
+            bits.extend(u64_to_bits_le(y, bit_width));
+            let values = evaluate_plain_circuit(&canonical.layout.gates, &bits).unwrap();
+            assert_eq!(values[output as usize], Some(u8::from(x > y)));
+        }
+    }
+}
+
+#[test]
+fn rejects_cycles_and_double_writes() {
+    let cycle = layout_of(vec![
+        GateDesc::new(GateType::And, 0, 3, 2),
+        GateDesc::new(GateType::Xor, 2, 1, 3),
+    ]);
+    assert!(canonicalize_layout(&cycle).unwrap_err().contains("cycle"));
+
+    let double_write = layout_of(vec![
+        GateDesc::new(GateType::And, 0, 1, 2),
+        GateDesc::new(GateType::Xor, 0, 1, 2),
+    ]);
+    assert!(
+        canonicalize_layout(&double_write)
+            .unwrap_err()
+            .contains("written by gates 0 and 1")
+    );
+}
@@ -0,0 +1,150 @@
+//! Versioned CBOR protocol messages: round trips, framing and rejection of foreign input.
+
+use std::io::Cursor;
+
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::evaluation::{NotGateHint, derive_bob_label_offers};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::messages::{
+    BisectionQueryMessage, CheckpointMessage, CommitmentsMessage, DisputePacketMessage,
+    EvalPackageMessage, GateRevealMessage, InstanceCommitment, LabelOfferMessage, MESSAGE_VERSION,
+    Message, read_message, write_message,
+};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{GateDesc, GateType};
+
+mod common;
+
+use common::millionaires_layout;
+
+fn messages() -> Vec<Message> {
+    let layout = millionaires_layout(2, 4);
+    let seed = [0x33u8; 32];
+    let leaves = garble_circuit(seed, &layout);
+    let output_wire = build_millionaires_layout(4).gt_wire;
+    vec![
+        Message::Commitments(CommitmentsMessage {
+            circuit_id: layout.circuit_id,
+            instances: (0..3u8)
+                .map(|idx| InstanceCommitment {
+                    com_seed: [idx; 32],
+                    root_gc: [idx + 10; 32],
+                    blob_hash_gc: [idx + 20; 32],
+                    h_out: [idx + 30; 32],
+                })
+                .collect(),
+        }),
+        Message::EvalPackage(EvalPackageMessage {
+            bit_width: 4,
+            decoding: OutputDecodingTable::derive(seed, &layout, output_wire).expect("decoding"),
+            gc_leaves: leaves.clone(),
+            alice_labels: vec![[0xa1; 16]; 4],
+            not_hints: vec![NotGateHint {
+                gate_index: 5,
+                in_label0: [1; 16],
+                out_if_in0: [2; 16],
+                in_label1: [3; 16],
+                out_if_in1: [4; 16],
+            }],
+            ot_setup: Some([0x02; 33]),
+        }),
+        Message::LabelOffer(LabelOfferMessage {
+            instance_id: 2,
+            first_wire: 4,
+            encrypted: false,
+            rows: derive_bob_label_offers(seed, layout.circuit_id, 2, 4)
+                .into_iter()
+                .map(|(l0, l1)| [l0, l1])
+                .collect(),
+        }),
+        Message::DisputePacket(DisputePacketMessage {
+            circuit_id: layout.circuit_id,
+            instance_id: 2,
+            seed,
+            gate_index: 1,
+            gate: GateDesc::new(GateType::Xor, 0, 4, 9),
+            leaf: leaves[1],
+            root_gc: [0x44; 32],
+            layout_root: [0x55; 32],
+            ih_proof: vec![[0x66; 32]; 3],
+            layout_proof: Vec::new(),
+        }),
+        Message::BisectionQuery(BisectionQueryMessage {
+            instance_id: 2,
+            index: 17,
+            reveal: false,
+        }),
+        Message::Checkpoint(CheckpointMessage {
+            instance_id: 2,
+            index: 17,
+            state: [0x77; 32],
+        }),
+        Message::GateReveal(GateRevealMessage {
+            instance_id: 2,
+            gate_index: 16,
+            leaf: leaves[16],
+            ih_proof: vec![[0x88; 32]; 2],
+        }),
+    ]
+}
+
+#[test]
+fn every_message_kind_round_trips() {
+    for message in messages() {
+        let encoded = message.to_cbor();
+        // array(3), version, kind
+        assert_eq!(
+            &encoded[..3],
+            &[0x83, MESSAGE_VERSION as u8, message.kind() as u8]
+        );
+        assert_eq!(Message::from_cbor(&encoded), Ok(message));
+    }
+}
+
+#[test]
+fn optional_ot_setup_encodes_as_null() {
+    let Message::EvalPackage(mut package) = messages().remove(1) else {
+        unreachable!()
+    };
+    package.ot_setup = None;
+    let message = Message::EvalPackage(package);
+    assert_eq!(Message::from_cbor(&message.to_cbor()), Ok(message));
+}
+
+#[test]
+fn frames_a_stream_of_messages() {
+    let mut wire = Vec::new();
+    for message in messages() {
+        write_message(&mut wire, &message).expect("write");
+    }
+    let mut reader = Cursor::new(wire);
+    for message in messages() {
+        assert_eq!(read_message(&mut reader), Ok(message));
+    }
+    assert!(read_message(&mut reader).is_err());
+}
+
+#[test]
+fn rejects_foreign_encodings() {
+    let encoded = messages().remove(0).to_cbor();
+
+    let mut future = encoded.clone();
+    future[1] = 0x02;
+    let err = Message::from_cbor(&future).unwrap_err();
+    assert!(err.contains("unsupported message version 2"), "{err}");
+
+    let mut unknown = encoded.clone();
+    unknown[2] = 0x09;
+    let err = Message::from_cbor(&unknown).unwrap_err();
+    assert!(err.contains("unknown message kind 9"), "{err}");
+
+    let mut trailing = encoded.clone();
+    trailing.push(0x00);
+    let err = Message::from_cbor(&trailing).unwrap_err();
+    assert!(err.contains("1 trailing bytes"), "{err}");
+
+    assert!(Message::from_cbor(&encoded[..encoded.len() - 1]).is_err());
+    // [1, 1, [h'00', []]]: a 1-byte circuit id.
+    let err = Message::from_cbor(&[0x83, 0x01, 0x01, 0x82, 0x41, 0x00, 0x80]).unwrap_err();
+    assert!(err.contains("expected 32 bytes, got 1"), "{err}");
+}
@@ -0,0 +1,41 @@
+//! Off-chain commit-reveal of Bob's instance choice `m`.
+
+use off_chain_common::consensus::{keccak256, uint256_from_u64};
+use off_chain_common::scenario::{SessionParams, choice_commitment, verify_choice_opening};
+
+const CIRCUIT_ID: [u8; 32] = [0x11u8; 32];
+const SALT: [u8; 32] = [0x22u8; 32];
+
+#[test]
+fn commitment_matches_solidity_packing() {
+    let expected = keccak256(&[b"CHOICE", &CIRCUIT_ID, &uint256_from_u64(3), &SALT]);
+    assert_eq!(choice_commitment(CIRCUIT_ID, 3, SALT), expected);
+    assert_ne!(choice_commitment(CIRCUIT_ID, 4, SALT), expected);
+    assert_ne!(choice_commitment([0x12u8; 32], 3, SALT), expected);
+}
+
+#[test]
+fn accepts_opening_that_matches_onchain_m() {
+    let commitment = choice_commitment(CIRCUIT_ID, 6, SALT);
+    verify_choice_opening(SessionParams::default(), commitment, CIRCUIT_ID, 6, SALT, 6)
+        .expect("valid opening");
+}
+
+#[test]
+fn rejects_wrong_salt_or_m() {
+    let params = SessionParams::default();
+    let commitment = choice_commitment(CIRCUIT_ID, 6, SALT);
+    let err = verify_choice_opening(params, commitment, CIRCUIT_ID, 6, [0x23u8; 32], 6)
+        .expect_err("wrong salt");
+    assert!(err.contains("does not match the commitment"), "{err}");
+    assert!(verify_choice_opening(params, commitment, CIRCUIT_ID, 5, SALT, 5).is_err());
+    assert!(verify_choice_opening(params, commitment, CIRCUIT_ID, 10, SALT, 6).is_err());
+}
+
+#[test]
+fn rejects_choice_that_differs_from_onchain_m() {
+    let commitment = choice_commitment(CIRCUIT_ID, 2, SALT);
+    let err = verify_choice_opening(SessionParams::default(), commitment, CIRCUIT_ID, 2, SALT, 7)
+        .expect_err("adaptive choice");
+    assert!(err.contains("on-chain m=7"), "{err}");
+}
@@ -0,0 +1,76 @@
+//! Circuit registry: named versions resolve to the expected builders and commitments.
+
+use off_chain_common::consensus::{HIGHER_BID_WINS, circuit_id_from_layout, layout_leaf_hash};
+use off_chain_common::evaluation::{evaluate_plain_circuit, u64_to_bits_le};
+use off_chain_common::merkle::merkle_root_from_hashes;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, circuit_versions, lookup_circuit};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::GateSet;
+
+#[test]
+fn default_circuit_is_the_millionaires_layout() {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).unwrap();
+    assert_eq!(circuit.name, "millionaires-yao-v1");
+    assert_eq!(circuit.build(8), build_millionaires_layout(8).gates);
+    assert_eq!(
+        circuit.circuit_id(8, HIGHER_BID_WINS),
+        circuit_id_from_layout(&build_millionaires_layout(8).gates, HIGHER_BID_WINS)
+    );
+
+    let circuit_id = circuit.circuit_id(8, HIGHER_BID_WINS);
+    let hashes = build_millionaires_layout(8)
+        .gates
+        .iter()
+        .enumerate()
+        .map(|(idx, gate)| layout_leaf_hash(circuit_id, idx as u64, *gate))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        circuit.layout_root(circuit_id, 8),
+        merkle_root_from_hashes(&hashes)
+    );
+}
+
+#[test]
+fn registered_versions_decide_two_party_inputs() {
+    let bit_width = 3;
+    for circuit in circuit_versions() {
+        assert_eq!(circuit.params.gate_set, GateSet::V1);
+        let gates = circuit.build(bit_width);
+        let wire = circuit.decision_wire(&gates, bit_width).unwrap();
+        for x in 0..8u64 {
+            for y in 0..8u64 {
+                let mut inputs = u64_to_bits_le(x, bit_width);
+                inputs.extend(u64_to_bits_le(y, bit_width));
+                let values = evaluate_plain_circuit(&gates, &inputs).unwrap();
+                // Millionaires: Alice wins on x > y. Vickrey: index 1 (Bob) wins on y > x.
+                let expected = match circuit.name {
+                    "millionaires-yao-v1" => x > y,
+                    "vickrey-v1" => y > x,
+                    other => panic!("untested circuit {other}"),
+                };
+                assert_eq!(
+                    values[wire as usize],
+                    Some(expected as u8),
+                    "{}",
+                    circuit.name
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_unknown_names_and_bit_widths() {
+    let err = lookup_circuit("millionaires-yao-v0").unwrap_err();
+    assert!(err.contains("vickrey-v1"));
+
+    let vickrey = lookup_circuit("vickrey-v1").unwrap();
+    assert!(vickrey.check_bit_width(0).is_err());
+    assert!(vickrey.check_bit_width(257).is_err());
+    assert_ne!(
+        vickrey.circuit_id(8, HIGHER_BID_WINS),
+        lookup_circuit(DEFAULT_CIRCUIT)
+            .unwrap()
+            .circuit_id(8, HIGHER_BID_WINS)
+    );
+}
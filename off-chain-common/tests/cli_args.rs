@@ -0,0 +1,242 @@
+//! Clap building blocks of the binaries: value parsers, compression flags, the help footer,
+//! shell completions and restoring `--session` flags per subcommand.
+
+use std::fs;
+
+use clap::{Args, FromArgMatches, Parser, Subcommand};
+use clap_complete::Shell;
+use off_chain_common::cli::SessionSeed;
+use off_chain_common::cli_args::{
+    BeaconArgs, CompressionArgs, address_arg, bytes32_arg, bytes32_list_arg, cli_command,
+    completion_script, restore_session_argv, session_params_arg, subcommand_name, zstd_level_arg,
+};
+use off_chain_common::compression::ArtifactCompression;
+use off_chain_common::protocol::Phase;
+use off_chain_common::scenario::SessionParams;
+use off_chain_common::scenario::beacon::{BeaconEvidence, RandomnessSource};
+
+mod common;
+
+use common::temp_path;
+
+#[derive(Debug, Parser)]
+#[command(name = "party")]
+struct TestCli {
+    #[command(subcommand)]
+    command: Option<TestCommand>,
+}
+
+#[derive(Debug, Subcommand)]
+enum TestCommand {
+    Commit(CommitArgs),
+    Reveal(RevealArgs),
+}
+
+#[derive(Debug, Args)]
+struct CommitArgs {
+    #[arg(long = "n", default_value_t = SessionParams::default(), value_parser = session_params_arg)]
+    params: SessionParams,
+    #[arg(long)]
+    m: Option<usize>,
+    #[arg(long, value_parser = bytes32_arg)]
+    seed: Option<[u8; 32]>,
+    #[command(flatten)]
+    compression: CompressionArgs,
+}
+
+#[derive(Debug, Args)]
+struct RevealArgs {
+    #[arg(long, value_parser = bytes32_arg)]
+    seed: Option<[u8; 32]>,
+    #[command(flatten)]
+    beacon: BeaconArgs,
+}
+
+const PHASES: &[(Phase, &[&str])] = &[(Phase::Commit, &["commit", "reveal"])];
+
+const SEEDS: &[SessionSeed] = &[SessionSeed {
+    commands: &["commit"],
+    flag: "--seed",
+    name: "seed",
+    generate: true,
+}];
+
+fn argv(items: &[&str]) -> Vec<String> {
+    std::iter::once("party")
+        .chain(items.iter().copied())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses `args` (program name first) with the global `--session` flag added.
+fn parse(args: &[String]) -> Result<TestCli, clap::Error> {
+    let matches = cli_command::<TestCli>(PHASES, "commit").try_get_matches_from(args)?;
+    TestCli::from_arg_matches(&matches)
+}
+
+fn commit_args(args: &[String]) -> Result<CommitArgs, clap::Error> {
+    match parse(args)?.command {
+        Some(TestCommand::Commit(args)) => Ok(args),
+        other => panic!("parsed {other:?}"),
+    }
+}
+
+fn commit(items: &[&str]) -> Result<CommitArgs, clap::Error> {
+    let mut full = vec!["commit"];
+    full.extend_from_slice(items);
+    commit_args(&argv(&full))
+}
+
+#[test]
+fn value_parsers_accept_valid_and_reject_invalid_values() {
+    assert_eq!(commit(&[]).unwrap().params.n, 10);
+    assert_eq!(commit(&["--n", "16"]).unwrap().params.n, 16);
+    assert!(commit(&["--n=1"]).is_err());
+    assert!(commit(&["--n", "ten"]).is_err());
+
+    let hex = format!("0x{}", "11".repeat(32));
+    assert_eq!(bytes32_arg(&hex), Ok([0x11; 32]));
+    assert!(bytes32_arg("0x11").is_err());
+    assert_eq!(
+        address_arg(&format!("0x{}", "22".repeat(20))),
+        Ok([0x22; 20])
+    );
+    assert_eq!(
+        bytes32_list_arg(&format!("[{hex},{hex}]")),
+        Ok(vec![[0x11; 32]; 2])
+    );
+    assert_eq!(zstd_level_arg("22"), Ok(22));
+    assert!(zstd_level_arg("0").is_err());
+}
+
+#[test]
+fn compression_flags_select_zstd() {
+    let compression = |items: &[&str]| commit(items).unwrap().compression.compression();
+    assert_eq!(compression(&[]), ArtifactCompression::None);
+    assert_eq!(
+        compression(&["--compress"]),
+        ArtifactCompression::zstd(3).unwrap()
+    );
+    assert_eq!(
+        compression(&["--zstd-level", "9"]),
+        ArtifactCompression::zstd(9).unwrap()
+    );
+    assert!(commit(&["--zstd-level", "23"]).is_err());
+}
+
+#[test]
+fn beacon_flags_pair_each_source_with_its_evidence() {
+    let beacon = |items: &[&str]| -> Result<BeaconArgs, clap::Error> {
+        let mut full = vec!["reveal"];
+        full.extend_from_slice(items);
+        match parse(&argv(&full))?.command {
+            Some(TestCommand::Reveal(args)) => Ok(args.beacon),
+            other => panic!("parsed {other:?}"),
+        }
+    };
+    let key = format!("0x02{}", "11".repeat(32));
+    let proof = format!("0x{}", "22".repeat(81));
+
+    // A published VRF proof needs no node; a key alone leaves the proof to its holder.
+    let vrf = beacon(&["--vrf-key", &key, "--vrf-proof", &proof]).unwrap();
+    assert_eq!(
+        vrf.evidence("http://unused").unwrap(),
+        Some((
+            RandomnessSource::Vrf {
+                public_key: vrf.vrf_key.unwrap()
+            },
+            BeaconEvidence::Vrf { proof: [0x22; 81] }
+        ))
+    );
+    let key_only = beacon(&["--vrf-key", &key]).unwrap();
+    assert_eq!(key_only.evidence("http://unused").unwrap(), None);
+
+    let block_hash = format!("0x{}", "33".repeat(32));
+    assert!(beacon(&["--block-hash", &block_hash]).is_err());
+    assert!(beacon(&["--vrf-proof", &proof]).is_err());
+    assert!(beacon(&["--block-height", "5", "--vrf-key", &key]).is_err());
+    assert!(beacon(&["--block-height", "5", "--block-hash", &block_hash]).is_ok());
+}
+
+#[test]
+fn session_restores_only_flags_the_subcommand_accepts() {
+    let dir = temp_path("cli-args-session");
+    let session = dir.to_str().unwrap();
+    let command = cli_command::<TestCli>(PHASES, "commit");
+    let restore = |items: &[&str]| {
+        restore_session_argv(&command, &argv(items), "commit", &["--n", "--m"], SEEDS).unwrap()
+    };
+
+    let (name, args, store) = restore(&["--session", session, "commit", "--n", "16"]);
+    assert_eq!(name, "commit");
+    assert!(store.is_some());
+    let seed = args
+        .iter()
+        .find_map(|arg| arg.strip_prefix("--seed="))
+        .expect("generated seed")
+        .to_string();
+
+    // `reveal` takes no --n, and its --seed is not one the session keeps.
+    let (name, args, _) = restore(&["reveal", "--session", session]);
+    assert_eq!(name, "reveal");
+    assert_eq!(args, argv(&["reveal", "--session", session]));
+    assert!(parse(&args).is_ok());
+
+    let (_, args, _) = restore(&["commit", "--session", session, "--m", "3"]);
+    let parsed = commit_args(&args).unwrap();
+    assert_eq!(parsed.params.n, 16);
+    assert_eq!(parsed.m, Some(3));
+    assert_eq!(parsed.seed, Some(bytes32_arg(&seed).unwrap()));
+
+    // Unknown subcommands are left for clap to reject.
+    let (name, args, store) = restore(&["--session", session, "comit"]);
+    assert_eq!(name, "comit");
+    assert_eq!(args, argv(&["--session", session, "comit"]));
+    assert!(store.is_none());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn help_lists_the_phases_and_the_default_command() {
+    assert_eq!(
+        subcommand_name(&argv(&["--session", "s", "reveal"]), "x"),
+        "reveal"
+    );
+    assert_eq!(
+        subcommand_name(&argv(&["--session", "s"]), "commit"),
+        "commit"
+    );
+    assert_eq!(
+        subcommand_name(&argv(&["--profile", "sepolia", "reveal"]), "x"),
+        "reveal"
+    );
+
+    let mut command = cli_command::<TestCli>(PHASES, "commit");
+    command.build();
+    let help = command.render_long_help().to_string();
+    assert!(help.contains("--session <DIR>"), "{help}");
+    assert!(
+        help.contains("Protocol phases:\n  commit: commit, reveal"),
+        "{help}"
+    );
+    assert!(
+        help.contains("Default command with no args: commit"),
+        "{help}"
+    );
+    let reveal = command.find_subcommand("reveal").expect("reveal");
+    assert!(reveal.get_arguments().any(|arg| arg.get_id() == "session"));
+}
+
+#[test]
+fn completions_cover_the_subcommands_and_long_flags() {
+    let script = |shell: Shell| completion_script(cli_command::<TestCli>(PHASES, "commit"), shell);
+    let bash = script(Shell::Bash);
+    assert!(bash.contains("complete -F _party"), "{bash}");
+    for word in ["commit", "reveal", "--zstd-level", "--seed", "--session"] {
+        assert!(bash.contains(word), "bash script lacks {word}");
+    }
+    let fish = script(Shell::Fish);
+    assert!(fish.contains("complete -c party"), "{fish}");
+    assert!(fish.contains("-l compress"), "{fish}");
+    assert!(script(Shell::Zsh).starts_with("#compdef party"));
+}
@@ -0,0 +1,103 @@
+//! Alice's `verify-commitments` preflight: submitted values against re-derived instances.
+
+use off_chain_common::cli::hex32;
+use off_chain_common::commitment_check::{
+    CommitmentField, CommitmentMismatch, DerivedCommitments, check_commitments, drift_hint,
+};
+
+fn derived() -> DerivedCommitments {
+    DerivedCommitments {
+        com_seeds: vec![[0x01; 32], [0x02; 32], [0x03; 32]],
+        root_gcs: vec![[0x11; 32], [0x12; 32], [0x13; 32]],
+        blob_hashes: vec![[0x21; 32], [0x22; 32], [0x23; 32]],
+        h0: vec![[0x31; 32], [0x32; 32], [0x33; 32]],
+        h1: vec![[0x41; 32], [0x42; 32], [0x43; 32]],
+    }
+}
+
+fn mismatch(field: CommitmentField, instance_id: usize, found: [u8; 32]) -> CommitmentMismatch {
+    CommitmentMismatch {
+        source: "contract".to_string(),
+        field,
+        instance_id,
+        expected: derived().values(field)[instance_id],
+        found,
+    }
+}
+
+#[test]
+fn matching_values_pass() {
+    let derived = derived();
+    let lists = [
+        CommitmentField::ComSeed,
+        CommitmentField::RootGc,
+        CommitmentField::BlobHashGc,
+        CommitmentField::H0,
+        CommitmentField::H1,
+    ]
+    .map(|field| (field, derived.values(field).to_vec()));
+    assert_eq!(check_commitments(&derived, "contract", &lists), Ok(vec![]));
+    assert_eq!(check_commitments(&derived, "contract", &[]), Ok(vec![]));
+    assert_eq!(drift_hint(&[]), None);
+}
+
+#[test]
+fn differing_values_are_listed_per_instance() {
+    let mut root_gcs = derived().root_gcs;
+    root_gcs[2] = [0xee; 32];
+    let mut h1 = derived().h1;
+    h1[0] = [0xff; 32];
+    let mismatches = check_commitments(
+        &derived(),
+        "contract",
+        &[
+            (CommitmentField::RootGc, root_gcs),
+            (CommitmentField::H1, h1),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        mismatches,
+        vec![
+            mismatch(CommitmentField::RootGc, 2, [0xee; 32]),
+            mismatch(CommitmentField::H1, 0, [0xff; 32]),
+        ]
+    );
+    assert_eq!(
+        mismatches[0].to_string(),
+        format!(
+            "contract: instance 2 rootGC is {}, re-derived {}",
+            hex32([0xee; 32]),
+            hex32([0x13; 32])
+        )
+    );
+}
+
+#[test]
+fn lists_for_another_instance_count_are_rejected() {
+    let err = check_commitments(
+        &derived(),
+        "--com-seeds",
+        &[(CommitmentField::ComSeed, vec![[0x01; 32]; 2])],
+    )
+    .unwrap_err();
+    assert_eq!(err, "--com-seeds lists 2 comSeed values, expected 3");
+}
+
+#[test]
+fn hints_name_the_parameters_that_drifted() {
+    let seeds = [
+        mismatch(CommitmentField::RootGc, 0, [0; 32]),
+        mismatch(CommitmentField::ComSeed, 0, [0; 32]),
+    ];
+    assert!(drift_hint(&seeds).unwrap().contains("--master-seed"));
+
+    let circuits = [
+        mismatch(CommitmentField::BlobHashGc, 1, [0; 32]),
+        mismatch(CommitmentField::RootGc, 1, [0; 32]),
+    ];
+    assert!(drift_hint(&circuits).unwrap().contains("--bit-width"));
+
+    let blobs = [mismatch(CommitmentField::H0, 1, [0; 32])];
+    assert!(drift_hint(&blobs).unwrap().starts_with("eval blobs differ"));
+}
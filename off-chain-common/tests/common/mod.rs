@@ -0,0 +1,47 @@
+//! Helpers shared by the integration tests (`mod common;`).
+
+#![allow(dead_code)]
+
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::CircuitLayout;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Fresh path under the system temp dir that does not exist yet. Unique per process (pid)
+/// and per call (counter), so parallel tests never share a directory.
+pub fn temp_path(prefix: &str) -> PathBuf {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let path = env::temp_dir().join(format!("{prefix}-{}-{id}", process::id()));
+    // A reused pid may have left a directory behind.
+    let _ = fs::remove_dir_all(&path);
+    path
+}
+
+/// Like `temp_path`, but creates the directory.
+pub fn temp_dir(prefix: &str) -> PathBuf {
+    let dir = temp_path(prefix);
+    fs::create_dir_all(&dir).expect("temp dir");
+    dir
+}
+
+/// Circuit id the test layouts are garbled under.
+pub fn millionaires_circuit_id() -> [u8; 32] {
+    keccak256(&[b"millionaires-yao-v1"])
+}
+
+/// Millionaires layout of `bit_width` bits for `instance_id` under
+/// `millionaires_circuit_id`.
+pub fn millionaires_layout(instance_id: u64, bit_width: usize) -> CircuitLayout {
+    CircuitLayout::new(
+        millionaires_circuit_id(),
+        instance_id,
+        build_millionaires_layout(bit_width).gates,
+    )
+}
@@ -0,0 +1,118 @@
+//! Compressed artifacts: zstd files next to the plain path, transparent reads and the
+//! `--compress` flags.
+
+use std::fs;
+
+use off_chain_common::cli::{hex_prefixed, parse_artifact_compression};
+use off_chain_common::compression::{
+    ArtifactCompression, DEFAULT_ZSTD_LEVEL, compress, compressed_path, find_artifact, is_zstd,
+    read_artifact, read_artifact_to_string, write_artifact,
+};
+use off_chain_common::garble::garble_all_instances;
+use off_chain_common::scenario::build_millionaires_layout;
+
+mod common;
+
+use common::temp_dir;
+
+fn leaves_text() -> String {
+    let gates = build_millionaires_layout(16).gates;
+    let instance = garble_all_instances([0x24; 32], [0xab; 32], &gates, 1).remove(0);
+    instance
+        .leaves
+        .iter()
+        .map(|leaf| format!("{}\n", hex_prefixed(leaf)))
+        .collect()
+}
+
+fn args(raw: &[&str]) -> Vec<String> {
+    raw.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn compressed_artifacts_read_back_through_the_plain_path() {
+    let dir = temp_dir("compression-read");
+    let path = dir.join("gc-m-leaves.txt");
+    let leaves = leaves_text();
+
+    let written = write_artifact(
+        &path,
+        leaves.as_bytes(),
+        ArtifactCompression::zstd(19).unwrap(),
+    )
+    .expect("write");
+    assert_eq!(written, compressed_path(&path));
+    assert!(!path.exists());
+    let raw = fs::read(&written).expect("raw");
+    assert!(is_zstd(&raw));
+    // Hex text halves at least: every byte is spelled with two digits.
+    assert!(
+        raw.len() * 2 < leaves.len(),
+        "{} vs {}",
+        raw.len(),
+        leaves.len()
+    );
+
+    assert_eq!(find_artifact(&path), Some(written.clone()));
+    assert_eq!(read_artifact_to_string(&path), Ok(leaves.clone()));
+    assert_eq!(read_artifact(&written), Ok(leaves.into_bytes()));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rewriting_an_artifact_drops_the_other_variant() {
+    let dir = temp_dir("compression-stale");
+    let path = dir.join("eval-m-blob.bin");
+    let zstd = ArtifactCompression::zstd(DEFAULT_ZSTD_LEVEL).unwrap();
+
+    write_artifact(&path, b"old", zstd).expect("compressed");
+    write_artifact(&path, b"new", ArtifactCompression::None).expect("plain");
+    assert!(!compressed_path(&path).exists());
+    assert_eq!(read_artifact(&path), Ok(b"new".to_vec()));
+
+    write_artifact(&path, b"newer", zstd).expect("compressed again");
+    assert!(!path.exists());
+    assert_eq!(read_artifact(&path), Ok(b"newer".to_vec()));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn reports_missing_and_corrupt_artifacts() {
+    let dir = temp_dir("compression-errors");
+    let path = dir.join("not-hints.txt");
+    assert_eq!(find_artifact(&path), None);
+    let err = read_artifact(&path).unwrap_err();
+    assert!(err.starts_with("failed to read"), "{err}");
+
+    let mut truncated = compress(b"0x01,0x02\n", DEFAULT_ZSTD_LEVEL).expect("compress");
+    truncated.truncate(truncated.len() - 4);
+    fs::write(compressed_path(&path), truncated).expect("corrupt");
+    let err = read_artifact(&path).unwrap_err();
+    assert!(err.contains("zstd decompression failed"), "{err}");
+    assert!(err.contains("not-hints.txt.zst"), "{err}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn compress_flags_select_the_zstd_level() {
+    assert_eq!(
+        parse_artifact_compression(&args(&["--out-dir", "x"])).unwrap(),
+        ArtifactCompression::None
+    );
+    assert_eq!(
+        parse_artifact_compression(&args(&["--compress"])).unwrap(),
+        ArtifactCompression::Zstd {
+            level: DEFAULT_ZSTD_LEVEL
+        }
+    );
+    assert_eq!(
+        parse_artifact_compression(&args(&["--zstd-level", "19"])).unwrap(),
+        ArtifactCompression::Zstd { level: 19 }
+    );
+    for level in ["0", "23", "99999999999"] {
+        let err = parse_artifact_compression(&args(&["--compress", "--zstd-level", level]))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("zstd level must be within 1..=22"), "{err}");
+    }
+}
@@ -0,0 +1,64 @@
+//! Hash backends: the keccak backend stays byte-identical to the consensus helpers, other
+//! backends garble independent experimental variants.
+
+use off_chain_common::consensus::{
+    ConsensusHasher, Keccak, Sha256Hasher, compute_row_key, derive_wire_flip_bit,
+    derive_wire_label, expand_pad, keccak256,
+};
+use off_chain_common::garble::{garble_circuit, garble_circuit_with_hasher};
+use off_chain_common::types::GarblingScheme;
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x31u8; 32];
+
+#[test]
+fn keccak_backend_matches_consensus_helpers() {
+    let circuit_id = millionaires_layout(4, 4).circuit_id;
+    assert_eq!(Keccak.hash(&[b"ab", b"c"]), keccak256(&[b"abc"]));
+    for wire in 0..8u16 {
+        assert_eq!(
+            Keccak.wire_flip_bit(circuit_id, 4, wire, SEED),
+            derive_wire_flip_bit(circuit_id, 4, wire, SEED)
+        );
+        assert_eq!(
+            Keccak.wire_label(circuit_id, 4, wire, 1, SEED),
+            derive_wire_label(circuit_id, 4, wire, 1, SEED)
+        );
+    }
+    let row_key = Keccak.row_key(circuit_id, 4, 7, [1, 0], [[1u8; 16], [2u8; 16]]);
+    assert_eq!(
+        row_key,
+        compute_row_key(circuit_id, 4, 7, 1, 0, [1u8; 16], [2u8; 16])
+    );
+    assert_eq!(Keccak.pad(row_key), expand_pad(row_key));
+}
+
+#[test]
+fn keccak_backend_garbles_consensus_leaves() {
+    let leaves =
+        garble_circuit_with_hasher(&Keccak, SEED, &millionaires_layout(4, 4)).expect("garble");
+    assert_eq!(leaves, garble_circuit(SEED, &millionaires_layout(4, 4)));
+}
+
+#[test]
+fn sha256_backend_keeps_headers_and_changes_rows() {
+    let keccak = garble_circuit(SEED, &millionaires_layout(4, 4));
+    let sha = garble_circuit_with_hasher(&Sha256Hasher, SEED, &millionaires_layout(4, 4))
+        .expect("garble");
+    assert_eq!(Sha256Hasher.name(), "sha256");
+    assert_eq!(sha.len(), keccak.len());
+    for (sha, keccak) in sha.iter().zip(&keccak) {
+        assert_eq!(sha[..7], keccak[..7]);
+    }
+    assert_ne!(sha, keccak);
+}
+
+#[test]
+fn rejects_non_classic_layouts() {
+    let free_xor = millionaires_layout(4, 4).with_scheme(GarblingScheme::FreeXor);
+    let err = garble_circuit_with_hasher(&Sha256Hasher, SEED, &free_xor).unwrap_err();
+    assert!(err.contains("sha256 backend supports classic v1"), "{err}");
+}
@@ -0,0 +1,119 @@
+//! Constant wires: public labels, garbling and evaluation.
+
+use off_chain_common::builder::CircuitBuilder;
+use off_chain_common::consensus::{derive_constant_label, derive_wire_label, keccak256};
+use off_chain_common::evaluation::{
+    derive_not_gate_hints, evaluate_garbled_wires, evaluate_plain_layout, u64_to_bits_le,
+};
+use off_chain_common::garble::{derive_layout_wire_label, garble_circuit};
+use off_chain_common::labels::get_permutation_bit;
+use off_chain_common::types::{CircuitLayout, ConstantWire, GateDesc, GateType};
+
+/// Compares a 3-bit `x` against a 4-bit `y` by padding `x` with a constant 0 MSB.
+/// Also exposes `NOT(1)` to cover NOT gates reading a constant.
+fn build_padded_compare() -> (CircuitLayout, u16, u16) {
+    let mut builder = CircuitBuilder::new().with_circuit_id(keccak256(&[b"padded-compare"]));
+    let mut x_bits = builder.inputs(3);
+    let y_bits = builder.inputs(4);
+    x_bits.push(builder.constant(false));
+    let one = builder.constant(true);
+    let (gt, _) = builder.compare(&x_bits, &y_bits);
+    let not_one = builder.not(one);
+    (builder.finish(), gt, not_one)
+}
+
+#[test]
+fn constant_label_is_public_and_carries_its_bit() {
+    let circuit_id = [0x33u8; 32];
+    for value in 0..=1u8 {
+        let label = derive_constant_label(circuit_id, 2, 9, value);
+        assert_eq!(get_permutation_bit(label), value);
+        let layout = CircuitLayout::new(circuit_id, 2, Vec::new())
+            .with_constant_wires(vec![ConstantWire { wire: 9, value }]);
+        // The active label does not depend on the garbler seed.
+        assert_eq!(
+            derive_layout_wire_label([1u8; 32], &layout, 9, value),
+            label
+        );
+        assert_eq!(
+            derive_layout_wire_label([2u8; 32], &layout, 9, value),
+            label
+        );
+    }
+    assert_ne!(
+        derive_constant_label(circuit_id, 2, 9, 0),
+        derive_constant_label(circuit_id, 3, 9, 0)
+    );
+}
+
+#[test]
+fn padded_compare_evaluates_in_plain_and_garbled_form() {
+    let (layout, gt, not_one) = build_padded_compare();
+    assert_eq!(layout.constant_wires.len(), 2);
+    let seed = keccak256(&[b"padded-compare-seed"]);
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+
+    for x in 0..8u64 {
+        for y in 0..16u64 {
+            let mut inputs = u64_to_bits_le(x, 3);
+            inputs.extend(u64_to_bits_le(y, 4));
+            let values = evaluate_plain_layout(&layout, &inputs).unwrap();
+            assert_eq!(values[gt as usize], Some((x > y) as u8));
+            assert_eq!(values[not_one as usize], Some(0));
+
+            // Only real inputs get labels; constants are derived by the evaluator.
+            let input_labels: Vec<(u16, [u8; 16])> = inputs
+                .iter()
+                .enumerate()
+                .map(|(wire, bit)| {
+                    let wire = wire as u16;
+                    (
+                        wire,
+                        derive_wire_label(layout.circuit_id, 0, wire, *bit, seed),
+                    )
+                })
+                .collect();
+            let labels = evaluate_garbled_wires(&layout, &leaves, &input_labels, &hints).unwrap();
+            let expected_gt = derive_wire_label(layout.circuit_id, 0, gt, (x > y) as u8, seed);
+            assert_eq!(labels[gt as usize], Some(expected_gt), "x={x} y={y}");
+        }
+    }
+}
+
+#[test]
+fn layouts_without_constants_garble_unchanged() {
+    let (layout, _, _) = build_padded_compare();
+    let plain = CircuitLayout::new(layout.circuit_id, 0, layout.gates.clone());
+    let seed = [0x44u8; 32];
+    let with_constants = garble_circuit(seed, &layout);
+    let without = garble_circuit(seed, &plain);
+    // Only gates reading a constant wire change.
+    for (idx, gate) in layout.gates.iter().enumerate() {
+        let reads_constant = [gate.wire_a, gate.wire_b]
+            .iter()
+            .any(|wire| layout.constant_value(*wire).is_some());
+        if !reads_constant || gate.gate_type == GateType::Not {
+            assert_eq!(with_constants[idx], without[idx], "gate {idx}");
+        }
+    }
+}
+
+#[test]
+fn rejects_invalid_constant_wires() {
+    let gates = vec![GateDesc::new(GateType::And, 0, 1, 2)];
+    let written = CircuitLayout::new([0u8; 32], 0, gates.clone())
+        .with_constant_wires(vec![ConstantWire { wire: 2, value: 1 }]);
+    assert!(written.check_constant_wires().is_err());
+    assert!(evaluate_plain_layout(&written, &[1, 1]).is_err());
+
+    let twice = CircuitLayout::new([0u8; 32], 0, gates.clone()).with_constant_wires(vec![
+        ConstantWire { wire: 1, value: 1 },
+        ConstantWire { wire: 1, value: 0 },
+    ]);
+    assert!(twice.check_constant_wires().is_err());
+
+    let non_bit = CircuitLayout::new([0u8; 32], 0, gates)
+        .with_constant_wires(vec![ConstantWire { wire: 1, value: 2 }]);
+    assert!(non_bit.check_constant_wires().is_err());
+}
@@ -0,0 +1,151 @@
+//! `status` snapshot: the contract reads rendered as `key=value` lines.
+
+use off_chain_common::cli::hex32;
+use off_chain_common::contract_status::{
+    BuyerState, ContractStatus, InstanceCommitment, status_lines,
+};
+use off_chain_common::output::command_output_json;
+use off_chain_common::timeouts::Deadlines;
+use serde_json::json;
+
+const ALICE: &str = "0x00000000000000000000000000000000000000a1";
+const BUYER: &str = "0x00000000000000000000000000000000000000b1";
+const RECEIVER: &str = "0x00000000000000000000000000000000000000c1";
+
+fn wei(amount: u64) -> [u8; 32] {
+    let mut value = [0u8; 32];
+    value[24..].copy_from_slice(&amount.to_be_bytes());
+    value
+}
+
+/// A contract in `BuyerSeedCommit`: deposits made, nothing committed yet.
+fn early_status() -> ContractStatus {
+    ContractStatus {
+        contract_address: "0x00000000000000000000000000000000000000ff".to_string(),
+        stage: 1,
+        now: 1_000,
+        deadlines: Deadlines::from_fields(&[900, 1_600, 0, 0, 0, 0, 0, 0]).expect("deadlines"),
+        alice: ALICE.to_string(),
+        alice_vault_wei: wei(1_200),
+        buyers: vec![BuyerState {
+            address: BUYER.to_string(),
+            receiver: RECEIVER.to_string(),
+            status: 0,
+            vault_wei: wei(1_200),
+        }],
+        circuit_id: [0x11; 32],
+        bit_width: 8,
+        verifier_seed_finalized: false,
+        m: 0,
+        opened: vec![],
+        commitments: vec![InstanceCommitment::default(); 3],
+    }
+}
+
+/// The same contract in `Dispute`, after commitments and openings.
+fn dispute_status() -> ContractStatus {
+    let commitment = |byte: u8| InstanceCommitment {
+        com_seed: [byte; 32],
+        root_gc: [byte + 1; 32],
+        blob_hash_gc: [byte + 2; 32],
+        h_out: [byte + 3; 32],
+    };
+    ContractStatus {
+        stage: 7,
+        deadlines: Deadlines::from_fields(&[900, 1_600, 2_000, 2_400, 2_800, 3_200, 0, 0])
+            .expect("deadlines"),
+        now: 3_000,
+        verifier_seed_finalized: true,
+        m: 1,
+        opened: vec![0, 2],
+        commitments: vec![commitment(0x10), commitment(0x20), commitment(0x30)],
+        ..early_status()
+    }
+}
+
+#[test]
+fn early_stage_shows_parties_and_the_running_deadline() {
+    let lines = status_lines(&early_status()).unwrap();
+    for expected in [
+        "stage=1",
+        "stage_name=BuyerSeedCommit",
+        "stage_deadline=1600",
+        "stage_seconds_left=600",
+        "deadline_verifierSeed=1600",
+        "alice_vault_wei=1200",
+        "buyer_count=1",
+        "verifier_seed_finalized=false",
+        "commitments_submitted=false",
+    ] {
+        assert!(
+            lines.iter().any(|line| line == expected),
+            "{expected}: {lines:?}"
+        );
+    }
+    assert!(lines.contains(&format!(
+        "buyer={BUYER} status=Pending receiver={RECEIVER} vault_wei=1200"
+    )));
+    assert!(!lines.iter().any(|line| line.starts_with("m=")));
+    assert!(!lines.iter().any(|line| line.starts_with("commitment=")));
+}
+
+#[test]
+fn finalized_seed_shows_m_openings_and_commitments() {
+    let lines = status_lines(&dispute_status()).unwrap();
+    assert!(lines.contains(&"stage_name=Dispute".to_string()));
+    assert!(lines.contains(&"stage_seconds_left=200".to_string()));
+    assert!(lines.contains(&"m=1".to_string()));
+    assert!(lines.contains(&"opened=[0,2]".to_string()));
+    let commitments = lines
+        .iter()
+        .filter(|line| line.starts_with("commitment="))
+        .collect::<Vec<_>>();
+    assert_eq!(commitments.len(), 3);
+    assert_eq!(
+        commitments[1],
+        &format!(
+            "commitment=1 com_seed={} root_gc={} blob_hash_gc={} h_out={}",
+            hex32([0x20; 32]),
+            hex32([0x21; 32]),
+            hex32([0x22; 32]),
+            hex32([0x23; 32])
+        )
+    );
+}
+
+#[test]
+fn closed_contracts_have_no_running_deadline() {
+    let status = ContractStatus {
+        stage: 11,
+        ..dispute_status()
+    };
+    let lines = status_lines(&status).unwrap();
+    assert!(lines.contains(&"stage_name=Closed".to_string()));
+    assert!(!lines.iter().any(|line| line.starts_with("stage_deadline=")));
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.starts_with("deadline_"))
+            .count(),
+        8
+    );
+
+    let mut unknown = early_status();
+    unknown.buyers[0].status = 3;
+    assert!(
+        status_lines(&unknown)
+            .unwrap_err()
+            .contains("buyer status 3")
+    );
+}
+
+#[test]
+fn json_output_lists_opened_instances_and_buyers() {
+    let lines = status_lines(&dispute_status()).unwrap();
+    let json = command_output_json("status", &lines, None);
+    assert_eq!(json["status"], "ok");
+    assert_eq!(json["stage_name"], "Dispute");
+    assert_eq!(json["opened"], json!(["0", "2"]));
+    assert_eq!(json["verifier_seed_finalized"], true);
+    assert_eq!(json["commitment"].as_array().map(Vec::len), Some(3));
+}
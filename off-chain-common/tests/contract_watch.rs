@@ -0,0 +1,166 @@
+//! `watch` events: what changed between two contract snapshots, and deadline warnings that
+//! fire once per deadline.
+
+use off_chain_common::contract_status::{
+    BuyerState, ContractEvent, ContractStatus, ContractWatch, InstanceCommitment,
+};
+use off_chain_common::timeouts::Deadlines;
+
+const ALICE: &str = "0x00000000000000000000000000000000000000a1";
+const BUYER: &str = "0x00000000000000000000000000000000000000b1";
+const LATE_BUYER: &str = "0x00000000000000000000000000000000000000b2";
+
+fn wei(amount: u64) -> [u8; 32] {
+    let mut value = [0u8; 32];
+    value[24..].copy_from_slice(&amount.to_be_bytes());
+    value
+}
+
+fn buyer(address: &str, status: u8, vault: u64) -> BuyerState {
+    BuyerState {
+        address: address.to_string(),
+        receiver: address.to_string(),
+        status,
+        vault_wei: wei(vault),
+    }
+}
+
+/// A contract in `Deposits` with Alice's deposit made and one buyer registered.
+fn deposits() -> ContractStatus {
+    ContractStatus {
+        contract_address: "0x00000000000000000000000000000000000000ff".to_string(),
+        stage: 0,
+        now: 1_000,
+        deadlines: Deadlines::from_fields(&[2_000, 0, 0, 0, 0, 0, 0, 0]).expect("deadlines"),
+        alice: ALICE.to_string(),
+        alice_vault_wei: wei(1_200),
+        buyers: vec![buyer(BUYER, 0, 0)],
+        circuit_id: [0x11; 32],
+        bit_width: 8,
+        verifier_seed_finalized: false,
+        m: 0,
+        opened: vec![],
+        commitments: vec![InstanceCommitment::default(); 3],
+    }
+}
+
+fn lines(events: &[ContractEvent]) -> Vec<String> {
+    events
+        .iter()
+        .map(|event| event.line().expect("event line"))
+        .collect()
+}
+
+#[test]
+fn first_poll_reports_the_current_stage_only() {
+    let mut watch = ContractWatch::new(600);
+    let events = watch.poll(deposits());
+    assert_eq!(
+        events,
+        vec![ContractEvent::Stage {
+            previous: None,
+            stage: 0
+        }]
+    );
+    assert_eq!(
+        lines(&events),
+        vec!["event=stage stage=0 stage_name=Deposits"]
+    );
+    assert!(watch.poll(deposits()).is_empty());
+    assert!(!watch.is_closed());
+}
+
+#[test]
+fn parties_actions_are_reported_in_order() {
+    let mut watch = ContractWatch::new(0);
+    watch.poll(deposits());
+
+    let mut next = deposits();
+    next.stage = 1;
+    next.buyers = vec![buyer(BUYER, 1, 1_200), buyer(LATE_BUYER, 0, 500)];
+    assert_eq!(
+        lines(&watch.poll(next)),
+        vec![
+            "event=stage stage=1 stage_name=BuyerSeedCommit previous=Deposits".to_string(),
+            format!("event=buyer_status buyer={BUYER} status=Ready"),
+            format!("event=vault party={BUYER} previous_wei=0 vault_wei=1200"),
+            format!("event=buyer_joined buyer={LATE_BUYER}"),
+            format!("event=buyer_status buyer={LATE_BUYER} status=Pending"),
+            format!("event=vault party={LATE_BUYER} previous_wei=0 vault_wei=500"),
+        ]
+    );
+}
+
+#[test]
+fn commitments_and_openings_are_reported_once() {
+    let mut watch = ContractWatch::new(0);
+    let mut status = deposits();
+    status.stage = 2;
+    watch.poll(status.clone());
+
+    status.commitments[1].root_gc = [0x22; 32];
+    assert_eq!(
+        watch.poll(status.clone()),
+        vec![ContractEvent::CommitmentsSubmitted]
+    );
+    status.commitments[0].root_gc = [0x33; 32];
+    assert!(watch.poll(status.clone()).is_empty());
+
+    status.verifier_seed_finalized = true;
+    status.m = 1;
+    status.opened = vec![0, 2];
+    assert_eq!(
+        lines(&watch.poll(status)),
+        vec!["event=verifier_seed_finalized m=1 opened=[0,2]"]
+    );
+}
+
+#[test]
+fn each_deadline_warns_once_before_and_once_after_it_passes() {
+    let mut watch = ContractWatch::new(600);
+    let at = |now: u64| ContractStatus { now, ..deposits() };
+    assert_eq!(watch.poll(at(1_000)).len(), 1);
+    assert!(watch.poll(at(1_399)).is_empty());
+    assert_eq!(
+        lines(&watch.poll(at(1_400))),
+        vec!["event=deadline_approaching stage_name=Deposits deadline=2000 seconds_left=600"]
+    );
+    assert!(watch.poll(at(2_000)).is_empty());
+    assert_eq!(
+        watch.poll(at(2_001)),
+        vec![ContractEvent::DeadlinePassed {
+            stage: 0,
+            deadline: 2_000
+        }]
+    );
+    assert!(watch.poll(at(2_500)).is_empty());
+
+    // A stage whose deadline slot is not set yet has nothing to warn about.
+    let mut unset = at(2_500);
+    unset.stage = 2;
+    assert_eq!(watch.poll(unset).len(), 1);
+}
+
+#[test]
+fn slashing_and_closing_are_reported() {
+    let mut watch = ContractWatch::new(0);
+    let mut status = deposits();
+    status.stage = 7;
+    status.deadlines = Deadlines::from_fields(&[0, 0, 0, 0, 0, 5_000, 0, 0]).expect("deadlines");
+    watch.poll(status.clone());
+
+    status.stage = 11;
+    status.alice_vault_wei = wei(0);
+    status.buyers[0].vault_wei = wei(1_200);
+    let events = watch.poll(status);
+    assert_eq!(
+        lines(&events),
+        vec![
+            "event=stage stage=11 stage_name=Closed previous=Dispute".to_string(),
+            format!("event=vault party={ALICE} previous_wei=1200 vault_wei=0"),
+            format!("event=vault party={BUYER} previous_wei=0 vault_wei=1200"),
+        ]
+    );
+    assert_eq!(events[1].kind(), "vault");
+    assert!(watch.is_closed());
+}
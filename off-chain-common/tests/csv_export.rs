@@ -0,0 +1,123 @@
+//! CSV exports of a garbled instance: gate rows with layout and IH hashes, audit verdicts of
+//! mismatching gates, and wire labels.
+
+use off_chain_common::audit::audit_claimed_leaves;
+use off_chain_common::consensus::layout_leaf_hash;
+use off_chain_common::csv_export::{
+    GATES_CSV_HEADER, LABELS_CSV_HEADER, write_gates_csv, write_labels_csv,
+};
+use off_chain_common::garble::{LayoutLabels, garble_circuit};
+use off_chain_common::ih::gc_block_hash;
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x5au8; 32];
+
+fn gates_csv(leaves: &[[u8; 71]], audit: bool) -> Vec<Vec<String>> {
+    let layout = millionaires_layout(2, 4);
+    let report = audit.then(|| audit_claimed_leaves(SEED, &layout, leaves).expect("audit"));
+    let mut out = Vec::new();
+    let rows = write_gates_csv(&mut out, &layout, leaves, report.as_ref()).expect("csv");
+    let text = String::from_utf8(out).unwrap();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], GATES_CSV_HEADER);
+    assert_eq!(lines.len(), rows + 1);
+    lines
+        .iter()
+        .map(|line| line.split(',').map(str::to_string).collect())
+        .collect()
+}
+
+#[test]
+fn gate_rows_carry_descriptors_and_hashes() {
+    let layout = millionaires_layout(2, 4);
+    let leaves = garble_circuit(SEED, &layout);
+    let table = gates_csv(&leaves, false);
+    assert_eq!(table.len(), layout.gates.len() + 1);
+    let columns = GATES_CSV_HEADER.split(',').count();
+    for (idx, row) in table[1..].iter().enumerate() {
+        let gate = layout.gates[idx];
+        assert_eq!(row.len(), columns);
+        assert_eq!(row[0], idx.to_string());
+        assert_eq!(row[1], (gate.gate_type as u8).to_string());
+        assert_eq!(
+            row[3..6],
+            [gate.wire_a, gate.wire_b, gate.wire_c].map(|w| w.to_string())
+        );
+        let leaf_hash = layout_leaf_hash(layout.circuit_id, idx as u64, gate);
+        assert_eq!(row[6], format!("0x{}", hex::encode(leaf_hash)));
+        let block_hash = gc_block_hash(idx as u64, &leaves[idx]);
+        assert_eq!(row[7], format!("0x{}", hex::encode(block_hash)));
+        // Without an audit the verdict columns stay empty.
+        assert!(row[8..].iter().all(String::is_empty));
+    }
+    assert!(table[1..].iter().any(|row| row[2] == "and"));
+    assert!(table[1..].iter().any(|row| row[2] == "xor"));
+}
+
+#[test]
+fn audited_rows_explain_mismatches() {
+    let honest = garble_circuit(SEED, &millionaires_layout(2, 4));
+    let mut claimed = honest.clone();
+    claimed[4][7] ^= 1;
+    claimed[4][7 + 32] ^= 1;
+    let table = gates_csv(&claimed, true);
+
+    let row = &table[5];
+    assert_eq!(row[8..11], ["mismatch", "rows", "0;2"]);
+    assert_eq!(
+        row[7],
+        format!("0x{}", hex::encode(gc_block_hash(4, &claimed[4])))
+    );
+    assert_eq!(
+        row[11],
+        format!("0x{}", hex::encode(gc_block_hash(4, &honest[4])))
+    );
+    let matching = table[1..].iter().filter(|row| row[8] == "match").count();
+    assert_eq!(matching, claimed.len() - 1);
+    assert!(table[1].ends_with(&["match".into(), String::new(), String::new(), String::new()]));
+}
+
+#[test]
+fn label_rows_cover_every_wire() {
+    let layout = millionaires_layout(2, 4);
+    let mut out = Vec::new();
+    let rows = write_labels_csv(&mut out, SEED, &layout).expect("csv");
+    let max_wire = layout
+        .gates
+        .iter()
+        .map(|gate| gate.wire_a.max(gate.wire_b).max(gate.wire_c))
+        .max()
+        .unwrap();
+    assert_eq!(rows, max_wire as usize + 1);
+
+    let text = String::from_utf8(out).unwrap();
+    let mut lines = text.lines();
+    assert_eq!(lines.next(), Some(LABELS_CSV_HEADER));
+    let labels = LayoutLabels::new(SEED, &layout);
+    for (wire, line) in lines.enumerate() {
+        let wire = wire as u16;
+        let expected = format!(
+            "{wire},0x{},0x{},{}",
+            hex::encode(labels.label(wire, 0)),
+            hex::encode(labels.label(wire, 1)),
+            labels.flip_bit(wire)
+        );
+        assert_eq!(line, expected);
+    }
+}
+
+#[test]
+fn rejects_a_wrong_leaf_count() {
+    let leaves = garble_circuit(SEED, &millionaires_layout(2, 4));
+    let err = write_gates_csv(
+        &mut Vec::new(),
+        &millionaires_layout(2, 4),
+        &leaves[1..],
+        None,
+    )
+    .unwrap_err();
+    assert!(err.contains("does not match circuit gate count"), "{err}");
+}
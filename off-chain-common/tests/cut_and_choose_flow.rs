@@ -20,7 +20,7 @@ fn generates_10_instances_and_valid_gate_proofs() {
 
     let circuit_id = keccak256(&[b"millionaires-yao-v1"]);
     let master_seed = keccak256(&[b"master-seed-v1"]);
-    let gates = build_millionaires_layout(8);
+    let gates = build_millionaires_layout(8).gates;
     assert!(gate_index < gates.len());
 
     let layout_leaf_hashes: Vec<[u8; 32]> = gates
@@ -45,11 +45,7 @@ fn generates_10_instances_and_valid_gate_proofs() {
         let commitment = com_seed(seed);
         assert_ne!(commitment, [0u8; 32]);
 
-        let layout = CircuitLayout {
-            circuit_id,
-            instance_id: instance_id as u64,
-            gates: gates.clone(),
-        };
+        let layout = CircuitLayout::new(circuit_id, instance_id as u64, gates.clone());
         let leaves = garble_circuit(seed, &layout);
         let block_hashes: Vec<[u8; 32]> = leaves
             .iter()
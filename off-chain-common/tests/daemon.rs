@@ -0,0 +1,253 @@
+//! JSON-RPC daemon behind the `serve` command.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::thread;
+
+use off_chain_common::cli_args::SERVE_REFUSED_FLAGS;
+use off_chain_common::daemon::{
+    BindPolicy, COMMAND_FAILED, INVALID_PARAMS, METHOD_NOT_FOUND, MIN_PUBLIC_TOKEN_LEN,
+    PARSE_ERROR, PathRoot, RpcServer, dispatch, key_value_output_to_json, params_to_args,
+};
+use serde_json::{Value, json};
+
+fn echo_args(method: &str, args: &[String]) -> Result<String, String> {
+    if method == "prepare-eval" && args.is_empty() {
+        return Err("Missing required argument: --m".to_string());
+    }
+    Ok(format!(
+        "status=ok\nmethod={method}\nargs={}\n",
+        args.join(" ")
+    ))
+}
+
+const TOKEN: &str = "0123abcd";
+
+fn session_root() -> PathRoot {
+    PathRoot {
+        root: PathBuf::from("/srv/session"),
+        path_flags: vec!["eval-dir".to_string(), "out-dir".to_string()],
+        refused_flags: SERVE_REFUSED_FLAGS
+            .iter()
+            .map(|flag| flag.to_string())
+            .collect(),
+    }
+}
+
+/// Sends one raw HTTP request to a daemon answering a single call; returns the status line
+/// and the body.
+fn http_exchange(request: impl Fn(&str) -> String) -> (String, String) {
+    let server =
+        RpcServer::bind("127.0.0.1:0", &["evaluate-m"], TOKEN, session_root()).expect("bind");
+    let addr = server.local_addr().expect("addr").to_string();
+    let daemon = thread::spawn(move || server.serve_one(&mut echo_args));
+
+    let mut stream = TcpStream::connect(&addr).expect("connect");
+    stream
+        .write_all(request(&addr).as_bytes())
+        .expect("request");
+    let mut response = String::new();
+    stream.read_to_string(&mut response).expect("response");
+    daemon.join().expect("join").expect("serve");
+
+    let (head, body) = response.split_once("\r\n\r\n").expect("body");
+    let status = head.lines().next().unwrap_or_default().to_string();
+    (status, body.to_string())
+}
+
+fn post(addr: &str, headers: &str, body: &str) -> String {
+    format!(
+        "POST / HTTP/1.1\r\nHost: {addr}\r\n{headers}Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[test]
+fn maps_params_to_cli_args() {
+    let object = json!({ "m": 2, "out-dir": "eval", "blob": true, "skip": false });
+    assert_eq!(
+        params_to_args(Some(&object)),
+        Ok(vec![
+            "--blob".to_string(),
+            "--m".to_string(),
+            "2".to_string(),
+            "--out-dir".to_string(),
+            "eval".to_string(),
+        ])
+    );
+    let array = json!(["--m", 3]);
+    assert_eq!(
+        params_to_args(Some(&array)),
+        Ok(vec!["--m".to_string(), "3".to_string()])
+    );
+    assert_eq!(params_to_args(None), Ok(Vec::new()));
+    let err = params_to_args(Some(&json!({ "m": [1] }))).unwrap_err();
+    assert_eq!(err.code, INVALID_PARAMS);
+    assert!(params_to_args(Some(&json!("--m 2"))).is_err());
+}
+
+#[test]
+fn parses_key_value_output() {
+    let out = key_value_output_to_json("status=ok\nroot_gc=0xab\n\nGarbling 4 instances\n");
+    assert_eq!(
+        out,
+        json!({ "status": "ok", "root_gc": "0xab", "output": ["Garbling 4 instances"] })
+    );
+}
+
+#[test]
+fn dispatches_json_rpc_calls() {
+    let methods = ["prepare-eval", "evaluate-m"];
+    let paths = session_root();
+    let call =
+        |body: &str| dispatch(body, &methods, &paths, &mut echo_args).expect("call has an id");
+
+    let ok = call(r#"{"jsonrpc":"2.0","id":7,"method":"evaluate-m","params":{"m":1}}"#);
+    assert_eq!(ok["id"], json!(7));
+    assert_eq!(ok["result"]["args"], json!("--m 1"));
+
+    let failed = call(r#"{"jsonrpc":"2.0","id":"a","method":"prepare-eval"}"#);
+    assert_eq!(failed["id"], json!("a"));
+    assert_eq!(failed["error"]["code"], json!(COMMAND_FAILED));
+    assert_eq!(
+        failed["error"]["message"],
+        json!("Missing required argument: --m")
+    );
+
+    let unknown = call(r#"{"jsonrpc":"2.0","id":1,"method":"deposit"}"#);
+    assert_eq!(unknown["error"]["code"], json!(METHOD_NOT_FOUND));
+    let garbage = call("{");
+    assert_eq!(garbage["error"]["code"], json!(PARSE_ERROR));
+    assert_eq!(garbage["id"], Value::Null);
+    let no_version = call(r#"{"id":1,"method":"evaluate-m"}"#);
+    assert!(no_version.get("error").is_some());
+}
+
+#[test]
+fn answers_no_notifications() {
+    let methods = ["evaluate-m"];
+    let mut calls = 0;
+    let mut count = |method: &str, args: &[String]| {
+        calls += 1;
+        echo_args(method, args)
+    };
+    let body = r#"{"jsonrpc":"2.0","method":"evaluate-m","params":["--m","1"]}"#;
+    assert_eq!(dispatch(body, &methods, &session_root(), &mut count), None);
+    assert_eq!(calls, 1);
+}
+
+#[test]
+fn confines_path_flags_to_the_root() {
+    let paths = session_root();
+    let args = |raw: &[&str]| raw.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+    assert_eq!(
+        paths.confine(&args(&[
+            "--eval-dir",
+            "eval",
+            "--out-dir=out/m1",
+            "--m",
+            "eval"
+        ])),
+        Ok(args(&[
+            "--eval-dir",
+            "/srv/session/eval",
+            "--out-dir=/srv/session/out/m1",
+            "--m",
+            "eval"
+        ]))
+    );
+    for escape in ["/etc", "../eval", "eval/../../x", ""] {
+        let err = paths.confine(&args(&["--out-dir", escape])).unwrap_err();
+        assert_eq!(err.code, INVALID_PARAMS, "{escape}");
+    }
+    let methods = ["evaluate-m"];
+    let body = r#"{"jsonrpc":"2.0","id":2,"method":"evaluate-m","params":{"eval-dir":"/tmp"}}"#;
+    let refused = dispatch(body, &methods, &paths, &mut echo_args).expect("response");
+    assert_eq!(refused["error"]["code"], json!(INVALID_PARAMS));
+}
+
+#[test]
+fn refuses_global_session_and_profile_flags() {
+    let methods = ["evaluate-m"];
+    let mut calls = 0;
+    let mut count = |method: &str, args: &[String]| {
+        calls += 1;
+        echo_args(method, args)
+    };
+    for params in [
+        r#"["--session","/tmp/x","--m","1"]"#,
+        r#"["--m","1","--session=/tmp/x"]"#,
+        r#"{"session":"/tmp/x"}"#,
+        r#"{"profile":"mainnet"}"#,
+        r#"{"key-stdin":true}"#,
+    ] {
+        let body = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"evaluate-m","params":{params}}}"#);
+        let response = dispatch(&body, &methods, &session_root(), &mut count).expect("response");
+        assert_eq!(response["error"]["code"], json!(INVALID_PARAMS), "{params}");
+    }
+    assert_eq!(calls, 0);
+}
+
+#[test]
+fn serves_over_http_on_loopback_unless_public() {
+    let bind = |addr: &str, token: &str| {
+        RpcServer::bind(addr, &["evaluate-m"], token, session_root()).map(|_| ())
+    };
+    assert!(bind("0.0.0.0:0", TOKEN).is_err());
+    assert!(bind("127.0.0.1:0", "").is_err());
+
+    // The relay endpoint may listen publicly, with a long enough token.
+    let bind_public = |addr: &str, token: &str| {
+        RpcServer::bind_with_policy(addr, BindPolicy::Public, &["relay"], token, session_root())
+            .map(|_| ())
+    };
+    assert!(bind_public("0.0.0.0:0", &"t".repeat(MIN_PUBLIC_TOKEN_LEN)).is_ok());
+    assert!(bind_public("0.0.0.0:0", &"t".repeat(MIN_PUBLIC_TOKEN_LEN - 1)).is_err());
+    assert!(bind_public("127.0.0.1:0", TOKEN).is_ok());
+
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"evaluate-m","params":{"eval-dir":"e"}}"#;
+    let (status, json_body) = http_exchange(|addr| {
+        let headers =
+            format!("Authorization: Bearer {TOKEN}\r\nContent-Type: application/json\r\n");
+        post(addr, &headers, body)
+    });
+    assert!(status.starts_with("HTTP/1.1 200"), "{status}");
+    let value: Value = serde_json::from_str(&json_body).expect("json");
+    assert_eq!(value["result"]["method"], json!("evaluate-m"));
+    assert_eq!(value["result"]["args"], json!("--eval-dir /srv/session/e"));
+}
+
+#[test]
+fn refuses_unauthenticated_cross_origin_and_non_json_requests() {
+    let body = r#"{"jsonrpc":"2.0","id":1,"method":"evaluate-m"}"#;
+    let json = "Content-Type: application/json\r\n";
+    let auth = format!("Authorization: Bearer {TOKEN}\r\n");
+    let cases = [
+        (json.to_string(), "401"),
+        (
+            format!("Authorization: Bearer wrong-token\r\n{json}"),
+            "401",
+        ),
+        (format!("{auth}{json}Origin: http://example.com\r\n"), "403"),
+        (format!("{auth}Content-Type: text/plain\r\n"), "415"),
+    ];
+    for (headers, expected) in cases {
+        let (status, _) = http_exchange(|addr| post(addr, &headers, body));
+        assert!(
+            status.starts_with(&format!("HTTP/1.1 {expected}")),
+            "{headers}: {status}"
+        );
+    }
+
+    let (status, _) = http_exchange(|addr| {
+        format!("GET /health HTTP/1.1\r\nHost: {addr}\r\nConnection: close\r\n\r\n")
+    });
+    assert!(status.starts_with("HTTP/1.1 200"), "{status}");
+
+    let notification = r#"{"jsonrpc":"2.0","method":"evaluate-m"}"#;
+    let (status, body) = http_exchange(|addr| post(addr, &format!("{auth}{json}"), notification));
+    assert!(status.starts_with("HTTP/1.1 204"), "{status}");
+    assert!(body.is_empty());
+}
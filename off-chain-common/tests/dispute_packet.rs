@@ -0,0 +1,150 @@
+//! Dispute packets as CBOR and JSON files: prepared disputes round-trip and tampered packets
+//! are rejected.
+
+use std::fs;
+
+use off_chain_common::canonical_json::to_canonical_json;
+use off_chain_common::cli::hex_prefixed;
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::dispute::{
+    PrepareDisputeConfig, check_dispute_packet, decode_dispute_packet, is_json_packet_path,
+    prepare_dispute_packet, read_dispute_packet_file,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::identity::EthIdentity;
+use off_chain_common::messages::{CheckpointMessage, Message, SignedMessage};
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::{CircuitLayout, GateType};
+
+mod common;
+
+use common::temp_dir;
+
+const BIT_WIDTH: usize = 4;
+
+/// Instance 1 with the leaf of gate 3 corrupted.
+fn config() -> PrepareDisputeConfig {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let circuit_id = circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS);
+    let seed = [0x21; 32];
+    let layout = CircuitLayout::new(circuit_id, 1, circuit.build(BIT_WIDTH));
+    let mut claimed_leaves = garble_circuit(seed, &layout);
+    claimed_leaves[3][20] ^= 0x01;
+    PrepareDisputeConfig {
+        circuit,
+        bit_width: BIT_WIDTH,
+        circuit_id,
+        instance_id: 1,
+        seed,
+        claimed_leaves,
+        gate_index: None,
+        allow_false_challenge: false,
+        expected_root_gc: None,
+    }
+}
+
+#[test]
+fn prepared_disputes_round_trip_through_cbor() {
+    let config = config();
+    let prepared = prepare_dispute_packet(&config).expect("prepare");
+    assert_eq!(prepared.gate_index, 3);
+
+    let packet = decode_dispute_packet(&prepared.to_cbor(&config)).expect("decode");
+    assert_eq!(packet, prepared.packet(&config));
+    assert_eq!(
+        (packet.instance_id, packet.seed, packet.gate_index),
+        (1, config.seed, 3)
+    );
+    assert_eq!(packet.leaf, config.claimed_leaves[3]);
+    assert_eq!(
+        (packet.root_gc, packet.layout_root),
+        (prepared.root_gc, prepared.layout_root)
+    );
+    assert_eq!(packet.ih_proof, prepared.ih_proof);
+    assert_eq!(packet.layout_proof, prepared.layout_proof);
+}
+
+#[test]
+fn packets_whose_proofs_miss_their_roots_are_rejected() {
+    let config = config();
+    let packet = prepare_dispute_packet(&config)
+        .expect("prepare")
+        .packet(&config);
+    assert_eq!(check_dispute_packet(&packet), Ok(()));
+
+    let mut leaf = packet.clone();
+    leaf.leaf[30] ^= 0x01;
+    let err = check_dispute_packet(&leaf).unwrap_err();
+    assert!(err.contains("IH proof of gate 3"), "{err}");
+
+    let mut gate = packet.clone();
+    gate.gate.gate_type = if gate.gate.gate_type == GateType::And {
+        GateType::Xor
+    } else {
+        GateType::And
+    };
+    let err = check_dispute_packet(&gate).unwrap_err();
+    assert!(err.contains("layout proof of gate 3"), "{err}");
+
+    let encoded = Message::DisputePacket(gate).to_cbor();
+    assert!(decode_dispute_packet(&encoded).is_err());
+}
+
+#[test]
+fn signed_packets_decode_like_plain_ones() {
+    let config = config();
+    let packet = prepare_dispute_packet(&config)
+        .expect("prepare")
+        .packet(&config);
+    let bob = EthIdentity::from_private_key([0x22; 32]).expect("key");
+    let signed = SignedMessage::sign(Message::DisputePacket(packet.clone()), [0xc0; 20], &bob)
+        .expect("sign");
+    assert_eq!(decode_dispute_packet(&signed.to_cbor()), Ok(packet));
+}
+
+#[test]
+fn other_messages_and_trailing_bytes_are_rejected() {
+    let checkpoint = Message::Checkpoint(CheckpointMessage {
+        instance_id: 1,
+        index: 3,
+        state: [0x44; 32],
+    });
+    let err = decode_dispute_packet(&checkpoint.to_cbor()).unwrap_err();
+    assert_eq!(err, "expected a dispute packet, got message kind 6");
+
+    let config = config();
+    let mut encoded = prepare_dispute_packet(&config)
+        .expect("prepare")
+        .to_cbor(&config);
+    encoded.push(0x00);
+    let err = decode_dispute_packet(&encoded).unwrap_err();
+    assert!(err.contains("trailing bytes"), "{err}");
+}
+
+#[test]
+fn packet_files_read_back_as_json_or_cbor() {
+    let dir = temp_dir("dispute-packet");
+    let config = config();
+    let prepared = prepare_dispute_packet(&config).expect("prepare");
+    let packet = prepared.packet(&config);
+
+    let json_file = dir.join("packet.json");
+    let cbor_file = dir.join("packet.cbor");
+    assert!(is_json_packet_path(&json_file));
+    assert!(!is_json_packet_path(&cbor_file));
+    let json = to_canonical_json(&packet).expect("json");
+    assert!(json.contains(r#""gate_index":3"#), "{json}");
+    fs::write(&json_file, &json).expect("json file");
+    fs::write(&cbor_file, prepared.to_cbor(&config)).expect("cbor file");
+    assert_eq!(read_dispute_packet_file(&json_file), Ok(packet.clone()));
+    assert_eq!(read_dispute_packet_file(&cbor_file), Ok(packet.clone()));
+
+    let forged = json.replace(&hex_prefixed(&packet.root_gc), &hex_prefixed(&[0u8; 32]));
+    fs::write(&json_file, forged).expect("tamper");
+    let err = read_dispute_packet_file(&json_file).unwrap_err();
+    assert!(
+        err.contains("IH proof of gate 3 does not reach rootGC"),
+        "{err}"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
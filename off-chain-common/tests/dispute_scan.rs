@@ -0,0 +1,137 @@
+//! Dispute scan: every opened instance audited in parallel, one packet per bad instance.
+
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::dispute::PrepareDisputeConfig;
+use off_chain_common::dispute::scan::{ScanVerdict, scan_disputes};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::CircuitLayout;
+
+const BIT_WIDTH: usize = 4;
+
+fn seed(instance_id: u64) -> [u8; 32] {
+    [instance_id as u8 + 1; 32]
+}
+
+fn leaves(instance_id: u64) -> Vec<[u8; 71]> {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let layout = CircuitLayout::new(
+        circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS),
+        instance_id,
+        circuit.build(BIT_WIDTH),
+    );
+    garble_circuit(seed(instance_id), &layout)
+}
+
+fn root_gc(leaves: &[[u8; 71]]) -> [u8; 32] {
+    let block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    incremental_root_from_hashes(&block_hashes)
+}
+
+fn config(instance_id: u64, claimed_leaves: Vec<[u8; 71]>) -> PrepareDisputeConfig {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    PrepareDisputeConfig {
+        circuit,
+        bit_width: BIT_WIDTH,
+        circuit_id: circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS),
+        instance_id,
+        seed: seed(instance_id),
+        expected_root_gc: Some(root_gc(&claimed_leaves)),
+        claimed_leaves,
+        gate_index: None,
+        allow_false_challenge: false,
+    }
+}
+
+#[test]
+fn honest_instances_scan_clean() {
+    let configs = (0..4).map(|id| config(id, leaves(id))).collect::<Vec<_>>();
+    let scans = scan_disputes(&configs);
+    assert_eq!(
+        scans
+            .iter()
+            .map(|scan| scan.instance_id)
+            .collect::<Vec<_>>(),
+        vec![0, 1, 2, 3]
+    );
+    assert!(
+        scans
+            .iter()
+            .all(|scan| matches!(scan.verdict, ScanVerdict::Clean))
+    );
+    assert!(scan_disputes::<Vec<[u8; 71]>>(&[]).is_empty());
+}
+
+#[test]
+fn every_tampered_instance_gets_its_own_packet() {
+    let mut tampered_1 = leaves(1);
+    tampered_1[7][40] ^= 0x02;
+    let mut tampered_3 = leaves(3);
+    tampered_3[2][12] ^= 0x01;
+    tampered_3[9][30] ^= 0x04;
+    let configs = vec![
+        config(0, leaves(0)),
+        config(1, tampered_1.clone()),
+        config(2, leaves(2)),
+        config(3, tampered_3.clone()),
+    ];
+
+    let scans = scan_disputes(&configs);
+    let packets = scans
+        .iter()
+        .zip(&configs)
+        .filter_map(|(scan, config)| match &scan.verdict {
+            ScanVerdict::Dispute(prepared) => Some(prepared.packet(config)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(packets.len(), 2);
+    assert_eq!((packets[0].instance_id, packets[0].gate_index), (1, 7));
+    assert_eq!(packets[0].leaf, tampered_1[7]);
+    assert_eq!(packets[0].root_gc, root_gc(&tampered_1));
+    assert_eq!((packets[1].instance_id, packets[1].gate_index), (3, 2));
+    assert_eq!(packets[1].root_gc, root_gc(&tampered_3));
+
+    let ScanVerdict::Dispute(prepared) = &scans[3].verdict else {
+        panic!("instance 3 not disputed: {:?}", scans[3].verdict);
+    };
+    assert_eq!(prepared.audit.mismatch_indices(), vec![2, 9]);
+}
+
+#[test]
+fn leaves_not_hashing_to_the_commitment_fail() {
+    let mut tampered = leaves(0);
+    tampered[3][20] ^= 0x08;
+    let mut configs = vec![config(0, tampered), config(1, leaves(1))];
+    for config in &mut configs {
+        config.expected_root_gc = Some([0xaa; 32]);
+    }
+
+    let scans = scan_disputes(&configs);
+    let [first, second] = &scans[..] else {
+        panic!("unexpected scans {scans:?}");
+    };
+    let (ScanVerdict::Failed(tampered), ScanVerdict::Failed(honest)) =
+        (&first.verdict, &second.verdict)
+    else {
+        panic!("unexpected verdicts {scans:?}");
+    };
+    assert!(tampered.contains("does not match expected"), "{tampered}");
+    assert!(honest.contains("match the regarbled circuit"), "{honest}");
+}
+
+#[test]
+fn truncated_leaf_sets_fail_without_stopping_the_scan() {
+    let mut truncated = leaves(0);
+    truncated.truncate(3);
+    let mut tampered = leaves(1);
+    tampered[0][5] ^= 0x01;
+    let scans = scan_disputes(&[config(0, truncated), config(1, tampered)]);
+    assert!(matches!(scans[0].verdict, ScanVerdict::Failed(_)));
+    assert!(matches!(scans[1].verdict, ScanVerdict::Dispute(_)));
+}
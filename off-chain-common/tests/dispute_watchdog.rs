@@ -0,0 +1,180 @@
+//! Dispute watchdog: audits opened instances once revealed and reports disputes in time.
+
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::dispute::watchdog::{
+    ContractSnapshot, DISPUTE_STAGE, InstanceVerdict, OpenedInstance, WatchEvent, Watchdog,
+    WatchdogConfig,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::protocol::Phase;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::CircuitLayout;
+
+const BIT_WIDTH: usize = 4;
+
+fn config() -> WatchdogConfig {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    WatchdogConfig {
+        circuit,
+        bit_width: BIT_WIDTH,
+        circuit_id: circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS),
+        margin_secs: 600,
+    }
+}
+
+fn leaves(instance_id: u64, seed: [u8; 32]) -> Vec<[u8; 71]> {
+    let config = config();
+    let layout = CircuitLayout::new(
+        config.circuit_id,
+        instance_id,
+        config.circuit.build(BIT_WIDTH),
+    );
+    garble_circuit(seed, &layout)
+}
+
+fn root_gc(leaves: &[[u8; 71]]) -> [u8; 32] {
+    let block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    incremental_root_from_hashes(&block_hashes)
+}
+
+fn opened(instance_id: u64, leaves: &[[u8; 71]]) -> OpenedInstance {
+    OpenedInstance {
+        instance_id,
+        seed: [instance_id as u8 + 1; 32],
+        root_gc: root_gc(leaves),
+    }
+}
+
+fn snapshot(now: u64, opened: Vec<OpenedInstance>) -> ContractSnapshot {
+    ContractSnapshot {
+        stage: DISPUTE_STAGE,
+        now,
+        dispute_deadline: 10_000,
+        opened,
+    }
+}
+
+#[test]
+fn waits_for_openings_and_stops_after_the_window() {
+    let mut watchdog = Watchdog::new(config());
+    let before = ContractSnapshot {
+        stage: 6,
+        ..snapshot(0, Vec::new())
+    };
+    let events = watchdog.poll(&before, |_| None).expect("poll");
+    assert!(matches!(events[..], [WatchEvent::Waiting(Phase::Open)]));
+
+    let late = snapshot(10_001, vec![opened(0, &leaves(0, [1u8; 32]))]);
+    let events = watchdog.poll(&late, |_| None).expect("poll");
+    assert!(matches!(events[..], [WatchEvent::WindowClosed]));
+    let labels = ContractSnapshot {
+        stage: DISPUTE_STAGE + 1,
+        ..snapshot(0, Vec::new())
+    };
+    let events = watchdog.poll(&labels, |_| None).expect("poll");
+    assert!(matches!(events[..], [WatchEvent::WindowClosed]));
+}
+
+#[test]
+fn honest_openings_are_audited_once() {
+    let mut watchdog = Watchdog::new(config());
+    let instances = (0..3)
+        .map(|id| opened(id, &leaves(id, [id as u8 + 1; 32])))
+        .collect::<Vec<_>>();
+    let events = watchdog
+        .poll(&snapshot(100, instances.clone()), |_| {
+            panic!("honest instances need no claimed leaves")
+        })
+        .expect("poll");
+    assert_eq!(events.len(), 4);
+    assert!(events[..3].iter().all(|event| matches!(
+        event,
+        WatchEvent::Audited {
+            verdict: InstanceVerdict::Honest,
+            ..
+        }
+    )));
+    assert!(matches!(
+        events[3],
+        WatchEvent::NothingToDispute {
+            seconds_left: 9_900
+        }
+    ));
+
+    let events = watchdog
+        .poll(&snapshot(200, instances), |_| None)
+        .expect("poll");
+    assert!(matches!(events[..], [WatchEvent::NothingToDispute { .. }]));
+}
+
+#[test]
+fn tampered_instance_yields_a_dispute_packet() {
+    let mut watchdog = Watchdog::new(config());
+    let mut claimed = leaves(2, [3u8; 32]);
+    claimed[5][40] ^= 0x02;
+    let instances = vec![opened(1, &leaves(1, [2u8; 32])), opened(2, &claimed)];
+
+    let events = watchdog
+        .poll(&snapshot(100, instances.clone()), |id| {
+            (id == 2).then(|| claimed.clone())
+        })
+        .expect("poll");
+    let [
+        WatchEvent::Audited {
+            verdict: InstanceVerdict::Honest,
+            ..
+        },
+        WatchEvent::Audited {
+            instance_id: 2,
+            verdict: InstanceVerdict::Dispute(prepared),
+        },
+    ] = &events[..]
+    else {
+        panic!("unexpected events {events:?}");
+    };
+    assert_eq!(prepared.gate_index, 5);
+    assert_eq!(prepared.claimed_leaf, claimed[5]);
+    assert_eq!(prepared.root_gc, instances[1].root_gc);
+
+    // Still pending close to the deadline.
+    let events = watchdog
+        .poll(&snapshot(9_500, instances), |_| None)
+        .expect("poll");
+    assert!(matches!(
+        events[..],
+        [WatchEvent::Urgent { seconds_left: 500 }]
+    ));
+}
+
+#[test]
+fn wrong_root_without_matching_leaves_is_unprovable() {
+    let honest = leaves(0, [1u8; 32]);
+    let mut claimed = honest.clone();
+    claimed[0][10] ^= 0x01;
+    let instance = opened(0, &claimed);
+
+    for available in [None, Some(honest)] {
+        let mut watchdog = Watchdog::new(config());
+        let events = watchdog
+            .poll(&snapshot(100, vec![instance]), |_| available.clone())
+            .expect("poll");
+        assert!(
+            matches!(
+                events[..],
+                [
+                    WatchEvent::Audited {
+                        verdict: InstanceVerdict::Unprovable(_),
+                        ..
+                    },
+                    WatchEvent::NothingToDispute { .. }
+                ]
+            ),
+            "{events:?}"
+        );
+    }
+}
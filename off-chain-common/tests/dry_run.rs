@@ -0,0 +1,106 @@
+//! `--dry-run`: the transaction a `cast send` would carry, printed instead of sent.
+
+use off_chain_common::abi::{AbiCall, AbiValue, call_from_cast_args, reveal_openings_call};
+use off_chain_common::cli::{dry_run_tx_lines, hex_prefixed};
+
+const CONTRACT: &str = "0x00000000000000000000000000000000000000ff";
+const PRIVATE_KEY: &str = "0x1111111111111111111111111111111111111111111111111111111111111111";
+
+fn send(function: &str, args: &[&str], options: &[&str]) -> Vec<String> {
+    ["send", CONTRACT, function]
+        .iter()
+        .chain(args)
+        .chain(&["--private-key", PRIVATE_KEY, "--rpc-url", "http://rpc"])
+        .chain(options)
+        .map(|arg| arg.to_string())
+        .collect()
+}
+
+fn strings(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn signature_sends_print_target_value_and_calldata() {
+    let lines = dry_run_tx_lines(
+        &send("deposit()", &[], &["--value", "1200000000000000000"]),
+        "deposit()",
+    )
+    .unwrap();
+    let selector = hex_prefixed(&AbiCall::new("deposit()", vec![]).selector());
+    assert_eq!(
+        lines,
+        vec![
+            format!("tx_to={CONTRACT}"),
+            "tx_value_wei=1200000000000000000".to_string(),
+            "tx_call=deposit()".to_string(),
+            format!("tx_selector={selector}"),
+            format!("tx_calldata={selector}"),
+        ]
+    );
+
+    let seed = format!("0x{}", "ab".repeat(32));
+    let lines = dry_run_tx_lines(
+        &send("commitBuyerSeed(bytes32)", &[&seed], &[]),
+        "commitBuyerSeed(bytes32)",
+    )
+    .unwrap();
+    let expected = AbiCall::new(
+        "commitBuyerSeed(bytes32)",
+        vec![AbiValue::Bytes32([0xab; 32])],
+    );
+    assert!(lines.contains(&"tx_value_wei=0".to_string()));
+    assert!(lines.contains(&format!("tx_calldata={}", expected.calldata_hex())));
+    assert!(lines.iter().all(|line| !line.contains(&PRIVATE_KEY[2..])));
+}
+
+#[test]
+fn calldata_sends_keep_their_calldata_and_name() {
+    let call = reveal_openings_call(&[0, 2], &[[0x01; 32], [0x02; 32]]);
+    let lines = dry_run_tx_lines(&send(&call.calldata_hex(), &[], &[]), &call.signature).unwrap();
+    assert!(lines.contains(&"tx_call=revealOpenings(uint256[],bytes32[])".to_string()));
+    assert!(lines.contains(&format!("tx_selector={}", hex_prefixed(&call.selector()))));
+    assert!(lines.contains(&format!("tx_calldata={}", call.calldata_hex())));
+}
+
+#[test]
+fn cast_arguments_encode_like_native_calls() {
+    let labels = format!("[0x{},0x{}]", "01".repeat(32), "02".repeat(32));
+    let call = call_from_cast_args("revealGarblerLabels(bytes32[])", &strings(&[&labels])).unwrap();
+    assert_eq!(
+        call.args,
+        vec![AbiValue::Array(vec![
+            AbiValue::Bytes32([0x01; 32]),
+            AbiValue::Bytes32([0x02; 32]),
+        ])]
+    );
+    let settle = call_from_cast_args("settle(bytes)", &strings(&["0x0102"])).unwrap();
+    assert_eq!(settle.args, vec![AbiValue::Bytes(vec![1, 2])]);
+    let dispute = call_from_cast_args("disputeObliviousTransferRoot(uint256)", &strings(&["7"]));
+    assert_eq!(dispute.unwrap().args, vec![AbiValue::Uint(7)]);
+
+    let lines = dry_run_tx_lines(
+        &send(
+            "revealGarblerLabels(bytes32[])",
+            &[&labels],
+            &["--blob", "--path", "labels.blob"],
+        ),
+        "revealGarblerLabels(bytes32[])",
+    )
+    .unwrap();
+    assert!(lines.contains(&format!("tx_calldata={}", call.calldata_hex())));
+    assert_eq!(lines.last().unwrap(), "tx_blob_file=labels.blob");
+}
+
+#[test]
+fn unencodable_sends_are_rejected() {
+    let err = call_from_cast_args("commitBuyerSeed(bytes32)", &[]).unwrap_err();
+    assert_eq!(err, "commitBuyerSeed(bytes32) takes 1 arguments, got 0");
+    let err = call_from_cast_args("f((uint8,uint16))", &strings(&["(1,2)"])).unwrap_err();
+    assert!(err.contains("unsupported function signature"), "{err}");
+    let err = call_from_cast_args("f(string)", &strings(&["x"])).unwrap_err();
+    assert_eq!(err, "unsupported argument type string");
+    assert!(call_from_cast_args("f(bytes32)", &strings(&["0x01"])).is_err());
+    assert!(call_from_cast_args("f(uint256[])", &strings(&["1,2"])).is_err());
+    assert!(dry_run_tx_lines(&strings(&["call", CONTRACT, "m()(uint256)"]), "m()").is_err());
+}
@@ -0,0 +1,143 @@
+//! Passphrase-encrypted artifacts: AES-GCM round trips, wrong passphrases and tampering, and
+//! transparent decryption through `read_artifact` and `verify_artifacts`.
+
+use std::fs;
+
+use off_chain_common::compression::{
+    ArtifactCompression, read_artifact, read_artifact_to_string, write_artifact_with,
+};
+use off_chain_common::encryption::{
+    ArtifactKey, ENCRYPTION_MAGIC, decrypt, encrypted_salt, is_encrypted, set_artifact_passphrase,
+};
+use off_chain_common::manifest::{
+    ArtifactManifest, ArtifactRole, ExpectedArtifact, verify_artifacts,
+};
+
+mod common;
+
+use common::temp_dir;
+
+/// Every test sets the same process-wide passphrase, so they can run in parallel.
+const PASSPHRASE: &str = "correct horse battery staple";
+
+#[test]
+fn encrypted_artifacts_round_trip_under_fresh_nonces() {
+    let key = ArtifactKey::generate(PASSPHRASE).expect("key");
+    let seed = b"0x1111111111111111111111111111111111111111111111111111111111111111\n";
+    let first = key.encrypt(seed).expect("encrypt");
+    let second = key.encrypt(seed).expect("encrypt");
+
+    assert!(is_encrypted(&first) && first.starts_with(&ENCRYPTION_MAGIC));
+    assert_eq!(encrypted_salt(&first), Ok(key.salt()));
+    assert_ne!(first, second);
+    assert!(
+        !first
+            .windows(16)
+            .any(|window| seed.windows(16).any(|s| s == window))
+    );
+    assert_eq!(key.decrypt(&first).as_deref(), Ok(&seed[..]));
+    assert_eq!(decrypt(&second, PASSPHRASE).as_deref(), Ok(&seed[..]));
+    assert!(!is_encrypted(seed));
+}
+
+#[test]
+fn wrong_passphrases_and_tampered_files_are_rejected() {
+    let key = ArtifactKey::generate(PASSPHRASE).expect("key");
+    let encrypted = key.encrypt(b"0xaa\n0xbb\n").expect("encrypt");
+
+    let err = decrypt(&encrypted, "wrong passphrase").unwrap_err();
+    assert_eq!(err, "wrong passphrase or corrupted encrypted artifact");
+
+    // The header is authenticated too, so a rewritten nonce fails like a flipped ciphertext.
+    for index in [30, encrypted.len() - 1] {
+        let mut tampered = encrypted.clone();
+        tampered[index] ^= 0x01;
+        let err = key.decrypt(&tampered).unwrap_err();
+        assert_eq!(err, "wrong passphrase or corrupted encrypted artifact");
+    }
+    let err = key.decrypt(&encrypted[..40]).unwrap_err();
+    assert!(err.starts_with("encrypted artifact truncated"), "{err}");
+
+    let other = ArtifactKey::generate(PASSPHRASE).expect("key");
+    assert_eq!(
+        other.decrypt(&encrypted).unwrap_err(),
+        "artifact was encrypted under a different salt"
+    );
+    assert!(ArtifactKey::generate("").is_err());
+}
+
+#[test]
+fn read_artifact_decrypts_then_decompresses() {
+    set_artifact_passphrase(PASSPHRASE);
+    let dir = temp_dir("encrypted-read");
+    let key = ArtifactKey::generate(PASSPHRASE).expect("key");
+    let leaves = "0xaa\n".repeat(64);
+
+    let seed_path = dir.join("instance-0-seed.txt");
+    let written = write_artifact_with(&seed_path, b"0x11\n", ArtifactCompression::None, Some(&key))
+        .expect("seed");
+    assert_eq!(written, seed_path);
+    assert!(is_encrypted(&fs::read(&seed_path).unwrap()));
+    assert_eq!(read_artifact_to_string(&seed_path).as_deref(), Ok("0x11\n"));
+
+    let leaves_path = dir.join("instance-0-leaves.txt");
+    let zstd = ArtifactCompression::zstd(3).unwrap();
+    let written =
+        write_artifact_with(&leaves_path, leaves.as_bytes(), zstd, Some(&key)).expect("leaves");
+    assert_eq!(written, dir.join("instance-0-leaves.txt.zst"));
+    assert!(is_encrypted(&fs::read(&written).unwrap()));
+    assert_eq!(read_artifact(&leaves_path), Ok(leaves.into_bytes()));
+
+    // A file encrypted under another passphrase names itself in the error.
+    fs::write(
+        &seed_path,
+        ArtifactKey::generate("another passphrase")
+            .unwrap()
+            .encrypt(b"0x11\n")
+            .unwrap(),
+    )
+    .expect("foreign");
+    let err = read_artifact(&seed_path).unwrap_err();
+    assert!(
+        err.contains("instance-0-seed.txt: wrong passphrase"),
+        "{err}"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn manifests_hash_ciphertexts_and_verify_artifacts_decrypts() {
+    set_artifact_passphrase(PASSPHRASE);
+    let dir = temp_dir("encrypted-manifest");
+    let key = ArtifactKey::generate(PASSPHRASE).expect("key");
+    let seed_path = dir.join("instance-0-seed.txt");
+    write_artifact_with(&seed_path, b"0x11\n", ArtifactCompression::None, Some(&key))
+        .expect("seed");
+
+    let mut manifest = ArtifactManifest::new("millionaires", 0, [0x33; 32], 8);
+    manifest
+        .add_file(&dir, "instance-0-seed.txt", ArtifactRole::Seed, Some(0))
+        .expect("add");
+    manifest.write(&dir).expect("write manifest");
+    let stored = fs::read(&seed_path).unwrap();
+    assert_eq!(manifest.files[0].length, stored.len() as u64);
+
+    let expected = |bytes: &[u8]| {
+        [ExpectedArtifact {
+            name: "instance-0-seed.txt".to_string(),
+            bytes: bytes.to_vec(),
+        }]
+    };
+    let report = verify_artifacts(&dir, &expected(b"0x11\n"), &[]).expect("verify");
+    assert!(report.is_intact(), "{:?}", report.mismatches);
+    let report = verify_artifacts(&dir, &expected(b"0x12\n"), &[]).expect("verify");
+    assert_eq!(
+        report
+            .mismatches
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+        ["instance-0-seed.txt: differs from the re-derived contents at byte 3"]
+    );
+    let _ = fs::remove_dir_all(dir);
+}
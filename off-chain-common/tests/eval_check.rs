@@ -0,0 +1,221 @@
+//! Eval package check: each broken part of a package is reported by the check it fails.
+
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::eval_check::{
+    EvalCheck, EvalPackageParts, EvalPackageReport, check_eval_package,
+};
+use off_chain_common::evaluation::{
+    NotGateHint, derive_alice_input_labels, derive_bob_label_offers, derive_not_gate_hints,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::{CircuitLayout, GateType};
+
+const BIT_WIDTH: usize = 4;
+const SEED: [u8; 32] = [0x5a; 32];
+
+struct Package {
+    layout: CircuitLayout,
+    decoding: OutputDecodingTable,
+    leaves: Vec<[u8; 71]>,
+    alice_labels: Vec<[u8; 16]>,
+    not_hints: Vec<NotGateHint>,
+    y_offers: Vec<([u8; 16], [u8; 16])>,
+    root_gc: [u8; 32],
+}
+
+impl Package {
+    fn honest() -> Self {
+        let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+        let circuit_id = circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS);
+        let layout = CircuitLayout::new(circuit_id, 2, circuit.build(BIT_WIDTH));
+        let output_wire = circuit
+            .decision_wire(&layout.gates, BIT_WIDTH)
+            .expect("decision wire");
+        let leaves = garble_circuit(SEED, &layout);
+        let block_hashes = leaves
+            .iter()
+            .enumerate()
+            .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+            .collect::<Vec<_>>();
+        Self {
+            decoding: OutputDecodingTable::derive(SEED, &layout, output_wire).expect("decoding"),
+            alice_labels: derive_alice_input_labels(SEED, circuit_id, 2, BIT_WIDTH, 9),
+            not_hints: derive_not_gate_hints(SEED, &layout),
+            y_offers: Vec::new(),
+            root_gc: incremental_root_from_hashes(&block_hashes),
+            leaves,
+            layout,
+        }
+    }
+
+    fn check(&self) -> EvalPackageReport {
+        let parts = EvalPackageParts {
+            bit_width: BIT_WIDTH,
+            decoding: &self.decoding,
+            gc_leaves: &self.leaves,
+            alice_labels: &self.alice_labels,
+            not_hints: &self.not_hints,
+            y_offers: &self.y_offers,
+        };
+        check_eval_package(&parts, &self.layout, self.root_gc)
+    }
+}
+
+fn failed_checks(report: &EvalPackageReport) -> Vec<EvalCheck> {
+    let mut checks = report
+        .failures
+        .iter()
+        .map(|failure| failure.check)
+        .collect::<Vec<_>>();
+    checks.dedup();
+    checks
+}
+
+#[test]
+fn an_honest_package_passes_every_check() {
+    let package = Package::honest();
+    assert!(!package.not_hints.is_empty());
+    let report = package.check();
+    assert!(report.is_consistent(), "{:?}", report.failures);
+    assert_eq!(
+        report.checked,
+        vec![
+            EvalCheck::Leaves,
+            EvalCheck::RootGc,
+            EvalCheck::AliceLabels,
+            EvalCheck::YOffers,
+            EvalCheck::NotHints,
+            EvalCheck::Anchors,
+        ]
+    );
+}
+
+#[test]
+fn published_y_label_pairs_are_rejected() {
+    let mut package = Package::honest();
+    let circuit_id = package.layout.circuit_id;
+    package.y_offers = derive_bob_label_offers(SEED, circuit_id, 2, BIT_WIDTH);
+    let report = package.check();
+    assert_eq!(failed_checks(&report), vec![EvalCheck::YOffers]);
+    assert_eq!(
+        report.failures[0].to_string(),
+        "y_offers: 4 y-wire label pairs are published, Bob must get his labels by OT only"
+    );
+}
+
+#[test]
+fn leaves_must_match_the_layout_and_the_committed_root() {
+    let mut package = Package::honest();
+    package.root_gc = [0xaa; 32];
+    let report = package.check();
+    assert_eq!(failed_checks(&report), vec![EvalCheck::RootGc]);
+    assert!(report.failures[0].reason.contains("committed rootGC"));
+
+    let mut package = Package::honest();
+    package.leaves[3][1] ^= 0x01;
+    let report = package.check();
+    assert_eq!(
+        failed_checks(&report),
+        vec![EvalCheck::Leaves, EvalCheck::RootGc]
+    );
+    assert!(
+        report.failures[0]
+            .to_string()
+            .starts_with("leaves: 1 leaves")
+    );
+
+    let mut package = Package::honest();
+    package.leaves.pop();
+    assert_eq!(
+        failed_checks(&package.check()),
+        vec![EvalCheck::Leaves, EvalCheck::RootGc]
+    );
+}
+
+#[test]
+fn label_counts_must_match_the_bit_width() {
+    let mut package = Package::honest();
+    package.alice_labels.pop();
+    let report = package.check();
+    assert_eq!(failed_checks(&report), vec![EvalCheck::AliceLabels]);
+    assert_eq!(
+        report.failures[0].to_string(),
+        "alice_labels: 3 x-wire labels for bit-width 4"
+    );
+}
+
+#[test]
+fn not_hints_must_cover_exactly_the_not_gates() {
+    let mut package = Package::honest();
+    let dropped = package.not_hints.remove(0);
+    let report = package.check();
+    assert_eq!(failed_checks(&report), vec![EvalCheck::NotHints]);
+    assert!(
+        report.failures[0]
+            .reason
+            .contains(&format!("first gate {}", dropped.gate_index)),
+        "{}",
+        report.failures[0]
+    );
+
+    let mut package = Package::honest();
+    package.not_hints.push(package.not_hints[0]);
+    assert_eq!(failed_checks(&package.check()), vec![EvalCheck::NotHints]);
+
+    let mut package = Package::honest();
+    let and_gate = package
+        .layout
+        .gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::And)
+        .expect("AND gate");
+    let mut misplaced = package.not_hints[0];
+    misplaced.gate_index = and_gate;
+    package.not_hints.push(misplaced);
+    let report = package.check();
+    assert!(
+        report.failures[0]
+            .reason
+            .contains("not a NOT gate needing a hint")
+    );
+}
+
+#[test]
+fn label_pairs_must_carry_opposite_permutation_bits() {
+    let mut package = Package::honest();
+    package.not_hints[0].in_label1[0] ^= 0x01;
+    package.not_hints[0].out_if_in1 = package.not_hints[0].out_if_in0;
+    let report = package.check();
+    assert_eq!(failed_checks(&report), vec![EvalCheck::NotHints]);
+    assert!(report.failures[0].reason.contains("input: "));
+    assert!(report.failures[1].reason.contains("output: "));
+}
+
+#[test]
+fn anchors_must_be_distinct_and_open_with_the_output_labels() {
+    let mut package = Package::honest();
+    package.decoding.h1 = package.decoding.h0;
+    let report = package.check();
+    assert_eq!(failed_checks(&report), vec![EvalCheck::Anchors]);
+    assert!(report.failures[0].reason.starts_with("h0 and h1 are both"));
+    assert!(
+        report
+            .failures
+            .iter()
+            .any(|failure| failure.reason == "lout_false does not open h1")
+    );
+
+    let mut package = Package::honest();
+    package.decoding.lout_false = package.decoding.lout_true;
+    let reasons = package
+        .check()
+        .failures
+        .into_iter()
+        .map(|failure| failure.reason)
+        .collect::<Vec<_>>();
+    assert!(reasons[0].starts_with("output labels: "), "{reasons:?}");
+    assert!(reasons.contains(&"lout_false does not open h1".to_string()));
+}
@@ -0,0 +1,97 @@
+//! Versioned `eval-meta.json`: round trips, validation on load and legacy `eval-meta.txt`.
+
+use std::fs;
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::eval_meta::{
+    EVAL_META_FILE, EVAL_META_VERSION, EvalMeta, LEGACY_EVAL_META_FILE,
+};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::CircuitLayout;
+
+mod common;
+
+use common::temp_dir;
+
+fn meta() -> EvalMeta {
+    let built = build_millionaires_layout(4);
+    let layout = CircuitLayout::new(keccak256(&[b"millionaires-yao-v1"]), 2, built.gates);
+    let decoding = OutputDecodingTable::derive([0x31; 32], &layout, built.gt_wire).unwrap();
+    EvalMeta::new(4, decoding)
+}
+
+#[test]
+fn eval_meta_round_trips_through_the_eval_dir() {
+    let dir = temp_dir("eval-meta-round-trip");
+    let meta = meta();
+    assert_eq!(meta.version, EVAL_META_VERSION);
+    let path = meta.write(&dir).expect("write");
+    assert_eq!(path, dir.join(EVAL_META_FILE));
+
+    let raw = fs::read_to_string(&path).expect("raw");
+    assert!(raw.starts_with(r#"{"bit_width":4,"decoding":{"#), "{raw}");
+    assert!(raw.ends_with(r#""version":1}"#), "{raw}");
+    assert_eq!(EvalMeta::read(&dir), Ok(meta));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn legacy_key_value_files_still_load() {
+    let dir = temp_dir("eval-meta-legacy");
+    let meta = meta();
+    let legacy = format!("bit_width=4\n{}", meta.decoding.to_key_values());
+    fs::write(dir.join(LEGACY_EVAL_META_FILE), legacy).expect("legacy");
+    assert_eq!(EvalMeta::read(&dir), Ok(meta));
+
+    // The JSON document wins once both exist.
+    let mut wider = meta;
+    wider.bit_width = 8;
+    wider.write(&dir).expect("write");
+    assert_eq!(EvalMeta::read(&dir), Ok(wider));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rejects_unknown_versions_and_inconsistent_tables() {
+    let raw = meta().to_json().expect("json");
+    let cases = [
+        (
+            raw.replace(r#""version":1"#, r#""version":2"#),
+            "unsupported eval-meta version 2 (expected 1)",
+        ),
+        (
+            raw.replace(r#""bit_width":4"#, r#""bit_width":0"#),
+            "bit_width must be positive",
+        ),
+        (
+            raw.replace(r#""version":1"#, r#""version":1,"outputs":[]"#),
+            "invalid JSON",
+        ),
+        (raw.replace(r#","version":1"#, ""), "invalid JSON"),
+    ];
+    for (raw, expected) in cases {
+        let err = EvalMeta::from_json(&raw).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+    }
+
+    let mut flipped = meta();
+    flipped.decoding.lsb_true ^= 1;
+    let err = EvalMeta::from_json(&flipped.to_json().unwrap()).unwrap_err();
+    assert!(err.contains("does not match the permutation bit"), "{err}");
+}
+
+#[test]
+fn read_names_the_file_that_failed() {
+    let dir = temp_dir("eval-meta-errors");
+    let err = EvalMeta::read(&dir).unwrap_err();
+    assert!(err.contains(EVAL_META_FILE), "{err}");
+
+    fs::write(dir.join(LEGACY_EVAL_META_FILE), "bit_width=4\nh0\n").expect("legacy");
+    let err = EvalMeta::read(&dir).unwrap_err();
+    assert!(
+        err.contains("eval-meta.txt: invalid key=value at line 2"),
+        "{err}"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,149 @@
+//! Resumable garbling of leaf files: checkpoint progress, resuming after an interruption and
+//! the checkpoint file itself.
+
+use std::fs;
+
+use off_chain_common::cli::hex_prefixed;
+use off_chain_common::export_checkpoint::{
+    EXPORT_CHECKPOINT_FILE, EXPORT_CHECKPOINT_VERSION, ExportCheckpoint, GarbleProgress,
+    garble_leaf_file,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::incremental_root;
+use off_chain_common::manifest::ArtifactManifest;
+
+mod common;
+
+use common::{millionaires_layout, temp_dir};
+
+const SEED: [u8; 32] = [0x5a; 32];
+
+fn leaf_lines(leaves: &[[u8; 71]]) -> String {
+    leaves
+        .iter()
+        .map(|leaf| hex_prefixed(leaf) + "\n")
+        .collect()
+}
+
+#[test]
+fn writes_the_leaves_and_reports_progress() {
+    let dir = temp_dir("checkpoint-garble");
+    let path = dir.join("leaves.txt.partial");
+    let layout = millionaires_layout(1, 8);
+    let leaves = garble_circuit(SEED, &layout);
+
+    let mut seen = Vec::new();
+    let done = garble_leaf_file(SEED, &layout, &path, None, 10, |progress| {
+        // Everything reported is already on disk.
+        assert_eq!(fs::metadata(&path).unwrap().len(), progress.leaves_offset);
+        seen.push(progress.gate_index);
+        Ok(())
+    })
+    .expect("garble");
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), leaf_lines(&leaves));
+    assert_eq!(done.gate_index, leaves.len() as u64);
+    assert_eq!(done.ih_state, incremental_root(&leaves));
+    assert_eq!(done.leaves_offset, fs::metadata(&path).unwrap().len());
+    let expected = (1..)
+        .map(|n| n * 10)
+        .take_while(|gate| *gate < leaves.len() as u64)
+        .collect::<Vec<_>>();
+    assert_eq!(seen, expected);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn resuming_after_an_interruption_matches_a_full_run() {
+    let dir = temp_dir("checkpoint-resume");
+    let path = dir.join("leaves.txt.partial");
+    let layout = millionaires_layout(1, 8);
+    let leaves = garble_circuit(SEED, &layout);
+
+    let mut saved = None;
+    let err = garble_leaf_file(SEED, &layout, &path, None, 16, |progress| {
+        if progress.gate_index == 32 {
+            saved = Some(progress);
+            return Err("interrupted".to_string());
+        }
+        Ok(())
+    })
+    .unwrap_err();
+    assert_eq!(err, "interrupted");
+    let saved = saved.expect("checkpoint at gate 32");
+
+    // Half-written lines after the checkpoint are cut off on resume.
+    let mut partial = fs::read(&path).unwrap();
+    partial.extend_from_slice(b"0xdeadbe");
+    fs::write(&path, partial).unwrap();
+
+    let done = garble_leaf_file(SEED, &layout, &path, Some(saved), 16, |_| Ok(())).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), leaf_lines(&leaves));
+    assert_eq!(done.ih_state, incremental_root(&leaves));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rejects_progress_the_file_or_circuit_cannot_hold() {
+    let dir = temp_dir("checkpoint-reject");
+    let path = dir.join("leaves.txt.partial");
+    let layout = millionaires_layout(1, 8);
+    let gate_count = layout.gates.len() as u64;
+    fs::write(&path, "0x00\n").unwrap();
+
+    let short = GarbleProgress {
+        gate_index: 1,
+        ih_state: [1; 32],
+        leaves_offset: 200,
+    };
+    let err = garble_leaf_file(SEED, &layout, &path, Some(short), 16, |_| Ok(())).unwrap_err();
+    assert!(err.contains("checkpoint expects at least 200"), "{err}");
+
+    let beyond = GarbleProgress {
+        gate_index: gate_count + 1,
+        ..short
+    };
+    let err = garble_leaf_file(SEED, &layout, &path, Some(beyond), 16, |_| Ok(())).unwrap_err();
+    assert!(
+        err.contains(&format!("circuit has {gate_count} gates")),
+        "{err}"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn checkpoint_files_round_trip_and_reject_other_versions() {
+    let dir = temp_dir("checkpoint-file");
+    assert_eq!(ExportCheckpoint::read(&dir), Ok(None));
+    let checkpoint = ExportCheckpoint {
+        version: EXPORT_CHECKPOINT_VERSION,
+        instance_count: 4,
+        manifest: ArtifactManifest::new("millionaires", 0, [0x11; 32], 8),
+        instance_id: 2,
+        com_seed: [0x22; 32],
+        progress: GarbleProgress {
+            gate_index: 64,
+            ih_state: [0x33; 32],
+            leaves_offset: 64 * 145,
+        },
+    };
+    let path = checkpoint.write(&dir).expect("write");
+    assert_eq!(path, dir.join(EXPORT_CHECKPOINT_FILE));
+    assert_eq!(ExportCheckpoint::read(&dir), Ok(Some(checkpoint.clone())));
+
+    let newer = ExportCheckpoint {
+        version: EXPORT_CHECKPOINT_VERSION + 1,
+        ..checkpoint
+    };
+    fs::write(&path, newer.to_json().unwrap()).unwrap();
+    let err = ExportCheckpoint::read(&dir).unwrap_err();
+    assert!(
+        err.contains("unsupported export checkpoint version"),
+        "{err}"
+    );
+
+    ExportCheckpoint::remove(&dir).expect("remove");
+    assert!(!path.exists());
+    ExportCheckpoint::remove(&dir).expect("remove again");
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,71 @@
+//! Boolean expression compiler: garbled evaluation must agree with plaintext evaluation.
+
+use std::collections::HashMap;
+
+use off_chain_common::circuits::{compile_expr, compile_expr_with_inputs, evaluate_expr};
+use off_chain_common::consensus::{derive_wire_label, keccak256};
+use off_chain_common::evaluation::{derive_not_gate_hints, evaluate_garbled_wires};
+use off_chain_common::garble::garble_circuit;
+
+fn assert_garbled_matches_plaintext(source: &str) {
+    let compiled = compile_expr_with_inputs(source).unwrap();
+    let mut layout = compiled.layout.clone();
+    layout.circuit_id = keccak256(&[b"expr-test", source.as_bytes()]);
+    layout.instance_id = 1;
+    let seed = keccak256(&[b"expr-seed"]);
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+
+    for assignment in 0..(1u32 << compiled.inputs.len()) {
+        let bits: Vec<u8> = (0..compiled.inputs.len())
+            .map(|idx| ((assignment >> idx) & 1) as u8)
+            .collect();
+        let values: HashMap<String, u8> = compiled
+            .inputs
+            .iter()
+            .cloned()
+            .zip(bits.iter().copied())
+            .collect();
+        let expected = evaluate_expr(source, &values).unwrap();
+
+        let input_labels: Vec<(u16, [u8; 16])> = bits
+            .iter()
+            .enumerate()
+            .map(|(wire, bit)| {
+                let wire = wire as u16;
+                (
+                    wire,
+                    derive_wire_label(layout.circuit_id, 1, wire, *bit, seed),
+                )
+            })
+            .collect();
+        let labels = evaluate_garbled_wires(&layout, &leaves, &input_labels, &hints).unwrap();
+        let out = compiled.output_wire;
+        assert_eq!(
+            labels[out as usize],
+            Some(derive_wire_label(layout.circuit_id, 1, out, expected, seed)),
+            "{source} with {values:?}"
+        );
+    }
+}
+
+#[test]
+fn compiled_expressions_evaluate_like_plaintext() {
+    assert_garbled_matches_plaintext("a3 & !b3 | (a2 ^ b2)");
+    assert_garbled_matches_plaintext("!(x & y) ^ ~z");
+    assert_garbled_matches_plaintext("a0 | b0 & a1 ^ b1");
+}
+
+#[test]
+fn inputs_follow_natural_name_order() {
+    let compiled = compile_expr_with_inputs("b1 & a10 | a2 ^ b0").unwrap();
+    assert_eq!(compiled.inputs, vec!["a2", "a10", "b0", "b1"]);
+    assert_eq!(compile_expr("a & b").unwrap().gates.len(), 1);
+}
+
+#[test]
+fn rejects_malformed_expressions() {
+    for source in ["a &", "(a | b", "a b", "a + b", "a", ""] {
+        assert!(compile_expr(source).is_err(), "{source} should be rejected");
+    }
+}
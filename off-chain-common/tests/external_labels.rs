@@ -0,0 +1,87 @@
+//! External input labels: garbling against precommitted label pairs instead of seed labels.
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::evaluation::{derive_not_gate_hints_from, evaluate_garbled_circuit};
+use off_chain_common::garble::{
+    InputLabelOverrides, LayoutLabels, WireLabelProvider, garble_circuit,
+    garble_circuit_with_labels,
+};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::GarblingScheme;
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x4du8; 32];
+const BIT_WIDTH: usize = 4;
+
+/// Label pair as an OT sender would commit to it, with permutation bits `flip`/`flip ^ 1`.
+fn ot_pair(wire: u16, flip: u8) -> [[u8; 16]; 2] {
+    [0u8, 1].map(|bit| {
+        let mut label = [0u8; 16];
+        label.copy_from_slice(&keccak256(&[b"OT-LABEL", &wire.to_be_bytes(), &[bit]])[..16]);
+        label[0] = (label[0] & 0xFE) | (flip ^ bit);
+        label
+    })
+}
+
+#[test]
+fn seed_provider_matches_garble_circuit() {
+    let layout = millionaires_layout(1, BIT_WIDTH);
+    let labels = LayoutLabels::new(SEED, &layout);
+    assert_eq!(
+        garble_circuit_with_labels(&layout, &labels),
+        garble_circuit(SEED, &layout)
+    );
+    let overrides = InputLabelOverrides::new(SEED, &layout).expect("classic layout");
+    assert_eq!(
+        garble_circuit_with_labels(&layout, &overrides),
+        garble_circuit(SEED, &layout)
+    );
+}
+
+#[test]
+fn evaluates_with_overridden_input_labels() {
+    let layout = millionaires_layout(1, BIT_WIDTH);
+    let mut overrides = InputLabelOverrides::new(SEED, &layout).expect("classic layout");
+    for wire in 0..(2 * BIT_WIDTH) as u16 {
+        overrides
+            .insert(wire, ot_pair(wire, (wire % 2) as u8))
+            .expect("input wire");
+    }
+    let leaves = garble_circuit_with_labels(&layout, &overrides);
+    assert_ne!(leaves, garble_circuit(SEED, &layout));
+
+    let input = |offset: usize, bits: [u8; 4]| {
+        bits.iter()
+            .enumerate()
+            .map(|(idx, bit)| overrides.label((offset + idx) as u16, *bit))
+            .collect::<Vec<_>>()
+    };
+    // Alice 0b0101 = 5 (little-endian bits), Bob 0b0011 = 3.
+    let alice = input(0, [1, 0, 1, 0]);
+    let bob = input(BIT_WIDTH, [1, 1, 0, 0]);
+    let hints = derive_not_gate_hints_from(&overrides, &layout);
+    let leaves = leaves.iter().map(|leaf| leaf.to_vec()).collect::<Vec<_>>();
+    let output_wire = build_millionaires_layout(BIT_WIDTH).gt_wire;
+    let out = evaluate_garbled_circuit(&layout, &leaves, &alice, &bob, &hints, output_wire)
+        .expect("evaluate");
+    assert_eq!(out, overrides.label(output_wire, 1));
+}
+
+#[test]
+fn rejects_invalid_overrides() {
+    let layout = millionaires_layout(1, BIT_WIDTH);
+    let mut overrides = InputLabelOverrides::new(SEED, &layout).expect("classic layout");
+    let gate_output = layout.gates[0].wire_c;
+    let err = overrides.insert(gate_output, ot_pair(0, 0)).unwrap_err();
+    assert!(err.contains("is a gate output"), "{err}");
+
+    let [l0, _] = ot_pair(0, 0);
+    let err = overrides.insert(0, [l0, l0]).unwrap_err();
+    assert!(err.contains("distinct permutation bits"), "{err}");
+
+    let free_xor = layout.clone().with_scheme(GarblingScheme::FreeXor);
+    assert!(InputLabelOverrides::new(SEED, &free_xor).is_err());
+}
@@ -0,0 +1,135 @@
+//! Fault injection: every corrupted leaf still has a valid IH proof against the faulty root,
+//! but no longer matches the leaf recomputed from the revealed seed.
+
+use off_chain_common::consensus::{LEAF_BYTES_LEN, keccak256};
+use off_chain_common::garble::{garble_circuit, recompute_gate_leaf};
+use off_chain_common::ih::{
+    gc_block_hash, ih_proof_from_hashes, incremental_root_from_hashes, verify_ih_proof,
+};
+use off_chain_common::scenario::derive_instance_seed;
+use off_chain_common::scenario::faults::{Fault, HeaderWire, garble_with_fault, inject_fault};
+use off_chain_common::types::{CircuitLayout, GateType};
+
+mod common;
+
+use common::millionaires_layout;
+
+fn instance() -> ([u8; 32], CircuitLayout) {
+    let layout = millionaires_layout(3, 4);
+    let seed = derive_instance_seed(keccak256(&[b"master-seed-v1"]), layout.circuit_id, 3);
+    (seed, layout)
+}
+
+#[test]
+fn faulty_leaves_are_provable_but_disputable() {
+    let (seed, layout) = instance();
+    let and_gate = layout
+        .gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::And)
+        .unwrap();
+    let faults = [
+        Fault::FlipRowByte {
+            gate_index: and_gate,
+            row: 2,
+            byte: 0,
+            mask: 0x01,
+        },
+        Fault::SwapRows {
+            gate_index: and_gate,
+            row_a: 0,
+            row_b: 3,
+        },
+        Fault::WrongWireHeader {
+            gate_index: 1,
+            wire: HeaderWire::C,
+            value: 999,
+        },
+        Fault::TruncateLeaf {
+            gate_index: 0,
+            len: 70,
+        },
+    ];
+
+    for fault in faults {
+        let faulty = garble_with_fault(seed, &layout, fault).unwrap();
+        let idx = faulty.gate_index;
+        assert_eq!(faulty.description, fault.to_string());
+
+        let block_hashes = faulty
+            .leaves
+            .iter()
+            .enumerate()
+            .map(|(i, leaf)| gc_block_hash(i as u64, leaf))
+            .collect::<Vec<_>>();
+        let root = incremental_root_from_hashes(&block_hashes);
+        let proof = ih_proof_from_hashes(&block_hashes, idx);
+        assert!(verify_ih_proof(block_hashes[idx], &proof, root));
+
+        let expected = recompute_gate_leaf(
+            seed,
+            layout.circuit_id,
+            layout.instance_id,
+            idx as u64,
+            layout.gates[idx],
+        );
+        assert_ne!(
+            faulty.leaves[idx],
+            expected.to_vec(),
+            "{}",
+            faulty.description
+        );
+        if let Fault::TruncateLeaf { len, .. } = fault {
+            assert_eq!(faulty.leaves[idx].len(), len);
+            assert!(len < LEAF_BYTES_LEN);
+        }
+
+        // Only the targeted leaf changes.
+        let honest = garble_circuit(seed, &layout);
+        for (i, leaf) in faulty.leaves.iter().enumerate() {
+            if i != idx {
+                assert_eq!(leaf.as_slice(), honest[i].as_slice());
+            }
+        }
+    }
+}
+
+#[test]
+fn rejects_no_op_and_out_of_range_faults() {
+    let (seed, layout) = instance();
+    let leaves = garble_circuit(seed, &layout);
+    let not_gate = layout
+        .gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::Not)
+        .unwrap();
+
+    // NOT rows are all zero, so swapping them changes nothing.
+    let swap = Fault::SwapRows {
+        gate_index: not_gate,
+        row_a: 0,
+        row_b: 1,
+    };
+    assert!(inject_fault(&leaves, swap).is_err());
+
+    let same_header = Fault::WrongWireHeader {
+        gate_index: 0,
+        wire: HeaderWire::A,
+        value: layout.gates[0].wire_a,
+    };
+    assert!(inject_fault(&leaves, same_header).is_err());
+
+    let out_of_range = Fault::TruncateLeaf {
+        gate_index: leaves.len(),
+        len: 10,
+    };
+    assert!(inject_fault(&leaves, out_of_range).is_err());
+
+    let bad_row = Fault::FlipRowByte {
+        gate_index: 0,
+        row: 4,
+        byte: 0,
+        mask: 0xff,
+    };
+    assert!(inject_fault(&leaves, bad_row).is_err());
+}
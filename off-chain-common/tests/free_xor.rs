@@ -0,0 +1,118 @@
+//! Free-XOR scheme: zero-row XOR/NOT leaves, hint-free evaluation and plain-circuit parity.
+
+use off_chain_common::builder::CircuitBuilder;
+use off_chain_common::consensus::{derive_free_xor_delta, keccak256, xor16};
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, derive_output_labels,
+    evaluate_garbled_circuit, evaluate_garbled_wires, evaluate_plain_layout, u64_to_bits_le,
+};
+use off_chain_common::garble::{LayoutLabels, garble_circuit};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{CircuitLayout, GarblingScheme, GateType};
+
+mod common;
+
+use common::millionaires_layout;
+
+fn free_xor_layout(bit_width: usize) -> CircuitLayout {
+    millionaires_layout(4, bit_width).with_scheme(GarblingScheme::FreeXor)
+}
+
+#[test]
+fn labels_differ_by_delta_and_keep_point_and_permute() {
+    let seed = [7u8; 32];
+    let layout = free_xor_layout(4);
+    let labels = LayoutLabels::new(seed, &layout);
+    let delta = derive_free_xor_delta(layout.circuit_id, layout.instance_id, seed);
+    assert_eq!(labels.delta(), Some(delta));
+    assert_eq!(delta[0] & 1, 1);
+
+    for wire in 0..layout.gates.last().unwrap().wire_c {
+        let l0 = labels.label(wire, 0);
+        let l1 = labels.label(wire, 1);
+        assert_eq!(xor16(l0, l1), delta);
+        assert_eq!(l0[0] & 1, labels.flip_bit(wire));
+    }
+    assert_eq!(
+        LayoutLabels::new(seed, &CircuitLayout::new([0u8; 32], 0, Vec::new())).delta(),
+        None
+    );
+}
+
+#[test]
+fn only_non_free_gates_carry_rows() {
+    let layout = free_xor_layout(8);
+    let leaves = garble_circuit([9u8; 32], &layout);
+    let classic = garble_circuit(
+        [9u8; 32],
+        &CircuitLayout {
+            scheme: GarblingScheme::Classic,
+            ..layout.clone()
+        },
+    );
+
+    for ((gate, leaf), classic_leaf) in layout.gates.iter().zip(&leaves).zip(&classic) {
+        let has_rows = leaf[7..].iter().any(|byte| *byte != 0);
+        let free = matches!(gate.gate_type, GateType::Xor | GateType::Not);
+        assert_eq!(has_rows, !free, "{gate:?}");
+        // Headers stay identical to the classic encoding.
+        assert_eq!(leaf[..7], classic_leaf[..7]);
+    }
+    assert!(derive_not_gate_hints([9u8; 32], &layout).is_empty());
+}
+
+#[test]
+fn garbled_evaluation_matches_plain_comparison() {
+    let bit_width = 4;
+    let layout = free_xor_layout(bit_width);
+    let output_wire = build_millionaires_layout(bit_width).gt_wire;
+    let seed = [3u8; 32];
+    let leaves = garble_circuit(seed, &layout);
+    let (label_false, label_true) = derive_output_labels(seed, &layout, output_wire).unwrap();
+
+    for x in 0..16u64 {
+        for y in 0..16u64 {
+            let alice = derive_layout_input_labels(seed, &layout, 0, &u64_to_bits_le(x, bit_width));
+            let bob = derive_layout_input_labels(
+                seed,
+                &layout,
+                bit_width as u16,
+                &u64_to_bits_le(y, bit_width),
+            );
+            let out =
+                evaluate_garbled_circuit(&layout, &leaves, &alice, &bob, &[], output_wire).unwrap();
+            let expected = if x > y { label_true } else { label_false };
+            assert_eq!(out, expected, "x={x} y={y}");
+        }
+    }
+}
+
+#[test]
+fn constants_and_native_gates_evaluate_under_free_xor() {
+    let mut builder = CircuitBuilder::new().with_circuit_id(keccak256(&[b"free-xor-constants"]));
+    let a = builder.input();
+    let b = builder.input();
+    let one = builder.constant(true);
+    let zero = builder.constant(false);
+    let nand = builder.nand(a, b);
+    let flipped = builder.xor(nand, one);
+    let masked = builder.and(flipped, zero);
+    let out = builder.or(masked, flipped);
+    let layout = builder.finish().with_scheme(GarblingScheme::FreeXor);
+
+    let seed = [5u8; 32];
+    let leaves = garble_circuit(seed, &layout);
+    let labels = LayoutLabels::new(seed, &layout);
+    for bits in [[0u8, 0], [0, 1], [1, 0], [1, 1]] {
+        let plain = evaluate_plain_layout(&layout, &bits).unwrap();
+        let inputs = derive_layout_input_labels(seed, &layout, 0, &bits)
+            .into_iter()
+            .enumerate()
+            .map(|(wire, label)| (wire as u16, label))
+            .collect::<Vec<_>>();
+        let wires = evaluate_garbled_wires(&layout, &leaves, &inputs, &[]).unwrap();
+        let bit = plain[out as usize].unwrap();
+        assert_eq!(bit, bits[0] & bits[1]);
+        assert_eq!(wires[out as usize], Some(labels.label(out, bit)));
+    }
+}
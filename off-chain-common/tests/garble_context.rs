@@ -0,0 +1,83 @@
+//! Memoizing garbler: `GarbleContext` leaves and labels match the uncached derivation.
+
+use off_chain_common::builder::CircuitBuilder;
+use off_chain_common::consensus::{derive_wire_label, keccak256};
+use off_chain_common::garble::{
+    GarbleContext, derive_layout_wire_label, garble_circuit, recompute_gate_leaf,
+};
+use off_chain_common::types::{GarblingScheme, NotGateMode, PadFunction};
+
+mod common;
+
+use common::millionaires_layout;
+
+#[test]
+fn cached_leaves_match_per_gate_recompute() {
+    let seed = [0x5au8; 32];
+    let layout = millionaires_layout(5, 8);
+    let ctx = GarbleContext::new(seed, &layout);
+    for (idx, gate) in layout.gates.iter().enumerate() {
+        let expected = recompute_gate_leaf(seed, layout.circuit_id, 5, idx as u64, *gate);
+        assert_eq!(ctx.gate_leaf(idx), expected, "gate {idx}");
+    }
+    assert_eq!(
+        garble_circuit(seed, &layout),
+        (0..layout.gates.len())
+            .map(|idx| ctx.gate_leaf(idx))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn cached_labels_match_layout_labels() {
+    let seed = [0x5au8; 32];
+    let mut builder = CircuitBuilder::new().with_circuit_id(keccak256(&[b"ctx-constants"]));
+    let x = builder.inputs(3);
+    let mut y = builder.inputs(2);
+    y.push(builder.constant(true));
+    let (gt, _) = builder.compare(&x, &y);
+    let _ = builder.not(gt);
+    let constants = builder.finish();
+
+    for layout in [
+        millionaires_layout(5, 4),
+        millionaires_layout(5, 4).with_scheme(GarblingScheme::FreeXor),
+        millionaires_layout(5, 4).with_not_gate_mode(NotGateMode::Garbled),
+        millionaires_layout(5, 4).with_pad_function(PadFunction::FixedKeyAes),
+        constants,
+    ] {
+        let ctx = GarbleContext::new(seed, &layout);
+        for wire in 0..layout.gates.iter().map(|gate| gate.wire_c).max().unwrap() {
+            for bit in 0..=1 {
+                assert_eq!(
+                    ctx.label(wire, bit),
+                    derive_layout_wire_label(seed, &layout, wire, bit)
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn each_label_is_derived_once() {
+    let seed = [0x5au8; 32];
+    let layout = millionaires_layout(5, 16);
+    let ctx = GarbleContext::new(seed, &layout);
+    for idx in 0..layout.gates.len() {
+        let _ = ctx.gate_leaf(idx);
+    }
+    let wires = layout
+        .gates
+        .iter()
+        .flat_map(|gate| [gate.wire_a, gate.wire_b, gate.wire_c])
+        .collect::<std::collections::BTreeSet<_>>();
+    assert!(ctx.cached_label_count() <= 2 * wires.len());
+
+    // Later lookups are served from the cache.
+    let before = ctx.cached_label_count();
+    assert_eq!(
+        ctx.label(0, 1),
+        derive_wire_label(layout.circuit_id, 5, 0, 1, seed)
+    );
+    assert_eq!(ctx.cached_label_count(), before);
+}
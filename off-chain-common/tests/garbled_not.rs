@@ -0,0 +1,86 @@
+//! Garbled NOT gates: single-input tables replace out-of-band `NotGateHint`s.
+
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, derive_output_labels,
+    evaluate_garbled_circuit, u64_to_bits_le,
+};
+use off_chain_common::garble::{garble_circuit, garble_leaves};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{CircuitLayout, GateType, LeafFormat, NotGateMode};
+
+mod common;
+
+use common::millionaires_layout;
+
+const BIT_WIDTH: usize = 4;
+
+fn layout(not_gate_mode: NotGateMode, leaf_format: LeafFormat) -> CircuitLayout {
+    millionaires_layout(6, BIT_WIDTH)
+        .with_not_gate_mode(not_gate_mode)
+        .with_leaf_format(leaf_format)
+}
+
+fn evaluate(
+    layout: &CircuitLayout,
+    leaves: &[Vec<u8>],
+    x: u64,
+    y: u64,
+) -> Result<[u8; 16], String> {
+    let seed = [8u8; 32];
+    let alice = derive_layout_input_labels(seed, layout, 0, &u64_to_bits_le(x, BIT_WIDTH));
+    let bob = derive_layout_input_labels(
+        seed,
+        layout,
+        BIT_WIDTH as u16,
+        &u64_to_bits_le(y, BIT_WIDTH),
+    );
+    let output_wire = build_millionaires_layout(BIT_WIDTH).gt_wire;
+    evaluate_garbled_circuit(layout, leaves, &alice, &bob, &[], output_wire)
+}
+
+#[test]
+fn garbled_not_leaves_carry_two_rows() {
+    let v1 = layout(NotGateMode::Garbled, LeafFormat::V1);
+    let leaves = garble_circuit([8u8; 32], &v1);
+    let v2 = layout(NotGateMode::Garbled, LeafFormat::V2);
+    let compact = garble_leaves([8u8; 32], &v2);
+
+    for ((gate, leaf), compact_leaf) in v1.gates.iter().zip(&leaves).zip(&compact) {
+        if gate.gate_type != GateType::Not {
+            continue;
+        }
+        assert!(leaf[7..39].iter().any(|byte| *byte != 0));
+        assert!(leaf[39..].iter().all(|byte| *byte == 0));
+        assert_eq!(compact_leaf.len(), 8 + 16);
+    }
+    assert!(derive_not_gate_hints([8u8; 32], &v1).is_empty());
+}
+
+#[test]
+fn evaluates_without_hints() {
+    let seed = [8u8; 32];
+    let output_wire = build_millionaires_layout(BIT_WIDTH).gt_wire;
+    for leaf_format in [LeafFormat::V1, LeafFormat::V2] {
+        let layout = layout(NotGateMode::Garbled, leaf_format);
+        let leaves = garble_leaves(seed, &layout);
+        let (label_false, label_true) = derive_output_labels(seed, &layout, output_wire).unwrap();
+        for x in 0..16u64 {
+            for y in 0..16u64 {
+                let expected = if x > y { label_true } else { label_false };
+                assert_eq!(
+                    evaluate(&layout, &leaves, x, y).unwrap(),
+                    expected,
+                    "{leaf_format:?} x={x} y={y}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn hinted_mode_still_requires_hints() {
+    let layout = layout(NotGateMode::Hinted, LeafFormat::V1);
+    let leaves = garble_leaves([8u8; 32], &layout);
+    let err = evaluate(&layout, &leaves, 3, 5).unwrap_err();
+    assert!(err.contains("missing NOT hint"), "{err}");
+}
@@ -0,0 +1,87 @@
+//! Garbling audits: per-gate classification of claimed leaves against the opened seed.
+
+use off_chain_common::audit::{LeafCorruption, audit_claimed_leaves};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::scenario::faults::{Fault, HeaderWire, garble_with_fault};
+use off_chain_common::types::LeafFormat;
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x5au8; 32];
+
+fn claimed_with(fault: Fault) -> Vec<[u8; 71]> {
+    garble_with_fault(SEED, &millionaires_layout(2, 4), fault)
+        .expect("inject fault")
+        .leaves
+        .iter()
+        .map(|leaf| leaf.as_slice().try_into().expect("71-byte leaf"))
+        .collect()
+}
+
+#[test]
+fn honest_leaves_audit_clean() {
+    let layout = millionaires_layout(2, 4);
+    let report =
+        audit_claimed_leaves(SEED, &layout, &garble_circuit(SEED, &layout)).expect("audit");
+    assert!(report.is_clean());
+    assert_eq!(report.gate_count, layout.gates.len());
+    assert!(report.mismatch_indices().is_empty());
+}
+
+#[test]
+fn classifies_row_and_header_corruption() {
+    let rows = claimed_with(Fault::SwapRows {
+        gate_index: 3,
+        row_a: 0,
+        row_b: 2,
+    });
+    let report = audit_claimed_leaves(SEED, &millionaires_layout(2, 4), &rows).expect("audit");
+    assert_eq!(report.mismatch_indices(), vec![3]);
+    let gate = report.gate(3).expect("gate 3 audited");
+    assert_eq!(gate.corruption(), LeafCorruption::Rows);
+    assert_eq!(gate.differing_rows, vec![0, 2]);
+    assert_eq!(gate.to_string(), "gate=3 rows rows=[0, 2]");
+
+    let real_wire_b = millionaires_layout(2, 4).gates[1].wire_b;
+    let header = claimed_with(Fault::WrongWireHeader {
+        gate_index: 1,
+        wire: HeaderWire::B,
+        value: real_wire_b + 1,
+    });
+    let report = audit_claimed_leaves(SEED, &millionaires_layout(2, 4), &header).expect("audit");
+    let gate = report.gate(1).expect("gate 1 audited");
+    assert_eq!(gate.corruption(), LeafCorruption::Header);
+    assert!(gate.differing_rows.is_empty());
+    assert_eq!(gate.claimed_leaf[7..], gate.expected_leaf[7..]);
+}
+
+#[test]
+fn reports_every_mismatching_gate() {
+    let mut claimed = garble_circuit(SEED, &millionaires_layout(2, 4));
+    claimed[0][0] ^= 1;
+    claimed[0][7] ^= 1;
+    claimed[5][70] ^= 1;
+    let report = audit_claimed_leaves(SEED, &millionaires_layout(2, 4), &claimed).expect("audit");
+    assert_eq!(report.mismatch_indices(), vec![0, 5]);
+    assert_eq!(
+        report.gate(0).map(|gate| gate.corruption()),
+        Some(LeafCorruption::HeaderAndRows)
+    );
+    assert_eq!(
+        report.gate(5).map(|gate| gate.differing_rows.clone()),
+        Some(vec![3])
+    );
+    assert!(report.gate(1).is_none());
+}
+
+#[test]
+fn rejects_wrong_leaf_count_and_offchain_schemes() {
+    let claimed = garble_circuit(SEED, &millionaires_layout(2, 4));
+    let err = audit_claimed_leaves(SEED, &millionaires_layout(2, 4), &claimed[1..]).unwrap_err();
+    assert!(err.contains("does not match circuit gate count"), "{err}");
+
+    let v2 = millionaires_layout(2, 4).with_leaf_format(LeafFormat::V2);
+    assert!(audit_claimed_leaves(SEED, &v2, &claimed).is_err());
+}
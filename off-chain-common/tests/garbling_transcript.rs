@@ -0,0 +1,103 @@
+//! Garbling transcripts: one hash over every flip bit, label, row key and pad of a run.
+
+use off_chain_common::consensus::{
+    compute_row_key, derive_wire_flip_bit, derive_wire_label, expand_pad, keccak256,
+    uint256_from_u64,
+};
+use off_chain_common::garble::garbling_transcript;
+use off_chain_common::types::{CircuitLayout, GarblingScheme, GateDesc, GateType, LeafFormat};
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x17u8; 32];
+
+#[test]
+fn single_and_gate_transcript_matches_spec() {
+    let circuit_id = [0xabu8; 32];
+    let gate = GateDesc {
+        gate_type: GateType::And,
+        wire_a: 0,
+        wire_b: 1,
+        wire_c: 2,
+    };
+    let layout = CircuitLayout::new(circuit_id, 5, vec![gate]);
+
+    let instance = uint256_from_u64(5);
+    let mut t = keccak256(&[b"GC-TRANSCRIPT", &circuit_id, &instance, &[0x00]]);
+    t = keccak256(&[
+        b"GATE",
+        &t,
+        &uint256_from_u64(0),
+        &[GateType::And as u8, 0, 0, 0, 1, 0, 2],
+    ]);
+    let flip_a = derive_wire_flip_bit(circuit_id, 5, 0, SEED);
+    let flip_b = derive_wire_flip_bit(circuit_id, 5, 1, SEED);
+    for perm_a in 0..=1u8 {
+        for perm_b in 0..=1u8 {
+            let (bit_a, bit_b) = (perm_a ^ flip_a, perm_b ^ flip_b);
+            let label_a = derive_wire_label(circuit_id, 5, 0, bit_a, SEED);
+            let label_b = derive_wire_label(circuit_id, 5, 1, bit_b, SEED);
+            let out_label = derive_wire_label(circuit_id, 5, 2, bit_a & bit_b, SEED);
+            let row_key = compute_row_key(circuit_id, 5, 0, perm_a, perm_b, label_a, label_b);
+            t = keccak256(&[
+                b"ROW",
+                &t,
+                &[perm_a, perm_b],
+                &[flip_a, flip_b],
+                &label_a,
+                &label_b,
+                &out_label,
+                &row_key,
+                &expand_pad(row_key),
+            ]);
+        }
+    }
+
+    let transcript = garbling_transcript(SEED, &layout);
+    assert_eq!(transcript.root, t);
+    assert_eq!(transcript.gate_states, vec![t]);
+}
+
+#[test]
+fn transcripts_are_deterministic_and_seed_bound() {
+    let a = garbling_transcript(SEED, &millionaires_layout(3, 4));
+    assert_eq!(a, garbling_transcript(SEED, &millionaires_layout(3, 4)));
+    assert_eq!(a.gate_states.len(), millionaires_layout(3, 4).gates.len());
+    assert_eq!(a.first_divergence(&a.clone()), None);
+
+    let other_seed = garbling_transcript([0x18u8; 32], &millionaires_layout(3, 4));
+    assert_ne!(a.root, other_seed.root);
+    assert_eq!(a.first_divergence(&other_seed), Some(0));
+}
+
+#[test]
+fn locates_first_diverging_gate() {
+    let honest = garbling_transcript(SEED, &millionaires_layout(3, 4));
+    let mut patched = millionaires_layout(3, 4);
+    let gate = &mut patched.gates[6];
+    gate.gate_type = match gate.gate_type {
+        GateType::And => GateType::Xor,
+        _ => GateType::And,
+    };
+    let patched = garbling_transcript(SEED, &patched);
+    assert_eq!(honest.first_divergence(&patched), Some(6));
+    assert_eq!(honest.gate_states[..6], patched.gate_states[..6]);
+}
+
+#[test]
+fn scheme_is_bound_into_the_transcript() {
+    let classic = garbling_transcript(SEED, &millionaires_layout(3, 4));
+    let free_xor = garbling_transcript(
+        SEED,
+        &millionaires_layout(3, 4).with_scheme(GarblingScheme::FreeXor),
+    );
+    let v2 = garbling_transcript(
+        SEED,
+        &millionaires_layout(3, 4).with_leaf_format(LeafFormat::V2),
+    );
+    assert_ne!(classic.root, free_xor.root);
+    assert_ne!(classic.root, v2.root);
+    assert_eq!(classic.first_divergence(&v2), Some(0));
+}
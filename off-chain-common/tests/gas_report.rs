@@ -0,0 +1,109 @@
+//! Gas report: intrinsic gas of prepared calldata, the fallback when a call cannot be
+//! estimated, and the size of the worst-case dispute.
+
+use off_chain_common::abi::{
+    AbiCall, DISPUTE_GARBLED_TABLE_SIGNATURE, reveal_garbler_labels_call, settle_call,
+};
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::cost::estimate_instance_cost;
+use off_chain_common::gas_report::{
+    GasCall, GasEstimate, TX_BASE_GAS, calldata_gas, gas_report, intrinsic_gas, sender_gas,
+    worst_case_dispute_call,
+};
+use off_chain_common::protocol::Party;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::CircuitLayout;
+
+const SEED: [u8; 32] = [0x3c; 32];
+
+fn gas_call(name: &'static str, sender: Party, call: AbiCall) -> GasCall {
+    GasCall { name, sender, call }
+}
+
+#[test]
+fn intrinsic_gas_prices_zero_and_nonzero_bytes() {
+    assert_eq!(calldata_gas(&[]), 0);
+    assert_eq!(calldata_gas(&[0, 0, 0x01, 0xff, 0]), 3 * 4 + 2 * 16);
+    assert_eq!(intrinsic_gas(&[]), TX_BASE_GAS);
+    assert_eq!(intrinsic_gas(&[0x01; 10]), 21_000 + 160);
+}
+
+#[test]
+fn unavailable_estimates_fall_back_to_intrinsic_gas() {
+    let calls = vec![
+        gas_call(
+            "reveal_labels",
+            Party::Garbler,
+            reveal_garbler_labels_call(&[[0x11; 32]; 4]),
+        ),
+        gas_call("settle", Party::Evaluator, settle_call(&[0x22; 40])),
+    ];
+    let entries = gas_report(&calls, |call| match call.name {
+        "reveal_labels" => Ok(90_000),
+        _ => Err("execution reverted: wrong stage".to_string()),
+    });
+
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].estimate, GasEstimate::Estimated(90_000));
+    assert_eq!(entries[0].gas(), 90_000);
+    assert_eq!(entries[0].calldata_bytes, 4 + 32 + 32 + 4 * 32);
+
+    let settle = calls[1].call.calldata();
+    assert_eq!(entries[1].calldata_bytes, settle.len());
+    assert_eq!(
+        entries[1].estimate,
+        GasEstimate::Unavailable("execution reverted: wrong stage".to_string())
+    );
+    assert_eq!(entries[1].gas(), intrinsic_gas(&settle));
+}
+
+#[test]
+fn sender_gas_totals_each_party() {
+    let calls = vec![
+        gas_call("a", Party::Garbler, settle_call(&[1])),
+        gas_call("b", Party::Evaluator, settle_call(&[2])),
+        gas_call("c", Party::Garbler, settle_call(&[3])),
+    ];
+    let mut next = 0;
+    let entries = gas_report(&calls, |_| {
+        next += 1_000;
+        Ok(next)
+    });
+
+    assert_eq!(sender_gas(&entries, Party::Garbler), 1_000 + 3_000);
+    assert_eq!(sender_gas(&entries, Party::Evaluator), 2_000);
+    assert_eq!(sender_gas(&entries, Party::Bidder(0)), 0);
+}
+
+#[test]
+fn label_and_settle_calls_encode_their_arguments() {
+    let call = reveal_garbler_labels_call(&[[0xaa; 32], [0xbb; 32]]);
+    let calldata = call.calldata();
+    assert_eq!(calldata[..4], call.selector());
+    assert_eq!(calldata.len(), 4 + 32 * 4);
+    assert_eq!(calldata[4 + 3 * 32..], [0xbb; 32]);
+
+    let call = settle_call(&[0x5e; 33]);
+    assert_eq!(call.signature, "settle(bytes)");
+    let calldata = call.calldata();
+    assert_eq!(calldata.len(), 4 + 32 + 32 + 64);
+    assert_eq!(calldata[4 + 64..4 + 64 + 33], [0x5e; 33]);
+}
+
+#[test]
+fn worst_case_dispute_is_gate_zero_at_the_estimated_size() {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let circuit_id = circuit.circuit_id(4, HIGHER_BID_WINS);
+    let layout = CircuitLayout::new(circuit_id, 2, circuit.build(4));
+    let cost = estimate_instance_cost(&layout);
+
+    let call = worst_case_dispute_call(circuit, 4, circuit_id, 2, SEED).expect("dispute call");
+    assert_eq!(call.signature, DISPUTE_GARBLED_TABLE_SIGNATURE);
+    let calldata = call.calldata();
+    assert_eq!(calldata[4..36], [[0; 31].as_slice(), &[2]].concat());
+    assert_eq!(calldata[36..68], SEED);
+    assert_eq!(calldata[68..100], [0; 32]);
+    assert_eq!(calldata.len(), cost.dispute_calldata_bytes);
+
+    assert!(worst_case_dispute_call(circuit, 0, circuit_id, 2, SEED).is_err());
+}
@@ -0,0 +1,127 @@
+//! Gate-set V2 (native OR/NAND/NOR): truth tables, garbling, contract parity, and V1
+//! compatibility guards.
+
+use off_chain_common::builder::CircuitBuilder;
+use off_chain_common::consensus::{derive_wire_label, keccak256, truth_table};
+use off_chain_common::evaluation::{
+    derive_not_gate_hints, evaluate_garbled_wires, evaluate_plain_circuit,
+};
+use off_chain_common::garble::{garble_circuit, recompute_gate_leaf};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{GateDesc, GateSet, GateType};
+
+#[test]
+fn opcodes_keep_solidity_values_and_round_trip() {
+    assert_eq!(GateType::And as u8, 0);
+    assert_eq!(GateType::Xor as u8, 1);
+    assert_eq!(GateType::Not as u8, 2);
+    for value in 0..=5u8 {
+        assert_eq!(GateType::from_u8(value).unwrap() as u8, value);
+    }
+    assert_eq!(GateType::from_u8(6), None);
+
+    assert!(GateSet::V1.allows(GateType::Not));
+    assert!(!GateSet::V1.allows(GateType::Or));
+    assert!(GateSet::V2.allows(GateType::Nor));
+    assert!(
+        GateSet::V1
+            .check_gates(&build_millionaires_layout(8).gates)
+            .is_ok()
+    );
+}
+
+#[test]
+fn v2_truth_tables() {
+    for (a, b) in [(0u8, 0u8), (0, 1), (1, 0), (1, 1)] {
+        assert_eq!(truth_table(GateType::Or, a, b), a | b);
+        assert_eq!(truth_table(GateType::Nand, a, b), (a & b) ^ 1);
+        assert_eq!(truth_table(GateType::Nor, a, b), (a | b) ^ 1);
+    }
+}
+
+#[test]
+fn builder_emits_native_gates_only_in_v2() {
+    let mut v1 = CircuitBuilder::new();
+    let (a, b) = (v1.input(), v1.input());
+    v1.or(a, b);
+    v1.nor(a, b);
+    let v1_gates = v1.finish().gates;
+    assert!(GateSet::V1.check_gates(&v1_gates).is_ok());
+    assert_eq!(v1_gates.len(), 7);
+
+    let mut v2 = CircuitBuilder::new().with_gate_set(GateSet::V2);
+    let (a, b) = (v2.input(), v2.input());
+    v2.or(a, b);
+    v2.nor(a, b);
+    let v2_gates = v2.finish().gates;
+    assert_eq!(v2_gates.len(), 2);
+    let err = GateSet::V1.check_gates(&v2_gates).unwrap_err();
+    assert!(err.contains("gate 0 uses Or"), "{err}");
+}
+
+#[test]
+fn v2_gates_garble_and_evaluate() {
+    let circuit_id = keccak256(&[b"gate-set-v2"]);
+    let seed = keccak256(&[b"gate-set-seed"]);
+    let mut builder = CircuitBuilder::new()
+        .with_gate_set(GateSet::V2)
+        .with_circuit_id(circuit_id);
+    let (a, b) = (builder.input(), builder.input());
+    let outputs = [builder.or(a, b), builder.nand(a, b), builder.nor(a, b)];
+    let layout = builder.finish();
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+
+    for (bit_a, bit_b) in [(0u8, 0u8), (0, 1), (1, 0), (1, 1)] {
+        let plain = evaluate_plain_circuit(&layout.gates, &[bit_a, bit_b]).unwrap();
+        let inputs = [
+            (a, derive_wire_label(circuit_id, 0, a, bit_a, seed)),
+            (b, derive_wire_label(circuit_id, 0, b, bit_b, seed)),
+        ];
+        let labels = evaluate_garbled_wires(&layout, &leaves, &inputs, &hints).unwrap();
+        for wire in outputs {
+            let bit = plain[wire as usize].unwrap();
+            assert_eq!(
+                labels[wire as usize],
+                Some(derive_wire_label(circuit_id, 0, wire, bit, seed))
+            );
+        }
+    }
+}
+
+/// Pinned leaves for `test_ComputeLeaf_GateSetV2_MatchesRust` in the Foundry suite, which
+/// recomputes them with `recomputeGateLeafBytes` (seed `keccak256("seed")`, instance 0, gate 3).
+#[test]
+fn v2_leaves_match_contract_vectors() {
+    let mut circuit_id = [0u8; 32];
+    hex::decode_to_slice(
+        "4b38f6018cce9cce241946cda9af3509db31d6ef0f4b17e25e4f589faa71da7e",
+        &mut circuit_id,
+    )
+    .unwrap();
+    let seed = keccak256(&[b"seed"]);
+    let vectors = [
+        (
+            GateType::Or,
+            "030007001200133ceba8f231e3c528bd5f9d2364804fd11405a58bd60a679475654156274943fa0c33ea573adfb8bfe02ad7c62eaed15bfe3fce0901ddf7475ebc3ed4f626e8d0",
+        ),
+        (
+            GateType::Nand,
+            "040007001200133d199219040ea8bbdd90320802771a791405a58bd60a679475654156274943fa0c33ea573adfb8bfe02ad7c62eaed15bffcdf4e234309ad43e7391ff90d1bd78",
+        ),
+        (
+            GateType::Nor,
+            "050007001200133d199219040ea8bbdd90320802771a7915f79f60e3e70a0715aaee7d41be16520dc1d0bc0f32d52c80e578ed485984f3ffcdf4e234309ad43e7391ff90d1bd78",
+        ),
+    ];
+    for (gate_type, expected) in vectors {
+        let gate = GateDesc {
+            gate_type,
+            wire_a: 7,
+            wire_b: 18,
+            wire_c: 19,
+        };
+        let leaf = recompute_gate_leaf(seed, circuit_id, 0, 3, gate);
+        assert_eq!(hex::encode(leaf), expected, "{gate_type:?}");
+    }
+}
@@ -0,0 +1,103 @@
+//! Instance cost estimates agree with actually garbled leaves and generated proofs.
+
+use off_chain_common::consensus::layout_leaf_hash;
+use off_chain_common::cost::{CALLDATA_GAS_PER_BYTE, estimate_instance_cost};
+use off_chain_common::garble::{garble_circuit, garble_leaves};
+use off_chain_common::ih::{gc_block_hash, ih_proof_from_hashes};
+use off_chain_common::merkle::merkle_proof_from_hashes;
+use off_chain_common::types::{
+    CircuitLayout, GarblingScheme, LeafFormat, NotGateMode, PadFunction, RowAuthMode, SchemeId,
+};
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x61u8; 32];
+
+#[test]
+fn v1_estimate_matches_garbled_instance_and_proofs() {
+    let layout = millionaires_layout(0, 8);
+    let cost = estimate_instance_cost(&layout);
+    let leaves = garble_circuit(SEED, &layout);
+    let block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    let layout_hashes = layout
+        .gates
+        .iter()
+        .enumerate()
+        .map(|(idx, gate)| layout_leaf_hash(layout.circuit_id, idx as u64, *gate))
+        .collect::<Vec<_>>();
+
+    assert_eq!(cost.gate_count, leaves.len());
+    assert_eq!(cost.leaf_bytes, 71 * leaves.len());
+    assert_eq!(cost.max_leaf_bytes, 71);
+    assert_eq!(cost.block_hash_count, block_hashes.len());
+    let worst_ih = (0..leaves.len())
+        .map(|idx| ih_proof_from_hashes(&block_hashes, idx).len())
+        .max();
+    assert_eq!(Some(cost.ih_proof_len), worst_ih);
+    for idx in [0, leaves.len() / 2, leaves.len() - 1] {
+        assert_eq!(
+            merkle_proof_from_hashes(&layout_hashes, idx).len(),
+            cost.layout_proof_len
+        );
+    }
+}
+
+#[test]
+fn dispute_calldata_follows_abi_layout() {
+    let cost = estimate_instance_cost(&millionaires_layout(0, 4));
+    // Selector, 10 head words, leaf length + 3 padded words, two arrays.
+    let expected =
+        4 + 10 * 32 + (32 + 96) + (32 + 32 * cost.ih_proof_len) + (32 + 32 * cost.layout_proof_len);
+    assert_eq!(cost.dispute_calldata_bytes, expected);
+    assert_eq!(
+        cost.dispute_calldata_gas,
+        expected as u64 * CALLDATA_GAS_PER_BYTE
+    );
+
+    let wider = estimate_instance_cost(&millionaires_layout(0, 16));
+    assert!(wider.dispute_calldata_bytes > cost.dispute_calldata_bytes);
+}
+
+#[test]
+fn v2_estimate_matches_encoded_leaves() {
+    for row_auth in [RowAuthMode::Unauthenticated, RowAuthMode::Mac] {
+        for scheme in [GarblingScheme::Classic, GarblingScheme::FreeXor] {
+            let id = SchemeId::new(
+                scheme,
+                LeafFormat::V2,
+                NotGateMode::Garbled,
+                PadFunction::Keccak,
+                row_auth,
+            );
+            let layout = millionaires_layout(0, 4).with_scheme_id(id);
+            let leaves = garble_leaves(SEED, &layout);
+            let cost = estimate_instance_cost(&layout);
+            assert_eq!(cost.leaf_bytes, leaves.iter().map(Vec::len).sum::<usize>());
+            assert_eq!(
+                cost.max_leaf_bytes,
+                leaves.iter().map(Vec::len).max().unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn single_gate_and_empty_layouts() {
+    let mut single = millionaires_layout(0, 4);
+    single.gates.truncate(1);
+    let cost = estimate_instance_cost(&single);
+    assert_eq!((cost.ih_proof_len, cost.layout_proof_len), (0, 0));
+
+    let empty = CircuitLayout::new([0u8; 32], 0, Vec::new());
+    let cost = estimate_instance_cost(&empty);
+    assert_eq!(
+        (cost.gate_count, cost.leaf_bytes, cost.max_leaf_bytes),
+        (0, 0, 0)
+    );
+}
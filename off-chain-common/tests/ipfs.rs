@@ -0,0 +1,235 @@
+//! IPFS publication of eval blobs: CIDv1 encoding, pinning and fetching through a mocked Kubo
+//! RPC API and gateway, and CIDs recorded in the manifest.
+
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use off_chain_common::ipfs::{Cid, IpfsClient};
+use off_chain_common::manifest::{ArtifactManifest, ArtifactRole, ManifestInstance};
+use off_chain_common::retry::RetryPolicy;
+
+mod common;
+
+use common::temp_dir;
+
+/// Response of the mocked node: status and body.
+type Reply = (u16, Vec<u8>);
+
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        initial_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(1),
+    }
+}
+
+/// Serves `handle(method, url, body)` on a loopback port; returns its base URL.
+fn mock_node(handle: impl Fn(&str, &str, &[u8]) -> Reply + Send + 'static) -> String {
+    let server = tiny_http::Server::http("127.0.0.1:0").expect("bind");
+    let url = format!("http://{}", server.server_addr().to_ip().expect("ip"));
+    thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let mut body = Vec::new();
+            request.as_reader().read_to_end(&mut body).expect("body");
+            let method = request.method().to_string();
+            let (status, reply) = handle(&method, request.url(), &body);
+            let response = tiny_http::Response::from_data(reply).with_status_code(status);
+            let _ = request.respond(response);
+        }
+    });
+    url
+}
+
+/// The file part of a multipart `add` body.
+fn multipart_file(body: &[u8]) -> Vec<u8> {
+    let start = body
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .expect("part headers")
+        + 4;
+    let end = body
+        .windows(4)
+        .rposition(|w| w == b"\r\n--")
+        .expect("closing boundary");
+    body[start..end].to_vec()
+}
+
+/// A Kubo node that stores added blobs under their raw CID.
+fn kubo_node() -> (String, Arc<Mutex<Vec<String>>>) {
+    let store = Arc::new(Mutex::new(Vec::<(String, Vec<u8>)>::new()));
+    let urls = Arc::new(Mutex::new(Vec::new()));
+    let seen = urls.clone();
+    let url = mock_node(move |method, url, body| {
+        seen.lock().unwrap().push(format!("{method} {url}"));
+        let mut store = store.lock().unwrap();
+        if url.starts_with("/api/v0/add?") {
+            let blob = multipart_file(body);
+            let cid = Cid::raw(&blob).to_string();
+            let reply = format!(
+                r#"{{"Name":"blob","Hash":"{cid}","Size":"{}"}}"#,
+                blob.len()
+            );
+            store.push((cid, blob));
+            return (200, reply.into_bytes());
+        }
+        let cid = url
+            .strip_prefix("/api/v0/cat?arg=")
+            .or_else(|| url.strip_prefix("/ipfs/"))
+            .unwrap_or_default();
+        match store.iter().find(|(stored, _)| stored == cid) {
+            Some((_, blob)) => (200, blob.clone()),
+            None => (
+                500,
+                br#"{"Message":"block was not found locally"}"#.to_vec(),
+            ),
+        }
+    });
+    (url, urls)
+}
+
+#[test]
+fn cids_encode_like_kubo_and_round_trip() {
+    // The well-known CIDv1 of the empty raw block.
+    let empty = Cid::raw(b"");
+    assert_eq!(
+        empty.to_string(),
+        "bafkreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku"
+    );
+    assert_eq!(Cid::parse(&empty.to_string()), Ok(empty));
+    assert_eq!(empty.verify(b""), Ok(true));
+    let err = empty.verify(b"x").unwrap_err();
+    assert!(err.starts_with("fetched bytes do not hash to CID"), "{err}");
+
+    // Chunked blobs get dag-pb CIDs, which parse but cannot be checked without the DAG.
+    let text = "bafybeigdyrzt5sfp7udm7hu76uh7y26nf3efuylqabf3oclgtqy55fbzdi";
+    let dag = Cid::parse(text).expect("dag-pb");
+    assert!(!dag.is_raw());
+    assert_eq!(dag.to_string(), text);
+    assert_eq!(dag.verify(b"anything"), Ok(false));
+
+    for (text, reason) in [
+        (
+            "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG",
+            "expected a base32 CIDv1",
+        ),
+        ("bafkrei!", "not base32"),
+        ("bafk", "too short"),
+        (
+            "bafyreihdwdcefgh4dqkjv67uzcmw7ojee6xedzdetojuzjevtenxquvyku",
+            "unsupported codec",
+        ),
+    ] {
+        let err = Cid::parse(text).unwrap_err();
+        assert!(err.contains(reason), "{text}: {err}");
+    }
+}
+
+#[test]
+fn pins_and_fetches_through_the_rpc_api() {
+    let (url, requests) = kubo_node();
+    let client = IpfsClient::api(&format!("{url}/"), policy());
+    assert_eq!(client.url(), url);
+    let blob = (0..70_000u32)
+        .flat_map(u32::to_be_bytes)
+        .collect::<Vec<_>>();
+
+    let cid = client.pin(&blob).expect("pin");
+    assert_eq!(cid, Cid::raw(&blob));
+    assert_eq!(client.fetch(&cid), Ok(blob));
+    let requests = requests.lock().unwrap();
+    assert!(requests[0].starts_with("POST /api/v0/add?"), "{requests:?}");
+    for param in ["pin=true", "cid-version=1", "raw-leaves=true"] {
+        assert!(requests[0].contains(param), "{requests:?}");
+    }
+    assert_eq!(requests[1], format!("POST /api/v0/cat?arg={cid}"));
+    drop(requests);
+
+    let missing = Cid::raw(b"never added");
+    let err = client.fetch(&missing).unwrap_err();
+    assert!(err.contains("returned 500"), "{err}");
+}
+
+#[test]
+fn rejects_nodes_that_answer_with_other_cids_or_bytes() {
+    let url = mock_node(|_, url, _| {
+        if url.starts_with("/api/v0/add?") {
+            let cid = Cid::raw(b"something else");
+            (200, format!(r#"{{"Hash":"{cid}"}}"#).into_bytes())
+        } else {
+            (200, b"tampered".to_vec())
+        }
+    });
+    let client = IpfsClient::api(&url, policy());
+    let err = client.pin(b"eval blob").unwrap_err();
+    assert!(err.contains("expected"), "{err}");
+    let err = client.fetch(&Cid::raw(b"eval blob")).unwrap_err();
+    assert!(err.starts_with("fetched bytes do not hash to CID"), "{err}");
+
+    let gateway = IpfsClient::gateway(&url, policy());
+    let err = gateway.pin(b"eval blob").unwrap_err();
+    assert!(err.starts_with("cannot pin through the gateway"), "{err}");
+}
+
+#[test]
+fn gateways_serve_fetches_and_transient_failures_are_retried() {
+    let blob = b"eval blob".to_vec();
+    let cid = Cid::raw(&blob);
+    let attempts = Arc::new(Mutex::new(0));
+    let counter = attempts.clone();
+    let served = blob.clone();
+    let url = mock_node(move |method, url, _| {
+        assert_eq!((method, url), ("GET", format!("/ipfs/{cid}").as_str()));
+        let mut attempts = counter.lock().unwrap();
+        *attempts += 1;
+        if *attempts == 1 {
+            (503, b"busy".to_vec())
+        } else {
+            (200, served.clone())
+        }
+    });
+
+    let gateway = IpfsClient::gateway(&url, policy());
+    assert_eq!(gateway.fetch(&cid), Ok(blob));
+    assert_eq!(*attempts.lock().unwrap(), 2);
+}
+
+#[test]
+fn manifests_record_cids_per_instance() {
+    let dir = temp_dir("ipfs-manifest");
+    fs::write(dir.join("instance-0-eval-blob.bin"), b"eval blob").expect("blob");
+
+    let mut manifest = ArtifactManifest::new("millionaires", 0, [0x33; 32], 8);
+    manifest
+        .add_file(
+            &dir,
+            "instance-0-eval-blob.bin",
+            ArtifactRole::EvalBlob,
+            Some(0),
+        )
+        .expect("add");
+    manifest.instances.push(ManifestInstance {
+        instance_id: 0,
+        blob_hash_gc: [0x44; 32],
+        cid: None,
+    });
+    // Manifests written before publication carry no `cid` key at all.
+    assert!(!manifest.to_json().unwrap().contains("cid"));
+
+    let cid = Cid::raw(b"eval blob").to_string();
+    manifest.instances[0].cid = Some(cid.clone());
+    manifest.write(&dir).expect("write");
+    let read = ArtifactManifest::read(&dir).expect("read");
+    assert_eq!(
+        read.instance(0).and_then(|i| i.cid.as_deref()),
+        Some(cid.as_str())
+    );
+    assert_eq!(
+        read.instance_file(0, ArtifactRole::EvalBlob)
+            .map(|file| file.name.as_str()),
+        Some("instance-0-eval-blob.bin")
+    );
+    assert!(read.instance(1).is_none());
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,87 @@
+//! `--json` output of the binaries: `key=value` lines turned into one JSON object.
+
+use off_chain_common::cli::{bytes32_vec_literal, tx_summary_lines};
+use off_chain_common::output::command_output_json;
+use serde_json::json;
+
+fn lines(raw: &[&str]) -> Vec<String> {
+    raw.iter().map(|line| line.to_string()).collect()
+}
+
+#[test]
+fn fields_keep_string_values_and_default_to_ok() {
+    let proof = bytes32_vec_literal(&[[0x11; 32], [0x22; 32]]);
+    let output = lines(&[
+        "instance_id=3",
+        "root_gc=0xabcd",
+        "matches_h0=true",
+        "matches_h1=false",
+        &format!("ih_proof={proof}"),
+        "layout_proof=[]",
+        "selected_gate_differing_rows=[0, 2]",
+    ]);
+    assert_eq!(
+        command_output_json("prepare-dispute", &output, None),
+        json!({
+            "command": "prepare-dispute",
+            "status": "ok",
+            "instance_id": "3",
+            "root_gc": "0xabcd",
+            "matches_h0": true,
+            "matches_h1": false,
+            "ih_proof": [format!("0x{}", "11".repeat(32)), format!("0x{}", "22".repeat(32))],
+            "layout_proof": [],
+            "selected_gate_differing_rows": ["0", "2"],
+        })
+    );
+}
+
+#[test]
+fn failures_report_the_error_over_the_command_status() {
+    let output = lines(&["status=prepared", "instance_id=1"]);
+    let ok = command_output_json("prepare-ot-dispute", &output, None);
+    assert_eq!(ok["status"], "prepared");
+
+    let failed = command_output_json("prepare-ot-dispute", &output, Some("root mismatch"));
+    assert_eq!(failed["status"], "error");
+    assert_eq!(failed["error"], "root mismatch");
+    assert_eq!(failed["instance_id"], "1");
+}
+
+#[test]
+fn repeated_keys_collect_and_other_lines_go_to_output() {
+    let output = lines(&[
+        "event=waiting phase=commit",
+        "",
+        "event=audited instance_id=2 verdict=honest",
+        "cast send template:",
+        "cast send 0xc0ffee \"closeDispute()\" --rpc-url http://127.0.0.1:8545",
+        "=orphan",
+    ]);
+    let value = command_output_json("watch", &output, None);
+    assert_eq!(
+        value["event"],
+        json!([
+            "waiting phase=commit",
+            "audited instance_id=2 verdict=honest"
+        ])
+    );
+    assert_eq!(
+        value["output"],
+        json!([
+            "cast send template:",
+            "cast send 0xc0ffee \"closeDispute()\" --rpc-url http://127.0.0.1:8545",
+            "=orphan",
+        ])
+    );
+}
+
+#[test]
+fn tx_summaries_become_prefixed_fields() {
+    let receipt = "blockNumber 7\ntransactionHash 0xfeed\nstatus 1 (success)\ngasUsed 21000";
+    let value = command_output_json("deposit", &tx_summary_lines("deposit", receipt), None);
+    assert_eq!(value["deposit_tx_hash"], "0xfeed");
+    assert_eq!(value["deposit_status"], "1");
+    assert_eq!(value["deposit_gas_used"], "21000");
+    assert_eq!(value["status"], "ok");
+}
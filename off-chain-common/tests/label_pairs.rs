@@ -0,0 +1,58 @@
+//! Label-pair helpers: full pair derivation, semantic decoding and point-and-permute checks.
+
+use off_chain_common::consensus::derive_wire_flip_bit;
+use off_chain_common::garble::derive_layout_wire_label;
+use off_chain_common::labels::{
+    check_point_and_permute, derive_label_pair, derive_layout_label_pair, get_permutation_bit,
+    semantic_bit,
+};
+use off_chain_common::types::{ConstantWire, GarblingScheme};
+
+mod common;
+
+use common::millionaires_layout;
+
+const SEED: [u8; 32] = [0x0cu8; 32];
+
+#[test]
+fn pairs_follow_flip_bits() {
+    let layout = millionaires_layout(2, 4);
+    for wire in 0..8u16 {
+        let pair = derive_label_pair(layout.circuit_id, 2, wire, SEED);
+        let flip = derive_wire_flip_bit(layout.circuit_id, 2, wire, SEED);
+        assert_eq!(get_permutation_bit(pair[0]), flip);
+        assert_eq!(get_permutation_bit(pair[1]), flip ^ 1);
+        assert!(check_point_and_permute(pair).is_ok());
+        assert_eq!(derive_layout_label_pair(SEED, &layout, wire), pair);
+    }
+}
+
+#[test]
+fn layout_pairs_honor_scheme_and_constants() {
+    let free_xor = millionaires_layout(2, 4).with_scheme(GarblingScheme::FreeXor);
+    let pair = derive_layout_label_pair(SEED, &free_xor, 3);
+    assert_eq!(pair[1], derive_layout_wire_label(SEED, &free_xor, 3, 1));
+    assert!(check_point_and_permute(pair).is_ok());
+
+    let constant_wire = 200;
+    let with_constant = millionaires_layout(2, 4).with_constant_wires(vec![ConstantWire {
+        wire: constant_wire,
+        value: 1,
+    }]);
+    let pair = derive_layout_label_pair(SEED, &with_constant, constant_wire);
+    assert_eq!(get_permutation_bit(pair[1]), 1);
+    assert!(check_point_and_permute(pair).is_ok());
+}
+
+#[test]
+fn semantic_bit_decodes_only_wire_labels() {
+    let layout = millionaires_layout(2, 4);
+    let [l0, l1] = derive_layout_label_pair(SEED, &layout, 5);
+    assert_eq!(semantic_bit(SEED, &layout, 5, l0), Some(0));
+    assert_eq!(semantic_bit(SEED, &layout, 5, l1), Some(1));
+    assert_eq!(semantic_bit(SEED, &layout, 6, l1), None);
+    assert_eq!(semantic_bit([0x0du8; 32], &layout, 5, l1), None);
+
+    let err = check_point_and_permute([l0, l0]).unwrap_err();
+    assert!(err.contains("distinct permutation bits"), "{err}");
+}
@@ -0,0 +1,188 @@
+//! Memory-mapped claimed-leaf files: random access, streamed block hashes, the in-memory
+//! fallback for compressed and bundled files, and disputes prepared straight from a file.
+
+use std::fs;
+use std::path::Path;
+
+use off_chain_common::bundle::BundleBuilder;
+use off_chain_common::cli::hex_prefixed;
+use off_chain_common::compression::{ArtifactCompression, write_artifact};
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::dispute::{PrepareDisputeConfig, prepare_dispute_packet};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::ih::{gc_block_hash, incremental_root, incremental_root_from_hashes};
+use off_chain_common::leaf_file::LeafFile;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::CircuitLayout;
+
+mod common;
+
+use common::temp_dir;
+
+fn leaves(count: u32) -> Vec<[u8; 71]> {
+    (0..count)
+        .map(|i| {
+            let mut leaf = [0x5a; 71];
+            leaf[..4].copy_from_slice(&i.to_be_bytes());
+            leaf
+        })
+        .collect()
+}
+
+fn leaf_text(leaves: &[[u8; 71]]) -> String {
+    leaves
+        .iter()
+        .map(|leaf| format!("{}\n", hex_prefixed(leaf)))
+        .collect()
+}
+
+fn write_leaves(path: &Path, leaves: &[[u8; 71]]) {
+    fs::write(path, leaf_text(leaves)).expect("write leaves");
+}
+
+#[test]
+fn mapped_files_give_random_access_and_streamed_block_hashes() {
+    let dir = temp_dir("leaf-file-mapped");
+    let path = dir.join("instance-0-leaves.txt");
+    let leaves = leaves(5_000);
+    write_leaves(&path, &leaves);
+
+    let file = LeafFile::open(&path).expect("open");
+    assert!(file.is_mapped());
+    assert_eq!(file.len(), leaves.len());
+    for index in [0, 1, 2_500, 4_999] {
+        assert_eq!(file.get(index), Ok(leaves[index]));
+    }
+    let err = file.get(5_000).unwrap_err();
+    assert!(err.starts_with("leaf index 5000 out of range"), "{err}");
+
+    let block_hashes = file.block_hashes().expect("block hashes");
+    assert_eq!(block_hashes[7], gc_block_hash(7, &leaves[7]));
+    assert_eq!(
+        incremental_root_from_hashes(&block_hashes),
+        incremental_root(&leaves)
+    );
+    let read = file.iter().collect::<Result<Vec<_>, _>>().expect("iter");
+    assert_eq!(read, leaves);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn mapped_files_accept_hand_edited_lines_and_name_bad_ones() {
+    let dir = temp_dir("leaf-file-lines");
+    let path = dir.join("leaves.txt");
+    let leaves = leaves(3);
+    let text = format!(
+        "# claimed leaves\n[\n  \"{}\",\r\n\n  {},  # middle\n{}]",
+        hex_prefixed(&leaves[0]),
+        hex_prefixed(&leaves[1]),
+        hex_prefixed(&leaves[2])
+    );
+    fs::write(&path, text).expect("write");
+    let file = LeafFile::open(&path).expect("open");
+    assert!(file.is_mapped());
+    assert_eq!(
+        file.iter().collect::<Result<Vec<_>, _>>(),
+        Ok(leaves.clone())
+    );
+
+    fs::write(&path, format!("{}0x1234\n", leaf_text(&leaves))).expect("rewrite");
+    let err = LeafFile::open(&path).unwrap_err();
+    assert!(
+        err.starts_with(&format!("invalid claimed leaf at {}:4:", path.display())),
+        "{err}"
+    );
+
+    fs::write(&path, "").expect("empty");
+    assert!(LeafFile::open(&path).expect("open empty").is_empty());
+    let err = LeafFile::open(&dir.join("missing.txt")).unwrap_err();
+    assert!(err.starts_with("failed to read"), "{err}");
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn compressed_and_bundled_files_are_loaded_instead() {
+    let dir = temp_dir("leaf-file-loaded");
+    let leaves = leaves(200);
+    let path = dir.join("instance-0-leaves.txt");
+    write_artifact(
+        &path,
+        leaf_text(&leaves).as_bytes(),
+        ArtifactCompression::zstd(3).unwrap(),
+    )
+    .expect("write zstd");
+    let file = LeafFile::open(&path).expect("open zstd");
+    assert!(!file.is_mapped());
+    assert_eq!(file.get(199), Ok(leaves[199]));
+    assert_eq!(
+        incremental_root_from_hashes(&file.block_hashes().unwrap()),
+        incremental_root(&leaves)
+    );
+
+    let mut bundle = BundleBuilder::new();
+    bundle
+        .add("instance-0-leaves.txt", leaf_text(&leaves).into_bytes())
+        .expect("add");
+    let bundle_path = dir.join("session.ppa");
+    bundle.write(&bundle_path).expect("write bundle");
+    let file = LeafFile::open(&bundle_path.join("instance-0-leaves.txt")).expect("open entry");
+    assert!(!file.is_mapped());
+    assert_eq!(file.len(), 200);
+    assert_eq!(file.get(42), Ok(leaves[42]));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn disputes_prepared_from_a_leaf_file_match_in_memory_ones() {
+    let dir = temp_dir("leaf-file-dispute");
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let bit_width = 4;
+    let circuit_id = circuit.circuit_id(bit_width, HIGHER_BID_WINS);
+    let seed = [0x21; 32];
+    let layout = CircuitLayout::new(circuit_id, 1, circuit.build(bit_width));
+    let mut claimed_leaves = garble_circuit(seed, &layout);
+    claimed_leaves[3][20] ^= 0x01;
+    claimed_leaves[9][0] ^= 0x01;
+    let path = dir.join("instance-1-leaves.txt");
+    write_leaves(&path, &claimed_leaves);
+
+    let in_memory = PrepareDisputeConfig {
+        circuit,
+        bit_width,
+        circuit_id,
+        instance_id: 1,
+        seed,
+        claimed_leaves: claimed_leaves.clone(),
+        gate_index: None,
+        allow_false_challenge: false,
+        expected_root_gc: Some(incremental_root(&claimed_leaves)),
+    };
+    let from_file = PrepareDisputeConfig {
+        circuit,
+        bit_width,
+        circuit_id,
+        instance_id: 1,
+        seed,
+        claimed_leaves: LeafFile::open(&path).expect("open"),
+        gate_index: None,
+        allow_false_challenge: false,
+        expected_root_gc: in_memory.expected_root_gc,
+    };
+    let expected = prepare_dispute_packet(&in_memory).expect("prepare in memory");
+    let prepared = prepare_dispute_packet(&from_file).expect("prepare from file");
+    assert_eq!(prepared.audit, expected.audit);
+    assert_eq!(prepared.audit.mismatch_indices(), [3, 9]);
+    assert_eq!(prepared.packet(&from_file), expected.packet(&in_memory));
+    assert_eq!(prepared.expected_leaf, expected.expected_leaf);
+
+    // A file with a gate missing is rejected before any hashing.
+    let short_path = dir.join("short-leaves.txt");
+    write_leaves(&short_path, &claimed_leaves[..claimed_leaves.len() - 1]);
+    let short = PrepareDisputeConfig {
+        claimed_leaves: LeafFile::open(&short_path).expect("open short"),
+        ..from_file
+    };
+    let err = prepare_dispute_packet(&short).unwrap_err();
+    assert!(err.starts_with("claimed leaves count"), "{err}");
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,105 @@
+//! Leaf format v2 (GRR3): compact leaves, evaluator support and the v2 block-hash domain.
+
+use off_chain_common::consensus::LEAF_BYTES_LEN;
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, derive_output_labels,
+    evaluate_garbled_circuit, u64_to_bits_le,
+};
+use off_chain_common::garble::{garble_circuit, garble_leaves};
+use off_chain_common::ih::{gc_block_hash, gc_block_hash_v2, incremental_root_v2};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{CircuitLayout, GarblingScheme, GateType, LeafFormat};
+
+mod common;
+
+use common::millionaires_layout;
+
+fn layout(scheme: GarblingScheme, leaf_format: LeafFormat) -> CircuitLayout {
+    millionaires_layout(2, 4)
+        .with_scheme(scheme)
+        .with_leaf_format(leaf_format)
+}
+
+#[test]
+fn v1_leaves_match_garble_circuit() {
+    let layout = layout(GarblingScheme::Classic, LeafFormat::V1);
+    let leaves = garble_leaves([1u8; 32], &layout);
+    let expected = garble_circuit([1u8; 32], &layout);
+    assert_eq!(leaves.len(), expected.len());
+    for (leaf, v1) in leaves.iter().zip(&expected) {
+        assert_eq!(leaf.as_slice(), v1.as_slice());
+    }
+}
+
+#[test]
+fn v2_leaves_drop_row_zero_and_free_gate_rows() {
+    for scheme in [GarblingScheme::Classic, GarblingScheme::FreeXor] {
+        let layout = layout(scheme, LeafFormat::V2);
+        let leaves = garble_leaves([2u8; 32], &layout);
+        for (gate, leaf) in layout.gates.iter().zip(&leaves) {
+            let free = gate.gate_type == GateType::Not
+                || (scheme == GarblingScheme::FreeXor && gate.gate_type == GateType::Xor);
+            assert_eq!(leaf.len(), if free { 8 } else { 8 + 3 * 16 }, "{gate:?}");
+        }
+        let total = leaves.iter().map(Vec::len).sum::<usize>();
+        assert!(total < layout.gates.len() * LEAF_BYTES_LEN);
+    }
+}
+
+#[test]
+fn v2_evaluation_matches_plain_comparison() {
+    let bit_width = 4;
+    let output_wire = build_millionaires_layout(bit_width).gt_wire;
+    for scheme in [GarblingScheme::Classic, GarblingScheme::FreeXor] {
+        let layout = layout(scheme, LeafFormat::V2);
+        let seed = [3u8; 32];
+        let leaves = garble_leaves(seed, &layout);
+        let hints = derive_not_gate_hints(seed, &layout);
+        let (label_false, label_true) = derive_output_labels(seed, &layout, output_wire).unwrap();
+        for x in 0..16u64 {
+            for y in 0..16u64 {
+                let alice =
+                    derive_layout_input_labels(seed, &layout, 0, &u64_to_bits_le(x, bit_width));
+                let bob = derive_layout_input_labels(
+                    seed,
+                    &layout,
+                    bit_width as u16,
+                    &u64_to_bits_le(y, bit_width),
+                );
+                let out =
+                    evaluate_garbled_circuit(&layout, &leaves, &alice, &bob, &hints, output_wire)
+                        .unwrap();
+                let expected = if x > y { label_true } else { label_false };
+                assert_eq!(out, expected, "{scheme:?} x={x} y={y}");
+            }
+        }
+    }
+}
+
+#[test]
+fn v2_block_hash_is_domain_separated_and_leaf_length_checked() {
+    let layout = layout(GarblingScheme::Classic, LeafFormat::V2);
+    let seed = [4u8; 32];
+    let mut leaves = garble_leaves(seed, &layout);
+    assert_ne!(
+        gc_block_hash_v2(0, &leaves[0]),
+        gc_block_hash(0, &leaves[0])
+    );
+    let root = incremental_root_v2(&leaves);
+
+    let and_gate = layout
+        .gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::And)
+        .unwrap();
+    leaves[and_gate].pop();
+    assert_ne!(incremental_root_v2(&leaves), root);
+
+    let alice = derive_layout_input_labels(seed, &layout, 0, &[0; 4]);
+    let bob = derive_layout_input_labels(seed, &layout, 4, &[0; 4]);
+    let hints = derive_not_gate_hints(seed, &layout);
+    let output_wire = build_millionaires_layout(4).gt_wire;
+    let err =
+        evaluate_garbled_circuit(&layout, &leaves, &alice, &bob, &hints, output_wire).unwrap_err();
+    assert!(err.contains("expected 56"), "{err}");
+}
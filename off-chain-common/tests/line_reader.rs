@@ -0,0 +1,121 @@
+//! Streaming value-line reader: comment/quote/bracket tolerance, line numbers in errors, and
+//! plain, zstd and bundled files read alike.
+
+use std::fs;
+
+use off_chain_common::bundle::BundleBuilder;
+use off_chain_common::cli::{hex_prefixed, parse_bytes32, parse_leaf71};
+use off_chain_common::compression::{ArtifactCompression, write_artifact};
+use off_chain_common::line_reader::{ValueLines, clean_value_line, read_value_lines};
+
+mod common;
+
+use common::temp_dir;
+
+#[test]
+fn clean_value_line_strips_comments_quotes_commas_and_brackets() {
+    let cases = [
+        ("0x11", "0x11"),
+        ("  [0x11],  # inline comment\n", "0x11"),
+        ("\"0x22\",\r\n", "0x22"),
+        ("[\n", ""),
+        ("]", ""),
+        ("# only a comment", ""),
+        ("   \n", ""),
+    ];
+    for (line, expected) in cases {
+        assert_eq!(clean_value_line(line), expected, "{line:?}");
+    }
+}
+
+#[test]
+fn value_lines_skip_blank_lines_and_keep_line_numbers() {
+    let dir = temp_dir("line-reader-plain");
+    let path = dir.join("roots.txt");
+    let one = format!("0x{}", "11".repeat(32));
+    let two = format!("0x{}", "22".repeat(32));
+    fs::write(
+        &path,
+        format!("# roots\n[\n  \"{one}\",\n\n  {two}  # last\n]\n"),
+    )
+    .expect("write");
+
+    let values = ValueLines::open(&path)
+        .expect("open")
+        .collect::<Result<Vec<_>, _>>()
+        .expect("lines");
+    assert_eq!(values, [(3, one), (5, two)]);
+
+    let parsed = read_value_lines(&path, "bytes32 line", parse_bytes32).expect("parse");
+    assert_eq!(parsed, [[0x11; 32], [0x22; 32]]);
+
+    fs::write(&path, "# nothing yet\n").expect("empty");
+    let parsed = read_value_lines(&path, "bytes32 line", parse_bytes32).expect("empty");
+    assert!(parsed.is_empty());
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn compressed_leaf_files_stream_and_name_the_bad_line() {
+    let dir = temp_dir("line-reader-zstd");
+    let path = dir.join("instance-0-leaves.txt");
+    let leaves = (0..20_000u32)
+        .map(|i| {
+            let mut leaf = [0u8; 71];
+            leaf[..4].copy_from_slice(&i.to_be_bytes());
+            leaf
+        })
+        .collect::<Vec<_>>();
+    let mut raw = String::new();
+    for leaf in &leaves {
+        raw.push_str(&hex_prefixed(leaf));
+        raw.push('\n');
+    }
+    let written = write_artifact(&path, raw.as_bytes(), ArtifactCompression::zstd(3).unwrap())
+        .expect("write");
+    assert!(fs::metadata(&written).unwrap().len() < raw.len() as u64 / 10);
+    assert_eq!(
+        read_value_lines(&path, "claimed leaf", parse_leaf71),
+        Ok(leaves)
+    );
+
+    raw.push_str("0x1234\n");
+    write_artifact(&path, raw.as_bytes(), ArtifactCompression::zstd(3).unwrap()).expect("rewrite");
+    let err = read_value_lines(&path, "claimed leaf", parse_leaf71).unwrap_err();
+    assert!(
+        err.starts_with(&format!(
+            "invalid claimed leaf at {}:20001:",
+            path.display()
+        )),
+        "{err}"
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn bundled_and_missing_files() {
+    let dir = temp_dir("line-reader-bundle");
+    let mut bundle = BundleBuilder::new();
+    bundle
+        .add(
+            "labels.txt",
+            format!("0x{}\n0x{}\n", "aa".repeat(32), "bb".repeat(32)).into_bytes(),
+        )
+        .expect("add");
+    let bundle_path = dir.join("session.ppa");
+    bundle.write(&bundle_path).expect("write bundle");
+
+    let parsed = read_value_lines(
+        &bundle_path.join("labels.txt"),
+        "bytes32 line",
+        parse_bytes32,
+    )
+    .expect("read from bundle");
+    assert_eq!(parsed, [[0xaa; 32], [0xbb; 32]]);
+
+    let err = ValueLines::open(&dir.join("missing.txt"))
+        .err()
+        .expect("missing");
+    assert!(err.starts_with("failed to read"), "{err}");
+    let _ = fs::remove_dir_all(dir);
+}
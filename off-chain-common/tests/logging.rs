@@ -0,0 +1,47 @@
+//! Log filters of the binaries: `-v` counts and `RUST_LOG`.
+
+use off_chain_common::logging::{log_filter, verbosity_filter};
+use tracing_subscriber::filter::LevelFilter;
+
+#[test]
+fn each_v_lowers_the_level() {
+    assert_eq!(verbosity_filter(0), "warn");
+    assert_eq!(verbosity_filter(1), "info");
+    assert_eq!(verbosity_filter(2), "debug");
+    assert_eq!(verbosity_filter(3), "trace");
+    assert_eq!(verbosity_filter(9), "trace");
+}
+
+#[test]
+fn without_rust_log_the_verbosity_decides() {
+    assert_eq!(
+        log_filter(None, 0).max_level_hint(),
+        Some(LevelFilter::WARN)
+    );
+    assert_eq!(
+        log_filter(None, 2).max_level_hint(),
+        Some(LevelFilter::DEBUG)
+    );
+}
+
+#[test]
+fn rust_log_overrides_the_verbosity() {
+    let filter = log_filter(Some("off_chain_common::retry=trace"), 0);
+    assert_eq!(filter.to_string(), "off_chain_common::retry=trace");
+    assert_eq!(
+        log_filter(Some("error"), 3).max_level_hint(),
+        Some(LevelFilter::ERROR)
+    );
+}
+
+#[test]
+fn blank_or_invalid_rust_log_falls_back_to_the_verbosity() {
+    assert_eq!(
+        log_filter(Some(" "), 1).max_level_hint(),
+        Some(LevelFilter::INFO)
+    );
+    assert_eq!(
+        log_filter(Some("retry=loud"), 1).max_level_hint(),
+        Some(LevelFilter::INFO)
+    );
+}
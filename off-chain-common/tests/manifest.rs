@@ -0,0 +1,227 @@
+//! `manifest.json`: recorded lengths and digests, tamper detection, reads through bundles and
+//! comparison with re-derived contents.
+
+use std::fs;
+use std::path::Path;
+
+use off_chain_common::bundle::BundleBuilder;
+use off_chain_common::compression::{ArtifactCompression, write_artifact};
+use off_chain_common::consensus::keccak256;
+use off_chain_common::manifest::{
+    ArtifactManifest, ArtifactRole, ExpectedArtifact, MANIFEST_FILE, ManifestInstance,
+    ManifestMismatch, verify_artifacts, verify_manifest,
+};
+
+mod common;
+
+use common::temp_dir;
+
+/// Writes a small instance-0 artifact set with its manifest into `dir`.
+fn write_artifact_set(dir: &Path) -> ArtifactManifest {
+    fs::write(dir.join("instance-0-seed.txt"), "0x11\n").expect("seed");
+    fs::write(dir.join("instance-0-root-gc.txt"), "0x22\n").expect("root");
+    let leaves = write_artifact(
+        &dir.join("instance-0-leaves.txt"),
+        b"0xaa\n0xbb\n",
+        ArtifactCompression::zstd(3).unwrap(),
+    )
+    .expect("leaves");
+    let leaves_name = leaves.file_name().unwrap().to_str().unwrap();
+
+    let mut manifest = ArtifactManifest::new("millionaires", 0, [0x33; 32], 8);
+    for (name, role) in [
+        ("instance-0-seed.txt", ArtifactRole::Seed),
+        (leaves_name, ArtifactRole::Leaves),
+        ("instance-0-root-gc.txt", ArtifactRole::RootGc),
+    ] {
+        manifest.add_file(dir, name, role, Some(0)).expect("add");
+    }
+    manifest.instances.push(ManifestInstance {
+        instance_id: 0,
+        blob_hash_gc: [0x44; 32],
+        cid: None,
+    });
+    manifest.write(dir).expect("write manifest");
+    manifest
+}
+
+#[test]
+fn manifests_record_sorted_files_and_round_trip() {
+    let dir = temp_dir("manifest-round-trip");
+    let written = write_artifact_set(&dir);
+
+    let names = written
+        .files
+        .iter()
+        .map(|file| file.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        [
+            "instance-0-leaves.txt.zst",
+            "instance-0-root-gc.txt",
+            "instance-0-seed.txt"
+        ]
+    );
+    let seed = written.file("instance-0-seed.txt").expect("seed entry");
+    assert_eq!((seed.length, seed.keccak256), (5, keccak256(&[b"0x11\n"])));
+    let leaves = written
+        .file("instance-0-leaves.txt.zst")
+        .expect("leaves entry");
+    assert_eq!(
+        leaves.length,
+        fs::metadata(dir.join(&leaves.name)).unwrap().len()
+    );
+
+    let raw = fs::read_to_string(dir.join(MANIFEST_FILE)).expect("raw");
+    assert!(raw.contains(r#""role":"root_gc""#), "{raw}");
+    assert!(!raw.contains('\n'), "{raw}");
+    assert_eq!(ArtifactManifest::read(&dir), Ok(written));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn verify_manifest_reports_tampered_missing_and_unlisted_files() {
+    let dir = temp_dir("manifest-tamper");
+    write_artifact_set(&dir);
+    let report = verify_manifest(&dir).expect("verify");
+    assert!(report.is_intact());
+    assert!(report.unlisted.is_empty());
+
+    fs::write(dir.join("instance-0-seed.txt"), "0x12\n").expect("tamper");
+    fs::write(dir.join("instance-0-root-gc.txt"), "0x2\n").expect("resize");
+    fs::remove_file(dir.join("instance-0-leaves.txt.zst")).expect("remove");
+    fs::write(dir.join("bob-ot-choices.txt"), "0x01\n").expect("extra");
+
+    let report = verify_manifest(&dir).expect("verify");
+    assert!(!report.is_intact());
+    assert_eq!(
+        report.mismatches,
+        [
+            ManifestMismatch::Missing {
+                name: "instance-0-leaves.txt.zst".to_string()
+            },
+            ManifestMismatch::Length {
+                name: "instance-0-root-gc.txt".to_string(),
+                expected: 5,
+                actual: 4
+            },
+            ManifestMismatch::Digest {
+                name: "instance-0-seed.txt".to_string()
+            },
+        ]
+    );
+    assert_eq!(report.unlisted, ["bob-ot-choices.txt"]);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn manifests_verify_inside_bundles() {
+    let dir = temp_dir("manifest-bundle");
+    let artifacts = dir.join("artifacts");
+    fs::create_dir_all(&artifacts).expect("artifacts");
+    write_artifact_set(&artifacts);
+    let path = dir.join("session.ppa");
+    let mut bundle = BundleBuilder::new();
+    bundle.add_dir("", &artifacts).expect("add dir");
+    bundle.write(&path).expect("write");
+
+    let report = verify_manifest(&path).expect("verify");
+    assert!(report.is_intact(), "{:?}", report.mismatches);
+    assert_eq!(report.manifest.circuit, "millionaires");
+    assert_eq!(
+        report.manifest_hash,
+        keccak256(&[&fs::read(artifacts.join(MANIFEST_FILE)).unwrap()])
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rejects_unreadable_or_unknown_manifests() {
+    let dir = temp_dir("manifest-invalid");
+    let err = verify_manifest(&dir).unwrap_err();
+    assert!(err.starts_with("failed to read"), "{err}");
+
+    let manifest = write_artifact_set(&dir);
+    let raw = manifest.to_json().expect("json");
+    let cases = [
+        (
+            raw.replace(r#""version":1"#, r#""version":2"#),
+            "unsupported manifest version 2",
+        ),
+        (
+            raw.replace(r#""role":"seed""#, r#""role":"key""#),
+            "invalid JSON",
+        ),
+        (raw.replacen('{', r#"{"extra":1,"#, 1), "invalid JSON"),
+    ];
+    for (raw, expected) in cases {
+        fs::write(dir.join(MANIFEST_FILE), raw).expect("write case");
+        let err = verify_manifest(&dir).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+    }
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn verify_artifacts_compares_decompressed_contents_with_rederived_ones() {
+    let dir = temp_dir("manifest-rederived");
+    write_artifact_set(&dir);
+    let expected = |name: &str, bytes: &[u8]| ExpectedArtifact {
+        name: name.to_string(),
+        bytes: bytes.to_vec(),
+    };
+    let instance = ManifestInstance {
+        instance_id: 0,
+        blob_hash_gc: [0x44; 32],
+        cid: None,
+    };
+    let artifacts = [
+        expected("instance-0-seed.txt", b"0x11\n"),
+        expected("instance-0-leaves.txt", b"0xaa\n0xbb\n"),
+    ];
+    let report = verify_artifacts(&dir, &artifacts, std::slice::from_ref(&instance)).unwrap();
+    assert!(report.is_intact(), "{:?}", report.mismatches);
+
+    // A manifest rewritten over a forged file passes its digests but not the re-derivation.
+    fs::write(dir.join("instance-0-seed.txt"), "0x10\n").expect("forge");
+    let mut manifest = ArtifactManifest::read(&dir).expect("read");
+    manifest
+        .files
+        .retain(|file| file.name != "instance-0-seed.txt");
+    manifest
+        .add_file(&dir, "instance-0-seed.txt", ArtifactRole::Seed, Some(0))
+        .expect("add");
+    manifest.write(&dir).expect("rewrite");
+    assert!(verify_manifest(&dir).unwrap().is_intact());
+
+    let artifacts = [
+        expected("instance-0-seed.txt", b"0x11\n"),
+        expected("instance-0-leaves.txt", b"0xaa\n0xbc\n"),
+        expected("instance-0-root-ot.txt", b"0x55\n"),
+    ];
+    let other = ManifestInstance {
+        instance_id: 1,
+        blob_hash_gc: [0x44; 32],
+        cid: None,
+    };
+    let report = verify_artifacts(&dir, &artifacts, &[instance, other]).unwrap();
+    assert_eq!(
+        report.mismatches,
+        [
+            ManifestMismatch::Content {
+                name: "instance-0-seed.txt".to_string(),
+                offset: 3
+            },
+            ManifestMismatch::Content {
+                name: "instance-0-leaves.txt.zst".to_string(),
+                offset: 8
+            },
+            ManifestMismatch::Unrecorded {
+                name: "instance-0-root-ot.txt".to_string()
+            },
+            ManifestMismatch::BlobHash { instance_id: 1 },
+        ]
+    );
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,148 @@
+//! `manifest.sig.json`: Alice's signature over a manifest, bound to the contract and checked
+//! against the expected signer, in directories and bundles.
+
+use std::fs;
+use std::path::Path;
+
+use off_chain_common::bundle::BundleBuilder;
+use off_chain_common::consensus::keccak256;
+use off_chain_common::identity::{EthIdentity, recover_personal_sign};
+use off_chain_common::manifest::{
+    ArtifactManifest, ArtifactRole, MANIFEST_FILE, MANIFEST_SIGNATURE_FILE, ManifestSignature,
+    manifest_signing_digest, sign_manifest, verify_manifest,
+};
+
+mod common;
+
+use common::temp_dir;
+
+const CONTRACT: [u8; 20] = [0xc0; 20];
+
+fn alice() -> EthIdentity {
+    EthIdentity::from_private_key([0x11; 32]).expect("key")
+}
+
+/// Writes one seed file and its manifest into `dir`; returns the manifest's keccak256.
+fn write_manifest(dir: &Path, seed: &str) -> [u8; 32] {
+    fs::write(dir.join("instance-0-seed.txt"), seed).expect("seed");
+    let mut manifest = ArtifactManifest::new("millionaires", 0, [0x33; 32], 8);
+    manifest
+        .add_file(dir, "instance-0-seed.txt", ArtifactRole::Seed, Some(0))
+        .expect("add");
+    manifest.write(dir).expect("write manifest");
+    keccak256(&[&fs::read(dir.join(MANIFEST_FILE)).unwrap()])
+}
+
+#[test]
+fn signed_manifests_round_trip_and_recover_alice() {
+    let dir = temp_dir("manifest-sig-round-trip");
+    let manifest_hash = write_manifest(&dir, "0x11\n");
+    let alice = alice();
+    let signed = sign_manifest(&dir, CONTRACT, &alice).expect("sign");
+    assert_eq!(signed.manifest_hash, manifest_hash);
+    assert_eq!(signed.signer, alice.address());
+    assert_eq!(
+        recover_personal_sign(
+            manifest_signing_digest(CONTRACT, manifest_hash),
+            &signed.signature
+        ),
+        Ok(alice.address())
+    );
+
+    let read = ManifestSignature::read(&dir).expect("read");
+    assert_eq!(read, signed);
+    assert_eq!(
+        read.verify(CONTRACT, manifest_hash, alice.address()),
+        Ok(())
+    );
+
+    // The signature sits next to the manifest without being reported as unlisted.
+    let report = verify_manifest(&dir).expect("verify");
+    assert!(report.is_intact() && report.unlisted.is_empty());
+    assert_eq!(report.manifest_hash, manifest_hash);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rejects_other_contracts_signers_and_rewritten_manifests() {
+    let dir = temp_dir("manifest-sig-reject");
+    let manifest_hash = write_manifest(&dir, "0x11\n");
+    let alice = alice();
+    let signed = sign_manifest(&dir, CONTRACT, &alice).expect("sign");
+
+    let err = signed
+        .verify([0xc1; 20], manifest_hash, alice.address())
+        .unwrap_err();
+    assert!(err.starts_with("manifest is signed for contract"), "{err}");
+
+    let mallory = EthIdentity::from_private_key([0x66; 32]).expect("key");
+    let err = signed
+        .verify(CONTRACT, manifest_hash, mallory.address())
+        .unwrap_err();
+    assert!(err.starts_with("manifest is signed by"), "{err}");
+
+    // A forged file with a manifest rewritten over it no longer matches the signed hash.
+    let rewritten = write_manifest(&dir, "0x12\n");
+    assert_ne!(rewritten, manifest_hash);
+    let err = signed
+        .verify(CONTRACT, rewritten, alice.address())
+        .unwrap_err();
+    assert!(err.starts_with("signature covers manifest"), "{err}");
+
+    // Claiming another signer does not change who recovers.
+    let mut relabeled = signed.clone();
+    relabeled.signer = mallory.address();
+    assert_eq!(relabeled.recover(), Ok(alice.address()));
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn signatures_verify_inside_bundles() {
+    let dir = temp_dir("manifest-sig-bundle");
+    let artifacts = dir.join("artifacts");
+    fs::create_dir_all(&artifacts).expect("artifacts");
+    let manifest_hash = write_manifest(&artifacts, "0x11\n");
+    let alice = alice();
+    sign_manifest(&artifacts, CONTRACT, &alice).expect("sign");
+
+    let path = dir.join("session.ppa");
+    let mut bundle = BundleBuilder::new();
+    bundle.add_dir("", &artifacts).expect("add dir");
+    bundle.write(&path).expect("write");
+
+    let signed = ManifestSignature::read(&path).expect("read from bundle");
+    let report = verify_manifest(&path).expect("verify");
+    assert_eq!(report.manifest_hash, manifest_hash);
+    assert_eq!(
+        signed.verify(CONTRACT, report.manifest_hash, alice.address()),
+        Ok(())
+    );
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn rejects_missing_or_malformed_signature_files() {
+    let dir = temp_dir("manifest-sig-invalid");
+    write_manifest(&dir, "0x11\n");
+    let err = ManifestSignature::read(&dir).unwrap_err();
+    assert!(err.starts_with("failed to read"), "{err}");
+
+    let raw = sign_manifest(&dir, CONTRACT, &alice())
+        .expect("sign")
+        .to_json()
+        .expect("json");
+    let cases = [
+        (
+            raw.replace(r#""version":1"#, r#""version":2"#),
+            "unsupported manifest signature version 2",
+        ),
+        (raw.replacen("0x", "0x00", 1), "invalid JSON"),
+        (raw.replacen('{', r#"{"extra":1,"#, 1), "invalid JSON"),
+    ];
+    for (raw, expected) in cases {
+        fs::write(dir.join(MANIFEST_SIGNATURE_FILE), raw).expect("write case");
+        let err = ManifestSignature::read(&dir).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+    }
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,176 @@
+//! One garbler serving several bidders: wire ranges, per-bidder packages and sessions.
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::evaluation::{
+    derive_not_gate_hints, derive_output_labels, evaluate_garbled_wires, u64_to_bits_le,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::labels::derive_label_pair;
+use off_chain_common::messages::{EvalPackageMessage, Message};
+use off_chain_common::protocol::{
+    CommitPayload, DepositPayload, Party, Phase, PhasePayload, ProtocolSession,
+};
+use off_chain_common::scenario::participants::ParticipantLayout;
+use off_chain_common::scenario::{SessionParams, build_argmax_layout, com_seed};
+use off_chain_common::types::CircuitLayout;
+
+const BIT_WIDTH: usize = 4;
+const INSTANCE_ID: u64 = 3;
+
+fn package(bit_width: usize, alice_labels: Vec<[u8; 16]>) -> EvalPackageMessage {
+    EvalPackageMessage {
+        bit_width: bit_width as u16,
+        decoding: OutputDecodingTable::from_openings(
+            [0x11; 32],
+            INSTANCE_ID,
+            0,
+            [[0x22; 32], [0x33; 32]],
+            [[0x44; 32], [0x55; 32]],
+        ),
+        gc_leaves: Vec::new(),
+        alice_labels,
+        not_hints: Vec::new(),
+        ot_setup: None,
+    }
+}
+
+#[test]
+fn assigns_input_wires_in_participant_order() {
+    let two_party = ParticipantLayout::two_party(8);
+    assert_eq!(two_party.garbler_wires(), 0..8);
+    assert_eq!(two_party.bidder_wires(0), Ok(8..16));
+    assert_eq!(two_party.parties(), vec![Party::Garbler, Party::Evaluator]);
+    assert_eq!(two_party.owner(15), Some(Party::Evaluator));
+    assert_eq!(two_party.owner(16), None);
+
+    let auction = ParticipantLayout::auction(BIT_WIDTH, 3).expect("auction");
+    assert_eq!(auction.input_wire_count(), 12);
+    assert!(auction.garbler_wires().is_empty());
+    assert_eq!(auction.bidder_wires(2), Ok(8..12));
+    assert_eq!(auction.owner(0), Some(Party::Bidder(0)));
+    assert_eq!(auction.owner(7), Some(Party::Bidder(1)));
+    assert!(auction.bidder_wires(3).is_err());
+    assert!(ParticipantLayout::auction(BIT_WIDTH, 1).is_err());
+}
+
+#[test]
+fn bidders_evaluate_the_argmax_from_their_own_packages() {
+    let bids = [5u64, 13, 9];
+    let participants = ParticipantLayout::auction(BIT_WIDTH, bids.len()).expect("auction");
+    let argmax = build_argmax_layout(BIT_WIDTH, bids.len());
+    let circuit_id = keccak256(&[b"argmax-multi-party"]);
+    let seed = keccak256(&[b"auctioneer-seed"]);
+    let layout = CircuitLayout::new(circuit_id, INSTANCE_ID, argmax.gates.clone());
+
+    let rows = (0..participants.input_wire_count() as u16)
+        .map(|wire| derive_label_pair(circuit_id, INSTANCE_ID, wire, seed))
+        .collect::<Vec<_>>();
+    let mut shared = package(BIT_WIDTH, Vec::new());
+    shared.gc_leaves = garble_circuit(seed, &layout);
+    shared.not_hints = derive_not_gate_hints(seed, &layout);
+    let packages = participants
+        .bidder_packages(INSTANCE_ID, &shared, &rows, false)
+        .expect("packages");
+    assert_eq!(packages.len(), bids.len());
+
+    // Each bidder only sees its own wire range and picks the labels of its bid.
+    let selected = packages
+        .iter()
+        .map(|bidder_package| {
+            let received =
+                Message::from_cbor(&Message::LabelOffer(bidder_package.inputs.clone()).to_cbor())
+                    .expect("decode");
+            let Message::LabelOffer(offer) = received else {
+                panic!("expected a label offer");
+            };
+            assert_eq!(
+                offer.first_wire,
+                participants
+                    .bidder_wires(bidder_package.bidder)
+                    .unwrap()
+                    .start
+            );
+            u64_to_bits_le(bids[bidder_package.bidder], BIT_WIDTH)
+                .iter()
+                .zip(&offer.rows)
+                .map(|(bit, row)| row[*bit as usize])
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let inputs = participants
+        .input_labels(&packages[0].package.alice_labels, &selected)
+        .expect("inputs");
+    let wires = evaluate_garbled_wires(
+        &layout,
+        &packages[0].package.gc_leaves,
+        &inputs,
+        &packages[0].package.not_hints,
+    )
+    .expect("evaluate");
+    let decode = |wire: u16| {
+        let (label0, label1) = derive_output_labels(seed, &layout, wire).unwrap();
+        let active = wires[wire as usize].expect("output label");
+        assert!(active == label0 || active == label1);
+        (active == label1) as u8
+    };
+    let winner = argmax
+        .index_wires
+        .iter()
+        .enumerate()
+        .map(|(bit, wire)| (decode(*wire) as usize) << bit)
+        .sum::<usize>();
+    assert_eq!(winner, 1);
+}
+
+#[test]
+fn rejects_mismatched_packages_and_labels() {
+    let participants = ParticipantLayout::auction(BIT_WIDTH, 2).expect("auction");
+    let rows = vec![[[0u8; 16]; 2]; 2 * BIT_WIDTH];
+    let err = participants
+        .bidder_packages(0, &package(BIT_WIDTH, vec![[0; 16]]), &rows, false)
+        .unwrap_err();
+    assert!(err.contains("garbler labels"), "{err}");
+    let err = participants
+        .bidder_packages(0, &package(BIT_WIDTH, Vec::new()), &rows[1..], true)
+        .unwrap_err();
+    assert!(err.contains("label rows"), "{err}");
+
+    let err = participants
+        .input_labels(&[], &[vec![[0; 16]; BIT_WIDTH], vec![[0; 16]; 3]])
+        .unwrap_err();
+    assert_eq!(err, "bidder 1 sent 3 labels, expected 4");
+    assert!(
+        participants
+            .input_labels(&[], &[vec![[0; 16]; BIT_WIDTH]])
+            .is_err()
+    );
+}
+
+#[test]
+fn session_waits_for_every_bidder_deposit() {
+    let n = 3;
+    let participants = ParticipantLayout::auction(BIT_WIDTH, 3).expect("auction");
+    let mut session =
+        ProtocolSession::with_participants(SessionParams::new(n).expect("n"), &participants);
+    let deposit = |party| PhasePayload::Deposit(DepositPayload { party });
+    let commit = PhasePayload::Commit(CommitPayload {
+        com_seeds: (0..n).map(|idx| com_seed([idx as u8; 32])).collect(),
+        root_gcs: vec![[0; 32]; n],
+    });
+
+    let err = session.apply(deposit(Party::Evaluator)).unwrap_err();
+    assert!(err.contains("not a party"), "{err}");
+    for party in [Party::Garbler, Party::Bidder(0), Party::Bidder(2)] {
+        assert_eq!(session.apply(deposit(party)), Ok(Phase::Deposit));
+    }
+    let err = session.apply(commit.clone()).unwrap_err();
+    assert_eq!(err, "commit requires deposits from all 4 parties, got 3");
+
+    session
+        .apply(deposit(Party::Bidder(1)))
+        .expect("last deposit");
+    assert_eq!(session.apply(commit), Ok(Phase::Commit));
+    assert_eq!(session.parties(), participants.parties().as_slice());
+}
@@ -0,0 +1,308 @@
+//! Orchestrated happy path: the steps each party runs and the values threaded between them.
+
+use std::path::PathBuf;
+
+use off_chain_common::cli::{hex_prefixed, hex32};
+use off_chain_common::consensus::{HIGHER_BID_WINS, keccak256};
+use off_chain_common::dispute::{PrepareDisputeConfig, prepare_dispute_packet};
+use off_chain_common::leaf_file::LeafFile;
+use off_chain_common::orchestrate::{
+    ANVIL_KEYS, FAULT_KINDS, OrchestratePlan, buyer_seed, corrupt_instance, corrupted_instance_id,
+    deployed_to, fault_of_kind, instance_leaves, offered_namehashes, output_value, root_gcs_with,
+    verifier_seed,
+};
+use off_chain_common::protocol::Party;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::scenario::SessionParams;
+use off_chain_common::scenario::faults::Fault;
+use off_chain_common::types::GateType;
+
+mod common;
+
+use common::temp_path;
+
+fn plan(buyers: usize) -> OrchestratePlan {
+    OrchestratePlan::new(
+        57,
+        ANVIL_KEYS[0].to_string(),
+        ANVIL_KEYS[1..=buyers]
+            .iter()
+            .map(|key| key.to_string())
+            .collect(),
+        vec![70, 100, 90][..buyers].to_vec(),
+        5,
+        PathBuf::from("/tmp/run"),
+    )
+    .expect("valid plan")
+}
+
+fn commands(steps: &[off_chain_common::orchestrate::Step]) -> Vec<(String, &str)> {
+    steps
+        .iter()
+        .map(|step| (step.party_name(), step.command))
+        .collect()
+}
+
+#[test]
+fn setup_runs_every_party_up_to_choose() {
+    let plan = plan(2);
+    assert_eq!(
+        hex_prefixed(&plan.buyers[0]),
+        "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"
+    );
+    let steps = plan.steps_until_choose();
+    let expected = [
+        ("alice", "deposit"),
+        ("buyer0", "deposit"),
+        ("buyer1", "deposit"),
+        ("buyer0", "commit-verifier-seed"),
+        ("buyer1", "commit-verifier-seed"),
+        ("buyer0", "reveal-verifier-seed"),
+        ("buyer1", "reveal-verifier-seed"),
+        ("alice", "submit-core-commitments"),
+        ("alice", "submit-ot-roots"),
+        ("alice", "submit-ot-roots"),
+        ("buyer0", "buyer-ready"),
+        ("buyer1", "buyer-ready"),
+        ("buyer0", "choose"),
+    ]
+    .map(|(party, command)| (party.to_string(), command));
+    assert_eq!(commands(&steps), expected);
+
+    let (seed, salt) = buyer_seed(1);
+    assert_eq!(
+        steps[4].args,
+        ["--seed", &hex32(seed), "--salt", &hex32(salt)]
+    );
+    let mut aggregate = buyer_seed(0).0;
+    aggregate.iter_mut().zip(seed).for_each(|(a, b)| *a ^= b);
+    assert_eq!(verifier_seed(2), aggregate);
+    assert!(steps[9].args.contains(&hex32(aggregate)));
+    assert!(steps[9].args.contains(&hex_prefixed(&plan.buyers[1])));
+    assert!(steps[7].args.contains(&"70,100".to_string()));
+}
+
+#[test]
+fn buyer_zero_evaluates_and_settles_instance_m() {
+    let plan = plan(3);
+    let steps = plan.steps_from_open(4);
+    let parties = commands(&steps);
+    assert_eq!(parties[0], ("alice".to_string(), "reveal-openings"));
+    assert_eq!(
+        parties[1..4]
+            .iter()
+            .map(|(party, command)| format!("{party} {command}"))
+            .collect::<Vec<_>>(),
+        [
+            "buyer0 close-dispute",
+            "buyer1 close-dispute",
+            "buyer2 close-dispute"
+        ]
+    );
+    assert_eq!(
+        parties[4..]
+            .iter()
+            .map(|(_, command)| *command)
+            .collect::<Vec<_>>(),
+        [
+            "prepare-eval",
+            "ot-choose",
+            "ot-respond",
+            "evaluate-m",
+            "reveal-labels",
+            "settle-auction",
+            "finalize-assignment"
+        ]
+    );
+    assert!(
+        steps
+            .iter()
+            .filter(|step| matches!(step.party, Party::Bidder(_)))
+            .skip(3)
+            .all(|step| step.party == Party::Bidder(0))
+    );
+    assert_eq!(steps[0].args[..2], ["--m", "4"]);
+    assert!(steps[5].args.contains(&"70".to_string()));
+    assert!(
+        steps[8]
+            .args
+            .contains(&"/tmp/run/export/instance-4-eval-blob.bin".to_string())
+    );
+    assert!(steps[9].args.contains(&hex32(offered_namehashes()[0])));
+}
+
+#[test]
+fn keys_only_travel_through_the_environment() {
+    let plan = plan(3);
+    let steps = [plan.steps_until_choose(), plan.steps_from_open(1)].concat();
+    assert!(steps.iter().all(|step| {
+        ANVIL_KEYS
+            .iter()
+            .all(|key| !step.args.iter().any(|arg| arg.contains(&key[2..])))
+    }));
+    assert_eq!(
+        plan.party_env(Party::Garbler),
+        [("ALICE_PRIVATE_KEY", ANVIL_KEYS[0].to_string())]
+    );
+    let buyer_env = plan.party_env(Party::Bidder(2));
+    assert_eq!(buyer_env[0], ("BOB_PRIVATE_KEY", ANVIL_KEYS[3].to_string()));
+    assert_eq!(buyer_env[1].0, "BOB_OT_SECRET");
+    assert_ne!(buyer_env[1], plan.party_env(Party::Bidder(0))[1]);
+}
+
+#[test]
+fn deployment_and_outputs_are_parsed() {
+    let plan = plan(3);
+    let args = plan.constructor_args("0xada", [1; 32], [2; 32]);
+    assert_eq!(args[0], args[1]);
+    assert_eq!(args[3..], ["0xada", &hex32([1; 32]), &hex32([2; 32]), "57"]);
+    let [buyers, receivers] = plan.register_buyers_args().expect("two more buyers");
+    assert_eq!(buyers, receivers);
+    assert_eq!(buyers.matches("0x").count(), 2);
+    assert_eq!(self::plan(1).register_buyers_args(), None);
+
+    let forge = "Compiling...\n{\"deployer\":\"0xf3\",\"deployedTo\":\"0x5fbd\",\"transactionHash\":\"0x1\"}\n";
+    assert_eq!(deployed_to(forge), Some("0x5fbd".to_string()));
+    assert_eq!(deployed_to("No files changed"), None);
+    let stdout = "status=ok\nselected_m=3\nmatch_expected=true\nselected_m=7\n";
+    assert_eq!(output_value(stdout, "selected_m"), Some("7".to_string()));
+    assert_eq!(output_value(stdout, "selected"), None);
+}
+
+#[test]
+fn invalid_runs_are_rejected_before_anything_is_sent() {
+    let keys = || vec![ANVIL_KEYS[1].to_string(), ANVIL_KEYS[2].to_string()];
+    let new = |keys, bids, x| {
+        OrchestratePlan::new(8, ANVIL_KEYS[0].to_string(), keys, bids, x, PathBuf::new())
+    };
+    assert_eq!(
+        new(keys(), vec![1], 0).unwrap_err(),
+        "--bids lists 1 bids for 2 buyers"
+    );
+    assert_eq!(
+        new(keys(), vec![1, 256], 0).unwrap_err(),
+        "bid 256 does not fit bit-width 8"
+    );
+    assert_eq!(
+        new(keys(), vec![1, 2], 300).unwrap_err(),
+        "x 300 does not fit bit-width 8"
+    );
+    assert!(new(vec![], vec![], 0).is_err());
+    assert!(
+        new(vec!["0x12".to_string()], vec![1], 0)
+            .unwrap_err()
+            .starts_with("invalid private key")
+    );
+}
+
+#[test]
+fn every_fault_kind_is_disputed_at_its_gate() {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let master_seed = keccak256(&[b"master-seed-v1"]);
+    let dir = temp_path("orchestrate-dispute");
+    assert_eq!((corrupted_instance_id(0), corrupted_instance_id(6)), (1, 0));
+
+    let gates = circuit.build(8);
+    let and_gate = gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::And)
+        .unwrap();
+    let not_gate = gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::Not)
+        .unwrap();
+    let swap_not_rows = fault_of_kind("swap-rows", not_gate).unwrap();
+    let err = corrupt_instance(circuit, 8, master_seed, 1, swap_not_rows).unwrap_err();
+    assert!(err.ends_with("swap is a no-op"), "{err}");
+
+    for (kind, gate_index) in FAULT_KINDS.into_iter().zip([3, and_gate, 7]) {
+        let fault = fault_of_kind(kind, gate_index).unwrap();
+        let corrupted = corrupt_instance(circuit, 8, master_seed, 1, fault).unwrap();
+        let (seed, honest) = instance_leaves(circuit, 8, master_seed, 1);
+        assert_eq!(corrupted.seed, seed);
+        assert_ne!(corrupted.leaves[gate_index], honest[gate_index], "{kind}");
+
+        let path = corrupted.write_leaves(&dir).unwrap();
+        assert!(path.ends_with("instance-1-leaves.txt"));
+        let config = PrepareDisputeConfig {
+            circuit,
+            bit_width: 8,
+            circuit_id: circuit.circuit_id(8, HIGHER_BID_WINS),
+            instance_id: 1,
+            seed,
+            claimed_leaves: LeafFile::open(&path).unwrap(),
+            gate_index: None,
+            allow_false_challenge: false,
+            expected_root_gc: Some(corrupted.root_gc),
+        };
+        let prepared = prepare_dispute_packet(&config).unwrap();
+        assert_eq!(prepared.gate_index, gate_index, "{}", corrupted.description);
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(fault_of_kind("truncate", 0).is_err());
+    let truncate = Fault::TruncateLeaf {
+        gate_index: 0,
+        len: 70,
+    };
+    assert!(corrupt_instance(circuit, 8, master_seed, 1, truncate).is_err());
+}
+
+#[test]
+fn the_cheating_run_commits_the_corrupted_root_and_disputes_it() {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit");
+    let master_seed = keccak256(&[b"master-seed-v1"]);
+    let fault = fault_of_kind("flip-row", 2).unwrap();
+    let corrupted = corrupt_instance(circuit, 8, master_seed, 0, fault).unwrap();
+    let params = SessionParams::default();
+    let root_gcs = root_gcs_with(circuit, 8, master_seed, params, &corrupted);
+    assert_eq!(root_gcs.len(), params.n);
+    assert_eq!(root_gcs[0], corrupted.root_gc);
+    let honest = root_gcs_with(
+        circuit,
+        8,
+        master_seed,
+        params,
+        &corrupt_instance(circuit, 8, master_seed, 9, fault).unwrap(),
+    );
+    assert_ne!(honest[0], root_gcs[0]);
+    assert_eq!(honest[1..9], root_gcs[1..9]);
+
+    let plan = plan(2);
+    let setup = plan.setup_steps();
+    assert_eq!(setup.last().unwrap().command, "reveal-verifier-seed");
+    let commit = plan.commitment_steps(Some(&root_gcs));
+    assert_eq!(
+        [setup.clone(), plan.commitment_steps(None)].concat(),
+        plan.steps_until_choose()
+    );
+    let roots_csv = root_gcs.iter().map(|root| hex32(*root)).collect::<Vec<_>>();
+    assert_eq!(
+        commit[0].args[commit[0].args.len() - 2..],
+        ["--root-gcs".to_string(), roots_csv.join(",")]
+    );
+    assert_eq!(commit.last().unwrap().command, "choose");
+
+    let steps = plan.dispute_steps(3, &corrupted);
+    assert_eq!(
+        commands(&steps),
+        [
+            ("alice".to_string(), "reveal-openings"),
+            ("buyer0".to_string(), "auto-dispute")
+        ]
+    );
+    assert_eq!(
+        steps[1].args,
+        [
+            "--instance-id",
+            "0",
+            "--seed",
+            &hex32(corrupted.seed),
+            "--artifacts-dir",
+            "/tmp/run/dispute",
+            "--bit-width",
+            "57",
+            "--yes"
+        ]
+    );
+}
@@ -0,0 +1,140 @@
+//! IKNP OT extension: many y-labels from a fixed number of base OTs, matching the plain
+//! label-offer selection.
+
+use k256::ProjectivePoint;
+use off_chain_common::base_ot::decode_ot_point;
+use off_chain_common::consensus::keccak256;
+use off_chain_common::evaluation::derive_bob_label_offers;
+use off_chain_common::ot_extension::{OT_EXTENSION_K, OtExtReceiver, OtExtSender};
+
+const SESSION: [u8; 32] = [0x5e; 32];
+
+fn offers(wire_count: usize) -> Vec<([u8; 16], [u8; 16])> {
+    derive_bob_label_offers([0x71u8; 32], keccak256(&[b"circuit"]), 3, wire_count)
+}
+
+fn choice_bits(wire_count: usize) -> Vec<u8> {
+    (0..wire_count)
+        .map(|idx| keccak256(&[b"bits", &[idx as u8, (idx >> 8) as u8]])[0] & 1)
+        .collect()
+}
+
+fn transfer(bits: &[u8]) -> (Vec<[[u8; 16]; 2]>, Vec<[u8; 16]>) {
+    let mut receiver = OtExtReceiver::new([0xb2u8; 32], SESSION, bits);
+    let sender = OtExtSender::new([0xa1u8; 32], &receiver.setup_message()).expect("sender");
+    let request = receiver
+        .extend(&sender.base_choice_messages())
+        .expect("extend");
+    let responses = sender
+        .respond(&request, &offers(bits.len()))
+        .expect("respond");
+    let labels = receiver.decrypt(&responses).expect("decrypt");
+    (responses, labels)
+}
+
+#[test]
+fn extension_matches_plain_offer_selection() {
+    for wire_count in [1, 8, 13, 300] {
+        let bits = choice_bits(wire_count);
+        let (_, labels) = transfer(&bits);
+        let expected = offers(wire_count)
+            .into_iter()
+            .zip(&bits)
+            .map(|((l0, l1), bit)| if *bit == 0 { l0 } else { l1 })
+            .collect::<Vec<_>>();
+        assert_eq!(labels, expected, "wire_count={wire_count}");
+    }
+}
+
+#[test]
+fn unchosen_rows_stay_opaque() {
+    let bits = choice_bits(64);
+    let (responses, labels) = transfer(&bits);
+    for (((rows, label), bit), (l0, l1)) in responses.iter().zip(&labels).zip(&bits).zip(offers(64))
+    {
+        let (chosen, other) = if *bit == 0 { (0, l1) } else { (1, l0) };
+        let key = std::array::from_fn::<u8, 16, _>(|i| rows[chosen][i] ^ label[i]);
+        let opened = std::array::from_fn::<u8, 16, _>(|i| rows[1 - chosen][i] ^ key[i]);
+        assert_ne!(opened, other);
+        assert!(rows[0] != l0 && rows[1] != l1);
+    }
+}
+
+#[test]
+fn base_ot_count_is_independent_of_wire_count() {
+    let mut receiver = OtExtReceiver::new([0xb2u8; 32], SESSION, &choice_bits(512));
+    let sender = OtExtSender::new([0xa1u8; 32], &receiver.setup_message()).expect("sender");
+    let request = receiver
+        .extend(&sender.base_choice_messages())
+        .expect("extend");
+    assert_eq!(sender.base_choice_messages().len(), OT_EXTENSION_K);
+    assert_eq!(request.base_responses.len(), OT_EXTENSION_K);
+    assert!(request.columns.iter().all(|column| column.len() == 512 / 8));
+}
+
+#[test]
+fn a_reused_sender_secret_keeps_s_hidden_across_receivers() {
+    let point = |bytes: &[u8]| decode_ot_point(bytes).expect("point");
+    let (first, second) = (
+        OtExtReceiver::new([0xb2u8; 32], SESSION, &choice_bits(8)),
+        OtExtReceiver::new([0xb3u8; 32], SESSION, &choice_bits(8)),
+    );
+    let setup_gap = point(&first.setup_message()) - point(&second.setup_message());
+    let choices = |receiver: &OtExtReceiver| {
+        OtExtSender::new([0xa1u8; 32], &receiver.setup_message())
+            .expect("sender")
+            .base_choice_messages()
+    };
+    for (a, b) in choices(&first).iter().zip(&choices(&second)) {
+        // A fixed `s` would make each gap 0 (s_j = 0) or the setup gap (s_j = 1).
+        let gap = point(a) - point(b);
+        assert_ne!(gap, ProjectivePoint::IDENTITY);
+        assert_ne!(gap, setup_gap);
+    }
+}
+
+#[test]
+fn receiver_seeds_follow_the_base_choices() {
+    let bits = choice_bits(64);
+    let mut receiver = OtExtReceiver::new([0xb2u8; 32], SESSION, &bits);
+    let mut request_for = |sender_secret: [u8; 32]| {
+        let sender = OtExtSender::new(sender_secret, &receiver.setup_message()).expect("sender");
+        receiver
+            .extend(&sender.base_choice_messages())
+            .expect("extend")
+    };
+    let first = request_for([0xa1u8; 32]);
+    assert_eq!(request_for([0xa1u8; 32]), first);
+    // Fixed seeds would repeat `u_j` against another `s` and let Alice solve for the bits.
+    let second = request_for([0xa2u8; 32]);
+    assert!(
+        first
+            .columns
+            .iter()
+            .zip(&second.columns)
+            .all(|(a, b)| a != b)
+    );
+
+    let other_session = OtExtReceiver::new([0xb2u8; 32], [0x5f; 32], &bits);
+    assert_ne!(other_session.setup_message(), receiver.setup_message());
+}
+
+#[test]
+fn rejects_mismatched_messages() {
+    let mut receiver = OtExtReceiver::new([0xb2u8; 32], SESSION, &choice_bits(16));
+    let sender = OtExtSender::new([0xa1u8; 32], &receiver.setup_message()).expect("sender");
+    let choices = sender.base_choice_messages();
+    assert!(receiver.extend(&choices[..OT_EXTENSION_K - 1]).is_err());
+
+    let request = receiver.extend(&choices).expect("extend");
+    let err = sender.respond(&request, &offers(24)).unwrap_err();
+    assert!(err.contains("expected 3 for 24 label pairs"), "{err}");
+    let mut short = request.clone();
+    short.columns.pop();
+    assert!(sender.respond(&short, &offers(16)).is_err());
+    assert!(receiver.decrypt(&[[[0u8; 16]; 2]]).is_err());
+
+    let fresh = OtExtReceiver::new([0xb2u8; 32], SESSION, &choice_bits(1));
+    let err = fresh.decrypt(&[[[0u8; 16]; 2]]).unwrap_err();
+    assert!(err.contains("before extend"), "{err}");
+}
@@ -0,0 +1,99 @@
+//! Output decoding tables: derivation, decoding and the `eval-meta.txt` key/value form.
+
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, derive_output_labels,
+    evaluate_garbled_circuit, label16_to_bytes32,
+};
+use off_chain_common::garble::garble_leaves;
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::settlement::output_anchor_hash;
+use off_chain_common::types::GarblingScheme;
+
+mod common;
+
+use common::millionaires_layout;
+
+#[test]
+fn table_commits_to_both_output_labels() {
+    let seed = [0x31u8; 32];
+    let layout = millionaires_layout(4, 4);
+    let wire = build_millionaires_layout(4).gt_wire;
+    let table = OutputDecodingTable::derive(seed, &layout, wire).unwrap();
+    let (label_false, label_true) = derive_output_labels(seed, &layout, wire).unwrap();
+
+    assert_eq!(table.lout_true, label16_to_bytes32(label_true));
+    assert_eq!(table.lout_false, label16_to_bytes32(label_false));
+    assert_eq!(
+        table.h0,
+        output_anchor_hash(layout.circuit_id, 4, true, table.lout_true)
+    );
+    assert_eq!(
+        table.h1,
+        output_anchor_hash(layout.circuit_id, 4, false, table.lout_false)
+    );
+    assert_eq!(table.anchor_matches(label_true), (true, false));
+    assert_eq!(table.anchor_matches(label_false), (false, true));
+    assert_eq!(table.decode([0u8; 16]), None);
+    assert_eq!(table.anchor_matches([0u8; 16]), (false, false));
+}
+
+#[test]
+fn decodes_evaluated_labels() {
+    let seed = [0x31u8; 32];
+    let circuit = build_millionaires_layout(4);
+    for layout in [
+        millionaires_layout(4, 4),
+        millionaires_layout(4, 4).with_scheme(GarblingScheme::FreeXor),
+    ] {
+        let table = OutputDecodingTable::derive(seed, &layout, circuit.gt_wire).unwrap();
+        let leaves = garble_leaves(seed, &layout);
+        let hints = derive_not_gate_hints(seed, &layout);
+        for (x, y) in [(5u8, 2u8), (2, 5), (6, 6)] {
+            let x_bits = (0..4).map(|i| (x >> i) & 1).collect::<Vec<_>>();
+            let y_bits = (0..4).map(|i| (y >> i) & 1).collect::<Vec<_>>();
+            let alice = derive_layout_input_labels(seed, &layout, 0, &x_bits);
+            let bob = derive_layout_input_labels(seed, &layout, 4, &y_bits);
+            let out =
+                evaluate_garbled_circuit(&layout, &leaves, &alice, &bob, &hints, circuit.gt_wire)
+                    .unwrap();
+            let expected = (x > y) as u8;
+            assert_eq!(table.decode(out), Some(expected));
+            assert_eq!(table.decode_lsb(out), expected);
+        }
+    }
+}
+
+#[test]
+fn key_values_round_trip() {
+    let layout = millionaires_layout(4, 4);
+    let table = OutputDecodingTable::derive([0x31u8; 32], &layout, 20).unwrap();
+    let text = format!("bit_width=4\n{}", table.to_key_values());
+    let entries = text
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        OutputDecodingTable::from_key_values(entries.iter().copied()),
+        Ok(table)
+    );
+
+    // Files written before `lsb_true` existed still parse.
+    let legacy = entries.iter().copied().filter(|(k, _)| *k != "lsb_true");
+    assert_eq!(OutputDecodingTable::from_key_values(legacy), Ok(table));
+
+    let flipped = (1 - table.lsb_true).to_string();
+    let wrong = entries.iter().map(|&(k, v)| {
+        if k == "lsb_true" {
+            (k, flipped.as_str())
+        } else {
+            (k, v)
+        }
+    });
+    let err = OutputDecodingTable::from_key_values(wrong).unwrap_err();
+    assert!(err.contains("does not match the permutation bit"), "{err}");
+
+    let missing = entries.iter().copied().filter(|(k, _)| *k != "h1");
+    let err = OutputDecodingTable::from_key_values(missing).unwrap_err();
+    assert_eq!(err, "missing key 'h1'");
+}
@@ -2,14 +2,15 @@
 //! If any expected hash changes, Rust behavior has diverged from the frozen rules.
 
 use off_chain_common::consensus::{
-    LEAF_BYTES_LEN, compute_row_key, derive_wire_flip_bit, derive_wire_label, expand_pad,
-    layout_leaf_hash,
+    HIGHER_BID_WINS, LEAF_BYTES_LEN, LOWER_BID_WINS, circuit_id_from_layout, compute_row_key,
+    derive_wire_flip_bit, derive_wire_label, expand_pad, keccak256, layout_leaf_hash,
 };
 use off_chain_common::garble::{garble_circuit, recompute_gate_leaf};
 use off_chain_common::ih::{
     gc_block_hash, ih_proof_from_hashes, incremental_root, verify_ih_proof,
 };
 use off_chain_common::labels::get_permutation_bit;
+use off_chain_common::scenario::build_millionaires_layout;
 use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
 
 fn base_inputs() -> ([u8; 32], [u8; 32], u64) {
@@ -82,15 +83,15 @@ fn not_gate_rows_are_zero() {
 #[test]
 fn whole_circuit_and_incremental_root_are_stable() {
     let (circuit_id, seed, instance_id) = base_inputs();
-    let layout = CircuitLayout {
+    let layout = CircuitLayout::new(
         circuit_id,
         instance_id,
-        gates: vec![
+        vec![
             GateDesc::new(GateType::And, 0, 1, 2),
             GateDesc::new(GateType::Xor, 2, 3, 4),
             GateDesc::new(GateType::Not, 4, 0, 5),
         ],
-    };
+    );
 
     let leaves = garble_circuit(seed, &layout);
     assert_eq!(leaves.len(), 3);
@@ -104,19 +105,45 @@ fn whole_circuit_and_incremental_root_are_stable() {
     );
 }
 
+#[test]
+fn circuit_id_from_layout_commits_to_formula_and_gate_list() {
+    let gates = build_millionaires_layout(4).gates;
+    let id = circuit_id_from_layout(&gates, HIGHER_BID_WINS);
+    assert_eq!(
+        hex::encode(id),
+        "df1a9ef41019fdf8d0a84e8d837497635426becb4d1f0e0b91760367e9451d7c"
+    );
+
+    // Empty layout hashes only the domain tag, the formula byte and a zero count.
+    assert_eq!(
+        circuit_id_from_layout(&[], LOWER_BID_WINS),
+        keccak256(&[b"CIRCUIT", &[LOWER_BID_WINS], &[0u8; 32]])
+    );
+
+    assert_ne!(circuit_id_from_layout(&gates, LOWER_BID_WINS), id);
+    let mut rewired = gates.clone();
+    rewired[0].wire_b ^= 1;
+    assert_ne!(circuit_id_from_layout(&rewired, HIGHER_BID_WINS), id);
+    assert_ne!(circuit_id_from_layout(&gates[1..], HIGHER_BID_WINS), id);
+    assert_ne!(
+        circuit_id_from_layout(&build_millionaires_layout(5).gates, HIGHER_BID_WINS),
+        id
+    );
+}
+
 #[test]
 fn ih_proof_roundtrip_matches_contract_style_hashing() {
     let (circuit_id, seed, instance_id) = base_inputs();
-    let layout = CircuitLayout {
+    let layout = CircuitLayout::new(
         circuit_id,
         instance_id,
-        gates: vec![
+        vec![
             GateDesc::new(GateType::And, 0, 1, 2),
             GateDesc::new(GateType::Xor, 2, 3, 4),
             GateDesc::new(GateType::Not, 4, 0, 5),
             GateDesc::new(GateType::And, 5, 6, 7),
         ],
-    };
+    );
 
     let leaves = garble_circuit(seed, &layout);
     let block_hashes: Vec<[u8; 32]> = leaves
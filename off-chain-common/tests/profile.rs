@@ -0,0 +1,116 @@
+//! `--profile`: named network settings from the config file in place of env vars.
+
+use std::env;
+use std::fs;
+
+use off_chain_common::cli::{cast_args_with_tx_overrides, config_var, rpc_url};
+use off_chain_common::profile::{
+    NetworkProfile, ProfileName, activate_profile, load_profile, parse_profile,
+};
+
+mod common;
+
+use common::temp_path;
+
+const CONFIG: &str = r#"
+[profile.dev]
+rpc_url = "http://127.0.0.1:8545"
+contract_address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+legacy = true
+gas_price_wei = 0
+
+[profile.sepolia]
+rpc_url = "https://sepolia.example.org"
+contract_address = "0x00000000000000000000000000000000000000aa"
+"#;
+
+fn strings(items: &[&str]) -> Vec<String> {
+    items.iter().map(|item| item.to_string()).collect()
+}
+
+#[test]
+fn profiles_map_to_the_env_vars_they_replace() {
+    let dev = parse_profile(CONFIG, ProfileName::Dev).unwrap();
+    assert_eq!(
+        dev.env_vars(),
+        vec![
+            ("RPC_URL", "http://127.0.0.1:8545".to_string()),
+            (
+                "CONTRACT_ADDRESS",
+                "0x5FbDB2315678afecb367f032d93F642f64180aa3".to_string()
+            ),
+            ("TX_LEGACY", "1".to_string()),
+            ("TX_GAS_PRICE_WEI", "0".to_string()),
+        ]
+    );
+
+    let sepolia = parse_profile(CONFIG, ProfileName::Sepolia).unwrap();
+    assert_eq!(sepolia.legacy, None);
+    assert_eq!(sepolia.gas_price_wei, None);
+    assert_eq!(sepolia.env_vars().len(), 2);
+}
+
+#[test]
+fn missing_profiles_and_unknown_settings_are_rejected() {
+    let err = parse_profile(CONFIG, ProfileName::Mainnet).unwrap_err();
+    assert!(err.contains("[profile.mainnet]"), "{err}");
+
+    let err = parse_profile("[profile.dev]\nrpc = \"http://x\"\n", ProfileName::Dev).unwrap_err();
+    assert!(err.contains("invalid config file"), "{err}");
+    assert!(parse_profile("[profile.dev]\ngas_price_wei = \"1\"\n", ProfileName::Dev).is_err());
+    assert!(parse_profile("", ProfileName::Dev).is_err());
+}
+
+#[test]
+fn config_files_are_read_from_disk() {
+    let path = temp_path("profile-config").with_extension("toml");
+    fs::write(&path, CONFIG).unwrap();
+    let sepolia = load_profile(&path, ProfileName::Sepolia).unwrap();
+    assert_eq!(
+        sepolia.rpc_url.as_deref(),
+        Some("https://sepolia.example.org")
+    );
+
+    let err = load_profile(&path, ProfileName::Mainnet).unwrap_err();
+    assert!(err.starts_with(&path.display().to_string()), "{err}");
+    fs::remove_file(&path).unwrap();
+    let err = load_profile(&path, ProfileName::Dev).unwrap_err();
+    assert!(err.contains("failed to read config file"), "{err}");
+}
+
+#[test]
+fn active_profile_takes_precedence_over_env_vars() {
+    let send = strings(&["send", "0xc0ffee", "deposit()"]);
+    activate_profile(&NetworkProfile {
+        rpc_url: Some("https://sepolia.example.org".to_string()),
+        legacy: Some(true),
+        gas_price_wei: Some(7),
+        ..NetworkProfile::default()
+    });
+    assert_eq!(rpc_url(), "https://sepolia.example.org");
+    assert_eq!(config_var("TX_GAS_PRICE_WEI").as_deref(), Some("7"));
+    assert_eq!(
+        cast_args_with_tx_overrides(&send),
+        strings(&[
+            "send",
+            "0xc0ffee",
+            "deposit()",
+            "--legacy",
+            "--gas-price",
+            "7"
+        ])
+    );
+
+    // Settings the profile leaves out still come from the environment.
+    assert_eq!(
+        config_var("CARGO_PKG_NAME"),
+        env::var("CARGO_PKG_NAME").ok()
+    );
+
+    activate_profile(&parse_profile(CONFIG, ProfileName::Dev).unwrap());
+    assert_eq!(rpc_url(), "http://127.0.0.1:8545");
+    assert_eq!(
+        config_var("CONTRACT_ADDRESS").as_deref(),
+        Some("0x5FbDB2315678afecb367f032d93F642f64180aa3")
+    );
+}
@@ -0,0 +1,170 @@
+//! Protocol session state machine: phase order, transitions and per-phase payload checks.
+
+use off_chain_common::protocol::{
+    ChoosePayload, CommitPayload, DepositPayload, DisputePayload, EvalPayload, OpenPayload, Party,
+    Phase, PhasePayload, ProtocolSession, SettlePayload,
+};
+use off_chain_common::scenario::{SessionParams, com_seed};
+
+const N: usize = 4;
+
+fn seed(idx: usize) -> [u8; 32] {
+    [idx as u8 + 1; 32]
+}
+
+fn committed_session() -> ProtocolSession {
+    let mut session = ProtocolSession::new(SessionParams::new(N).expect("n"));
+    for party in [Party::Garbler, Party::Evaluator] {
+        let payload = PhasePayload::Deposit(DepositPayload { party });
+        assert_eq!(session.apply(payload), Ok(Phase::Deposit));
+    }
+    let commit = CommitPayload {
+        com_seeds: (0..N).map(|idx| com_seed(seed(idx))).collect(),
+        root_gcs: vec![[0xee; 32]; N],
+    };
+    assert_eq!(
+        session.apply(PhasePayload::Commit(commit)),
+        Ok(Phase::Commit)
+    );
+    session
+}
+
+fn openings(m: usize) -> PhasePayload {
+    PhasePayload::Open(OpenPayload {
+        seeds: (0..N)
+            .filter(|idx| *idx != m)
+            .map(|idx| (idx, seed(idx)))
+            .collect(),
+    })
+}
+
+#[test]
+fn drives_a_session_through_every_phase() {
+    let mut session = committed_session();
+    assert_eq!(
+        session.apply(PhasePayload::Choose(ChoosePayload { m: 2 })),
+        Ok(Phase::Choose)
+    );
+    assert_eq!(session.apply(openings(2)), Ok(Phase::Open));
+    assert_eq!(session.chosen_instance(), Some(2));
+    assert_eq!(session.opened_seed(0), Some(seed(0)));
+    assert_eq!(session.opened_seed(2), None);
+
+    let eval = EvalPayload {
+        output_label: [7; 32],
+        decoded_bit: Some(1),
+    };
+    assert_eq!(
+        session.apply(PhasePayload::Eval(eval.clone())),
+        Ok(Phase::Eval)
+    );
+    let settle = SettlePayload { output: vec![1, 2] };
+    assert_eq!(
+        session.apply(PhasePayload::Settle(settle.clone())),
+        Ok(Phase::Settle)
+    );
+    assert_eq!(session.eval(), Some(&eval));
+    assert_eq!(session.settlement(), Some(&settle));
+    assert!(Phase::Settle.next_phases().is_empty());
+}
+
+#[test]
+fn rejects_transitions_outside_the_phase_graph() {
+    let mut session = ProtocolSession::new(SessionParams::default());
+    let err = session
+        .apply(PhasePayload::Choose(ChoosePayload { m: 0 }))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        "cannot move from deposit to choose (allowed: [commit])"
+    );
+
+    let mut session = committed_session();
+    let settle = PhasePayload::Settle(SettlePayload { output: Vec::new() });
+    assert!(session.apply(settle.clone()).is_err());
+    let deposit = PhasePayload::Deposit(DepositPayload {
+        party: Party::Garbler,
+    });
+    assert!(session.apply(deposit).is_err());
+    assert_eq!(session.phase(), Phase::Commit);
+
+    for phase in Phase::ALL {
+        assert_eq!(Phase::from_name(phase.name()), Ok(phase));
+        assert!(!phase.can_transition_to(Phase::Deposit));
+    }
+    assert!(Phase::from_name("labels").is_err());
+}
+
+#[test]
+fn checks_phase_payloads() {
+    let mut session = ProtocolSession::new(SessionParams::new(N).expect("n"));
+    let deposit = PhasePayload::Deposit(DepositPayload {
+        party: Party::Evaluator,
+    });
+    session.apply(deposit.clone()).expect("first deposit");
+    assert!(
+        session
+            .apply(deposit)
+            .unwrap_err()
+            .contains("already deposited")
+    );
+    let commit = PhasePayload::Commit(CommitPayload {
+        com_seeds: vec![[0; 32]; N],
+        root_gcs: vec![[0; 32]; N],
+    });
+    assert!(session.apply(commit).unwrap_err().contains("both parties"));
+
+    let mut session = committed_session();
+    assert!(
+        session
+            .apply(PhasePayload::Choose(ChoosePayload { m: N }))
+            .is_err()
+    );
+    session
+        .apply(PhasePayload::Choose(ChoosePayload { m: 1 }))
+        .expect("choose");
+    assert!(
+        session
+            .apply(openings(0))
+            .unwrap_err()
+            .contains("all but m=1")
+    );
+    let PhasePayload::Open(mut forged) = openings(1) else {
+        unreachable!()
+    };
+    forged.seeds[0].1 = [0xab; 32];
+    let err = session.apply(PhasePayload::Open(forged)).unwrap_err();
+    assert!(err.contains("does not match comSeed"), "{err}");
+    assert_eq!(session.phase(), Phase::Choose);
+
+    session.apply(openings(1)).expect("open");
+    let dispute = |instance_id| {
+        PhasePayload::Dispute(DisputePayload {
+            instance_id,
+            gate_index: 3,
+        })
+    };
+    assert!(
+        session
+            .apply(dispute(1))
+            .unwrap_err()
+            .contains("was not opened")
+    );
+    assert_eq!(session.apply(dispute(3)), Ok(Phase::Dispute));
+    assert_eq!(session.disputes().len(), 1);
+}
+
+#[test]
+fn maps_contract_stages_to_phases() {
+    let phases = (0..=11)
+        .map(|stage| Phase::from_contract_stage(stage).expect("stage"))
+        .collect::<Vec<_>>();
+    assert_eq!(phases[0], Phase::Deposit);
+    assert!(phases[1..=4].iter().all(|phase| *phase == Phase::Commit));
+    assert_eq!(
+        &phases[5..=8],
+        &[Phase::Choose, Phase::Open, Phase::Dispute, Phase::Eval]
+    );
+    assert!(phases[9..].iter().all(|phase| *phase == Phase::Settle));
+    assert!(Phase::from_contract_stage(12).is_err());
+}
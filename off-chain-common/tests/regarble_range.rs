@@ -0,0 +1,68 @@
+//! Partial re-garbling: a gate range matches the full garbling and patches the IH root.
+
+use off_chain_common::garble::{garble_circuit, regarble_range};
+use off_chain_common::ih::{gc_block_hash, incremental_root, incremental_root_from_hashes};
+use off_chain_common::types::{GarblingScheme, GateType, LeafFormat};
+
+mod common;
+
+use common::millionaires_layout;
+
+#[test]
+fn range_matches_full_garbling() {
+    let seed = [0x77u8; 32];
+    for layout in [
+        millionaires_layout(7, 8),
+        millionaires_layout(7, 8).with_scheme(GarblingScheme::FreeXor),
+    ] {
+        let full = garble_circuit(seed, &layout);
+        for range in [0..5, 10..20, 0..full.len(), full.len()..full.len()] {
+            let part = regarble_range(seed, &layout, range.clone()).unwrap();
+            assert_eq!(part.start, range.start);
+            assert_eq!(part.leaves, full[range.clone()]);
+            for (offset, hash) in part.block_hashes.iter().enumerate() {
+                let idx = range.start + offset;
+                assert_eq!(*hash, gc_block_hash(idx as u64, &full[idx]));
+            }
+        }
+    }
+}
+
+#[test]
+fn patched_range_updates_the_root() {
+    let seed = [0x77u8; 32];
+    let original = millionaires_layout(7, 8);
+    let mut leaves = garble_circuit(seed, &original);
+    let mut block_hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+
+    // "Fix" gate 12 by changing its type; only that gate's leaf changes.
+    let mut patched = original.clone();
+    patched.gates[12].gate_type = match patched.gates[12].gate_type {
+        GateType::And => GateType::Xor,
+        _ => GateType::And,
+    };
+    let part = regarble_range(seed, &patched, 12..13).unwrap();
+    part.apply(&mut leaves, &mut block_hashes).unwrap();
+
+    assert_eq!(leaves, garble_circuit(seed, &patched));
+    assert_eq!(
+        incremental_root_from_hashes(&block_hashes),
+        incremental_root(&garble_circuit(seed, &patched))
+    );
+    assert!(part.apply(&mut leaves[..12], &mut block_hashes).is_err());
+}
+
+#[test]
+fn rejects_bad_ranges_and_v2_layouts() {
+    let seed = [0x77u8; 32];
+    let layout = millionaires_layout(7, 8);
+    let gate_count = layout.gates.len();
+    let err = regarble_range(seed, &layout, 0..gate_count + 1).unwrap_err();
+    assert!(err.contains("out of bounds"), "{err}");
+    let err = regarble_range(seed, &layout.with_leaf_format(LeafFormat::V2), 0..1).unwrap_err();
+    assert!(err.contains("emits v1 leaves"), "{err}");
+}
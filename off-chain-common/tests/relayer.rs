@@ -0,0 +1,203 @@
+//! Relayer mode: signed legacy transactions, calldata checks and gas top-ups.
+
+use off_chain_common::cli::decode_hex;
+use off_chain_common::daemon::params_to_args;
+use off_chain_common::identity::EthIdentity;
+use off_chain_common::relayer::{
+    LegacyTx, MAX_GAS_PRICE_FACTOR, PreparedCall, SponsorLimits, check_gas_terms,
+    check_signed_call, decode_signed_tx, gas_limit_cap, gas_shortfall,
+};
+
+/// Example transaction from EIP-155.
+fn eip155_tx() -> LegacyTx {
+    LegacyTx {
+        nonce: 9,
+        gas_price: 20_000_000_000,
+        gas_limit: 21_000,
+        to: [0x35; 20],
+        value: 1_000_000_000_000_000_000,
+        data: Vec::new(),
+        chain_id: 1,
+    }
+}
+
+fn party() -> EthIdentity {
+    EthIdentity::from_private_key([0x46; 32]).expect("key")
+}
+
+fn call(tx: &LegacyTx) -> PreparedCall {
+    PreparedCall {
+        from: party().address(),
+        to: tx.to,
+        value: tx.value,
+        data: tx.data.clone(),
+        chain_id: tx.chain_id,
+    }
+}
+
+#[test]
+fn matches_eip155_example() {
+    let tx = eip155_tx();
+    assert_eq!(
+        tx.signing_hash().to_vec(),
+        decode_hex("0xdaf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53").unwrap()
+    );
+    let raw = tx.sign(&party()).expect("sign");
+    assert_eq!(
+        raw,
+        decode_hex(
+            "0xf86c098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83"
+        )
+        .unwrap()
+    );
+    let signed = decode_signed_tx(&raw).expect("decode");
+    assert_eq!(signed.tx, tx);
+    assert_eq!(signed.from, party().address());
+}
+
+#[test]
+fn round_trips_contract_calls() {
+    let tx = LegacyTx {
+        nonce: 0,
+        gas_price: 1,
+        gas_limit: 250_000,
+        to: [0xab; 20],
+        value: 0,
+        // Long calldata exercises multi-byte RLP lengths.
+        data: (0..300u32).map(|i| i as u8).collect(),
+        chain_id: 31_337,
+    };
+    let raw = tx.sign(&party()).expect("sign");
+    let signed = check_signed_call(&call(&tx), &raw).expect("prepared call");
+    assert_eq!(signed.tx, tx);
+    assert_eq!(signed.from, party().address());
+}
+
+#[test]
+fn rejects_calls_other_than_prepared() {
+    let tx = LegacyTx {
+        data: vec![0xde, 0xad, 0xbe, 0xef],
+        ..eip155_tx()
+    };
+    let raw = tx.sign(&party()).expect("sign");
+    let prepared = call(&tx);
+
+    let mut other = prepared.clone();
+    other.data[0] ^= 1;
+    let err = check_signed_call(&other, &raw).unwrap_err();
+    assert!(err.contains("calldata"), "{err}");
+    for other in [
+        PreparedCall {
+            to: [0x36; 20],
+            ..prepared.clone()
+        },
+        PreparedCall {
+            value: 0,
+            ..prepared.clone()
+        },
+        PreparedCall {
+            chain_id: 5,
+            ..prepared.clone()
+        },
+        PreparedCall {
+            from: [0x01; 20],
+            ..prepared.clone()
+        },
+    ] {
+        assert!(check_signed_call(&other, &raw).is_err());
+    }
+
+    // Signed by someone else for the same call: the recovered signer gives it away.
+    let forged = tx
+        .sign(&EthIdentity::from_private_key([0x47; 32]).expect("key"))
+        .expect("sign");
+    let err = check_signed_call(&prepared, &forged).unwrap_err();
+    assert!(err.contains("signed by"), "{err}");
+
+    let mut raw = raw;
+    let last = raw.len() - 1;
+    raw[last] ^= 1;
+    assert_ne!(
+        decode_signed_tx(&raw).map(|signed| signed.from),
+        Ok(party().address())
+    );
+    assert!(check_signed_call(&prepared, &raw).is_err());
+}
+
+#[test]
+fn relay_params_round_trip_through_the_daemon() {
+    let tx = LegacyTx {
+        data: vec![0xde, 0xad, 0xbe, 0xef],
+        ..eip155_tx()
+    };
+    let raw = tx.sign(&party()).expect("sign");
+    let prepared = call(&tx);
+    let args = params_to_args(Some(&prepared.relay_params(&raw))).expect("args");
+    let (decoded, decoded_raw) = PreparedCall::from_relay_args(&args).expect("relay args");
+    assert_eq!(decoded, prepared);
+    assert_eq!(decoded_raw, raw);
+}
+
+#[test]
+fn tops_up_gas_but_never_the_call_value() {
+    let tx = eip155_tx();
+    let signed = decode_signed_tx(&tx.sign(&party()).expect("sign")).expect("decode");
+    let gas = 20_000_000_000u128 * 21_000;
+    assert_eq!(gas_shortfall(&signed, tx.value), Ok(gas));
+    assert_eq!(
+        gas_shortfall(&signed, tx.value + gas / 2),
+        Ok(gas - gas / 2)
+    );
+    assert_eq!(gas_shortfall(&signed, tx.value + gas * 2), Ok(0));
+    let err = gas_shortfall(&signed, tx.value - 1).unwrap_err();
+    assert!(err.contains("does not cover the call value"), "{err}");
+}
+
+#[test]
+fn refuses_gas_terms_above_the_node_view() {
+    let tx = eip155_tx();
+    let node_gas_price = tx.gas_price;
+    let estimate = tx.gas_limit;
+    assert_eq!(check_gas_terms(&tx, node_gas_price, estimate), Ok(()));
+    let headroom = LegacyTx {
+        gas_price: node_gas_price * MAX_GAS_PRICE_FACTOR,
+        gas_limit: gas_limit_cap(estimate),
+        ..tx.clone()
+    };
+    assert_eq!(check_gas_terms(&headroom, node_gas_price, estimate), Ok(()));
+
+    let pricey = LegacyTx {
+        gas_price: node_gas_price * MAX_GAS_PRICE_FACTOR + 1,
+        ..tx.clone()
+    };
+    let err = check_gas_terms(&pricey, node_gas_price, estimate).unwrap_err();
+    assert!(err.contains("gas price"), "{err}");
+    let greedy = LegacyTx {
+        gas_limit: gas_limit_cap(estimate) + 1,
+        ..tx.clone()
+    };
+    let err = check_gas_terms(&greedy, node_gas_price, estimate).unwrap_err();
+    assert!(err.contains("gas limit"), "{err}");
+    let huge = LegacyTx {
+        gas_price: u128::MAX,
+        gas_limit: u64::MAX,
+        ..tx
+    };
+    assert!(check_gas_terms(&huge, node_gas_price, estimate).is_err());
+}
+
+#[test]
+fn caps_each_top_up_and_their_total() {
+    let limits = SponsorLimits {
+        max_topup_wei: 100,
+        total_topup_wei: 250,
+    };
+    assert_eq!(limits.check(100, 0), Ok(()));
+    assert_eq!(limits.check(50, 200), Ok(()));
+    let err = limits.check(101, 0).unwrap_err();
+    assert!(err.contains("per-call limit"), "{err}");
+    let err = limits.check(100, 200).unwrap_err();
+    assert!(err.contains("remaining budget of 50 wei"), "{err}");
+    assert!(limits.check(1, u128::MAX).is_err());
+    assert_eq!(SponsorLimits::default().check(0, 0), Ok(()));
+}
@@ -0,0 +1,183 @@
+//! Retry and backoff: the policy, error classification and idempotent file-batch resends.
+
+use std::cell::Cell;
+use std::fs;
+use std::io::Cursor;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::Duration;
+
+use off_chain_common::retry::{RetryPolicy, is_transient_error, is_unsent_error, retry_with};
+use off_chain_common::transport::{
+    BATCH_LEDGER, Frame, batch_key, list_dir_files, read_frame, receive_batch, receive_files_on,
+    send_files, send_files_to, write_frame,
+};
+
+mod common;
+
+use common::temp_path;
+
+fn policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_delay: Duration::from_millis(10),
+        max_delay: Duration::from_millis(25),
+    }
+}
+
+#[test]
+fn backs_off_exponentially_up_to_the_cap() {
+    let policy = policy(5);
+    let delays = (1..=4)
+        .map(|attempt| policy.delay(attempt))
+        .collect::<Vec<_>>();
+    assert_eq!(delays, [10, 20, 25, 25].map(Duration::from_millis).to_vec());
+    assert_eq!(RetryPolicy::none().max_attempts, 1);
+    assert_eq!(
+        RetryPolicy::default().delay(u32::MAX),
+        Duration::from_secs(8)
+    );
+}
+
+#[test]
+fn retries_transient_errors_only() {
+    let slept = Cell::new(Vec::new());
+    let record = |delay| {
+        let mut all = slept.take();
+        all.push(delay);
+        slept.set(all);
+    };
+    let result = retry_with(&policy(4), is_transient_error, record, |attempt| {
+        if attempt < 3 {
+            Err("failed to connect to 127.0.0.1:9: Connection refused (os error 111)".to_string())
+        } else {
+            Ok(attempt)
+        }
+    });
+    assert_eq!(result, Ok(3));
+    assert_eq!(slept.take(), [10, 20].map(Duration::from_millis).to_vec());
+
+    let calls = Cell::new(0);
+    let err = retry_with(
+        &policy(3),
+        is_transient_error,
+        |_| {},
+        |_| {
+            calls.set(calls.get() + 1);
+            Err::<(), _>("cast call failed: operation timed out".to_string())
+        },
+    )
+    .unwrap_err();
+    assert_eq!(calls.get(), 3);
+    assert!(err.ends_with("(after 3 attempts)"), "{err}");
+
+    let calls = Cell::new(0);
+    let err = retry_with(
+        &policy(3),
+        is_transient_error,
+        |_| {},
+        |_| {
+            calls.set(calls.get() + 1);
+            Err::<(), _>("execution reverted: Not in dispute window".to_string())
+        },
+    )
+    .unwrap_err();
+    assert_eq!(calls.get(), 1);
+    assert_eq!(err, "execution reverted: Not in dispute window");
+}
+
+#[test]
+fn classifies_rpc_and_transport_failures() {
+    for transient in [
+        "error sending request for url (http://127.0.0.1:8545/)",
+        "HTTP error 429 with body: Too Many Requests",
+        "failed to read transport frame length: failed to fill whole buffer",
+        "failed to write transport frame: Broken pipe (os error 32)",
+    ] {
+        assert!(is_transient_error(transient), "{transient}");
+    }
+    for permanent in [
+        "execution reverted: Wrong stage",
+        "peer 0x11 is not the expected 0x22",
+        "invalid transport file name '../x'",
+    ] {
+        assert!(!is_transient_error(permanent), "{permanent}");
+    }
+    // A send is only resent when the node never saw it.
+    assert!(is_unsent_error("Connection refused (os error 111)"));
+    assert!(!is_unsent_error("operation timed out"));
+}
+
+#[test]
+fn resent_batches_leave_received_files_alone() {
+    let src = temp_path("retry-src");
+    let dst = temp_path("retry-dst");
+    fs::create_dir_all(&src).expect("src dir");
+    fs::write(src.join("eval-package.cbor"), [1u8, 2, 3]).expect("package");
+    let names = vec!["eval-package.cbor".to_string()];
+
+    let mut wire = Vec::new();
+    send_files(&mut wire, &src, &names).expect("send");
+    let key = batch_key(&[(names[0].clone(), vec![1, 2, 3])]);
+    assert_eq!(
+        read_frame(&mut Cursor::new(&wire)),
+        Ok(Frame::Begin { key })
+    );
+
+    let first = receive_batch(&mut Cursor::new(&wire), &dst).expect("first");
+    assert_eq!((first.key, first.duplicate), (Some(key), false));
+    // Bob already moved on with the file when Alice's retry delivers the batch again.
+    fs::write(dst.join("eval-package.cbor"), [9u8]).expect("consume");
+    let resent = receive_batch(&mut Cursor::new(&wire), &dst).expect("resent");
+    assert!(resent.duplicate);
+    assert_eq!(resent.names, names);
+    assert_eq!(fs::read(dst.join("eval-package.cbor")).expect("read"), [9]);
+    assert_eq!(list_dir_files(&dst).expect("list"), names);
+    assert!(dst.join(BATCH_LEDGER).exists());
+
+    let mut nested = Vec::new();
+    write_frame(&mut nested, &Frame::Begin { key }).expect("begin");
+    write_frame(&mut nested, &Frame::Begin { key }).expect("begin again");
+    let nested_dst = temp_path("retry-nested");
+    let err = receive_batch(&mut Cursor::new(nested), &nested_dst).unwrap_err();
+    assert!(err.contains("unexpected begin frame"), "{err}");
+    let _ = fs::remove_dir_all(nested_dst);
+    let _ = fs::remove_dir_all(src);
+    let _ = fs::remove_dir_all(dst);
+}
+
+#[test]
+fn receiver_waits_for_the_next_connection_after_a_drop() {
+    let src = temp_path("retry-tcp-src");
+    let dst = temp_path("retry-tcp-dst");
+    fs::create_dir_all(&src).expect("src dir");
+    fs::write(src.join("ot-setup.txt"), "0x02\n").expect("setup");
+    let names = vec!["ot-setup.txt".to_string()];
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .and_then(|listener| listener.local_addr())
+        .expect("free port")
+        .port();
+    let addr = format!("127.0.0.1:{port}");
+    let receiver = {
+        let (addr, dst) = (addr.clone(), dst.clone());
+        thread::spawn(move || receive_files_on(&addr, &dst, &policy(3)))
+    };
+
+    // The first connection dies after the begin frame; the receiver accepts the next one.
+    let mut dropped = loop {
+        match TcpStream::connect(&addr) {
+            Ok(stream) => break stream,
+            Err(_) => thread::sleep(Duration::from_millis(5)),
+        }
+    };
+    write_frame(&mut dropped, &Frame::Begin { key: [0; 32] }).expect("partial");
+    drop(dropped);
+    assert_eq!(send_files_to(&addr, &src, &names, &policy(3)), Ok(5));
+
+    let (_, batch) = receiver.join().expect("join").expect("receive");
+    assert_eq!(batch.names, names);
+    assert_eq!(fs::read(dst.join("ot-setup.txt")).expect("read"), b"0x02\n");
+    let _ = fs::remove_dir_all(src);
+    let _ = fs::remove_dir_all(dst);
+}
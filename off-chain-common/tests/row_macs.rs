@@ -0,0 +1,156 @@
+//! Authenticated garbled rows: tag layout in v2 leaves and tamper detection by the evaluator.
+
+use off_chain_common::consensus::ROW_MAC_LEN;
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, derive_output_labels,
+    evaluate_garbled_circuit,
+};
+use off_chain_common::garble::garble_leaves;
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{
+    CircuitLayout, GarblingScheme, GateType, LeafFormat, NotGateMode, RowAuthMode,
+};
+
+mod common;
+
+use common::millionaires_layout;
+
+const BIT_WIDTH: usize = 4;
+
+fn layout() -> CircuitLayout {
+    millionaires_layout(6, BIT_WIDTH)
+        .with_leaf_format(LeafFormat::V2)
+        .with_row_auth(RowAuthMode::Mac)
+}
+
+fn bits(value: u8) -> Vec<u8> {
+    (0..BIT_WIDTH).map(|i| (value >> i) & 1).collect()
+}
+
+fn evaluate(
+    seed: [u8; 32],
+    layout: &CircuitLayout,
+    leaves: &[Vec<u8>],
+    x: u8,
+    y: u8,
+) -> Result<[u8; 16], String> {
+    let alice = derive_layout_input_labels(seed, layout, 0, &bits(x));
+    let bob = derive_layout_input_labels(seed, layout, BIT_WIDTH as u16, &bits(y));
+    let hints = derive_not_gate_hints(seed, layout);
+    let output_wire = build_millionaires_layout(BIT_WIDTH).gt_wire;
+    evaluate_garbled_circuit(layout, leaves, &alice, &bob, &hints, output_wire)
+}
+
+#[test]
+fn v2_leaves_append_one_tag_per_garbled_row() {
+    let seed = [0x44u8; 32];
+    for layout in [
+        layout(),
+        layout().with_not_gate_mode(NotGateMode::Garbled),
+        layout().with_scheme(GarblingScheme::FreeXor),
+    ] {
+        assert_eq!(layout.scheme_id().row_auth(), RowAuthMode::Mac);
+        let plain = garble_leaves(
+            seed,
+            &layout.clone().with_row_auth(RowAuthMode::Unauthenticated),
+        );
+        for ((leaf, plain_leaf), gate) in garble_leaves(seed, &layout)
+            .iter()
+            .zip(&plain)
+            .zip(&layout.gates)
+        {
+            let row_count = layout.garbled_row_count(gate.gate_type);
+            assert_eq!(leaf.len(), plain_leaf.len() + ROW_MAC_LEN * row_count);
+            // Rows are unchanged; only the version byte and the tags differ.
+            assert_eq!(leaf[1..plain_leaf.len()], plain_leaf[1..]);
+        }
+    }
+
+    // v1 leaves have no room for tags, so the mode does not apply to them.
+    let v1 = layout().with_leaf_format(LeafFormat::V1);
+    assert_eq!(v1.scheme_id().row_auth(), RowAuthMode::Unauthenticated);
+}
+
+#[test]
+fn authenticated_leaves_evaluate_correctly() {
+    let seed = [0x44u8; 32];
+    let output_wire = build_millionaires_layout(BIT_WIDTH).gt_wire;
+    for layout in [
+        layout(),
+        layout().with_not_gate_mode(NotGateMode::Garbled),
+        layout().with_scheme(GarblingScheme::FreeXor),
+    ] {
+        let leaves = garble_leaves(seed, &layout);
+        let (label_false, label_true) = derive_output_labels(seed, &layout, output_wire).unwrap();
+        for (x, y) in [(9u8, 3u8), (3, 9), (12, 12)] {
+            let expected = if x > y { label_true } else { label_false };
+            assert_eq!(evaluate(seed, &layout, &leaves, x, y), Ok(expected));
+        }
+    }
+}
+
+#[test]
+fn tampered_rows_are_rejected_instead_of_mis_decrypted() {
+    let seed = [0x44u8; 32];
+    let layout = layout();
+    let output_wire = build_millionaires_layout(BIT_WIDTH).gt_wire;
+    let (label_false, label_true) = derive_output_labels(seed, &layout, output_wire).unwrap();
+    let gate_index = layout
+        .gates
+        .iter()
+        .position(|gate| gate.gate_type == GateType::And)
+        .unwrap();
+
+    // Flip one byte of every transmitted row of the gate; only the implicit row 0 survives.
+    let mut leaves = garble_leaves(seed, &layout);
+    for row in 0..3 {
+        leaves[gate_index][8 + 16 * row] ^= 0x01;
+    }
+    let unauthenticated = layout.clone().with_row_auth(RowAuthMode::Unauthenticated);
+    let mut plain_leaves = garble_leaves(seed, &unauthenticated);
+    for row in 0..3 {
+        plain_leaves[gate_index][8 + 16 * row] ^= 0x01;
+    }
+
+    let mut detected = 0;
+    let mut silent = 0;
+    for x in 0..16u8 {
+        for y in 0..16u8 {
+            let expected = if x > y { label_true } else { label_false };
+            match evaluate(seed, &layout, &leaves, x, y) {
+                Ok(label) => assert_eq!(label, expected, "x={x} y={y}"),
+                Err(err) => {
+                    assert!(err.contains("row MAC mismatch"), "{err}");
+                    assert!(err.contains(&format!("gate={gate_index}")), "{err}");
+                    detected += 1;
+                }
+            }
+            if evaluate(seed, &unauthenticated, &plain_leaves, x, y).is_ok_and(|l| l != expected) {
+                silent += 1;
+            }
+        }
+    }
+    assert!(detected > 0);
+    // Without tags the same tampering goes unnoticed and corrupts outputs.
+    assert!(silent > 0);
+}
+
+#[test]
+fn tampered_tags_are_rejected() {
+    let seed = [0x44u8; 32];
+    let layout = layout();
+    let mut leaves = garble_leaves(seed, &layout);
+    for leaf in &mut leaves {
+        let len = leaf.len();
+        if len > 8 {
+            // `8 + 16 * (rows - 1) + 8 * rows` bytes.
+            let row_count = (len + 8) / (16 + ROW_MAC_LEN);
+            let macs_start = 8 + 16 * (row_count - 1);
+            for byte in &mut leaf[macs_start..] {
+                *byte ^= 0xff;
+            }
+        }
+    }
+    let err = evaluate(seed, &layout, &leaves, 5, 6).unwrap_err();
+    assert!(err.contains("row MAC mismatch"), "{err}");
+}
@@ -0,0 +1,138 @@
+//! Scheme ids: canonical byte encoding, versioned v2 leaves and scheme checks on leaves.
+
+use off_chain_common::consensus::{check_leaf_scheme, leaf_scheme_id};
+use off_chain_common::evaluation::{
+    derive_layout_input_labels, derive_not_gate_hints, evaluate_garbled_circuit,
+};
+use off_chain_common::garble::{garble_circuit, garble_leaves};
+use off_chain_common::ih::{gc_block_hash, gc_block_hash_for_scheme, gc_block_hash_v2};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{
+    CircuitLayout, GarblingScheme, LeafFormat, NotGateMode, PadFunction, RowAuthMode, SchemeId,
+};
+
+mod common;
+
+use common::millionaires_layout;
+
+fn evaluate(layout: &CircuitLayout, leaves: &[Vec<u8>]) -> Result<[u8; 16], String> {
+    let seed = [6u8; 32];
+    let alice = derive_layout_input_labels(seed, layout, 0, &[1, 0, 1, 0]);
+    let bob = derive_layout_input_labels(seed, layout, 4, &[0, 1, 1, 0]);
+    let hints = derive_not_gate_hints(seed, layout);
+    let output_wire = build_millionaires_layout(4).gt_wire;
+    evaluate_garbled_circuit(layout, leaves, &alice, &bob, &hints, output_wire)
+}
+
+#[test]
+fn scheme_ids_round_trip_through_layouts() {
+    let mut seen = Vec::new();
+    for scheme in [GarblingScheme::Classic, GarblingScheme::FreeXor] {
+        for leaf_format in [LeafFormat::V1, LeafFormat::V2] {
+            for not_gate_mode in [NotGateMode::Hinted, NotGateMode::Garbled] {
+                for pad_function in [PadFunction::Keccak, PadFunction::FixedKeyAes] {
+                    for row_auth in [RowAuthMode::Unauthenticated, RowAuthMode::Mac] {
+                        let id = SchemeId::new(
+                            scheme,
+                            leaf_format,
+                            not_gate_mode,
+                            pad_function,
+                            row_auth,
+                        );
+                        assert_eq!(SchemeId::from_byte(id.to_byte()), Ok(id));
+                        let layout = millionaires_layout(1, 4).with_scheme_id(id);
+                        assert_eq!(layout.scheme_id(), id);
+                        assert_eq!(layout.scheme, scheme);
+                        assert_eq!(layout.leaf_format, leaf_format);
+                        assert_eq!(layout.pad_function, pad_function);
+                        if !seen.contains(&id) {
+                            seen.push(id);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    // Free-XOR ignores the NOT-gate mode and v1 leaves ignore row MACs, leaving eighteen
+    // distinct ids.
+    assert_eq!(seen.len(), 18);
+    assert_eq!(millionaires_layout(1, 4).scheme_id(), SchemeId::CLASSIC_V1);
+    assert_eq!(SchemeId::CLASSIC_V1.to_string(), "0x00");
+    assert!(SchemeId::CLASSIC_V1.check_onchain().is_ok());
+    assert!(seen.iter().skip(1).all(|id| id.check_onchain().is_err()));
+
+    assert!(SchemeId::from_byte(0x03).is_err());
+    assert!(SchemeId::from_byte(0x10).is_err());
+    assert!(SchemeId::from_byte(0x20).is_err());
+    assert_eq!(
+        SchemeId::from_byte(0x14).map(SchemeId::row_auth),
+        Ok(RowAuthMode::Mac)
+    );
+}
+
+#[test]
+fn v2_leaves_carry_their_scheme() {
+    let seed = [6u8; 32];
+    let free_xor = millionaires_layout(1, 4)
+        .with_scheme(GarblingScheme::FreeXor)
+        .with_leaf_format(LeafFormat::V2);
+    let classic = millionaires_layout(1, 4).with_leaf_format(LeafFormat::V2);
+    let free_xor_leaves = garble_leaves(seed, &free_xor);
+    let classic_leaves = garble_leaves(seed, &classic);
+
+    for leaf in &free_xor_leaves {
+        assert_eq!(leaf_scheme_id(leaf), Ok(Some(free_xor.scheme_id())));
+        assert!(check_leaf_scheme(free_xor.scheme_id(), leaf).is_ok());
+    }
+    assert!(evaluate(&free_xor, &free_xor_leaves).is_ok());
+
+    // Same bytes under another scheme are rejected instead of mis-parsed.
+    let err = evaluate(&classic, &free_xor_leaves).unwrap_err();
+    assert!(err.contains("encoded for scheme 0x05"), "{err}");
+    let err = evaluate(&free_xor, &classic_leaves).unwrap_err();
+    assert!(err.contains("expected 0x05"), "{err}");
+
+    let v1_leaves = garble_circuit(seed, &millionaires_layout(1, 4))
+        .iter()
+        .map(|leaf| leaf.to_vec())
+        .collect::<Vec<_>>();
+    let err = evaluate(&classic, &v1_leaves).unwrap_err();
+    assert!(err.contains("unversioned leaf"), "{err}");
+    assert_eq!(leaf_scheme_id(&v1_leaves[0]), Ok(None));
+}
+
+#[test]
+fn block_hash_dispatches_on_scheme() {
+    let seed = [6u8; 32];
+    let v1 = garble_circuit(seed, &millionaires_layout(1, 4));
+    let v2_layout = millionaires_layout(1, 4).with_leaf_format(LeafFormat::V2);
+    let v2 = garble_leaves(seed, &v2_layout);
+
+    assert_eq!(
+        gc_block_hash_for_scheme(SchemeId::CLASSIC_V1, 3, &v1[3]),
+        Ok(gc_block_hash(3, &v1[3]))
+    );
+    assert_eq!(
+        gc_block_hash_for_scheme(v2_layout.scheme_id(), 3, &v2[3]),
+        Ok(gc_block_hash_v2(3, &v2[3]))
+    );
+    assert!(gc_block_hash_for_scheme(SchemeId::CLASSIC_V1, 3, &v2[3]).is_err());
+    assert!(gc_block_hash_for_scheme(v2_layout.scheme_id(), 3, &v1[3]).is_err());
+}
+
+#[test]
+fn rejects_leaves_with_foreign_headers() {
+    let seed = [6u8; 32];
+    let layout = millionaires_layout(1, 4);
+    let mut leaves = garble_circuit(seed, &layout)
+        .iter()
+        .map(|leaf| leaf.to_vec())
+        .collect::<Vec<_>>();
+    assert!(evaluate(&layout, &leaves).is_ok());
+    leaves.swap(0, 1);
+    let err = evaluate(&layout, &leaves).unwrap_err();
+    assert!(
+        err.contains("leaf header does not match layout gate=0"),
+        "{err}"
+    );
+}
@@ -0,0 +1,79 @@
+//! Secret flags given as `-` are replaced by the secrets read for them, in argument order.
+
+use off_chain_common::secrets::{FROM_STDIN, resolve_secret_args, resolve_secret_args_with};
+
+const FLAGS: &[&str] = &["--master-seed", "--seed"];
+
+fn args(raw: &[&str]) -> Vec<String> {
+    raw.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// Resolves with a reader answering each flag with `<flag>-secret-<n>`, and the flags asked.
+fn resolve(raw: &[&str]) -> (Vec<String>, Vec<String>) {
+    let mut asked = Vec::new();
+    let resolved = resolve_secret_args_with(&args(raw), FLAGS, |flag| {
+        asked.push(flag.to_string());
+        Ok(format!(
+            "{}-secret-{}",
+            flag.trim_start_matches("--"),
+            asked.len()
+        ))
+    })
+    .expect("resolve");
+    (resolved, asked)
+}
+
+#[test]
+fn separate_and_inline_values_are_read_in_order() {
+    let (resolved, asked) = resolve(&[
+        "off-chain-bob",
+        "reveal-verifier-seed",
+        "--seed=-",
+        "--master-seed",
+        FROM_STDIN,
+    ]);
+    assert_eq!(
+        resolved,
+        args(&[
+            "off-chain-bob",
+            "reveal-verifier-seed",
+            "--seed=seed-secret-1",
+            "--master-seed",
+            "master-seed-secret-2",
+        ])
+    );
+    assert_eq!(asked, ["--seed", "--master-seed"]);
+}
+
+#[test]
+fn given_values_and_other_flags_are_left_alone() {
+    let raw = [
+        "off-chain-alice",
+        "export-artifacts",
+        "--master-seed",
+        "0x11",
+        "--seed=0x22",
+        "--out-dir",
+        "-",
+        "--salt=-",
+        "--seed",
+    ];
+    let (resolved, asked) = resolve(&raw);
+    assert_eq!(resolved, args(&raw));
+    assert!(asked.is_empty());
+}
+
+#[test]
+fn a_failed_read_stops_the_command() {
+    let err = resolve_secret_args_with(&args(&["bin", "--seed", "-"]), FLAGS, |flag| {
+        Err(format!("{flag} must not be empty"))
+    })
+    .unwrap_err();
+    assert_eq!(err, "--seed must not be empty");
+}
+
+#[test]
+fn arguments_without_secret_flags_never_read_stdin() {
+    let raw = args(&["off-chain-bob", "status", "--json"]);
+    assert_eq!(resolve_secret_args(&raw, FLAGS).unwrap(), raw);
+}
@@ -0,0 +1,106 @@
+//! Noise XX channel between Alice and Bob, bound to their Ethereum keys.
+
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use off_chain_common::cli::hex_prefixed;
+use off_chain_common::identity::{EthIdentity, recover_personal_sign};
+use off_chain_common::secure_channel::{accept, connect};
+use off_chain_common::transport::{read_length_prefixed, write_length_prefixed};
+
+fn identity(byte: u8) -> EthIdentity {
+    EthIdentity::from_private_key([byte; 32]).expect("key")
+}
+
+#[test]
+fn identities_use_ethereum_addresses() {
+    let mut one = [0u8; 32];
+    one[31] = 1;
+    let key = EthIdentity::from_private_key(one).expect("key");
+    assert_eq!(
+        hex_prefixed(&key.address()),
+        "0x7e5f4552091a69125d5dfcb7b8c2659029395bdf"
+    );
+    assert!(EthIdentity::from_private_key([0u8; 32]).is_err());
+
+    let message = [0x42u8; 32];
+    let mut signature = key.personal_sign(message).expect("sign");
+    assert!(matches!(signature[64], 27 | 28));
+    assert_eq!(
+        recover_personal_sign(message, &signature),
+        Ok(key.address())
+    );
+    signature[64] = 29;
+    assert!(recover_personal_sign(message, &signature).is_err());
+}
+
+#[test]
+fn exchanges_messages_across_records() {
+    let (alice, bob) = (identity(0x11), identity(0x22));
+    let (alice_address, bob_address) = (alice.address(), bob.address());
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let package = (0..200_000u32).map(|i| i as u8).collect::<Vec<_>>();
+
+    let bob_side = {
+        let package = package.clone();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept");
+            let mut secure = accept(stream, &bob, alice_address).expect("accept handshake");
+            assert_eq!(secure.peer_address(), alice_address);
+            assert_eq!(read_length_prefixed(&mut secure), Ok(package));
+            write_length_prefixed(&mut secure, b"bob-ot-choices").expect("reply");
+        })
+    };
+    let stream = TcpStream::connect(addr).expect("connect");
+    let mut secure = connect(stream, &alice, bob_address).expect("connect handshake");
+    assert_eq!(secure.peer_address(), bob_address);
+    write_length_prefixed(&mut secure, &package).expect("send package");
+    assert_eq!(
+        read_length_prefixed(&mut secure),
+        Ok(b"bob-ot-choices".to_vec())
+    );
+    bob_side.join().expect("join");
+}
+
+#[test]
+fn rejects_unexpected_peer_key() {
+    let (alice, bob, mallory) = (identity(0x11), identity(0x22), identity(0x33));
+    let alice_address = alice.address();
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    let mallory_side = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        accept(stream, &mallory, alice_address).map(|_| ())
+    });
+    let stream = TcpStream::connect(addr).expect("connect");
+    let err = connect(stream, &alice, bob.address()).unwrap_err();
+    assert!(err.contains("does not match expected"), "{err}");
+    assert!(err.contains(&hex_prefixed(&bob.address())), "{err}");
+    let _ = mallory_side.join().expect("join");
+}
+
+#[test]
+fn rejects_tampered_records() {
+    let (alice, bob) = (identity(0x11), identity(0x22));
+    let (alice_address, bob_address) = (alice.address(), bob.address());
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+
+    let bob_side = thread::spawn(move || {
+        let (stream, _) = listener.accept().expect("accept");
+        let mut secure = accept(stream, &bob, alice_address).expect("accept handshake");
+        let mut buf = [0u8; 8];
+        secure.read(&mut buf).unwrap_err().to_string()
+    });
+    let stream = TcpStream::connect(addr).expect("connect");
+    let mut raw = stream.try_clone().expect("clone");
+    let _secure = connect(stream, &alice, bob_address).expect("connect handshake");
+    raw.write_all(&[0, 24]).expect("length");
+    raw.write_all(&[0xa5; 24]).expect("forged record");
+
+    let err = bob_side.join().expect("join");
+    assert!(err.contains("Noise decryption failed"), "{err}");
+}
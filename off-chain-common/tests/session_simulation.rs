@@ -0,0 +1,112 @@
+//! In-process sessions: `scenario::simulate_session` end to end, honest and with a cheating
+//! garbler.
+
+use off_chain_common::consensus::HIGHER_BID_WINS;
+use off_chain_common::dispute::watchdog::InstanceVerdict;
+use off_chain_common::protocol::Phase;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::scenario::faults::Fault;
+use off_chain_common::scenario::{SessionParams, SimulationConfig, simulate_session};
+
+fn config(x: u64, y: u64, m: usize) -> SimulationConfig {
+    SimulationConfig {
+        circuit: lookup_circuit(DEFAULT_CIRCUIT).expect("default circuit"),
+        bit_width: 4,
+        params: SessionParams::new(4).expect("n"),
+        master_seed: [0x5a; 32],
+        m,
+        x,
+        y,
+        faults: Vec::new(),
+    }
+}
+
+#[test]
+fn honest_sessions_settle_the_comparison_bit() {
+    for (x, y, m, expected) in [(9, 4, 0, 1), (4, 9, 3, 0), (7, 7, 1, 0), (15, 14, 2, 1)] {
+        let simulated = simulate_session(&config(x, y, m)).expect("simulate");
+        assert_eq!(simulated.output_bit(), Some(expected), "x={x} y={y}");
+        assert_eq!(simulated.session.phase(), Phase::Settle);
+        assert_eq!(simulated.session.chosen_instance(), Some(m));
+        assert!(simulated.dispute.is_none());
+
+        let eval = simulated.evaluation.as_ref().expect("evaluation");
+        assert_eq!(eval.decoding.instance_id, m as u64);
+        assert_eq!(eval.decoding.decode(eval.output_label), Some(expected));
+        for inst in &simulated.instances {
+            match (&inst.verdict, inst.instance_id == m) {
+                (None, true) | (Some(InstanceVerdict::Honest), false) => {}
+                (verdict, _) => panic!("instance {}: {verdict:?}", inst.instance_id),
+            }
+        }
+    }
+}
+
+#[test]
+fn runs_other_registered_circuits() {
+    let vickrey = lookup_circuit("vickrey-v1").expect("vickrey");
+    for (x, y, winner) in [(3, 9, 1), (9, 3, 0)] {
+        let simulated = simulate_session(&SimulationConfig {
+            circuit: vickrey,
+            ..config(x, y, 1)
+        })
+        .expect("simulate");
+        assert_eq!(simulated.output_bit(), Some(winner), "bids {x}/{y}");
+        assert_eq!(simulated.circuit_id, vickrey.circuit_id(4, HIGHER_BID_WINS));
+    }
+}
+
+#[test]
+fn cheating_on_an_opened_instance_ends_in_a_dispute() {
+    let fault = |gate_index| Fault::FlipRowByte {
+        gate_index,
+        row: 1,
+        byte: 3,
+        mask: 0x10,
+    };
+    let simulated = simulate_session(&SimulationConfig {
+        faults: vec![(2, fault(6)), (3, fault(1))],
+        ..config(9, 4, 0)
+    })
+    .expect("simulate");
+
+    let dispute = simulated.dispute.as_ref().expect("dispute");
+    assert_eq!((dispute.instance_id, dispute.gate_index), (2, 6));
+    assert_eq!(simulated.session.phase(), Phase::Dispute);
+    assert_eq!(simulated.session.disputes(), std::slice::from_ref(dispute));
+    assert_eq!(simulated.output_bit(), None);
+    // The second cheating instance is still audited.
+    assert!(matches!(
+        &simulated.instances[3].verdict,
+        Some(InstanceVerdict::Dispute(prepared)) if prepared.gate_index == 1
+    ));
+    assert!(matches!(
+        simulated.instances[1].verdict,
+        Some(InstanceVerdict::Honest)
+    ));
+}
+
+#[test]
+fn rejects_configs_the_session_cannot_run() {
+    let err = simulate_session(&config(1, 2, 4)).unwrap_err();
+    assert_eq!(err, "m=4 out of range [0, 4)");
+
+    let truncated = SimulationConfig {
+        faults: vec![(
+            1,
+            Fault::TruncateLeaf {
+                gate_index: 0,
+                len: 40,
+            },
+        )],
+        ..config(1, 2, 0)
+    };
+    let err = simulate_session(&truncated).unwrap_err();
+    assert!(err.contains("truncated"), "{err}");
+
+    let wide = SimulationConfig {
+        bit_width: 65,
+        ..config(1, 2, 0)
+    };
+    assert!(simulate_session(&wide).is_err());
+}
@@ -0,0 +1,113 @@
+//! Session persistence: flags, seeds and completed commands survive reopening the session
+//! directory.
+
+use std::fs;
+
+use off_chain_common::cli::{SessionSeed, parse_flag_value, resume_session_args};
+use off_chain_common::session_store::SessionStore;
+
+mod common;
+
+use common::temp_path;
+
+fn args(raw: &[&str]) -> Vec<String> {
+    raw.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn flags_seeds_and_completed_commands_survive_reopening() {
+    let dir = temp_path("session-store-flags");
+    let mut store = SessionStore::open(&dir).expect("open");
+    store.set_flag("--bit-width", "4").expect("flag");
+    store.set_flag("--n", "3").expect("flag");
+    store.set_flag("--bit-width", "8").expect("overwrite");
+    store.set_seed("master-seed", [0x42; 32]).expect("seed");
+    store.mark_completed("deposit").expect("completed");
+    store.mark_completed("derive-anchors").expect("completed");
+    assert!(store.set_flag("bit-width", "4").is_err());
+    assert!(store.set_seed("../escape", [0; 32]).is_err());
+
+    let reopened = SessionStore::open(&dir).expect("reopen");
+    assert_eq!(reopened, store);
+    assert_eq!(reopened.flag("--bit-width"), Some("8"));
+    assert_eq!(reopened.flag("--m"), None);
+    assert_eq!(reopened.seed("master-seed"), Some([0x42; 32]));
+    assert_eq!(reopened.completed(), ["deposit", "derive-anchors"]);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[cfg(unix)]
+#[test]
+fn seeds_are_readable_by_the_owner_only() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = temp_path("session-store-seeds");
+    let mut store = SessionStore::open(&dir).expect("open");
+    store.set_seed("master-seed", [0x42; 32]).expect("seed");
+    store.set_seed("verifier-salt", [0x07; 32]).expect("seed");
+    let mode = fs::metadata(dir.join("seeds.txt"))
+        .expect("seeds.txt")
+        .permissions()
+        .mode();
+    assert_eq!(mode & 0o777, 0o600);
+    let _ = fs::remove_dir_all(dir);
+}
+
+#[test]
+fn resumed_arguments_fill_in_saved_flags_and_seeds() {
+    const SEEDS: &[SessionSeed] = &[
+        SessionSeed {
+            commands: &["derive-anchors"],
+            flag: "--master-seed",
+            name: "master-seed",
+            generate: true,
+        },
+        SessionSeed {
+            commands: &["prepare-eval"],
+            flag: "--master-seed",
+            name: "master-seed",
+            generate: false,
+        },
+    ];
+    let flags = ["--bit-width", "--n"];
+    let plain = args(&["--bit-width", "4"]);
+    let (unchanged, store) =
+        resume_session_args("derive-anchors", &plain, &flags, SEEDS).expect("no session");
+    assert_eq!(unchanged, plain);
+    assert!(store.is_none());
+
+    let dir = temp_path("session-store-resume");
+    let session = dir.display().to_string();
+    // A later command never draws the seed the first one should have.
+    let (early, _) =
+        resume_session_args("prepare-eval", &args(&["--session", &session]), &[], SEEDS)
+            .expect("early");
+    assert_eq!(parse_flag_value(&early, "--master-seed"), None);
+    let first = args(&["--session", &session, "--bit-width", "4", "--n=3"]);
+    let (first, _) = resume_session_args("derive-anchors", &first, &flags, SEEDS).expect("first");
+    let master_seed = parse_flag_value(&first, "--master-seed").expect("drawn master seed");
+
+    let second = args(&["--session", &session]);
+    let (second, store) =
+        resume_session_args("derive-anchors", &second, &flags, SEEDS).expect("second");
+    assert_eq!(
+        parse_flag_value(&second, "--bit-width").as_deref(),
+        Some("4")
+    );
+    assert_eq!(parse_flag_value(&second, "--n").as_deref(), Some("3"));
+    assert_eq!(
+        parse_flag_value(&second, "--master-seed"),
+        Some(master_seed.clone())
+    );
+    assert_eq!(store.expect("store").seed_names(), ["master-seed"]);
+
+    // Commands outside the seed's list neither get nor draw it.
+    let (other, _) = resume_session_args("deposit", &args(&["--session", &session]), &[], SEEDS)
+        .expect("other command");
+    assert_eq!(parse_flag_value(&other, "--master-seed"), None);
+    let (later, _) =
+        resume_session_args("prepare-eval", &args(&["--session", &session]), &[], SEEDS)
+            .expect("later command");
+    assert_eq!(parse_flag_value(&later, "--master-seed"), Some(master_seed));
+    let _ = fs::remove_dir_all(dir);
+}
@@ -0,0 +1,100 @@
+//! Signed protocol messages: signer recovery, contract binding and rejection of tampered or
+//! unsigned input.
+
+use off_chain_common::identity::EthIdentity;
+use off_chain_common::messages::{
+    BisectionQueryMessage, CheckpointMessage, Envelope, LabelOfferMessage, Message, SIGNED_KIND,
+    SignedMessage, signing_digest,
+};
+use off_chain_common::protocol::Party;
+
+const CONTRACT: [u8; 20] = [0xc0; 20];
+
+fn identity(byte: u8) -> EthIdentity {
+    EthIdentity::from_private_key([byte; 32]).expect("key")
+}
+
+fn offer() -> Message {
+    Message::LabelOffer(LabelOfferMessage {
+        instance_id: 2,
+        first_wire: 4,
+        encrypted: false,
+        rows: vec![[[0x01; 16], [0x02; 16]]; 4],
+    })
+}
+
+#[test]
+fn signed_messages_round_trip_and_recover_the_signer() {
+    let alice = identity(0x11);
+    let signed = SignedMessage::sign(offer(), CONTRACT, &alice).expect("sign");
+    assert_eq!(signed.signer(), Ok(alice.address()));
+    assert_eq!(signed.verify(CONTRACT, alice.address()), Ok(&offer()));
+
+    let encoded = signed.to_cbor();
+    assert_eq!(SignedMessage::from_cbor(&encoded), Ok(signed.clone()));
+    let envelope = Envelope::from_cbor(&encoded).expect("envelope");
+    assert_eq!(envelope.message(), &offer());
+    assert_eq!(envelope, Envelope::Signed(signed));
+}
+
+#[test]
+fn rejects_other_contracts_signers_and_tampered_messages() {
+    let alice = identity(0x11);
+    let mallory = identity(0x22);
+    let signed = SignedMessage::sign(offer(), CONTRACT, &alice).expect("sign");
+
+    let err = signed.verify([0xc1; 20], alice.address()).unwrap_err();
+    assert!(err.contains("signed for contract"), "{err}");
+    let err = signed.verify(CONTRACT, mallory.address()).unwrap_err();
+    assert!(err.starts_with("kind 3 message is signed by"), "{err}");
+
+    // A relayer swapping in its own rows, or re-binding the message to another auction, no
+    // longer recovers Alice.
+    let mut tampered = signed.clone();
+    let Message::LabelOffer(inner) = &mut tampered.message else {
+        unreachable!();
+    };
+    inner.rows[0][0] = [0xff; 16];
+    assert!(tampered.verify(CONTRACT, alice.address()).is_err());
+    let mut rebound = signed.clone();
+    rebound.contract = [0xc1; 20];
+    assert!(rebound.verify([0xc1; 20], alice.address()).is_err());
+    assert_ne!(
+        signing_digest(CONTRACT, &offer().to_cbor()),
+        signing_digest([0xc1; 20], &offer().to_cbor())
+    );
+}
+
+#[test]
+fn unsigned_and_signed_encodings_are_not_interchangeable() {
+    let plain = offer().to_cbor();
+    assert_eq!(Envelope::from_cbor(&plain), Ok(Envelope::Plain(offer())));
+    let err = SignedMessage::from_cbor(&plain).unwrap_err();
+    assert_eq!(err, "kind 3 message is not signed");
+
+    let signed = SignedMessage::sign(offer(), CONTRACT, &identity(0x11))
+        .expect("sign")
+        .to_cbor();
+    let err = Message::from_cbor(&signed).unwrap_err();
+    assert_eq!(err, format!("unknown message kind {SIGNED_KIND}"));
+    let mut trailing = signed.clone();
+    trailing.push(0);
+    assert!(Envelope::from_cbor(&trailing).is_err());
+}
+
+#[test]
+fn sender_roles_follow_the_protocol_direction() {
+    assert_eq!(offer().sender(), Party::Garbler);
+    let checkpoint = Message::Checkpoint(CheckpointMessage {
+        instance_id: 1,
+        index: 8,
+        state: [0x33; 32],
+    });
+    assert_eq!(checkpoint.sender(), Party::Garbler);
+    let query = Message::BisectionQuery(BisectionQueryMessage {
+        instance_id: 1,
+        index: 8,
+        reveal: false,
+    });
+    assert_eq!(query.sender(), Party::Evaluator);
+}
@@ -0,0 +1,92 @@
+//! Streaming garbler: `garble_circuit_iter` and `garble_circuit_to_writer` produce exactly the
+//! leaves of `garble_circuit`.
+
+use std::io::{self, Write};
+
+use off_chain_common::garble::{garble_circuit, garble_circuit_iter, garble_circuit_to_writer};
+use off_chain_common::ih::{gc_block_hash, inc_hash, incremental_root};
+use off_chain_common::types::{GarblingScheme, LeafFormat, NotGateMode};
+
+mod common;
+
+use common::millionaires_layout;
+
+#[test]
+fn iterator_matches_materialized_leaves() {
+    let seed = [8u8; 32];
+    for layout in [
+        millionaires_layout(2, 8),
+        millionaires_layout(2, 8).with_scheme(GarblingScheme::FreeXor),
+        millionaires_layout(2, 8).with_not_gate_mode(NotGateMode::Garbled),
+    ] {
+        let leaves = garble_circuit(seed, &layout);
+        let streamed = garble_circuit_iter(seed, &layout).collect::<Vec<_>>();
+        assert_eq!(streamed, leaves);
+        assert_eq!(
+            garble_circuit_iter(seed, &layout).count(),
+            layout.gates.len()
+        );
+    }
+}
+
+#[test]
+fn streamed_root_matches_incremental_root() {
+    let seed = [8u8; 32];
+    let layout = millionaires_layout(2, 16);
+    let root = garble_circuit_iter(seed, &layout)
+        .enumerate()
+        .fold([0u8; 32], |state, (idx, leaf)| {
+            inc_hash(state, gc_block_hash(idx as u64, &leaf))
+        });
+    assert_eq!(root, incremental_root(&garble_circuit(seed, &layout)));
+}
+
+#[test]
+#[should_panic(expected = "use garble_leaves")]
+fn iterator_rejects_v2_layouts() {
+    let layout = millionaires_layout(2, 4).with_leaf_format(LeafFormat::V2);
+    let _ = garble_circuit_iter([8u8; 32], &layout);
+}
+
+#[test]
+fn writer_receives_raw_leaf_records_and_root() {
+    let seed = [8u8; 32];
+    let layout = millionaires_layout(2, 8).with_scheme(GarblingScheme::FreeXor);
+    let leaves = garble_circuit(seed, &layout);
+
+    let mut out = Vec::new();
+    let root = garble_circuit_to_writer(seed, &layout, &mut out).unwrap();
+    assert_eq!(root, incremental_root(&leaves));
+    assert_eq!(out.len(), leaves.len() * 71);
+    for (record, leaf) in out.chunks_exact(71).zip(&leaves) {
+        assert_eq!(record, leaf);
+    }
+}
+
+struct FailAfter(usize);
+
+impl Write for FailAfter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.0 < buf.len() {
+            return Err(io::Error::other("disk full"));
+        }
+        self.0 -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn writer_errors_name_the_failing_leaf() {
+    let err = garble_circuit_to_writer(
+        [8u8; 32],
+        &millionaires_layout(2, 4),
+        &mut FailAfter(71 * 3),
+    )
+    .unwrap_err();
+    assert!(err.contains("failed to write leaf 3"), "{err}");
+    assert!(err.contains("disk full"), "{err}");
+}
@@ -0,0 +1,247 @@
+//! Machine-readable output of the test-vector generator: every JSON value a consumer loads
+//! must check out against the others, `--out` writes the same document as stdout,
+//! `--format sol` writes a fixture library around the printed snippet, and `--format abi`
+//! prints the same vector ABI-encoded for `vm.ffi`.
+
+use std::process::{Command, Output};
+
+use off_chain_common::cli::decode_hex;
+use off_chain_common::ih::{gc_block_hash, verify_ih_proof};
+use off_chain_common::merkle::verify_proof;
+use off_chain_common::scenario::com_seed;
+use serde_json::Value;
+
+mod common;
+
+use common::temp_path;
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_off-chain-common"))
+        .args(args)
+        .output()
+        .expect("run vector generator")
+}
+
+fn vectors(args: &[&str]) -> Value {
+    let mut all = vec!["--format", "json"];
+    all.extend_from_slice(args);
+    let output = run(&all);
+    assert!(output.status.success(), "{output:?}");
+    serde_json::from_slice(&output.stdout).expect("JSON vectors")
+}
+
+fn b32(value: &Value) -> [u8; 32] {
+    decode_hex(value.as_str().expect("hex string"))
+        .expect("hex")
+        .try_into()
+        .expect("32 bytes")
+}
+
+fn b32_list(value: &Value) -> Vec<[u8; 32]> {
+    value.as_array().expect("array").iter().map(b32).collect()
+}
+
+#[test]
+fn challenge_packet_verifies_against_expected_roots() {
+    let v = vectors(&["--bits", "4", "--gate-index", "5"]);
+    let challenge = &v["challenge"];
+    let instance = challenge["instanceId"].as_u64().unwrap();
+    let gate_index = challenge["gateIndex"].as_u64().unwrap();
+    assert_eq!(gate_index, 5);
+
+    let leaf = decode_hex(challenge["leafBytes"].as_str().unwrap()).unwrap();
+    let leaf_hash = b32(&challenge["leafHash"]);
+    assert_eq!(gc_block_hash(gate_index, &leaf), leaf_hash);
+    let root_gcs = b32_list(&v["expectedRoots"]["rootGCs"]);
+    assert!(verify_ih_proof(
+        leaf_hash,
+        &b32_list(&challenge["ihProof"]),
+        root_gcs[instance as usize]
+    ));
+    assert!(verify_proof(
+        b32(&challenge["layoutLeaf"]),
+        &b32_list(&challenge["layoutProof"]),
+        b32(&v["expectedRoots"]["circuitLayoutRoot"])
+    ));
+    assert_eq!(v["proofSanity"]["gcIhProofValid"], true);
+    assert_eq!(v["proofSanity"]["layoutProofValid"], true);
+}
+
+#[test]
+fn openings_match_commitments() {
+    let v = vectors(&["--bits", "4", "--n", "4", "--m", "2"]);
+    assert_eq!(v["n"], 4);
+    assert_eq!(v["mChoice"], 2);
+    let commitments = v["commitments"].as_array().unwrap();
+    assert_eq!(commitments.len(), 4);
+    let root_gcs = b32_list(&v["expectedRoots"]["rootGCs"]);
+
+    let indices = v["openings"]["indices"].as_array().unwrap();
+    assert_eq!(*indices, [0, 1, 3]);
+    let seeds = b32_list(&v["openings"]["seeds"]);
+    for (idx, seed) in indices.iter().zip(&seeds) {
+        let commitment = &commitments[idx.as_u64().unwrap() as usize];
+        assert_eq!(b32(&commitment["comSeed"]), com_seed(*seed));
+        assert_eq!(
+            b32(&commitment["rootGC"]),
+            root_gcs[idx.as_u64().unwrap() as usize]
+        );
+    }
+    assert_eq!(v["challenge"]["instanceId"], 0);
+}
+
+#[test]
+fn out_writes_the_stdout_document() {
+    let path = temp_path("ppa-vectors").with_extension("json");
+    let path_arg = path.to_str().unwrap();
+    let output = run(&["--bits", "4", "--format", "json", "--out", path_arg]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("out={path_arg}\n")
+    );
+    let written: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert_eq!(written, vectors(&["--bits", "4"]));
+}
+
+#[test]
+fn sol_fixture_wraps_the_printed_snippet() {
+    let path = std::env::temp_dir().join(format!("ppa-vectors-{}.sol", std::process::id()));
+    let path_arg = path.to_str().unwrap();
+    let output = run(&[
+        "--bits",
+        "4",
+        "--format",
+        "sol",
+        "--out",
+        path_arg,
+        "--fn-name",
+        "defaultAndGate",
+        "--library",
+        "Vectors",
+    ]);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("out={path_arg}\nlibrary=Vectors\nfunction=defaultAndGate\n")
+    );
+    let fixture = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(fixture.starts_with("// SPDX-License-Identifier: MIT\n"));
+    assert!(fixture.contains("--fn-name defaultAndGate --library Vectors`"));
+    assert!(fixture.contains("\nlibrary Vectors {\n    struct RustGateChallengeVector {\n"));
+    assert!(fixture.ends_with("    }\n}\n"));
+
+    // The function body is the text snippet, indented one level.
+    let text = String::from_utf8(run(&["--bits", "4"]).stdout).unwrap();
+    let snippet = text
+        .split("=== Solidity Paste Snippet ===\n")
+        .nth(1)
+        .unwrap()
+        .replace("_rustVectorDefaultAndGate3()", "defaultAndGate()");
+    let indented = snippet
+        .lines()
+        .map(|line| match line {
+            "" => "\n".to_string(),
+            line => format!("    {line}\n"),
+        })
+        .collect::<String>();
+    assert!(fixture.contains(&indented));
+}
+
+/// Reads `abi.encode(RustGateChallengeVector)` back: static fields in order, then the offsets
+/// of the dynamic ones.
+struct AbiReader(Vec<u8>);
+
+impl AbiReader {
+    fn word(&self, at: usize) -> &[u8] {
+        &self.0[at..at + 32]
+    }
+
+    fn uint(&self, at: usize) -> u64 {
+        assert_eq!(self.word(at)[..24], [0; 24]);
+        u64::from_be_bytes(self.word(at)[24..].try_into().unwrap())
+    }
+
+    /// `(length, start of the items)` of the dynamic field in head slot `slot`.
+    fn dynamic(&self, slot: usize) -> (usize, usize) {
+        let start = 32 + self.uint(32 + 32 * slot) as usize;
+        (self.uint(start) as usize, start + 32)
+    }
+
+    fn bytes32_array(&self, slot: usize) -> Vec<[u8; 32]> {
+        let (len, at) = self.dynamic(slot);
+        (0..len)
+            .map(|i| self.word(at + 32 * i).try_into().unwrap())
+            .collect()
+    }
+}
+
+#[test]
+fn abi_output_is_one_encoded_vector() {
+    let args = ["--bits", "4", "--n", "4", "--m", "1", "--gate-index", "6"];
+    let output = run(&[&args[..], &["--format", "abi"]].concat());
+    assert!(output.status.success(), "{output:?}");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 1);
+    let abi = AbiReader(decode_hex(stdout.trim()).expect("hex line"));
+    let v = vectors(&args);
+    let challenge = &v["challenge"];
+
+    // One dynamic tuple: its offset, then 17 head slots.
+    assert_eq!(abi.uint(0), 32);
+    assert_eq!(abi.word(32), b32(&v["circuitId"]));
+    assert_eq!(abi.word(64), b32(&v["circuitLayoutRoot"]));
+    assert_eq!(abi.uint(96), 1);
+    assert_eq!(abi.uint(128), challenge["instanceId"].as_u64().unwrap());
+    assert_eq!(abi.uint(160), 6);
+    let gate = &challenge["gate"];
+    for (idx, key) in ["gateType", "wireA", "wireB", "wireC"].iter().enumerate() {
+        assert_eq!(
+            abi.uint(192 + 32 * idx),
+            gate[key].as_u64().unwrap(),
+            "{key}"
+        );
+    }
+    let leaf = decode_hex(challenge["leafBytes"].as_str().unwrap()).unwrap();
+    let (len, at) = abi.dynamic(9);
+    assert_eq!(abi.0[at..at + len], leaf);
+
+    let commitments = v["commitments"].as_array().unwrap();
+    let com_seeds = commitments
+        .iter()
+        .map(|c| b32(&c["comSeed"]))
+        .collect::<Vec<_>>();
+    assert_eq!(abi.bytes32_array(10), com_seeds);
+    assert_eq!(
+        abi.bytes32_array(11),
+        b32_list(&v["expectedRoots"]["rootGCs"])
+    );
+    let (len, at) = abi.dynamic(12);
+    let indices = (0..len).map(|i| abi.uint(at + 32 * i)).collect::<Vec<_>>();
+    assert_eq!(indices, [0, 2, 3]);
+    assert_eq!(abi.bytes32_array(13), b32_list(&v["openings"]["seeds"]));
+    assert_eq!(abi.bytes32_array(14), b32_list(&challenge["ihProof"]));
+    assert_eq!(abi.bytes32_array(15), b32_list(&challenge["layoutProof"]));
+    // expectMatch
+    assert_eq!(abi.uint(32 + 32 * 16), 1);
+}
+
+#[test]
+fn rejects_unknown_formats_and_text_files() {
+    let output = run(&["--bits", "4", "--format", "yaml"]);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("format must be text, json, sol or abi")
+    );
+    let output = run(&["--bits", "4", "--out", "vectors.txt"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--out requires --format json"));
+
+    // Without `--format` the text snapshot is unchanged.
+    let output = run(&["--bits", "4"]);
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("=== Cut-and-Choose Snapshot ==="));
+    assert!(text.contains("=== Solidity Paste Snippet ==="));
+}
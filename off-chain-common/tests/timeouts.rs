@@ -0,0 +1,166 @@
+//! Timeout monitoring: deadline slot per contract stage and the liveness call open to each
+//! party once it has passed.
+
+use off_chain_common::protocol::Party;
+use off_chain_common::timeouts::{
+    CLOSED_STAGE, Deadlines, SenderState, TimeoutSnapshot, TimeoutStatus, stage_name, timeout_path,
+    timeout_status,
+};
+
+fn deadlines() -> Deadlines {
+    Deadlines::from_fields(&[100, 200, 300, 400, 500, 600, 700, 800]).expect("deadlines")
+}
+
+fn snapshot(stage: u8, now: u64) -> TimeoutSnapshot {
+    TimeoutSnapshot {
+        stage,
+        now,
+        deadlines: deadlines(),
+    }
+}
+
+#[test]
+fn maps_each_stage_to_its_deadline_slot() {
+    let deadlines = deadlines();
+    assert_eq!(deadlines.open, 500);
+    assert_eq!(deadlines.settle, 800);
+    let slots = (0..=CLOSED_STAGE)
+        .map(|stage| deadlines.for_stage(stage))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        slots,
+        [
+            Some(100),
+            Some(200),
+            Some(300),
+            Some(200),
+            Some(300),
+            Some(400),
+            Some(500),
+            Some(600),
+            Some(700),
+            Some(800),
+            Some(800),
+            None,
+        ]
+    );
+    let err = Deadlines::from_fields(&[1, 2, 3]).unwrap_err();
+    assert_eq!(err, "deadlines() has 8 fields, got 3");
+    assert_eq!(stage_name(6), Ok("Open"));
+    assert!(stage_name(12).is_err());
+}
+
+#[test]
+fn penalties_against_alice_are_open_to_buyers_only() {
+    let deadlines = deadlines();
+    let call = |stage, party| timeout_path(stage, party, &deadlines).map(|path| path.signature);
+    for buyer in [Party::Evaluator, Party::Bidder(1)] {
+        assert_eq!(call(2, buyer), Some("abortPhase2()"));
+        assert_eq!(call(4, buyer), Some("abortPhase2()"));
+        assert_eq!(call(6, buyer), Some("abortPhase4()"));
+        assert_eq!(call(8, buyer), Some("abortPhase5()"));
+        assert_eq!(call(9, buyer), None);
+    }
+    for stage in [2, 4, 6, 8] {
+        assert_eq!(call(stage, Party::Garbler), None);
+    }
+    assert_eq!(call(9, Party::Garbler), Some("abortPhase6()"));
+    assert_eq!(call(10, Party::Garbler), Some("abortPhase6()"));
+
+    // Liveness paths any participant may trigger.
+    for party in [Party::Garbler, Party::Evaluator] {
+        assert_eq!(call(0, party), Some("refund()"));
+        assert_eq!(
+            call(1, party),
+            Some("finalizeBuyerSeedCommitAfterDeadline()")
+        );
+        assert_eq!(
+            call(3, party),
+            Some("finalizeBuyerSeedRevealAfterDeadline()")
+        );
+        assert_eq!(call(5, party), Some("finalizeBuyerInputAfterDeadline()"));
+        assert_eq!(call(7, party), Some("closeDispute()"));
+    }
+}
+
+#[test]
+fn claimable_only_after_the_deadline_second() {
+    let status = timeout_status(&snapshot(6, 450), Party::Evaluator).expect("status");
+    let TimeoutStatus::Waiting { path, seconds_left } = status else {
+        panic!("unexpected {status:?}");
+    };
+    assert_eq!(path.signature, "abortPhase4()");
+    assert_eq!(path.deadline, 500);
+    assert_eq!(seconds_left, 51);
+
+    // The contract requires `block.timestamp > deadline`.
+    assert!(matches!(
+        timeout_status(&snapshot(6, 500), Party::Evaluator),
+        Ok(TimeoutStatus::Waiting {
+            seconds_left: 1,
+            ..
+        })
+    ));
+    assert!(matches!(
+        timeout_status(&snapshot(6, 501), Party::Evaluator),
+        Ok(TimeoutStatus::Claimable(path)) if path.stage == 6
+    ));
+}
+
+#[test]
+fn reports_no_path_for_the_acting_party_and_after_close() {
+    assert_eq!(
+        timeout_status(&snapshot(6, 10_000), Party::Garbler),
+        Ok(TimeoutStatus::NoPath)
+    );
+    assert_eq!(
+        timeout_status(&snapshot(9, 10_000), Party::Evaluator),
+        Ok(TimeoutStatus::NoPath)
+    );
+    assert_eq!(
+        timeout_status(&snapshot(CLOSED_STAGE, 10_000), Party::Garbler),
+        Ok(TimeoutStatus::Closed)
+    );
+    let err = timeout_status(&snapshot(12, 0), Party::Garbler).unwrap_err();
+    assert_eq!(err, "unknown contract stage 12");
+}
+
+#[test]
+fn checks_the_sender_the_contract_requires() {
+    let deadlines = deadlines();
+    let blocker = |stage, party, sender: SenderState| {
+        timeout_path(stage, party, &deadlines)
+            .expect("path")
+            .sender_blocker(&sender)
+    };
+    let buyer = SenderState {
+        is_buyer: true,
+        ..SenderState::default()
+    };
+    let alice = SenderState {
+        is_alice: true,
+        ..SenderState::default()
+    };
+    let mut deposited = buyer;
+    deposited.vault_wei[31] = 1;
+
+    assert_eq!(blocker(0, Party::Evaluator, deposited), None);
+    assert_eq!(
+        blocker(0, Party::Evaluator, buyer),
+        Some("the sender's vault is empty, nothing to refund")
+    );
+    assert_eq!(blocker(6, Party::Evaluator, buyer), None);
+    assert_eq!(
+        blocker(6, Party::Evaluator, alice),
+        Some("the sender is not a buyer")
+    );
+    assert_eq!(blocker(9, Party::Garbler, alice), None);
+    assert_eq!(
+        blocker(9, Party::Garbler, buyer),
+        Some("the sender is not Alice")
+    );
+    assert_eq!(blocker(7, Party::Garbler, alice), None);
+    assert!(blocker(7, Party::Evaluator, SenderState::default()).is_some());
+    // Defaulting stalled buyers is open to anyone.
+    assert_eq!(blocker(5, Party::Evaluator, SenderState::default()), None);
+}
@@ -0,0 +1,200 @@
+//! Hash-chained session transcripts: append and reopen, tamper detection and replay.
+
+use std::fs;
+use std::path::PathBuf;
+
+use off_chain_common::identity::EthIdentity;
+use off_chain_common::messages::{
+    BisectionQueryMessage, CheckpointMessage, Message, SignedMessage,
+};
+use off_chain_common::protocol::{
+    ChoosePayload, DepositPayload, Party, Phase, PhasePayload, ProtocolSession,
+};
+use off_chain_common::scenario::SessionParams;
+use off_chain_common::transcript::{Transcript, TranscriptEvent};
+
+mod common;
+
+const CONTRACT: [u8; 20] = [0xc0; 20];
+
+fn transcript_path(prefix: &str) -> PathBuf {
+    common::temp_path(prefix).join("transcript.log")
+}
+
+fn new_session() -> ProtocolSession {
+    ProtocolSession::new(SessionParams::new(3).expect("n"))
+}
+
+fn action(label: &str, payload: Option<PhasePayload>) -> TranscriptEvent {
+    TranscriptEvent::Action {
+        label: label.to_string(),
+        tx_hash: Some(format!("0x{}", "ab".repeat(32))),
+        payload,
+    }
+}
+
+fn deposit(party: Party) -> Option<PhasePayload> {
+    Some(PhasePayload::Deposit(DepositPayload { party }))
+}
+
+fn signed(message: Message, key: u8, contract: [u8; 20]) -> Vec<u8> {
+    let identity = EthIdentity::from_private_key([key; 32]).expect("key");
+    SignedMessage::sign(message, contract, &identity)
+        .expect("sign")
+        .to_cbor()
+}
+
+fn checkpoint() -> Message {
+    Message::Checkpoint(CheckpointMessage {
+        instance_id: 1,
+        index: 4,
+        state: [0x44; 32],
+    })
+}
+
+fn query() -> Message {
+    Message::BisectionQuery(BisectionQueryMessage {
+        instance_id: 1,
+        index: 4,
+        reveal: false,
+    })
+}
+
+#[test]
+fn appended_entries_survive_reopening() {
+    let path = transcript_path("transcript-reopen");
+    let mut transcript = Transcript::open(&path).expect("open");
+    assert_eq!(transcript.head(), [0; 32]);
+    transcript
+        .append(action("deposit()", deposit(Party::Garbler)))
+        .expect("action");
+    transcript
+        .append(TranscriptEvent::Received(query().to_cbor()))
+        .expect("received");
+    let last = transcript
+        .append(TranscriptEvent::Action {
+            label: "transfer".to_string(),
+            tx_hash: None,
+            payload: None,
+        })
+        .expect("plain action")
+        .clone();
+    assert_eq!(last.index, 2);
+    assert_eq!(last.prev_hash, transcript.entries()[1].hash);
+
+    let reopened = Transcript::open(&path).expect("reopen");
+    assert_eq!(reopened, transcript);
+    assert_eq!(reopened.head(), last.hash);
+    assert!(
+        transcript
+            .append(action("has space", None))
+            .unwrap_err()
+            .contains("invalid transcript token")
+    );
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn edited_dropped_or_reordered_lines_break_the_chain() {
+    let path = transcript_path("transcript-tamper");
+    let mut transcript = Transcript::open(&path).expect("open");
+    for label in ["deposit()", "choose(uint256)", "settle()"] {
+        transcript.append(action(label, None)).expect("append");
+    }
+    let original = fs::read_to_string(&path).expect("read");
+    let lines = original.lines().collect::<Vec<_>>();
+
+    let cases = [
+        (
+            original.replace("choose(uint256)", "close()"),
+            "entry 2: hash",
+        ),
+        (format!("{}\n{}\n", lines[0], lines[2]), "entry 2: index 2"),
+        (
+            format!("{}\n{}\n{}\n", lines[1], lines[0], lines[2]),
+            "entry 1: index 1",
+        ),
+        (format!("{}\n", lines[1..].join("\n")), "entry 1: index 1"),
+    ];
+    for (tampered, expected) in cases {
+        fs::write(&path, tampered).expect("tamper");
+        let err = Transcript::open(&path).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+    }
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn replay_drives_the_session_and_collects_signers() {
+    let path = transcript_path("transcript-replay");
+    let mut transcript = Transcript::open(&path).expect("open");
+    let events = [
+        action("deposit()", deposit(Party::Garbler)),
+        action("deposit()", deposit(Party::Evaluator)),
+        TranscriptEvent::Received(signed(checkpoint(), 0x11, CONTRACT)),
+        TranscriptEvent::Sent(signed(query(), 0x22, CONTRACT)),
+        TranscriptEvent::Received(checkpoint().to_cbor()),
+        TranscriptEvent::Received(signed(checkpoint(), 0x11, CONTRACT)),
+    ];
+    for event in events {
+        transcript.append(event).expect("append");
+    }
+
+    let replay = Transcript::open(&path)
+        .expect("reopen")
+        .replay(new_session())
+        .expect("replay");
+    assert_eq!((replay.sent, replay.received, replay.actions), (1, 3, 2));
+    assert_eq!(replay.session.phase(), Phase::Deposit);
+    assert_eq!(replay.contract, Some(CONTRACT));
+    let address = |key| EthIdentity::from_private_key([key; 32]).unwrap().address();
+    assert_eq!(
+        replay.signers,
+        [
+            (Party::Garbler, address(0x11)),
+            (Party::Evaluator, address(0x22))
+        ]
+    );
+    let _ = fs::remove_dir_all(path.parent().unwrap());
+}
+
+#[test]
+fn replay_rejects_inconsistent_sessions() {
+    let cases = [
+        (
+            vec![action(
+                "choose(uint256)",
+                Some(PhasePayload::Choose(ChoosePayload { m: 1 })),
+            )],
+            "transcript entry 1:",
+        ),
+        (
+            vec![
+                TranscriptEvent::Received(signed(checkpoint(), 0x11, CONTRACT)),
+                TranscriptEvent::Received(signed(checkpoint(), 0x33, CONTRACT)),
+            ],
+            "earlier ones by",
+        ),
+        (
+            vec![
+                TranscriptEvent::Sent(signed(query(), 0x22, CONTRACT)),
+                TranscriptEvent::Sent(signed(query(), 0x22, [0xc1; 20])),
+            ],
+            "earlier ones for",
+        ),
+        (
+            vec![TranscriptEvent::Sent(vec![0x83, 0x01])],
+            "transcript entry 1: invalid CBOR",
+        ),
+    ];
+    for (events, expected) in cases {
+        let path = transcript_path("transcript-reject");
+        let mut transcript = Transcript::open(&path).expect("open");
+        for event in events {
+            transcript.append(event).expect("append");
+        }
+        let err = transcript.replay(new_session()).unwrap_err();
+        assert!(err.contains(expected), "{err}");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
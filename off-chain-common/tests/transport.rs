@@ -0,0 +1,123 @@
+//! Length-prefixed transport frames and file batches between the alice and bob binaries.
+
+use std::fs;
+use std::io::Cursor;
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+
+use off_chain_common::transport::{
+    Frame, MAX_FRAME_LEN, list_dir_files, read_frame, receive_files, send_files, write_frame,
+};
+
+mod common;
+
+use common::temp_dir;
+
+#[test]
+fn frames_round_trip() {
+    let frames = [
+        Frame::File {
+            name: "eval-meta.txt".to_string(),
+            bytes: b"bit_width=8\n".to_vec(),
+        },
+        Frame::File {
+            name: "empty.bin".to_string(),
+            bytes: Vec::new(),
+        },
+        Frame::Done,
+    ];
+    let mut wire = Vec::new();
+    for frame in &frames {
+        write_frame(&mut wire, frame).expect("write");
+    }
+    assert_eq!(&wire[..4], &(1 + 2 + 13 + 12u32).to_be_bytes());
+
+    let mut reader = Cursor::new(wire);
+    for frame in &frames {
+        assert_eq!(&read_frame(&mut reader).expect("read"), frame);
+    }
+    assert!(read_frame(&mut reader).is_err());
+}
+
+#[test]
+fn rejects_malformed_frames() {
+    for name in [
+        "",
+        "..",
+        "../escape.txt",
+        "dir/file.txt",
+        ".transport-batches",
+    ] {
+        let frame = Frame::File {
+            name: name.to_string(),
+            bytes: vec![1],
+        };
+        assert!(frame.encode().is_err(), "{name}");
+    }
+    assert!(Frame::decode(&[]).is_err());
+    assert!(Frame::decode(&[0x01, 0x00]).is_err());
+    assert!(Frame::decode(&[0x01, 0x00, 0x05, b'a']).is_err());
+    assert!(Frame::decode(&[0x02, 0x00]).is_err());
+    let err = Frame::decode(&[0x7f]).unwrap_err();
+    assert!(err.contains("unknown transport frame tag 0x7f"), "{err}");
+
+    let oversized = ((MAX_FRAME_LEN + 1) as u32).to_be_bytes();
+    let err = read_frame(&mut Cursor::new(oversized.to_vec())).unwrap_err();
+    assert!(err.contains("exceeds MAX_FRAME_LEN"), "{err}");
+    let truncated = [0, 0, 0, 9, 0x02];
+    assert!(read_frame(&mut Cursor::new(truncated.to_vec())).is_err());
+}
+
+#[test]
+fn sends_a_directory_over_tcp() {
+    let src = temp_dir("transport-src");
+    let dst = temp_dir("transport-dst");
+    fs::create_dir_all(src.join("nested")).expect("src dir");
+    fs::write(src.join("gc-m-leaves.txt"), "0x00\n0x01\n").expect("leaves");
+    fs::write(src.join("eval-m-blob.bin"), [0u8, 1, 2, 255]).expect("blob");
+    fs::write(src.join("nested").join("skipped.txt"), "x").expect("nested");
+
+    let names = list_dir_files(&src).expect("list");
+    assert_eq!(names, ["eval-m-blob.bin", "gc-m-leaves.txt"]);
+
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    let receiver = {
+        let dst = dst.clone();
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().expect("accept");
+            receive_files(&mut stream, &dst)
+        })
+    };
+    let mut stream = TcpStream::connect(addr).expect("connect");
+    assert_eq!(send_files(&mut stream, &src, &names), Ok(14));
+
+    let received = receiver.join().expect("join").expect("receive");
+    assert_eq!(received, names);
+    for name in &names {
+        assert_eq!(
+            fs::read(dst.join(name)).expect("read dst"),
+            fs::read(src.join(name)).expect("read src")
+        );
+    }
+    assert!(!dst.join("nested").exists());
+    let _ = fs::remove_dir_all(src);
+    let _ = fs::remove_dir_all(dst);
+}
+
+#[test]
+fn rejects_duplicate_files_in_one_batch() {
+    let mut wire = Vec::new();
+    let frame = Frame::File {
+        name: "ot-setup.txt".to_string(),
+        bytes: b"0x02".to_vec(),
+    };
+    write_frame(&mut wire, &frame).expect("first");
+    write_frame(&mut wire, &frame).expect("second");
+    write_frame(&mut wire, &Frame::Done).expect("done");
+
+    let dst = temp_dir("transport-dup");
+    let err = receive_files(&mut Cursor::new(wire), &dst).unwrap_err();
+    assert!(err.contains("received twice"), "{err}");
+    let _ = fs::remove_dir_all(dst);
+}
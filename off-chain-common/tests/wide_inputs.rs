@@ -0,0 +1,70 @@
+//! Wide (u128 / uint256) bid inputs through bit decomposition and garbled evaluation.
+
+use off_chain_common::consensus::{derive_wire_label, keccak256, uint256_from_u64};
+use off_chain_common::evaluation::{
+    bits_le_to_u256, derive_alice_input_labels_u256, derive_input_labels_for_bits,
+    derive_not_gate_hints, derive_output_labels, evaluate_garbled_circuit,
+    millionaires_gt_output_wire, u128_to_bits_le, u256_bit_length, u256_to_bits_le,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::CircuitLayout;
+
+fn u128_be(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+#[test]
+fn u256_bit_helpers_agree_with_native_integers() {
+    let value = 0x1234_5678_9abc_def0_1122_3344_5566_7788u128;
+    assert_eq!(
+        u256_to_bits_le(u128_be(value), 128),
+        u128_to_bits_le(value, 128)
+    );
+    assert_eq!(
+        bits_le_to_u256(&u128_to_bits_le(value, 130)).unwrap(),
+        u128_be(value)
+    );
+    assert_eq!(u256_bit_length(u128_be(value)), 125);
+    assert_eq!(u256_bit_length(uint256_from_u64(1)), 1);
+    assert_eq!(u256_bit_length([0u8; 32]), 0);
+    assert_eq!(u256_bit_length([0xff; 32]), 256);
+    assert!(bits_le_to_u256(&[0u8; 257]).is_ok());
+    let mut too_wide = vec![0u8; 257];
+    too_wide[256] = 1;
+    assert!(bits_le_to_u256(&too_wide).is_err());
+}
+
+#[test]
+fn wei_denominated_bids_compare_through_garbling() {
+    // 1.5 ether vs 1.2 ether in wei, compared in a 72-bit circuit.
+    let bit_width = 72;
+    let x = u128_be(1_500_000_000_000_000_000);
+    let y = u128_be(1_200_000_000_000_000_000);
+    let circuit_id = keccak256(&[b"wide-bids"]);
+    let seed = keccak256(&[b"wide-seed"]);
+    let layout = CircuitLayout::new(circuit_id, 4, build_millionaires_layout(bit_width).gates);
+    let leaves = garble_circuit(seed, &layout);
+    let hints = derive_not_gate_hints(seed, &layout);
+    let output_wire = millionaires_gt_output_wire(&layout.gates, bit_width).unwrap();
+    let (_, out_true) = derive_output_labels(seed, &layout, output_wire).unwrap();
+
+    let alice = derive_alice_input_labels_u256(seed, circuit_id, 4, bit_width, x);
+    let bob = derive_input_labels_for_bits(
+        seed,
+        circuit_id,
+        4,
+        bit_width as u16,
+        &u256_to_bits_le(y, bit_width),
+    );
+    assert_eq!(
+        bob[0],
+        derive_wire_label(circuit_id, 4, bit_width as u16, 0, seed)
+    );
+
+    let out =
+        evaluate_garbled_circuit(&layout, &leaves, &alice, &bob, &hints, output_wire).unwrap();
+    assert_eq!(out, out_true);
+}
@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Vendored protoc, so integrators do not need a system install.
+    let mut config = prost_build::Config::new();
+    config.protoc_executable(protoc_bin_vendored::protoc_bin_path()?);
+    tonic_prost_build::configure().compile_with_config(
+        config,
+        &["proto/auction.proto", "proto/artifacts.proto"],
+        &["proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,280 @@
+//! Protobuf encoding (`proto/artifacts.proto`) of the eval packages, label offers and dispute
+//! packets that `off-chain-common::messages` encodes as CBOR. Conversions are lossless in both
+//! directions, so a client can take either encoding and hand back the other.
+
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::evaluation::NotGateHint;
+use off_chain_common::messages::{
+    DisputePacketMessage, EvalPackageMessage, LabelOfferMessage, MESSAGE_VERSION, Message,
+};
+use off_chain_common::types::{GateDesc, GateType};
+use prost::Message as _;
+
+pub mod pb {
+    tonic::include_proto!("ppa.artifacts.v1");
+}
+
+use pb::{Artifact, artifact};
+
+fn fixed<const N: usize>(bytes: &[u8], name: &str) -> Result<[u8; N], String> {
+    bytes
+        .try_into()
+        .map_err(|_| format!("{name} must be {N} bytes, got {}", bytes.len()))
+}
+
+fn fixed_list<const N: usize>(items: &[Vec<u8>], name: &str) -> Result<Vec<[u8; N]>, String> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| fixed(item, &format!("{name}[{idx}]")))
+        .collect()
+}
+
+fn narrow<T: TryFrom<u64>>(value: impl Into<u64>, name: &str) -> Result<T, String> {
+    let value = value.into();
+    T::try_from(value).map_err(|_| format!("{name}: value {value} out of range"))
+}
+
+fn to_vecs<const N: usize>(items: &[[u8; N]]) -> Vec<Vec<u8>> {
+    items.iter().map(|item| item.to_vec()).collect()
+}
+
+impl From<&GateDesc> for pb::Gate {
+    fn from(gate: &GateDesc) -> Self {
+        Self {
+            gate_type: gate.gate_type as u32,
+            wire_a: gate.wire_a.into(),
+            wire_b: gate.wire_b.into(),
+            wire_c: gate.wire_c.into(),
+        }
+    }
+}
+
+impl TryFrom<&pb::Gate> for GateDesc {
+    type Error = String;
+
+    fn try_from(gate: &pb::Gate) -> Result<Self, String> {
+        let gate_type = u8::try_from(gate.gate_type)
+            .ok()
+            .and_then(GateType::from_u8)
+            .ok_or_else(|| format!("gate: unknown opcode {}", gate.gate_type))?;
+        Ok(GateDesc::new(
+            gate_type,
+            narrow(gate.wire_a, "wire_a")?,
+            narrow(gate.wire_b, "wire_b")?,
+            narrow(gate.wire_c, "wire_c")?,
+        ))
+    }
+}
+
+impl From<&NotGateHint> for pb::NotGateHint {
+    fn from(hint: &NotGateHint) -> Self {
+        Self {
+            gate_index: hint.gate_index as u64,
+            in_label0: hint.in_label0.to_vec(),
+            out_if_in0: hint.out_if_in0.to_vec(),
+            in_label1: hint.in_label1.to_vec(),
+            out_if_in1: hint.out_if_in1.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<&pb::NotGateHint> for NotGateHint {
+    type Error = String;
+
+    fn try_from(hint: &pb::NotGateHint) -> Result<Self, String> {
+        Ok(NotGateHint {
+            gate_index: narrow(hint.gate_index, "gate_index")?,
+            in_label0: fixed(&hint.in_label0, "in_label0")?,
+            out_if_in0: fixed(&hint.out_if_in0, "out_if_in0")?,
+            in_label1: fixed(&hint.in_label1, "in_label1")?,
+            out_if_in1: fixed(&hint.out_if_in1, "out_if_in1")?,
+        })
+    }
+}
+
+impl From<&EvalPackageMessage> for pb::EvalPackage {
+    fn from(m: &EvalPackageMessage) -> Self {
+        let d = &m.decoding;
+        Self {
+            bit_width: m.bit_width.into(),
+            decoding: Some(pb::DecodingTable {
+                circuit_id: d.circuit_id.to_vec(),
+                instance_id: d.instance_id,
+                output_wire: d.output_wire.into(),
+                h0: d.h0.to_vec(),
+                h1: d.h1.to_vec(),
+                lout_true: d.lout_true.to_vec(),
+                lout_false: d.lout_false.to_vec(),
+            }),
+            gc_leaves: to_vecs(&m.gc_leaves),
+            alice_labels: to_vecs(&m.alice_labels),
+            not_hints: m.not_hints.iter().map(pb::NotGateHint::from).collect(),
+            ot_setup: m.ot_setup.map(|point| point.to_vec()).unwrap_or_default(),
+        }
+    }
+}
+
+impl TryFrom<&pb::EvalPackage> for EvalPackageMessage {
+    type Error = String;
+
+    fn try_from(m: &pb::EvalPackage) -> Result<Self, String> {
+        let d = m
+            .decoding
+            .as_ref()
+            .ok_or("eval package: decoding is required")?;
+        Ok(EvalPackageMessage {
+            bit_width: narrow(m.bit_width, "bit_width")?,
+            decoding: OutputDecodingTable::from_openings(
+                fixed(&d.circuit_id, "circuit_id")?,
+                d.instance_id,
+                narrow(d.output_wire, "output_wire")?,
+                [fixed(&d.h0, "h0")?, fixed(&d.h1, "h1")?],
+                [
+                    fixed(&d.lout_true, "lout_true")?,
+                    fixed(&d.lout_false, "lout_false")?,
+                ],
+            ),
+            gc_leaves: fixed_list(&m.gc_leaves, "gc_leaves")?,
+            alice_labels: fixed_list(&m.alice_labels, "alice_labels")?,
+            not_hints: m
+                .not_hints
+                .iter()
+                .map(NotGateHint::try_from)
+                .collect::<Result<_, _>>()?,
+            ot_setup: match m.ot_setup.as_slice() {
+                [] => None,
+                point => Some(fixed(point, "ot_setup")?),
+            },
+        })
+    }
+}
+
+impl From<&LabelOfferMessage> for pb::LabelOffer {
+    fn from(m: &LabelOfferMessage) -> Self {
+        Self {
+            instance_id: m.instance_id,
+            first_wire: m.first_wire.into(),
+            encrypted: m.encrypted,
+            rows: m
+                .rows
+                .iter()
+                .map(|[row0, row1]| pb::LabelRow {
+                    row0: row0.to_vec(),
+                    row1: row1.to_vec(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&pb::LabelOffer> for LabelOfferMessage {
+    type Error = String;
+
+    fn try_from(m: &pb::LabelOffer) -> Result<Self, String> {
+        Ok(LabelOfferMessage {
+            instance_id: m.instance_id,
+            first_wire: narrow(m.first_wire, "first_wire")?,
+            encrypted: m.encrypted,
+            rows: m
+                .rows
+                .iter()
+                .enumerate()
+                .map(|(idx, row)| {
+                    Ok([
+                        fixed(&row.row0, &format!("rows[{idx}].row0"))?,
+                        fixed(&row.row1, &format!("rows[{idx}].row1"))?,
+                    ])
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+impl From<&DisputePacketMessage> for pb::DisputePacket {
+    fn from(m: &DisputePacketMessage) -> Self {
+        Self {
+            circuit_id: m.circuit_id.to_vec(),
+            instance_id: m.instance_id,
+            seed: m.seed.to_vec(),
+            gate_index: m.gate_index,
+            gate: Some(pb::Gate::from(&m.gate)),
+            leaf: m.leaf.to_vec(),
+            root_gc: m.root_gc.to_vec(),
+            layout_root: m.layout_root.to_vec(),
+            ih_proof: to_vecs(&m.ih_proof),
+            layout_proof: to_vecs(&m.layout_proof),
+        }
+    }
+}
+
+impl TryFrom<&pb::DisputePacket> for DisputePacketMessage {
+    type Error = String;
+
+    fn try_from(m: &pb::DisputePacket) -> Result<Self, String> {
+        let gate = m.gate.as_ref().ok_or("dispute packet: gate is required")?;
+        Ok(DisputePacketMessage {
+            circuit_id: fixed(&m.circuit_id, "circuit_id")?,
+            instance_id: m.instance_id,
+            seed: fixed(&m.seed, "seed")?,
+            gate_index: m.gate_index,
+            gate: GateDesc::try_from(gate)?,
+            leaf: fixed(&m.leaf, "leaf")?,
+            root_gc: fixed(&m.root_gc, "root_gc")?,
+            layout_root: fixed(&m.layout_root, "layout_root")?,
+            ih_proof: fixed_list(&m.ih_proof, "ih_proof")?,
+            layout_proof: fixed_list(&m.layout_proof, "layout_proof")?,
+        })
+    }
+}
+
+impl TryFrom<&Message> for Artifact {
+    type Error = String;
+
+    fn try_from(message: &Message) -> Result<Self, String> {
+        let body = match message {
+            Message::EvalPackage(m) => artifact::Body::EvalPackage(m.into()),
+            Message::LabelOffer(m) => artifact::Body::LabelOffer(m.into()),
+            Message::DisputePacket(m) => artifact::Body::DisputePacket(m.into()),
+            other => {
+                return Err(format!(
+                    "kind {} messages have no protobuf form",
+                    other.kind()
+                ));
+            }
+        };
+        Ok(Artifact {
+            version: MESSAGE_VERSION,
+            body: Some(body),
+        })
+    }
+}
+
+impl TryFrom<&Artifact> for Message {
+    type Error = String;
+
+    fn try_from(artifact: &Artifact) -> Result<Self, String> {
+        if artifact.version != MESSAGE_VERSION {
+            return Err(format!(
+                "unsupported artifact version {} (expected {MESSAGE_VERSION})",
+                artifact.version
+            ));
+        }
+        match artifact.body.as_ref().ok_or("artifact carries no body")? {
+            artifact::Body::EvalPackage(m) => m.try_into().map(Message::EvalPackage),
+            artifact::Body::LabelOffer(m) => m.try_into().map(Message::LabelOffer),
+            artifact::Body::DisputePacket(m) => m.try_into().map(Message::DisputePacket),
+        }
+    }
+}
+
+/// Protobuf encoding of an eval package, label offer or dispute packet.
+pub fn to_protobuf(message: &Message) -> Result<Vec<u8>, String> {
+    Ok(Artifact::try_from(message)?.encode_to_vec())
+}
+
+/// Decodes an `Artifact`, checking its version and the length of every byte field.
+pub fn from_protobuf(raw: &[u8]) -> Result<Message, String> {
+    let artifact = Artifact::decode(raw).map_err(|e| format!("invalid protobuf artifact: {e}"))?;
+    Message::try_from(&artifact)
+}
@@ -0,0 +1,335 @@
+//! gRPC service (`proto/auction.proto`) wrapping the garbling, evaluation and
+//! dispute-preparation APIs of `off-chain-common` for non-Rust integrators. Leaves stream in
+//! batches in both directions; CPU-bound work runs on the blocking pool. `artifacts` gives the
+//! CBOR protocol messages a protobuf form (`proto/artifacts.proto`).
+
+use off_chain_common::consensus::LOWER_BID_WINS;
+use off_chain_common::dispute::{PrepareDisputeConfig, prepare_dispute_packet};
+use off_chain_common::evaluation::{NotGateHint, evaluate_garbled_circuit};
+use off_chain_common::garble::garble_circuit_iter;
+use off_chain_common::ih::{gc_block_hash, incremental_root_from_hashes};
+use off_chain_common::registry::{CircuitVersion, DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::CircuitLayout;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status, Streaming};
+
+pub mod artifacts;
+
+pub mod pb {
+    tonic::include_proto!("ppa.v1");
+}
+
+use pb::auction_protocol_server::AuctionProtocol;
+use pb::{
+    CircuitInfo, CircuitRef, DisputeChunk, DisputePacket, EvaluateChunk, EvaluateResponse,
+    GarbleRequest, Gate, LeafBatch, LeafChunk, dispute_chunk, evaluate_chunk,
+};
+
+pub use pb::auction_protocol_server::AuctionProtocolServer;
+
+/// Leaves per `LeafChunk` when the request leaves `chunk_size` at 0 (~73 KB per message).
+pub const DEFAULT_CHUNK_SIZE: usize = 1024;
+/// Largest accepted `chunk_size`, keeping chunks well below tonic's 4 MB message limit.
+pub const MAX_CHUNK_SIZE: usize = 16384;
+
+/// Stateless implementation of `ppa.v1.AuctionProtocol`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AuctionProtocolService;
+
+impl AuctionProtocolService {
+    pub fn into_server(self) -> AuctionProtocolServer<Self> {
+        AuctionProtocolServer::new(self)
+    }
+}
+
+fn fixed<const N: usize>(bytes: &[u8], name: &str) -> Result<[u8; N], Status> {
+    bytes.try_into().map_err(|_| {
+        Status::invalid_argument(format!("{name} must be {N} bytes, got {}", bytes.len()))
+    })
+}
+
+fn fixed_list<const N: usize>(items: &[Vec<u8>], name: &str) -> Result<Vec<[u8; N]>, Status> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(idx, item)| fixed(item, &format!("{name}[{idx}]")))
+        .collect()
+}
+
+/// Circuit version, bit width and circuit id of a `CircuitRef`.
+struct ResolvedCircuit {
+    version: &'static CircuitVersion,
+    bit_width: usize,
+    circuit_id: [u8; 32],
+}
+
+impl ResolvedCircuit {
+    fn new(circuit: Option<CircuitRef>) -> Result<Self, Status> {
+        let circuit = circuit.ok_or_else(|| Status::invalid_argument("circuit is required"))?;
+        let name = if circuit.circuit.is_empty() {
+            DEFAULT_CIRCUIT
+        } else {
+            &circuit.circuit
+        };
+        let version = lookup_circuit(name).map_err(Status::invalid_argument)?;
+        let bit_width = circuit.bit_width as usize;
+        version
+            .check_bit_width(bit_width)
+            .map_err(Status::invalid_argument)?;
+        let winner_formula = u8::try_from(circuit.winner_formula)
+            .ok()
+            .filter(|formula| *formula <= LOWER_BID_WINS)
+            .ok_or_else(|| {
+                Status::invalid_argument(
+                    "winner_formula must be 0 (HigherBidWins) or 1 (LowerBidWins)",
+                )
+            })?;
+        let circuit_id = if circuit.circuit_id.is_empty() {
+            version.circuit_id(bit_width, winner_formula)
+        } else {
+            fixed(&circuit.circuit_id, "circuit_id")?
+        };
+        Ok(Self {
+            version,
+            bit_width,
+            circuit_id,
+        })
+    }
+
+    fn layout(&self, instance_id: u64) -> CircuitLayout {
+        CircuitLayout::new(
+            self.circuit_id,
+            instance_id,
+            self.version.build(self.bit_width),
+        )
+        .with_scheme_id(self.version.params.scheme_id)
+    }
+}
+
+fn root_gc(leaves: &[[u8; 71]]) -> [u8; 32] {
+    let hashes = leaves
+        .iter()
+        .enumerate()
+        .map(|(idx, leaf)| gc_block_hash(idx as u64, leaf))
+        .collect::<Vec<_>>();
+    incremental_root_from_hashes(&hashes)
+}
+
+/// Reads the header of a client stream, then every leaf batch after it.
+async fn read_header_and_leaves<C, H>(
+    stream: &mut Streaming<C>,
+    split: impl Fn(C) -> Option<Result<H, LeafBatch>>,
+) -> Result<(H, Vec<[u8; 71]>), Status> {
+    let header = match stream.message().await?.and_then(&split) {
+        Some(Ok(header)) => header,
+        _ => {
+            return Err(Status::invalid_argument(
+                "first message must carry the header",
+            ));
+        }
+    };
+    let mut leaves = Vec::new();
+    while let Some(chunk) = stream.message().await? {
+        match split(chunk) {
+            Some(Err(batch)) => leaves.extend(fixed_list::<71>(&batch.leaves, "leaves")?),
+            _ => {
+                return Err(Status::invalid_argument(
+                    "only the first message may be a header",
+                ));
+            }
+        }
+    }
+    Ok((header, leaves))
+}
+
+async fn run_blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, Status> + Send + 'static,
+) -> Result<T, Status> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| Status::internal(format!("worker failed: {e}")))?
+}
+
+#[tonic::async_trait]
+impl AuctionProtocol for AuctionProtocolService {
+    type GarbleInstanceStream = ReceiverStream<Result<LeafChunk, Status>>;
+
+    async fn describe_circuit(
+        &self,
+        request: Request<CircuitRef>,
+    ) -> Result<Response<CircuitInfo>, Status> {
+        let circuit = ResolvedCircuit::new(Some(request.into_inner()))?;
+        let gates = circuit.version.build(circuit.bit_width);
+        let decision_wire = circuit
+            .version
+            .decision_wire(&gates, circuit.bit_width)
+            .map_err(Status::internal)?;
+        Ok(Response::new(CircuitInfo {
+            circuit: circuit.version.name.to_string(),
+            bit_width: circuit.bit_width as u32,
+            circuit_id: circuit.circuit_id.to_vec(),
+            layout_root: circuit
+                .version
+                .layout_root(circuit.circuit_id, circuit.bit_width)
+                .to_vec(),
+            gate_count: gates.len() as u64,
+            decision_wire: decision_wire as u32,
+        }))
+    }
+
+    async fn garble_instance(
+        &self,
+        request: Request<GarbleRequest>,
+    ) -> Result<Response<Self::GarbleInstanceStream>, Status> {
+        let request = request.into_inner();
+        let circuit = ResolvedCircuit::new(request.circuit)?;
+        let seed = fixed::<32>(&request.seed, "seed")?;
+        let chunk_size = match request.chunk_size as usize {
+            0 => DEFAULT_CHUNK_SIZE,
+            size if size > MAX_CHUNK_SIZE => {
+                return Err(Status::invalid_argument(format!(
+                    "chunk_size {size} exceeds {MAX_CHUNK_SIZE}"
+                )));
+            }
+            size => size,
+        };
+        let layout = circuit.layout(request.instance_id);
+
+        let (tx, rx) = mpsc::channel(4);
+        tokio::task::spawn_blocking(move || {
+            let mut hashes = Vec::with_capacity(layout.gates.len());
+            let mut chunk = LeafChunk::default();
+            for (idx, leaf) in garble_circuit_iter(seed, &layout).enumerate() {
+                hashes.push(gc_block_hash(idx as u64, &leaf));
+                chunk.leaves.push(leaf.to_vec());
+                if chunk.leaves.len() == chunk_size {
+                    let next = LeafChunk {
+                        start_index: idx as u64 + 1,
+                        ..LeafChunk::default()
+                    };
+                    if tx
+                        .blocking_send(Ok(std::mem::replace(&mut chunk, next)))
+                        .is_err()
+                    {
+                        // Client went away.
+                        return;
+                    }
+                }
+            }
+            chunk.root_gc = incremental_root_from_hashes(&hashes).to_vec();
+            let _ = tx.blocking_send(Ok(chunk));
+        });
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn evaluate(
+        &self,
+        request: Request<Streaming<EvaluateChunk>>,
+    ) -> Result<Response<EvaluateResponse>, Status> {
+        let (header, leaves) =
+            read_header_and_leaves(&mut request.into_inner(), |chunk| match chunk.part? {
+                evaluate_chunk::Part::Header(header) => Some(Ok(header)),
+                evaluate_chunk::Part::Leaves(batch) => Some(Err(batch)),
+            })
+            .await?;
+        let circuit = ResolvedCircuit::new(header.circuit)?;
+        let alice_labels = fixed_list::<16>(&header.alice_labels, "alice_labels")?;
+        let bob_labels = fixed_list::<16>(&header.bob_labels, "bob_labels")?;
+        let not_hints = header
+            .not_hints
+            .iter()
+            .map(|hint| {
+                Ok(NotGateHint {
+                    gate_index: hint.gate_index as usize,
+                    in_label0: fixed(&hint.in_label0, "in_label0")?,
+                    out_if_in0: fixed(&hint.out_if_in0, "out_if_in0")?,
+                    in_label1: fixed(&hint.in_label1, "in_label1")?,
+                    out_if_in1: fixed(&hint.out_if_in1, "out_if_in1")?,
+                })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+        let layout = circuit.layout(header.instance_id);
+        let output_wire = match header.output_wire {
+            Some(wire) => u16::try_from(wire)
+                .map_err(|_| Status::invalid_argument(format!("output_wire {wire} is not u16")))?,
+            None => circuit
+                .version
+                .decision_wire(&layout.gates, circuit.bit_width)
+                .map_err(Status::internal)?,
+        };
+
+        run_blocking(move || {
+            let output_label = evaluate_garbled_circuit(
+                &layout,
+                &leaves,
+                &alice_labels,
+                &bob_labels,
+                &not_hints,
+                output_wire,
+            )
+            .map_err(Status::failed_precondition)?;
+            Ok(Response::new(EvaluateResponse {
+                output_label: output_label.to_vec(),
+                output_wire: output_wire as u32,
+                root_gc: root_gc(&leaves).to_vec(),
+            }))
+        })
+        .await
+    }
+
+    async fn prepare_dispute(
+        &self,
+        request: Request<Streaming<DisputeChunk>>,
+    ) -> Result<Response<DisputePacket>, Status> {
+        let (header, claimed_leaves) =
+            read_header_and_leaves(&mut request.into_inner(), |chunk| match chunk.part? {
+                dispute_chunk::Part::Header(header) => Some(Ok(header)),
+                dispute_chunk::Part::Leaves(batch) => Some(Err(batch)),
+            })
+            .await?;
+        let circuit = ResolvedCircuit::new(header.circuit)?;
+        let expected_root_gc = if header.expected_root_gc.is_empty() {
+            None
+        } else {
+            Some(fixed(&header.expected_root_gc, "expected_root_gc")?)
+        };
+        let config = PrepareDisputeConfig {
+            circuit: circuit.version,
+            bit_width: circuit.bit_width,
+            circuit_id: circuit.circuit_id,
+            instance_id: header.instance_id,
+            seed: fixed(&header.seed, "seed")?,
+            claimed_leaves,
+            gate_index: header.gate_index.map(|idx| idx as usize),
+            allow_false_challenge: header.allow_false_challenge,
+            expected_root_gc,
+        };
+
+        run_blocking(move || {
+            let prepared = prepare_dispute_packet(&config).map_err(Status::failed_precondition)?;
+            Ok(Response::new(DisputePacket {
+                gate_index: prepared.gate_index as u64,
+                gate: Some(Gate {
+                    gate_type: prepared.gate.gate_type as u32,
+                    wire_a: prepared.gate.wire_a as u32,
+                    wire_b: prepared.gate.wire_b as u32,
+                    wire_c: prepared.gate.wire_c as u32,
+                }),
+                claimed_leaf: prepared.claimed_leaf.to_vec(),
+                expected_leaf: prepared.expected_leaf.to_vec(),
+                root_gc: prepared.root_gc.to_vec(),
+                layout_root: prepared.layout_root.to_vec(),
+                ih_proof: prepared.ih_proof.iter().map(|h| h.to_vec()).collect(),
+                layout_proof: prepared.layout_proof.iter().map(|h| h.to_vec()).collect(),
+                mismatch_indices: prepared
+                    .audit
+                    .mismatch_indices()
+                    .into_iter()
+                    .map(|idx| idx as u64)
+                    .collect(),
+            }))
+        })
+        .await
+    }
+}
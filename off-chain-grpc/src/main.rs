@@ -0,0 +1,40 @@
+use off_chain_common::cli::parse_flag_value;
+use off_chain_grpc::AuctionProtocolService;
+use std::env;
+use std::error::Error;
+use std::net::SocketAddr;
+use tonic::transport::Server;
+
+type AppResult<T> = Result<T, Box<dyn Error>>;
+
+const DEFAULT_LISTEN: &str = "127.0.0.1:50051";
+
+fn print_help() {
+    println!("off-chain-grpc: gRPC service ppa.v1.AuctionProtocol (proto/auction.proto)");
+    println!("  off-chain-grpc [--listen <host:port>]   (default {DEFAULT_LISTEN})");
+}
+
+#[tokio::main]
+async fn main() -> AppResult<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args
+        .iter()
+        .any(|arg| matches!(arg.as_str(), "-h" | "--help" | "help"))
+    {
+        print_help();
+        return Ok(());
+    }
+    let listen = parse_flag_value(&args, "--listen").unwrap_or_else(|| DEFAULT_LISTEN.into());
+    let addr: SocketAddr = listen
+        .parse()
+        .map_err(|_| format!("invalid listen address '{listen}'"))?;
+
+    println!("status=serving");
+    println!("listen={addr}");
+    println!("service=ppa.v1.AuctionProtocol");
+    Server::builder()
+        .add_service(AuctionProtocolService.into_server())
+        .serve(addr)
+        .await?;
+    Ok(())
+}
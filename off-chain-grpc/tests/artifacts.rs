@@ -0,0 +1,173 @@
+//! Protobuf artifacts (`ppa.artifacts.v1`): lossless conversion to and from the CBOR messages
+//! and rejection of malformed input.
+
+use off_chain_common::consensus::keccak256;
+use off_chain_common::decoding::OutputDecodingTable;
+use off_chain_common::evaluation::{NotGateHint, derive_bob_label_offers};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::messages::{
+    CheckpointMessage, DisputePacketMessage, EvalPackageMessage, LabelOfferMessage,
+    MESSAGE_VERSION, Message,
+};
+use off_chain_common::scenario::build_millionaires_layout;
+use off_chain_common::types::{CircuitLayout, GateDesc, GateType};
+use off_chain_grpc::artifacts::pb::{Artifact, artifact};
+use off_chain_grpc::artifacts::{from_protobuf, to_protobuf};
+use prost::Message as _;
+
+fn layout() -> CircuitLayout {
+    CircuitLayout::new(
+        keccak256(&[b"millionaires-yao-v1"]),
+        2,
+        build_millionaires_layout(4).gates,
+    )
+}
+
+fn artifacts() -> Vec<Message> {
+    let layout = layout();
+    let seed = [0x33u8; 32];
+    let leaves = garble_circuit(seed, &layout);
+    let output_wire = build_millionaires_layout(4).gt_wire;
+    let package = EvalPackageMessage {
+        bit_width: 4,
+        decoding: OutputDecodingTable::derive(seed, &layout, output_wire).expect("decoding"),
+        gc_leaves: leaves.clone(),
+        alice_labels: vec![[0xa1; 16]; 4],
+        not_hints: vec![NotGateHint {
+            gate_index: 5,
+            in_label0: [1; 16],
+            out_if_in0: [2; 16],
+            in_label1: [3; 16],
+            out_if_in1: [4; 16],
+        }],
+        ot_setup: Some([0x02; 33]),
+    };
+    let offer = LabelOfferMessage {
+        instance_id: 2,
+        first_wire: 4,
+        encrypted: false,
+        rows: derive_bob_label_offers(seed, layout.circuit_id, 2, 4)
+            .into_iter()
+            .map(|(l0, l1)| [l0, l1])
+            .collect(),
+    };
+    vec![
+        Message::EvalPackage(package.clone()),
+        Message::EvalPackage(EvalPackageMessage {
+            ot_setup: None,
+            not_hints: Vec::new(),
+            ..package
+        }),
+        Message::LabelOffer(offer.clone()),
+        Message::LabelOffer(LabelOfferMessage {
+            encrypted: true,
+            rows: vec![[[0x77; 16], [0x88; 16]]],
+            ..offer
+        }),
+        Message::DisputePacket(DisputePacketMessage {
+            circuit_id: layout.circuit_id,
+            instance_id: 2,
+            seed,
+            gate_index: 1,
+            gate: GateDesc::new(GateType::Xor, 0, 4, 9),
+            leaf: leaves[1],
+            root_gc: [0x44; 32],
+            layout_root: [0x55; 32],
+            ih_proof: vec![[0x66; 32]; 3],
+            layout_proof: Vec::new(),
+        }),
+    ]
+}
+
+#[test]
+fn artifacts_round_trip_through_protobuf() {
+    for message in artifacts() {
+        let encoded = to_protobuf(&message).expect("encode");
+        assert_eq!(from_protobuf(&encoded), Ok(message.clone()));
+        // Converting back yields the same CBOR bytes the Rust binaries write.
+        let cbor = message.to_cbor();
+        let via_protobuf = from_protobuf(&encoded).expect("decode").to_cbor();
+        assert_eq!(via_protobuf, cbor, "kind {}", message.kind());
+    }
+}
+
+#[test]
+fn body_field_numbers_are_the_cbor_kinds() {
+    for message in artifacts() {
+        let encoded = to_protobuf(&message).expect("encode");
+        let artifact = Artifact::decode(encoded.as_slice()).expect("artifact");
+        assert_eq!(artifact.version, MESSAGE_VERSION);
+        // `version` is field 1 (tag 0x08); the body follows as a length-delimited field.
+        assert_eq!(encoded[..2], [0x08, MESSAGE_VERSION as u8]);
+        assert_eq!(u64::from(encoded[2] >> 3), message.kind());
+        assert_eq!(encoded[2] & 7, 2);
+    }
+
+    let err = to_protobuf(&Message::Checkpoint(CheckpointMessage {
+        instance_id: 0,
+        index: 1,
+        state: [0; 32],
+    }))
+    .unwrap_err();
+    assert_eq!(err, "kind 6 messages have no protobuf form");
+}
+
+#[test]
+fn rejects_wrong_byte_lengths() {
+    let Message::EvalPackage(package) = &artifacts()[0] else {
+        unreachable!()
+    };
+    let mut artifact = Artifact::try_from(&Message::EvalPackage(package.clone())).unwrap();
+    let Some(artifact::Body::EvalPackage(pb)) = artifact.body.as_mut() else {
+        unreachable!()
+    };
+    pb.gc_leaves[3].pop();
+    let err = from_protobuf(&artifact.encode_to_vec()).unwrap_err();
+    assert_eq!(err, "gc_leaves[3] must be 71 bytes, got 70");
+
+    let Some(artifact::Body::EvalPackage(pb)) = artifact.body.as_mut() else {
+        unreachable!()
+    };
+    pb.gc_leaves[3].push(0);
+    pb.ot_setup.truncate(32);
+    let err = from_protobuf(&artifact.encode_to_vec()).unwrap_err();
+    assert_eq!(err, "ot_setup must be 33 bytes, got 32");
+
+    let Some(artifact::Body::EvalPackage(pb)) = artifact.body.as_mut() else {
+        unreachable!()
+    };
+    pb.ot_setup.clear();
+    pb.decoding = None;
+    let err = from_protobuf(&artifact.encode_to_vec()).unwrap_err();
+    assert_eq!(err, "eval package: decoding is required");
+}
+
+#[test]
+fn rejects_foreign_artifacts() {
+    let dispute = &artifacts()[4];
+    let mut artifact = Artifact::try_from(dispute).unwrap();
+    artifact.version = MESSAGE_VERSION + 1;
+    let err = from_protobuf(&artifact.encode_to_vec()).unwrap_err();
+    assert!(err.starts_with("unsupported artifact version"), "{err}");
+
+    artifact.version = MESSAGE_VERSION;
+    let Some(artifact::Body::DisputePacket(pb)) = artifact.body.as_mut() else {
+        unreachable!()
+    };
+    pb.gate.as_mut().unwrap().gate_type = 200;
+    let err = from_protobuf(&artifact.encode_to_vec()).unwrap_err();
+    assert_eq!(err, "gate: unknown opcode 200");
+
+    let mut offer = Artifact::try_from(&artifacts()[2]).unwrap();
+    let Some(artifact::Body::LabelOffer(pb)) = offer.body.as_mut() else {
+        unreachable!()
+    };
+    pb.first_wire = 70_000;
+    let err = from_protobuf(&offer.encode_to_vec()).unwrap_err();
+    assert_eq!(err, "first_wire: value 70000 out of range");
+
+    let err = from_protobuf(&[0x08, 0x01]).unwrap_err();
+    assert_eq!(err, "artifact carries no body");
+    let err = from_protobuf(&[0xff]).unwrap_err();
+    assert!(err.starts_with("invalid protobuf artifact"), "{err}");
+}
@@ -0,0 +1,264 @@
+//! `ppa.v1.AuctionProtocol` over a loopback server and the generated client.
+
+use off_chain_common::consensus::{HIGHER_BID_WINS, LOWER_BID_WINS};
+use off_chain_common::evaluation::{
+    derive_alice_input_labels, derive_bob_label_offers, derive_not_gate_hints,
+    evaluate_garbled_circuit,
+};
+use off_chain_common::garble::garble_circuit;
+use off_chain_common::registry::{DEFAULT_CIRCUIT, lookup_circuit};
+use off_chain_common::types::CircuitLayout;
+use off_chain_grpc::AuctionProtocolService;
+use off_chain_grpc::pb::auction_protocol_client::AuctionProtocolClient;
+use off_chain_grpc::pb::{
+    CircuitRef, DisputeChunk, DisputeHeader, EvaluateChunk, EvaluateHeader, GarbleRequest,
+    LeafBatch, NotGateHint, dispute_chunk, evaluate_chunk,
+};
+use tokio::net::TcpListener;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::Code;
+use tonic::transport::{Channel, Server};
+
+const BIT_WIDTH: usize = 4;
+const SEED: [u8; 32] = [0x5au8; 32];
+
+async fn client() -> AuctionProtocolClient<Channel> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind");
+    let addr = listener.local_addr().expect("addr");
+    tokio::spawn(
+        Server::builder()
+            .add_service(AuctionProtocolService.into_server())
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+    AuctionProtocolClient::connect(format!("http://{addr}"))
+        .await
+        .expect("connect")
+}
+
+fn circuit_ref() -> CircuitRef {
+    CircuitRef {
+        circuit: String::new(),
+        bit_width: BIT_WIDTH as u32,
+        circuit_id: Vec::new(),
+        winner_formula: HIGHER_BID_WINS as u32,
+    }
+}
+
+fn layout(instance_id: u64) -> CircuitLayout {
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("circuit");
+    CircuitLayout::new(
+        circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS),
+        instance_id,
+        circuit.build(BIT_WIDTH),
+    )
+}
+
+fn leaf_batches(leaves: &[[u8; 71]], size: usize) -> Vec<LeafBatch> {
+    leaves
+        .chunks(size)
+        .map(|chunk| LeafBatch {
+            leaves: chunk.iter().map(|leaf| leaf.to_vec()).collect(),
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn describes_registered_circuits() {
+    let mut client = client().await;
+    let info = client
+        .describe_circuit(circuit_ref())
+        .await
+        .expect("describe")
+        .into_inner();
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("circuit");
+    let circuit_id = circuit.circuit_id(BIT_WIDTH, HIGHER_BID_WINS);
+    assert_eq!(info.circuit, DEFAULT_CIRCUIT);
+    assert_eq!(info.circuit_id, circuit_id.to_vec());
+    assert_eq!(
+        info.layout_root,
+        circuit.layout_root(circuit_id, BIT_WIDTH).to_vec()
+    );
+    assert_eq!(info.gate_count, circuit.build(BIT_WIDTH).len() as u64);
+
+    let lower_wins = client
+        .describe_circuit(CircuitRef {
+            winner_formula: LOWER_BID_WINS as u32,
+            ..circuit_ref()
+        })
+        .await
+        .expect("describe")
+        .into_inner();
+    assert_eq!(
+        lower_wins.circuit_id,
+        circuit.circuit_id(BIT_WIDTH, LOWER_BID_WINS).to_vec()
+    );
+    assert_ne!(lower_wins.circuit_id, info.circuit_id);
+    let err = client
+        .describe_circuit(CircuitRef {
+            winner_formula: 2,
+            ..circuit_ref()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+
+    let err = client
+        .describe_circuit(CircuitRef {
+            circuit: "unknown-v9".to_string(),
+            ..circuit_ref()
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn streams_garbled_leaves_in_chunks() {
+    let mut client = client().await;
+    let mut stream = client
+        .garble_instance(GarbleRequest {
+            circuit: Some(circuit_ref()),
+            instance_id: 2,
+            seed: SEED.to_vec(),
+            chunk_size: 5,
+        })
+        .await
+        .expect("garble")
+        .into_inner();
+
+    let mut leaves = Vec::new();
+    let mut root_gc = Vec::new();
+    while let Some(chunk) = stream.message().await.expect("chunk") {
+        assert_eq!(chunk.start_index, leaves.len() as u64);
+        assert!(chunk.leaves.len() <= 5);
+        assert!(root_gc.is_empty(), "root_gc only on the last chunk");
+        leaves.extend(chunk.leaves);
+        root_gc = chunk.root_gc;
+    }
+    let expected = garble_circuit(SEED, &layout(2));
+    assert_eq!(
+        leaves,
+        expected
+            .iter()
+            .map(|leaf| leaf.to_vec())
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(root_gc.len(), 32);
+}
+
+#[tokio::test]
+async fn evaluates_streamed_leaves() {
+    let mut client = client().await;
+    let layout = layout(1);
+    let leaves = garble_circuit(SEED, &layout);
+    let (x, y) = (9u64, 5u64);
+    let alice_labels = derive_alice_input_labels(SEED, layout.circuit_id, 1, BIT_WIDTH, x);
+    let bob_labels = derive_bob_label_offers(SEED, layout.circuit_id, 1, BIT_WIDTH)
+        .iter()
+        .enumerate()
+        .map(|(idx, offer)| {
+            if (y >> idx) & 1 == 1 {
+                offer.1
+            } else {
+                offer.0
+            }
+        })
+        .collect::<Vec<_>>();
+    let not_hints = derive_not_gate_hints(SEED, &layout);
+    let circuit = lookup_circuit(DEFAULT_CIRCUIT).expect("circuit");
+    let output_wire = circuit
+        .decision_wire(&layout.gates, BIT_WIDTH)
+        .expect("wire");
+    let expected = evaluate_garbled_circuit(
+        &layout,
+        &leaves,
+        &alice_labels,
+        &bob_labels,
+        &not_hints,
+        output_wire,
+    )
+    .expect("local evaluation");
+
+    let header = EvaluateHeader {
+        circuit: Some(circuit_ref()),
+        instance_id: 1,
+        alice_labels: alice_labels.iter().map(|l| l.to_vec()).collect(),
+        bob_labels: bob_labels.iter().map(|l| l.to_vec()).collect(),
+        not_hints: not_hints
+            .iter()
+            .map(|hint| NotGateHint {
+                gate_index: hint.gate_index as u64,
+                in_label0: hint.in_label0.to_vec(),
+                out_if_in0: hint.out_if_in0.to_vec(),
+                in_label1: hint.in_label1.to_vec(),
+                out_if_in1: hint.out_if_in1.to_vec(),
+            })
+            .collect(),
+        output_wire: None,
+    };
+    let mut chunks = vec![EvaluateChunk {
+        part: Some(evaluate_chunk::Part::Header(header)),
+    }];
+    chunks.extend(
+        leaf_batches(&leaves, 7)
+            .into_iter()
+            .map(|batch| EvaluateChunk {
+                part: Some(evaluate_chunk::Part::Leaves(batch)),
+            }),
+    );
+    let response = client
+        .evaluate(tokio_stream::iter(chunks))
+        .await
+        .expect("evaluate")
+        .into_inner();
+    assert_eq!(response.output_label, expected.to_vec());
+    assert_eq!(response.output_wire, output_wire as u32);
+
+    let headerless = vec![EvaluateChunk {
+        part: Some(evaluate_chunk::Part::Leaves(LeafBatch {
+            leaves: Vec::new(),
+        })),
+    }];
+    let err = client
+        .evaluate(tokio_stream::iter(headerless))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn prepares_dispute_for_tampered_gate() {
+    let mut client = client().await;
+    let mut claimed = garble_circuit(SEED, &layout(0));
+    claimed[3][10] ^= 0x80;
+
+    let header = DisputeHeader {
+        circuit: Some(circuit_ref()),
+        instance_id: 0,
+        seed: SEED.to_vec(),
+        gate_index: None,
+        allow_false_challenge: false,
+        expected_root_gc: Vec::new(),
+    };
+    let mut chunks = vec![DisputeChunk {
+        part: Some(dispute_chunk::Part::Header(header)),
+    }];
+    chunks.extend(
+        leaf_batches(&claimed, 4)
+            .into_iter()
+            .map(|batch| DisputeChunk {
+                part: Some(dispute_chunk::Part::Leaves(batch)),
+            }),
+    );
+    let packet = client
+        .prepare_dispute(tokio_stream::iter(chunks))
+        .await
+        .expect("prepare dispute")
+        .into_inner();
+    assert_eq!(packet.gate_index, 3);
+    assert_eq!(packet.mismatch_indices, vec![3]);
+    assert_eq!(packet.claimed_leaf, claimed[3].to_vec());
+    assert_ne!(packet.claimed_leaf, packet.expected_leaf);
+    assert!(!packet.ih_proof.is_empty());
+    assert!(packet.gate.is_some());
+}
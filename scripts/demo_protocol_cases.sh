@@ -25,7 +25,7 @@ BIT_WIDTH="${BIT_WIDTH:-57}"
 WINNER_FORMULA="${WINNER_FORMULA:-0}"
 DEPOSIT_WEI="${DEPOSIT_WEI:-1200000000000000000}" # 1.2 ETH
 
-# Optional override; if empty, values are auto-derived from (bitWidth, winnerFormula).
+# Optional override; if empty, values are auto-derived from the bitWidth layout.
 CIRCUIT_ID="${CIRCUIT_ID:-}"
 LAYOUT_ROOT="${LAYOUT_ROOT:-}"
 OFFERED_NAMEHASH_1="${OFFERED_NAMEHASH_1:-}"